@@ -39,6 +39,7 @@ impl<M: rig::completion::CompletionModel> MultiTurnAgent<M> {
                         self.chat_history.push(current_prompt.clone());
                         let response_message = Message::Assistant {
                             content: OneOrMany::one(AssistantContent::text(&text.text)),
+                            name: None,
                         };
                         self.chat_history.push(response_message);
                     }
@@ -49,6 +50,7 @@ impl<M: rig::completion::CompletionModel> MultiTurnAgent<M> {
 
                         self.chat_history.push(Message::Assistant {
                             content: OneOrMany::one(tool_call_msg),
+                            name: None,
                         });
 
                         let ToolCall {
@@ -64,6 +66,7 @@ impl<M: rig::completion::CompletionModel> MultiTurnAgent<M> {
                                 id,
                                 OneOrMany::one(ToolResultContent::text(tool_result)),
                             )),
+                            name: None,
                         };
 
                         final_text = None;
@@ -1,23 +1,16 @@
 use anyhow::{Context, Result};
 use rig::{
-    embeddings::EmbeddingsBuilder, loaders::PdfFileLoader, providers::openai,
-    vector_store::in_memory_store::InMemoryVectorStore, Embed,
+    embeddings::EmbeddingsBuilder, loaders::PdfFileLoader, metadata::Document, providers::openai,
+    vector_store::in_memory_store::InMemoryVectorStore,
 };
-use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Embed, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
-struct Document {
-    id: String,
-    #[embed]
-    content: String,
-}
-
-fn load_pdf(path: PathBuf) -> Result<Vec<String>> {
+fn load_pdf(path: PathBuf) -> Result<Vec<Document>> {
     const CHUNK_SIZE: usize = 2000;
+    const CHUNK_OVERLAP: usize = 200;
 
-    let content_chunks = PdfFileLoader::with_glob(path.to_str().context("Invalid path")?)?
-        .read()
+    let chunks = PdfFileLoader::with_glob(path.to_str().context("Invalid path")?)?
+        .read_with_metadata()
         .into_iter()
         .filter_map(|result| {
             result
@@ -27,31 +20,14 @@ fn load_pdf(path: PathBuf) -> Result<Vec<String>> {
                 })
                 .ok()
         })
-        .flat_map(|content| {
-            let mut chunks = Vec::new();
-            let mut current = String::new();
-
-            for word in content.split_whitespace() {
-                if current.len() + word.len() + 1 > CHUNK_SIZE && !current.is_empty() {
-                    chunks.push(std::mem::take(&mut current).trim().to_string());
-                }
-                current.push_str(word);
-                current.push(' ');
-            }
-
-            if !current.is_empty() {
-                chunks.push(current.trim().to_string());
-            }
-
-            chunks
-        })
+        .flat_map(|document| document.chunks(CHUNK_SIZE, CHUNK_OVERLAP))
         .collect::<Vec<_>>();
 
-    if content_chunks.is_empty() {
+    if chunks.is_empty() {
         anyhow::bail!("No content found in PDF file: {}", path.display());
     }
 
-    Ok(content_chunks)
+    Ok(chunks)
 }
 
 #[tokio::main]
@@ -74,11 +50,8 @@ async fn main() -> Result<()> {
     let mut builder = EmbeddingsBuilder::new(model.clone());
 
     // Add chunks from pdf documents
-    for (i, chunk) in pdf_chunks.into_iter().enumerate() {
-        builder = builder.document(Document {
-            id: format!("pdf_document_{}", i),
-            content: chunk,
-        })?;
+    for chunk in pdf_chunks {
+        builder = builder.document(chunk)?;
     }
 
     // Build embeddings
@@ -87,7 +60,9 @@ async fn main() -> Result<()> {
     println!("Successfully generated embeddings");
 
     // Create vector store and index
-    let vector_store = InMemoryVectorStore::from_documents(embeddings);
+    let vector_store = InMemoryVectorStore::from_documents_with_id_f(embeddings, |chunk| {
+        chunk.metadata.id.clone()
+    });
     let index = vector_store.index(model);
 
     println!("Successfully created vector store and index");
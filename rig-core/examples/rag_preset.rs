@@ -0,0 +1,27 @@
+use rig::{completion::Prompt, loaders::FileLoader, providers::openai, rag::Rag};
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let openai = openai::Client::from_env();
+
+    // Chunk, embed and index the rust examples, and answer from the most relevant chunks
+    let rag = Rag::builder(
+        openai.completion_model(openai::GPT_4O),
+        openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+    )
+    .documents(
+        FileLoader::with_glob("rig-core/examples/*.rs")?
+            .read_with_metadata()
+            .ignore_errors(),
+    )
+    .build()
+    .await?;
+
+    let response = rag
+        .prompt("Which example shows how to stream the responses of Anthropic models?")
+        .await?;
+
+    println!("{}", response);
+
+    Ok(())
+}
@@ -12,6 +12,9 @@
 //! completions responses and prompts. The [Agent] struct also implements the [Chat] trait, which allows it to
 //! be used for generating chat completions.
 //!
+//! An [Agent] keeps no state between calls, and can be cloned cheaply and shared between tasks to
+//! serve concurrent conversations.
+//!
 //! The [AgentBuilder] implements the builder pattern for creating instances of [Agent].
 //! It allows configuring the model, preamble, context documents, tools, temperature, and additional parameters
 //! before building the agent.
@@ -106,28 +109,167 @@
 //! let response = agent.prompt("What does \"glarb-glarb\" mean?").await
 //!     .expect("Failed to prompt the agent");
 //! ```
-use std::collections::HashMap;
+use std::{sync::Arc, time::SystemTime};
 
 use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     completion::{
-        Chat, Completion, CompletionError, CompletionModel, CompletionRequestBuilder, Document,
-        Message, Prompt, PromptError,
+        Chat, Completion, CompletionError, CompletionModel, CompletionRequestBuilder,
+        CompletionResponse, Message, Prompt, PromptError,
     },
+    embeddings::{Embed, EmbedError, TextEmbedder},
+    grounding::GroundingChecker,
+    json_utils,
+    language::ResponseLanguage,
+    memory::history::{self, HistoryPolicy, HistoryPolicyDyn},
     message::AssistantContent,
+    metadata::{Document, DocumentMetadata},
+    post_processors::{PostProcessed, ResponsePostProcessor},
     streaming::{
         StreamingChat, StreamingCompletion, StreamingCompletionModel, StreamingPrompt,
         StreamingResult,
     },
-    tool::{Tool, ToolSet},
+    tokens::{Heuristic, TokenCounter},
+    tool::{ToolDyn, ToolLimit, ToolSet, ToolSetError, READ_RESULT_PAGE_TOOL},
+    validation::{self, ValidationError, Validator},
     vector_store::{VectorStoreError, VectorStoreIndexDyn},
 };
 
+/// A few-shot example: a user message and the ideal assistant response to it.
+/// Examples are sent to the model as chat turns ahead of the actual conversation.
+///
+/// Examples can be stored in a vector store (they are embedded using the user message) and
+/// retrieved by similarity with [AgentBuilder::dynamic_examples].
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Example {
+    pub user: String,
+    pub assistant: String,
+}
+
+impl Example {
+    pub fn new(user: &str, assistant: &str) -> Self {
+        Self {
+            user: user.into(),
+            assistant: assistant.into(),
+        }
+    }
+
+    /// Rough estimate of the number of tokens of the example, see [Heuristic].
+    fn estimated_tokens(&self) -> usize {
+        let counter = Heuristic::default();
+        counter.count_tokens(&self.user) + counter.count_tokens(&self.assistant)
+    }
+}
+
+impl Embed for Example {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.user.clone());
+        Ok(())
+    }
+}
+
+/// Injection of the current date and time in the requests of an agent, so that the model does
+/// not assume that "today" is the end of its training data. See [AgentBuilder::current_date].
+///
+/// The date is appended to the preamble by default, e.g.: `The current date and time is
+/// Wednesday, 2024-01-31 13:00 (CET, UTC+01:00).`
+#[derive(Clone, Debug)]
+pub struct CurrentDate {
+    as_document: bool,
+    timezone: String,
+    utc_offset_minutes: i32,
+    now: Option<SystemTime>,
+}
+
+impl Default for CurrentDate {
+    fn default() -> Self {
+        Self {
+            as_document: false,
+            timezone: "UTC".to_string(),
+            utc_offset_minutes: 0,
+            now: None,
+        }
+    }
+}
+
+impl CurrentDate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send the date as a context document (with id `current_date`) instead of appending it
+    /// to the preamble.
+    pub fn as_document(mut self) -> Self {
+        self.as_document = true;
+        self
+    }
+
+    /// Set the timezone of the date, as a name and a fixed offset from UTC in minutes (e.g.:
+    /// `("CET", 60)`). Defaults to UTC.
+    pub fn timezone(mut self, name: &str, utc_offset_minutes: i32) -> Self {
+        self.timezone = name.to_string();
+        self.utc_offset_minutes = utc_offset_minutes;
+        self
+    }
+
+    /// Set a fixed current time (defaults to the system time on each request).
+    pub fn now(mut self, now: SystemTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Render the current date and time as a sentence.
+    fn render(&self) -> String {
+        const WEEKDAYS: [&str; 7] = [
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+        ];
+
+        let utc_ms = self
+            .now
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let ms = (utc_ms + i64::from(self.utc_offset_minutes) * 60_000).max(0) as u64;
+        let (year, month, day, hours, minutes, _, _) = crate::telemetry::utc_datetime(ms);
+        // 1970-01-01 was a Thursday
+        let weekday = WEEKDAYS[(ms / 86_400_000 % 7) as usize];
+
+        let offset = self.utc_offset_minutes.unsigned_abs();
+        format!(
+            "The current date and time is {weekday}, {year:04}-{month:02}-{day:02} \
+            {hours:02}:{minutes:02} ({}, UTC{}{:02}:{:02}).",
+            self.timezone,
+            if self.utc_offset_minutes < 0 {
+                '-'
+            } else {
+                '+'
+            },
+            offset / 60,
+            offset % 60,
+        )
+    }
+}
+
 /// Struct representing an LLM agent. An agent is an LLM model combined with a preamble
 /// (i.e.: system prompt) and a static set of context documents and tools.
 /// All context documents and tools are always provided to the agent when prompted.
 ///
+/// An agent holds no per-conversation state: the chat history is passed to each call (or kept
+/// by a [Conversation](crate::conversation::Conversation)), and the configuration of the agent
+/// cannot change once it is built. Agents are `Send + Sync` and cheap to clone (the clones share
+/// their configuration and tools), so a single agent can serve many concurrent conversations.
+/// Only the [usage statistics and limits](ToolSet::stats) of the tools are shared by all the
+/// conversations.
+///
 /// # Example
 /// ```
 /// use rig::{completion::Prompt, providers::openai};
@@ -144,9 +286,18 @@ use crate::{
 ///     .await
 ///     .expect("Failed to prompt the agent");
 /// ```
+#[derive(Clone)]
 pub struct Agent<M: CompletionModel> {
     /// Completion model (e.g.: OpenAI's gpt-3.5-turbo-1106, Cohere's command-r)
     model: M,
+    /// Configuration of the agent, shared by its clones
+    config: Arc<AgentConfig>,
+    /// Actual tool implementations, shared by the clones of the agent
+    pub tools: Arc<ToolSet>,
+}
+
+/// The configuration of an [Agent]: immutable once the agent is built.
+struct AgentConfig {
     /// System prompt
     preamble: String,
     /// Context documents always available to the agent
@@ -163,8 +314,84 @@ pub struct Agent<M: CompletionModel> {
     dynamic_context: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
     /// Dynamic tools
     dynamic_tools: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
-    /// Actual tool implementations
-    pub tools: ToolSet,
+    /// Few-shot examples always provided to the agent
+    static_examples: Vec<Example>,
+    /// Vector stores of few-shot examples, with the sample number
+    dynamic_examples: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
+    /// Maximum (estimated) number of tokens of the few-shot examples sent with each prompt
+    examples_token_budget: Option<usize>,
+    /// Policy trimming the chat history sent with each prompt
+    history_policy: Option<Box<dyn HistoryPolicyDyn>>,
+    /// Maximum number of times a request exceeding the context window is sent again with a
+    /// shorter chat history
+    max_context_shrinks: usize,
+    /// Injection of the current date in each request
+    current_date: Option<CurrentDate>,
+    /// Language in which the agent must respond
+    response_language: Option<ResponseLanguage>,
+    /// Post-processors applied to the text responses of the agent
+    post_processors: Vec<Box<dyn ResponsePostProcessor>>,
+    /// Maximum number of times the model is asked to answer again after a post-processor violation
+    max_reasks: usize,
+    /// Validators of the text responses of the agent
+    validators: Vec<Validator<str>>,
+    /// Maximum number of times the model is asked to answer again after a validation error
+    max_validation_retries: usize,
+    /// Checker of the grounding of the text responses in the documents of the context
+    grounding: Option<GroundingChecker>,
+}
+
+impl<M: CompletionModel> Agent<M> {
+    /// Select the few-shot examples for the given prompt and render them as chat messages.
+    ///
+    /// Examples retrieved from the dynamic examples (most relevant first) take priority over
+    /// static examples when the token budget does not allow to include all of them.
+    async fn examples(&self, rag_text: Option<&str>) -> Result<Vec<Message>, CompletionError> {
+        let mut retrieved = vec![];
+        if let Some(text) = rag_text {
+            for (num_sample, index) in &self.config.dynamic_examples {
+                let results = index
+                    .top_n(text, *num_sample)
+                    .await
+                    .map_err(|e| CompletionError::RequestError(Box::new(e)))?;
+
+                for (_, id, doc) in results {
+                    match serde_json::from_value::<Example>(doc) {
+                        Ok(example) => retrieved.push(example),
+                        Err(e) => tracing::warn!("Invalid few-shot example {}: {}", id, e),
+                    }
+                }
+            }
+        }
+
+        let mut budget = self.config.examples_token_budget.unwrap_or(usize::MAX);
+        let mut fits = |example: &Example| match budget.checked_sub(example.estimated_tokens()) {
+            Some(remaining) => {
+                budget = remaining;
+                true
+            }
+            None => false,
+        };
+        retrieved.retain(&mut fits);
+        let selected = self
+            .config
+            .static_examples
+            .iter()
+            .filter(|example| fits(example))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Ok(selected
+            .into_iter()
+            .chain(retrieved)
+            .flat_map(|example| {
+                [
+                    Message::user(example.user),
+                    Message::assistant(example.assistant),
+                ]
+            })
+            .collect())
+    }
 }
 
 impl<M: CompletionModel> Completion<M> for Agent<M> {
@@ -175,20 +402,49 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
     ) -> Result<CompletionRequestBuilder<M>, CompletionError> {
         let prompt = prompt.into();
         let rag_text = prompt.rag_text().clone();
+        let examples = self.examples(rag_text.as_deref()).await?;
+        let chat_history = match &self.config.history_policy {
+            Some(policy) => policy
+                .apply(chat_history)
+                .await
+                .map_err(|e| CompletionError::RequestError(Box::new(e)))?,
+            None => chat_history,
+        };
+
+        let mut preamble = self.config.preamble.clone();
+        let mut static_context = self.config.static_context.clone();
+        if let Some(current_date) = &self.config.current_date {
+            let date = current_date.render();
+            if current_date.as_document {
+                static_context.push(Document::new(DocumentMetadata::new("current_date"), &date));
+            } else if preamble.is_empty() {
+                preamble = date;
+            } else {
+                preamble = format!("{preamble}\n{date}");
+            }
+        }
+        if let Some(language) = &self.config.response_language {
+            preamble = if preamble.is_empty() {
+                language.instruction()
+            } else {
+                format!("{preamble}\n{}", language.instruction())
+            };
+        }
 
         let completion_request = self
             .model
             .completion_request(prompt)
-            .preamble(self.preamble.clone())
+            .preamble(preamble)
+            .messages(examples)
             .messages(chat_history)
-            .temperature_opt(self.temperature)
-            .max_tokens_opt(self.max_tokens)
-            .additional_params_opt(self.additional_params.clone())
-            .documents(self.static_context.clone());
+            .temperature_opt(self.config.temperature)
+            .max_tokens_opt(self.config.max_tokens)
+            .additional_params_opt(self.config.additional_params.clone())
+            .documents(static_context);
 
         let agent = match &rag_text {
             Some(text) => {
-                let dynamic_context = stream::iter(self.dynamic_context.iter())
+                let dynamic_context = stream::iter(self.config.dynamic_context.iter())
                     .then(|(num_sample, index)| async {
                         Ok::<_, VectorStoreError>(
                             index
@@ -196,15 +452,20 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                                 .await?
                                 .into_iter()
                                 .map(|(_, id, doc)| {
+                                    // Send documents with metadata along with their citation
+                                    // fields, so that the model can cite them
+                                    if let Ok(mut document) =
+                                        serde_json::from_value::<Document>(doc.clone())
+                                    {
+                                        document.metadata.id = id;
+                                        return document;
+                                    }
+
                                     // Pretty print the document if possible for better readability
                                     let text = serde_json::to_string_pretty(&doc)
                                         .unwrap_or_else(|_| doc.to_string());
 
-                                    Document {
-                                        id,
-                                        text,
-                                        additional_props: HashMap::new(),
-                                    }
+                                    Document::new(DocumentMetadata::new(&id), &text)
                                 })
                                 .collect::<Vec<_>>(),
                         )
@@ -216,7 +477,7 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     .await
                     .map_err(|e| CompletionError::RequestError(Box::new(e)))?;
 
-                let dynamic_tools = stream::iter(self.dynamic_tools.iter())
+                let dynamic_tools = stream::iter(self.config.dynamic_tools.iter())
                     .then(|(num_sample, index)| async {
                         Ok::<_, VectorStoreError>(
                             index
@@ -240,7 +501,7 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     .await
                     .map_err(|e| CompletionError::RequestError(Box::new(e)))?;
 
-                let static_tools = stream::iter(self.static_tools.iter())
+                let static_tools = stream::iter(self.config.static_tools.iter())
                     .filter_map(|toolname| async move {
                         if let Some(tool) = self.tools.get(toolname) {
                             Some(tool.definition(text.into()).await)
@@ -260,7 +521,7 @@ impl<M: CompletionModel> Completion<M> for Agent<M> {
                     .tools([static_tools.clone(), dynamic_tools].concat())
             }
             None => {
-                let static_tools = stream::iter(self.static_tools.iter())
+                let static_tools = stream::iter(self.config.static_tools.iter())
                     .filter_map(|toolname| async move {
                         if let Some(tool) = self.tools.get(toolname) {
                             // TODO: tool definitions should likely take an `Option<String>`
@@ -296,24 +557,212 @@ impl<M: CompletionModel> Prompt for &Agent<M> {
     }
 }
 
+/// The outcome of a stage reviewing a text response of the agent (see [Agent::review]).
+enum Review {
+    /// The response, possibly rewritten, is accepted by the stage
+    Accept(String),
+    /// The model must answer again, following the instruction
+    Reask(String),
+}
+
+/// The number of times the model was asked to answer again, by stage.
+#[derive(Default)]
+struct Reasks {
+    language: usize,
+    post_processors: usize,
+    validation: usize,
+    grounding: usize,
+}
+
+impl<M: CompletionModel> Agent<M> {
+    /// Send the request of the prompt, sending it again without the oldest turns of the chat
+    /// history when it exceeds the context window of the model (see
+    /// [AgentBuilder::shrink_on_context_overflow]). Returns the response with the documents of
+    /// the context of the request, which are only collected for the grounding check.
+    async fn send(
+        &self,
+        prompt: &Message,
+        chat_history: &mut Vec<Message>,
+    ) -> Result<(CompletionResponse<M::Response>, Vec<Document>), PromptError> {
+        let mut context_shrinks = 0;
+        loop {
+            let request = self
+                .completion(prompt.clone(), chat_history.clone())
+                .await?;
+            let documents = match &self.config.grounding {
+                Some(_) => request.context_documents().to_vec(),
+                None => vec![],
+            };
+            match request.send().await {
+                Ok(response) => return Ok((response, documents)),
+                Err(error)
+                    if error.is_context_length_exceeded()
+                        && context_shrinks < self.config.max_context_shrinks
+                        && !chat_history.is_empty() =>
+                {
+                    context_shrinks += 1;
+                    if let Some(policy) = &self.config.history_policy {
+                        *chat_history = policy.apply(std::mem::take(chat_history)).await?;
+                    }
+                    *chat_history = history::drop_oldest_turns(std::mem::take(chat_history));
+                    tracing::warn!(
+                        "The request exceeds the context window of the model, retrying with {} messages of history",
+                        chat_history.len()
+                    );
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Check that the response is in the language of the agent (see
+    /// [AgentBuilder::response_language]).
+    fn review_language(&self, response: String, reasks: usize) -> Review {
+        let Some(language) = &self.config.response_language else {
+            return Review::Accept(response);
+        };
+        if language.matches(&response) {
+            Review::Accept(response)
+        } else if reasks < language.max_retries {
+            Review::Reask(language.correction())
+        } else {
+            tracing::warn!("The response of the agent is not in {}", language.name);
+            Review::Accept(response)
+        }
+    }
+
+    /// Apply the post-processors of the agent to the response (see
+    /// [AgentBuilder::post_processor]).
+    fn post_process(&self, response: String, reasks: usize) -> Review {
+        let mut response = response;
+        for post_processor in &self.config.post_processors {
+            response = match post_processor.process(response) {
+                PostProcessed::Text(response) => response,
+                PostProcessed::Violation { instruction, .. } if reasks < self.config.max_reasks => {
+                    return Review::Reask(instruction)
+                }
+                PostProcessed::Violation {
+                    response,
+                    instruction,
+                } => {
+                    tracing::warn!(
+                        "The response of the agent violates a post-processor rule: {}",
+                        instruction
+                    );
+                    response
+                }
+            };
+        }
+        Review::Accept(response)
+    }
+
+    /// Validate the response with the validators of the agent (see [AgentBuilder::validate]).
+    fn review_validation(&self, response: String, reasks: usize) -> Result<Review, PromptError> {
+        match validation::validate(&self.config.validators, &response) {
+            Ok(()) => Ok(Review::Accept(response)),
+            Err(error) if reasks < self.config.max_validation_retries => {
+                Ok(Review::Reask(validation::correction(&error)))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Check that the response is supported by the documents of the context of the request
+    /// (see [AgentBuilder::grounding]).
+    async fn review_grounding(
+        &self,
+        response: String,
+        documents: &[Document],
+        reasks: usize,
+    ) -> Review {
+        let Some(grounding) = &self.config.grounding else {
+            return Review::Accept(response);
+        };
+        match grounding.review(response, documents, reasks).await {
+            Ok(response) => Review::Accept(response),
+            Err(instruction) => Review::Reask(instruction),
+        }
+    }
+
+    /// Run the stages reviewing a text response, in order: language, post-processors,
+    /// validators and grounding. The first stage asking the model to answer again stops the
+    /// review, and its count of re-asks is incremented.
+    async fn review(
+        &self,
+        response: String,
+        documents: &[Document],
+        reasks: &mut Reasks,
+    ) -> Result<Review, PromptError> {
+        let response = match self.review_language(response, reasks.language) {
+            Review::Accept(response) => response,
+            reask => {
+                reasks.language += 1;
+                return Ok(reask);
+            }
+        };
+        let response = match self.post_process(response, reasks.post_processors) {
+            Review::Accept(response) => response,
+            reask => {
+                reasks.post_processors += 1;
+                return Ok(reask);
+            }
+        };
+        let response = match self.review_validation(response, reasks.validation)? {
+            Review::Accept(response) => response,
+            reask => {
+                reasks.validation += 1;
+                return Ok(reask);
+            }
+        };
+        let review = self
+            .review_grounding(response, documents, reasks.grounding)
+            .await;
+        if let Review::Reask(_) = review {
+            reasks.grounding += 1;
+        }
+        Ok(review)
+    }
+}
+
 impl<M: CompletionModel> Chat for Agent<M> {
     async fn chat(
         &self,
         prompt: impl Into<Message> + Send,
         chat_history: Vec<Message>,
     ) -> Result<String, PromptError> {
-        let resp = self.completion(prompt, chat_history).await?.send().await?;
-
-        // TODO: consider returning a `Message` instead of `String` for parallel responses / tool calls
-        match resp.choice.first() {
-            AssistantContent::Text(text) => Ok(text.text.clone()),
-            AssistantContent::ToolCall(tool_call) => Ok(self
-                .tools
-                .call(
-                    &tool_call.function.name,
-                    tool_call.function.arguments.to_string(),
-                )
-                .await?),
+        let mut prompt = prompt.into();
+        let mut chat_history = chat_history;
+        let mut reasks = Reasks::default();
+
+        loop {
+            let (resp, documents) = self.send(&prompt, &mut chat_history).await?;
+
+            // TODO: consider returning a `Message` instead of `String` for parallel responses / tool calls
+            match resp.choice.first() {
+                AssistantContent::Text(text) => {
+                    match self
+                        .review(text.text.clone(), &documents, &mut reasks)
+                        .await?
+                    {
+                        Review::Accept(response) => return Ok(response),
+                        Review::Reask(instruction) => {
+                            // Ask the model to answer again, following the instruction
+                            chat_history.push(prompt);
+                            chat_history.push(Message::assistant(text.text));
+                            prompt = Message::user(instruction);
+                        }
+                    }
+                }
+                AssistantContent::ToolCall(tool_call) => {
+                    return Ok(self
+                        .tools
+                        .call(
+                            &tool_call.function.name,
+                            tool_call.function.arguments.to_string(),
+                        )
+                        .await?)
+                }
+            }
         }
     }
 }
@@ -356,10 +805,37 @@ pub struct AgentBuilder<M: CompletionModel> {
     dynamic_context: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
     /// Dynamic tools
     dynamic_tools: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
+    /// Few-shot examples always provided to the agent
+    static_examples: Vec<Example>,
+    /// Vector stores of few-shot examples, with the sample number
+    dynamic_examples: Vec<(usize, Box<dyn VectorStoreIndexDyn>)>,
+    /// Maximum (estimated) number of tokens of the few-shot examples sent with each prompt
+    examples_token_budget: Option<usize>,
+    /// Policy trimming the chat history sent with each prompt
+    history_policy: Option<Box<dyn HistoryPolicyDyn>>,
+    /// Maximum number of times a request exceeding the context window is sent again with a
+    /// shorter chat history
+    max_context_shrinks: usize,
+    /// Injection of the current date in each request
+    current_date: Option<CurrentDate>,
+    /// Language in which the agent must respond
+    response_language: Option<ResponseLanguage>,
+    /// Post-processors applied to the text responses of the agent
+    post_processors: Vec<Box<dyn ResponsePostProcessor>>,
+    /// Maximum number of times the model is asked to answer again after a post-processor violation
+    max_reasks: usize,
+    /// Validators of the text responses of the agent
+    validators: Vec<Validator<str>>,
+    /// Maximum number of times the model is asked to answer again after a validation error
+    max_validation_retries: usize,
+    /// Checker of the grounding of the text responses in the documents of the context
+    grounding: Option<GroundingChecker>,
     /// Temperature of the model
     temperature: Option<f64>,
     /// Actual tool implementations
     tools: ToolSet,
+    /// Errors of the tools rejected by the builder, reported by [AgentBuilder::try_build]
+    tool_errors: Vec<ToolSetError>,
 }
 
 impl<M: CompletionModel> AgentBuilder<M> {
@@ -374,7 +850,20 @@ impl<M: CompletionModel> AgentBuilder<M> {
             additional_params: None,
             dynamic_context: vec![],
             dynamic_tools: vec![],
+            static_examples: vec![],
+            dynamic_examples: vec![],
+            examples_token_budget: None,
+            history_policy: None,
+            max_context_shrinks: 0,
+            current_date: None,
+            response_language: None,
+            post_processors: vec![],
+            max_reasks: 2,
+            validators: vec![],
+            max_validation_retries: 2,
+            grounding: None,
             tools: ToolSet::default(),
+            tool_errors: vec![],
         }
     }
 
@@ -396,19 +885,24 @@ impl<M: CompletionModel> AgentBuilder<M> {
 
     /// Add a static context document to the agent
     pub fn context(mut self, doc: &str) -> Self {
-        self.static_context.push(Document {
-            id: format!("static_doc_{}", self.static_context.len()),
-            text: doc.into(),
-            additional_props: HashMap::new(),
-        });
+        self.static_context.push(Document::new(
+            DocumentMetadata::new(&format!("static_doc_{}", self.static_context.len())),
+            doc,
+        ));
         self
     }
 
-    /// Add a static tool to the agent
-    pub fn tool(mut self, tool: impl Tool + 'static) -> Self {
+    /// Add a static tool to the agent. A tool with the same name as a tool of the agent is
+    /// rejected, and the error returned by [AgentBuilder::try_build].
+    pub fn tool(mut self, tool: impl ToolDyn + 'static) -> Self {
         let toolname = tool.name();
-        self.tools.add_tool(tool);
-        self.static_tools.push(toolname);
+        if self.tools.contains(&toolname) {
+            self.tool_errors
+                .push(ToolSetError::DuplicateToolError(toolname));
+        } else {
+            self.tools.add_tool(tool);
+            self.static_tools.push(toolname);
+        }
         self
     }
 
@@ -426,14 +920,157 @@ impl<M: CompletionModel> AgentBuilder<M> {
 
     /// Add some dynamic tools to the agent. On each prompt, `sample` tools from the
     /// dynamic toolset will be inserted in the request.
+    ///
+    /// If a tool of the toolset has the same name as a tool of the agent, the whole toolset (and
+    /// its index) is rejected and the error returned by [AgentBuilder::try_build] (see
+    /// [ToolSet::namespaced] to avoid collisions).
     pub fn dynamic_tools(
         mut self,
         sample: usize,
         dynamic_tools: impl VectorStoreIndexDyn + 'static,
         toolset: ToolSet,
     ) -> Self {
-        self.dynamic_tools.push((sample, Box::new(dynamic_tools)));
-        self.tools.add_tools(toolset);
+        match self.tools.add_tools(toolset) {
+            Ok(()) => self.dynamic_tools.push((sample, Box::new(dynamic_tools))),
+            Err(e) => self.tool_errors.push(e),
+        }
+        self
+    }
+
+    /// Limit the number of calls of a tool of the agent. See [ToolSet::limit].
+    pub fn tool_limit(mut self, toolname: &str, limit: ToolLimit) -> Self {
+        self.tools.limit(toolname, limit);
+        self
+    }
+
+    /// Truncate the tool results longer than `max_chars` characters, keeping their beginning
+    /// and their end. See [ToolSet::truncate_results].
+    pub fn max_tool_result_chars(mut self, max_chars: usize) -> Self {
+        self.tools.truncate_results(max_chars);
+        self
+    }
+
+    /// Split the tool results longer than `page_chars` characters into pages, that the agent
+    /// reads on demand with an additional tool. See [ToolSet::paginate_results].
+    pub fn paginate_tool_results(mut self, page_chars: usize) -> Self {
+        self.tools.paginate_results(page_chars);
+        if !self
+            .static_tools
+            .iter()
+            .any(|name| name == READ_RESULT_PAGE_TOOL)
+        {
+            self.static_tools.push(READ_RESULT_PAGE_TOOL.to_string());
+        }
+        self
+    }
+
+    /// Add a few-shot example to the agent. Examples are sent, in the order they were added,
+    /// as user and assistant turns ahead of the conversation.
+    pub fn example(mut self, user: &str, assistant: &str) -> Self {
+        self.static_examples.push(Example::new(user, assistant));
+        self
+    }
+
+    /// Add some dynamic few-shot examples to the agent. On each prompt, the `sample` examples
+    /// most similar to the prompt are retrieved from the vector store, whose documents must
+    /// deserialize into [Example]s.
+    pub fn dynamic_examples(
+        mut self,
+        sample: usize,
+        dynamic_examples: impl VectorStoreIndexDyn + 'static,
+    ) -> Self {
+        self.dynamic_examples
+            .push((sample, Box::new(dynamic_examples)));
+        self
+    }
+
+    /// Set the maximum number of tokens (estimated at ~4 characters per token) of the few-shot
+    /// examples sent with each prompt. Examples that do not fit in the budget are skipped.
+    pub fn examples_token_budget(mut self, tokens: usize) -> Self {
+        self.examples_token_budget = Some(tokens);
+        self
+    }
+
+    /// Set the policy trimming the chat history sent with each prompt (e.g.: to keep long
+    /// conversations within the context window of the model). See [crate::memory::history].
+    pub fn history_policy(mut self, policy: impl HistoryPolicy + 'static) -> Self {
+        self.history_policy = Some(Box::new(policy));
+        self
+    }
+
+    /// When the model rejects a request exceeding its context window, send it again up to
+    /// `max_shrinks` times, each time without the oldest half of the turns of the chat history
+    /// (after the [history policy](AgentBuilder::history_policy)). Disabled by default.
+    ///
+    /// Only applies to [Chat] and [Prompt]: streaming requests (see [StreamingChat]) are not
+    /// sent again.
+    pub fn shrink_on_context_overflow(mut self, max_shrinks: usize) -> Self {
+        self.max_context_shrinks = max_shrinks;
+        self
+    }
+
+    /// Inject the current date and time in each request of the agent.
+    pub fn current_date(mut self, current_date: CurrentDate) -> Self {
+        self.current_date = Some(current_date);
+        self
+    }
+
+    /// Set the language in which the agent must respond, see [crate::language].
+    ///
+    /// The language is only enforced by [Chat] and [Prompt]: streamed responses (see
+    /// [StreamingChat]) are not checked.
+    pub fn response_language(mut self, language: ResponseLanguage) -> Self {
+        self.response_language = Some(language);
+        self
+    }
+
+    /// Add a post-processor of the text responses of the agent (see
+    /// [post_processors](crate::post_processors)). Post-processors are applied in the order
+    /// they are added.
+    ///
+    /// Only applies to [Chat] and [Prompt]: streamed responses (see [StreamingChat]) are not
+    /// post-processed.
+    pub fn post_processor(mut self, post_processor: impl ResponsePostProcessor + 'static) -> Self {
+        self.post_processors.push(Box::new(post_processor));
+        self
+    }
+
+    /// Set the maximum number of times the model is asked to answer again when a response
+    /// violates the rule of a post-processor (2 by default). Only applies to [Chat] and
+    /// [Prompt].
+    pub fn max_reasks(mut self, max_reasks: usize) -> Self {
+        self.max_reasks = max_reasks;
+        self
+    }
+
+    /// Add a validator of the text responses of the agent (see [validation](crate::validation)).
+    /// Validators run after the post-processors, in the order they are added.
+    ///
+    /// Only applies to [Chat] and [Prompt]: streamed responses (see [StreamingChat]) are not
+    /// validated.
+    pub fn validate(
+        mut self,
+        validator: impl Fn(&str) -> Result<(), ValidationError> + Send + Sync + 'static,
+    ) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Set the maximum number of times the model is asked to answer again when a response is
+    /// invalid (2 by default). The validation error is returned once the retries are exhausted.
+    /// Only applies to [Chat] and [Prompt].
+    pub fn max_validation_retries(mut self, max_retries: usize) -> Self {
+        self.max_validation_retries = max_retries;
+        self
+    }
+
+    /// Check that the text responses of the agent are supported by the documents of its context
+    /// (see [grounding](crate::grounding)). The check runs after the validators.
+    ///
+    /// Only applies to [Chat] and [Prompt]: streamed responses (see [StreamingChat]) are not
+    /// checked.
+    pub fn grounding(mut self, checker: GroundingChecker) -> Self {
+        self.grounding = Some(checker);
         self
     }
 
@@ -449,26 +1086,60 @@ impl<M: CompletionModel> AgentBuilder<M> {
         self
     }
 
-    /// Set additional parameters to be passed to the model
+    /// Set additional parameters to be passed to the model with every request of the agent
+    /// (e.g.: provider-specific options, such as xAI's `search_parameters`). Calling it again
+    /// merges the parameters, the last ones winning. The parameters set on a request (see
+    /// [Completion::completion]) override the parameters of the agent.
     pub fn additional_params(mut self, params: serde_json::Value) -> Self {
-        self.additional_params = Some(params);
+        self.additional_params = Some(match self.additional_params {
+            Some(agent_params) => json_utils::merge(agent_params, params),
+            None => params,
+        });
         self
     }
 
-    /// Build the agent
-    pub fn build(self) -> Agent<M> {
-        Agent {
-            model: self.model,
-            preamble: self.preamble.unwrap_or_default(),
-            static_context: self.static_context,
-            static_tools: self.static_tools,
-            temperature: self.temperature,
-            max_tokens: self.max_tokens,
-            additional_params: self.additional_params,
-            dynamic_context: self.dynamic_context,
-            dynamic_tools: self.dynamic_tools,
-            tools: self.tools,
+    /// Build the agent, checking that no tool was rejected because of a name collision (see
+    /// [AgentBuilder::tool] and [AgentBuilder::dynamic_tools]).
+    pub fn try_build(mut self) -> Result<Agent<M>, ToolSetError> {
+        if !self.tool_errors.is_empty() {
+            return Err(self.tool_errors.remove(0));
         }
+
+        Ok(Agent {
+            model: self.model,
+            config: Arc::new(AgentConfig {
+                preamble: self.preamble.unwrap_or_default(),
+                static_context: self.static_context,
+                static_tools: self.static_tools,
+                temperature: self.temperature,
+                max_tokens: self.max_tokens,
+                additional_params: self.additional_params,
+                dynamic_context: self.dynamic_context,
+                dynamic_tools: self.dynamic_tools,
+                static_examples: self.static_examples,
+                dynamic_examples: self.dynamic_examples,
+                examples_token_budget: self.examples_token_budget,
+                history_policy: self.history_policy,
+                max_context_shrinks: self.max_context_shrinks,
+                current_date: self.current_date,
+                response_language: self.response_language,
+                post_processors: self.post_processors,
+                max_reasks: self.max_reasks,
+                validators: self.validators,
+                max_validation_retries: self.max_validation_retries,
+                grounding: self.grounding,
+            }),
+            tools: Arc::new(self.tools),
+        })
+    }
+
+    /// Build the agent.
+    ///
+    /// # Panics
+    /// Panics if a tool was rejected because of a name collision (see [AgentBuilder::tool] and
+    /// [AgentBuilder::dynamic_tools]). Use [AgentBuilder::try_build] to get an error instead.
+    pub fn build(self) -> Agent<M> {
+        self.try_build().unwrap_or_else(|e| panic!("{e}"))
     }
 }
 
@@ -490,6 +1161,9 @@ impl<M: StreamingCompletionModel> StreamingPrompt for Agent<M> {
     }
 }
 
+/// Streamed responses are returned as the model generates them: unlike [Chat::chat], they are
+/// not reviewed (language, post-processors, validators and grounding) and requests exceeding the
+/// context window are not sent again.
 impl<M: StreamingCompletionModel> StreamingChat for Agent<M> {
     async fn stream_chat(
         &self,
@@ -502,3 +1176,409 @@ impl<M: StreamingCompletionModel> StreamingChat for Agent<M> {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::MockModel, vector_store::VectorStoreIndex};
+
+    fn mock() -> MockModel {
+        MockModel::text("")
+    }
+
+    struct MockExamples;
+
+    impl VectorStoreIndex for MockExamples {
+        async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+            &self,
+            query: &str,
+            n: usize,
+        ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+            Ok((0..n)
+                .map(|i| {
+                    let example = Example::new(query, &format!("Retrieved answer {i}"));
+                    let doc = serde_json::from_value(serde_json::to_value(example)?)?;
+                    Ok((1.0, format!("example{i}"), doc))
+                })
+                .collect::<Result<_, serde_json::Error>>()?)
+        }
+
+        async fn top_n_ids(
+            &self,
+            _query: &str,
+            _n: usize,
+        ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_few_shot_examples() {
+        let agent = AgentBuilder::new(mock())
+            .example("2 + 2", "4")
+            .example("A very long question that does not fit", "Its long answer")
+            .dynamic_examples(1, MockExamples)
+            .examples_token_budget(10)
+            .build();
+
+        let history = vec![Message::user("Hi"), Message::assistant("Hello!")];
+        let request = agent.completion("1 + 1", history).await.unwrap().build();
+
+        assert_eq!(
+            request.chat_history,
+            vec![
+                Message::user("2 + 2"),
+                Message::assistant("4"),
+                Message::user("1 + 1"),
+                Message::assistant("Retrieved answer 0"),
+                Message::user("Hi"),
+                Message::assistant("Hello!"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_policy() {
+        let agent = AgentBuilder::new(mock())
+            .history_policy(crate::memory::history::KeepLast(2))
+            .build();
+
+        let history = vec![
+            Message::user("Hi"),
+            Message::assistant("Hello!"),
+            Message::user("2 + 2"),
+            Message::assistant("4"),
+        ];
+        let request = agent.completion("1 + 1", history).await.unwrap().build();
+
+        assert_eq!(
+            request.chat_history,
+            vec![Message::user("2 + 2"), Message::assistant("4")]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_tools() {
+        let echo = |name: &str| {
+            crate::tool::FnTool::from_fn(
+                name,
+                "Echo",
+                serde_json::json!({"type": "object"}),
+                |args: serde_json::Value| async move { Ok::<_, String>(args) },
+            )
+        };
+
+        let result = AgentBuilder::new(mock())
+            .tool(echo("search"))
+            .tool(echo("search"))
+            .try_build();
+        assert!(matches!(
+            result,
+            Err(ToolSetError::DuplicateToolError(name)) if name == "search"
+        ));
+
+        let result = AgentBuilder::new(mock())
+            .tool(echo("search"))
+            .dynamic_tools(1, MockExamples, ToolSet::from_tools(vec![echo("search")]))
+            .try_build();
+        assert!(matches!(
+            result,
+            Err(ToolSetError::DuplicateToolError(name)) if name == "search"
+        ));
+
+        let agent = AgentBuilder::new(mock())
+            .tool(echo("search"))
+            .dynamic_tools(
+                1,
+                MockExamples,
+                ToolSet::from_tools(vec![echo("search")]).namespaced("web"),
+            )
+            .try_build()
+            .unwrap();
+        assert_eq!(agent.config.dynamic_tools.len(), 1);
+        assert!(agent.tools.contains("search") && agent.tools.contains("web_search"));
+    }
+
+    #[test]
+    #[should_panic(expected = "DuplicateToolError: search")]
+    fn test_build_duplicate_tools() {
+        let echo = || {
+            crate::tool::FnTool::from_fn(
+                "search",
+                "Echo",
+                serde_json::json!({"type": "object"}),
+                |args: serde_json::Value| async move { Ok::<_, String>(args) },
+            )
+        };
+
+        AgentBuilder::new(mock()).tool(echo()).tool(echo()).build();
+    }
+
+    /// Rejects the requests with more than 2 messages of history, echoes the history otherwise
+    fn small_context() -> MockModel {
+        MockModel::new(|request| {
+            if request.chat_history.len() > 2 {
+                return Err(CompletionError::ProviderError(
+                    "This model's maximum context length is 8192 tokens \
+                    (context_length_exceeded)"
+                        .to_string(),
+                ));
+            }
+            let text = request
+                .chat_history
+                .iter()
+                .filter_map(|message| message.rag_text())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Ok(crate::test_utils::response(
+                AssistantContent::text(text),
+                (),
+            ))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_shrink_on_context_overflow() {
+        let history = vec![
+            Message::user("Hi"),
+            Message::assistant("Hello!"),
+            Message::user("2 + 2"),
+            Message::assistant("4"),
+            Message::user("3 + 3"),
+            Message::assistant("6"),
+        ];
+
+        let error = AgentBuilder::new(small_context())
+            .build()
+            .chat("1 + 1", history.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            PromptError::CompletionError(error) if error.is_context_length_exceeded()
+        ));
+
+        let response = AgentBuilder::new(small_context())
+            .shrink_on_context_overflow(2)
+            .build()
+            .chat("1 + 1", history)
+            .await
+            .unwrap();
+        assert_eq!(response, "3 + 3");
+    }
+
+    #[tokio::test]
+    async fn test_shared_agent() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+        let agent = AgentBuilder::new(small_context())
+            .preamble("Be nice")
+            .build();
+        assert_shareable(&agent);
+
+        let clone = agent.clone();
+        assert!(Arc::ptr_eq(&agent.config, &clone.config));
+        assert!(Arc::ptr_eq(&agent.tools, &clone.tools));
+
+        // Concurrent conversations do not see each other's history
+        let handles = ["Alice", "Bob"].map(|name| {
+            let agent = agent.clone();
+            tokio::spawn(async move {
+                agent
+                    .chat("Hi", vec![Message::user(format!("I am {name}"))])
+                    .await
+            })
+        });
+        let [alice, bob] = handles;
+        assert_eq!(alice.await.unwrap().unwrap(), "I am Alice");
+        assert_eq!(bob.await.unwrap().unwrap(), "I am Bob");
+    }
+
+    #[tokio::test]
+    async fn test_additional_params() {
+        let agent = AgentBuilder::new(mock())
+            .additional_params(serde_json::json!({"top_p": 0.5, "seed": 1}))
+            .additional_params(serde_json::json!({"seed": 2}))
+            .build();
+
+        let request = agent.completion("Hi", vec![]).await.unwrap().build();
+        assert_eq!(
+            request.additional_params,
+            Some(serde_json::json!({"top_p": 0.5, "seed": 2}))
+        );
+
+        let request = agent
+            .completion("Hi", vec![])
+            .await
+            .unwrap()
+            .additional_params(serde_json::json!({"top_p": 0.9}))
+            .build();
+        assert_eq!(
+            request.additional_params,
+            Some(serde_json::json!({"top_p": 0.9, "seed": 2}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_current_date() {
+        // 2024-01-31T12:30:00Z
+        let now = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_706_704_200);
+
+        let agent = AgentBuilder::new(mock())
+            .preamble("Be nice")
+            .current_date(CurrentDate::new().timezone("EST", -300).now(now))
+            .build();
+        let request = agent.completion("Hi", vec![]).await.unwrap().build();
+        assert_eq!(
+            request.preamble.as_deref(),
+            Some(
+                "Be nice\nThe current date and time is Wednesday, 2024-01-31 07:30 \
+                (EST, UTC-05:00)."
+            )
+        );
+
+        let agent = AgentBuilder::new(mock())
+            .current_date(CurrentDate::new().as_document().now(now))
+            .build();
+        let request = agent.completion("Hi", vec![]).await.unwrap().build();
+        assert_eq!(request.preamble.as_deref(), Some(""));
+        assert_eq!(request.documents[0].metadata.id, "current_date");
+        assert_eq!(
+            request.documents[0].text,
+            "The current date and time is Wednesday, 2024-01-31 12:30 (UTC, UTC+00:00)."
+        );
+    }
+
+    /// Answers in English, unless asked to answer again in French
+    fn english() -> MockModel {
+        MockModel::reply(|request| {
+            match request.prompt.rag_text() {
+                Some(text) if text.contains("in French only") => {
+                    "Le temps est beau et le soleil brille."
+                }
+                _ => "The weather is nice and the sun is shining.",
+            }
+            .to_string()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_response_language() {
+        use crate::language::StopwordDetector;
+
+        let agent = AgentBuilder::new(english())
+            .response_language(ResponseLanguage::new("fr", "French").detector(StopwordDetector))
+            .build();
+        let request = agent.completion("Hi", vec![]).await.unwrap().build();
+        assert!(request
+            .preamble
+            .unwrap()
+            .contains("Always respond in French"));
+
+        let response = agent.chat("Quel temps fait-il ?", vec![]).await.unwrap();
+        assert_eq!(response, "Le temps est beau et le soleil brille.");
+
+        let agent = AgentBuilder::new(english())
+            .response_language(
+                ResponseLanguage::new("fr", "French")
+                    .detector(StopwordDetector)
+                    .max_retries(0),
+            )
+            .build();
+        let response = agent.chat("Quel temps fait-il ?", vec![]).await.unwrap();
+        assert_eq!(response, "The weather is nice and the sun is shining.");
+    }
+
+    #[tokio::test]
+    async fn test_post_processors() {
+        use crate::post_processors::{MaxLength, ReaskOnViolation, RegexRewrite};
+
+        let agent = || {
+            AgentBuilder::new(english())
+                .post_processor(ReaskOnViolation::new(|response| {
+                    response
+                        .contains("weather")
+                        .then(|| "Answer in French only.".to_string())
+                }))
+                .post_processor(RegexRewrite::new(r"\bsun\b|\bsoleil\b", "☀").unwrap())
+                .post_processor(MaxLength::new(30))
+        };
+
+        let response = agent()
+            .build()
+            .chat("Quel temps fait-il ?", vec![])
+            .await
+            .unwrap();
+        assert_eq!(response, "Le temps est beau et le ☀…");
+
+        // Once the re-asks are exhausted, the response goes through the other post-processors
+        let response = agent()
+            .max_reasks(0)
+            .build()
+            .chat("Quel temps fait-il ?", vec![])
+            .await
+            .unwrap();
+        assert_eq!(response, "The weather is nice and the ☀…");
+    }
+
+    #[tokio::test]
+    async fn test_validation() {
+        use crate::{completion::PromptError, validation::ValidationError};
+
+        let agent = || {
+            AgentBuilder::new(english()).validate(|response| match response.contains("weather") {
+                true => Err(ValidationError::new("Answer in French only.")),
+                false => Ok(()),
+            })
+        };
+
+        let response = agent()
+            .build()
+            .chat("Quel temps fait-il ?", vec![])
+            .await
+            .unwrap();
+        assert_eq!(response, "Le temps est beau et le soleil brille.");
+
+        // Once the retries are exhausted, the validation error is returned
+        let error = agent()
+            .max_validation_retries(0)
+            .build()
+            .chat("Quel temps fait-il ?", vec![])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            PromptError::ValidationError(ValidationError(message)) if message == "Answer in French only."
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_review_stages() {
+        use crate::{post_processors::ReaskOnViolation, validation::ValidationError};
+
+        let agent = AgentBuilder::new(english())
+            .post_processor(ReaskOnViolation::new(|response| {
+                response.contains("rain").then(|| "No rain.".to_string())
+            }))
+            .max_reasks(1)
+            .validate(|response| match response.contains("snow") {
+                true => Err(ValidationError::new("No snow.")),
+                false => Ok(()),
+            })
+            .max_validation_retries(1)
+            .build();
+
+        // Each stage counts its own re-asks
+        let mut reasks = Reasks::default();
+        let review = agent.review("rain".into(), &[], &mut reasks).await.unwrap();
+        assert!(matches!(review, Review::Reask(instruction) if instruction == "No rain."));
+        let review = agent.review("snow".into(), &[], &mut reasks).await.unwrap();
+        assert!(matches!(review, Review::Reask(_)));
+        assert_eq!((reasks.post_processors, reasks.validation), (1, 1));
+
+        // Exhausted post-processors accept the response, exhausted validators reject it
+        let review = agent.review("rain".into(), &[], &mut reasks).await.unwrap();
+        assert!(matches!(review, Review::Accept(response) if response == "rain"));
+        assert!(agent.review("snow".into(), &[], &mut reasks).await.is_err());
+    }
+}
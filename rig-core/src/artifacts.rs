@@ -0,0 +1,483 @@
+//! This module provides content-addressed stores of the binary outputs of tools and models
+//! (e.g.: generated images, audio, documents), so that they flow through messages and chat
+//! histories as small [ArtifactRef]s (URI, SHA-256 hash, MIME type and size) instead of large
+//! base64 blobs.
+//!
+//! Artifacts are stored under their SHA-256 hash, so storing the same content twice is a no-op,
+//! and their integrity is checked when they are read. Two stores are provided:
+//! - [LocalArtifactStore]: a local directory;
+//! - [ObjectArtifactStore]: an object store, e.g.: an S3 bucket (requires the `s3` feature).
+//!
+//! [offload] replaces the base64 images, audio and documents of a message by text references to
+//! artifacts, and [load_image] loads an artifact back as an image to send to a model.
+//!
+//! This module is only available with the `artifacts` feature.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     artifacts::{offload, ArtifactStore, LocalArtifactStore},
+//!     message::Message,
+//! };
+//!
+//! # async fn run(png: Vec<u8>, chat_history: Vec<Message>) -> Result<(), rig::artifacts::ArtifactError> {
+//! let store = LocalArtifactStore::new("artifacts")?;
+//!
+//! // A tool writes its output to the store and returns the reference
+//! let chart = store.put(png, "image/png").await?;
+//! println!("{}", chart.to_text());
+//!
+//! // Base64 contents of the chat history are replaced by references
+//! let mut history = vec![];
+//! for message in chat_history {
+//!     history.push(offload(&store, message).await?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    message::{
+        ContentFormat, Image, ImageMediaType, Message, MimeType, Text, ToolResultContent,
+        UserContent,
+    },
+    OneOrMany,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArtifactError {
+    /// Error reading or writing a local artifact
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// Error of the object store
+    #[cfg(feature = "s3")]
+    #[error("ObjectStoreError: {0}")]
+    ObjectStoreError(#[from] object_store::Error),
+
+    /// The URI of the artifact does not belong to the store
+    #[error("The artifact {0} does not belong to this store")]
+    ForeignUri(String),
+
+    /// The content of the artifact does not match its hash
+    #[error("The content of the artifact {uri} does not match its hash")]
+    HashMismatch { uri: String },
+
+    /// Base64 content that cannot be decoded
+    #[error("Base64Error: {0}")]
+    Base64Error(#[from] base64::DecodeError),
+}
+
+/// A reference to an artifact.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ArtifactRef {
+    pub uri: String,
+    /// SHA-256 hash of the content, in hexadecimal
+    pub sha256: String,
+    pub mime_type: String,
+    /// Size of the content, in bytes
+    pub size: u64,
+}
+
+impl ArtifactRef {
+    /// Create the reference of the content stored at the URI.
+    pub fn new(uri: String, data: &[u8], mime_type: &str) -> Self {
+        Self {
+            uri,
+            sha256: sha256(data),
+            mime_type: mime_type.to_string(),
+            size: data.len() as u64,
+        }
+    }
+
+    /// A compact text representation of the reference, e.g.: to replace the content in messages.
+    pub fn to_text(&self) -> String {
+        format!(
+            "[artifact: {} ({}, {} bytes, sha256:{})]",
+            self.uri, self.mime_type, self.size, self.sha256
+        )
+    }
+
+    /// Check that the content is the one of the artifact.
+    fn verify(&self, data: Vec<u8>) -> Result<Vec<u8>, ArtifactError> {
+        if sha256(&data) == self.sha256 {
+            Ok(data)
+        } else {
+            Err(ArtifactError::HashMismatch {
+                uri: self.uri.clone(),
+            })
+        }
+    }
+}
+
+/// The SHA-256 hash of the data, in hexadecimal.
+pub fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The key of an artifact: its hash (sharded by its first two characters) and the extension of
+/// its MIME type.
+fn key(sha256: &str, mime_type: &str) -> String {
+    let extension = match mime_type {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/wav" => "wav",
+        "audio/ogg" => "ogg",
+        "application/pdf" => "pdf",
+        "application/json" => "json",
+        "text/plain" => "txt",
+        _ => "bin",
+    };
+    format!("{}/{}.{}", &sha256[..2], sha256, extension)
+}
+
+/// A content-addressed store of artifacts.
+pub trait ArtifactStore: Send + Sync {
+    /// Store the content, if it is not already stored, and return its reference.
+    fn put(
+        &self,
+        data: Vec<u8>,
+        mime_type: &str,
+    ) -> impl Future<Output = Result<ArtifactRef, ArtifactError>> + Send;
+
+    /// Read the content of an artifact of the store, checking its hash.
+    fn get(
+        &self,
+        artifact: &ArtifactRef,
+    ) -> impl Future<Output = Result<Vec<u8>, ArtifactError>> + Send;
+}
+
+/// Stores the artifacts in a local directory, with `file://` URIs.
+#[derive(Clone, Debug)]
+pub struct LocalArtifactStore {
+    dir: PathBuf,
+}
+
+impl LocalArtifactStore {
+    /// Store the artifacts in the directory, which is created if needed.
+    pub fn new(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir: dir.as_ref().canonicalize()?,
+        })
+    }
+
+    fn path(&self, artifact: &ArtifactRef) -> Result<PathBuf, ArtifactError> {
+        artifact
+            .uri
+            .strip_prefix("file://")
+            .map(PathBuf::from)
+            .filter(|path| {
+                path.starts_with(&self.dir)
+                    && !path
+                        .components()
+                        .any(|component| component == std::path::Component::ParentDir)
+            })
+            .ok_or_else(|| ArtifactError::ForeignUri(artifact.uri.clone()))
+    }
+}
+
+impl ArtifactStore for LocalArtifactStore {
+    async fn put(&self, data: Vec<u8>, mime_type: &str) -> Result<ArtifactRef, ArtifactError> {
+        let hash = sha256(&data);
+        let path = self.dir.join(key(&hash, mime_type));
+        let artifact = ArtifactRef::new(format!("file://{}", path.display()), &data, mime_type);
+
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            // Write to a temporary file first, so that the artifact is never partially written
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, &data)?;
+            std::fs::rename(&tmp, &path)?;
+        }
+
+        Ok(artifact)
+    }
+
+    async fn get(&self, artifact: &ArtifactRef) -> Result<Vec<u8>, ArtifactError> {
+        artifact.verify(std::fs::read(self.path(artifact)?)?)
+    }
+}
+
+/// Stores the artifacts in an object store (e.g.: an S3 bucket), under an optional prefix.
+#[cfg(feature = "s3")]
+#[derive(Clone, Debug)]
+pub struct ObjectArtifactStore {
+    store: std::sync::Arc<dyn object_store::ObjectStore>,
+    base_uri: String,
+    prefix: String,
+}
+
+#[cfg(feature = "s3")]
+impl ObjectArtifactStore {
+    /// Store the artifacts in the given object store, whose objects have URIs starting with
+    /// `base_uri` (e.g.: `s3://bucket`).
+    pub fn new(store: std::sync::Arc<dyn object_store::ObjectStore>, base_uri: &str) -> Self {
+        Self {
+            store,
+            base_uri: base_uri.trim_end_matches('/').to_string(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Store the artifacts in an S3 bucket, configured from the `AWS_*` environment variables
+    /// (credentials, region, endpoint for S3-compatible services).
+    pub fn s3(bucket: &str) -> Result<Self, ArtifactError> {
+        let store = object_store::aws::AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()?;
+        Ok(Self::new(
+            std::sync::Arc::new(store),
+            &format!("s3://{bucket}"),
+        ))
+    }
+
+    /// Store the artifacts under the prefix (e.g.: `artifacts/`).
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_matches('/').to_string();
+        self
+    }
+
+    fn location(&self, key: &str) -> object_store::path::Path {
+        match self.prefix.as_str() {
+            "" => object_store::path::Path::from(key),
+            prefix => object_store::path::Path::from(format!("{prefix}/{key}")),
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+impl ArtifactStore for ObjectArtifactStore {
+    async fn put(&self, data: Vec<u8>, mime_type: &str) -> Result<ArtifactRef, ArtifactError> {
+        use object_store::ObjectStore;
+
+        let location = self.location(&key(&sha256(&data), mime_type));
+        let artifact =
+            ArtifactRef::new(format!("{}/{}", self.base_uri, location), &data, mime_type);
+
+        match self.store.head(&location).await {
+            Ok(_) => {}
+            Err(object_store::Error::NotFound { .. }) => {
+                self.store.put(&location, data.into()).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(artifact)
+    }
+
+    async fn get(&self, artifact: &ArtifactRef) -> Result<Vec<u8>, ArtifactError> {
+        use object_store::ObjectStore;
+
+        let location = artifact
+            .uri
+            .strip_prefix(&self.base_uri)
+            .and_then(|path| path.strip_prefix('/'))
+            .ok_or_else(|| ArtifactError::ForeignUri(artifact.uri.clone()))?;
+
+        let data = self
+            .store
+            .get(&object_store::path::Path::from(location))
+            .await?
+            .bytes()
+            .await?;
+        artifact.verify(data.to_vec())
+    }
+}
+
+/// Store the base64 content in the store, and return its text reference.
+async fn offload_base64(
+    store: &impl ArtifactStore,
+    data: &str,
+    mime_type: &str,
+) -> Result<Text, ArtifactError> {
+    let artifact = store.put(BASE64_STANDARD.decode(data)?, mime_type).await?;
+    Ok(Text {
+        text: artifact.to_text(),
+    })
+}
+
+/// Replace the base64 images, audio and documents of a message (including the images of tool
+/// results) by text references to artifacts of the store. Other contents are left as is.
+pub async fn offload(
+    store: &impl ArtifactStore,
+    message: Message,
+) -> Result<Message, ArtifactError> {
+    let (content, name) = match message {
+        Message::User { content, name } => (content, name),
+        message => return Ok(message),
+    };
+
+    let mut offloaded = vec![];
+    for content in content {
+        offloaded.push(match content {
+            UserContent::Image(image) if is_base64(&image.format) => {
+                let mime_type = image
+                    .media_type
+                    .as_ref()
+                    .map_or("image/jpeg", MimeType::to_mime_type);
+                UserContent::Text(offload_base64(store, &image.data, mime_type).await?)
+            }
+            UserContent::Audio(audio) if is_base64(&audio.format) => {
+                let mime_type = audio
+                    .media_type
+                    .as_ref()
+                    .map_or("application/octet-stream", MimeType::to_mime_type);
+                UserContent::Text(offload_base64(store, &audio.data, mime_type).await?)
+            }
+            UserContent::Document(document) if document.format == Some(ContentFormat::Base64) => {
+                let mime_type = document
+                    .media_type
+                    .as_ref()
+                    .map_or("application/octet-stream", MimeType::to_mime_type);
+                UserContent::Text(offload_base64(store, &document.data, mime_type).await?)
+            }
+            UserContent::ToolResult(mut result) => {
+                let mut contents = vec![];
+                for content in result.content {
+                    contents.push(match content {
+                        ToolResultContent::Image(image) if is_base64(&image.format) => {
+                            let mime_type = image
+                                .media_type
+                                .as_ref()
+                                .map_or("image/jpeg", MimeType::to_mime_type);
+                            ToolResultContent::Text(
+                                offload_base64(store, &image.data, mime_type).await?,
+                            )
+                        }
+                        content => content,
+                    });
+                }
+                result.content = OneOrMany::many(contents).expect("Tool results are not empty");
+                UserContent::ToolResult(result)
+            }
+            content => content,
+        });
+    }
+
+    Ok(Message::User {
+        content: OneOrMany::many(offloaded).expect("Messages are not empty"),
+        name,
+    })
+}
+
+/// Images without format are base64 images.
+fn is_base64(format: &Option<ContentFormat>) -> bool {
+    !matches!(format, Some(ContentFormat::String))
+}
+
+/// Load an image artifact as a base64 image, e.g.: to send it to a model.
+pub async fn load_image(
+    store: &impl ArtifactStore,
+    artifact: &ArtifactRef,
+) -> Result<Image, ArtifactError> {
+    let data = store.get(artifact).await?;
+    Ok(Image {
+        data: BASE64_STANDARD.encode(data),
+        format: Some(ContentFormat::Base64),
+        media_type: ImageMediaType::from_mime_type(&artifact.mime_type),
+        detail: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_store() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let store = LocalArtifactStore::new(dir.path()).unwrap();
+
+        let artifact = store.put(b"PNG data".to_vec(), "image/png").await.unwrap();
+        assert_eq!(
+            artifact.sha256,
+            "e0aca812fc3d38653d3820f80b03b24c932c1bb5ad889c099417d44f90be3cd8"
+        );
+        assert!(artifact
+            .uri
+            .ends_with(&format!("/e0/{}.png", artifact.sha256)));
+        assert_eq!(artifact.size, 8);
+
+        // The same content is stored once
+        assert_eq!(
+            store.put(b"PNG data".to_vec(), "image/png").await.unwrap(),
+            artifact
+        );
+        assert_eq!(store.get(&artifact).await.unwrap(), b"PNG data");
+
+        // Corrupted artifacts are detected
+        let path = artifact.uri.strip_prefix("file://").unwrap();
+        std::fs::write(path, b"Corrupted").unwrap();
+        assert!(matches!(
+            store.get(&artifact).await,
+            Err(ArtifactError::HashMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_offload() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let store = LocalArtifactStore::new(dir.path()).unwrap();
+
+        let message = Message::User {
+            content: OneOrMany::many([
+                UserContent::text("What is in this image?"),
+                UserContent::image(
+                    BASE64_STANDARD.encode(b"PNG data"),
+                    Some(ContentFormat::Base64),
+                    Some(ImageMediaType::PNG),
+                    None,
+                ),
+            ])
+            .unwrap(),
+            name: None,
+        };
+
+        let offloaded = offload(&store, message).await.unwrap();
+        let Message::User { content, .. } = offloaded else {
+            panic!("Unexpected message");
+        };
+        let UserContent::Text(Text { text }) = content.rest()[0].clone() else {
+            panic!("Unexpected content");
+        };
+        assert!(text.starts_with("[artifact: file://"));
+        assert!(text.ends_with("(image/png, 8 bytes, sha256:e0aca812fc3d38653d3820f80b03b24c932c1bb5ad889c099417d44f90be3cd8)]"));
+    }
+
+    #[cfg(feature = "s3")]
+    #[tokio::test]
+    async fn test_object_store() {
+        let store = ObjectArtifactStore::new(
+            std::sync::Arc::new(object_store::memory::InMemory::new()),
+            "memory://bucket/",
+        )
+        .with_prefix("/artifacts/");
+
+        let artifact = store.put(b"PNG data".to_vec(), "image/png").await.unwrap();
+        assert_eq!(
+            artifact.uri,
+            format!("memory://bucket/artifacts/e0/{}.png", artifact.sha256)
+        );
+
+        let image = load_image(&store, &artifact).await.unwrap();
+        assert_eq!(image.data, BASE64_STANDARD.encode(b"PNG data"));
+        assert_eq!(image.media_type, Some(ImageMediaType::PNG));
+    }
+}
@@ -0,0 +1,544 @@
+//! This module provides an audit logger that records every completion and embedding call made
+//! through a model as JSON lines appended to a file, for compliance and offline analysis.
+//!
+//! Each line is an [AuditEntry] containing a timestamp, the ID of the run (shared by all the
+//! entries written by the same [AuditLogger]), the request, and the response or error. The parts
+//! of the calls that should not be persisted (e.g.: user prompts) can be redacted with a
+//! [RedactionPolicy].
+//!
+//! Entries are written to the file by a dedicated background thread, so logging never blocks
+//! the calls. Use [AuditLogger::flush] to wait until all the logged entries are written.
+//!
+//! This module is only available with the `audit` feature.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use rig::{
+//!     audit::{Audited, AuditLogger, RedactionPolicy},
+//!     completion::Prompt,
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let logger = Arc::new(
+//!     AuditLogger::new("audit.jsonl")?
+//!         .with_policy(RedactionPolicy::none().redact_prompts(true)),
+//! );
+//!
+//! let openai = openai::Client::from_env();
+//! let model = Audited::new(openai.completion_model(openai::GPT_4O), logger.clone());
+//! let embedding_model = Audited::new(
+//!     openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+//!     logger.clone(),
+//! );
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//! let answer = agent.prompt("Hello!").await?;
+//!
+//! logger.flush()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{mpsc, Arc},
+    thread::JoinHandle,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    conversation::formats::Transcript,
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    http::RequestOptions,
+    message::Message,
+    test_mode,
+};
+
+/// Value that replaces redacted content in the audit log.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Defines which parts of the audited calls are replaced by [REDACTED] in the audit log.
+/// By default, nothing is redacted.
+#[derive(Clone, Debug, Default)]
+pub struct RedactionPolicy {
+    prompts: bool,
+    responses: bool,
+    documents: bool,
+    embedding_inputs: bool,
+    params: Vec<String>,
+}
+
+impl RedactionPolicy {
+    /// A policy that does not redact anything.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// A policy that redacts all the content of the calls, keeping only their metadata
+    /// (e.g.: timestamps, durations, token counts).
+    pub fn all() -> Self {
+        Self {
+            prompts: true,
+            responses: true,
+            documents: true,
+            embedding_inputs: true,
+            params: vec![],
+        }
+    }
+
+    /// Redact the preamble, prompt and chat history of completion requests.
+    pub fn redact_prompts(mut self, redact: bool) -> Self {
+        self.prompts = redact;
+        self
+    }
+
+    /// Redact the content returned by completion models.
+    pub fn redact_responses(mut self, redact: bool) -> Self {
+        self.responses = redact;
+        self
+    }
+
+    /// Redact the context documents of completion requests.
+    pub fn redact_documents(mut self, redact: bool) -> Self {
+        self.documents = redact;
+        self
+    }
+
+    /// Redact the texts sent to embedding models.
+    pub fn redact_embedding_inputs(mut self, redact: bool) -> Self {
+        self.embedding_inputs = redact;
+        self
+    }
+
+    /// Redact the given additional parameter of completion requests (e.g.: `"user"`).
+    pub fn redact_param(mut self, key: &str) -> Self {
+        self.params.push(key.to_string());
+        self
+    }
+}
+
+fn redact_if(redact: bool, value: Value) -> Value {
+    if redact {
+        json!(REDACTED)
+    } else {
+        value
+    }
+}
+
+/// The kind of call recorded by an [AuditEntry].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    Completion,
+    Embedding,
+}
+
+/// A single line of the audit log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Time at which the call was made, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub run_id: String,
+    pub kind: AuditKind,
+    pub duration_ms: u64,
+    pub request: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// The successful completion of the entry as a transcript (the chat history, prompt and
+    /// response, with the preamble as system prompt), e.g.: to build fine-tuning datasets.
+    ///
+    /// Returns `None` for embedding entries, failed completions and entries whose prompt, chat
+    /// history or response was redacted.
+    pub fn transcript(&self) -> Option<Transcript> {
+        if self.kind != AuditKind::Completion {
+            return None;
+        }
+
+        let mut messages: Vec<Message> =
+            serde_json::from_value(self.request["chat_history"].clone()).ok()?;
+        messages.push(serde_json::from_value(self.request["prompt"].clone()).ok()?);
+        messages.push(Message::Assistant {
+            content: serde_json::from_value(self.response.as_ref()?["choice"].clone()).ok()?,
+            name: None,
+        });
+
+        let transcript = Transcript::new(messages);
+        match &self.request["preamble"] {
+            Value::Null => Some(transcript),
+            Value::String(preamble) => Some(transcript.with_system(preamble)),
+            _ => None,
+        }
+    }
+}
+
+/// Read the entries of an audit log.
+pub fn read_log(path: impl AsRef<Path>) -> std::io::Result<Vec<AuditEntry>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(std::io::Error::from))
+        .collect()
+}
+
+/// Message sent to the writer thread of an [AuditLogger].
+enum WriterMessage {
+    Line(String),
+    /// Flush the file and report the result once all the previous lines are written
+    Flush(mpsc::SyncSender<std::io::Result<()>>),
+}
+
+/// Appends [AuditEntry]s as JSON lines to a file, from a background thread.
+/// Dropping the logger waits for the pending entries to be written.
+pub struct AuditLogger {
+    sender: Option<mpsc::Sender<WriterMessage>>,
+    writer: Option<JoinHandle<()>>,
+    run_id: String,
+    policy: RedactionPolicy,
+}
+
+impl AuditLogger {
+    /// Open (or create) the audit file at the given path. Entries are appended to the file.
+    /// A new run ID is generated (see [test_mode::generate_id]).
+    pub fn new(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("rig-audit-writer".to_string())
+            .spawn(move || write_lines(file, receiver))?;
+
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            run_id: test_mode::generate_id("run"),
+            policy: RedactionPolicy::default(),
+        })
+    }
+
+    /// Use the given run ID instead of a generated one.
+    pub fn with_run_id(mut self, run_id: &str) -> Self {
+        self.run_id = run_id.to_string();
+        self
+    }
+
+    /// Use the given redaction policy.
+    pub fn with_policy(mut self, policy: RedactionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Queue an entry to be appended to the audit file. Does not wait for the entry to be
+    /// written: errors occurring while writing it are logged by the writer thread.
+    pub fn log(&self, entry: &AuditEntry) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.send(WriterMessage::Line(line))
+    }
+
+    /// Wait until all the entries logged so far are written to the audit file. Blocks the
+    /// current thread.
+    pub fn flush(&self) -> std::io::Result<()> {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        self.send(WriterMessage::Flush(sender))?;
+        receiver.recv().map_err(|_| writer_stopped())?
+    }
+
+    fn send(&self, message: WriterMessage) -> std::io::Result<()> {
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(message).ok())
+            .ok_or_else(writer_stopped)
+    }
+
+    fn record(
+        &self,
+        kind: AuditKind,
+        timestamp: u64,
+        started: Instant,
+        request: Value,
+        result: Result<Value, String>,
+    ) {
+        let (response, error) = match result {
+            Ok(response) => (Some(response), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        let entry = AuditEntry {
+            timestamp,
+            run_id: self.run_id.clone(),
+            kind,
+            duration_ms: started.elapsed().as_millis() as u64,
+            request,
+            response,
+            error,
+        };
+
+        if let Err(e) = self.log(&entry) {
+            tracing::warn!("Failed to write audit log entry: {}", e);
+        }
+    }
+
+    fn completion_request(&self, request: &CompletionRequest) -> Value {
+        let policy = &self.policy;
+
+        let additional_params = request.additional_params.clone().map(|mut params| {
+            if let Value::Object(map) = &mut params {
+                for key in &policy.params {
+                    if let Some(value) = map.get_mut(key) {
+                        *value = json!(REDACTED);
+                    }
+                }
+            }
+            params
+        });
+
+        json!({
+            "preamble": redact_if(policy.prompts, json!(request.preamble)),
+            "prompt": redact_if(policy.prompts, json!(request.prompt)),
+            "chat_history": redact_if(policy.prompts, json!(request.chat_history)),
+            "documents": redact_if(policy.documents, json!(request.documents)),
+            "tools": request.tools.iter().map(|tool| &tool.name).collect::<Vec<_>>(),
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+            "additional_params": additional_params,
+        })
+    }
+}
+
+impl Drop for AuditLogger {
+    fn drop(&mut self) {
+        // Closing the channel stops the writer thread once the pending lines are written
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Loop of the writer thread: append the received lines to the file, flushing it whenever
+/// there are no more pending lines.
+fn write_lines(file: File, receiver: mpsc::Receiver<WriterMessage>) {
+    let mut file = BufWriter::new(file);
+    let mut error = None;
+
+    while let Ok(mut message) = receiver.recv() {
+        loop {
+            match message {
+                WriterMessage::Line(line) => {
+                    if let Err(e) = file.write_all(line.as_bytes()) {
+                        tracing::warn!("Failed to write audit log entry: {}", e);
+                        error = Some(e);
+                    }
+                }
+                WriterMessage::Flush(reply) => {
+                    let result = match (error.take(), file.flush()) {
+                        (Some(e), _) | (None, Err(e)) => Err(e),
+                        (None, Ok(())) => Ok(()),
+                    };
+                    let _ = reply.send(result);
+                }
+            }
+            match receiver.try_recv() {
+                Ok(next) => message = next,
+                Err(_) => break,
+            }
+        }
+
+        if let Err(e) = file.flush() {
+            tracing::warn!("Failed to write audit log entries: {}", e);
+        }
+    }
+}
+
+fn writer_stopped() -> std::io::Error {
+    std::io::Error::other("the audit log writer thread has stopped")
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Wraps a completion or embedding model and records all its calls with an [AuditLogger].
+#[derive(Clone)]
+pub struct Audited<M> {
+    pub model: M,
+    logger: Arc<AuditLogger>,
+}
+
+impl<M> Audited<M> {
+    pub fn new(model: M, logger: Arc<AuditLogger>) -> Self {
+        Self { model, logger }
+    }
+}
+
+impl<M: CompletionModel> CompletionModel for Audited<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let timestamp = now_ms();
+        let started = Instant::now();
+        let logged_request = self.logger.completion_request(&request);
+
+        let result = self.model.completion(request).await;
+
+        self.logger.record(
+            AuditKind::Completion,
+            timestamp,
+            started,
+            logged_request,
+            match &result {
+                Ok(response) => Ok(json!({
+                    "choice": redact_if(self.logger.policy.responses, json!(response.choice)),
+                    "annotations": response.annotations,
+                    "finish_reason": response.finish_reason,
+                })),
+                Err(e) => Err(e.to_string()),
+            },
+        );
+
+        result
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+impl<M: EmbeddingModel> EmbeddingModel for Audited<M> {
+    const MAX_DOCUMENTS: usize = M::MAX_DOCUMENTS;
+
+    fn ndims(&self) -> usize {
+        self.model.ndims()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(texts, RequestOptions::default())
+            .await
+    }
+
+    async fn embed_texts_with_options(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        let texts = texts.into_iter().collect::<Vec<_>>();
+        let timestamp = now_ms();
+        let started = Instant::now();
+        let logged_request = json!({
+            "count": texts.len(),
+            "texts": redact_if(self.logger.policy.embedding_inputs, json!(texts)),
+        });
+
+        let result = self
+            .model
+            .embed_texts_with_options(texts, request_options)
+            .await;
+
+        self.logger.record(
+            AuditKind::Embedding,
+            timestamp,
+            started,
+            logged_request,
+            match &result {
+                Ok(embeddings) => Ok(json!({
+                    "count": embeddings.len(),
+                    "ndims": embeddings.first().map(|embedding| embedding.vec.len()),
+                })),
+                Err(e) => Err(e.to_string()),
+            },
+        );
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use assert_fs::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        test_utils::{MockEmbeddingModel, MockModel},
+    };
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.child("audit.jsonl");
+
+        let logger = Arc::new(
+            AuditLogger::new(path.path())
+                .unwrap()
+                .with_run_id("run_test")
+                .with_policy(
+                    RedactionPolicy::none()
+                        .redact_prompts(true)
+                        .redact_param("user"),
+                ),
+        );
+        let model = Audited::new(MockModel::text("Hi!"), logger.clone());
+
+        CompletionRequestBuilder::new(model, "Hello")
+            .additional_params(json!({"user": "alice", "top_p": 0.5}))
+            .send()
+            .await
+            .unwrap();
+        Audited::new(MockEmbeddingModel::constant(vec![0.0, 1.0]), logger.clone())
+            .embed_text("Glarb-glarb")
+            .await
+            .unwrap();
+        logger.flush().unwrap();
+
+        let entries = read_log(path.path()).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.run_id == "run_test"));
+
+        assert_eq!(entries[0].kind, AuditKind::Completion);
+        assert_eq!(entries[0].request["prompt"], json!(REDACTED));
+        assert_eq!(
+            entries[0].request["additional_params"],
+            json!({"user": REDACTED, "top_p": 0.5})
+        );
+        assert_eq!(
+            entries[0].response.as_ref().unwrap()["choice"],
+            json!([{"text": "Hi!"}])
+        );
+
+        assert_eq!(entries[1].kind, AuditKind::Embedding);
+        assert_eq!(entries[1].request["texts"], json!(["Glarb-glarb"]));
+        assert_eq!(entries[1].response, Some(json!({"count": 1, "ndims": 2})));
+    }
+}
@@ -0,0 +1,366 @@
+//! This module provides [SemanticCache], a completion model wrapper that returns cached responses
+//! for prompts that are semantically similar to previous ones.
+//!
+//! Incoming prompts are embedded with an [EmbeddingModel] and compared (using cosine similarity)
+//! to the prompts of the cached responses. If the similarity with one of them is above the
+//! threshold, the cached response is returned without calling the model. Only responses to
+//! requests with the same context (preamble, chat history and tools) can be reused.
+//!
+//! Cached responses expire after an optional TTL, and can be invalidated manually with
+//! [SemanticCache::invalidate], [SemanticCache::invalidate_similar] and [SemanticCache::clear].
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use rig::{cache::SemanticCache, completion::Prompt, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//!
+//! let model = SemanticCache::new(
+//!     openai.completion_model(openai::GPT_4O),
+//!     openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+//! )
+//! .with_threshold(0.95)
+//! .with_ttl(Duration::from_secs(3600));
+//!
+//! let agent = rig::agent::AgentBuilder::new(model.clone())
+//!     .preamble("You are the FAQ assistant of ACME Inc.")
+//!     .build();
+//!
+//! agent.prompt("How do I reset my password?").await?;
+//! // Served from the cache
+//! agent.prompt("How can I reset my password?").await?;
+//!
+//! // The password reset procedure changed, forget the related answers
+//! model.invalidate(|prompt| prompt.contains("password"));
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    completion::{
+        Annotation, CompletionError, CompletionModel, CompletionRequest, CompletionResponse,
+        FinishReason,
+    },
+    embeddings::{distance::VectorDistance, Embedding, EmbeddingModel},
+    message::AssistantContent,
+    OneOrMany,
+};
+
+struct CacheEntry {
+    /// Hash of the context of the request (preamble, chat history and tools)
+    context: u64,
+    prompt: Embedding,
+    choice: OneOrMany<AssistantContent>,
+    alternatives: Vec<OneOrMany<AssistantContent>>,
+    annotations: Vec<Annotation>,
+    finish_reason: Option<FinishReason>,
+    created_at: Instant,
+}
+
+/// Number of cache hits and misses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct CacheState {
+    entries: Vec<CacheEntry>,
+    stats: CacheStats,
+}
+
+/// Wraps a completion model and caches its responses, keyed by the embedding of the prompts.
+/// Clones of a [SemanticCache] share the same cache.
+///
+/// The raw response of the completions served from the cache is `None`.
+pub struct SemanticCache<M: CompletionModel, E: EmbeddingModel> {
+    pub model: M,
+    embedding_model: E,
+    threshold: f64,
+    ttl: Option<Duration>,
+    max_entries: usize,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl<M: CompletionModel, E: EmbeddingModel> Clone for SemanticCache<M, E> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            embedding_model: self.embedding_model.clone(),
+            threshold: self.threshold,
+            ttl: self.ttl,
+            max_entries: self.max_entries,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<M: CompletionModel, E: EmbeddingModel> SemanticCache<M, E> {
+    /// Create a new cache with a similarity threshold of 0.95, no TTL and at most 1000 entries.
+    pub fn new(model: M, embedding_model: E) -> Self {
+        Self {
+            model,
+            embedding_model,
+            threshold: 0.95,
+            ttl: None,
+            max_entries: 1000,
+            state: Arc::new(Mutex::new(CacheState {
+                entries: vec![],
+                stats: CacheStats::default(),
+            })),
+        }
+    }
+
+    /// Set the minimum cosine similarity between two prompts for a cached response to be reused.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the duration after which cached responses expire.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Set the maximum number of cached responses. When the cache is full, the oldest
+    /// response is evicted.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// The number of cache hits and misses so far.
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    /// The number of responses currently cached (including expired ones not evicted yet).
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the cached responses.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Remove the cached responses whose prompt matches the predicate.
+    pub fn invalidate(&self, predicate: impl Fn(&str) -> bool) {
+        self.lock()
+            .entries
+            .retain(|entry| !predicate(&entry.prompt.document));
+    }
+
+    /// Remove the cached responses whose prompt is similar (as defined by the threshold) to the
+    /// given text, regardless of their context.
+    pub async fn invalidate_similar(&self, text: &str) -> Result<(), CompletionError> {
+        let embedding = self.embed(text).await?;
+        let threshold = self.threshold;
+
+        self.lock()
+            .entries
+            .retain(|entry| entry.prompt.cosine_similarity(&embedding, false) < threshold);
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn embed(&self, text: &str) -> Result<Embedding, CompletionError> {
+        self.embedding_model
+            .embed_text(text)
+            .await
+            .map_err(|e| CompletionError::RequestError(Box::new(e)))
+    }
+
+    /// Look up a cached response, evicting expired entries.
+    fn lookup(
+        &self,
+        context: u64,
+        prompt: &Embedding,
+    ) -> Option<CompletionResponse<Option<M::Response>>> {
+        let mut state = self.lock();
+
+        if let Some(ttl) = self.ttl {
+            state
+                .entries
+                .retain(|entry| entry.created_at.elapsed() < ttl);
+        }
+
+        let response = state
+            .entries
+            .iter()
+            .filter(|entry| entry.context == context)
+            .map(|entry| (entry.prompt.cosine_similarity(prompt, false), entry))
+            .filter(|(similarity, _)| *similarity >= self.threshold)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, entry)| CompletionResponse {
+                choice: entry.choice.clone(),
+                annotations: entry.annotations.clone(),
+                alternatives: entry.alternatives.clone(),
+                finish_reason: entry.finish_reason.clone(),
+                raw_response: None,
+            });
+
+        match response {
+            Some(_) => state.stats.hits += 1,
+            None => state.stats.misses += 1,
+        }
+        response
+    }
+
+    fn insert(&self, entry: CacheEntry) {
+        let mut state = self.lock();
+
+        if state.entries.len() >= self.max_entries {
+            state.entries.remove(0);
+        }
+        state.entries.push(entry);
+    }
+}
+
+/// Hash of the parts of a request, other than the prompt, that the response depends on.
+fn context_hash(request: &CompletionRequest) -> u64 {
+    let context = serde_json::json!({
+        "preamble": request.preamble,
+        "chat_history": request.chat_history,
+        "tools": request.tools,
+    });
+
+    let mut hasher = DefaultHasher::new();
+    context.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<M: CompletionModel, E: EmbeddingModel> CompletionModel for SemanticCache<M, E> {
+    type Response = Option<M::Response>;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        // Prompts without text (e.g.: tool results) are not cached
+        let Some(text) = request.prompt.rag_text() else {
+            return Ok(with_raw_response(self.model.completion(request).await?));
+        };
+
+        let context = context_hash(&request);
+        let prompt = self.embed(&text).await?;
+
+        if let Some(response) = self.lookup(context, &prompt) {
+            return Ok(response);
+        }
+
+        let response = self.model.completion(request).await?;
+        self.insert(CacheEntry {
+            context,
+            prompt,
+            choice: response.choice.clone(),
+            annotations: response.annotations.clone(),
+            alternatives: response.alternatives.clone(),
+            finish_reason: response.finish_reason.clone(),
+            created_at: Instant::now(),
+        });
+
+        Ok(with_raw_response(response))
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+fn with_raw_response<R>(response: CompletionResponse<R>) -> CompletionResponse<Option<R>> {
+    CompletionResponse {
+        choice: response.choice,
+        annotations: response.annotations,
+        alternatives: response.alternatives,
+        finish_reason: response.finish_reason,
+        raw_response: Some(response.raw_response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        test_utils::{MockEmbeddingModel, MockModel},
+    };
+
+    /// Embeds texts by counting some keywords
+    fn embedding() -> MockEmbeddingModel {
+        MockEmbeddingModel::counting(&["password", "refund"])
+    }
+
+    fn mock() -> MockModel {
+        MockModel::reply(|request| format!("Answer to {}", request.prompt.rag_text().unwrap()))
+    }
+
+    /// Send the prompt and return the number of calls made to the underlying model so far
+    async fn prompt(cache: &SemanticCache<MockModel, MockEmbeddingModel>, prompt: &str) -> usize {
+        CompletionRequestBuilder::new(cache.clone(), prompt)
+            .preamble("You are a FAQ bot.".to_string())
+            .send()
+            .await
+            .unwrap();
+        cache.model.calls()
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache() {
+        let cache = SemanticCache::new(mock(), embedding());
+
+        assert_eq!(prompt(&cache, "How do I reset my password?").await, 1);
+        assert_eq!(prompt(&cache, "Reset password please").await, 1);
+        assert_eq!(prompt(&cache, "How do I get a refund?").await, 2);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+
+        // Different context
+        let response = CompletionRequestBuilder::new(cache.clone(), "Reset password please")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.raw_response.is_some());
+        assert_eq!(cache.model.calls(), 3);
+
+        cache.invalidate(|prompt| prompt.contains("refund"));
+        assert_eq!(prompt(&cache, "refund?").await, 4);
+
+        cache
+            .invalidate_similar("I forgot my password")
+            .await
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(prompt(&cache, "password").await, 5);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache_ttl() {
+        let cache = SemanticCache::new(mock(), embedding()).with_ttl(Duration::from_millis(20));
+
+        assert_eq!(prompt(&cache, "password").await, 1);
+        assert_eq!(prompt(&cache, "password").await, 1);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(prompt(&cache, "password").await, 2);
+        assert_eq!(cache.len(), 1);
+    }
+}
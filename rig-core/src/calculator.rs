@@ -0,0 +1,918 @@
+//! This module provides [CalculatorTool], a deterministic calculator for agents, so that
+//! numeric tasks do not rely on the arithmetic of the model.
+//!
+//! Expressions are evaluated with arbitrary-precision decimals ([Decimal]): additions,
+//! subtractions and multiplications are exact, and divisions are computed to (at least) 50
+//! decimal places.
+//! The calculator supports `+`, `-`, `*`, `/`, `%`, `^` (integer exponents) and parentheses,
+//! and unit conversions with `to` or `in` (e.g.: `(3 + 2) km to mi`, `100 F to C`).
+//!
+//! # Example
+//! ```rust
+//! use rig::calculator::evaluate;
+//!
+//! assert_eq!(evaluate("0.1 + 0.2").unwrap().to_string(), "0.3");
+//! assert_eq!(evaluate("2 ^ 100").unwrap().to_string(), "1267650600228229401496703205376");
+//! assert_eq!(evaluate("1 mi to km").unwrap().to_string(), "1.609344");
+//! ```
+use std::{fmt, str::FromStr};
+
+use bigdecimal::{num_bigint::BigInt, BigDecimal, Pow, RoundingMode, Signed, ToPrimitive, Zero};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{completion::ToolDefinition, tool::Tool};
+
+/// Number of decimal places of the results of divisions.
+const DIVISION_PLACES: usize = 50;
+
+/// Maximum absolute value of exponents, to bound the size of the results.
+const MAX_EXPONENT: u32 = 10_000;
+
+/// Maximum number of digits of the results of the exponentiations and multiplications of an
+/// expression, which would otherwise be unbounded with large bases or nested exponents (e.g.:
+/// `(9^10000)^10000`).
+const MAX_DIGITS: usize = 20_000;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CalculatorError {
+    #[error("ParseError: {0}")]
+    ParseError(String),
+
+    #[error("Division by zero")]
+    DivisionByZero,
+
+    #[error("Unsupported exponent: {0} (exponents must be integers between -10000 and 10000)")]
+    UnsupportedExponent(String),
+
+    #[error("Result too large: {0} has more than 20000 digits")]
+    ResultTooLarge(String),
+
+    #[error("Unknown unit: {0}")]
+    UnknownUnit(String),
+
+    #[error("Cannot convert {0} to {1}")]
+    IncompatibleUnits(String, String),
+}
+
+/// An arbitrary-precision decimal number, backed by [BigDecimal]. Its scale is never negative
+/// and its decimal places have no trailing zeros.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Decimal(BigDecimal);
+
+impl Decimal {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0.is_negative()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.scale() == 0
+    }
+
+    /// Remove the trailing zeros of the decimal places (but not of the integer part).
+    fn normalized(value: BigDecimal) -> Self {
+        let value = value.normalized();
+        if value.fractional_digit_count() < 0 {
+            Self(value.with_scale(0))
+        } else {
+            Self(value)
+        }
+    }
+
+    fn scale(&self) -> i64 {
+        self.0.fractional_digit_count()
+    }
+
+    /// Fail if the number has more than [MAX_DIGITS] digits, which bounds the cost of the
+    /// following operations (e.g.: chained divisions growing the scale by 50 each).
+    fn bounded(self, operation: impl FnOnce() -> String) -> Result<Self, CalculatorError> {
+        if self.0.digits() > MAX_DIGITS as u64 {
+            return Err(CalculatorError::ResultTooLarge(operation()));
+        }
+        Ok(self)
+    }
+
+    pub fn neg(&self) -> Self {
+        Self(-&self.0)
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        Self::normalized(&self.0 + &other.0)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        Self::normalized(&self.0 - &other.0)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::normalized(&self.0 * &other.0)
+    }
+
+    /// Multiply, failing if the result could have more than [MAX_DIGITS] digits.
+    fn checked_mul(&self, other: &Self) -> Result<Self, CalculatorError> {
+        if self.0.digits() + other.0.digits() > MAX_DIGITS as u64 {
+            return Err(CalculatorError::ResultTooLarge(format!("{self} * {other}")));
+        }
+        Ok(self.mul(other))
+    }
+
+    /// Divide, truncating the result to 50 more decimal places than the dividend, and failing
+    /// if it has more than `MAX_DIGITS` digits.
+    pub fn div(&self, other: &Self) -> Result<Self, CalculatorError> {
+        if other.is_zero() {
+            return Err(CalculatorError::DivisionByZero);
+        }
+        // self / other = (a / 10^sa) / (b / 10^sb) = (a * 10^(sb + P) / b) / 10^(sa + P)
+        let (a, sa) = self.0.as_bigint_and_exponent();
+        let (b, sb) = other.0.as_bigint_and_exponent();
+        let shift = u32::try_from(sb).unwrap_or(0) + DIVISION_PLACES as u32;
+        let quotient = a * BigInt::from(10).pow(shift) / b;
+        Self::normalized(BigDecimal::new(quotient, sa + DIVISION_PLACES as i64))
+            .bounded(|| format!("{self} / {other}"))
+    }
+
+    /// Remainder of the truncated division, with the sign of the dividend.
+    pub fn rem(&self, other: &Self) -> Result<Self, CalculatorError> {
+        if other.is_zero() {
+            return Err(CalculatorError::DivisionByZero);
+        }
+        Ok(Self::normalized(&self.0 % &other.0))
+    }
+
+    /// Raise to an integer power.
+    pub fn pow(&self, exponent: &Self) -> Result<Self, CalculatorError> {
+        let power = exponent
+            .is_integer()
+            .then(|| exponent.0.abs().to_u32())
+            .flatten()
+            .filter(|power| *power <= MAX_EXPONENT)
+            .ok_or_else(|| CalculatorError::UnsupportedExponent(exponent.to_string()))?;
+
+        // Bound the size of the result before computing it, from a lower bound of the base 10
+        // logarithm of the digits of the base
+        let (digits, _) = self.0.as_bigint_and_exponent();
+        let log10 = digits.bits().saturating_sub(1) as f64 * std::f64::consts::LOG10_2;
+        if f64::from(power) * log10 >= MAX_DIGITS as f64 {
+            return Err(CalculatorError::ResultTooLarge(format!(
+                "{self} ^ {exponent}"
+            )));
+        }
+
+        let result = Self::normalized(self.0.powi(i64::from(power)))
+            .bounded(|| format!("{self} ^ {exponent}"))?;
+        if exponent.is_negative() {
+            Self::from(1).div(&result)
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Round to the given number of decimal places (half away from zero).
+    pub fn round(&self, places: usize) -> Self {
+        if self.scale() <= places as i64 {
+            return self.clone();
+        }
+        Self::normalized(self.0.with_scale_round(places as i64, RoundingMode::HalfUp))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        Self(BigDecimal::from(n))
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = CalculatorError;
+
+    /// Parse a decimal number (e.g.: `-12.5`, `1e-3`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CalculatorError::ParseError(format!("Invalid number: {s}"));
+
+        // Validate the number before parsing it, as `BigDecimal` accepts exponents of any size
+        let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => {
+                (mantissa, exponent.parse::<i32>().map_err(|_| invalid())?)
+            }
+            None => (unsigned, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+            || exponent.unsigned_abs() > MAX_EXPONENT
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self::normalized(s.parse().map_err(|_| invalid())?))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_plain_string(f)
+    }
+}
+
+/// Recursive descent parser of arithmetic expressions.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> CalculatorError {
+        CalculatorError::ParseError(format!("{message} at position {}", self.position))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume one of the given operators, if it is next.
+    fn operator(&mut self, operators: &[&'static str]) -> Option<&'static str> {
+        self.skip_whitespace();
+        let operator = operators
+            .iter()
+            .find(|operator| self.input[self.position..].starts_with(**operator))?;
+        self.position += operator.len();
+        Some(operator)
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Decimal, CalculatorError> {
+        let mut value = self.term()?;
+        while let Some(operator) = self.operator(&["+", "-"]) {
+            let rhs = self.term()?;
+            value = match operator {
+                "+" => value.add(&rhs),
+                _ => value.sub(&rhs),
+            };
+        }
+        Ok(value)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Decimal, CalculatorError> {
+        let mut value = self.unary()?;
+        loop {
+            // `**` is a power, not a multiplication
+            self.skip_whitespace();
+            if self.input[self.position..].starts_with("**") {
+                return Ok(value);
+            }
+            let Some(operator) = self.operator(&["*", "/", "%"]) else {
+                return Ok(value);
+            };
+            let rhs = self.unary()?;
+            value = match operator {
+                "*" => value.checked_mul(&rhs)?,
+                "/" => value.div(&rhs)?,
+                _ => value.rem(&rhs)?,
+            };
+        }
+    }
+
+    /// unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<Decimal, CalculatorError> {
+        match self.operator(&["-", "+"]) {
+            Some("-") => Ok(self.unary()?.neg()),
+            Some(_) => self.unary(),
+            None => self.power(),
+        }
+    }
+
+    /// power := primary (('^' | '**') unary)?
+    fn power(&mut self) -> Result<Decimal, CalculatorError> {
+        let base = self.primary()?;
+        match self.operator(&["^", "**"]) {
+            Some(_) => base.pow(&self.unary()?),
+            None => Ok(base),
+        }
+    }
+
+    /// primary := number | '(' expression ')'
+    fn primary(&mut self) -> Result<Decimal, CalculatorError> {
+        if self.operator(&["("]).is_some() {
+            let value = self.expression()?;
+            return match self.operator(&[")"]) {
+                Some(_) => Ok(value),
+                None => Err(self.error("Expected `)`")),
+            };
+        }
+
+        self.skip_whitespace();
+        let rest = &self.input[self.position..];
+        let mut end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        // Scientific notation (e.g.: `1.5e-3`)
+        if end > 0 && rest[end..].starts_with(['e', 'E']) {
+            let exponent = rest[end + 1..]
+                .strip_prefix(['-', '+'])
+                .unwrap_or(&rest[end + 1..]);
+            let digits = exponent
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(exponent.len());
+            if digits > 0 {
+                end = rest.len() - exponent.len() + digits;
+            }
+        }
+        if end == 0 {
+            return Err(self.error("Expected a number"));
+        }
+        self.position += end;
+        rest[..end].parse()
+    }
+}
+
+/// Evaluate an arithmetic expression, optionally followed by a unit conversion (e.g.:
+/// `2 * 3.5 km to mi`), see the [module documentation](self).
+pub fn evaluate(expression: &str) -> Result<Decimal, CalculatorError> {
+    // ASCII lowercasing keeps the byte offsets of the original expression
+    let lowercase = expression.to_ascii_lowercase();
+    let conversion = [" to ", " in "]
+        .iter()
+        .filter_map(|keyword| lowercase.rfind(keyword).map(|i| (i, keyword.len())))
+        .max();
+
+    match conversion {
+        Some((i, len)) => {
+            let (quantity, to) = (&expression[..i], expression[i + len..].trim());
+            let quantity = quantity.trim_end();
+            let (value, from) = split_unit(quantity).ok_or_else(|| {
+                CalculatorError::UnknownUnit(
+                    quantity
+                        .rsplit(|c: char| c.is_ascii_digit() || c == ')' || c.is_whitespace())
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                )
+            })?;
+            convert(&evaluate_arithmetic(value)?, from, to)
+        }
+        None => evaluate_arithmetic(expression),
+    }
+}
+
+fn evaluate_arithmetic(expression: &str) -> Result<Decimal, CalculatorError> {
+    let mut parser = Parser {
+        input: expression,
+        position: 0,
+    };
+    let value = parser.expression()?;
+    parser.skip_whitespace();
+    if parser.position < expression.len() {
+        return Err(parser.error("Unexpected input"));
+    }
+    Ok(value)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Volume,
+    Data,
+    Speed,
+    Energy,
+    Temperature,
+}
+
+/// The units, with their dimension and their value in the base unit of the dimension, as a
+/// fraction.
+const UNITS: &[(&[&str], Dimension, &str, &str)] = &[
+    (
+        &["m", "meter", "meters", "metre", "metres"],
+        Dimension::Length,
+        "1",
+        "1",
+    ),
+    (
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        Dimension::Length,
+        "1000",
+        "1",
+    ),
+    (
+        &["cm", "centimeter", "centimeters"],
+        Dimension::Length,
+        "0.01",
+        "1",
+    ),
+    (
+        &["mm", "millimeter", "millimeters"],
+        Dimension::Length,
+        "0.001",
+        "1",
+    ),
+    (
+        &["um", "µm", "micrometer", "micrometers"],
+        Dimension::Length,
+        "0.000001",
+        "1",
+    ),
+    (
+        &["nm", "nanometer", "nanometers"],
+        Dimension::Length,
+        "0.000000001",
+        "1",
+    ),
+    (&["mi", "mile", "miles"], Dimension::Length, "1609.344", "1"),
+    (&["yd", "yard", "yards"], Dimension::Length, "0.9144", "1"),
+    (&["ft", "foot", "feet"], Dimension::Length, "0.3048", "1"),
+    (&["in", "inch", "inches"], Dimension::Length, "0.0254", "1"),
+    (
+        &["nmi", "nautical mile", "nautical miles"],
+        Dimension::Length,
+        "1852",
+        "1",
+    ),
+    (&["kg", "kilogram", "kilograms"], Dimension::Mass, "1", "1"),
+    (&["g", "gram", "grams"], Dimension::Mass, "0.001", "1"),
+    (
+        &["mg", "milligram", "milligrams"],
+        Dimension::Mass,
+        "0.000001",
+        "1",
+    ),
+    (&["t", "tonne", "tonnes"], Dimension::Mass, "1000", "1"),
+    (
+        &["lb", "lbs", "pound", "pounds"],
+        Dimension::Mass,
+        "0.45359237",
+        "1",
+    ),
+    (
+        &["oz", "ounce", "ounces"],
+        Dimension::Mass,
+        "0.028349523125",
+        "1",
+    ),
+    (
+        &["st", "stone", "stones"],
+        Dimension::Mass,
+        "6.35029318",
+        "1",
+    ),
+    (
+        &["s", "sec", "second", "seconds"],
+        Dimension::Time,
+        "1",
+        "1",
+    ),
+    (
+        &["ms", "millisecond", "milliseconds"],
+        Dimension::Time,
+        "0.001",
+        "1",
+    ),
+    (&["min", "minute", "minutes"], Dimension::Time, "60", "1"),
+    (&["h", "hr", "hour", "hours"], Dimension::Time, "3600", "1"),
+    (&["d", "day", "days"], Dimension::Time, "86400", "1"),
+    (&["wk", "week", "weeks"], Dimension::Time, "604800", "1"),
+    (
+        &["l", "liter", "liters", "litre", "litres"],
+        Dimension::Volume,
+        "1",
+        "1",
+    ),
+    (
+        &[
+            "ml",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+        ],
+        Dimension::Volume,
+        "0.001",
+        "1",
+    ),
+    (
+        &["m3", "m³", "cubic meter", "cubic meters"],
+        Dimension::Volume,
+        "1000",
+        "1",
+    ),
+    (
+        &["gal", "gallon", "gallons"],
+        Dimension::Volume,
+        "3.785411784",
+        "1",
+    ),
+    (
+        &["qt", "quart", "quarts"],
+        Dimension::Volume,
+        "0.946352946",
+        "1",
+    ),
+    (
+        &["pt", "pint", "pints"],
+        Dimension::Volume,
+        "0.473176473",
+        "1",
+    ),
+    (&["cup", "cups"], Dimension::Volume, "0.2365882365", "1"),
+    (
+        &["floz", "fl oz", "fluid ounce", "fluid ounces"],
+        Dimension::Volume,
+        "0.0295735295625",
+        "1",
+    ),
+    (&["b", "byte", "bytes"], Dimension::Data, "1", "1"),
+    (&["bit", "bits"], Dimension::Data, "1", "8"),
+    (
+        &["kb", "kilobyte", "kilobytes"],
+        Dimension::Data,
+        "1000",
+        "1",
+    ),
+    (
+        &["mb", "megabyte", "megabytes"],
+        Dimension::Data,
+        "1000000",
+        "1",
+    ),
+    (
+        &["gb", "gigabyte", "gigabytes"],
+        Dimension::Data,
+        "1000000000",
+        "1",
+    ),
+    (
+        &["tb", "terabyte", "terabytes"],
+        Dimension::Data,
+        "1000000000000",
+        "1",
+    ),
+    (
+        &["kib", "kibibyte", "kibibytes"],
+        Dimension::Data,
+        "1024",
+        "1",
+    ),
+    (
+        &["mib", "mebibyte", "mebibytes"],
+        Dimension::Data,
+        "1048576",
+        "1",
+    ),
+    (
+        &["gib", "gibibyte", "gibibytes"],
+        Dimension::Data,
+        "1073741824",
+        "1",
+    ),
+    (
+        &["tib", "tebibyte", "tebibytes"],
+        Dimension::Data,
+        "1099511627776",
+        "1",
+    ),
+    (&["m/s", "mps"], Dimension::Speed, "1", "1"),
+    (&["km/h", "kmh", "kph"], Dimension::Speed, "1000", "3600"),
+    (&["mph", "mi/h"], Dimension::Speed, "1609.344", "3600"),
+    (
+        &["kn", "kt", "knot", "knots"],
+        Dimension::Speed,
+        "1852",
+        "3600",
+    ),
+    (&["j", "joule", "joules"], Dimension::Energy, "1", "1"),
+    (
+        &["kj", "kilojoule", "kilojoules"],
+        Dimension::Energy,
+        "1000",
+        "1",
+    ),
+    (
+        &["cal", "calorie", "calories"],
+        Dimension::Energy,
+        "4.184",
+        "1",
+    ),
+    (
+        &["kcal", "kilocalorie", "kilocalories"],
+        Dimension::Energy,
+        "4184",
+        "1",
+    ),
+    (
+        &["wh", "watt hour", "watt hours"],
+        Dimension::Energy,
+        "3600",
+        "1",
+    ),
+    (
+        &["kwh", "kilowatt hour", "kilowatt hours"],
+        Dimension::Energy,
+        "3600000",
+        "1",
+    ),
+    (&["c", "°c", "celsius"], Dimension::Temperature, "", ""),
+    (&["f", "°f", "fahrenheit"], Dimension::Temperature, "", ""),
+    (&["k", "kelvin"], Dimension::Temperature, "", ""),
+];
+
+fn find_unit(
+    unit: &str,
+) -> Result<(&'static str, Dimension, &'static str, &'static str), CalculatorError> {
+    let lowercase = unit.trim().to_lowercase();
+    UNITS
+        .iter()
+        .find(|(names, ..)| names.contains(&lowercase.as_str()))
+        .map(|(names, dimension, numerator, denominator)| {
+            (names[0], *dimension, *numerator, *denominator)
+        })
+        .ok_or_else(|| CalculatorError::UnknownUnit(unit.trim().to_string()))
+}
+
+/// Split a quantity (e.g.: `3.5 km`) into its value and the longest known unit it ends with.
+fn split_unit(quantity: &str) -> Option<(&str, &str)> {
+    let lowercase = quantity.to_ascii_lowercase();
+    UNITS
+        .iter()
+        .flat_map(|(names, ..)| names.iter())
+        .filter(|name| {
+            lowercase.strip_suffix(**name).is_some_and(|value| {
+                !value.ends_with(|c: char| c.is_alphabetic() || c == '/' || c == '°')
+            })
+        })
+        .max_by_key(|name| name.len())
+        .map(|name| quantity.split_at(quantity.len() - name.len()))
+}
+
+/// Convert a value from a unit to another (e.g.: `km` to `mi`).
+pub fn convert(value: &Decimal, from: &str, to: &str) -> Result<Decimal, CalculatorError> {
+    let number = |s: &str| s.parse::<Decimal>();
+    let (from_name, from_dimension, from_numerator, from_denominator) = find_unit(from)?;
+    let (to_name, to_dimension, to_numerator, to_denominator) = find_unit(to)?;
+    if from_dimension != to_dimension {
+        return Err(CalculatorError::IncompatibleUnits(
+            from.trim().to_string(),
+            to.trim().to_string(),
+        ));
+    }
+
+    if from_dimension == Dimension::Temperature {
+        let (five, nine) = (Decimal::from(5), Decimal::from(9));
+        let (thirty_two, zero_celsius) = (Decimal::from(32), number("273.15")?);
+        let kelvin = match from_name {
+            "c" => value.add(&zero_celsius),
+            "f" => value
+                .sub(&thirty_two)
+                .mul(&five)
+                .div(&nine)?
+                .add(&zero_celsius),
+            _ => value.clone(),
+        };
+        return match to_name {
+            "c" => Ok(kelvin.sub(&zero_celsius)),
+            "f" => Ok(kelvin
+                .sub(&zero_celsius)
+                .mul(&nine)
+                .div(&five)?
+                .add(&thirty_two)),
+            _ => Ok(kelvin),
+        };
+    }
+
+    // value * from / to, with a single division
+    value
+        .mul(&number(from_numerator)?)
+        .mul(&number(to_denominator)?)
+        .div(&number(from_denominator)?.mul(&number(to_numerator)?))
+}
+
+#[derive(Deserialize)]
+pub struct CalculatorArgs {
+    pub expression: String,
+}
+
+/// A tool evaluating arithmetic expressions and unit conversions, see the
+/// [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct CalculatorTool {
+    places: usize,
+}
+
+impl Default for CalculatorTool {
+    fn default() -> Self {
+        Self { places: 20 }
+    }
+}
+
+impl CalculatorTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of decimal places of the results (defaults to 20).
+    pub fn places(mut self, places: usize) -> Self {
+        self.places = places;
+        self
+    }
+}
+
+impl Tool for CalculatorTool {
+    const NAME: &'static str = "calculator";
+
+    type Error = CalculatorError;
+    type Args = CalculatorArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Evaluate an arithmetic expression exactly, with +, -, *, /, % \
+                (remainder), ^ (integer powers) and parentheses, optionally followed by a unit \
+                conversion (e.g.: `(3 + 2) km to mi`, `100 F to C`, `2 GiB in MB`). Use it \
+                for all calculations."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "expression": {
+                        "type": "string",
+                        "description": "The expression to evaluate"
+                    }
+                },
+                "required": ["expression"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        Ok(evaluate(&args.expression)?.round(self.places).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn eval(expression: &str) -> String {
+        evaluate(expression).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(eval("1 + 2 * 3"), "7");
+        assert_eq!(eval("(1 + 2) * 3"), "9");
+        assert_eq!(eval("-2 ^ 2"), "-4");
+        assert_eq!(eval("2 ** -2"), "0.25");
+        assert_eq!(eval("2 ^ 3 ^ 2"), "512");
+        assert_eq!(eval("0.1 + 0.2 - 0.3"), "0");
+        assert_eq!(eval("7 % 3"), "1");
+        assert_eq!(eval("-7.5 % 2"), "-1.5");
+        assert_eq!(eval("1.5e3 / 4"), "375");
+        assert_eq!(eval("1 / 3"), format!("0.{}", "3".repeat(DIVISION_PLACES)));
+        assert_eq!(
+            eval("99999999999999999999 * 99999999999999999999"),
+            "9999999999999999999800000000000000000001"
+        );
+
+        assert_eq!(evaluate("1 / 0"), Err(CalculatorError::DivisionByZero));
+        assert_eq!(eval("2 ^ 10000").len(), 3011);
+        assert!(matches!(
+            evaluate("99999999 ^ 10000"),
+            Err(CalculatorError::ResultTooLarge(_))
+        ));
+        assert!(matches!(
+            evaluate("(2 ^ 1000) ^ 100"),
+            Err(CalculatorError::ResultTooLarge(_))
+        ));
+        assert!(matches!(
+            evaluate("1e10000 * 1e10000"),
+            Err(CalculatorError::ResultTooLarge(_))
+        ));
+        assert!(matches!(
+            evaluate("2 ^ 0.5"),
+            Err(CalculatorError::UnsupportedExponent(_))
+        ));
+        assert!(matches!(
+            evaluate("(1 + 2"),
+            Err(CalculatorError::ParseError(_))
+        ));
+        assert!(matches!(
+            evaluate(&format!("1{}", " / 3".repeat(1000))),
+            Err(CalculatorError::ResultTooLarge(_))
+        ));
+        assert!(matches!(
+            evaluate("İ to é"),
+            Err(CalculatorError::UnknownUnit(_))
+        ));
+        assert!(matches!(
+            evaluate("1 + x"),
+            Err(CalculatorError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(eval("1 mi to km"), "1.609344");
+        assert_eq!(eval("(3 + 2)km in m"), "5000");
+        assert_eq!(
+            eval("100 F to C")
+                .parse::<Decimal>()
+                .unwrap()
+                .round(10)
+                .to_string(),
+            "37.7777777778"
+        );
+        assert_eq!(eval("-40 c to f"), "-40");
+        assert_eq!(eval("0 K to C"), "-273.15");
+        assert_eq!(eval("2 GiB in MB"), "2147.483648");
+        assert_eq!(eval("36 km/h to m/s"), "10");
+        assert_eq!(eval("16 bits to bytes"), "2");
+
+        assert_eq!(
+            evaluate("1 kg to km"),
+            Err(CalculatorError::IncompatibleUnits("kg".into(), "km".into()))
+        );
+        assert_eq!(
+            evaluate("1 parsec to km"),
+            Err(CalculatorError::UnknownUnit("parsec".into()))
+        );
+    }
+
+    #[test]
+    fn test_round() {
+        let round = |s: &str, places| s.parse::<Decimal>().unwrap().round(places).to_string();
+        assert_eq!(round("1.2345", 2), "1.23");
+        assert_eq!(round("1.235", 2), "1.24");
+        assert_eq!(round("-1.235", 2), "-1.24");
+        assert_eq!(round("9.999", 2), "10");
+        assert_eq!(round("0.004", 2), "0");
+    }
+
+    /// Integers of any magnitude (small ones are rare with `any::<i64>()`)
+    fn int() -> impl Strategy<Value = i64> {
+        prop_oneof![any::<i64>(), -1_000_000i64..1_000_000, -10i64..10]
+    }
+
+    fn decimal(n: i64, scale: u32) -> Decimal {
+        Decimal::from(n)
+            .div(&Decimal::from(10i64.pow(scale)))
+            .unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn test_arithmetic_properties(
+            a in int(),
+            b in int(),
+            (m, s) in (int(), 0u32..8),
+            (n, t) in (int(), 0u32..8),
+        ) {
+            let (x, y) = (Decimal::from(a), Decimal::from(b));
+
+            // Integer arithmetic matches i128
+            prop_assert_eq!(x.add(&y).to_string(), (a as i128 + b as i128).to_string());
+            prop_assert_eq!(x.sub(&y).to_string(), (a as i128 - b as i128).to_string());
+            prop_assert_eq!(x.mul(&y).to_string(), (a as i128 * b as i128).to_string());
+            if b != 0 {
+                prop_assert_eq!(x.rem(&y).unwrap().to_string(), (a as i128 % b as i128).to_string());
+            }
+
+            // Decimals are parsed and printed without loss
+            let (p, q) = (decimal(m, s), decimal(n, t));
+            prop_assert_eq!(p.to_string().parse::<Decimal>().unwrap(), p.clone());
+
+            // Exact operations are consistent
+            prop_assert_eq!(p.add(&q).sub(&q), p.clone());
+            prop_assert_eq!(p.add(&q), q.add(&p));
+            prop_assert_eq!(p.mul(&q), q.mul(&p));
+            prop_assert_eq!(p.mul(&q).add(&p), p.mul(&q.add(&Decimal::from(1))));
+            if !q.is_zero() {
+                prop_assert_eq!(p.mul(&q).div(&q).unwrap(), p.clone());
+            }
+
+            // Conversions round trip
+            let converted = convert(&convert(&p, "km", "mi").unwrap(), "mi", "km").unwrap();
+            prop_assert_eq!(converted.round(30), p);
+        }
+
+        #[test]
+        fn test_evaluate_never_panics(expression in "\\PC*( (?i:to|in) \\PC*)?") {
+            let _ = evaluate(&expression);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! This module provides [CancellationToken], used to abort in-flight completion requests,
+//! streams, tool calls and pipelines (e.g.: when the HTTP request that triggered them is
+//! abandoned by the client).
+//!
+//! Cancelling a token drops the futures it guards, which aborts the underlying HTTP requests
+//! to the providers. Tokens are cheap to clone, and all the clones of a token are cancelled
+//! together. Child tokens are cancelled along with their parent, but not the other way around.
+//!
+//! The token is runtime agnostic: it does not spawn tasks or timers.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     cancellation::CancellationToken,
+//!     completion::{CompletionModel, Prompt},
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let model = openai.completion_model(openai::GPT_4O);
+//!
+//! let token = CancellationToken::new();
+//! // E.g.: cancel the token when the client disconnects
+//! let on_disconnect = token.clone();
+//!
+//! // Cancel a single completion request
+//! let response = model
+//!     .completion_request("Write a long story")
+//!     .cancellation_token(token.child_token())
+//!     .send()
+//!     .await?;
+//!
+//! // Cancel any future, e.g.: an agent prompt including its tool calls
+//! let agent = openai.agent(openai::GPT_4O).build();
+//! let answer = token.run_until_cancelled(agent.prompt("Hello")).await??;
+//!
+//! on_disconnect.cancel();
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use futures::future::{select, Either};
+
+/// The error returned by operations aborted by a [CancellationToken].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("Operation cancelled")]
+pub struct Cancelled;
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+    children: Mutex<Vec<Weak<Inner>>>,
+}
+
+impl Inner {
+    fn cancel(&self) {
+        if self.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        for waker in std::mem::take(&mut *lock(&self.wakers)) {
+            waker.wake();
+        }
+        for child in std::mem::take(&mut *lock(&self.children)) {
+            if let Some(child) = child.upgrade() {
+                child.cancel();
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A token signaling the cancellation of operations. See the [module documentation](self).
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that is cancelled when this token is cancelled. Cancelling the child
+    /// token does not cancel this token.
+    pub fn child_token(&self) -> Self {
+        let child = Self::new();
+
+        let mut children = lock(&self.inner.children);
+        if self.is_cancelled() {
+            child.cancel();
+        } else {
+            children.retain(|child| child.strong_count() > 0);
+            children.push(Arc::downgrade(&child.inner));
+        }
+        child
+    }
+
+    /// Cancel the token, its clones and its children.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// A future that completes when the token is cancelled.
+    pub fn cancelled(&self) -> WaitForCancellation {
+        WaitForCancellation {
+            token: self.clone(),
+        }
+    }
+
+    /// Run the future until it completes or the token is cancelled, in which case the future
+    /// is dropped and [Cancelled] is returned.
+    pub async fn run_until_cancelled<F: Future>(&self, future: F) -> Result<F::Output, Cancelled> {
+        if self.is_cancelled() {
+            return Err(Cancelled);
+        }
+
+        match select(std::pin::pin!(future), self.cancelled()).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(_) => Err(Cancelled),
+        }
+    }
+}
+
+/// Future returned by [CancellationToken::cancelled].
+pub struct WaitForCancellation {
+    token: CancellationToken,
+}
+
+impl Future for WaitForCancellation {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = lock(&self.token.inner.wakers);
+        // Check again with the lock held, since `cancel` takes the wakers after setting the flag
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        completion::{CompletionError, CompletionRequestBuilder},
+        streaming::StreamingChoice,
+        test_utils::MockModel,
+    };
+
+    /// Hangs before responding, and streams one chunk before hanging
+    fn mock() -> MockModel {
+        MockModel::text("Hi")
+            .delay(Duration::from_secs(3600))
+            .stream(|_| {
+                Box::pin(
+                    futures::stream::once(async { Ok(StreamingChoice::Message("Hi".into())) })
+                        .chain(futures::stream::pending()),
+                )
+            })
+    }
+
+    fn cancel_soon(token: &CancellationToken) {
+        let token = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            token.cancel();
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let child = token.child_token();
+        let grandchild = child.child_token();
+
+        child.cancel();
+        assert!(!token.is_cancelled());
+        assert!(grandchild.is_cancelled());
+        assert_eq!(
+            grandchild.run_until_cancelled(async { 1 }).await,
+            Err(Cancelled)
+        );
+
+        cancel_soon(&token);
+        let result = token
+            .run_until_cancelled(tokio::time::sleep(Duration::from_secs(60)))
+            .await;
+        assert_eq!(result, Err(Cancelled));
+        assert!(token.child_token().is_cancelled());
+
+        let token = CancellationToken::new();
+        assert_eq!(token.run_until_cancelled(async { 1 }).await, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_completion_request() {
+        let token = CancellationToken::new();
+        cancel_soon(&token);
+        let result = CompletionRequestBuilder::new(mock(), "Hello")
+            .cancellation_token(token)
+            .send()
+            .await;
+        assert!(matches!(result, Err(CompletionError::Cancelled(Cancelled))));
+
+        let token = CancellationToken::new();
+        let stream = CompletionRequestBuilder::new(mock(), "Hello")
+            .cancellation_token(token.clone())
+            .stream()
+            .await
+            .unwrap();
+        cancel_soon(&token);
+        let chunks = stream.collect::<Vec<_>>().await;
+        assert!(matches!(
+            chunks[..],
+            [Ok(_), Err(CompletionError::Cancelled(_))]
+        ));
+    }
+}
@@ -0,0 +1,371 @@
+//! Fault injection for resilience testing of retry and fallback logic.
+//!
+//! [Chaos] wraps a completion, streaming completion or embedding model and injects [Fault]s
+//! (latency, rate limiting, server errors, malformed responses and truncated streams) into its
+//! calls according to a [ChaosSchedule]. Clones of a [Chaos] model share the same schedule.
+//!
+//! This module is only available with the `chaos` feature, and is meant for development and
+//! testing only.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use rig::{
+//!     chaos::{Chaos, ChaosSchedule, Fault},
+//!     providers::openai,
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//!
+//! // The first call is rate limited, the second one is slow, and all the following calls
+//! // go through unchanged.
+//! let model = Chaos::new(
+//!     openai.completion_model(openai::GPT_4O),
+//!     ChaosSchedule::Sequence(vec![
+//!         Some(Fault::RateLimited),
+//!         Some(Fault::Latency(Duration::from_secs(5))),
+//!     ]),
+//! );
+//!
+//! // 10% of the calls fail with a server error or a malformed response
+//! let flaky_model = Chaos::new(
+//!     openai.completion_model(openai::GPT_4O),
+//!     ChaosSchedule::Random {
+//!         seed: 42,
+//!         rate: 0.1,
+//!         faults: vec![Fault::ServerError, Fault::MalformedJson],
+//!     },
+//! );
+//! # }
+//! ```
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::StreamExt;
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    http::RequestOptions,
+    streaming::{StreamingCompletionModel, StreamingResult},
+};
+
+/// A fault injected into a model call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fault {
+    /// Delay the call by the given duration. The call itself goes through.
+    Latency(Duration),
+    /// Fail the call as if the provider responded with `429 Too Many Requests`.
+    RateLimited,
+    /// Fail the call as if the provider responded with `500 Internal Server Error`.
+    ServerError,
+    /// Fail the call as if the provider responded with a body that is not valid JSON.
+    MalformedJson,
+    /// Cut the response stream with an error after the given number of chunks.
+    /// Non-streaming calls fail as with [Fault::MalformedJson] (i.e.: a truncated body).
+    TruncatedStream { after: usize },
+}
+
+/// Defines which fault, if any, is injected into each call.
+#[derive(Clone, Debug)]
+pub enum ChaosSchedule {
+    /// Inject the faults in order, one per call (`None` lets the call through), then let all the
+    /// following calls through.
+    Sequence(Vec<Option<Fault>>),
+    /// Same as [ChaosSchedule::Sequence], but starts over when the end of the sequence is reached.
+    Cycle(Vec<Option<Fault>>),
+    /// Inject a fault in each call with probability `rate`, picked uniformly from `faults`.
+    /// The faults injected are the same from one run to the other for a given seed.
+    Random {
+        seed: u64,
+        rate: f64,
+        faults: Vec<Fault>,
+    },
+}
+
+struct ChaosState {
+    calls: usize,
+    rng: u64,
+}
+
+impl ChaosState {
+    /// xorshift64* pseudo-random number in `[0, 1)`
+    fn next_random(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        (self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Wraps a model and injects faults into its calls according to a [ChaosSchedule].
+#[derive(Clone)]
+pub struct Chaos<M> {
+    pub model: M,
+    schedule: ChaosSchedule,
+    state: Arc<Mutex<ChaosState>>,
+}
+
+impl<M> Chaos<M> {
+    pub fn new(model: M, schedule: ChaosSchedule) -> Self {
+        let seed = match &schedule {
+            ChaosSchedule::Random { seed, .. } => *seed,
+            _ => 0,
+        };
+
+        Self {
+            model,
+            schedule,
+            state: Arc::new(Mutex::new(ChaosState {
+                calls: 0,
+                // xorshift requires a non-zero state
+                rng: seed ^ 0x9e37_79b9_7f4a_7c15,
+            })),
+        }
+    }
+
+    /// The number of calls made to the model so far.
+    pub fn calls(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).calls
+    }
+
+    /// Pick the fault to inject into the next call.
+    fn next_fault(&self) -> Option<Fault> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let call = state.calls;
+        state.calls += 1;
+
+        match &self.schedule {
+            ChaosSchedule::Sequence(faults) => faults.get(call).cloned().flatten(),
+            ChaosSchedule::Cycle(faults) if faults.is_empty() => None,
+            ChaosSchedule::Cycle(faults) => faults[call % faults.len()].clone(),
+            ChaosSchedule::Random { rate, faults, .. } => {
+                if faults.is_empty() || state.next_random() >= *rate {
+                    None
+                } else {
+                    let index = (state.next_random() * faults.len() as f64) as usize;
+                    faults.get(index).cloned()
+                }
+            }
+        }
+    }
+
+    /// Inject the fault, returning the error the call should fail with, if any.
+    /// Latency is injected here, truncated streams are injected by the caller.
+    async fn inject(fault: &Option<Fault>, streaming: bool) -> Option<InjectedError> {
+        match fault {
+            None => None,
+            Some(Fault::Latency(duration)) => {
+                tokio::time::sleep(*duration).await;
+                None
+            }
+            Some(Fault::RateLimited) => Some(InjectedError::Provider(
+                "429 Too Many Requests: rate limit exceeded (injected fault)".to_string(),
+            )),
+            Some(Fault::ServerError) => Some(InjectedError::Provider(
+                "500 Internal Server Error (injected fault)".to_string(),
+            )),
+            Some(Fault::TruncatedStream { .. }) if streaming => None,
+            Some(Fault::MalformedJson) | Some(Fault::TruncatedStream { .. }) => {
+                Some(InjectedError::Json(malformed_json_error()))
+            }
+        }
+    }
+}
+
+enum InjectedError {
+    Provider(String),
+    Json(serde_json::Error),
+}
+
+impl From<InjectedError> for CompletionError {
+    fn from(error: InjectedError) -> Self {
+        match error {
+            InjectedError::Provider(message) => CompletionError::ProviderError(message),
+            InjectedError::Json(error) => CompletionError::JsonError(error),
+        }
+    }
+}
+
+impl From<InjectedError> for EmbeddingError {
+    fn from(error: InjectedError) -> Self {
+        match error {
+            InjectedError::Provider(message) => EmbeddingError::ProviderError(message),
+            InjectedError::Json(error) => EmbeddingError::JsonError(error),
+        }
+    }
+}
+
+/// The error returned when parsing a truncated response body.
+fn malformed_json_error() -> serde_json::Error {
+    serde_json::from_str::<serde_json::Value>(r#"{"id": "chatcmpl-123", "choices": [{"#)
+        .expect_err("Truncated JSON should not parse")
+}
+
+impl<M: CompletionModel> CompletionModel for Chaos<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let fault = self.next_fault();
+        if let Some(error) = Self::inject(&fault, false).await {
+            return Err(error.into());
+        }
+
+        self.model.completion(request).await
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+impl<M: StreamingCompletionModel + Sync> StreamingCompletionModel for Chaos<M> {
+    async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
+        let fault = self.next_fault();
+        if let Some(error) = Self::inject(&fault, true).await {
+            return Err(error.into());
+        }
+
+        let stream = self.model.stream(request).await?;
+
+        match fault {
+            Some(Fault::TruncatedStream { after }) => Ok(Box::pin(stream.take(after).chain(
+                futures::stream::once(async {
+                    Err(CompletionError::ResponseError(
+                        "Stream ended unexpectedly (injected fault)".to_string(),
+                    ))
+                }),
+            ))),
+            _ => Ok(stream),
+        }
+    }
+}
+
+impl<M: EmbeddingModel> EmbeddingModel for Chaos<M> {
+    const MAX_DOCUMENTS: usize = M::MAX_DOCUMENTS;
+
+    fn ndims(&self) -> usize {
+        self.model.ndims()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(texts, RequestOptions::default())
+            .await
+    }
+
+    async fn embed_texts_with_options(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        let fault = self.next_fault();
+        if let Some(error) = Self::inject(&fault, false).await {
+            return Err(error.into());
+        }
+
+        self.model
+            .embed_texts_with_options(texts, request_options)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        streaming::{StreamingChoice, StreamingCompletionModel},
+        test_utils::MockModel,
+    };
+
+    /// Answers "Hi!", and streams 3 chunks
+    fn mock() -> MockModel {
+        MockModel::text("Hi!").stream(|_| {
+            Box::pin(futures::stream::iter(
+                ["a", "b", "c"].map(|chunk| Ok(StreamingChoice::Message(chunk.to_string()))),
+            ))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sequence_schedule() {
+        let model = Chaos::new(
+            mock(),
+            ChaosSchedule::Sequence(vec![
+                Some(Fault::RateLimited),
+                None,
+                Some(Fault::MalformedJson),
+                Some(Fault::Latency(Duration::from_millis(50))),
+            ]),
+        );
+        let send = || CompletionRequestBuilder::new(model.clone(), "Hello").send();
+
+        assert!(matches!(
+            send().await,
+            Err(CompletionError::ProviderError(message)) if message.starts_with("429")
+        ));
+        assert!(send().await.is_ok());
+        assert!(matches!(send().await, Err(CompletionError::JsonError(_))));
+
+        let start = Instant::now();
+        assert!(send().await.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        assert!(send().await.is_ok());
+        assert_eq!(model.calls(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_stream() {
+        let model = Chaos::new(
+            mock(),
+            ChaosSchedule::Cycle(vec![Some(Fault::TruncatedStream { after: 2 })]),
+        );
+
+        let chunks = model
+            .stream(CompletionRequestBuilder::new(model.clone(), "Hello").build())
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[..2].iter().all(|chunk| chunk.is_ok()));
+        assert!(matches!(chunks[2], Err(CompletionError::ResponseError(_))));
+    }
+
+    #[test]
+    fn test_random_schedule_is_reproducible() {
+        let schedule = ChaosSchedule::Random {
+            seed: 7,
+            rate: 0.3,
+            faults: vec![Fault::ServerError, Fault::RateLimited],
+        };
+        let faults =
+            |model: Chaos<MockModel>| (0..100).map(|_| model.next_fault()).collect::<Vec<_>>();
+
+        let first = faults(Chaos::new(mock(), schedule.clone()));
+        let second = faults(Chaos::new(mock(), schedule));
+
+        assert_eq!(first, second);
+        let injected = first.iter().filter(|fault| fault.is_some()).count();
+        assert!((15..45).contains(&injected), "{injected} faults injected");
+    }
+}
@@ -0,0 +1,321 @@
+//! This module provides high-level abstractions for classifying text into the variants of an enum
+//! using LLMs, with an optional confidence score.
+//!
+//! Like the [extractor](crate::extractor), the classifier asks the model to call a tool whose
+//! parameters are constrained by the JSON schema of the enum. Since providers do not all enforce
+//! the schema of tool parameters, the output of the model is normalized before being deserialized:
+//! labels are matched case insensitively, confidences given as strings or percentages are
+//! converted, and a plain text answer naming one of the variants is accepted.
+//!
+//! Note: The target enum must implement the `serde::Deserialize`, `serde::Serialize`,
+//! and `schemars::JsonSchema` traits, and its variants must be unit variants.
+//!
+//! # Example
+//! ```
+//! use rig::{classifier::ClassifierBuilder, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::extractor::ExtractionError> {
+//! let openai = openai::Client::from_env();
+//!
+//! /// The sentiment of a product review
+//! #[derive(Debug, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
+//! enum Sentiment {
+//!     Positive,
+//!     Negative,
+//!     /// The review is neither positive nor negative
+//!     Neutral,
+//! }
+//!
+//! let classifier = ClassifierBuilder::<Sentiment, _>::new(openai.completion_model(openai::GPT_4O))
+//!     .with_confidence()
+//!     .build();
+//!
+//! let classification = classifier.classify("The battery died after two days.").await?;
+//! println!("{:?} ({:?})", classification.label, classification.confidence);
+//! # Ok(())
+//! # }
+//! ```
+use std::marker::PhantomData;
+
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    agent::{Agent, AgentBuilder},
+    completion::{CompletionModel, Prompt, ToolDefinition},
+    extractor::ExtractionError,
+    output_parsers::{EnumParser, OutputParser},
+    tool::Tool,
+};
+
+/// The result of a classification.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct Classification<T> {
+    /// The category of the text
+    pub label: T,
+    /// How confident you are in the category, between 0 and 1
+    pub confidence: Option<f64>,
+}
+
+/// Classifier of text into the variants of `T`
+pub struct Classifier<M: CompletionModel, T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync> {
+    agent: Agent<M>,
+    variants: Vec<String>,
+    confidence: bool,
+    _t: PhantomData<T>,
+}
+
+impl<M: CompletionModel, T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync> Classifier<M, T> {
+    pub async fn classify(&self, text: &str) -> Result<Classification<T>, ExtractionError> {
+        let output = self.agent.prompt(text).await?;
+
+        if output.trim().is_empty() {
+            return Err(ExtractionError::NoData);
+        }
+
+        let mut classification = match serde_json::from_str::<Value>(&output) {
+            Ok(value @ Value::Object(_)) => self.normalize(value),
+            // The model answered in plain text instead of calling the tool
+            _ => match EnumParser::new(self.variants.clone()).parse(&output) {
+                Ok(label) => json!({ "label": label }),
+                Err(_) => json!({ "label": output }),
+            },
+        };
+
+        if !self.confidence {
+            classification["confidence"] = Value::Null;
+        }
+
+        Ok(serde_json::from_value(classification)?)
+    }
+
+    /// Fix the common deviations of the model output from the schema.
+    fn normalize(&self, mut output: Value) -> Value {
+        if let Some(Value::String(label)) = output.get("label") {
+            if let Some(variant) = self
+                .variants
+                .iter()
+                .find(|variant| variant.eq_ignore_ascii_case(label.trim()))
+            {
+                output["label"] = Value::String(variant.clone());
+            }
+        }
+
+        let confidence = match output.get("confidence") {
+            Some(Value::Number(number)) => number.as_f64(),
+            Some(Value::String(number)) => {
+                let number = number.trim();
+                match number.strip_suffix('%') {
+                    Some(percentage) => percentage.trim().parse::<f64>().ok().map(|p| p / 100.0),
+                    None => number.parse::<f64>().ok(),
+                }
+            }
+            _ => None,
+        };
+        output["confidence"] = match confidence {
+            // Confidence given as a percentage
+            Some(confidence) if confidence > 1.0 && confidence <= 100.0 => {
+                json!(confidence / 100.0)
+            }
+            Some(confidence) => json!(confidence.clamp(0.0, 1.0)),
+            None => Value::Null,
+        };
+
+        output
+    }
+}
+
+/// Builder for the Classifier
+pub struct ClassifierBuilder<
+    T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync + 'static,
+    M: CompletionModel,
+> {
+    agent_builder: AgentBuilder<M>,
+    confidence: bool,
+    _t: PhantomData<T>,
+}
+
+impl<T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync + 'static, M: CompletionModel>
+    ClassifierBuilder<T, M>
+{
+    pub fn new(model: M) -> Self {
+        Self {
+            agent_builder: AgentBuilder::new(model)
+                .preamble("\
+                    You are an AI assistant whose purpose is to classify the provided text into one of a fixed set of categories.\n\
+                    You will have access to a `classify` function whose `label` parameter defines the possible categories.\n\
+                    ALWAYS CALL THE `classify` function with the category that fits the provided text best.
+                ")
+                .tool(ClassifyTool::<T> { _t: PhantomData }),
+            confidence: false,
+            _t: PhantomData,
+        }
+    }
+
+    /// Also ask the model how confident it is in the classification.
+    pub fn with_confidence(mut self) -> Self {
+        self.agent_builder = self.agent_builder.append_preamble(
+            "\nAlso set the `confidence` parameter to how confident you are in the category, between 0 and 1.",
+        );
+        self.confidence = true;
+        self
+    }
+
+    /// Add additional preamble to the classifier
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.agent_builder = self.agent_builder.append_preamble(&format!(
+            "\n=============== ADDITIONAL INSTRUCTIONS ===============\n{preamble}"
+        ));
+        self
+    }
+
+    /// Add a context document to the classifier
+    pub fn context(mut self, doc: &str) -> Self {
+        self.agent_builder = self.agent_builder.context(doc);
+        self
+    }
+
+    /// Build the Classifier
+    pub fn build(self) -> Classifier<M, T> {
+        Classifier {
+            agent: self.agent_builder.build(),
+            variants: variant_names(&json!(schema_for!(T))),
+            confidence: self.confidence,
+            _t: PhantomData,
+        }
+    }
+}
+
+/// The names of the unit variants of an enum, from its JSON schema.
+fn variant_names(schema: &Value) -> Vec<String> {
+    let names = |schema: &Value| {
+        schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .chain(schema.get("const"))
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    match schema.get("oneOf").and_then(Value::as_array) {
+        Some(variants) => variants.iter().flat_map(names).collect(),
+        None => names(schema),
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct ClassifyTool<T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync> {
+    _t: PhantomData<T>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("ClassifyError")]
+struct ClassifyError;
+
+impl<T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync> Tool for ClassifyTool<T> {
+    const NAME: &'static str = "classify";
+    type Error = ClassifyError;
+    // The arguments are normalized by the classifier before being deserialized
+    type Args = Value;
+    type Output = Value;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Submit the category of the provided text.".to_string(),
+            parameters: json!(schema_for!(Classification<T>)),
+        }
+    }
+
+    async fn call(&self, data: Self::Args) -> Result<Self::Output, Self::Error> {
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        message::AssistantContent,
+        test_utils::{response, MockModel},
+    };
+
+    /// Responds to the prompt `args:<json>` with a call to the classify tool, and to other
+    /// prompts with the prompt itself
+    fn mock() -> MockModel {
+        MockModel::new(|request| {
+            let prompt = request.prompt.rag_text().unwrap();
+            let choice = match prompt.strip_prefix("args:") {
+                Some(args) => AssistantContent::tool_call(
+                    "call_1",
+                    "classify",
+                    serde_json::from_str(args).unwrap(),
+                ),
+                None => AssistantContent::text(prompt),
+            };
+            Ok(response(choice, ()))
+        })
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Sentiment {
+        Positive,
+        /// Negative
+        Negative,
+    }
+
+    #[test]
+    fn test_variant_names() {
+        assert_eq!(
+            variant_names(&json!(schema_for!(Sentiment))),
+            vec!["positive", "negative"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classify() {
+        let classifier = ClassifierBuilder::<Sentiment, _>::new(mock())
+            .with_confidence()
+            .build();
+
+        assert_eq!(
+            classifier
+                .classify(r#"args:{"label": "Positive", "confidence": "85%"}"#)
+                .await
+                .unwrap(),
+            Classification {
+                label: Sentiment::Positive,
+                confidence: Some(0.85)
+            }
+        );
+        assert_eq!(
+            classifier
+                .classify("The sentiment is NEGATIVE.")
+                .await
+                .unwrap(),
+            Classification {
+                label: Sentiment::Negative,
+                confidence: None
+            }
+        );
+        assert!(classifier
+            .classify("args:{\"label\": \"meh\"}")
+            .await
+            .is_err());
+
+        let classifier = ClassifierBuilder::<Sentiment, _>::new(mock()).build();
+        assert_eq!(
+            classifier
+                .classify(r#"args:{"label": "negative", "confidence": 0.9}"#)
+                .await
+                .unwrap()
+                .confidence,
+            None
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! This module provides [Coalesce], a completion model wrapper that coalesces identical
+//! concurrent completion requests into a single call to the model, whose result is shared by
+//! all the callers.
+//!
+//! This protects the provider (and the rate limits of the API key) against accidental thundering
+//! herds, e.g.: many requests of a web handler triggering the same completion at the same time.
+//! Two requests are identical if their canonicalized payloads (prompt, preamble, chat history,
+//! documents, tools, parameters and request options) are equal. Requests are only coalesced
+//! while a call is in flight: nothing is cached once the call completes.
+//!
+//! # Example
+//! ```rust
+//! use rig::{coalesce::Coalesce, completion::Prompt, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//! let model = Coalesce::new(openai.completion_model(openai::GPT_4O));
+//!
+//! let agent = rig::agent::AgentBuilder::new(model)
+//!     .preamble("You are the FAQ assistant of ACME Inc.")
+//!     .build();
+//!
+//! // A single call is made to OpenAI
+//! let (first, second) = futures::join!(
+//!     agent.prompt("How do I reset my password?"),
+//!     agent.prompt("How do I reset my password?"),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures::{
+    future::{BoxFuture, WeakShared},
+    FutureExt,
+};
+use serde_json::json;
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    test_mode,
+};
+
+/// The result of a completion call, shared by all the coalesced callers.
+type SharedResult<R> = Result<CompletionResponse<Arc<R>>, Arc<CompletionError>>;
+
+/// In-flight calls are referenced weakly: a call is dropped once all its callers are.
+type WeakCompletion<R> = WeakShared<BoxFuture<'static, SharedResult<R>>>;
+
+/// Number of completion calls made to the model, and of requests served by a call made for
+/// another request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CoalesceStats {
+    pub calls: u64,
+    pub coalesced: u64,
+}
+
+struct CoalesceState<R> {
+    in_flight: HashMap<String, WeakCompletion<R>>,
+    stats: CoalesceStats,
+}
+
+/// Wraps a completion model and coalesces identical concurrent requests into a single call.
+/// Clones of a [Coalesce] share the same in-flight calls.
+///
+/// The raw response of the model is shared by all the coalesced callers, hence wrapped in an
+/// [Arc]. Errors other than provider and response errors are shared as a
+/// [CompletionError::RequestError] wrapping a [CoalescedError].
+pub struct Coalesce<M: CompletionModel> {
+    pub model: M,
+    state: Arc<Mutex<CoalesceState<M::Response>>>,
+}
+
+impl<M: CompletionModel> Clone for Coalesce<M> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<M: CompletionModel> Coalesce<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            state: Arc::new(Mutex::new(CoalesceState {
+                in_flight: HashMap::new(),
+                stats: CoalesceStats::default(),
+            })),
+        }
+    }
+
+    /// The number of calls made and of requests coalesced so far.
+    pub fn stats(&self) -> CoalesceStats {
+        self.lock().stats
+    }
+
+    /// The number of distinct calls currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.lock()
+            .in_flight
+            .values()
+            .filter(|call| call.upgrade().is_some())
+            .count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CoalesceState<M::Response>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The canonicalized payload of a request, used as the key of the in-flight calls.
+fn request_key(request: &CompletionRequest) -> String {
+    test_mode::canonicalize(json!({
+        "prompt": request.prompt,
+        "preamble": request.preamble,
+        "chat_history": request.chat_history,
+        "documents": request.documents,
+        "tools": request.tools,
+        "temperature": request.temperature,
+        "max_tokens": request.max_tokens,
+        "n": request.n,
+        "additional_params": request.additional_params,
+        "headers": request.request_options.headers,
+        "query": request.request_options.query,
+    }))
+    .to_string()
+}
+
+/// An error shared by coalesced completion requests.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{0}")]
+pub struct CoalescedError(pub Arc<CompletionError>);
+
+fn shared_error(error: &Arc<CompletionError>) -> CompletionError {
+    match error.as_ref() {
+        CompletionError::ProviderError(message) => CompletionError::ProviderError(message.clone()),
+        CompletionError::ResponseError(message) => CompletionError::ResponseError(message.clone()),
+        CompletionError::ContentFiltered(reason) => {
+            CompletionError::ContentFiltered(reason.clone())
+        }
+        CompletionError::Refused { reason } => CompletionError::Refused {
+            reason: reason.clone(),
+        },
+        _ => CompletionError::RequestError(Box::new(CoalescedError(error.clone()))),
+    }
+}
+
+impl<M> CompletionModel for Coalesce<M>
+where
+    M: CompletionModel + 'static,
+{
+    type Response = Arc<M::Response>;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let key = request_key(&request);
+
+        let call = {
+            let mut state = self.lock();
+            match state.in_flight.get(&key).and_then(WeakShared::upgrade) {
+                Some(call) => {
+                    state.stats.coalesced += 1;
+                    call
+                }
+                None => {
+                    let model = self.model.clone();
+                    let call = async move {
+                        model
+                            .completion(request)
+                            .await
+                            .map(|response| CompletionResponse {
+                                choice: response.choice,
+                                alternatives: response.alternatives,
+                                annotations: response.annotations,
+                                finish_reason: response.finish_reason,
+                                raw_response: Arc::new(response.raw_response),
+                            })
+                            .map_err(Arc::new)
+                    }
+                    .boxed()
+                    .shared();
+
+                    state.stats.calls += 1;
+                    if let Some(weak) = call.downgrade() {
+                        state.in_flight.insert(key.clone(), weak);
+                    }
+                    call
+                }
+            }
+        };
+
+        let result = call.clone().await;
+
+        // The first caller to get the result ends the call, so that later requests are sent
+        // to the model again
+        {
+            let mut state = self.lock();
+            let ended = state
+                .in_flight
+                .get(&key)
+                .and_then(WeakShared::upgrade)
+                .is_none_or(|in_flight| in_flight.ptr_eq(&call));
+            if ended {
+                state.in_flight.remove(&key);
+            }
+        }
+
+        result.map_err(|e| shared_error(&e))
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        message::AssistantContent,
+        test_utils::{response, MockModel},
+    };
+
+    /// Answers after 50ms with the number of the call, rejecting the requests with a temperature
+    fn mock(calls: &Arc<AtomicUsize>) -> MockModel<usize> {
+        let calls = calls.clone();
+        MockModel::new(move |request| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            if request.temperature.is_some() {
+                return Err(CompletionError::ProviderError("Bad request".to_string()));
+            }
+            Ok(response(AssistantContent::text("Hi!"), call))
+        })
+        .delay(Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    async fn test_coalesce() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let model = Coalesce::new(mock(&calls));
+        let send = |prompt: &str| CompletionRequestBuilder::new(model.clone(), prompt).send();
+
+        let (first, second, third, other) =
+            tokio::join!(send("Hello"), send("Hello"), send("Hello"), send("Bye"));
+        let (first, second, third) = (first.unwrap(), second.unwrap(), third.unwrap());
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            model.stats(),
+            CoalesceStats {
+                calls: 2,
+                coalesced: 2
+            }
+        );
+        assert!(Arc::ptr_eq(&first.raw_response, &second.raw_response));
+        assert!(Arc::ptr_eq(&first.raw_response, &third.raw_response));
+        assert_ne!(first.raw_response, other.unwrap().raw_response);
+        assert_eq!(model.in_flight(), 0);
+
+        // Completed calls are not cached
+        send("Hello").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Errors are shared too
+        let error = |prompt: &str| {
+            CompletionRequestBuilder::new(model.clone(), prompt)
+                .temperature(0.5)
+                .send()
+        };
+        let (first, second) = tokio::join!(error("Hello"), error("Hello"));
+        assert!(matches!(first, Err(CompletionError::ProviderError(_))));
+        assert!(matches!(second, Err(CompletionError::ProviderError(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_n() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let model = Coalesce::new(mock(&calls));
+        let send = |n: Option<u64>| {
+            let builder = CompletionRequestBuilder::new(model.clone(), "Hello");
+            match n {
+                Some(n) => builder.n(n).send(),
+                None => builder.send(),
+            }
+        };
+
+        // Requests asking for a different number of choices are not coalesced
+        let (one, three) = tokio::join!(send(None), send(Some(3)));
+        assert_ne!(one.unwrap().raw_response, three.unwrap().raw_response);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            model.stats(),
+            CoalesceStats {
+                calls: 2,
+                coalesced: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_request_key() {
+        let request = |params: serde_json::Value| CompletionRequest {
+            prompt: "Hello".into(),
+            preamble: None,
+            chat_history: vec![],
+            documents: vec![],
+            tools: vec![],
+            temperature: None,
+            max_tokens: None,
+            n: None,
+            additional_params: Some(params),
+            request_options: Default::default(),
+        };
+
+        assert_eq!(
+            request_key(&request(json!({"a": 1, "b": 2}))),
+            request_key(&request(json!({"b": 2, "a": 1})))
+        );
+        assert_ne!(
+            request_key(&request(json!({"a": 1}))),
+            request_key(&request(json!({"a": 2})))
+        );
+    }
+}
@@ -24,11 +24,21 @@ use super::CompletionError;
 #[serde(tag = "role", rename_all = "lowercase")]
 pub enum Message {
     /// User message containing one or more content types defined by `UserContent`.
-    User { content: OneOrMany<UserContent> },
+    User {
+        content: OneOrMany<UserContent>,
+        /// Name of the participant, e.g.: to tell apart the users of a multi-user conversation
+        /// (for the providers supporting it)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
 
     /// Assistant message containing one or more content types defined by `AssistantContent`.
     Assistant {
         content: OneOrMany<AssistantContent>,
+        /// Name of the participant, e.g.: to tell apart the agents of a multi-agent conversation
+        /// (for the providers supporting it)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
     },
 }
 
@@ -204,7 +214,7 @@ impl Message {
     /// Since `Message` might have more than just text content, we need to find the first text.
     pub(crate) fn rag_text(&self) -> Option<String> {
         match self {
-            Message::User { content } => {
+            Message::User { content, .. } => {
                 for item in content.iter() {
                     if let UserContent::Text(Text { text }) = item {
                         return Some(text.clone());
@@ -220,6 +230,7 @@ impl Message {
     pub fn user(text: impl Into<String>) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::text(text)),
+            name: None,
         }
     }
 
@@ -227,6 +238,16 @@ impl Message {
     pub fn assistant(text: impl Into<String>) -> Self {
         Message::Assistant {
             content: OneOrMany::one(AssistantContent::text(text)),
+            name: None,
+        }
+    }
+
+    /// Set the name of the participant sending the message.
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        let name = Some(name.into());
+        match self {
+            Message::User { content, .. } => Message::User { content, name },
+            Message::Assistant { content, .. } => Message::Assistant { content, name },
         }
     }
 }
@@ -485,6 +506,7 @@ impl From<String> for Message {
     fn from(text: String) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::Text(text.into())),
+            name: None,
         }
     }
 }
@@ -493,6 +515,7 @@ impl From<&str> for Message {
     fn from(text: &str) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::Text(text.into())),
+            name: None,
         }
     }
 }
@@ -501,6 +524,7 @@ impl From<Text> for Message {
     fn from(text: Text) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::Text(text)),
+            name: None,
         }
     }
 }
@@ -509,6 +533,7 @@ impl From<Image> for Message {
     fn from(image: Image) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::Image(image)),
+            name: None,
         }
     }
 }
@@ -517,6 +542,7 @@ impl From<Audio> for Message {
     fn from(audio: Audio) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::Audio(audio)),
+            name: None,
         }
     }
 }
@@ -525,6 +551,7 @@ impl From<Document> for Message {
     fn from(document: Document) -> Self {
         Message::User {
             content: OneOrMany::one(UserContent::Document(document)),
+            name: None,
         }
     }
 }
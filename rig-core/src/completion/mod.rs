@@ -1,5 +1,6 @@
 pub mod message;
 pub mod request;
+pub mod roles;
 
 pub use message::{AssistantContent, Message, MessageError};
 pub use request::*;
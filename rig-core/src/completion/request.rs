@@ -62,21 +62,33 @@
 //!
 //! For more information on how to use the completion functionality, refer to the documentation of
 //! the individual traits, structs, and enums defined in this module.
-use std::collections::HashMap;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use futures::StreamExt;
+
 use crate::streaming::{StreamingCompletionModel, StreamingResult};
 use crate::OneOrMany;
 use crate::{
+    cancellation::{CancellationToken, Cancelled},
+    guided::GuidedDecoding,
+    http::RequestOptions,
     json_utils,
     message::{Message, UserContent},
+    metadata,
+    tokens::{self, Heuristic, TokenCounter},
     tool::ToolSetError,
+    validation::ValidationError,
 };
 
 use super::message::AssistantContent;
 
+/// A document sent as context with a completion request.
+#[deprecated(since = "0.10.0", note = "use `rig::metadata::Document` instead")]
+pub type Document = metadata::Document;
+
 // Errors
 #[derive(Debug, Error)]
 pub enum CompletionError {
@@ -99,6 +111,67 @@ pub enum CompletionError {
     /// Error returned by the completion model provider
     #[error("ProviderError: {0}")]
     ProviderError(String),
+
+    /// The prompt or the response was blocked by the content filters of the provider. The
+    /// message is the reason reported by the provider.
+    #[error("ContentFiltered: {0}")]
+    ContentFiltered(String),
+
+    /// The model refused to respond to the prompt (e.g.: because of its content policy). The
+    /// reason is the refusal message of the model, or the reason reported by the provider.
+    #[error("Refused: {reason}")]
+    Refused { reason: String },
+
+    /// The request was aborted by a cancellation token
+    #[error("{0}")]
+    Cancelled(#[from] Cancelled),
+
+    /// A streamed response was interrupted midway, after the `partial` text was received
+    /// (see [Resume](crate::resume::Resume))
+    #[error("Stream interrupted after {} characters: {error}", partial.chars().count())]
+    Interrupted {
+        partial: String,
+        error: Box<CompletionError>,
+    },
+}
+
+impl CompletionError {
+    /// Whether the model or the content filters of the provider refused the request, i.e. the
+    /// error is a [CompletionError::Refused] or a [CompletionError::ContentFiltered]. Sending the
+    /// same request again is pointless.
+    pub fn is_refusal(&self) -> bool {
+        matches!(
+            self,
+            CompletionError::Refused { .. } | CompletionError::ContentFiltered(_)
+        )
+    }
+
+    /// Whether the request was rejected because it does not fit in the context window of the
+    /// model. Providers return the body of error responses as the message of
+    /// [CompletionError::ProviderError], so these errors are recognized by their content.
+    pub fn is_context_length_exceeded(&self) -> bool {
+        match self {
+            CompletionError::HttpError(error) => {
+                error.status().is_some_and(|status| status.as_u16() == 413)
+            }
+            CompletionError::ProviderError(message) => {
+                let message = message.to_lowercase();
+                [
+                    "context_length_exceeded",
+                    "context length",
+                    "context window",
+                    "prompt is too long",
+                    "input is too long",
+                    "too many tokens",
+                    "request too large",
+                    "reduce the length",
+                ]
+                .iter()
+                .any(|pattern| message.contains(pattern))
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -108,36 +181,10 @@ pub enum PromptError {
 
     #[error("ToolCallError: {0}")]
     ToolError(#[from] ToolSetError),
-}
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Document {
-    pub id: String,
-    pub text: String,
-    #[serde(flatten)]
-    pub additional_props: HashMap<String, String>,
-}
-
-impl std::fmt::Display for Document {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            concat!("<file id: {}>\n", "{}\n", "</file>\n"),
-            self.id,
-            if self.additional_props.is_empty() {
-                self.text.clone()
-            } else {
-                let mut sorted_props = self.additional_props.iter().collect::<Vec<_>>();
-                sorted_props.sort_by(|a, b| a.0.cmp(b.0));
-                let metadata = sorted_props
-                    .iter()
-                    .map(|(k, v)| format!("{}: {:?}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                format!("<metadata {} />\n{}", metadata, self.text)
-            }
-        )
-    }
+    /// The response is still invalid after the maximum number of retries
+    #[error("ValidationError: {0}")]
+    ValidationError(#[from] ValidationError),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -147,6 +194,62 @@ pub struct ToolDefinition {
     pub parameters: serde_json::Value,
 }
 
+/// The price of a completion model, in USD per million tokens, along with the token counter
+/// used to estimate the number of tokens of requests (see [CompletionRequestBuilder::estimate_cost]).
+#[derive(Clone)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl ModelPricing {
+    /// Create a new pricing, counting tokens with the default [Heuristic].
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+            counter: Arc::new(Heuristic::default()),
+        }
+    }
+
+    /// Create a new pricing for a model, counting tokens with the best counter available for it
+    /// (see [tokens::counter_for_model]).
+    pub fn for_model(model: &str, input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+            counter: tokens::counter_for_model(model),
+        }
+    }
+
+    /// Set the token counter used to estimate the number of tokens of requests.
+    pub fn counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+}
+
+/// The estimated cost of a completion request, in USD, before it is sent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostEstimate {
+    /// Estimated number of tokens of the assembled prompt (preamble, chat history, documents,
+    /// prompt and tool definitions)
+    pub input_tokens: usize,
+    pub input_cost: f64,
+    /// Maximum number of output tokens, if the request sets `max_tokens`
+    pub max_output_tokens: Option<u64>,
+    /// Cost of the maximum number of output tokens, if the request sets `max_tokens`
+    pub max_output_cost: Option<f64>,
+}
+
+impl CostEstimate {
+    /// The maximum total cost of the request, if the request sets `max_tokens`.
+    pub fn max_total_cost(&self) -> Option<f64> {
+        self.max_output_cost.map(|cost| self.input_cost + cost)
+    }
+}
+
 // ================================================================
 // Implementations
 // ================================================================
@@ -205,15 +308,132 @@ pub trait Completion<M: CompletionModel> {
 
 /// General completion response struct that contains the high-level completion choice
 /// and the raw response. The completion choice contains one or more assistant content.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CompletionResponse<T> {
     /// The completion choice (represented by one or more assistant message content)
     /// returned by the completion model provider
     pub choice: OneOrMany<AssistantContent>,
+    /// The other choices returned by the model, when several were requested (see
+    /// [CompletionRequestBuilder::n]). Empty otherwise.
+    pub alternatives: Vec<OneOrMany<AssistantContent>>,
+    /// Citations of the sources used to generate the response (e.g.: web pages or files),
+    /// for providers that return them. Empty otherwise.
+    pub annotations: Vec<Annotation>,
+    /// Why the model stopped generating the response, if the provider reports it.
+    pub finish_reason: Option<FinishReason>,
     /// The raw response returned by the completion model provider
     pub raw_response: T,
 }
 
+impl<T> CompletionResponse<T> {
+    /// Create a response with a single choice, without annotations nor finish reason.
+    pub fn new(choice: OneOrMany<AssistantContent>, raw_response: T) -> Self {
+        Self {
+            choice,
+            alternatives: vec![],
+            annotations: vec![],
+            finish_reason: None,
+            raw_response,
+        }
+    }
+
+    /// All the choices returned by the model: the [choice](CompletionResponse::choice), then the
+    /// [alternatives](CompletionResponse::alternatives).
+    pub fn choices(&self) -> impl Iterator<Item = &OneOrMany<AssistantContent>> {
+        std::iter::once(&self.choice).chain(&self.alternatives)
+    }
+}
+
+/// Why the model stopped generating a [CompletionResponse], normalized across providers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The model reached a natural stop point or a stop sequence
+    Stop,
+    /// The response was cut by the output token limit (see the
+    /// [continuation](crate::continuation) module)
+    Length,
+    /// The model called tools
+    ToolCalls,
+    /// The response was cut or blocked by the content filters of the provider
+    ContentFilter,
+    /// Any other reason, as reported by the provider
+    Other(String),
+}
+
+/// Finish reasons of the OpenAI API, also used by the OpenAI compatible APIs.
+impl From<&str> for FinishReason {
+    fn from(reason: &str) -> Self {
+        match reason {
+            "stop" => FinishReason::Stop,
+            "length" => FinishReason::Length,
+            "tool_calls" | "function_call" => FinishReason::ToolCalls,
+            "content_filter" => FinishReason::ContentFilter,
+            other => FinishReason::Other(other.to_string()),
+        }
+    }
+}
+
+/// A citation of a source attached to the text of a [CompletionResponse].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Start index (in characters) of the cited span of the response text, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_index: Option<usize>,
+    /// End index (in characters, exclusive) of the cited span of the response text, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_index: Option<usize>,
+    /// The cited source.
+    pub source: AnnotationSource,
+    /// Confidence (between 0 and 1) of the provider in the support of the span by the source,
+    /// for providers that return it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+/// The source cited by an [Annotation].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnotationSource {
+    /// A web page.
+    Url { url: String, title: Option<String> },
+    /// A file stored by the provider (e.g.: in an OpenAI vector store).
+    File {
+        file_id: String,
+        filename: Option<String>,
+    },
+    /// A document given to the provider along with the request.
+    Document { id: String },
+}
+
+impl Annotation {
+    /// Annotation citing a web page, without span.
+    pub fn url(url: impl Into<String>, title: Option<String>) -> Self {
+        Self {
+            start_index: None,
+            end_index: None,
+            source: AnnotationSource::Url {
+                url: url.into(),
+                title,
+            },
+            confidence: None,
+        }
+    }
+
+    /// Set the cited span of the response text.
+    pub fn span(mut self, start_index: usize, end_index: usize) -> Self {
+        self.start_index = Some(start_index);
+        self.end_index = Some(end_index);
+        self
+    }
+
+    /// Set the confidence of the annotation.
+    pub fn confidence(mut self, confidence: f64) -> Self {
+        self.confidence = Some(confidence);
+        self
+    }
+}
+
 /// Trait defining a completion model that can be used to generate completion responses.
 /// This trait is meant to be implemented by the user to define a custom completion model,
 /// either from a third party provider (e.g.: OpenAI) or a local model.
@@ -228,6 +448,20 @@ pub trait CompletionModel: Clone + Send + Sync {
     ) -> impl std::future::Future<Output = Result<CompletionResponse<Self::Response>, CompletionError>>
            + Send;
 
+    /// Returns the exact JSON payload that [CompletionModel::completion] would send to the
+    /// provider for the given completion request, without sending it (i.e.: a dry run).
+    ///
+    /// Models that do not send JSON requests return a [CompletionError::RequestError].
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        let _ = request;
+        Err(CompletionError::RequestError(
+            "This completion model does not support dry runs".into(),
+        ))
+    }
+
     /// Generates a completion request builder for the given `prompt`.
     fn completion_request(&self, prompt: impl Into<Message>) -> CompletionRequestBuilder<Self> {
         CompletionRequestBuilder::new(self.clone(), prompt)
@@ -235,6 +469,7 @@ pub trait CompletionModel: Clone + Send + Sync {
 }
 
 /// Struct representing a general completion request that can be sent to a completion model provider.
+#[derive(Clone, Debug)]
 pub struct CompletionRequest {
     /// The prompt to be sent to the completion model provider
     pub prompt: Message,
@@ -243,21 +478,30 @@ pub struct CompletionRequest {
     /// The chat history to be sent to the completion model provider
     pub chat_history: Vec<Message>,
     /// The documents to be sent to the completion model provider
-    pub documents: Vec<Document>,
+    pub documents: Vec<metadata::Document>,
     /// The tools to be sent to the completion model provider
     pub tools: Vec<ToolDefinition>,
     /// The temperature to be sent to the completion model provider
     pub temperature: Option<f64>,
     /// The max tokens to be sent to the completion model provider
     pub max_tokens: Option<u64>,
+    /// The number of choices to generate (see [CompletionResponse::alternatives]), for the
+    /// providers supporting it (OpenAI, Azure OpenAI, xAI, Together and Gemini). Ignored by the
+    /// other providers.
+    pub n: Option<u64>,
     /// Additional provider-specific parameters to be sent to the completion model provider
     pub additional_params: Option<serde_json::Value>,
+    /// Extra HTTP headers and query parameters to be sent to the completion model provider
+    pub request_options: RequestOptions,
 }
 
 impl CompletionRequest {
     pub fn prompt_with_context(&self) -> Message {
         let mut new_prompt = self.prompt.clone();
-        if let Message::User { ref mut content } = new_prompt {
+        if let Message::User {
+            ref mut content, ..
+        } = new_prompt
+        {
             if !self.documents.is_empty() {
                 let attachments = self
                     .documents
@@ -266,13 +510,47 @@ impl CompletionRequest {
                     .collect::<Vec<_>>()
                     .join("");
                 let formatted_content = format!("<attachments>\n{}</attachments>", attachments);
-                let mut new_content = vec![UserContent::text(formatted_content)];
-                new_content.extend(content.clone());
-                *content = OneOrMany::many(new_content).expect("This has more than 1 item");
+                *content =
+                    OneOrMany::one(UserContent::text(formatted_content)).concat(content.clone());
             }
         }
         new_prompt
     }
+
+    /// Estimate the cost of the request with the given pricing, before sending it. The number
+    /// of input tokens is approximate, since providers add their own formatting to the prompt.
+    pub fn estimate_cost(&self, pricing: &ModelPricing) -> CostEstimate {
+        let counter = &pricing.counter;
+        let preamble = self
+            .preamble
+            .as_ref()
+            .map(|preamble| counter.count_message_tokens(&Message::user(preamble)))
+            .unwrap_or_default();
+        let tools = self
+            .tools
+            .iter()
+            .map(|tool| {
+                counter.count_tokens(&tool.name)
+                    + counter.count_tokens(&tool.description)
+                    + counter.count_tokens(&tool.parameters.to_string())
+            })
+            .sum::<usize>();
+        let input_tokens = preamble
+            + counter.count_messages_tokens(&self.chat_history)
+            + counter.count_message_tokens(&self.prompt_with_context())
+            + tools;
+
+        let per_token = |per_million: f64| per_million / 1_000_000.0;
+        let choices = self.n.unwrap_or(1);
+        CostEstimate {
+            input_tokens,
+            input_cost: input_tokens as f64 * per_token(pricing.input_per_million),
+            max_output_tokens: self.max_tokens.map(|tokens| tokens * choices),
+            max_output_cost: self
+                .max_tokens
+                .map(|tokens| (tokens * choices) as f64 * per_token(pricing.output_per_million)),
+        }
+    }
 }
 
 /// Builder struct for constructing a completion request.
@@ -324,11 +602,14 @@ pub struct CompletionRequestBuilder<M: CompletionModel> {
     prompt: Message,
     preamble: Option<String>,
     chat_history: Vec<Message>,
-    documents: Vec<Document>,
+    documents: Vec<metadata::Document>,
     tools: Vec<ToolDefinition>,
     temperature: Option<f64>,
     max_tokens: Option<u64>,
+    n: Option<u64>,
     additional_params: Option<serde_json::Value>,
+    request_options: RequestOptions,
+    cancellation_token: Option<CancellationToken>,
 }
 
 impl<M: CompletionModel> CompletionRequestBuilder<M> {
@@ -342,7 +623,10 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
             tools: Vec::new(),
             temperature: None,
             max_tokens: None,
+            n: None,
             additional_params: None,
+            request_options: RequestOptions::default(),
+            cancellation_token: None,
         }
     }
 
@@ -366,13 +650,13 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
     }
 
     /// Adds a document to the completion request.
-    pub fn document(mut self, document: Document) -> Self {
+    pub fn document(mut self, document: metadata::Document) -> Self {
         self.documents.push(document);
         self
     }
 
     /// Adds a list of documents to the completion request.
-    pub fn documents(self, documents: Vec<Document>) -> Self {
+    pub fn documents(self, documents: Vec<metadata::Document>) -> Self {
         documents
             .into_iter()
             .fold(self, |builder, doc| builder.document(doc))
@@ -418,6 +702,11 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
         self
     }
 
+    /// Constrains the output of the model with guided decoding (see [crate::guided]).
+    pub fn guided_decoding(self, guided: &GuidedDecoding) -> Self {
+        self.additional_params(guided.params())
+    }
+
     /// Sets the temperature for the completion request.
     pub fn temperature(mut self, temperature: f64) -> Self {
         self.temperature = Some(temperature);
@@ -430,6 +719,14 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
         self
     }
 
+    /// Sets the number of choices to generate, e.g.: to select the best of them (see
+    /// [CompletionResponse::alternatives]). Only supported by some providers, see
+    /// [CompletionRequest::n].
+    pub fn n(mut self, n: u64) -> Self {
+        self.n = Some(n);
+        self
+    }
+
     /// Sets the max tokens for the completion request.
     /// Note: This is required if using Anthropic
     pub fn max_tokens(mut self, max_tokens: u64) -> Self {
@@ -444,6 +741,31 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
         self
     }
 
+    /// Adds an HTTP header to the completion request (e.g.: an idempotency key), in addition to
+    /// the headers of the client. See [RequestOptions].
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.request_options = self.request_options.header(name, value);
+        self
+    }
+
+    /// Adds a query parameter to the URL of the completion request. See [RequestOptions].
+    pub fn query_param(mut self, name: &str, value: &str) -> Self {
+        self.request_options = self.request_options.query_param(name, value);
+        self
+    }
+
+    /// Sets the extra HTTP headers and query parameters of the completion request.
+    pub fn request_options(mut self, request_options: RequestOptions) -> Self {
+        self.request_options = request_options;
+        self
+    }
+
+    /// Sets a token that aborts the request (or the stream of its response) when cancelled.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
     /// Builds the completion request.
     pub fn build(self) -> CompletionRequest {
         CompletionRequest {
@@ -454,38 +776,91 @@ impl<M: CompletionModel> CompletionRequestBuilder<M> {
             tools: self.tools,
             temperature: self.temperature,
             max_tokens: self.max_tokens,
+            n: self.n,
             additional_params: self.additional_params,
+            request_options: self.request_options,
+        }
+    }
+
+    /// Estimate the cost of the request with the given pricing, before sending it, e.g.: to
+    /// check it against a budget. See [CompletionRequest::estimate_cost].
+    pub fn estimate_cost(&self, pricing: &ModelPricing) -> CostEstimate {
+        CompletionRequest {
+            prompt: self.prompt.clone(),
+            preamble: self.preamble.clone(),
+            chat_history: self.chat_history.clone(),
+            documents: self.documents.clone(),
+            tools: self.tools.clone(),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            n: self.n,
+            additional_params: None,
+            request_options: RequestOptions::default(),
         }
+        .estimate_cost(pricing)
+    }
+
+    /// The documents of the request (e.g.: to check the grounding of the response).
+    pub(crate) fn context_documents(&self) -> &[metadata::Document] {
+        &self.documents
     }
 
     /// Sends the completion request to the completion model provider and returns the completion response.
     pub async fn send(self) -> Result<CompletionResponse<M::Response>, CompletionError> {
         let model = self.model.clone();
-        model.completion(self.build()).await
+        let token = self.cancellation_token.clone();
+        let request = self.build();
+
+        match token {
+            Some(token) => token.run_until_cancelled(model.completion(request)).await?,
+            None => model.completion(request).await,
+        }
+    }
+
+    /// Returns the exact JSON payload that would be sent to the completion model provider
+    /// (after the conversion of the request and the merging of the additional parameters),
+    /// without sending it.
+    pub fn dry_run(self) -> Result<serde_json::Value, CompletionError> {
+        let model = self.model.clone();
+        model.request_body(self.build())
     }
 }
 
 impl<M: StreamingCompletionModel> CompletionRequestBuilder<M> {
-    /// Stream the completion request
+    /// Stream the completion request.
+    /// If a cancellation token is set, the stream ends with a [CompletionError::Cancelled]
+    /// error when the token is cancelled.
     pub async fn stream(self) -> Result<StreamingResult, CompletionError> {
         let model = self.model.clone();
-        model.stream(self.build()).await
+        let Some(token) = self.cancellation_token.clone() else {
+            return model.stream(self.build()).await;
+        };
+
+        let stream = token
+            .run_until_cancelled(model.stream(self.build()))
+            .await??;
+        Ok(Box::pin(
+            stream.take_until(token.cancelled()).chain(
+                futures::stream::once(async move { token.is_cancelled() })
+                    .filter(|cancelled| std::future::ready(*cancelled))
+                    .map(|_| Err(CompletionError::Cancelled(Cancelled))),
+            ),
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::OneOrMany;
+    use crate::{
+        metadata::{Document, DocumentMetadata},
+        OneOrMany,
+    };
 
     use super::*;
 
     #[test]
     fn test_document_display_without_metadata() {
-        let doc = Document {
-            id: "123".to_string(),
-            text: "This is a test document.".to_string(),
-            additional_props: HashMap::new(),
-        };
+        let doc = Document::new(DocumentMetadata::new("123"), "This is a test document.");
 
         let expected = "<file id: 123>\nThis is a test document.\n</file>\n";
         assert_eq!(format!("{}", doc), expected);
@@ -493,15 +868,12 @@ mod tests {
 
     #[test]
     fn test_document_display_with_metadata() {
-        let mut additional_props = HashMap::new();
-        additional_props.insert("author".to_string(), "John Doe".to_string());
-        additional_props.insert("length".to_string(), "42".to_string());
-
-        let doc = Document {
-            id: "123".to_string(),
-            text: "This is a test document.".to_string(),
-            additional_props,
-        };
+        let doc = Document::new(
+            DocumentMetadata::new("123")
+                .custom("author", "John Doe")
+                .custom("length", "42"),
+            "This is a test document.",
+        );
 
         let expected = concat!(
             "<file id: 123>\n",
@@ -513,18 +885,28 @@ mod tests {
     }
 
     #[test]
-    fn test_prompt_with_context_with_documents() {
-        let doc1 = Document {
-            id: "doc1".to_string(),
-            text: "Document 1 text.".to_string(),
-            additional_props: HashMap::new(),
-        };
+    fn test_document_display_with_structured_metadata() {
+        let doc = Document::new(
+            DocumentMetadata::new("123")
+                .title("Test")
+                .custom("author", "John Doe")
+                .custom("length", 42),
+            "This is a test document.",
+        );
 
-        let doc2 = Document {
-            id: "doc2".to_string(),
-            text: "Document 2 text.".to_string(),
-            additional_props: HashMap::new(),
-        };
+        let expected = concat!(
+            "<file id: 123>\n",
+            "<metadata author: \"John Doe\" length: \"42\" title: \"Test\" />\n",
+            "This is a test document.\n",
+            "</file>\n"
+        );
+        assert_eq!(format!("{}", doc), expected);
+    }
+
+    #[test]
+    fn test_prompt_with_context_with_documents() {
+        let doc1 = Document::new(DocumentMetadata::new("doc1"), "Document 1 text.");
+        let doc2 = Document::new(DocumentMetadata::new("doc2"), "Document 2 text.");
 
         let request = CompletionRequest {
             prompt: "What is the capital of France?".into(),
@@ -534,7 +916,9 @@ mod tests {
             tools: Vec::new(),
             temperature: None,
             max_tokens: None,
+            n: None,
             additional_params: None,
+            request_options: Default::default(),
         };
 
         let expected = Message::User {
@@ -548,10 +932,52 @@ mod tests {
                 UserContent::text("What is the capital of France?"),
             ])
             .expect("This has more than 1 item"),
+            name: None,
         };
 
         request.prompt_with_context();
 
         assert_eq!(request.prompt_with_context(), expected);
     }
+
+    #[test]
+    fn test_estimate_cost() {
+        let request = CompletionRequest {
+            prompt: "Hello world!".into(),
+            preamble: Some("You are a bot".to_string()),
+            chat_history: Vec::new(),
+            documents: Vec::new(),
+            tools: vec![ToolDefinition {
+                name: "add".to_string(),
+                description: "Add x and y".to_string(),
+                parameters: serde_json::json!({}),
+            }],
+            temperature: None,
+            max_tokens: Some(100),
+            n: None,
+            additional_params: None,
+            request_options: Default::default(),
+        };
+
+        // (4 + 4) preamble + (3 + 4) prompt + (1 + 3 + 1) tool tokens
+        let estimate = request.estimate_cost(&ModelPricing::new(1_000_000.0, 2_000_000.0));
+        assert_eq!(
+            estimate,
+            CostEstimate {
+                input_tokens: 20,
+                input_cost: 20.0,
+                max_output_tokens: Some(100),
+                max_output_cost: Some(200.0),
+            }
+        );
+        assert_eq!(estimate.max_total_cost(), Some(220.0));
+
+        let request = CompletionRequest {
+            max_tokens: None,
+            n: None,
+            ..request
+        };
+        let estimate = request.estimate_cost(&ModelPricing::new(1.0, 1.0));
+        assert_eq!(estimate.max_total_cost(), None);
+    }
 }
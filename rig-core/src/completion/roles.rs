@@ -0,0 +1,279 @@
+//! This module provides the normalization of the roles of chat histories, for providers that
+//! reject some sequences of messages (e.g.: system messages, consecutive messages with the same
+//! role, or histories starting with an assistant message).
+//!
+//! Providers with such requirements normalize their requests with their own [RoleMapping]. The
+//! [Normalized] wrapper applies a custom mapping to any completion model, e.g.: an
+//! OpenAI-compatible server whose chat template requires alternating roles.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::roles::{Normalized, RoleMapping},
+//!     providers::openai,
+//! };
+//!
+//! # fn run() {
+//! let client = openai::Client::from_url("", "http://localhost:8000/v1");
+//! let model = Normalized::new(client.completion_model("mistral-7b-instruct"), RoleMapping::STRICT);
+//! # }
+//! ```
+use super::{
+    message::{Message, UserContent},
+    CompletionError, CompletionModel, CompletionRequest, CompletionResponse,
+};
+use crate::{
+    streaming::{StreamingCompletionModel, StreamingResult},
+    OneOrMany,
+};
+
+/// Text of the user message inserted before histories starting with an assistant message.
+const LEADING_USER_TEXT: &str = "(conversation continued)";
+
+/// The requirements of a provider on the roles of the messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoleMapping {
+    /// The provider does not support system messages: the preamble is sent as the first user
+    /// message.
+    pub system_as_user: bool,
+    /// The provider requires alternating roles: consecutive messages with the same role (and the
+    /// same name) are merged into one. Messages of different participants are never merged, so
+    /// that their names are kept.
+    pub merge_consecutive: bool,
+    /// The provider requires the first message (after the preamble) to be a user message: a
+    /// placeholder user message is inserted before leading assistant messages.
+    pub leading_user: bool,
+}
+
+impl RoleMapping {
+    /// No normalization.
+    pub const NONE: Self = Self {
+        system_as_user: false,
+        merge_consecutive: false,
+        leading_user: false,
+    };
+
+    /// Alternating user and assistant messages, starting with a user message, without system
+    /// messages.
+    pub const STRICT: Self = Self {
+        system_as_user: true,
+        merge_consecutive: true,
+        leading_user: true,
+    };
+
+    pub fn system_as_user(mut self, system_as_user: bool) -> Self {
+        self.system_as_user = system_as_user;
+        self
+    }
+
+    pub fn merge_consecutive(mut self, merge_consecutive: bool) -> Self {
+        self.merge_consecutive = merge_consecutive;
+        self
+    }
+
+    pub fn leading_user(mut self, leading_user: bool) -> Self {
+        self.leading_user = leading_user;
+        self
+    }
+}
+
+impl CompletionRequest {
+    /// Normalize the preamble, chat history and prompt of the request with the given mapping.
+    /// If the prompt is merged with the last messages of the chat history, the merged message
+    /// becomes the prompt.
+    pub fn normalize_roles(&mut self, mapping: RoleMapping) {
+        if mapping == RoleMapping::NONE {
+            return;
+        }
+
+        let mut messages = std::mem::take(&mut self.chat_history);
+        messages.push(self.prompt.clone());
+
+        if mapping.system_as_user {
+            if let Some(preamble) = self.preamble.take() {
+                messages.insert(0, Message::user(preamble));
+            }
+        }
+
+        if mapping.leading_user && matches!(messages.first(), Some(Message::Assistant { .. })) {
+            messages.insert(0, Message::user(LEADING_USER_TEXT));
+        }
+
+        if mapping.merge_consecutive {
+            messages = messages
+                .into_iter()
+                .fold(Vec::<Message>::new(), |mut merged, message| {
+                    match (merged.last_mut(), message) {
+                        (
+                            Some(Message::User { content, name }),
+                            Message::User {
+                                content: next_content,
+                                name: next_name,
+                            },
+                        ) if *name == next_name => {
+                            next_content.into_iter().for_each(|item| content.push(item))
+                        }
+                        (
+                            Some(Message::Assistant { content, name }),
+                            Message::Assistant {
+                                content: next_content,
+                                name: next_name,
+                            },
+                        ) if *name == next_name => {
+                            next_content.into_iter().for_each(|item| content.push(item))
+                        }
+                        (_, message) => merged.push(message),
+                    }
+                    merged
+                });
+        }
+
+        // The prompt is the last message, since it was pushed last
+        self.prompt = messages.pop().unwrap_or_else(|| Message::User {
+            content: OneOrMany::one(UserContent::text("")),
+            name: None,
+        });
+        self.chat_history = messages;
+    }
+}
+
+/// A completion model wrapper normalizing the roles of the requests with a [RoleMapping].
+#[derive(Clone)]
+pub struct Normalized<M> {
+    pub model: M,
+    mapping: RoleMapping,
+}
+
+impl<M: CompletionModel> Normalized<M> {
+    pub fn new(model: M, mapping: RoleMapping) -> Self {
+        Self { model, mapping }
+    }
+}
+
+impl<M: CompletionModel> CompletionModel for Normalized<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        request.normalize_roles(self.mapping);
+        self.model.completion(request).await
+    }
+
+    fn request_body(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        request.normalize_roles(self.mapping);
+        self.model.request_body(request)
+    }
+}
+
+impl<M: StreamingCompletionModel + Sync> StreamingCompletionModel for Normalized<M> {
+    async fn stream(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<StreamingResult, CompletionError> {
+        request.normalize_roles(self.mapping);
+        self.model.stream(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completion::AssistantContent;
+
+    fn request(
+        preamble: Option<&str>,
+        chat_history: Vec<Message>,
+        prompt: &str,
+    ) -> CompletionRequest {
+        CompletionRequest {
+            prompt: Message::user(prompt),
+            preamble: preamble.map(String::from),
+            chat_history,
+            documents: vec![],
+            tools: vec![],
+            temperature: None,
+            max_tokens: None,
+            n: None,
+            additional_params: None,
+            request_options: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_roles() {
+        let history = vec![
+            Message::assistant("Hi!"),
+            Message::assistant("How can I help?"),
+            Message::user("Hello"),
+        ];
+
+        let mut strict = request(Some("Be nice"), history.clone(), "Who are you?");
+        strict.normalize_roles(RoleMapping::STRICT);
+        assert_eq!(strict.preamble, None);
+        assert_eq!(
+            strict.chat_history,
+            vec![
+                Message::user("Be nice"),
+                Message::Assistant {
+                    content: OneOrMany::many(vec![
+                        AssistantContent::text("Hi!"),
+                        AssistantContent::text("How can I help?"),
+                    ])
+                    .unwrap(),
+                    name: None
+                },
+            ]
+        );
+        assert_eq!(
+            strict.prompt,
+            Message::User {
+                content: OneOrMany::many(vec![
+                    UserContent::text("Hello"),
+                    UserContent::text("Who are you?"),
+                ])
+                .unwrap(),
+                name: None
+            }
+        );
+
+        let mut leading = request(Some("Be nice"), history.clone(), "Who are you?");
+        leading.normalize_roles(RoleMapping::NONE.leading_user(true));
+        assert_eq!(leading.preamble.as_deref(), Some("Be nice"));
+        assert_eq!(leading.chat_history.len(), 4);
+        assert_eq!(leading.chat_history[0], Message::user(LEADING_USER_TEXT));
+        assert_eq!(leading.prompt, Message::user("Who are you?"));
+
+        // Messages of different participants are not merged
+        let named = vec![
+            Message::user("Hi!").with_name("alice"),
+            Message::user("Hello!").with_name("bob"),
+            Message::user("How are you?").with_name("bob"),
+        ];
+        let mut merged = request(None, named, "Who are you?");
+        merged.normalize_roles(RoleMapping::NONE.merge_consecutive(true));
+        assert_eq!(
+            merged.chat_history,
+            vec![
+                Message::user("Hi!").with_name("alice"),
+                Message::User {
+                    content: OneOrMany::many(vec![
+                        UserContent::text("Hello!"),
+                        UserContent::text("How are you?"),
+                    ])
+                    .unwrap(),
+                    name: Some("bob".to_string())
+                },
+            ]
+        );
+        assert_eq!(merged.prompt, Message::user("Who are you?"));
+
+        let mut none = request(None, history.clone(), "Who are you?");
+        none.normalize_roles(RoleMapping::NONE);
+        assert_eq!(none.chat_history, history);
+    }
+}
@@ -0,0 +1,276 @@
+//! Continuation of long outputs cut by the output token limit of a model.
+//!
+//! [Continuation] wraps a completion model. When a response is truncated (i.e. its
+//! [FinishReason] is [Length](FinishReason::Length)), the model is asked to continue it: the text generated so far is sent back
+//! as an assistant message, followed by a continuation instruction. The chunks are stitched
+//! together, dropping the text repeated by the model at the start of a chunk, until the response
+//! is complete or the maximum number of continuations is reached.
+//!
+//! With [Continuation::json], the chunks are stripped of their markdown code fences before being
+//! stitched, and the stitched text is repaired into JSON (as the outputs of
+//! [extractors](crate::extractor)), so that long structured outputs split across requests can be
+//! parsed.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, continuation::Continuation, providers::openai};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let model = Continuation::on_length(openai.completion_model(openai::GPT_4O)).max_continuations(8);
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).max_tokens(4_096).build();
+//! let report = agent.prompt("Write a detailed report on...").await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    completion::{
+        CompletionError, CompletionModel, CompletionRequest, CompletionResponse, FinishReason,
+    },
+    json_utils,
+    message::{AssistantContent, Message},
+    OneOrMany,
+};
+
+/// Function telling whether the raw response of a model was cut by its output token limit.
+pub type TruncatedFn<R> = fn(&R) -> bool;
+
+/// Wraps a model and continues its truncated responses, see the
+/// [module documentation](self).
+pub struct Continuation<M: CompletionModel> {
+    pub model: M,
+    is_truncated: TruncatedFn<M::Response>,
+    max_continuations: usize,
+    instruction: String,
+    json: bool,
+}
+
+impl<M: CompletionModel> Clone for Continuation<M> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            is_truncated: self.is_truncated,
+            max_continuations: self.max_continuations,
+            instruction: self.instruction.clone(),
+            json: self.json,
+        }
+    }
+}
+
+impl<M: CompletionModel> Continuation<M> {
+    /// Wrap a model, continuing the responses with the [Length](FinishReason::Length) finish
+    /// reason.
+    pub fn on_length(model: M) -> Self {
+        Self::new(model, |_| false)
+    }
+
+    /// Wrap a model, continuing the responses with the [Length](FinishReason::Length) finish
+    /// reason, and the responses detected as truncated by `is_truncated` (e.g.: for providers
+    /// not reporting the finish reason).
+    pub fn new(model: M, is_truncated: TruncatedFn<M::Response>) -> Self {
+        Self {
+            model,
+            is_truncated,
+            max_continuations: 4,
+            instruction: "Your response was cut off. Continue it exactly from where it \
+                stopped, without repeating anything and without any introduction."
+                .to_string(),
+            json: false,
+        }
+    }
+
+    /// Set the maximum number of continuation requests sent for a response (defaults to 4).
+    pub fn max_continuations(mut self, max_continuations: usize) -> Self {
+        self.max_continuations = max_continuations;
+        self
+    }
+
+    /// Set the continuation instruction.
+    pub fn instruction(mut self, instruction: &str) -> Self {
+        self.instruction = instruction.to_string();
+        self
+    }
+
+    /// Stitch the chunks as a JSON output: strip their code fences, and repair the stitched text.
+    pub fn json(mut self) -> Self {
+        self.json = true;
+        self
+    }
+
+    /// The text of a chunk, stripped of its code fences in JSON mode.
+    fn chunk_text(&self, choice: &OneOrMany<AssistantContent>) -> String {
+        let text = choice
+            .iter()
+            .filter_map(|content| match content {
+                AssistantContent::Text(text) => Some(text.text.as_str()),
+                AssistantContent::ToolCall(_) => None,
+            })
+            .collect::<String>();
+
+        match self.json {
+            true => strip_code_fences(&text).to_string(),
+            false => text,
+        }
+    }
+}
+
+/// The text without its opening code fence (and language tag), and without its closing one.
+fn strip_code_fences(text: &str) -> &str {
+    let mut text = text.trim();
+    if let Some(rest) = text.strip_prefix("```") {
+        text = match rest.find('\n') {
+            Some(newline) if !rest[..newline].contains(['{', '[']) => &rest[newline + 1..],
+            _ => rest,
+        };
+    }
+    text.strip_suffix("```").unwrap_or(text)
+}
+
+const MIN_OVERLAP: usize = 4;
+
+/// Append `chunk` to `text`, dropping the start of the chunk repeating the end of the text (if
+/// at least [MIN_OVERLAP] characters long, shorter overlaps being likely coincidental).
+fn stitch(text: &mut String, chunk: &str) {
+    let overlap = chunk
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([chunk.len()])
+        .skip(MIN_OVERLAP)
+        .filter(|&i| text.ends_with(&chunk[..i]))
+        .max()
+        .unwrap_or(0);
+    text.push_str(&chunk[overlap..]);
+}
+
+impl<M: CompletionModel> CompletionModel for Continuation<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let mut response = self.model.completion(request.clone()).await?;
+        let is_truncated = |response: &CompletionResponse<M::Response>| {
+            response.finish_reason == Some(FinishReason::Length)
+                || (self.is_truncated)(&response.raw_response)
+        };
+        let has_tool_calls = |response: &CompletionResponse<M::Response>| {
+            response
+                .choice
+                .iter()
+                .any(|content| matches!(content, AssistantContent::ToolCall(_)))
+        };
+        if !is_truncated(&response) || has_tool_calls(&response) {
+            return Ok(response);
+        }
+
+        let mut text = self.chunk_text(&response.choice);
+        let mut annotations = std::mem::take(&mut response.annotations);
+        let mut continuations = 0;
+        while is_truncated(&response) && !has_tool_calls(&response) {
+            if continuations == self.max_continuations {
+                tracing::warn!(
+                    "The response is still truncated after {} continuations",
+                    continuations
+                );
+                break;
+            }
+            continuations += 1;
+
+            let mut continuation = request.clone();
+            continuation.chat_history.push(request.prompt.clone());
+            continuation.chat_history.push(Message::assistant(&text));
+            continuation.prompt = Message::user(&self.instruction);
+            response = self.model.completion(continuation).await?;
+
+            stitch(&mut text, &self.chunk_text(&response.choice));
+            annotations.append(&mut response.annotations);
+        }
+
+        if self.json {
+            text = json_utils::repair(&text);
+        }
+        Ok(CompletionResponse {
+            choice: OneOrMany::one(AssistantContent::text(text)),
+            annotations,
+            alternatives: vec![],
+            finish_reason: response.finish_reason,
+            raw_response: response.raw_response,
+        })
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        completion::Prompt,
+        test_utils::{response, MockModel},
+    };
+
+    /// Responds with the chunks in order, the raw response and the finish reason telling whether
+    /// the chunk is truncated
+    fn chunked(chunks: Vec<&'static str>) -> MockModel<bool> {
+        let chunks = Mutex::new(chunks);
+        MockModel::new(move |_| {
+            let mut chunks = chunks.lock().unwrap();
+            let chunk = chunks.remove(0);
+            let truncated = !chunks.is_empty();
+            Ok(CompletionResponse {
+                finish_reason: Some(match truncated {
+                    true => FinishReason::Length,
+                    false => FinishReason::Stop,
+                }),
+                ..response(AssistantContent::text(chunk), truncated)
+            })
+        })
+    }
+
+    fn agent(
+        model: Continuation<MockModel<bool>>,
+    ) -> crate::agent::Agent<Continuation<MockModel<bool>>> {
+        crate::agent::AgentBuilder::new(model).build()
+    }
+
+    #[tokio::test]
+    async fn test_stitch_text() {
+        let model = Continuation::new(
+            chunked(vec!["Once upon a ti", "a time there was", " a dragon."]),
+            |truncated| *truncated,
+        );
+        let response = agent(model).prompt("Tell me a story").await.unwrap();
+        assert_eq!(response, "Once upon a time there was a dragon.");
+
+        let model = Continuation::new(
+            chunked(vec!["Once upon", " a time", " there was"]),
+            |truncated| *truncated,
+        )
+        .max_continuations(1);
+        let response = agent(model).prompt("Tell me a story").await.unwrap();
+        assert_eq!(response, "Once upon a time");
+    }
+
+    #[tokio::test]
+    async fn test_stitch_json() {
+        let model = Continuation::on_length(chunked(vec![
+            "```json\n{\"items\": [\"a\", \"b\",",
+            "```json\n \"c\", 'd',]}\n```",
+        ]))
+        .json();
+        let response = agent(model).prompt("List the items").await.unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&response).unwrap(),
+            serde_json::json!({"items": ["a", "b", "c", "d"]})
+        );
+    }
+}
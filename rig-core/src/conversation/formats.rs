@@ -0,0 +1,557 @@
+//! Conversion of chat histories from and to standard formats, to feed the conversations captured
+//! with rig to fine-tuning jobs, or to render them in documentation:
+//! - the [OpenAI fine-tuning](https://platform.openai.com/docs/guides/fine-tuning) JSONL format
+//!   ([to_openai_jsonl], [from_openai_jsonl]), one `{"messages": [...]}` object per line,
+//! - the ShareGPT format ([Transcript::to_sharegpt], [Transcript::from_sharegpt]), a
+//!   `{"conversations": [{"from": "human", "value": "..."}, ...]}` object per conversation,
+//! - markdown transcripts ([Transcript::to_markdown], [Transcript::from_markdown]), with a
+//!   `## User` / `## Assistant` section per message.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     conversation::formats::{from_openai_jsonl, to_openai_jsonl},
+//!     message::Message,
+//! };
+//!
+//! let transcript = rig::conversation::Conversation {
+//!     history: vec![
+//!         Message::user("What is the capital of France?"),
+//!         Message::assistant("Paris."),
+//!     ],
+//!     ..Default::default()
+//! }
+//! .transcript()
+//! .with_system("You are a geography teacher.");
+//!
+//! let jsonl = to_openai_jsonl(&[transcript.clone()]).unwrap();
+//! assert_eq!(from_openai_jsonl(&jsonl).unwrap(), vec![transcript.clone()]);
+//!
+//! println!("{}", transcript.to_markdown());
+//! ```
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::{
+    message::{
+        AssistantContent, Message, MessageError, Text, ToolCall, ToolFunction, ToolResultContent,
+        UserContent,
+    },
+    providers::openai,
+    OneOrMany,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    /// Json error (e.g.: serialization, deserialization)
+    #[error("JsonError: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    /// The message cannot be represented in the format
+    #[error("MessageError: {0}")]
+    MessageError(#[from] MessageError),
+
+    /// The content is not valid for the format
+    #[error("InvalidFormat: {0}")]
+    InvalidFormat(String),
+
+    /// Error on a line of a JSONL document (lines are numbered from 1)
+    #[error("Line {line}: {error}")]
+    Line {
+        line: usize,
+        error: Box<FormatError>,
+    },
+}
+
+/// A chat history with its (optional) system prompt.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transcript {
+    pub system: Option<String>,
+    pub messages: Vec<Message>,
+}
+
+impl Transcript {
+    pub fn new(messages: Vec<Message>) -> Self {
+        Self {
+            system: None,
+            messages,
+        }
+    }
+
+    pub fn with_system(mut self, system: &str) -> Self {
+        self.system = Some(system.to_string());
+        self
+    }
+
+    /// The transcript as an OpenAI fine-tuning example: `{"messages": [...]}`.
+    pub fn to_openai(&self) -> Result<Value, FormatError> {
+        let mut messages = vec![];
+
+        if let Some(system) = &self.system {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        for message in &self.messages {
+            for message in Vec::<openai::Message>::try_from(message.clone())? {
+                messages.push(simplify_openai_message(serde_json::to_value(message)?));
+            }
+        }
+
+        Ok(json!({ "messages": messages }))
+    }
+
+    /// Parse an OpenAI fine-tuning example (or any `{"messages": [...]}` object in the OpenAI
+    /// chat format). The leading system messages are joined into the system prompt.
+    pub fn from_openai(value: Value) -> Result<Self, FormatError> {
+        let messages = match value {
+            Value::Object(mut object) => object.remove("messages"),
+            _ => None,
+        }
+        .ok_or_else(|| FormatError::InvalidFormat("missing `messages` field".to_string()))?;
+        let messages: Vec<openai::Message> = serde_json::from_value(messages)?;
+
+        let mut transcript = Transcript::default();
+        let mut system = vec![];
+        for message in messages {
+            match message {
+                openai::Message::System { content, .. } if transcript.messages.is_empty() => {
+                    let content = serde_json::to_value(content)?;
+                    system.extend(
+                        content
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|content| content["text"].as_str().map(str::to_string)),
+                    );
+                }
+                message => transcript.messages.push(message.try_into()?),
+            }
+        }
+        if !system.is_empty() {
+            transcript.system = Some(system.join("\n"));
+        }
+
+        Ok(transcript)
+    }
+
+    /// The transcript in the ShareGPT format. Tool calls are represented as `function_call`
+    /// entries (with a `{"name": ..., "arguments": ...}` JSON value) and tool results as
+    /// `observation` entries. Images are replaced by an `<image>` placeholder.
+    pub fn to_sharegpt(&self) -> Value {
+        let mut entries = vec![];
+        let mut push =
+            |from: &str, value: String| entries.push(json!({"from": from, "value": value}));
+
+        if let Some(system) = &self.system {
+            push("system", system.clone());
+        }
+        for message in &self.messages {
+            match message {
+                Message::User { content, .. } => {
+                    let mut texts = vec![];
+                    for content in content.iter() {
+                        match content {
+                            UserContent::ToolResult(tool_result) => {
+                                push("observation", tool_result_text(&tool_result.content))
+                            }
+                            content => texts.extend(user_content_text(content)),
+                        }
+                    }
+                    if !texts.is_empty() {
+                        push("human", texts.join("\n"));
+                    }
+                }
+                Message::Assistant { content, .. } => {
+                    for content in content.iter() {
+                        match content {
+                            AssistantContent::Text(Text { text }) => push("gpt", text.clone()),
+                            AssistantContent::ToolCall(tool_call) => push(
+                                "function_call",
+                                json!({
+                                    "name": tool_call.function.name,
+                                    "arguments": tool_call.function.arguments,
+                                })
+                                .to_string(),
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+
+        json!({ "conversations": entries })
+    }
+
+    /// Parse a conversation in the ShareGPT format. Consecutive `gpt` and `function_call`
+    /// entries are merged into a single assistant message, and tool calls get the IDs
+    /// `call_<index of the entry>`.
+    pub fn from_sharegpt(value: Value) -> Result<Self, FormatError> {
+        let entries = value["conversations"]
+            .as_array()
+            .ok_or_else(|| FormatError::InvalidFormat("missing `conversations` field".into()))?;
+
+        let mut transcript = Transcript::default();
+        let mut last_call_id = None;
+
+        for (index, entry) in entries.iter().enumerate() {
+            let value = entry["value"].as_str().unwrap_or_default().to_string();
+
+            match entry["from"].as_str().unwrap_or_default() {
+                "system" if transcript.messages.is_empty() => transcript.system = Some(value),
+                "system" | "human" | "user" => transcript.messages.push(Message::user(value)),
+                "gpt" | "assistant" => {
+                    push_assistant_content(&mut transcript, AssistantContent::text(value))
+                }
+                "function_call" => {
+                    let call: Value = serde_json::from_str(&value)?;
+                    let id = format!("call_{index}");
+                    last_call_id = Some(id.clone());
+                    push_assistant_content(
+                        &mut transcript,
+                        AssistantContent::ToolCall(ToolCall {
+                            id,
+                            function: ToolFunction {
+                                name: call["name"].as_str().unwrap_or_default().to_string(),
+                                arguments: call["arguments"].clone(),
+                            },
+                        }),
+                    );
+                }
+                "observation" | "tool" => transcript.messages.push(Message::User {
+                    content: OneOrMany::one(UserContent::tool_result(
+                        last_call_id
+                            .take()
+                            .unwrap_or_else(|| format!("call_{index}")),
+                        OneOrMany::one(ToolResultContent::text(value)),
+                    )),
+                    name: None,
+                }),
+                from => {
+                    return Err(FormatError::InvalidFormat(format!(
+                        "unknown role `{from}` in entry {index}"
+                    )))
+                }
+            }
+        }
+
+        Ok(transcript)
+    }
+
+    /// Render the transcript as markdown, with a `## System`, `## User`, `## Assistant` or
+    /// `## Tool result` section per message. Tool calls are rendered as JSON code blocks.
+    pub fn to_markdown(&self) -> String {
+        let mut sections = vec![];
+
+        if let Some(system) = &self.system {
+            sections.push(format!("## System\n\n{system}"));
+        }
+        for message in &self.messages {
+            match message {
+                Message::User { content, .. } => {
+                    let mut texts = vec![];
+                    for content in content.iter() {
+                        match content {
+                            UserContent::ToolResult(tool_result) => sections.push(format!(
+                                "## Tool result (`{}`)\n\n{}",
+                                tool_result.id,
+                                tool_result_text(&tool_result.content)
+                            )),
+                            content => texts.extend(user_content_text(content)),
+                        }
+                    }
+                    if !texts.is_empty() {
+                        sections.push(format!("## User\n\n{}", texts.join("\n\n")));
+                    }
+                }
+                Message::Assistant { content, .. } => {
+                    let parts = content
+                        .iter()
+                        .map(|content| match content {
+                            AssistantContent::Text(Text { text }) => text.clone(),
+                            AssistantContent::ToolCall(tool_call) => format!(
+                                "**Tool call** `{}` (`{}`):\n\n```json\n{}\n```",
+                                tool_call.function.name,
+                                tool_call.id,
+                                serde_json::to_string_pretty(&tool_call.function.arguments)
+                                    .unwrap_or_default()
+                            ),
+                        })
+                        .collect::<Vec<_>>();
+                    sections.push(format!("## Assistant\n\n{}", parts.join("\n\n")));
+                }
+            }
+        }
+
+        sections.join("\n\n") + "\n"
+    }
+
+    /// Parse a markdown transcript, as rendered by [Transcript::to_markdown]. The content of the
+    /// messages must not contain the section headings.
+    pub fn from_markdown(markdown: &str) -> Result<Self, FormatError> {
+        static HEADING: OnceLock<Regex> = OnceLock::new();
+        static TOOL_CALL: OnceLock<Regex> = OnceLock::new();
+        let heading = HEADING.get_or_init(|| {
+            Regex::new(r"(?m)^## (System|User|Assistant|Tool result \(`([^`]*)`\))[ \t]*$")
+                .expect("Valid regex")
+        });
+        let tool_call = TOOL_CALL.get_or_init(|| {
+            Regex::new(r"(?s)\*\*Tool call\*\* `([^`]*)` \(`([^`]*)`\):\s*```json\n(.*?)\n```")
+                .expect("Valid regex")
+        });
+
+        let headings = heading.captures_iter(markdown).collect::<Vec<_>>();
+        if headings.is_empty() && !markdown.trim().is_empty() {
+            return Err(FormatError::InvalidFormat(
+                "no message heading found".to_string(),
+            ));
+        }
+
+        let mut transcript = Transcript::default();
+        for (i, captures) in headings.iter().enumerate() {
+            let start = captures.get(0).expect("Whole match").end();
+            let end = headings.get(i + 1).map_or(markdown.len(), |next| {
+                next.get(0).expect("Whole match").start()
+            });
+            let body = markdown[start..end].trim().to_string();
+
+            match &captures[1] {
+                "System" if transcript.messages.is_empty() => transcript.system = Some(body),
+                "System" | "User" => transcript.messages.push(Message::user(body)),
+                "Assistant" => {
+                    let mut content = vec![];
+                    let mut last = 0;
+                    for call in tool_call.captures_iter(&body) {
+                        let whole = call.get(0).expect("Whole match");
+                        let text = body[last..whole.start()].trim();
+                        if !text.is_empty() {
+                            content.push(AssistantContent::text(text));
+                        }
+                        content.push(AssistantContent::tool_call(
+                            &call[2],
+                            &call[1],
+                            serde_json::from_str(&call[3])?,
+                        ));
+                        last = whole.end();
+                    }
+                    let text = body[last..].trim();
+                    if !text.is_empty() || content.is_empty() {
+                        content.push(AssistantContent::text(text));
+                    }
+                    transcript.messages.push(Message::Assistant {
+                        content: OneOrMany::many(content).expect("At least one content"),
+                        name: None,
+                    });
+                }
+                _ => transcript.messages.push(Message::User {
+                    content: OneOrMany::one(UserContent::tool_result(
+                        captures.get(2).map_or("", |id| id.as_str()),
+                        OneOrMany::one(ToolResultContent::text(body)),
+                    )),
+                    name: None,
+                }),
+            }
+        }
+
+        Ok(transcript)
+    }
+}
+
+/// Serialize transcripts as OpenAI fine-tuning JSONL, one example per line.
+pub fn to_openai_jsonl(transcripts: &[Transcript]) -> Result<String, FormatError> {
+    transcripts
+        .iter()
+        .try_fold(String::new(), |mut jsonl, transcript| {
+            jsonl.push_str(&serde_json::to_string(&transcript.to_openai()?)?);
+            jsonl.push('\n');
+            Ok(jsonl)
+        })
+}
+
+/// Parse OpenAI fine-tuning JSONL, ignoring blank lines.
+pub fn from_openai_jsonl(jsonl: &str) -> Result<Vec<Transcript>, FormatError> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(FormatError::from)
+                .and_then(Transcript::from_openai)
+                .map_err(|error| FormatError::Line {
+                    line: i + 1,
+                    error: Box::new(error),
+                })
+        })
+        .collect()
+}
+
+/// Use plain strings for text-only contents, as in the OpenAI fine-tuning examples, and drop
+/// the empty contents of assistant messages with tool calls.
+fn simplify_openai_message(mut message: Value) -> Value {
+    let Some(object) = message.as_object_mut() else {
+        return message;
+    };
+
+    if let Some(Value::Array(parts)) = object.get("content") {
+        if parts.is_empty() {
+            object.remove("content");
+        } else if parts.iter().all(|part| part["type"] == "text") {
+            let text = parts
+                .iter()
+                .filter_map(|part| part["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            object.insert("content".to_string(), Value::String(text));
+        }
+    }
+    message
+}
+
+/// The text of a user content, if any (images are replaced by an `<image>` placeholder).
+fn user_content_text(content: &UserContent) -> Option<String> {
+    match content {
+        UserContent::Text(Text { text }) => Some(text.clone()),
+        UserContent::Document(document) => Some(document.data.clone()),
+        UserContent::Image(_) => Some("<image>".to_string()),
+        UserContent::Audio(_) | UserContent::ToolResult(_) => None,
+    }
+}
+
+fn tool_result_text(content: &OneOrMany<ToolResultContent>) -> String {
+    content
+        .iter()
+        .map(|content| match content {
+            ToolResultContent::Text(Text { text }) => text.clone(),
+            ToolResultContent::Image(_) => "<image>".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn push_assistant_content(transcript: &mut Transcript, content: AssistantContent) {
+    match transcript.messages.last_mut() {
+        Some(Message::Assistant {
+            content: contents, ..
+        }) => contents.push(content),
+        _ => transcript.messages.push(Message::Assistant {
+            content: OneOrMany::one(content),
+            name: None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Transcript {
+        Transcript::new(vec![
+            Message::user("What's the weather in Paris?"),
+            Message::Assistant {
+                content: OneOrMany::many(vec![
+                    AssistantContent::text("Let me check."),
+                    AssistantContent::tool_call("call_1", "get_weather", json!({"city": "Paris"})),
+                ])
+                .unwrap(),
+                name: None,
+            },
+            Message::User {
+                content: OneOrMany::one(UserContent::tool_result(
+                    "call_1",
+                    OneOrMany::one(ToolResultContent::text("Sunny, 25°C")),
+                )),
+                name: None,
+            },
+            Message::assistant("It's sunny and 25°C in Paris."),
+        ])
+        .with_system("You are a weather bot.")
+    }
+
+    #[test]
+    fn test_openai_jsonl() {
+        let jsonl =
+            to_openai_jsonl(&[transcript(), Transcript::new(vec![Message::user("Hi")])]).unwrap();
+        let lines = jsonl.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            serde_json::from_str::<Value>(lines[0]).unwrap(),
+            json!({"messages": [
+                {"role": "system", "content": "You are a weather bot."},
+                {"role": "user", "content": "What's the weather in Paris?"},
+                {"role": "assistant", "content": "Let me check.", "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"},
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "Sunny, 25°C"},
+                {"role": "assistant", "content": "It's sunny and 25°C in Paris."},
+            ]})
+        );
+
+        let parsed = from_openai_jsonl(&jsonl).unwrap();
+        assert_eq!(parsed[0], transcript());
+        assert_eq!(parsed[1].messages, vec![Message::user("Hi")]);
+
+        assert!(matches!(
+            from_openai_jsonl("\n{\"messages\": 1}"),
+            Err(FormatError::Line { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_sharegpt() {
+        let sharegpt = transcript().to_sharegpt();
+        let froms = sharegpt["conversations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["from"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            froms,
+            vec![
+                "system",
+                "human",
+                "gpt",
+                "function_call",
+                "observation",
+                "gpt"
+            ]
+        );
+
+        let parsed = Transcript::from_sharegpt(sharegpt).unwrap();
+        let mut expected = transcript();
+        // Tool call IDs are not part of the format
+        expected.messages[1] = Message::Assistant {
+            content: OneOrMany::many(vec![
+                AssistantContent::text("Let me check."),
+                AssistantContent::tool_call("call_3", "get_weather", json!({"city": "Paris"})),
+            ])
+            .unwrap(),
+            name: None,
+        };
+        expected.messages[2] = Message::User {
+            content: OneOrMany::one(UserContent::tool_result(
+                "call_3",
+                OneOrMany::one(ToolResultContent::text("Sunny, 25°C")),
+            )),
+            name: None,
+        };
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_markdown() {
+        let markdown = transcript().to_markdown();
+
+        assert!(markdown.starts_with(
+            "## System\n\nYou are a weather bot.\n\n## User\n\nWhat's the weather in Paris?\n\n\
+             ## Assistant\n\nLet me check.\n\n**Tool call** `get_weather` (`call_1`):"
+        ));
+        assert!(markdown.contains("## Tool result (`call_1`)\n\nSunny, 25°C\n\n"));
+        assert_eq!(Transcript::from_markdown(&markdown).unwrap(), transcript());
+        assert!(Transcript::from_markdown("Hello").is_err());
+    }
+}
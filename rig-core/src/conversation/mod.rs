@@ -0,0 +1,237 @@
+//! This module provides the [Conversation] struct, a serializable snapshot of a conversation
+//! with an agent: its message history, the memory (context documents) available to the agent
+//! and an optional sampling seed.
+//!
+//! Conversations can be forked into several independent branches with [Conversation::fork] and
+//! [Conversation::fork_at], and the user turns of a conversation can be replayed against another
+//! agent (e.g.: a different model, preamble or temperature) with [Conversation::replay]. This
+//! makes it easy to A/B test prompts and models on the exact same conversation.
+//!
+//! Conversations can be exported to (and imported from) standard formats, such as the OpenAI
+//! fine-tuning JSONL format, with the [formats] module.
+//!
+//! # Example
+//! ```rust
+//! use rig::{conversation::Conversation, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//! let gpt4o = openai.agent(openai::GPT_4O).preamble("You are a helpful assistant.").build();
+//! let gpt4o_mini = openai.agent(openai::GPT_4O_MINI).preamble("You are a helpful assistant.").build();
+//!
+//! let mut conversation = Conversation::new().with_seed(42);
+//! conversation.send(&gpt4o, "What is the capital of France?").await?;
+//! conversation.send(&gpt4o, "And its population?").await?;
+//!
+//! // Branch off after the first exchange and ask a different follow-up question
+//! let mut branch = conversation.fork_at(2);
+//! branch.send(&gpt4o, "And its area?").await?;
+//!
+//! // Replay the whole conversation with another model
+//! let replayed = conversation.replay(&gpt4o_mini).await?;
+//! println!("{:?}", replayed.history.last());
+//! # Ok(())
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    agent::Agent,
+    completion::{Completion, CompletionModel, PromptError},
+    message::{AssistantContent, Message, ToolResultContent, UserContent},
+    metadata::Document,
+    OneOrMany,
+};
+
+pub mod formats;
+
+use formats::Transcript;
+
+/// A snapshot of a conversation with an agent, which can be forked and replayed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Conversation {
+    /// The messages exchanged so far, in order.
+    pub history: Vec<Message>,
+    /// Context documents sent along with every prompt of the conversation.
+    pub memory: Vec<Document>,
+    /// Sampling seed sent to the model (as the `seed` additional parameter) with every prompt.
+    pub seed: Option<u64>,
+}
+
+impl Conversation {
+    /// Create a new, empty conversation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the seed sent to the model with every prompt of the conversation.
+    /// Note: not all providers support seeded sampling.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Add a context document to the memory of the conversation.
+    pub fn with_memory(mut self, document: Document) -> Self {
+        self.memory.push(document);
+        self
+    }
+
+    /// Send a prompt to the agent as the next turn of the conversation and return the response.
+    ///
+    /// The prompt and the response are appended to the history. If the agent responds with a
+    /// tool call, the tool is called and both the call and its result are recorded, so that the
+    /// history remains valid for any provider.
+    pub async fn send<M: CompletionModel>(
+        &mut self,
+        agent: &Agent<M>,
+        prompt: impl Into<Message> + Send,
+    ) -> Result<String, PromptError> {
+        let prompt: Message = prompt.into();
+
+        let mut request = agent
+            .completion(prompt.clone(), self.history.clone())
+            .await?
+            .documents(self.memory.clone());
+        if let Some(seed) = self.seed {
+            request = request.additional_params(json!({ "seed": seed }));
+        }
+        let resp = request.send().await?;
+
+        self.history.push(prompt);
+        self.history.push(Message::Assistant {
+            content: resp.choice.clone(),
+            name: None,
+        });
+
+        match resp.choice.first() {
+            AssistantContent::Text(text) => Ok(text.text),
+            AssistantContent::ToolCall(tool_call) => {
+                let output = agent
+                    .tools
+                    .call(
+                        &tool_call.function.name,
+                        tool_call.function.arguments.to_string(),
+                    )
+                    .await?;
+
+                self.history.push(Message::User {
+                    content: OneOrMany::one(UserContent::tool_result(
+                        tool_call.id,
+                        OneOrMany::one(ToolResultContent::text(output.clone())),
+                    )),
+                    name: None,
+                });
+
+                Ok(output)
+            }
+        }
+    }
+
+    /// Fork the conversation into a new, independent branch with the same history,
+    /// memory and seed.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
+    /// Fork the conversation into a new branch that only keeps the first `n` messages
+    /// of the history.
+    pub fn fork_at(&self, n: usize) -> Self {
+        let mut branch = self.clone();
+        branch.history.truncate(n);
+        branch
+    }
+
+    /// The prompts sent by the user in this conversation, in order (tool results excluded).
+    pub fn prompts(&self) -> impl Iterator<Item = &Message> {
+        self.history.iter().filter(|message| match message {
+            Message::User { content, .. } => !content
+                .iter()
+                .all(|content| matches!(content, UserContent::ToolResult(_))),
+            Message::Assistant { .. } => false,
+        })
+    }
+
+    /// The history of the conversation as a [Transcript], e.g.: to export it in a standard format.
+    pub fn transcript(&self) -> Transcript {
+        Transcript::new(self.history.clone())
+    }
+
+    /// Replay the user prompts of this conversation against another agent, starting from an
+    /// empty history with the same memory and seed. Returns the new branch.
+    pub async fn replay<M: CompletionModel>(
+        &self,
+        agent: &Agent<M>,
+    ) -> Result<Conversation, PromptError> {
+        let mut branch = Conversation {
+            history: vec![],
+            memory: self.memory.clone(),
+            seed: self.seed,
+        };
+
+        for prompt in self.prompts() {
+            branch.send(agent, prompt.clone()).await?;
+        }
+
+        Ok(branch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent::AgentBuilder, metadata::DocumentMetadata, test_utils::MockModel};
+
+    /// Answers with its name, the size of the request and the prompt
+    fn mock(name: &'static str) -> MockModel {
+        MockModel::reply(move |request| {
+            let prompt = request.prompt.rag_text().unwrap_or_default();
+            let seed = request
+                .additional_params
+                .and_then(|params| params["seed"].as_u64());
+            format!(
+                "{} ({} messages, {} documents, seed {:?}): {}",
+                name,
+                request.chat_history.len(),
+                request.documents.len(),
+                seed,
+                prompt
+            )
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fork_and_replay() {
+        let model_a = AgentBuilder::new(mock("a")).build();
+        let model_b = AgentBuilder::new(mock("b")).build();
+
+        let mut conversation = Conversation::new()
+            .with_seed(7)
+            .with_memory(Document::new(DocumentMetadata::new("doc0"), "Some context"));
+        conversation.send(&model_a, "hello").await.unwrap();
+        let response = conversation.send(&model_a, "how are you?").await.unwrap();
+        assert_eq!(
+            response,
+            "a (2 messages, 1 documents, seed Some(7)): how are you?"
+        );
+        assert_eq!(conversation.history.len(), 4);
+
+        let mut branch = conversation.fork_at(2);
+        branch.send(&model_a, "goodbye").await.unwrap();
+        assert_eq!(branch.history.len(), 4);
+        assert_eq!(branch.history[2], Message::user("goodbye"));
+        assert_eq!(conversation.history[2], Message::user("how are you?"));
+
+        let replayed = conversation.replay(&model_b).await.unwrap();
+        assert_eq!(
+            replayed.history,
+            vec![
+                Message::user("hello"),
+                Message::assistant("b (0 messages, 1 documents, seed Some(7)): hello"),
+                Message::user("how are you?"),
+                Message::assistant("b (2 messages, 1 documents, seed Some(7)): how are you?"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! This module provides [Debate], a multi-agent debate orchestrator exposed as a completion model.
+//!
+//! Several participant models first answer the request independently. Then, for a number of
+//! rounds, each participant reviews the answers of the others and revises its own answer.
+//! Finally, a judge model answers the original request, given the final answers of the
+//! participants, merging the correct parts of each.
+//!
+//! Since [Debate] implements [CompletionModel], it can be used as a drop-in replacement of a
+//! single model (e.g.: in an agent) to improve the accuracy of the answers, at the cost of
+//! `participants * (rounds + 1) + 1` calls per request.
+//!
+//! # Example
+//! ```rust
+//! use rig::{agent::AgentBuilder, completion::Prompt, debate::Debate, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//!
+//! let debate = Debate::new(
+//!     vec![
+//!         openai.completion_model(openai::GPT_4O_MINI),
+//!         openai.completion_model(openai::GPT_4O_MINI),
+//!         openai.completion_model(openai::GPT_4O_MINI),
+//!     ],
+//!     openai.completion_model(openai::GPT_4O),
+//! )
+//! .rounds(2);
+//!
+//! let agent = AgentBuilder::new(debate).build();
+//! let answer = agent
+//!     .prompt("How many times does the letter r appear in 'strawberry'?")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::future::try_join_all;
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    message::{AssistantContent, Message},
+    OneOrMany,
+};
+
+/// The raw response of a [Debate]: the answers of the participants at each round, along with
+/// the raw response of the judge.
+#[derive(Clone, Debug)]
+pub struct DebateResponse<T> {
+    /// `rounds[i][j]` is the answer of participant `j` at round `i` (round 0 being the
+    /// independent answers)
+    pub rounds: Vec<Vec<String>>,
+    pub raw_response: T,
+}
+
+/// Multi-agent debate between participant models, merged by a judge model.
+#[derive(Clone)]
+pub struct Debate<M: CompletionModel, J: CompletionModel> {
+    participants: Vec<M>,
+    judge: J,
+    rounds: usize,
+}
+
+impl<M: CompletionModel, J: CompletionModel> Debate<M, J> {
+    /// Create a new debate with one critique round.
+    pub fn new(participants: Vec<M>, judge: J) -> Self {
+        Self {
+            participants,
+            judge,
+            rounds: 1,
+        }
+    }
+
+    /// Set the number of rounds in which the participants critique each other's answers.
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Ask participant `index` to critique the answers of the others and revise its own.
+    fn critique_request(
+        request: &CompletionRequest,
+        answers: &[String],
+        index: usize,
+    ) -> CompletionRequest {
+        let others = answers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .map(|(i, answer)| format!("Assistant {}:\n{answer}", i + 1))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let mut critique = request.clone();
+        critique.chat_history.push(request.prompt.clone());
+        critique
+            .chat_history
+            .push(Message::assistant(answers[index].clone()));
+        critique.prompt = Message::user(format!(
+            "Other assistants answered the same message:\n\n{others}\n\n\
+            Critically review their answers and yours, then write your updated answer. \
+            Reply with the answer only."
+        ));
+        critique
+    }
+}
+
+/// The text of a response, with tool calls rendered as text.
+fn response_text(choice: &OneOrMany<AssistantContent>) -> String {
+    choice
+        .iter()
+        .map(|content| match content {
+            AssistantContent::Text(text) => text.text.clone(),
+            AssistantContent::ToolCall(tool_call) => format!(
+                "Call the `{}` tool with arguments: {}",
+                tool_call.function.name, tool_call.function.arguments
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl<M: CompletionModel, J: CompletionModel> CompletionModel for Debate<M, J> {
+    type Response = DebateResponse<J::Response>;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let mut answers = try_join_all(
+            self.participants
+                .iter()
+                .map(|participant| participant.completion(request.clone())),
+        )
+        .await?
+        .iter()
+        .map(|response| response_text(&response.choice))
+        .collect::<Vec<_>>();
+        let mut rounds = vec![answers.clone()];
+
+        for _ in 0..self.rounds {
+            answers = try_join_all(
+                self.participants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, participant)| {
+                        participant.completion(Self::critique_request(&request, &answers, i))
+                    }),
+            )
+            .await?
+            .iter()
+            .map(|response| response_text(&response.choice))
+            .collect();
+            rounds.push(answers.clone());
+        }
+
+        let candidates = answers
+            .iter()
+            .enumerate()
+            .map(|(i, answer)| format!("Assistant {}:\n{answer}", i + 1))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let instructions = format!(
+            "Several assistants answered the last message independently, then debated their \
+            answers. Their final answers are:\n\n{candidates}\n\n\
+            Write the best possible answer to the last message, merging the correct parts of \
+            their answers and discarding the incorrect ones."
+        );
+
+        let mut judge_request = request;
+        judge_request.preamble = Some(match judge_request.preamble {
+            Some(preamble) => format!("{preamble}\n\n{instructions}"),
+            None => instructions,
+        });
+        let response = self.judge.completion(judge_request).await?;
+
+        Ok(CompletionResponse {
+            choice: response.choice,
+            annotations: response.annotations,
+            alternatives: response.alternatives,
+            finish_reason: response.finish_reason,
+            raw_response: DebateResponse {
+                rounds,
+                raw_response: response.raw_response,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{completion::CompletionRequestBuilder, test_utils::MockModel};
+
+    /// Answers with its name and the number of messages it was sent (or with its preamble, for
+    /// the judge)
+    fn mock(name: &'static str) -> MockModel {
+        MockModel::reply(move |request| match name {
+            "judge" => request.preamble.unwrap(),
+            name => format!("{name}:{}", request.chat_history.len()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_debate() {
+        let debate = Debate::new(vec![mock("a"), mock("b")], mock("judge")).rounds(2);
+
+        let response = CompletionRequestBuilder::new(debate.clone(), "Question?")
+            .preamble("Be accurate.".to_string())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.raw_response.rounds,
+            vec![
+                vec!["a:0".to_string(), "b:0".to_string()],
+                vec!["a:2".to_string(), "b:2".to_string()],
+                vec!["a:2".to_string(), "b:2".to_string()],
+            ]
+        );
+
+        let AssistantContent::Text(text) = response.choice.first() else {
+            panic!("Expected a text response");
+        };
+        assert!(text.text.starts_with("Be accurate.\n\nSeveral assistants"));
+        assert!(text.text.contains("Assistant 1:\na:2\n\nAssistant 2:\nb:2"));
+    }
+}
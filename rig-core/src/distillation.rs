@@ -0,0 +1,417 @@
+//! This module provides [DistillationDataset], a builder of fine-tuning datasets from the
+//! responses of a (large, expensive) teacher model, to fine-tune a cheaper student model.
+//!
+//! The examples are recorded by prompting a teacher agent (see [DistillationDataset::distill]) or,
+//! with the `audit` feature, read from the completions of an audit log (see
+//! [DistillationDataset::extend_from_audit_log]). Before being recorded, the examples are:
+//! - redacted with a [PiiRedactor] (emails, phone and card numbers, etc.), if any;
+//! - checked with the quality filter of the dataset, if any;
+//! - deduplicated (on their normalized text, i.e.: ignoring case and whitespace).
+//!
+//! The dataset is exported as an OpenAI fine-tuning JSONL document, which can be uploaded with
+//! the `finetune` module.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     distillation::{DistillationDataset, PiiRedactor},
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let teacher = openai
+//!     .agent(openai::GPT_4O)
+//!     .preamble("You are the support assistant of ACME Inc. <long instructions...>")
+//!     .build();
+//!
+//! let mut dataset = DistillationDataset::new()
+//!     .with_system("You are the support assistant of ACME Inc.")
+//!     .redact_pii(PiiRedactor::new())
+//!     .quality_filter(|_prompt, response| response.len() > 20);
+//!
+//! for prompt in ["How do I reset my password?", "Can I change my email address?"] {
+//!     dataset.distill(&teacher, prompt).await?;
+//! }
+//!
+//! dataset.save("distillation.jsonl")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::HashSet, path::Path};
+
+use regex::Regex;
+
+use crate::{
+    completion::{Prompt, PromptError},
+    conversation::formats::{self, FormatError, Transcript},
+    message::{AssistantContent, Message, UserContent},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DistillationError {
+    /// Error reading or writing a file
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// The examples cannot be converted to the fine-tuning format
+    #[error("FormatError: {0}")]
+    FormatError(#[from] FormatError),
+}
+
+/// Replaces personally identifiable information in texts with placeholders (e.g.: `[EMAIL]`).
+#[derive(Clone, Debug)]
+pub struct PiiRedactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Default for PiiRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PiiRedactor {
+    /// A redactor of email addresses, social security, card and phone numbers, and IP addresses.
+    pub fn new() -> Self {
+        let rules = [
+            (r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+", "[EMAIL]"),
+            (r"\b\d{3}-\d{2}-\d{4}\b", "[SSN]"),
+            (r"\b(?:\d[ -]?){12,18}\d\b", "[CARD]"),
+            (
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]?\d{3}[ .-]?\d{4}\b",
+                "[PHONE]",
+            ),
+            (r"\b(?:\d{1,3}\.){3}\d{1,3}\b", "[IP]"),
+        ];
+
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|(pattern, placeholder)| {
+                    (
+                        Regex::new(pattern).expect("Valid regex"),
+                        placeholder.to_string(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// A redactor without rules.
+    pub fn none() -> Self {
+        Self { rules: vec![] }
+    }
+
+    /// Add a rule replacing the matches of the pattern with the placeholder.
+    pub fn rule(mut self, pattern: &str, placeholder: &str) -> Result<Self, regex::Error> {
+        self.rules
+            .push((Regex::new(pattern)?, placeholder.to_string()));
+        Ok(self)
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        self.rules
+            .iter()
+            .fold(text.to_string(), |text, (pattern, placeholder)| {
+                pattern
+                    .replace_all(&text, regex::NoExpand(placeholder))
+                    .into_owned()
+            })
+    }
+
+    /// Redact the texts of a message (text contents and tool results).
+    pub fn redact_message(&self, message: &mut Message) {
+        match message {
+            Message::User { content, .. } => {
+                for content in content.iter_mut() {
+                    match content {
+                        UserContent::Text(text) => text.text = self.redact(&text.text),
+                        UserContent::ToolResult(result) => {
+                            for content in result.content.iter_mut() {
+                                if let crate::message::ToolResultContent::Text(text) = content {
+                                    text.text = self.redact(&text.text);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Message::Assistant { content, .. } => {
+                for content in content.iter_mut() {
+                    match content {
+                        AssistantContent::Text(text) => text.text = self.redact(&text.text),
+                        AssistantContent::ToolCall(call) => {
+                            call.function.arguments =
+                                redact_json(self, call.function.arguments.take());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn redact_json(redactor: &PiiRedactor, value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::String(text) => Value::String(redactor.redact(&text)),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| redact_json(redactor, value))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, redact_json(redactor, value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// The text of a message (its text contents, joined with newlines).
+fn message_text(message: &Message) -> String {
+    match message {
+        Message::User { content, .. } => content
+            .iter()
+            .filter_map(|content| match content {
+                UserContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Message::Assistant { content, .. } => content
+            .iter()
+            .filter_map(|content| match content {
+                AssistantContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// The normalized text of a transcript, used to deduplicate the examples.
+fn dedup_key(transcript: &Transcript) -> String {
+    transcript
+        .messages
+        .iter()
+        .map(message_text)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Number of examples recorded, and of examples skipped because they were duplicates or rejected
+/// by the quality filter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DistillationStats {
+    pub recorded: usize,
+    pub duplicates: usize,
+    pub rejected: usize,
+}
+
+type QualityFilter = Box<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
+/// A fine-tuning dataset of examples answered by a teacher model.
+#[derive(Default)]
+pub struct DistillationDataset {
+    system: Option<String>,
+    redactor: Option<PiiRedactor>,
+    filter: Option<QualityFilter>,
+    examples: Vec<Transcript>,
+    seen: HashSet<String>,
+    stats: DistillationStats,
+}
+
+impl DistillationDataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the system prompt of the examples, i.e.: the preamble of the student model (replacing
+    /// the preamble of the teacher, if any).
+    pub fn with_system(mut self, system: &str) -> Self {
+        self.system = Some(system.to_string());
+        self
+    }
+
+    /// Redact the examples with the given redactor before recording them.
+    pub fn redact_pii(mut self, redactor: PiiRedactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Only record the examples for which the filter returns `true`, given the (last) prompt and
+    /// the response of the example.
+    pub fn quality_filter(
+        mut self,
+        filter: impl Fn(&str, &str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Record an example. Returns whether it was recorded (i.e.: it is not a duplicate, and was
+    /// accepted by the quality filter).
+    pub fn record(&mut self, mut transcript: Transcript) -> bool {
+        if let Some(redactor) = &self.redactor {
+            transcript
+                .messages
+                .iter_mut()
+                .for_each(|message| redactor.redact_message(message));
+        }
+        if let Some(system) = &self.system {
+            transcript.system = Some(system.clone());
+        }
+
+        if let Some(filter) = &self.filter {
+            let last_text = |user: bool| {
+                transcript
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|message| matches!(message, Message::User { .. }) == user)
+                    .map(message_text)
+                    .unwrap_or_default()
+            };
+            if !filter(&last_text(true), &last_text(false)) {
+                self.stats.rejected += 1;
+                return false;
+            }
+        }
+
+        if !self.seen.insert(dedup_key(&transcript)) {
+            self.stats.duplicates += 1;
+            return false;
+        }
+
+        self.examples.push(transcript);
+        self.stats.recorded += 1;
+        true
+    }
+
+    /// Record a (prompt, response) pair. Returns whether it was recorded.
+    pub fn record_pair(&mut self, prompt: &str, response: &str) -> bool {
+        self.record(Transcript::new(vec![
+            Message::user(prompt),
+            Message::assistant(response),
+        ]))
+    }
+
+    /// Prompt the teacher and record its response. The response is returned as is, even if the
+    /// example is not recorded.
+    pub async fn distill(
+        &mut self,
+        teacher: &impl Prompt,
+        prompt: &str,
+    ) -> Result<String, PromptError> {
+        let response = teacher.prompt(prompt).await?;
+        self.record_pair(prompt, &response);
+        Ok(response)
+    }
+
+    /// Record the successful completions of an audit log (see
+    /// [AuditEntry::transcript](crate::audit::AuditEntry::transcript)). Returns the number of
+    /// examples recorded.
+    #[cfg(feature = "audit")]
+    pub fn extend_from_audit_log(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<usize, DistillationError> {
+        let recorded = crate::audit::read_log(path)?
+            .iter()
+            .filter_map(|entry| entry.transcript())
+            .filter(|transcript| self.record(transcript.clone()))
+            .count();
+        Ok(recorded)
+    }
+
+    pub fn examples(&self) -> &[Transcript] {
+        &self.examples
+    }
+
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    pub fn stats(&self) -> DistillationStats {
+        self.stats
+    }
+
+    /// The dataset as an OpenAI fine-tuning JSONL document.
+    pub fn to_openai_jsonl(&self) -> Result<String, FormatError> {
+        formats::to_openai_jsonl(&self.examples)
+    }
+
+    /// Write the dataset to a file, as an OpenAI fine-tuning JSONL document.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DistillationError> {
+        std::fs::write(path, self.to_openai_jsonl()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pii_redactor() {
+        let redactor = PiiRedactor::new()
+            .rule(r"\bACME-\d+\b", "[ACCOUNT]")
+            .unwrap();
+
+        assert_eq!(
+            redactor.redact(
+                "Mail john.doe+spam@example.co.uk or call +1 (555) 123-4567 / 555.123.4567. \
+                 Card 4111 1111 1111 1111, SSN 123-45-6789, IP 192.168.0.1, account ACME-42. \
+                 Order 12345 costs $30."
+            ),
+            "Mail [EMAIL] or call [PHONE] / [PHONE]. Card [CARD], SSN [SSN], IP [IP], \
+             account [ACCOUNT]. Order 12345 costs $30."
+        );
+    }
+
+    #[test]
+    fn test_distillation_dataset() {
+        let mut dataset = DistillationDataset::new()
+            .with_system("Be helpful.")
+            .redact_pii(PiiRedactor::new())
+            .quality_filter(|_, response| !response.starts_with("I don't know"));
+
+        assert!(dataset.record_pair("My email is jane@example.com", "Thanks Jane!"));
+        assert!(!dataset.record_pair("my  EMAIL is bob@example.com", "thanks jane!"));
+        assert!(!dataset.record_pair("What is rig?", "I don't know."));
+        assert!(dataset.record_pair("What is rig?", "A Rust library for LLM apps."));
+
+        assert_eq!(
+            dataset.stats(),
+            DistillationStats {
+                recorded: 2,
+                duplicates: 1,
+                rejected: 1
+            }
+        );
+        let jsonl = dataset.to_openai_jsonl().unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(jsonl.lines().next().unwrap()).unwrap(),
+            serde_json::json!({"messages": [
+                {"role": "system", "content": "Be helpful."},
+                {"role": "user", "content": "My email is [EMAIL]"},
+                {"role": "assistant", "content": "Thanks Jane!"},
+            ]})
+        );
+    }
+}
@@ -7,9 +7,14 @@ use std::{cmp::max, collections::HashMap};
 use futures::{stream, StreamExt};
 
 use crate::{
+    completion::{Prompt, PromptError},
     embeddings::{
-        embed::TextEmbedder, Embed, EmbedError, Embedding, EmbeddingError, EmbeddingModel,
+        contextual::{ContextualChunk, Contextualizer},
+        embed::{ImageInput, TextEmbedder},
+        Embed, EmbedError, Embedding, EmbeddingError, EmbeddingModel,
     },
+    http::RequestOptions,
+    metadata::Document,
     OneOrMany,
 };
 
@@ -49,7 +54,14 @@ use crate::{
 /// ```
 pub struct EmbeddingsBuilder<M: EmbeddingModel, T: Embed> {
     model: M,
-    documents: Vec<(T, Vec<String>)>,
+    documents: Vec<(T, Vec<Input>)>,
+    request_options: RequestOptions,
+}
+
+/// A text or an image to be embedded
+enum Input {
+    Text(String),
+    Image(ImageInput),
 }
 
 impl<M: EmbeddingModel, T: Embed> EmbeddingsBuilder<M, T> {
@@ -58,15 +70,29 @@ impl<M: EmbeddingModel, T: Embed> EmbeddingsBuilder<M, T> {
         Self {
             model,
             documents: vec![],
+            request_options: RequestOptions::default(),
         }
     }
 
+    /// Set extra HTTP headers and query parameters sent with the requests embedding the texts
+    /// of the documents (see [RequestOptions]).
+    pub fn request_options(mut self, request_options: RequestOptions) -> Self {
+        self.request_options = request_options;
+        self
+    }
+
     /// Add a document to be embedded to the builder. `document` must implement the [Embed] trait.
     pub fn document(mut self, document: T) -> Result<Self, EmbedError> {
         let mut embedder = TextEmbedder::default();
         document.embed(&mut embedder)?;
 
-        self.documents.push((document, embedder.texts));
+        let inputs = embedder
+            .texts
+            .into_iter()
+            .map(Input::Text)
+            .chain(embedder.images.into_iter().map(Input::Image))
+            .collect();
+        self.documents.push((document, inputs));
 
         Ok(self)
     }
@@ -82,34 +108,81 @@ impl<M: EmbeddingModel, T: Embed> EmbeddingsBuilder<M, T> {
     }
 }
 
+impl<M: EmbeddingModel> EmbeddingsBuilder<M, ContextualChunk> {
+    /// Split a document into chunks, generate the context of each chunk with the
+    /// contextualizer and add the chunks to be embedded with their context.
+    /// See [contextual](crate::embeddings::contextual).
+    pub async fn contextual_document<P: Prompt>(
+        mut self,
+        contextualizer: &Contextualizer<P>,
+        document: &Document,
+    ) -> Result<Self, PromptError> {
+        for chunk in contextualizer.chunk(document).await? {
+            let inputs = vec![Input::Text(chunk.embedding_text())];
+            self.documents.push((chunk, inputs));
+        }
+
+        Ok(self)
+    }
+}
+
 impl<M: EmbeddingModel, T: Embed + Send> EmbeddingsBuilder<M, T> {
     /// Generate embeddings for all documents in the builder.
     /// Returns a vector of tuples, where the first element is the document and the second element is the embeddings (either one embedding or many).
     pub async fn build(self) -> Result<Vec<(T, OneOrMany<Embedding>)>, EmbeddingError> {
         use stream::TryStreamExt;
 
-        // Store the documents and their texts in a HashMap for easy access.
+        // Store the documents and their inputs in a HashMap for easy access.
         let mut docs = HashMap::new();
-        let mut texts = Vec::new();
+        let mut inputs = Vec::new();
 
-        // Iterate over all documents in the builder and insert their docs and texts into the lookup stores.
-        for (i, (doc, doc_texts)) in self.documents.into_iter().enumerate() {
+        // Iterate over all documents in the builder and insert their docs and inputs into the lookup stores.
+        for (i, (doc, doc_inputs)) in self.documents.into_iter().enumerate() {
             docs.insert(i, doc);
-            texts.push((i, doc_texts));
+            inputs.push((i, doc_inputs));
         }
 
         // Compute the embeddings.
-        let mut embeddings = stream::iter(texts.into_iter())
-            // Merge the texts of each document into a single list of texts.
-            .flat_map(|(i, texts)| stream::iter(texts.into_iter().map(move |text| (i, text))))
+        let mut embeddings = stream::iter(inputs)
+            // Merge the texts (and images) of each document into a single list of inputs.
+            .flat_map(|(i, inputs)| stream::iter(inputs.into_iter().map(move |input| (i, input))))
             // Chunk them into batches. Each batch size is at most the embedding API limit per request.
             .chunks(M::MAX_DOCUMENTS)
-            // Generate the embeddings for each batch.
-            .map(|text| async {
-                let (ids, docs): (Vec<_>, Vec<_>) = text.into_iter().unzip();
-
-                let embeddings = self.model.embed_texts(docs).await?;
-                Ok::<_, EmbeddingError>(ids.into_iter().zip(embeddings).collect::<Vec<_>>())
+            // Generate the embeddings for each batch, texts and images being embedded separately.
+            .map(|batch| async {
+                let (mut text_ids, mut texts, mut image_ids, mut images) =
+                    (vec![], vec![], vec![], vec![]);
+                for (i, input) in batch {
+                    match input {
+                        Input::Text(text) => {
+                            text_ids.push(i);
+                            texts.push(text);
+                        }
+                        Input::Image(image) => {
+                            image_ids.push(i);
+                            images.push(image);
+                        }
+                    }
+                }
+
+                let mut embeddings = vec![];
+                if !texts.is_empty() {
+                    embeddings.extend(
+                        text_ids.into_iter().zip(
+                            self.model
+                                .embed_texts_with_options(texts, self.request_options.clone())
+                                .await?,
+                        ),
+                    );
+                }
+                if !images.is_empty() {
+                    embeddings.extend(
+                        image_ids
+                            .into_iter()
+                            .zip(self.model.embed_images(images).await?),
+                    );
+                }
+                Ok::<_, EmbeddingError>(embeddings)
             })
             // Parallelize the embeddings generation over 10 concurrent requests
             .buffer_unordered(max(1, 1024 / M::MAX_DOCUMENTS))
@@ -144,7 +217,10 @@ impl<M: EmbeddingModel, T: Embed + Send> EmbeddingsBuilder<M, T> {
 #[cfg(test)]
 mod tests {
     use crate::{
-        embeddings::{embed::EmbedError, embed::TextEmbedder, Embedding, EmbeddingModel},
+        embeddings::{
+            embed::EmbedError, embed::TextEmbedder, Embedding, EmbeddingModel, ImageInput,
+        },
+        message::ImageMediaType,
         Embed,
     };
 
@@ -172,6 +248,19 @@ mod tests {
                 })
                 .collect())
         }
+
+        async fn embed_images(
+            &self,
+            images: impl IntoIterator<Item = ImageInput> + Send,
+        ) -> Result<Vec<crate::embeddings::Embedding>, crate::embeddings::EmbeddingError> {
+            Ok(images
+                .into_iter()
+                .map(|image| Embedding {
+                    document: image.description(),
+                    vec: vec![1.0; 10],
+                })
+                .collect())
+        }
     }
 
     #[derive(Clone, Debug)]
@@ -352,6 +441,21 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn test_build_request_options_unsupported() {
+        let result = EmbeddingsBuilder::new(Model)
+            .document("A green alien that lives on cold planets.".to_string())
+            .unwrap()
+            .request_options(crate::http::RequestOptions::new().header("Idempotency-Key", "1"))
+            .build()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(crate::embeddings::EmbeddingError::ProviderError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_build_string() {
         let bindings = definitions_multiple_text();
@@ -384,4 +488,40 @@ mod tests {
             second_definition.1.rest()[0].document, "A fictional creature found in the distant, swampy marshlands of the planet Glibbo in the Andromeda galaxy.".to_string()
         )
     }
+
+    #[derive(Clone, Debug)]
+    struct Picture {
+        caption: String,
+        image: ImageInput,
+    }
+
+    impl Embed for Picture {
+        fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+            embedder.embed(self.caption.clone());
+            embedder.embed_image(self.image.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_images() {
+        let picture = Picture {
+            caption: "A green alien".to_string(),
+            image: ImageInput::new(vec![0x89, 0x50, 0x4e, 0x47], ImageMediaType::PNG),
+        };
+
+        let result = EmbeddingsBuilder::new(Model)
+            .document(picture)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        let embeddings = result[0].1.iter().collect::<Vec<_>>();
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(embeddings[0].document, "A green alien");
+        assert_eq!(embeddings[1].document, "<image/png image, 4 bytes>");
+        assert_eq!(embeddings[1].vec, vec![1.0; 10]);
+    }
 }
@@ -0,0 +1,235 @@
+//! This module implements contextual chunk augmentation ("contextual retrieval"): documents are
+//! split into chunks, and a (cheap) model writes for each chunk a short context situating it
+//! within its document. The context is prepended to the chunk before embedding, so that chunks
+//! which only make sense within their document (e.g.: "Its revenue grew by 3%") can be found
+//! by queries about the document (e.g.: "ACME revenue growth in Q2").
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     embeddings::{contextual::Contextualizer, EmbeddingsBuilder},
+//!     metadata::{Document, DocumentMetadata},
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let contextualizer = Contextualizer::new(openai.agent(openai::GPT_4O_MINI).build())
+//!     .chunk_size(2_000)
+//!     .concurrency(8);
+//!
+//! let report = Document::new(
+//!     DocumentMetadata::new("acme-q2").title("ACME Q2 report"),
+//!     "<report>",
+//! );
+//! let embeddings = EmbeddingsBuilder::new(openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL))
+//!     .contextual_document(&contextualizer, &report)
+//!     .await?
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::{Prompt, PromptError},
+    embeddings::{Embed, EmbedError, TextEmbedder},
+    metadata::{Document, DocumentMetadata},
+    pipeline::presets::chunk_text,
+};
+
+/// A chunk of a document, with the context situating it within the document.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextualChunk {
+    /// The metadata of the chunk, see [DocumentMetadata::chunk]
+    pub metadata: DocumentMetadata,
+    /// The context generated by the model
+    pub context: String,
+    /// The text of the chunk
+    pub text: String,
+}
+
+impl ContextualChunk {
+    /// The text to embed: the context followed by the chunk.
+    pub fn embedding_text(&self) -> String {
+        if self.context.is_empty() {
+            self.text.clone()
+        } else {
+            format!("{}\n\n{}", self.context, self.text)
+        }
+    }
+}
+
+impl Embed for ContextualChunk {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.embedding_text());
+        Ok(())
+    }
+}
+
+/// Splits documents into [ContextualChunk]s, see the [module documentation](self).
+pub struct Contextualizer<P> {
+    model: P,
+    chunk_size: usize,
+    chunk_overlap: usize,
+    concurrency: usize,
+    prompt: String,
+}
+
+impl<P: Prompt> Contextualizer<P> {
+    /// Create a new contextualizer generating the contexts with the given model (typically a
+    /// cheap agent). The model is prompted with the whole document for each chunk.
+    pub fn new(model: P) -> Self {
+        Self {
+            model,
+            chunk_size: 2_000,
+            chunk_overlap: 0,
+            concurrency: 4,
+            prompt: "<document title=\"{title}\">\n{document}\n</document>\n\
+                Here is the chunk we want to situate within the whole document:\n\
+                <chunk>\n{chunk}\n</chunk>\n\
+                Give a short succinct context (e.g.: the subject of the document and of the \
+                section) to situate this chunk within the overall document, for the purposes of \
+                improving search retrieval of the chunk. Answer only with the succinct context \
+                and nothing else."
+                .to_string(),
+        }
+    }
+
+    /// Set the maximum size of the chunks, in characters (defaults to 2000).
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the number of characters shared by consecutive chunks (defaults to 0).
+    pub fn chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+
+    /// Set the maximum number of concurrent calls to the model (defaults to 4).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the prompt used to generate the contexts. `{title}`, `{document}` and `{chunk}` are
+    /// replaced by the title (or the id) of the document, the document and the chunk.
+    pub fn prompt(mut self, prompt: &str) -> Self {
+        self.prompt = prompt.to_string();
+        self
+    }
+
+    /// Split the document into chunks and generate their contexts. The chunks inherit the
+    /// metadata of the document.
+    pub async fn chunk(&self, document: &Document) -> Result<Vec<ContextualChunk>, PromptError> {
+        let metadata = &document.metadata;
+        let template = self
+            .prompt
+            .replace("{title}", metadata.title.as_ref().unwrap_or(&metadata.id))
+            .replace("{document}", &document.text);
+
+        stream::iter(chunk_text(
+            &document.text,
+            self.chunk_size,
+            self.chunk_overlap,
+        ))
+        .enumerate()
+        .map(|(index, text)| {
+            let prompt = template.replace("{chunk}", &text);
+            async move {
+                let context = self.model.prompt(prompt).await?;
+                Ok(ContextualChunk {
+                    metadata: metadata.chunk(index),
+                    context: context.trim().to_string(),
+                    text,
+                })
+            }
+        })
+        .buffered(self.concurrency)
+        .try_collect()
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        embeddings::EmbeddingsBuilder,
+        message::{Message, Text, UserContent},
+        test_utils::MockEmbeddingModel,
+    };
+
+    /// Responds with the title of the document and the first word of the chunk
+    struct MockModel;
+
+    impl Prompt for MockModel {
+        async fn prompt(&self, prompt: impl Into<Message>) -> Result<String, PromptError> {
+            let Message::User { content, .. } = prompt.into() else {
+                unreachable!()
+            };
+            let UserContent::Text(Text { text }) = content.first() else {
+                unreachable!()
+            };
+            let title = text.split('"').nth(1).unwrap();
+            let chunk = text.split("<chunk>\n").nth(1).unwrap();
+            Ok(format!(
+                " From {title}, {} ",
+                chunk.split_whitespace().next().unwrap()
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contextual_chunks() {
+        let contextualizer = Contextualizer::new(MockModel).chunk_size(10);
+
+        let report = Document::new(
+            DocumentMetadata::new("report")
+                .title("Report")
+                .tag("finance"),
+            "Revenue grew. Costs fell.",
+        );
+        let chunks = contextualizer.chunk(&report).await.unwrap();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (
+                    chunk.metadata.id.as_str(),
+                    chunk.context.as_str(),
+                    chunk.text.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("report#0", "From Report, Revenue", "Revenue"),
+                ("report#1", "From Report, grew.", "grew."),
+                ("report#2", "From Report, Costs", "Costs"),
+                ("report#3", "From Report, fell.", "fell."),
+            ]
+        );
+        assert_eq!(chunks[0].metadata.parent_id.as_deref(), Some("report"));
+        assert_eq!(chunks[0].metadata.tags, vec!["finance".to_string()]);
+
+        // Without a title, the id of the document is used
+        let notes = Document::new(DocumentMetadata::new("Notes"), "Hello world");
+        let mut embeddings = EmbeddingsBuilder::new(MockEmbeddingModel::constant(vec![0.0]))
+            .contextual_document(&contextualizer, &notes)
+            .await
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        embeddings.sort_by(|(a, _), (b, _)| a.metadata.id.cmp(&b.metadata.id));
+
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(embeddings[0].0.metadata.id, "Notes#0");
+        assert_eq!(
+            embeddings[0].1.first().document,
+            "From Notes, Hello\n\nHello"
+        );
+    }
+}
@@ -1,3 +1,115 @@
+//! Similarities and distances between embedding vectors.
+//!
+//! The [dot], [cosine_similarity] and [euclidean] functions work on raw vectors, and are used by
+//! the [VectorDistance] implementation of [Embedding](crate::embeddings::Embedding) and by the
+//! [in-memory vector store](crate::vector_store::in_memory_store). With the `simd` feature, they
+//! process the vectors four components at a time with SIMD instructions. With the `rayon` feature,
+//! the [VectorDistance] implementation also splits long vectors into chunks processed in parallel
+//! by the same functions.
+//!
+//! # Example
+//! ```
+//! use rig::embeddings::{distance, Embedding};
+//!
+//! let mut embedding = Embedding {
+//!     document: "Hello".to_string(),
+//!     vec: vec![3.0, 4.0],
+//! };
+//! embedding.normalize();
+//!
+//! assert_eq!(embedding.vec, vec![0.6, 0.8]);
+//! assert_eq!(distance::dot(&embedding.vec, &[1.0, 0.0]), 0.6);
+//! assert_eq!(distance::euclidean(&[0.0, 0.0], &[3.0, 4.0]), 5.0);
+//! ```
+
+/// Dot product of two vectors (the extra components of the longest vector are ignored).
+pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+    kernels::dot(a, b)
+}
+
+/// Euclidean (L2) norm of a vector.
+pub fn norm(a: &[f64]) -> f64 {
+    kernels::dot(a, a).sqrt()
+}
+
+/// Cosine similarity of two vectors, between -1 and 1. The similarity with a zero vector is 0.
+/// For vectors normalized beforehand (see
+/// [Embedding::normalize](crate::embeddings::Embedding::normalize)), [dot] is equivalent and
+/// faster.
+pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    cosine(kernels::dot, a, b)
+}
+
+/// Cosine similarity computed with the given dot product kernel.
+fn cosine(dot: fn(&[f64], &[f64]) -> f64, a: &[f64], b: &[f64]) -> f64 {
+    let magnitudes = dot(a, a).sqrt() * dot(b, b).sqrt();
+    if magnitudes == 0.0 {
+        0.0
+    } else {
+        dot(a, b) / magnitudes
+    }
+}
+
+/// Euclidean distance between two vectors.
+pub fn euclidean(a: &[f64], b: &[f64]) -> f64 {
+    kernels::squared_euclidean(a, b).sqrt()
+}
+
+#[cfg(not(feature = "simd"))]
+use scalar as kernels;
+#[cfg(feature = "simd")]
+use simd as kernels;
+
+mod scalar {
+    pub(super) fn dot(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    pub(super) fn squared_euclidean(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+    }
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use wide::f64x4;
+
+    const LANES: usize = 4;
+
+    /// Length of the common part of two vectors, and the index of its scalar remainder
+    fn split(a: &[f64], b: &[f64]) -> (usize, usize) {
+        let len = a.len().min(b.len());
+        (len, len - len % LANES)
+    }
+
+    fn lanes(chunk: &[f64]) -> f64x4 {
+        f64x4::from([chunk[0], chunk[1], chunk[2], chunk[3]])
+    }
+
+    pub(super) fn dot(a: &[f64], b: &[f64]) -> f64 {
+        let (len, split) = split(a, b);
+        let sum = a[..split]
+            .chunks_exact(LANES)
+            .zip(b[..split].chunks_exact(LANES))
+            .fold(f64x4::ZERO, |sum, (x, y)| sum + lanes(x) * lanes(y));
+
+        sum.reduce_add() + super::scalar::dot(&a[split..len], &b[split..len])
+    }
+
+    pub(super) fn squared_euclidean(a: &[f64], b: &[f64]) -> f64 {
+        let (len, split) = split(a, b);
+        let sum = a[..split]
+            .chunks_exact(LANES)
+            .zip(b[..split].chunks_exact(LANES))
+            .fold(f64x4::ZERO, |sum, (x, y)| {
+                let difference = lanes(x) - lanes(y);
+                sum + difference * difference
+            });
+
+        sum.reduce_add() + super::scalar::squared_euclidean(&a[split..len], &b[split..len])
+    }
+}
+
 pub trait VectorDistance {
     /// Get dot product of two embedding vectors
     fn dot_product(&self, other: &Self) -> f64;
@@ -20,25 +132,20 @@ pub trait VectorDistance {
 }
 
 #[cfg(not(feature = "rayon"))]
+use kernels as embedding_kernels;
+#[cfg(feature = "rayon")]
+use parallel as embedding_kernels;
+
 impl VectorDistance for crate::embeddings::Embedding {
     fn dot_product(&self, other: &Self) -> f64 {
-        self.vec
-            .iter()
-            .zip(other.vec.iter())
-            .map(|(x, y)| x * y)
-            .sum()
+        embedding_kernels::dot(&self.vec, &other.vec)
     }
 
     fn cosine_similarity(&self, other: &Self, normalized: bool) -> f64 {
-        let dot_product = self.dot_product(other);
-
         if normalized {
-            dot_product
+            self.dot_product(other)
         } else {
-            let magnitude1: f64 = self.vec.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
-            let magnitude2: f64 = other.vec.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
-
-            dot_product / (magnitude1 * magnitude2)
+            cosine(embedding_kernels::dot, &self.vec, &other.vec)
         }
     }
 
@@ -48,12 +155,7 @@ impl VectorDistance for crate::embeddings::Embedding {
     }
 
     fn euclidean_distance(&self, other: &Self) -> f64 {
-        self.vec
-            .iter()
-            .zip(other.vec.iter())
-            .map(|(x, y)| (x - y).powi(2))
-            .sum::<f64>()
-            .sqrt()
+        embedding_kernels::squared_euclidean(&self.vec, &other.vec).sqrt()
     }
 
     fn manhattan_distance(&self, other: &Self) -> f64 {
@@ -73,62 +175,28 @@ impl VectorDistance for crate::embeddings::Embedding {
     }
 }
 
+/// The kernels applied in parallel to chunks of the vectors.
 #[cfg(feature = "rayon")]
-mod rayon {
-    use crate::embeddings::{distance::VectorDistance, Embedding};
+mod parallel {
     use rayon::prelude::*;
 
-    impl VectorDistance for Embedding {
-        fn dot_product(&self, other: &Self) -> f64 {
-            self.vec
-                .par_iter()
-                .zip(other.vec.par_iter())
-                .map(|(x, y)| x * y)
-                .sum()
-        }
-
-        fn cosine_similarity(&self, other: &Self, normalized: bool) -> f64 {
-            let dot_product = self.dot_product(other);
-
-            if normalized {
-                dot_product
-            } else {
-                let magnitude1: f64 = self.vec.par_iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
-                let magnitude2: f64 = other.vec.par_iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
-
-                dot_product / (magnitude1 * magnitude2)
-            }
-        }
-
-        fn angular_distance(&self, other: &Self, normalized: bool) -> f64 {
-            let cosine_sim = self.cosine_similarity(other, normalized);
-            cosine_sim.acos() / std::f64::consts::PI
-        }
+    const CHUNK: usize = 1024;
 
-        fn euclidean_distance(&self, other: &Self) -> f64 {
-            self.vec
-                .par_iter()
-                .zip(other.vec.par_iter())
-                .map(|(x, y)| (x - y).powi(2))
-                .sum::<f64>()
-                .sqrt()
-        }
+    fn chunked(kernel: fn(&[f64], &[f64]) -> f64, a: &[f64], b: &[f64]) -> f64 {
+        let len = a.len().min(b.len());
+        a[..len]
+            .par_chunks(CHUNK)
+            .zip(b[..len].par_chunks(CHUNK))
+            .map(|(x, y)| kernel(x, y))
+            .sum()
+    }
 
-        fn manhattan_distance(&self, other: &Self) -> f64 {
-            self.vec
-                .par_iter()
-                .zip(other.vec.par_iter())
-                .map(|(x, y)| (x - y).abs())
-                .sum()
-        }
+    pub(super) fn dot(a: &[f64], b: &[f64]) -> f64 {
+        chunked(super::kernels::dot, a, b)
+    }
 
-        fn chebyshev_distance(&self, other: &Self) -> f64 {
-            self.vec
-                .iter()
-                .zip(other.vec.iter())
-                .map(|(x, y)| (x - y).abs())
-                .fold(0.0, f64::max)
-        }
+    pub(super) fn squared_euclidean(a: &[f64], b: &[f64]) -> f64 {
+        chunked(super::kernels::squared_euclidean, a, b)
     }
 }
 
@@ -198,4 +266,57 @@ mod tests {
 
         assert_eq!(embedding_1.chebyshev_distance(&embedding_2), 4.0)
     }
+
+    #[test]
+    fn test_normalize() {
+        let (mut embedding_1, mut embedding_2) = embeddings();
+        let similarity = embedding_1.cosine_similarity(&embedding_2, false);
+
+        embedding_1.normalize();
+        embedding_2.normalize();
+        assert!((super::norm(&embedding_1.vec) - 1.0).abs() < 1e-12);
+        assert!((embedding_1.cosine_similarity(&embedding_2, true) - similarity).abs() < 1e-12);
+
+        let mut zero = Embedding {
+            vec: vec![0.0; 3],
+            ..Default::default()
+        };
+        zero.normalize();
+        assert_eq!(zero.vec, vec![0.0; 3]);
+        assert_eq!(super::cosine_similarity(&zero.vec, &embedding_1.vec), 0.0);
+        assert_eq!(zero.cosine_similarity(&embedding_1, false), 0.0);
+    }
+
+    #[test]
+    fn test_long_vectors() {
+        // Long enough for the SIMD kernels (with a remainder), when the `simd` feature is enabled
+        let a = (0..1027).map(|i| (i as f64).sin()).collect::<Vec<_>>();
+        let b = (0..1027).map(|i| (i as f64).cos()).collect::<Vec<_>>();
+
+        let dot: f64 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        let euclidean = a
+            .iter()
+            .zip(&b)
+            .map(|(x, y)| (x - y).powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        assert!((super::dot(&a, &b) - dot).abs() < 1e-9);
+        assert!((super::euclidean(&a, &b) - euclidean).abs() < 1e-9);
+
+        // Long enough for the parallel chunks, when the `rayon` feature is enabled
+        let (a, b) = (a.repeat(3), b.repeat(3));
+        let (x, y) = (
+            Embedding {
+                vec: a.clone(),
+                ..Default::default()
+            },
+            Embedding {
+                vec: b.clone(),
+                ..Default::default()
+            },
+        );
+        assert!((x.dot_product(&y) - super::dot(&a, &b)).abs() < 1e-9);
+        assert!((x.euclidean_distance(&y) - super::euclidean(&a, &b)).abs() < 1e-9);
+    }
 }
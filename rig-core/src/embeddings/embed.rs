@@ -4,11 +4,17 @@
 //! The module also defines the [EmbedError] struct which is used for when the [Embed::embed]
 //! method of the [Embed] trait fails.
 //!
-//! The module also defines the [TextEmbedder] struct which accumulates string values that need to be embedded.
+//! The module also defines the [TextEmbedder] struct which accumulates string values (and
+//! [images](ImageInput), for multimodal embedding models) that need to be embedded.
 //! It is used directly with the [Embed] trait.
 //!
 //! Finally, the module implements [Embed] for many common primitive types.
 
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::message::{ImageMediaType, MimeType};
+
 /// Error type used for when the [Embed::embed] method of the [Embed] trait fails.
 /// Used by default implementations of [Embed] for common types.
 #[derive(Debug, thiserror::Error)]
@@ -64,11 +70,12 @@ pub trait Embed {
     fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError>;
 }
 
-/// Accumulates string values that need to be embedded.
+/// Accumulates string values (and images) that need to be embedded.
 /// Used by the [Embed] trait.
 #[derive(Default)]
 pub struct TextEmbedder {
     pub(crate) texts: Vec<String>,
+    pub(crate) images: Vec<ImageInput>,
 }
 
 impl TextEmbedder {
@@ -76,6 +83,47 @@ impl TextEmbedder {
     pub fn embed(&mut self, text: String) {
         self.texts.push(text);
     }
+
+    /// Adds an image to the list of images that need to be embedded. Images can only be
+    /// embedded by multimodal embedding models (see [EmbeddingModel::embed_images](crate::embeddings::EmbeddingModel::embed_images)).
+    pub fn embed_image(&mut self, image: ImageInput) {
+        self.images.push(image);
+    }
+}
+
+/// An image to be embedded by a multimodal embedding model.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ImageInput {
+    /// The raw bytes of the image
+    pub data: Vec<u8>,
+    pub media_type: ImageMediaType,
+}
+
+impl ImageInput {
+    pub fn new(data: impl Into<Vec<u8>>, media_type: ImageMediaType) -> Self {
+        Self {
+            data: data.into(),
+            media_type,
+        }
+    }
+
+    /// The image as a base64 data URL (e.g.: `data:image/png;base64,...`).
+    pub fn data_url(&self) -> String {
+        format!(
+            "data:{};base64,{}",
+            self.media_type.to_mime_type(),
+            BASE64_STANDARD.encode(&self.data)
+        )
+    }
+
+    /// A short description of the image, used as the document of its embedding.
+    pub fn description(&self) -> String {
+        format!(
+            "<{} image, {} bytes>",
+            self.media_type.to_mime_type(),
+            self.data.len()
+        )
+    }
 }
 
 /// Utility function that returns a vector of strings that need to be embedded for a
@@ -174,6 +222,13 @@ impl Embed for serde_json::Value {
     }
 }
 
+impl Embed for ImageInput {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed_image(self.clone());
+        Ok(())
+    }
+}
+
 impl<T: Embed> Embed for &T {
     fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
         (*self).embed(embedder)
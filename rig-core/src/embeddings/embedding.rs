@@ -8,6 +8,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::embed::ImageInput;
+use crate::http::RequestOptions;
+
 #[derive(Debug, thiserror::Error)]
 pub enum EmbeddingError {
     /// Http error (e.g.: connection error, timeout, etc.)
@@ -45,6 +48,25 @@ pub trait EmbeddingModel: Clone + Sync + Send {
         texts: impl IntoIterator<Item = String> + Send,
     ) -> impl std::future::Future<Output = Result<Vec<Embedding>, EmbeddingError>> + Send;
 
+    /// Embed multiple text documents in a single request, with extra HTTP headers and query
+    /// parameters (see [RequestOptions]). Models that do not send HTTP requests return an error
+    /// if `request_options` is not empty.
+    fn embed_texts_with_options(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
+    ) -> impl std::future::Future<Output = Result<Vec<Embedding>, EmbeddingError>> + Send {
+        async move {
+            if request_options.is_empty() {
+                self.embed_texts(texts).await
+            } else {
+                Err(EmbeddingError::ProviderError(
+                    "This embedding model does not support request options".into(),
+                ))
+            }
+        }
+    }
+
     /// Embed a single text document.
     fn embed_text(
         &self,
@@ -58,6 +80,21 @@ pub trait EmbeddingModel: Clone + Sync + Send {
                 .expect("There should be at least one embedding"))
         }
     }
+
+    /// Embed multiple images in a single request. Only supported by multimodal embedding
+    /// models, in the same vector space as their text embeddings (which enables searching
+    /// images with text queries).
+    fn embed_images(
+        &self,
+        images: impl IntoIterator<Item = ImageInput> + Send,
+    ) -> impl std::future::Future<Output = Result<Vec<Embedding>, EmbeddingError>> + Send {
+        let _ = images;
+        async {
+            Err(EmbeddingError::ProviderError(
+                "This embedding model does not support images".into(),
+            ))
+        }
+    }
 }
 
 /// Struct that holds a single document and its embedding.
@@ -69,6 +106,18 @@ pub struct Embedding {
     pub vec: Vec<f64>,
 }
 
+impl Embedding {
+    /// Scale the embedding vector to a unit (L2) norm, after which the dot product of two
+    /// embeddings is their cosine similarity (see the [distance](super::distance) module). Zero
+    /// vectors are left unchanged.
+    pub fn normalize(&mut self) {
+        let norm = super::distance::norm(&self.vec);
+        if norm > 0.0 {
+            self.vec.iter_mut().for_each(|x| *x /= norm);
+        }
+    }
+}
+
 impl PartialEq for Embedding {
     fn eq(&self, other: &Self) -> bool {
         self.document == other.document
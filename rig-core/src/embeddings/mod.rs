@@ -4,12 +4,15 @@
 //! and document similarity.
 
 pub mod builder;
+pub mod contextual;
 pub mod embed;
 pub mod embedding;
+pub mod sparse;
 pub mod tool;
 
 pub mod distance;
 pub use builder::EmbeddingsBuilder;
-pub use embed::{to_texts, Embed, EmbedError, TextEmbedder};
+pub use embed::{to_texts, Embed, EmbedError, ImageInput, TextEmbedder};
 pub use embedding::{Embedding, EmbeddingError, EmbeddingModel};
+pub use sparse::{Bm25, SparseEmbedding, SparseEmbeddingModel};
 pub use tool::ToolSchema;
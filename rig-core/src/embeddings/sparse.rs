@@ -0,0 +1,314 @@
+//! The module defines the [SparseEmbeddingModel] trait, which represents models generating
+//! sparse embeddings (e.g.: SPLADE models served by an API, or BM25 computed locally), and the
+//! [SparseEmbedding] struct, a map of term indices to weights.
+//!
+//! Sparse embeddings capture exact keyword matches that dense embeddings tend to miss. Vector
+//! stores supporting sparse vectors combine both kinds of embeddings in hybrid searches (see
+//! [HybridSearchIndex](crate::vector_store::HybridSearchIndex)).
+//!
+//! The module also provides [Bm25], a sparse embedding model computing BM25 term weights
+//! locally, without any API call.
+//!
+//! # Example
+//! ```rust
+//! use rig::embeddings::{Bm25, SparseEmbeddingModel};
+//!
+//! # async fn run() -> Result<(), rig::embeddings::EmbeddingError> {
+//! let corpus = ["A flurbo is a green alien", "A glarb-glarb is an ancient farming tool"];
+//! let bm25 = Bm25::new().fit(corpus);
+//!
+//! let documents = bm25.embed_sparse_texts(corpus.map(String::from)).await?;
+//! let query = bm25.embed_sparse_query("What is a flurbo?").await?;
+//!
+//! assert!(query.dot(&documents[0]) > query.dot(&documents[1]));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::EmbeddingError;
+
+/// Trait for embedding models generating sparse embeddings.
+pub trait SparseEmbeddingModel: Clone + Sync + Send {
+    /// The maximum number of documents that can be embedded in a single request.
+    const MAX_DOCUMENTS: usize;
+
+    /// Embed multiple text documents in a single request
+    fn embed_sparse_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> impl std::future::Future<Output = Result<Vec<SparseEmbedding>, EmbeddingError>> + Send;
+
+    /// Embed a single text document.
+    fn embed_sparse_text(
+        &self,
+        text: &str,
+    ) -> impl std::future::Future<Output = Result<SparseEmbedding, EmbeddingError>> + Send {
+        async {
+            Ok(self
+                .embed_sparse_texts(vec![text.to_string()])
+                .await?
+                .pop()
+                .expect("There should be at least one embedding"))
+        }
+    }
+
+    /// Embed a search query. Models weighting queries differently from documents (e.g.: BM25)
+    /// override this method, the default embeds the query as a document.
+    fn embed_sparse_query(
+        &self,
+        query: &str,
+    ) -> impl std::future::Future<Output = Result<SparseEmbedding, EmbeddingError>> + Send {
+        self.embed_sparse_text(query)
+    }
+}
+
+/// Struct that holds a single document and its sparse embedding.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq)]
+pub struct SparseEmbedding {
+    /// The document that was embedded. Used for debugging.
+    pub document: String,
+    /// The weights of the terms of the document, keyed by term index
+    pub weights: BTreeMap<u32, f64>,
+}
+
+impl SparseEmbedding {
+    /// Get the term indices, in increasing order
+    pub fn indices(&self) -> Vec<u32> {
+        self.weights.keys().copied().collect()
+    }
+
+    /// Get the term weights, in the order of [SparseEmbedding::indices]
+    pub fn values(&self) -> Vec<f64> {
+        self.weights.values().copied().collect()
+    }
+
+    /// Get the dot product of two sparse embeddings, i.e. the relevance score of a document
+    /// for a query.
+    pub fn dot(&self, other: &Self) -> f64 {
+        let (small, large) = if self.weights.len() <= other.weights.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        small
+            .weights
+            .iter()
+            .filter_map(|(index, weight)| large.weights.get(index).map(|other| weight * other))
+            .sum()
+    }
+}
+
+/// Get the index of a term in sparse embeddings: the 32-bit FNV-1a hash of the term.
+/// The hash is stable, so embeddings stored in a vector store remain valid across releases.
+pub fn term_index(term: &str) -> u32 {
+    term.bytes().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+/// Split a text into lowercase alphanumeric terms.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+}
+
+/// Sparse embedding model computing [BM25](https://en.wikipedia.org/wiki/Okapi_BM25) term
+/// weights locally.
+///
+/// Document embeddings hold the BM25 weight of each term, query embeddings a weight of 1 for
+/// each term, so that the dot product of the two is the BM25 score of the document.
+///
+/// The inverse document frequencies (IDF) of the terms are only known once the model is
+/// [fitted](Bm25::fit) on the corpus. An unfitted model leaves them out, for vector stores
+/// computing them server side (e.g.: Qdrant sparse vectors with the `idf` modifier).
+#[derive(Clone, Debug)]
+pub struct Bm25 {
+    k1: f64,
+    b: f64,
+    avg_doc_len: f64,
+    /// Inverse document frequencies of the terms and of unseen terms, set by [Bm25::fit]
+    idf: Option<Arc<(HashMap<u32, f64>, f64)>>,
+}
+
+impl Default for Bm25 {
+    fn default() -> Self {
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            avg_doc_len: 256.0,
+            idf: None,
+        }
+    }
+}
+
+impl Bm25 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the term frequency saturation parameter (defaults to 1.2)
+    pub fn k1(mut self, k1: f64) -> Self {
+        self.k1 = k1;
+        self
+    }
+
+    /// Set the document length normalization parameter, between 0 and 1 (defaults to 0.75)
+    pub fn b(mut self, b: f64) -> Self {
+        self.b = b;
+        self
+    }
+
+    /// Set the average length of the documents, in terms (defaults to 256). Set by [Bm25::fit].
+    pub fn avg_doc_len(mut self, avg_doc_len: f64) -> Self {
+        self.avg_doc_len = avg_doc_len;
+        self
+    }
+
+    /// Compute the average document length and the inverse document frequencies of the
+    /// terms of the corpus.
+    pub fn fit<I>(mut self, corpus: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut doc_count = 0usize;
+        let mut total_len = 0usize;
+        let mut doc_freqs = HashMap::<u32, usize>::new();
+
+        for doc in corpus {
+            let terms = tokenize(doc.as_ref())
+                .map(|term| term_index(&term))
+                .collect::<Vec<_>>();
+            doc_count += 1;
+            total_len += terms.len();
+            for index in terms.into_iter().collect::<HashSet<_>>() {
+                *doc_freqs.entry(index).or_default() += 1;
+            }
+        }
+
+        let n = doc_count as f64;
+        let idf = |df: usize| ((n - df as f64 + 0.5) / (df as f64 + 0.5) + 1.0).ln();
+
+        if doc_count > 0 {
+            self.avg_doc_len = (total_len as f64 / n).max(1.0);
+        }
+        self.idf = Some(Arc::new((
+            doc_freqs
+                .into_iter()
+                .map(|(index, df)| (index, idf(df)))
+                .collect(),
+            idf(0),
+        )));
+        self
+    }
+
+    fn idf(&self, index: u32) -> f64 {
+        match &self.idf {
+            Some(idf) => idf.0.get(&index).copied().unwrap_or(idf.1),
+            None => 1.0,
+        }
+    }
+
+    /// Compute the BM25 weights of the terms of a document.
+    pub fn document_weights(&self, text: &str) -> SparseEmbedding {
+        let mut term_freqs = BTreeMap::<u32, f64>::new();
+        let mut doc_len = 0.0;
+        for term in tokenize(text) {
+            *term_freqs.entry(term_index(&term)).or_default() += 1.0;
+            doc_len += 1.0;
+        }
+
+        let norm = self.k1 * (1.0 - self.b + self.b * doc_len / self.avg_doc_len);
+        SparseEmbedding {
+            document: text.to_string(),
+            weights: term_freqs
+                .into_iter()
+                .map(|(index, tf)| (index, self.idf(index) * tf * (self.k1 + 1.0) / (tf + norm)))
+                .collect(),
+        }
+    }
+
+    /// Compute the weights of the terms of a query.
+    pub fn query_weights(&self, query: &str) -> SparseEmbedding {
+        SparseEmbedding {
+            document: query.to_string(),
+            weights: tokenize(query)
+                .map(|term| (term_index(&term), 1.0))
+                .collect(),
+        }
+    }
+}
+
+impl SparseEmbeddingModel for Bm25 {
+    const MAX_DOCUMENTS: usize = 1024;
+
+    async fn embed_sparse_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<SparseEmbedding>, EmbeddingError> {
+        Ok(texts
+            .into_iter()
+            .map(|text| self.document_weights(&text))
+            .collect())
+    }
+
+    async fn embed_sparse_query(&self, query: &str) -> Result<SparseEmbedding, EmbeddingError> {
+        Ok(self.query_weights(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_index() {
+        // Reference values of the 32-bit FNV-1a hash
+        assert_eq!(term_index(""), 0x811c9dc5);
+        assert_eq!(term_index("a"), 0xe40c292c);
+        assert_eq!(term_index("foobar"), 0xbf9cf968);
+    }
+
+    #[tokio::test]
+    async fn test_bm25() {
+        let corpus = [
+            "The flurbo is a green alien, a flurbo lives on cold planets",
+            "A glarb-glarb is an ancient tool used to farm the land",
+            "The planet Jiro is cold",
+        ];
+        let bm25 = Bm25::new().fit(corpus);
+
+        let documents = bm25
+            .embed_sparse_texts(corpus.map(String::from))
+            .await
+            .unwrap();
+        let query = bm25.embed_sparse_query("Flurbo?").await.unwrap();
+        assert_eq!(query.indices(), vec![term_index("flurbo")]);
+        assert_eq!(query.values(), vec![1.0]);
+
+        let scores = documents
+            .iter()
+            .map(|document| query.dot(document))
+            .collect::<Vec<_>>();
+        assert!(scores[0] > 0.0);
+        assert_eq!(scores[1], 0.0);
+        assert_eq!(scores[2], 0.0);
+
+        // Rare terms weigh more than common ones
+        let query = bm25.query_weights("cold glarb");
+        assert!(query.dot(&documents[1]) > query.dot(&documents[2]));
+
+        // Without IDF, the weight only depends on the term frequency and document length
+        let weights = Bm25::new().avg_doc_len(4.0).document_weights("a b b c");
+        assert_eq!(weights.weights.len(), 3);
+        assert!((weights.weights[&term_index("a")] - 1.0).abs() < 1e-9);
+        assert!(weights.weights[&term_index("b")] > weights.weights[&term_index("a")]);
+    }
+}
@@ -0,0 +1,360 @@
+//! This module provides [EmbeddingBenchmark], to choose an embedding model and a chunking
+//! empirically: a labeled corpus is indexed with each combination of the embedding models and
+//! chunk sizes, and the [retrieval metrics](super::rag::RetrievalMetrics) of the indexes are
+//! reported side-by-side with the number of embedded tokens and their cost.
+//!
+//! The tokens (of the chunks and of the questions) are estimated with the best counter available
+//! for each model (see [tokens::counter_for_model]), so the costs are estimates too.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     eval::{benchmark::EmbeddingBenchmark, EvalDataset},
+//!     metadata::Document,
+//!     providers::{cohere, openai},
+//! };
+//!
+//! # async fn run(corpus: Vec<Document>, dataset: EvalDataset) -> Result<(), rig::eval::EvalError> {
+//! let openai = openai::Client::from_env();
+//! let cohere = cohere::Client::from_env();
+//!
+//! let report = EmbeddingBenchmark::new(corpus, dataset)
+//!     .model_with_price(
+//!         openai::TEXT_EMBEDDING_3_SMALL,
+//!         openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+//!         0.02,
+//!     )
+//!     .model_with_price(
+//!         openai::TEXT_EMBEDDING_3_LARGE,
+//!         openai.embedding_model(openai::TEXT_EMBEDDING_3_LARGE),
+//!         0.13,
+//!     )
+//!     .model(
+//!         cohere::EMBED_ENGLISH_V3,
+//!         cohere.embedding_model(cohere::EMBED_ENGLISH_V3, "search_document"),
+//!     )
+//!     .chunking(500, 50)
+//!     .chunking(1000, 100)
+//!     .top_k(5)
+//!     .run()
+//!     .await?;
+//!
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+
+use super::{
+    rag::{evaluate_retrieval, RetrievalReport},
+    EvalDataset, EvalError,
+};
+use crate::{
+    embeddings::{EmbeddingModel, EmbeddingsBuilder},
+    metadata::Document,
+    tokens::{self, TokenCounter},
+    vector_store::in_memory_store::InMemoryVectorStore,
+};
+
+type Run = Box<
+    dyn Fn(
+            Vec<Document>,
+            Arc<EvalDataset>,
+            usize,
+        ) -> BoxFuture<'static, Result<RetrievalReport, EvalError>>
+        + Send
+        + Sync,
+>;
+
+struct BenchmarkModel {
+    name: String,
+    price_per_million: Option<f64>,
+    counter: Arc<dyn TokenCounter>,
+    run: Run,
+}
+
+/// A benchmark of embedding models and chunkings, see the [module documentation](self).
+pub struct EmbeddingBenchmark {
+    corpus: Vec<Document>,
+    dataset: Arc<EvalDataset>,
+    models: Vec<BenchmarkModel>,
+    chunkings: Vec<(usize, usize)>,
+    top_k: usize,
+}
+
+impl EmbeddingBenchmark {
+    /// Benchmark the retrieval of the documents of the corpus, for the questions of the dataset.
+    pub fn new(corpus: impl IntoIterator<Item = Document>, dataset: EvalDataset) -> Self {
+        Self {
+            corpus: corpus.into_iter().collect(),
+            dataset: Arc::new(dataset),
+            models: vec![],
+            chunkings: vec![],
+            top_k: 4,
+        }
+    }
+
+    /// Add an embedding model. Its name is also used to pick its token counter.
+    pub fn model<E: EmbeddingModel + 'static>(self, name: &str, model: E) -> Self {
+        self.add_model(name, model, None)
+    }
+
+    /// Add an embedding model, priced in USD per million tokens.
+    pub fn model_with_price<E: EmbeddingModel + 'static>(
+        self,
+        name: &str,
+        model: E,
+        price_per_million: f64,
+    ) -> Self {
+        self.add_model(name, model, Some(price_per_million))
+    }
+
+    fn add_model<E: EmbeddingModel + 'static>(
+        mut self,
+        name: &str,
+        model: E,
+        price_per_million: Option<f64>,
+    ) -> Self {
+        self.models.push(BenchmarkModel {
+            name: name.to_string(),
+            price_per_million,
+            counter: tokens::counter_for_model(name),
+            run: Box::new(move |chunks, dataset, k| {
+                let model = model.clone();
+                async move {
+                    let embeddings = EmbeddingsBuilder::new(model.clone())
+                        .documents(chunks)?
+                        .build()
+                        .await?;
+                    let index =
+                        InMemoryVectorStore::from_documents_with_id_f(embeddings, |chunk| {
+                            chunk.metadata.id.clone()
+                        })
+                        .index(model);
+                    evaluate_retrieval(&index, &dataset, k).await
+                }
+                .boxed()
+            }),
+        });
+        self
+    }
+
+    /// Add a chunking: chunks of `size` characters, overlapping by `overlap` characters (see
+    /// [Document::chunks]). Without chunkings, the chunks of the [Rag](crate::rag::Rag)
+    /// preset are benchmarked (1000 and 100).
+    pub fn chunking(mut self, size: usize, overlap: usize) -> Self {
+        self.chunkings.push((size, overlap));
+        self
+    }
+
+    /// Set the number of chunks retrieved for each question (4 by default).
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Index the corpus and evaluate the retrieval with each model and chunking, one after the
+    /// other.
+    pub async fn run(&self) -> Result<BenchmarkReport, EvalError> {
+        let chunkings = if self.chunkings.is_empty() {
+            vec![(1000, 100)]
+        } else {
+            self.chunkings.clone()
+        };
+
+        let mut runs = vec![];
+        for (chunk_size, chunk_overlap) in chunkings {
+            let chunks = self
+                .corpus
+                .iter()
+                .flat_map(|document| document.chunks(chunk_size, chunk_overlap))
+                .collect::<Vec<_>>();
+
+            for model in &self.models {
+                let tokens = chunks
+                    .iter()
+                    .map(|chunk| model.counter.count_tokens(&chunk.text))
+                    .chain(
+                        self.dataset
+                            .examples
+                            .iter()
+                            .map(|example| model.counter.count_tokens(&example.question)),
+                    )
+                    .sum::<usize>();
+
+                tracing::info!(target: "rig",
+                    "Benchmarking {} with chunks of {chunk_size} characters", model.name
+                );
+                let started = Instant::now();
+                let retrieval =
+                    (model.run)(chunks.clone(), self.dataset.clone(), self.top_k).await?;
+
+                runs.push(BenchmarkRun {
+                    model: model.name.clone(),
+                    chunk_size,
+                    chunk_overlap,
+                    chunks: chunks.len(),
+                    tokens,
+                    cost: model
+                        .price_per_million
+                        .map(|price| tokens as f64 * price / 1_000_000.0),
+                    duration: started.elapsed(),
+                    retrieval,
+                });
+            }
+        }
+
+        Ok(BenchmarkReport { runs })
+    }
+}
+
+/// The results of a model with a chunking.
+#[derive(Clone, Debug)]
+pub struct BenchmarkRun {
+    pub model: String,
+    pub chunk_size: usize,
+    pub chunk_overlap: usize,
+    /// Number of indexed chunks
+    pub chunks: usize,
+    /// Estimated number of embedded tokens (chunks and questions)
+    pub tokens: usize,
+    /// Estimated cost of the embeddings in USD, if the model is priced
+    pub cost: Option<f64>,
+    /// Duration of the indexing and retrieval
+    pub duration: Duration,
+    pub retrieval: RetrievalReport,
+}
+
+/// The results of an [EmbeddingBenchmark], displayed as a table.
+#[derive(Clone, Debug)]
+pub struct BenchmarkReport {
+    /// The runs, by chunking then by model
+    pub runs: Vec<BenchmarkRun>,
+}
+
+impl BenchmarkReport {
+    /// The run with the best MRR, ties broken by recall.
+    pub fn best(&self) -> Option<&BenchmarkRun> {
+        self.runs.iter().max_by(|a, b| {
+            let (a, b) = (a.retrieval.metrics, b.retrieval.metrics);
+            a.mrr.total_cmp(&b.mrr).then(a.recall.total_cmp(&b.recall))
+        })
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .runs
+            .iter()
+            .map(|run| run.model.len())
+            .chain([5])
+            .max()
+            .unwrap_or_default();
+        let k = self
+            .runs
+            .first()
+            .map(|run| run.retrieval.k)
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:width$}  {:>11}  {:>6}  {:>9}  {:>12}  {:>6}  {:>8}  {:>9}  {:>10}  {:>8}",
+            "model",
+            "chunk",
+            "chunks",
+            format!("recall@{k}"),
+            format!("precision@{k}"),
+            "MRR",
+            "hit rate",
+            "tokens",
+            "cost (USD)",
+            "time (s)",
+        )?;
+        for run in &self.runs {
+            let metrics = run.retrieval.metrics;
+            writeln!(
+                f,
+                "{:width$}  {:>11}  {:>6}  {:>9.3}  {:>12.3}  {:>6.3}  {:>8.3}  {:>9}  {:>10}  {:>8.1}",
+                run.model,
+                format!("{}/{}", run.chunk_size, run.chunk_overlap),
+                run.chunks,
+                metrics.recall,
+                metrics.precision,
+                metrics.mrr,
+                metrics.hit_rate,
+                run.tokens,
+                run.cost
+                    .map(|cost| format!("{cost:.4}"))
+                    .unwrap_or_else(|| "-".to_string()),
+                run.duration.as_secs_f64(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval::EvalExample, metadata::DocumentMetadata, test_utils::MockEmbeddingModel};
+
+    #[tokio::test]
+    async fn test_embedding_benchmark() {
+        let corpus = [("alpha", "aaaa aaaa"), ("bravo", "bbbb bbbb")]
+            .map(|(id, text)| Document::new(DocumentMetadata::new(id), text));
+        let dataset = EvalDataset::new(vec![
+            EvalExample::new("aa?").relevant(["alpha"]),
+            EvalExample::new("bb?").relevant(["bravo"]),
+        ]);
+
+        let report = EmbeddingBenchmark::new(corpus, dataset)
+            .model_with_price(
+                "letters",
+                MockEmbeddingModel::counting(&["a", "b"]),
+                1_000_000.0,
+            )
+            .model("blind", MockEmbeddingModel::constant(vec![1.0, 1.0]))
+            .chunking(5, 0)
+            .chunking(100, 0)
+            .top_k(1)
+            .run()
+            .await
+            .unwrap();
+
+        let runs = report
+            .runs
+            .iter()
+            .map(|run| {
+                (
+                    run.model.as_str(),
+                    run.chunk_size,
+                    run.chunks,
+                    run.tokens,
+                    run.cost,
+                )
+            })
+            .collect::<Vec<_>>();
+        // 1 token per chunk of 4 letters and per question, 3 per document
+        assert_eq!(
+            runs,
+            [
+                ("letters", 5, 4, 6, Some(6.0)),
+                ("blind", 5, 4, 6, None),
+                ("letters", 100, 2, 8, Some(8.0)),
+                ("blind", 100, 2, 8, None),
+            ]
+        );
+        assert_eq!(report.runs[0].retrieval.metrics.recall, 1.0);
+        assert!(report.runs[1].retrieval.metrics.recall < 1.0);
+
+        assert_eq!(report.best().unwrap().model, "letters");
+        assert_eq!(report.to_string().lines().count(), 5);
+    }
+}
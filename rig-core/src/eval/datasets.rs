@@ -0,0 +1,387 @@
+//! This module provides the loading of [EvalDataset]s from the usual formats of benchmark files,
+//! so that existing datasets can be evaluated without conversion scripts:
+//! - JSONL: one JSON object per line,
+//! - CSV (feature `csv`): one row per example, with a header row,
+//! - parquet (feature `parquet`), e.g.: the files of HuggingFace datasets.
+//!
+//! A [ColumnMapping] maps the columns (or fields) of the files to the fields of the
+//! [EvalExample]s. The relevant ids can be lists (JSON arrays, or parquet lists), or strings
+//! holding a JSON array or separated ids (e.g.: `"holidays.md,expenses.md"`).
+//!
+//! # Example
+//! ```rust
+//! use rig::eval::{datasets::ColumnMapping, EvalDataset};
+//!
+//! # fn run() -> Result<(), rig::eval::datasets::DatasetError> {
+//! // {"query": "...", "context_ids": ["..."], "answer": "..."}
+//! let dataset = EvalDataset::from_jsonl(
+//!     "benchmark.jsonl",
+//!     &ColumnMapping::new("query")
+//!         .relevant_ids("context_ids")
+//!         .reference_answer("answer"),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+
+use super::{EvalDataset, EvalExample};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DatasetError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[cfg(feature = "csv")]
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    ParquetError(#[from] parquet::errors::ParquetError),
+
+    /// The row (numbered from 1) is invalid, e.g.: it lacks the question
+    #[error("Invalid row {row} of {path}: {reason}")]
+    InvalidRow {
+        path: PathBuf,
+        row: usize,
+        reason: String,
+    },
+}
+
+/// The columns of a dataset file holding the fields of the [EvalExample]s.
+///
+/// The question column is required. The relevant ids and reference answer columns are optional:
+/// the examples have no relevant ids (resp. reference answer) when they are missing or null.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnMapping {
+    question: String,
+    relevant_ids: String,
+    reference_answer: String,
+    separator: char,
+}
+
+impl Default for ColumnMapping {
+    /// The columns `question`, `relevant_ids` and `reference_answer`.
+    fn default() -> Self {
+        Self::new("question")
+    }
+}
+
+impl ColumnMapping {
+    /// Map the question to the given column, and the other fields to the `relevant_ids` and
+    /// `reference_answer` columns.
+    pub fn new(question: &str) -> Self {
+        Self {
+            question: question.to_string(),
+            relevant_ids: "relevant_ids".to_string(),
+            reference_answer: "reference_answer".to_string(),
+            separator: ',',
+        }
+    }
+
+    pub fn relevant_ids(mut self, column: &str) -> Self {
+        self.relevant_ids = column.to_string();
+        self
+    }
+
+    pub fn reference_answer(mut self, column: &str) -> Self {
+        self.reference_answer = column.to_string();
+        self
+    }
+
+    /// Set the separator of the relevant ids stored as strings (`,` by default).
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Convert a row of a dataset to an example, or return why the row is invalid.
+    fn example(&self, row: &Map<String, Value>) -> Result<EvalExample, String> {
+        let question = match row.get(&self.question) {
+            Some(Value::Null) | None => {
+                return Err(format!("missing question column `{}`", self.question))
+            }
+            Some(value) => scalar(value)
+                .ok_or_else(|| format!("question column `{}` is not a string", self.question))?,
+        };
+
+        let relevant_ids = match row.get(&self.relevant_ids) {
+            Some(Value::Null) | None => vec![],
+            Some(Value::Array(ids)) => ids.iter().filter_map(scalar).collect(),
+            Some(value) => {
+                let ids = scalar(value).ok_or_else(|| {
+                    format!("relevant ids column `{}` is not a list", self.relevant_ids)
+                })?;
+                match serde_json::from_str::<Vec<Value>>(&ids) {
+                    Ok(ids) => ids.iter().filter_map(scalar).collect(),
+                    Err(_) => ids
+                        .split(self.separator)
+                        .map(str::trim)
+                        .filter(|id| !id.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                }
+            }
+        };
+
+        let reference_answer = match row.get(&self.reference_answer) {
+            Some(Value::Null) | None => None,
+            Some(value) => Some(scalar(value).ok_or_else(|| {
+                format!(
+                    "reference answer column `{}` is not a string",
+                    self.reference_answer
+                )
+            })?),
+        };
+
+        Ok(EvalExample {
+            question,
+            relevant_ids,
+            reference_answer,
+        })
+    }
+
+    fn examples(
+        &self,
+        path: &Path,
+        rows: impl IntoIterator<Item = Result<Map<String, Value>, DatasetError>>,
+    ) -> Result<EvalDataset, DatasetError> {
+        let examples = rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                self.example(&row?)
+                    .map_err(|reason| DatasetError::InvalidRow {
+                        path: path.to_path_buf(),
+                        row: i + 1,
+                        reason,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(EvalDataset::new(examples))
+    }
+}
+
+/// The string of a string or number value.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+impl EvalDataset {
+    /// Load a dataset from a JSONL file, with one JSON object per (non-empty) line.
+    pub fn from_jsonl(
+        path: impl AsRef<Path>,
+        mapping: &ColumnMapping,
+    ) -> Result<Self, DatasetError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        mapping.examples(
+            path,
+            content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| Ok(serde_json::from_str(line)?)),
+        )
+    }
+
+    /// Load a dataset from a CSV file with a header row.
+    #[cfg(feature = "csv")]
+    pub fn from_csv(path: impl AsRef<Path>, mapping: &ColumnMapping) -> Result<Self, DatasetError> {
+        let path = path.as_ref();
+        let mut reader = csv::Reader::from_path(path)?;
+        let headers = reader.headers()?.clone();
+
+        mapping.examples(
+            path,
+            reader.records().map(|record| {
+                Ok(headers
+                    .iter()
+                    .zip(record?.iter())
+                    .map(|(header, field)| (header.to_string(), Value::String(field.to_string())))
+                    .collect())
+            }),
+        )
+    }
+
+    /// Load a dataset from a parquet file, e.g.: a split of a HuggingFace dataset.
+    #[cfg(feature = "parquet")]
+    pub fn from_parquet(
+        path: impl AsRef<Path>,
+        mapping: &ColumnMapping,
+    ) -> Result<Self, DatasetError> {
+        use parquet::file::{reader::FileReader, serialized_reader::SerializedFileReader};
+
+        let path = path.as_ref();
+        let reader = SerializedFileReader::new(fs::File::open(path)?)?;
+
+        mapping.examples(
+            path,
+            reader
+                .get_row_iter(None)?
+                .map(|row| match row?.to_json_value() {
+                    Value::Object(row) => Ok(row),
+                    _ => Ok(Map::new()),
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+
+    use super::*;
+
+    #[test]
+    fn test_jsonl_csv_datasets() {
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let jsonl = temp.child("benchmark.jsonl");
+        jsonl
+            .write_str(
+                r#"{"query": "How many days of holidays?", "context_ids": ["holidays.md"], "answer": "25"}
+
+{"query": "Who approves the expenses?", "context_ids": "[\"expenses.md\", 7]", "answer": null}
+{"query": "When?"}
+"#,
+            )
+            .unwrap();
+        let mapping = ColumnMapping::new("query")
+            .relevant_ids("context_ids")
+            .reference_answer("answer");
+
+        let expected = EvalDataset::new(vec![
+            EvalExample::new("How many days of holidays?")
+                .relevant(["holidays.md"])
+                .reference_answer("25"),
+            EvalExample::new("Who approves the expenses?").relevant(["expenses.md", "7"]),
+            EvalExample::new("When?"),
+        ]);
+        assert_eq!(
+            EvalDataset::from_jsonl(jsonl.path(), &mapping).unwrap(),
+            expected
+        );
+
+        // Rows without questions are invalid
+        let invalid = temp.child("invalid.jsonl");
+        invalid.write_str("{\"query\": \"When?\"}\n{}\n").unwrap();
+        assert!(matches!(
+            EvalDataset::from_jsonl(invalid.path(), &mapping),
+            Err(DatasetError::InvalidRow { row: 2, .. })
+        ));
+
+        #[cfg(feature = "csv")]
+        {
+            let csv = temp.child("benchmark.csv");
+            csv.write_str(
+                "question,relevant_ids,reference_answer\n\
+                 \"How many days of holidays?\",holidays.md,25\n\
+                 Who approves the expenses?,expenses.md; managers.md,\n",
+            )
+            .unwrap();
+
+            assert_eq!(
+                EvalDataset::from_csv(csv.path(), &ColumnMapping::default().separator(';'))
+                    .unwrap(),
+                EvalDataset::new(vec![
+                    EvalExample::new("How many days of holidays?")
+                        .relevant(["holidays.md"])
+                        .reference_answer("25"),
+                    EvalExample::new("Who approves the expenses?")
+                        .relevant(["expenses.md", "managers.md"])
+                        .reference_answer(""),
+                ])
+            );
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_dataset() {
+        use std::sync::Arc;
+
+        use parquet::{
+            data_type::{ByteArray, ByteArrayType},
+            file::{properties::WriterProperties, writer::SerializedFileWriter},
+            schema::parser::parse_message_type,
+        };
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let path = temp.child("train.parquet");
+
+        // A string column, and a list column as written by HuggingFace datasets
+        let schema = parse_message_type(
+            "message schema {
+                REQUIRED BYTE_ARRAY query (UTF8);
+                OPTIONAL group context_ids (LIST) {
+                    REPEATED group list {
+                        OPTIONAL BYTE_ARRAY element (UTF8);
+                    }
+                }
+            }",
+        )
+        .unwrap();
+        let mut writer = SerializedFileWriter::new(
+            fs::File::create(path.path()).unwrap(),
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[ByteArray::from("Who?"), ByteArray::from("When?")],
+                None,
+                None,
+            )
+            .unwrap();
+        column.close().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[
+                    ByteArray::from("managers.md"),
+                    ByteArray::from("expenses.md"),
+                ],
+                Some(&[3, 3, 1]),
+                Some(&[0, 1, 0]),
+            )
+            .unwrap();
+        column.close().unwrap();
+
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        assert_eq!(
+            EvalDataset::from_parquet(
+                path.path(),
+                &ColumnMapping::new("query").relevant_ids("context_ids")
+            )
+            .unwrap(),
+            EvalDataset::new(vec![
+                EvalExample::new("Who?").relevant(["managers.md", "expenses.md"]),
+                EvalExample::new("When?"),
+            ])
+        );
+    }
+}
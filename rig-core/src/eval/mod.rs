@@ -0,0 +1,130 @@
+//! This module provides an evaluation harness for RAG pipelines: datasets of labeled questions
+//! ([EvalDataset], which can be loaded from benchmark files, see [datasets]) and the metrics of
+//! their retrieval and generation stages (see [rag]), e.g.: to compare embedding models and
+//! chunkings (see [benchmark]).
+//!
+//! An [EvalExample] is a question labeled with the ids of its relevant documents and, optionally,
+//! a reference answer. The relevant ids are the ids of the source documents: chunks (whose ids
+//! are `<document id>#<chunk index>`, see [Document::chunks](crate::metadata::Document::chunks))
+//! are relevant if their document is.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     eval::{rag::evaluate_retrieval, EvalDataset, EvalExample},
+//!     providers::openai,
+//!     vector_store::in_memory_store::InMemoryVectorIndex,
+//! };
+//!
+//! # async fn run(index: InMemoryVectorIndex<openai::EmbeddingModel, String>) -> Result<(), rig::eval::EvalError> {
+//! let dataset = EvalDataset::new(vec![
+//!     EvalExample::new("How many days of holidays do employees get?").relevant(["holidays.md"]),
+//!     EvalExample::new("Who approves the expenses?").relevant(["expenses.md", "managers.md"]),
+//! ]);
+//!
+//! let report = evaluate_retrieval(&index, &dataset, 5).await?;
+//! println!("recall@5: {:.2}, MRR: {:.2}", report.metrics.recall, report.metrics.mrr);
+//! # Ok(())
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::{CompletionError, PromptError},
+    embeddings::{EmbedError, EmbeddingError},
+    extractor::ExtractionError,
+    vector_store::VectorStoreError,
+};
+
+pub mod benchmark;
+pub mod datasets;
+pub mod rag;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("Vector store error: {0}")]
+    VectorStoreError(#[from] VectorStoreError),
+
+    #[error("Embed error: {0}")]
+    EmbedError(#[from] EmbedError),
+
+    #[error("Embedding error: {0}")]
+    EmbeddingError(#[from] EmbeddingError),
+
+    #[error("Completion error: {0}")]
+    CompletionError(#[from] CompletionError),
+
+    #[error("Prompt error: {0}")]
+    PromptError(#[from] PromptError),
+
+    /// Error of the judge model
+    #[error("Judge error: {0}")]
+    JudgeError(#[from] ExtractionError),
+}
+
+/// A question labeled with its relevant documents and, optionally, its reference answer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvalExample {
+    pub question: String,
+    /// Ids of the documents relevant to the question
+    #[serde(default)]
+    pub relevant_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_answer: Option<String>,
+}
+
+impl EvalExample {
+    pub fn new(question: &str) -> Self {
+        Self {
+            question: question.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add ids of relevant documents.
+    pub fn relevant<S: AsRef<str>>(mut self, ids: impl IntoIterator<Item = S>) -> Self {
+        self.relevant_ids
+            .extend(ids.into_iter().map(|id| id.as_ref().to_string()));
+        self
+    }
+
+    pub fn reference_answer(mut self, answer: &str) -> Self {
+        self.reference_answer = Some(answer.to_string());
+        self
+    }
+
+    /// Whether the retrieved document (or chunk) is relevant to the question.
+    pub fn is_relevant(&self, id: &str) -> bool {
+        self.relevant_ids
+            .iter()
+            .any(|relevant_id| same_document(relevant_id, id))
+    }
+}
+
+/// Whether the retrieved id is the relevant id, or the id of one of its chunks.
+pub(crate) fn same_document(relevant_id: &str, id: &str) -> bool {
+    relevant_id == id
+        || id
+            .rsplit_once('#')
+            .is_some_and(|(document_id, _)| document_id == relevant_id)
+}
+
+/// A dataset of [EvalExample]s.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvalDataset {
+    pub examples: Vec<EvalExample>,
+}
+
+impl EvalDataset {
+    pub fn new(examples: Vec<EvalExample>) -> Self {
+        Self { examples }
+    }
+
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+}
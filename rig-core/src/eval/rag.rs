@@ -0,0 +1,403 @@
+//! The metrics of the retrieval and generation stages of RAG pipelines, over [EvalDataset]s.
+//!
+//! Retrieval metrics, computed from the ids of the retrieved documents (at most `k` per question)
+//! and the labels of the questions ([RetrievalMetrics]):
+//! - recall@k: the share of the relevant documents which are retrieved,
+//! - precision@k: the share of the retrieved documents which are relevant,
+//! - MRR: the reciprocal of the rank of the first relevant document,
+//! - hit rate: whether a relevant document is retrieved.
+//!
+//! Generation metrics, judged by a model ([GenerationMetrics]):
+//! - faithfulness: the share of the claims of the answer supported by the retrieved documents,
+//! - answer relevancy: how well the answer addresses the question.
+//!
+//! [evaluate_retrieval] evaluates an index alone, while [RagEvaluator::evaluate] evaluates both
+//! stages of an agent, from the documents of its requests and its answers, so that a regression
+//! can be attributed to the retriever or to the generator.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     eval::{rag::RagEvaluator, EvalDataset, EvalExample},
+//!     providers::openai,
+//! };
+//!
+//! # async fn run(agent: rig::agent::Agent<openai::CompletionModel>) -> Result<(), rig::eval::EvalError> {
+//! let openai = openai::Client::from_env();
+//! let dataset = EvalDataset::new(vec![
+//!     EvalExample::new("How many days of holidays do employees get?").relevant(["holidays.md"]),
+//! ]);
+//!
+//! let report = RagEvaluator::new(openai.completion_model(openai::GPT_4O))
+//!     .evaluate(&agent, &dataset)
+//!     .await?;
+//! println!("retrieval: {:?}", report.retrieval);
+//! println!("generation: {:?}", report.generation);
+//! # Ok(())
+//! # }
+//! ```
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::{same_document, EvalDataset, EvalError, EvalExample};
+use crate::{
+    agent::Agent,
+    completion::{Completion, CompletionModel},
+    extractor::{Extractor, ExtractorBuilder},
+    metadata::Document,
+    race,
+    vector_store::VectorStoreIndex,
+};
+
+/// The retrieval metrics of a question, or their means over a dataset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RetrievalMetrics {
+    /// Share of the relevant documents which are retrieved (recall@k)
+    pub recall: f64,
+    /// Share of the retrieved documents which are relevant (precision@k)
+    pub precision: f64,
+    /// Reciprocal of the rank of the first relevant document, 0 if none (MRR once averaged)
+    pub mrr: f64,
+    /// 1 if a relevant document is retrieved, 0 otherwise (hit rate once averaged)
+    pub hit_rate: f64,
+}
+
+impl RetrievalMetrics {
+    /// The metrics of the documents retrieved for the question, in order of relevance.
+    pub fn compute(example: &EvalExample, retrieved_ids: &[String]) -> Self {
+        if example.relevant_ids.is_empty() || retrieved_ids.is_empty() {
+            return Self::default();
+        }
+
+        let relevant = retrieved_ids
+            .iter()
+            .map(|id| example.is_relevant(id))
+            .collect::<Vec<_>>();
+        // Several chunks of a relevant document count once for the recall
+        let found = example
+            .relevant_ids
+            .iter()
+            .filter(|relevant_id| {
+                retrieved_ids
+                    .iter()
+                    .any(|id| same_document(relevant_id, id))
+            })
+            .count();
+
+        Self {
+            recall: found as f64 / example.relevant_ids.len() as f64,
+            precision: relevant.iter().filter(|relevant| **relevant).count() as f64
+                / retrieved_ids.len() as f64,
+            mrr: relevant
+                .iter()
+                .position(|relevant| *relevant)
+                .map_or(0.0, |rank| 1.0 / (rank + 1) as f64),
+            hit_rate: if found > 0 { 1.0 } else { 0.0 },
+        }
+    }
+
+    fn mean(metrics: &[Self]) -> Self {
+        if metrics.is_empty() {
+            return Self::default();
+        }
+        let n = metrics.len() as f64;
+        Self {
+            recall: metrics.iter().map(|m| m.recall).sum::<f64>() / n,
+            precision: metrics.iter().map(|m| m.precision).sum::<f64>() / n,
+            mrr: metrics.iter().map(|m| m.mrr).sum::<f64>() / n,
+            hit_rate: metrics.iter().map(|m| m.hit_rate).sum::<f64>() / n,
+        }
+    }
+}
+
+/// The generation metrics of an answer (or their means over a dataset), between 0 and 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GenerationMetrics {
+    /// Share of the factual claims of the answer which are supported by the documents (1 if
+    /// the answer makes no claim)
+    pub faithfulness: f64,
+    /// How well the answer addresses the question: 1 if it answers it completely and directly,
+    /// 0 if it is off-topic
+    pub answer_relevancy: f64,
+}
+
+impl GenerationMetrics {
+    fn mean(metrics: &[Self]) -> Self {
+        if metrics.is_empty() {
+            return Self::default();
+        }
+        let n = metrics.len() as f64;
+        Self {
+            faithfulness: metrics.iter().map(|m| m.faithfulness).sum::<f64>() / n,
+            answer_relevancy: metrics.iter().map(|m| m.answer_relevancy).sum::<f64>() / n,
+        }
+    }
+}
+
+/// The retrieval of a question.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetrievalResult {
+    pub question: String,
+    pub retrieved_ids: Vec<String>,
+    pub metrics: RetrievalMetrics,
+}
+
+/// The results of [evaluate_retrieval].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetrievalReport {
+    /// Maximum number of documents retrieved per question
+    pub k: usize,
+    /// Means of the metrics over the questions with relevant documents
+    pub metrics: RetrievalMetrics,
+    pub results: Vec<RetrievalResult>,
+}
+
+/// Retrieve the `k` most relevant documents of the index for each question of the dataset, and
+/// compute the retrieval metrics.
+pub async fn evaluate_retrieval<I: VectorStoreIndex>(
+    index: &I,
+    dataset: &EvalDataset,
+    k: usize,
+) -> Result<RetrievalReport, EvalError> {
+    let mut results = vec![];
+    for example in &dataset.examples {
+        let retrieved_ids = index
+            .top_n_ids(&example.question, k)
+            .await?
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect::<Vec<_>>();
+        results.push(RetrievalResult {
+            question: example.question.clone(),
+            metrics: RetrievalMetrics::compute(example, &retrieved_ids),
+            retrieved_ids,
+        });
+    }
+
+    Ok(RetrievalReport {
+        k,
+        metrics: mean_retrieval(dataset, results.iter().map(|result| result.metrics)),
+        results,
+    })
+}
+
+fn mean_retrieval(
+    dataset: &EvalDataset,
+    metrics: impl Iterator<Item = RetrievalMetrics>,
+) -> RetrievalMetrics {
+    let labeled = dataset
+        .examples
+        .iter()
+        .zip(metrics)
+        .filter(|(example, _)| !example.relevant_ids.is_empty())
+        .map(|(_, metrics)| metrics)
+        .collect::<Vec<_>>();
+    RetrievalMetrics::mean(&labeled)
+}
+
+/// The retrieval and answer of a question.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RagResult {
+    pub question: String,
+    pub retrieved_ids: Vec<String>,
+    pub answer: String,
+    pub retrieval: RetrievalMetrics,
+    pub generation: GenerationMetrics,
+}
+
+/// The results of [RagEvaluator::evaluate], with the means of the metrics of each stage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RagReport {
+    /// Means of the retrieval metrics over the questions with relevant documents
+    pub retrieval: RetrievalMetrics,
+    /// Means of the generation metrics over all the questions
+    pub generation: GenerationMetrics,
+    pub results: Vec<RagResult>,
+}
+
+/// Evaluates the retrieval and generation stages of agents, with a judge model for the
+/// generation metrics.
+pub struct RagEvaluator<J: CompletionModel> {
+    judge: Extractor<J, GenerationMetrics>,
+}
+
+impl<J: CompletionModel> RagEvaluator<J> {
+    pub fn new(judge: J) -> Self {
+        Self {
+            judge: ExtractorBuilder::new(judge)
+                .preamble(
+                    "You are evaluating the answer of a question-answering system, given the \
+                     question, the documents retrieved by the system and its answer. Score the \
+                     faithfulness of the answer (the share of its factual claims supported by the \
+                     documents) and its relevancy (how completely and directly it addresses the \
+                     question), between 0 and 1.",
+                )
+                .build(),
+        }
+    }
+
+    /// Judge the answer to the question, given the retrieved documents.
+    pub async fn judge(
+        &self,
+        example: &EvalExample,
+        documents: &[Document],
+        answer: &str,
+    ) -> Result<GenerationMetrics, EvalError> {
+        let documents = documents
+            .iter()
+            .map(|document| document.to_string())
+            .collect::<String>();
+        let reference = example
+            .reference_answer
+            .as_ref()
+            .map(|reference| format!("<reference_answer>\n{reference}\n</reference_answer>\n\n"))
+            .unwrap_or_default();
+
+        let metrics = self
+            .judge
+            .extract(&format!(
+                "<question>\n{}\n</question>\n\n<documents>\n{documents}</documents>\n\n\
+                 {reference}<answer>\n{answer}\n</answer>",
+                example.question
+            ))
+            .await?;
+        Ok(GenerationMetrics {
+            faithfulness: metrics.faithfulness.clamp(0.0, 1.0),
+            answer_relevancy: metrics.answer_relevancy.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Ask the questions of the dataset to the agent, and compute the metrics of the documents
+    /// of its requests (its static and dynamic context) and of its answers.
+    pub async fn evaluate<M: CompletionModel>(
+        &self,
+        agent: &Agent<M>,
+        dataset: &EvalDataset,
+    ) -> Result<RagReport, EvalError> {
+        let mut results = vec![];
+        for example in &dataset.examples {
+            let request = agent.completion(example.question.as_str(), vec![]).await?;
+            let documents = request.context_documents().to_vec();
+            let answer = race::text(&request.send().await?.choice);
+
+            let retrieved_ids = documents
+                .iter()
+                .map(|document| document.metadata.id.clone())
+                .collect::<Vec<_>>();
+            results.push(RagResult {
+                question: example.question.clone(),
+                retrieval: RetrievalMetrics::compute(example, &retrieved_ids),
+                generation: self.judge(example, &documents, &answer).await?,
+                retrieved_ids,
+                answer,
+            });
+        }
+
+        let generation = results
+            .iter()
+            .map(|result| result.generation)
+            .collect::<Vec<_>>();
+        Ok(RagReport {
+            retrieval: mean_retrieval(dataset, results.iter().map(|result| result.retrieval)),
+            generation: GenerationMetrics::mean(&generation),
+            results,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        embeddings::Embedding,
+        test_utils::{MockEmbeddingModel, MockModel},
+        vector_store::in_memory_store::InMemoryVectorStore,
+        OneOrMany,
+    };
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_retrieval_metrics() {
+        let example = EvalExample::new("?").relevant(["a.md", "b.md"]);
+
+        assert_eq!(
+            RetrievalMetrics::compute(&example, &ids(&["c.md", "a.md#0", "a.md#1", "d.md"])),
+            RetrievalMetrics {
+                recall: 0.5,
+                precision: 0.5,
+                mrr: 0.5,
+                hit_rate: 1.0,
+            }
+        );
+        assert_eq!(
+            RetrievalMetrics::compute(&example, &ids(&["c.md"])),
+            RetrievalMetrics::default()
+        );
+    }
+
+    fn index() -> impl VectorStoreIndex + 'static {
+        InMemoryVectorStore::from_documents_with_ids([
+            ("alpha.md", "aaaa".to_string(), embedding(vec![1.0, 0.0])),
+            ("bravo.md", "bbbb".to_string(), embedding(vec![0.0, 1.0])),
+        ])
+        .index(MockEmbeddingModel::counting(&["a", "b"]))
+    }
+
+    fn embedding(vec: Vec<f64>) -> OneOrMany<Embedding> {
+        OneOrMany::one(Embedding {
+            document: String::new(),
+            vec,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_retrieval() {
+        let dataset = EvalDataset::new(vec![
+            EvalExample::new("aaa?").relevant(["alpha.md"]),
+            EvalExample::new("bbb?").relevant(["alpha.md"]),
+            EvalExample::new("unlabeled"),
+        ]);
+
+        let report = evaluate_retrieval(&index(), &dataset, 1).await.unwrap();
+        assert_eq!(report.results[0].retrieved_ids, ["alpha.md"]);
+        assert_eq!(report.metrics.recall, 0.5);
+        assert_eq!(report.metrics.mrr, 0.5);
+    }
+
+    /// Answers with the number of documents of the requests
+    fn mock() -> MockModel {
+        MockModel::reply(|request| format!("{} documents", request.documents.len()))
+    }
+
+    #[tokio::test]
+    async fn test_rag_evaluator() {
+        let agent = AgentBuilder::new(mock())
+            .dynamic_context(1, index())
+            .build();
+        let dataset = EvalDataset::new(vec![EvalExample::new("bbb?").relevant(["bravo.md"])]);
+
+        let report = RagEvaluator::new(MockModel::tool_call(
+            "submit",
+            json!({"faithfulness": 1.5, "answer_relevancy": 0.5}),
+        ))
+        .evaluate(&agent, &dataset)
+        .await
+        .unwrap();
+        assert_eq!(report.results[0].answer, "1 documents");
+        assert_eq!(report.results[0].retrieved_ids, ["bravo.md"]);
+        assert_eq!(report.retrieval.recall, 1.0);
+        assert_eq!(
+            report.generation,
+            GenerationMetrics {
+                faithfulness: 1.0,
+                answer_relevancy: 0.5,
+            }
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! This module provides prompt versioning and A/B experiments.
+//!
+//! An [Experiment] holds several versions ([PromptVariant]) of a preamble and/or prompt template,
+//! each with a weight. Requests are routed to one of the variants (in proportion to their weights),
+//! and the outcomes of the requests (e.g.: user feedback or scores given by an LLM judge) are
+//! recorded and aggregated per variant, so that the variants can be compared.
+//!
+//! Requests made with the same key (e.g.: a user ID) are always routed to the same variant.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     experiments::{Experiment, PromptVariant},
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//! let agent = openai.agent(openai::GPT_4O).build();
+//!
+//! let experiment = Experiment::new("support-tone")
+//!     .variant(PromptVariant::new("formal", 0.5).preamble("You are a formal support agent."))
+//!     .variant(
+//!         PromptVariant::new("casual", 0.5)
+//!             .preamble("You are a friendly support agent.")
+//!             .template("Answer in two sentences: {input}"),
+//!     );
+//!
+//! let (variant, answer) = experiment
+//!     .prompt(&agent, "user-1234", "How do I reset my password?")
+//!     .await?;
+//!
+//! // Later, when the user rates the answer
+//! experiment.record_feedback(&variant, true);
+//!
+//! for results in experiment.results() {
+//!     println!("{}: {:?} ({} outcomes)", results.variant, results.mean_score, results.outcomes);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+};
+
+use crate::{
+    agent::Agent,
+    completion::{Completion, CompletionModel, PromptError},
+    message::AssistantContent,
+};
+
+/// A version of the preamble and/or prompt template used in an [Experiment].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PromptVariant {
+    pub name: String,
+    /// Relative weight of the variant when routing requests
+    pub weight: f64,
+    /// Preamble replacing the preamble of the agent, if set
+    pub preamble: Option<String>,
+    /// Template of the prompt, in which `{input}` is replaced by the input of the request
+    pub template: Option<String>,
+}
+
+impl PromptVariant {
+    pub fn new(name: &str, weight: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            weight: weight.max(0.0),
+            preamble: None,
+            template: None,
+        }
+    }
+
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.preamble = Some(preamble.to_string());
+        self
+    }
+
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
+
+    /// Render the prompt of this variant for the given input.
+    pub fn render(&self, input: &str) -> String {
+        match &self.template {
+            Some(template) => template.replace("{input}", input),
+            None => input.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct VariantStats {
+    assignments: u64,
+    outcomes: u64,
+    score_sum: f64,
+    score_sum_sq: f64,
+}
+
+/// Aggregated outcomes of a variant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantResults {
+    pub variant: String,
+    /// Number of requests routed to the variant
+    pub assignments: u64,
+    /// Number of outcomes recorded for the variant
+    pub outcomes: u64,
+    /// Mean score of the recorded outcomes, if any
+    pub mean_score: Option<f64>,
+    /// Standard deviation of the scores of the recorded outcomes, if any
+    pub std_dev: Option<f64>,
+}
+
+/// An A/B experiment between several prompt variants.
+pub struct Experiment {
+    name: String,
+    variants: Vec<PromptVariant>,
+    stats: Mutex<HashMap<String, VariantStats>>,
+}
+
+impl Experiment {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            variants: vec![],
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a variant to the experiment.
+    pub fn variant(mut self, variant: PromptVariant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn variants(&self) -> &[PromptVariant] {
+        &self.variants
+    }
+
+    /// Pick the variant for the given key. The same key is always assigned the same variant
+    /// (as long as the variants of the experiment do not change).
+    /// Panics if the experiment has no variants.
+    pub fn assign(&self, key: &str) -> &PromptVariant {
+        assert!(
+            !self.variants.is_empty(),
+            "Experiment {} has no variants",
+            self.name
+        );
+
+        let mut hasher = DefaultHasher::new();
+        (&self.name, key).hash(&mut hasher);
+        // Uniformly distributed point in [0, 1)
+        let point = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+
+        let total = self
+            .variants
+            .iter()
+            .map(|variant| variant.weight)
+            .sum::<f64>();
+        let mut threshold = 0.0;
+        let variant = self
+            .variants
+            .iter()
+            .find(|variant| {
+                threshold += variant.weight / total;
+                point < threshold
+            })
+            .unwrap_or_else(|| self.variants.last().expect("Variants are not empty"));
+
+        self.stats()
+            .entry(variant.name.clone())
+            .or_default()
+            .assignments += 1;
+
+        variant
+    }
+
+    /// Prompt the agent with the variant assigned to `key`, and return the name of the variant
+    /// along with the response. If the model responds with a tool call, the tool is called and
+    /// its output is returned.
+    pub async fn prompt<M: CompletionModel>(
+        &self,
+        agent: &Agent<M>,
+        key: &str,
+        input: &str,
+    ) -> Result<(String, String), PromptError> {
+        let variant = self.assign(key);
+
+        let mut request = agent.completion(variant.render(input), vec![]).await?;
+        if let Some(preamble) = &variant.preamble {
+            request = request.preamble(preamble.clone());
+        }
+        let response = request.send().await?;
+
+        let output = match response.choice.first() {
+            AssistantContent::Text(text) => text.text,
+            AssistantContent::ToolCall(tool_call) => {
+                agent
+                    .tools
+                    .call(
+                        &tool_call.function.name,
+                        tool_call.function.arguments.to_string(),
+                    )
+                    .await?
+            }
+        };
+
+        Ok((variant.name.clone(), output))
+    }
+
+    /// Record the score of an outcome of the given variant (e.g.: a score given by an LLM judge).
+    pub fn record_score(&self, variant: &str, score: f64) {
+        let mut stats = self.stats();
+        let stats = stats.entry(variant.to_string()).or_default();
+        stats.outcomes += 1;
+        stats.score_sum += score;
+        stats.score_sum_sq += score * score;
+    }
+
+    /// Record user feedback on an outcome of the given variant, as a score of 1 (positive)
+    /// or 0 (negative).
+    pub fn record_feedback(&self, variant: &str, positive: bool) {
+        self.record_score(variant, if positive { 1.0 } else { 0.0 });
+    }
+
+    /// The aggregated outcomes of each variant, in the order the variants were added.
+    pub fn results(&self) -> Vec<VariantResults> {
+        let stats = self.stats();
+
+        self.variants
+            .iter()
+            .map(|variant| {
+                let stats = stats.get(&variant.name).copied().unwrap_or_default();
+                let mean = (stats.outcomes > 0).then(|| stats.score_sum / stats.outcomes as f64);
+
+                VariantResults {
+                    variant: variant.name.clone(),
+                    assignments: stats.assignments,
+                    outcomes: stats.outcomes,
+                    mean_score: mean,
+                    std_dev: mean.map(|mean| {
+                        (stats.score_sum_sq / stats.outcomes as f64 - mean * mean)
+                            .max(0.0)
+                            .sqrt()
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    fn stats(&self) -> std::sync::MutexGuard<'_, HashMap<String, VariantStats>> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent::AgentBuilder, test_utils::MockModel};
+
+    /// Answers with the preamble and the prompt
+    fn mock() -> MockModel {
+        MockModel::reply(|request| {
+            format!(
+                "{} / {}",
+                request.preamble.unwrap_or_default(),
+                request.prompt.rag_text().unwrap_or_default()
+            )
+        })
+    }
+
+    #[test]
+    fn test_assignment_follows_weights() {
+        let experiment = Experiment::new("test")
+            .variant(PromptVariant::new("a", 3.0))
+            .variant(PromptVariant::new("b", 1.0))
+            .variant(PromptVariant::new("disabled", 0.0));
+
+        for i in 0..1000 {
+            let key = format!("user-{i}");
+            assert_eq!(experiment.assign(&key), experiment.assign(&key));
+        }
+
+        let results = experiment.results();
+        assert_eq!(results[2].assignments, 0);
+        // Each key was assigned twice
+        let share = results[0].assignments as f64 / 2000.0;
+        assert!((0.7..0.8).contains(&share), "share of a: {share}");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_and_results() {
+        let agent = AgentBuilder::new(mock()).preamble("default").build();
+        let experiment = Experiment::new("test").variant(
+            PromptVariant::new("short", 1.0)
+                .preamble("be brief")
+                .template("Q: {input}"),
+        );
+
+        let (variant, output) = experiment.prompt(&agent, "user", "hello").await.unwrap();
+        assert_eq!(variant, "short");
+        assert_eq!(output, "be brief / Q: hello");
+
+        experiment.record_feedback(&variant, true);
+        experiment.record_feedback(&variant, false);
+        experiment.record_score(&variant, 0.5);
+
+        assert_eq!(
+            experiment.results(),
+            vec![VariantResults {
+                variant: "short".to_string(),
+                assignments: 1,
+                outcomes: 3,
+                mean_score: Some(0.5),
+                std_dev: Some((1.0f64 / 6.0).sqrt()),
+            }]
+        );
+    }
+}
@@ -36,8 +36,11 @@ use serde_json::json;
 
 use crate::{
     agent::{Agent, AgentBuilder},
-    completion::{CompletionModel, Prompt, PromptError, ToolDefinition},
+    completion::{Chat, CompletionModel, PromptError, ToolDefinition},
+    json_utils,
+    message::Message,
     tool::Tool,
+    validation::{self, ValidationError, Validator},
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -48,14 +51,28 @@ pub enum ExtractionError {
     #[error("Failed to deserialize the extracted data: {0}")]
     DeserializationError(#[from] serde_json::Error),
 
+    /// The extracted data is not valid JSON, even after repairing it (e.g.: stripping
+    /// markdown code fences, removing trailing commas)
+    #[error("Failed to deserialize the extracted data: {error} (extracted data: {text})")]
+    InvalidJson {
+        text: String,
+        #[source]
+        error: serde_json::Error,
+    },
+
     #[error("PromptError: {0}")]
     PromptError(#[from] PromptError),
+
+    /// The extracted data is still invalid after the maximum number of retries
+    #[error("ValidationError: {0}")]
+    ValidationError(#[from] ValidationError),
 }
 
 /// Extractor for structured data from text
 pub struct Extractor<M: CompletionModel, T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync> {
     agent: Agent<M>,
-    _t: PhantomData<T>,
+    validators: Vec<Validator<T>>,
+    max_validation_retries: usize,
 }
 
 impl<T: JsonSchema + for<'a> Deserialize<'a> + Send + Sync, M: CompletionModel> Extractor<M, T>
@@ -63,13 +80,39 @@ where
     M: Sync,
 {
     pub async fn extract(&self, text: &str) -> Result<T, ExtractionError> {
-        let summary = self.agent.prompt(text).await?;
-
-        if summary.is_empty() {
-            return Err(ExtractionError::NoData);
+        let mut prompt = Message::user(text);
+        let mut chat_history = vec![];
+        let mut retries = 0;
+
+        loop {
+            let summary = self
+                .agent
+                .chat(prompt.clone(), chat_history.clone())
+                .await?;
+
+            if summary.is_empty() {
+                return Err(ExtractionError::NoData);
+            }
+
+            let data = json_utils::from_str_repaired(&summary).map_err(|error| {
+                ExtractionError::InvalidJson {
+                    text: summary.clone(),
+                    error,
+                }
+            })?;
+
+            match validation::validate(&self.validators, &data) {
+                Ok(()) => return Ok(data),
+                Err(error) if retries < self.max_validation_retries => {
+                    // Ask the model to extract the data again, fixing the error
+                    retries += 1;
+                    chat_history.push(prompt);
+                    chat_history.push(Message::assistant(summary));
+                    prompt = Message::user(validation::correction(&error));
+                }
+                Err(error) => return Err(error.into()),
+            }
         }
-
-        Ok(serde_json::from_str(&summary)?)
     }
 }
 
@@ -79,7 +122,8 @@ pub struct ExtractorBuilder<
     M: CompletionModel,
 > {
     agent_builder: AgentBuilder<M>,
-    _t: PhantomData<T>,
+    validators: Vec<Validator<T>>,
+    max_validation_retries: usize,
 }
 
 impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: CompletionModel>
@@ -95,7 +139,8 @@ impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: Compl
                     Be sure to fill out every field and ALWAYS CALL THE `submit` function, event with default values!!!.
                 ")
                 .tool(SubmitTool::<T> {_t: PhantomData}),
-            _t: PhantomData,
+            validators: vec![],
+            max_validation_retries: 2,
         }
     }
 
@@ -113,11 +158,28 @@ impl<T: JsonSchema + for<'a> Deserialize<'a> + Serialize + Send + Sync, M: Compl
         self
     }
 
+    /// Add a validator of the extracted data (see [validation](crate::validation)).
+    pub fn validate(
+        mut self,
+        validator: impl Fn(&T) -> Result<(), ValidationError> + Send + Sync + 'static,
+    ) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Set the maximum number of times the model is asked to extract the data again when it is
+    /// invalid (2 by default). The validation error is returned once the retries are exhausted.
+    pub fn max_validation_retries(mut self, max_retries: usize) -> Self {
+        self.max_validation_retries = max_retries;
+        self
+    }
+
     /// Build the Extractor
     pub fn build(self) -> Extractor<M, T> {
         Extractor {
             agent: self.agent_builder.build(),
-            _t: PhantomData,
+            validators: self.validators,
+            max_validation_retries: self.max_validation_retries,
         }
     }
 }
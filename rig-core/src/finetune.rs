@@ -0,0 +1,463 @@
+//! This module wraps the fine-tuning API of OpenAI: uploading training files, creating
+//! fine-tuning jobs, polling their status and listing their checkpoints.
+//!
+//! Training files are JSONL documents of chat transcripts (see
+//! [Transcript::to_openai](crate::conversation::formats::Transcript::to_openai)). With the `audit`
+//! feature, the completions recorded in an audit log can be turned into training examples with
+//! [training_examples].
+//!
+//! This module is only available with the `finetune` feature, and requires a tokio runtime to
+//! wait for the jobs.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use rig::{
+//!     conversation::formats::Transcript,
+//!     finetune::{FineTuning, JobRequest},
+//!     message::Message,
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), rig::finetune::FineTuningError> {
+//! let fine_tuning = FineTuning::new(openai::Client::from_env());
+//!
+//! let examples = vec![Transcript::new(vec![
+//!     Message::user("What is the capital of France?"),
+//!     Message::assistant("Paris, obviously."),
+//! ])
+//! .with_system("You are a sarcastic assistant.")];
+//!
+//! let file = fine_tuning.upload_transcripts("sarcasm.jsonl", &examples).await?;
+//! let job = fine_tuning
+//!     .create_job(JobRequest::new("gpt-4o-mini-2024-07-18", &file.id).suffix("sarcasm"))
+//!     .await?;
+//!
+//! let job = fine_tuning.wait_for_job(&job.id, Duration::from_secs(30)).await?;
+//! println!("Fine-tuned model: {:?}", job.fine_tuned_model);
+//! # Ok(())
+//! # }
+//! ```
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    conversation::formats::{self, FormatError, Transcript},
+    providers::openai,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum FineTuningError {
+    /// Http error (e.g.: connection error, timeout, etc.)
+    #[error("HttpError: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    /// Json error (e.g.: serialization, deserialization)
+    #[error("JsonError: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    /// The transcripts cannot be converted to training examples
+    #[error("FormatError: {0}")]
+    FormatError(#[from] FormatError),
+
+    /// Error returned by the API
+    #[error("ProviderError: {0}")]
+    ProviderError(String),
+
+    /// The job ended without producing a model
+    #[error("Job {id} {status:?}: {message}")]
+    JobFailed {
+        id: String,
+        status: JobStatus,
+        message: String,
+    },
+}
+
+/// A file uploaded to OpenAI.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileObject {
+    pub id: String,
+    pub bytes: u64,
+    pub created_at: u64,
+    pub filename: String,
+    pub purpose: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    ValidatingFiles,
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has ended (successfully or not).
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Hyperparameters {
+    /// Number of epochs (a number, or `"auto"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_epochs: Option<Value>,
+    /// Batch size (a number, or `"auto"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<Value>,
+    /// Learning rate multiplier (a number, or `"auto"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub learning_rate_multiplier: Option<Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JobError {
+    pub code: Option<String>,
+    pub message: String,
+    pub param: Option<String>,
+}
+
+/// A fine-tuning job.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FineTuningJob {
+    pub id: String,
+    /// The base model being fine-tuned
+    pub model: String,
+    pub status: JobStatus,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+    /// The name of the fine-tuned model, once the job has succeeded
+    pub fine_tuned_model: Option<String>,
+    pub training_file: String,
+    pub validation_file: Option<String>,
+    pub trained_tokens: Option<u64>,
+    pub error: Option<JobError>,
+    #[serde(default)]
+    pub hyperparameters: Option<Hyperparameters>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct CheckpointMetrics {
+    pub step: Option<f64>,
+    pub train_loss: Option<f64>,
+    pub train_mean_token_accuracy: Option<f64>,
+    pub valid_loss: Option<f64>,
+    pub valid_mean_token_accuracy: Option<f64>,
+    pub full_valid_loss: Option<f64>,
+    pub full_valid_mean_token_accuracy: Option<f64>,
+}
+
+/// A checkpoint of a fine-tuning job: a model usable for completions, saved at the end of an
+/// epoch.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Checkpoint {
+    pub id: String,
+    pub created_at: u64,
+    /// The name of the model of the checkpoint
+    pub fine_tuned_model_checkpoint: String,
+    pub step_number: u64,
+    #[serde(default)]
+    pub metrics: CheckpointMetrics,
+}
+
+/// A page of a list returned by the API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct List<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// The parameters of a fine-tuning job.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobRequest {
+    model: String,
+    training_file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validation_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hyperparameters: Option<Hyperparameters>,
+}
+
+impl JobRequest {
+    /// Fine-tune the given base model with the given (uploaded) training file.
+    pub fn new(model: &str, training_file: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            training_file: training_file.to_string(),
+            validation_file: None,
+            suffix: None,
+            seed: None,
+            hyperparameters: None,
+        }
+    }
+
+    /// Set the (uploaded) validation file of the job.
+    pub fn validation_file(mut self, file: &str) -> Self {
+        self.validation_file = Some(file.to_string());
+        self
+    }
+
+    /// Set the suffix added to the name of the fine-tuned model (up to 64 characters).
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = Some(suffix.to_string());
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn hyperparameters(mut self, hyperparameters: Hyperparameters) -> Self {
+        self.hyperparameters = Some(hyperparameters);
+        self
+    }
+}
+
+/// Client of the fine-tuning API of OpenAI.
+#[derive(Clone)]
+pub struct FineTuning {
+    client: openai::Client,
+}
+
+impl FineTuning {
+    pub fn new(client: openai::Client) -> Self {
+        Self { client }
+    }
+
+    /// Upload a JSONL training (or validation) file.
+    pub async fn upload_training_file(
+        &self,
+        filename: &str,
+        jsonl: Vec<u8>,
+    ) -> Result<FileObject, FineTuningError> {
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", "fine-tune")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(jsonl).file_name(filename.to_string()),
+            );
+
+        send(self.client.post("files").multipart(form)).await
+    }
+
+    /// Upload transcripts as a JSONL training (or validation) file.
+    pub async fn upload_transcripts(
+        &self,
+        filename: &str,
+        transcripts: &[Transcript],
+    ) -> Result<FileObject, FineTuningError> {
+        let jsonl = formats::to_openai_jsonl(transcripts)?;
+        self.upload_training_file(filename, jsonl.into_bytes())
+            .await
+    }
+
+    pub async fn create_job(&self, request: JobRequest) -> Result<FineTuningJob, FineTuningError> {
+        send(self.client.post("fine_tuning/jobs").json(&request)).await
+    }
+
+    pub async fn job(&self, id: &str) -> Result<FineTuningJob, FineTuningError> {
+        send(self.client.get(&format!("fine_tuning/jobs/{id}"))).await
+    }
+
+    /// List the fine-tuning jobs, most recent first.
+    pub async fn list_jobs(
+        &self,
+        limit: usize,
+        after: Option<&str>,
+    ) -> Result<List<FineTuningJob>, FineTuningError> {
+        let mut query = vec![("limit", limit.to_string())];
+        query.extend(after.map(|after| ("after", after.to_string())));
+
+        send(self.client.get("fine_tuning/jobs").query(&query)).await
+    }
+
+    pub async fn cancel_job(&self, id: &str) -> Result<FineTuningJob, FineTuningError> {
+        send(self.client.post(&format!("fine_tuning/jobs/{id}/cancel"))).await
+    }
+
+    /// List the checkpoints of a job, most recent first.
+    pub async fn checkpoints(&self, id: &str) -> Result<List<Checkpoint>, FineTuningError> {
+        send(
+            self.client
+                .get(&format!("fine_tuning/jobs/{id}/checkpoints")),
+        )
+        .await
+    }
+
+    /// Poll the status of a job at the given interval until it ends. Returns the job if it
+    /// succeeded, and [FineTuningError::JobFailed] otherwise.
+    pub async fn wait_for_job(
+        &self,
+        id: &str,
+        interval: Duration,
+    ) -> Result<FineTuningJob, FineTuningError> {
+        loop {
+            let job = self.job(id).await?;
+
+            match job.status {
+                JobStatus::Succeeded => return Ok(job),
+                status if status.is_terminal() => {
+                    return Err(FineTuningError::JobFailed {
+                        id: job.id,
+                        status,
+                        message: job.error.map(|error| error.message).unwrap_or_default(),
+                    })
+                }
+                status => {
+                    tracing::debug!(target: "rig", "Fine-tuning job {id}: {status:?}");
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        }
+    }
+}
+
+async fn send<T: DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, FineTuningError> {
+    let response = request.send().await?;
+
+    if response.status().is_success() {
+        Ok(serde_json::from_str(&response.text().await?)?)
+    } else {
+        Err(FineTuningError::ProviderError(response.text().await?))
+    }
+}
+
+/// Turn the successful completions of an audit log into training examples (see
+/// [AuditEntry::transcript](crate::audit::AuditEntry::transcript)).
+#[cfg(feature = "audit")]
+pub fn training_examples<'a>(
+    entries: impl IntoIterator<Item = &'a crate::audit::AuditEntry>,
+) -> Vec<Transcript> {
+    entries
+        .into_iter()
+        .filter_map(|entry| entry.transcript())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_job() {
+        let job: FineTuningJob = serde_json::from_value(json!({
+            "object": "fine_tuning.job",
+            "id": "ftjob-abc123",
+            "model": "gpt-4o-mini-2024-07-18",
+            "created_at": 1721764800,
+            "finished_at": null,
+            "fine_tuned_model": null,
+            "organization_id": "org-123",
+            "result_files": [],
+            "status": "validating_files",
+            "validation_file": null,
+            "training_file": "file-abc123",
+            "hyperparameters": {"n_epochs": "auto", "batch_size": 4},
+            "trained_tokens": null,
+            "error": null
+        }))
+        .unwrap();
+
+        assert_eq!(job.status, JobStatus::ValidatingFiles);
+        assert!(!job.status.is_terminal());
+        assert_eq!(
+            job.hyperparameters.unwrap(),
+            Hyperparameters {
+                n_epochs: Some(json!("auto")),
+                batch_size: Some(json!(4)),
+                learning_rate_multiplier: None,
+            }
+        );
+
+        let checkpoints: List<Checkpoint> = serde_json::from_value(json!({
+            "object": "list",
+            "data": [{
+                "object": "fine_tuning.job.checkpoint",
+                "id": "ftckpt_zc4Q7MP6XxulcVzj4MZdwsAB",
+                "created_at": 1721764867,
+                "fine_tuned_model_checkpoint": "ft:gpt-4o-mini-2024-07-18:org:suffix:ckpt-step-88",
+                "metrics": {"step": 88, "train_loss": 0.478, "full_valid_loss": 0.682},
+                "fine_tuning_job_id": "ftjob-abc123",
+                "step_number": 88
+            }],
+            "has_more": false
+        }))
+        .unwrap();
+        assert_eq!(checkpoints.data[0].step_number, 88);
+        assert_eq!(checkpoints.data[0].metrics.train_loss, Some(0.478));
+    }
+
+    #[test]
+    fn test_job_request() {
+        let request = JobRequest::new("gpt-4o-mini", "file-abc123")
+            .suffix("support")
+            .hyperparameters(Hyperparameters {
+                n_epochs: Some(json!(3)),
+                ..Default::default()
+            });
+
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "model": "gpt-4o-mini",
+                "training_file": "file-abc123",
+                "suffix": "support",
+                "hyperparameters": {"n_epochs": 3}
+            })
+        );
+    }
+
+    #[cfg(feature = "audit")]
+    #[test]
+    fn test_training_examples() {
+        use crate::audit::{AuditEntry, AuditKind, REDACTED};
+
+        let entry = |prompt: Value| AuditEntry {
+            timestamp: 0,
+            run_id: "run".to_string(),
+            kind: AuditKind::Completion,
+            duration_ms: 10,
+            request: json!({
+                "preamble": "Be brief.",
+                "prompt": prompt,
+                "chat_history": [],
+                "documents": [],
+            }),
+            response: Some(json!({"choice": [{"text": "Hi!"}]})),
+            error: None,
+        };
+        let entries = [
+            entry(json!({"role": "user", "content": [{"type": "text", "text": "Hello"}]})),
+            entry(json!(REDACTED)),
+        ];
+
+        let examples = training_examples(&entries);
+        assert_eq!(examples.len(), 1);
+        assert_eq!(
+            examples[0].to_openai().unwrap(),
+            json!({"messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Hello"},
+                {"role": "assistant", "content": "Hi!"},
+            ]})
+        );
+    }
+}
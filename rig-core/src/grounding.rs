@@ -0,0 +1,264 @@
+//! This module provides [GroundingChecker], an optional stage of [agents](crate::agent::Agent)
+//! verifying that their answers are supported by the documents of their context (e.g.: the
+//! chunks retrieved by RAG), to catch hallucinations.
+//!
+//! After the post-processors and validators of the agent, a judge model is given the documents
+//! and the answer, and lists the claims of the answer which are not supported by (or contradict)
+//! the documents, in the manner of natural language inference. Depending on the
+//! [GroundingAction], an answer with unsupported claims is then:
+//! - flagged: a warning is logged, and the answer is returned as is,
+//! - annotated: a note listing the unsupported claims is appended to the answer,
+//! - regenerated: the model is asked to answer again using only the documents, and the answer is
+//!   annotated if its claims are still unsupported after the retries.
+//!
+//! Answers without documents in their context are not checked, and the answers are returned as
+//! is when the judge fails.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     grounding::{GroundingAction, GroundingChecker},
+//!     providers::openai,
+//! };
+//!
+//! # fn run(index: rig::vector_store::in_memory_store::InMemoryVectorIndex<openai::EmbeddingModel, String>) {
+//! let openai = openai::Client::from_env();
+//!
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .preamble("Answer the questions of the user about the handbook of ACME Inc.")
+//!     .dynamic_context(4, index)
+//!     .grounding(
+//!         GroundingChecker::new(openai.completion_model(openai::GPT_4O_MINI))
+//!             .action(GroundingAction::Regenerate { max_retries: 1 }),
+//!     )
+//!     .build();
+//! # }
+//! ```
+use std::sync::Arc;
+
+use futures::{future::BoxFuture, FutureExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::CompletionModel,
+    extractor::{ExtractionError, ExtractorBuilder},
+    metadata::Document,
+};
+
+/// The verdict of the judge of a [GroundingChecker] on an answer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+pub struct GroundingVerdict {
+    /// Whether all the claims of the answer are supported by the documents
+    pub supported: bool,
+    /// The claims of the answer which are not supported by (or contradict) the documents
+    pub unsupported_claims: Vec<String>,
+}
+
+/// What to do with the answers whose claims are not supported by the documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroundingAction {
+    /// Log a warning, and return the answer as is
+    Flag,
+    /// Append a note listing the unsupported claims to the answer
+    Annotate,
+    /// Ask the model to answer again using only the documents, then annotate the answer if its
+    /// claims are still unsupported after the retries
+    Regenerate { max_retries: usize },
+}
+
+type Judge = Box<
+    dyn Fn(String) -> BoxFuture<'static, Result<GroundingVerdict, ExtractionError>> + Send + Sync,
+>;
+
+/// Checks that the answers of an agent are supported by its documents, see the
+/// [module documentation](self).
+pub struct GroundingChecker {
+    judge: Judge,
+    action: GroundingAction,
+}
+
+impl GroundingChecker {
+    /// Check the answers with the (judge) model. By default, the unsupported claims are
+    /// [annotated](GroundingAction::Annotate).
+    pub fn new<M: CompletionModel + 'static>(model: M) -> Self {
+        let extractor = Arc::new(
+            ExtractorBuilder::<GroundingVerdict, M>::new(model)
+                .preamble(
+                    "You are a fact-checker. You are given documents and an answer based on \
+                     them. Check each factual claim of the answer against the documents, and list \
+                     the claims which are not supported by the documents or contradict them. \
+                     Statements which are not factual claims (e.g.: greetings, or saying that the \
+                     documents do not contain the answer) are supported.",
+                )
+                .build(),
+        );
+
+        Self {
+            judge: Box::new(move |prompt| {
+                let extractor = extractor.clone();
+                async move { extractor.extract(&prompt).await }.boxed()
+            }),
+            action: GroundingAction::Annotate,
+        }
+    }
+
+    pub fn action(mut self, action: GroundingAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Judge whether the answer is supported by the documents.
+    pub async fn check(
+        &self,
+        answer: &str,
+        documents: &[Document],
+    ) -> Result<GroundingVerdict, ExtractionError> {
+        let documents = documents
+            .iter()
+            .map(|document| document.to_string())
+            .collect::<String>();
+        let mut verdict = (self.judge)(format!(
+            "<documents>\n{documents}</documents>\n\n<answer>\n{answer}\n</answer>"
+        ))
+        .await?;
+        verdict.supported = verdict.supported && verdict.unsupported_claims.is_empty();
+        Ok(verdict)
+    }
+
+    /// Check the answer of an agent, after `retries` regenerations. Returns the final answer,
+    /// or the instruction to answer again.
+    pub(crate) async fn review(
+        &self,
+        answer: String,
+        documents: &[Document],
+        retries: usize,
+    ) -> Result<String, String> {
+        if documents.is_empty() {
+            return Ok(answer);
+        }
+
+        let verdict = match self.check(&answer, documents).await {
+            Ok(verdict) if verdict.supported => return Ok(answer),
+            Ok(verdict) => verdict,
+            Err(e) => {
+                tracing::warn!(target: "rig", "Grounding check failed: {e}");
+                return Ok(answer);
+            }
+        };
+        let claims = verdict
+            .unsupported_claims
+            .iter()
+            .map(|claim| format!("- {claim}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        match self.action {
+            GroundingAction::Flag => {
+                tracing::warn!(target: "rig", "The answer of the agent has unsupported claims:\n{claims}");
+                Ok(answer)
+            }
+            GroundingAction::Regenerate { max_retries } if retries < max_retries => Err(format!(
+                "Your answer contains claims which are not supported by the provided documents:\n\
+                 {claims}\nAnswer again, using only the information of the documents."
+            )),
+            GroundingAction::Annotate | GroundingAction::Regenerate { .. } => Ok(format!(
+                "{answer}\n\nNote: the following statements are not supported by the sources:\n{claims}"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        completion::{CompletionRequest, Message, Prompt},
+        message::{AssistantContent, UserContent},
+        test_utils::{response, MockModel},
+    };
+
+    fn last_prompt(request: &CompletionRequest) -> String {
+        match &request.prompt {
+            Message::User { content, .. } => content
+                .iter()
+                .filter_map(|content| match content {
+                    UserContent::Text(text) => Some(text.text.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => String::new(),
+        }
+    }
+
+    /// Answers with a hallucination, then with the documents once asked to answer again
+    fn mock() -> MockModel {
+        MockModel::reply(|request| {
+            if last_prompt(&request).contains("Answer again") {
+                "The office opens at 9am.".to_string()
+            } else {
+                "The office opens at 9am and has a swimming pool.".to_string()
+            }
+        })
+    }
+
+    /// Flags the claims about swimming pools
+    fn judge() -> MockModel {
+        MockModel::new(|request| {
+            let prompt = last_prompt(&request);
+            let answer = prompt.split("<answer>").nth(1).unwrap_or_default();
+            let verdict = if answer.contains("swimming pool") {
+                json!({"supported": false, "unsupported_claims": ["The office has a swimming pool."]})
+            } else {
+                json!({"supported": true, "unsupported_claims": []})
+            };
+            Ok(response(
+                AssistantContent::tool_call("call_0", "submit", verdict),
+                (),
+            ))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_grounding() {
+        let agent = |action| {
+            AgentBuilder::new(mock())
+                .context("The office opens at 9am.")
+                .grounding(GroundingChecker::new(judge()).action(action))
+                .build()
+        };
+
+        assert_eq!(
+            agent(GroundingAction::Flag).prompt("When?").await.unwrap(),
+            "The office opens at 9am and has a swimming pool."
+        );
+        assert_eq!(
+            agent(GroundingAction::Annotate)
+                .prompt("When?")
+                .await
+                .unwrap(),
+            "The office opens at 9am and has a swimming pool.\n\nNote: the following statements \
+             are not supported by the sources:\n- The office has a swimming pool."
+        );
+        assert_eq!(
+            agent(GroundingAction::Regenerate { max_retries: 1 })
+                .prompt("When?")
+                .await
+                .unwrap(),
+            "The office opens at 9am."
+        );
+
+        // Answers without documents are not checked
+        let agent = AgentBuilder::new(mock())
+            .grounding(GroundingChecker::new(judge()))
+            .build();
+        assert_eq!(
+            agent.prompt("When?").await.unwrap(),
+            "The office opens at 9am and has a swimming pool."
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! This module provides [GuidedDecoding], typed constraints on the output of local models
+//! (GBNF grammars, regular expressions, choices and JSON schemas), for the servers enforcing them
+//! while sampling the tokens, e.g.: llama.cpp or vLLM (through their OpenAI-compatible API).
+//!
+//! Guided decoding guarantees that the output matches the constraint, which makes extraction
+//! with small local models reliable. The constraints are sent as additional parameters of the
+//! completion requests, whose names depend on the [server](GuidedBackend).
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::CompletionModel,
+//!     guided::{Constraint, GuidedBackend, GuidedDecoding},
+//!     providers::openai,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! // vLLM server
+//! let client = openai::Client::from_url("", "http://localhost:8000/v1");
+//! let model = client.completion_model("Qwen/Qwen2.5-7B-Instruct");
+//!
+//! let guided = GuidedDecoding::new(
+//!     GuidedBackend::Vllm,
+//!     Constraint::Regex(r"\d{4}-\d{2}-\d{2}".to_string()),
+//! )?;
+//!
+//! let response = model
+//!     .completion_request("When did the Apollo 11 mission land on the moon?")
+//!     .guided_decoding(&guided)
+//!     .send()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+#[derive(Debug, thiserror::Error)]
+pub enum GuidedDecodingError {
+    /// The server does not support the constraint
+    #[error("{backend:?} does not support {constraint} constraints")]
+    Unsupported {
+        backend: GuidedBackend,
+        constraint: &'static str,
+    },
+
+    /// The regular expression is invalid
+    #[error("Invalid regex: {0}")]
+    InvalidRegex(#[from] regex::Error),
+
+    /// The GBNF grammar is invalid
+    #[error("Invalid grammar: {0}")]
+    InvalidGrammar(String),
+}
+
+/// The server enforcing the constraints, which defines the names of their parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuidedBackend {
+    /// llama.cpp server: `grammar` and `json_schema` parameters (regexes are not supported)
+    LlamaCpp,
+    /// vLLM server: `guided_grammar`, `guided_regex`, `guided_choice` and `guided_json`
+    /// parameters
+    Vllm,
+}
+
+/// A constraint on the output of a model.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    /// A grammar in the GBNF format, whose start rule is `root`
+    Grammar(String),
+    /// A regular expression the whole output should match
+    Regex(String),
+    /// One of the given strings
+    Choice(Vec<String>),
+    /// A JSON value matching the given JSON schema
+    JsonSchema(Value),
+}
+
+impl Constraint {
+    /// A JSON value matching the JSON schema of `T`.
+    pub fn json_schema_for<T: JsonSchema>() -> Self {
+        Constraint::JsonSchema(json!(schemars::schema_for!(T)))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Constraint::Grammar(_) => "grammar",
+            Constraint::Regex(_) => "regex",
+            Constraint::Choice(_) => "choice",
+            Constraint::JsonSchema(_) => "JSON schema",
+        }
+    }
+}
+
+/// A [Constraint] validated for a [GuidedBackend].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuidedDecoding {
+    backend: GuidedBackend,
+    constraint: Constraint,
+}
+
+impl GuidedDecoding {
+    /// Check that the constraint is valid and supported by the server.
+    pub fn new(
+        backend: GuidedBackend,
+        constraint: Constraint,
+    ) -> Result<Self, GuidedDecodingError> {
+        match (&constraint, backend) {
+            (Constraint::Regex(_), GuidedBackend::LlamaCpp) => {
+                return Err(GuidedDecodingError::Unsupported {
+                    backend,
+                    constraint: constraint.name(),
+                })
+            }
+            (Constraint::Regex(regex), _) => {
+                regex::Regex::new(regex)?;
+            }
+            (Constraint::Grammar(grammar), _) => {
+                let has_root = grammar.lines().any(|line| {
+                    line.trim_start()
+                        .strip_prefix("root")
+                        .is_some_and(|rest| rest.trim_start().starts_with("::="))
+                });
+                if !has_root {
+                    return Err(GuidedDecodingError::InvalidGrammar(
+                        "missing `root` rule".to_string(),
+                    ));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(Self {
+            backend,
+            constraint,
+        })
+    }
+
+    pub fn backend(&self) -> GuidedBackend {
+        self.backend
+    }
+
+    pub fn constraint(&self) -> &Constraint {
+        &self.constraint
+    }
+
+    /// The additional parameters of the completion requests enforcing the constraint.
+    pub fn params(&self) -> Value {
+        match (&self.constraint, self.backend) {
+            (Constraint::Grammar(grammar), GuidedBackend::LlamaCpp) => json!({"grammar": grammar}),
+            (Constraint::JsonSchema(schema), GuidedBackend::LlamaCpp) => {
+                json!({"json_schema": schema})
+            }
+            (Constraint::Choice(choices), GuidedBackend::LlamaCpp) => {
+                json!({"grammar": choice_grammar(choices)})
+            }
+            // Rejected by `GuidedDecoding::new`
+            (Constraint::Regex(_), GuidedBackend::LlamaCpp) => json!({}),
+            (Constraint::Grammar(grammar), GuidedBackend::Vllm) => {
+                json!({"guided_grammar": grammar})
+            }
+            (Constraint::Regex(regex), GuidedBackend::Vllm) => json!({"guided_regex": regex}),
+            (Constraint::Choice(choices), GuidedBackend::Vllm) => {
+                json!({"guided_choice": choices})
+            }
+            (Constraint::JsonSchema(schema), GuidedBackend::Vllm) => {
+                json!({"guided_json": schema})
+            }
+        }
+    }
+}
+
+/// A GBNF grammar matching one of the choices.
+fn choice_grammar(choices: &[String]) -> String {
+    let alternatives = choices
+        .iter()
+        .map(|choice| serde_json::to_string(choice).expect("Strings serialize"))
+        .collect::<Vec<_>>();
+    format!("root ::= {}", alternatives.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params() {
+        let choice = Constraint::Choice(vec!["yes".to_string(), "no \"way\"".to_string()]);
+
+        assert_eq!(
+            GuidedDecoding::new(GuidedBackend::LlamaCpp, choice.clone())
+                .unwrap()
+                .params(),
+            json!({"grammar": r#"root ::= "yes" | "no \"way\"""#})
+        );
+        assert_eq!(
+            GuidedDecoding::new(GuidedBackend::Vllm, choice)
+                .unwrap()
+                .params(),
+            json!({"guided_choice": ["yes", "no \"way\""]})
+        );
+        assert_eq!(
+            GuidedDecoding::new(GuidedBackend::Vllm, Constraint::Regex(r"\d+".to_string()))
+                .unwrap()
+                .params(),
+            json!({"guided_regex": r"\d+"})
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(matches!(
+            GuidedDecoding::new(
+                GuidedBackend::LlamaCpp,
+                Constraint::Regex(r"\d+".to_string())
+            ),
+            Err(GuidedDecodingError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            GuidedDecoding::new(GuidedBackend::Vllm, Constraint::Regex(r"(\d+".to_string())),
+            Err(GuidedDecodingError::InvalidRegex(_))
+        ));
+        assert!(matches!(
+            GuidedDecoding::new(
+                GuidedBackend::LlamaCpp,
+                Constraint::Grammar(r#"answer ::= "yes""#.to_string())
+            ),
+            Err(GuidedDecodingError::InvalidGrammar(_))
+        ));
+        assert!(GuidedDecoding::new(
+            GuidedBackend::LlamaCpp,
+            Constraint::Grammar("root ::= answer\nanswer ::= \"yes\" | \"no\"".to_string())
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,86 @@
+//! This module provides [RequestOptions], the extra HTTP headers and query parameters sent with
+//! a single completion or embedding request, in addition to the ones of the provider client
+//! (e.g.: an `OpenAI-Organization` header, an idempotency key, or the routing hints of a
+//! gateway).
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::CompletionModel, providers::openai};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let model = openai::Client::new("your-openai-api-key").completion_model(openai::GPT_4O);
+//!
+//! let response = model
+//!     .completion_request("Who are you?")
+//!     .header("OpenAI-Organization", "org-1234")
+//!     .query_param("api-version", "2024-10-21")
+//!     .send()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+/// Extra HTTP headers and query parameters of a request, see the [module documentation](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header. Headers of the client with the same name are replaced.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a query parameter.
+    pub fn query_param(mut self, name: &str, value: &str) -> Self {
+        self.query.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty() && self.query.is_empty()
+    }
+
+    /// Add the headers and query parameters to an HTTP request.
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = self.headers.iter().fold(request, |request, (name, value)| {
+            request.header(name, value)
+        });
+
+        if self.query.is_empty() {
+            request
+        } else {
+            request.query(&self.query)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let options = RequestOptions::new()
+            .header("Idempotency-Key", "key-1")
+            .query_param("api-version", "2024-10-21");
+
+        let request = options
+            .apply(reqwest::Client::new().post("https://api.example.com/v1/chat?stream=false"))
+            .build()
+            .unwrap();
+
+        assert_eq!(request.headers()["Idempotency-Key"], "key-1");
+        assert_eq!(
+            request.url().query(),
+            Some("stream=false&api-version=2024-10-21")
+        );
+    }
+}
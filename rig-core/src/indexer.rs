@@ -0,0 +1,401 @@
+//! This module provides [Indexer], which keeps a vector store in sync with a corpus of
+//! [Document]s, re-indexing only what changed since its previous run.
+//!
+//! The indexer records, for each source document, a hash of its content (text and metadata), the
+//! date-times at which it was updated and indexed, and the ids of its chunks, in an
+//! [IndexStateStore] (e.g.: a [JsonFileIndexState] next to the vector store). On each run:
+//! - new and changed documents are chunked and embedded, and their chunks are inserted into the
+//!   vector store (the chunks they no longer have are deleted),
+//! - unchanged documents are skipped,
+//! - documents which are no longer in the corpus have their chunks deleted.
+//!
+//! The vector store must implement [VectorStoreImport] (to insert the chunks with their ids) and
+//! [VectorStoreDelete].
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     indexer::{Indexer, JsonFileIndexState},
+//!     loaders::FileLoader,
+//!     providers::openai,
+//!     vector_store::in_memory_store::InMemoryVectorStore,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let model = openai::Client::from_env().embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+//! let indexer = Indexer::new(model, JsonFileIndexState::new("index/state.json"))
+//!     .chunker(|document| document.chunks(2000, 200));
+//!
+//! let mut store = InMemoryVectorStore::load_from_path("index/store.json").unwrap_or_default();
+//!
+//! let documents = FileLoader::with_glob("docs/**/*.md")?
+//!     .read_with_metadata()
+//!     .ignore_errors();
+//! let report = indexer.index(&mut store, documents).await?;
+//! println!("{report:?}");
+//!
+//! store.save_to_path("index/store.json")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    embeddings::{EmbedError, EmbeddingError, EmbeddingModel, EmbeddingsBuilder},
+    metadata::{rfc3339, Document},
+    vector_store::{VectorStoreDelete, VectorStoreError, VectorStoreImport},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("Embed error: {0}")]
+    EmbedError(#[from] EmbedError),
+
+    #[error("Embedding error: {0}")]
+    EmbeddingError(#[from] EmbeddingError),
+
+    #[error("Vector store error: {0}")]
+    VectorStoreError(#[from] VectorStoreError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+
+    #[error("Json error: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// The indexed state of a source document.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceState {
+    /// Hash of the text and metadata of the document
+    pub hash: String,
+    /// Last update date-time of the document (from its metadata), in RFC 3339 format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// Date-time at which the document was indexed, in RFC 3339 format
+    pub indexed_at: String,
+    /// Ids of the chunks of the document in the vector store
+    pub chunk_ids: Vec<String>,
+}
+
+/// The indexed states of the source documents, by document id.
+pub type IndexState = HashMap<String, SourceState>;
+
+/// Trait for the stores of the [IndexState] of an [Indexer].
+pub trait IndexStateStore: Send + Sync {
+    fn load(&self) -> Result<IndexState, IndexerError>;
+
+    fn save(&self, state: &IndexState) -> Result<(), IndexerError>;
+}
+
+/// An [IndexStateStore] keeping the state in memory, e.g.: with an in-memory vector store.
+#[derive(Debug, Default)]
+pub struct InMemoryIndexState {
+    state: Mutex<IndexState>,
+}
+
+impl IndexStateStore for InMemoryIndexState {
+    fn load(&self) -> Result<IndexState, IndexerError> {
+        Ok(self.state.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn save(&self, state: &IndexState) -> Result<(), IndexerError> {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = state.clone();
+        Ok(())
+    }
+}
+
+/// An [IndexStateStore] keeping the state in a JSON file. A missing file is an empty state.
+#[derive(Clone, Debug)]
+pub struct JsonFileIndexState {
+    path: PathBuf,
+}
+
+impl JsonFileIndexState {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl IndexStateStore for JsonFileIndexState {
+    fn load(&self) -> Result<IndexState, IndexerError> {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(IndexState::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, state: &IndexState) -> Result<(), IndexerError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write then rename, to never leave a truncated state
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_string_pretty(state)?)?;
+        fs::rename(temp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// The changes applied by a run of an [Indexer].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    /// Documents indexed for the first time
+    pub added: usize,
+    /// Documents re-indexed because they changed
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Documents deleted from the vector store because they were not in the corpus anymore
+    pub removed: usize,
+    /// Chunks embedded and inserted into the vector store
+    pub chunks_embedded: usize,
+    /// Chunks deleted from the vector store
+    pub chunks_deleted: usize,
+}
+
+type Chunker = Box<dyn Fn(&Document) -> Vec<Document> + Send + Sync>;
+
+/// Re-indexes the changed documents of a corpus, see the [module documentation](self).
+pub struct Indexer<M: EmbeddingModel, S: IndexStateStore = InMemoryIndexState> {
+    model: M,
+    state: S,
+    chunker: Chunker,
+}
+
+impl<M: EmbeddingModel, S: IndexStateStore> Indexer<M, S> {
+    /// Create an indexer embedding the chunks with the model, and recording its state in the
+    /// store. By default, the documents are split with `Document::chunks(1000, 100)`.
+    pub fn new(model: M, state: S) -> Self {
+        Self {
+            model,
+            state,
+            chunker: Box::new(|document| document.chunks(1000, 100)),
+        }
+    }
+
+    /// Set the function splitting the documents into chunks, e.g.: `loaders::git::chunk_code`
+    /// for source files. The chunks must have unique ids.
+    pub fn chunker(
+        mut self,
+        chunker: impl Fn(&Document) -> Vec<Document> + Send + Sync + 'static,
+    ) -> Self {
+        self.chunker = Box::new(chunker);
+        self
+    }
+
+    /// The current state of the index.
+    pub fn state(&self) -> Result<IndexState, IndexerError> {
+        self.state.load()
+    }
+
+    /// Sync the vector store with the documents, which are the whole corpus: the documents
+    /// indexed by a previous run which are not in `documents` are deleted.
+    ///
+    /// The state is only saved once the vector store is updated: if the run fails, the next
+    /// run re-indexes the same documents.
+    pub async fn index<V>(
+        &self,
+        store: &mut V,
+        documents: impl IntoIterator<Item = Document>,
+    ) -> Result<IndexReport, IndexerError>
+    where
+        V: VectorStoreImport<Document> + VectorStoreDelete,
+    {
+        let mut state = self.state.load()?;
+        let mut report = IndexReport::default();
+        let mut seen = HashSet::new();
+        let mut chunks = vec![];
+        let mut stale_ids = vec![];
+        let indexed_at = rfc3339(SystemTime::now());
+
+        for document in documents {
+            let id = document.metadata.id.clone();
+            if !seen.insert(id.clone()) {
+                tracing::warn!(target: "rig", "Duplicate document {id} skipped");
+                continue;
+            }
+
+            let hash = content_hash(&document);
+            let previous = state.get(&id);
+            if previous.is_some_and(|previous| previous.hash == hash) {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let document_chunks = (self.chunker)(&document);
+            let chunk_ids = document_chunks
+                .iter()
+                .map(|chunk| chunk.metadata.id.clone())
+                .collect::<Vec<_>>();
+            match previous {
+                Some(previous) => {
+                    report.updated += 1;
+                    stale_ids.extend(
+                        previous
+                            .chunk_ids
+                            .iter()
+                            .filter(|chunk_id| !chunk_ids.contains(chunk_id))
+                            .cloned(),
+                    );
+                }
+                None => report.added += 1,
+            }
+
+            state.insert(
+                id,
+                SourceState {
+                    hash,
+                    updated_at: document.metadata.updated_at.clone(),
+                    indexed_at: indexed_at.clone(),
+                    chunk_ids,
+                },
+            );
+            chunks.extend(document_chunks);
+        }
+
+        let removed = state
+            .keys()
+            .filter(|id| !seen.contains(*id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for id in removed {
+            if let Some(source) = state.remove(&id) {
+                report.removed += 1;
+                stale_ids.extend(source.chunk_ids);
+            }
+        }
+
+        if !chunks.is_empty() {
+            let embeddings = EmbeddingsBuilder::new(self.model.clone())
+                .documents(chunks)?
+                .build()
+                .await?;
+            report.chunks_embedded = embeddings.len();
+            store
+                .import(
+                    embeddings
+                        .into_iter()
+                        .map(|(chunk, embeddings)| (chunk.metadata.id.clone(), chunk, embeddings))
+                        .collect(),
+                )
+                .await?;
+        }
+        if !stale_ids.is_empty() {
+            report.chunks_deleted = stale_ids.len();
+            store.delete(stale_ids).await?;
+        }
+
+        self.state.save(&state)?;
+        tracing::info!(target: "rig", "Index updated: {report:?}");
+        Ok(report)
+    }
+}
+
+/// Hash of the text and metadata of a document, except its dates, so that documents which are
+/// only touched are not re-indexed. The hash (64-bit FNV-1a) is stable across runs and versions.
+fn content_hash(document: &Document) -> String {
+    let mut metadata = document.metadata.clone();
+    metadata.created_at = None;
+    metadata.updated_at = None;
+    let metadata = serde_json::to_string(&metadata).unwrap_or_default();
+
+    let hash = [metadata.as_bytes(), &[0], document.text.as_bytes()]
+        .concat()
+        .iter()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metadata::DocumentMetadata, test_utils::MockEmbeddingModel,
+        vector_store::in_memory_store::InMemoryVectorStore,
+    };
+
+    fn document(id: &str, text: &str) -> Document {
+        Document::new(DocumentMetadata::new(id), text)
+    }
+
+    #[tokio::test]
+    async fn test_incremental_index() {
+        // Embeds texts by their length
+        let model = MockEmbeddingModel::new(1, |text| vec![text.len() as f64]);
+        let indexer = Indexer::new(model.clone(), InMemoryIndexState::default())
+            .chunker(|document| document.chunks(10, 0));
+        let mut store = InMemoryVectorStore::default();
+
+        let report = indexer
+            .index(
+                &mut store,
+                [
+                    document("a.md", "alpha beta gamma"),
+                    document("b.md", "bravo"),
+                    document("c.md", "charlie"),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.added, 3);
+        assert_eq!(report.chunks_embedded, 4);
+        assert_eq!(store.len(), 4);
+
+        // a.md loses a chunk, b.md is unchanged but touched, c.md is removed
+        let mut touched = document("b.md", "bravo");
+        touched.metadata.updated_at = Some("2024-01-31T12:00:00Z".to_string());
+        let report = indexer
+            .index(&mut store, [document("a.md", "alpha"), touched])
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            IndexReport {
+                added: 0,
+                updated: 1,
+                unchanged: 1,
+                removed: 1,
+                chunks_embedded: 1,
+                chunks_deleted: 2,
+            }
+        );
+        assert_eq!(model.texts(), 5);
+
+        let mut ids = store.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, ["a.md#0", "b.md#0"]);
+        assert_eq!(indexer.state().unwrap()["a.md"].chunk_ids, ["a.md#0"]);
+    }
+
+    #[test]
+    fn test_json_file_index_state() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let store = JsonFileIndexState::new(dir.path().join("index/state.json"));
+        assert!(store.load().unwrap().is_empty());
+
+        let state = IndexState::from([(
+            "a.md".to_string(),
+            SourceState {
+                hash: content_hash(&document("a.md", "alpha")),
+                updated_at: None,
+                indexed_at: "2024-01-31T12:00:00Z".to_string(),
+                chunk_ids: vec!["a.md#0".to_string()],
+            },
+        )]);
+        store.save(&state).unwrap();
+        assert_eq!(store.load().unwrap(), state);
+    }
+}
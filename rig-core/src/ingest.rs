@@ -0,0 +1,463 @@
+//! This module provides [Ingestor], which feeds inbound events (e.g.: webhook requests or
+//! messages from a queue) into a pipeline, for event-driven automations.
+//!
+//! Events are JSON payloads deserialized into the input type of the pipeline and buffered in a
+//! bounded queue: [IngestHandle::send] waits when the queue is full, and [IngestHandle::try_send]
+//! fails with [IngestError::QueueFull] (e.g.: to answer a webhook with a `429` status). Events
+//! whose pipeline fails are retried, then saved in a [DeadLetterStore].
+//!
+//! This module is only available with the `ingest` feature, and requires a tokio runtime.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use rig::{
+//!     ingest::{InMemoryDeadLetterStore, Ingestor},
+//!     pipeline::{self, Op},
+//!     providers::openai,
+//! };
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Clone, Serialize, Deserialize)]
+//! struct Ticket {
+//!     id: u64,
+//!     body: String,
+//! }
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let agent = openai.agent(openai::GPT_4O).build();
+//!
+//! let triage = pipeline::new()
+//!     .map(|ticket: Ticket| format!("Triage this ticket: {}", ticket.body))
+//!     .prompt(agent);
+//!
+//! let dead_letters = Arc::new(InMemoryDeadLetterStore::default());
+//! let ingestor = Ingestor::new(triage)
+//!     .capacity(100)
+//!     .concurrency(4)
+//!     .dead_letters(dead_letters.clone())
+//!     .start();
+//!
+//! // E.g.: in a webhook handler
+//! ingestor.try_send(r#"{"id": 1, "body": "The app crashes on startup"}"#)?;
+//!
+//! // On shutdown, process the buffered events
+//! ingestor.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fmt::Display,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{
+    sync::{mpsc, Semaphore},
+    task::JoinHandle,
+};
+
+use crate::{pipeline::Op, test_mode};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IngestError {
+    #[error("InvalidPayloadError: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+
+    /// The queue is full, only returned by [IngestHandle::try_send]
+    #[error("QueueFullError: the event queue is full")]
+    QueueFull,
+
+    #[error("ClosedError: the ingestor was shut down")]
+    Closed,
+}
+
+/// An event whose processing failed after all its attempts.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: String,
+    /// The JSON payload of the event
+    pub payload: String,
+    /// Error of the last attempt
+    pub error: String,
+    pub attempts: usize,
+    /// Time of the last attempt, in milliseconds since the Unix epoch
+    pub failed_at: u64,
+}
+
+/// Destination of the events whose processing failed (e.g.: a table or a dead-letter queue).
+pub trait DeadLetterStore: Send + Sync {
+    fn save(&self, letter: DeadLetter);
+}
+
+/// A [DeadLetterStore] keeping the dead letters in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryDeadLetterStore {
+    letters: Mutex<Vec<DeadLetter>>,
+}
+
+impl InMemoryDeadLetterStore {
+    /// Get the dead letters.
+    pub fn letters(&self) -> Vec<DeadLetter> {
+        self.letters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Remove and return the dead letters (e.g.: to replay them with [IngestHandle::replay]).
+    pub fn take(&self) -> Vec<DeadLetter> {
+        std::mem::take(&mut *self.letters.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl DeadLetterStore for InMemoryDeadLetterStore {
+    fn save(&self, letter: DeadLetter) {
+        self.letters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(letter);
+    }
+}
+
+struct Envelope<T> {
+    id: String,
+    payload: String,
+    event: T,
+}
+
+enum Message<T> {
+    Event(Envelope<T>),
+    Shutdown,
+}
+
+/// Builder of an event ingestor, see the [module documentation](self).
+pub struct Ingestor<P, T> {
+    pipeline: P,
+    capacity: usize,
+    concurrency: usize,
+    max_attempts: usize,
+    retry_delay: Duration,
+    dead_letters: Option<Arc<dyn DeadLetterStore>>,
+    _t: PhantomData<fn(T)>,
+}
+
+impl<P, T, O, E> Ingestor<P, T>
+where
+    P: Op<Input = T, Output = Result<O, E>> + 'static,
+    T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    O: Send + Sync,
+    E: Display + Send + Sync,
+{
+    /// Create an ingestor feeding the events to `pipeline`. A pipeline returning an error fails
+    /// the processing of the event.
+    pub fn new(pipeline: P) -> Self {
+        Self {
+            pipeline,
+            capacity: 64,
+            concurrency: 1,
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(1),
+            dead_letters: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Set the number of events buffered before [IngestHandle::send] waits (defaults to 64).
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the number of events processed concurrently (defaults to 1).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the number of times the processing of an event is attempted before it is sent to
+    /// the dead letters (defaults to 3).
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry of an event (defaults to 1 second). The delay
+    /// doubles after every attempt.
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Save the events whose processing failed in the given store. Without store, they are
+    /// only logged.
+    pub fn dead_letters(mut self, store: Arc<dyn DeadLetterStore>) -> Self {
+        self.dead_letters = Some(store);
+        self
+    }
+
+    /// Start processing the events in a background task.
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn start(self) -> IngestHandle<T> {
+        let (sender, mut receiver) = mpsc::channel::<Message<T>>(self.capacity);
+        let pipeline = Arc::new(self.pipeline);
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let (concurrency, max_attempts, retry_delay) =
+            (self.concurrency, self.max_attempts, self.retry_delay);
+        let dead_letters = self.dead_letters;
+
+        let dispatcher = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                let envelope = match message {
+                    Message::Event(envelope) => envelope,
+                    // Stop accepting events, but process the buffered ones
+                    Message::Shutdown => {
+                        receiver.close();
+                        continue;
+                    }
+                };
+
+                // Waiting for a permit leaves the events in the queue, which applies the
+                // backpressure to the senders
+                let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                    break;
+                };
+                let pipeline = pipeline.clone();
+                let dead_letters = dead_letters.clone();
+                tokio::spawn(async move {
+                    process(
+                        &*pipeline,
+                        envelope,
+                        max_attempts,
+                        retry_delay,
+                        dead_letters,
+                    )
+                    .await;
+                    drop(permit);
+                });
+            }
+
+            // Wait for the events in progress
+            let _ = semaphore.acquire_many(concurrency as u32).await;
+        });
+
+        IngestHandle {
+            sender,
+            dispatcher: Arc::new(tokio::sync::Mutex::new(Some(dispatcher))),
+        }
+    }
+}
+
+async fn process<P, T, O, E>(
+    pipeline: &P,
+    envelope: Envelope<T>,
+    max_attempts: usize,
+    retry_delay: Duration,
+    dead_letters: Option<Arc<dyn DeadLetterStore>>,
+) where
+    P: Op<Input = T, Output = Result<O, E>>,
+    T: Clone + Send + Sync,
+    E: Display,
+{
+    let mut delay = retry_delay;
+
+    for attempt in 1..=max_attempts {
+        let error = match pipeline.call(envelope.event.clone()).await {
+            Ok(_) => return,
+            Err(e) => e.to_string(),
+        };
+
+        if attempt < max_attempts {
+            tracing::warn!(
+                "Processing of event {} failed (attempt {attempt}/{max_attempts}): {error}",
+                envelope.id
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            continue;
+        }
+
+        tracing::error!(
+            "Processing of event {} failed after {max_attempts} attempts: {error}",
+            envelope.id
+        );
+        if let Some(store) = &dead_letters {
+            store.save(DeadLetter {
+                id: envelope.id.clone(),
+                payload: envelope.payload.clone(),
+                error,
+                attempts: max_attempts,
+                failed_at: now_ms(),
+            });
+        }
+    }
+}
+
+/// Handle to send events to a started [Ingestor]. Clones of a handle send to the same ingestor.
+pub struct IngestHandle<T> {
+    sender: mpsc::Sender<Message<T>>,
+    dispatcher: Arc<tokio::sync::Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl<T> Clone for IngestHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            dispatcher: self.dispatcher.clone(),
+        }
+    }
+}
+
+impl<T> IngestHandle<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn envelope(payload: &str) -> Result<Envelope<T>, IngestError> {
+        Ok(Envelope {
+            id: test_mode::generate_id("event"),
+            payload: payload.to_string(),
+            event: serde_json::from_str(payload)?,
+        })
+    }
+
+    /// Send a JSON payload, waiting for room in the queue if it is full. Returns the ID of the
+    /// event.
+    pub async fn send(&self, payload: &str) -> Result<String, IngestError> {
+        let envelope = Self::envelope(payload)?;
+        let id = envelope.id.clone();
+        self.sender
+            .send(Message::Event(envelope))
+            .await
+            .map_err(|_| IngestError::Closed)?;
+        Ok(id)
+    }
+
+    /// Send a JSON payload, failing with [IngestError::QueueFull] if the queue is full. Returns
+    /// the ID of the event.
+    pub fn try_send(&self, payload: &str) -> Result<String, IngestError> {
+        let envelope = Self::envelope(payload)?;
+        let id = envelope.id.clone();
+        self.sender
+            .try_send(Message::Event(envelope))
+            .map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => IngestError::QueueFull,
+                mpsc::error::TrySendError::Closed(_) => IngestError::Closed,
+            })?;
+        Ok(id)
+    }
+
+    /// Send an event, waiting for room in the queue if it is full. Returns the ID of the event.
+    pub async fn send_event(&self, event: T) -> Result<String, IngestError> {
+        self.send(&serde_json::to_string(&event)?).await
+    }
+
+    /// Send a dead letter again, e.g.: after fixing the cause of its failure.
+    pub async fn replay(&self, letter: DeadLetter) -> Result<String, IngestError> {
+        self.send(&letter.payload).await
+    }
+
+    /// Stop accepting events, and wait for the processing of the events already sent.
+    pub async fn shutdown(&self) {
+        let _ = self.sender.send(Message::Shutdown).await;
+        if let Some(dispatcher) = self.dispatcher.lock().await.take() {
+            let _ = dispatcher.await;
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::pipeline;
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    struct Order {
+        amount: i64,
+    }
+
+    #[tokio::test]
+    async fn test_ingest() {
+        let processed = Arc::new(AtomicUsize::new(0));
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let (processed_clone, attempts_clone) = (processed.clone(), attempts.clone());
+
+        let pipeline = pipeline::new().map(move |order: Order| {
+            attempts_clone.fetch_add(1, Ordering::SeqCst);
+            if order.amount < 0 {
+                return Err(format!("Invalid amount: {}", order.amount));
+            }
+            processed_clone.fetch_add(1, Ordering::SeqCst);
+            Ok(order.amount)
+        });
+
+        let dead_letters = Arc::new(InMemoryDeadLetterStore::default());
+        let ingestor = Ingestor::new(pipeline)
+            .concurrency(2)
+            .max_attempts(2)
+            .retry_delay(Duration::from_millis(1))
+            .dead_letters(dead_letters.clone())
+            .start();
+
+        ingestor.send(r#"{"amount": 10}"#).await.unwrap();
+        ingestor.send_event(Order { amount: 20 }).await.unwrap();
+        let id = ingestor.try_send(r#"{"amount": -5}"#).unwrap();
+        assert!(matches!(
+            ingestor.try_send(r#"{"total": 10}"#),
+            Err(IngestError::InvalidPayload(_))
+        ));
+
+        ingestor.shutdown().await;
+        assert!(matches!(
+            ingestor.send(r#"{"amount": 30}"#).await,
+            Err(IngestError::Closed)
+        ));
+
+        assert_eq!(processed.load(Ordering::SeqCst), 2);
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+        let letters = dead_letters.take();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].id, id);
+        assert_eq!(letters[0].payload, r#"{"amount": -5}"#);
+        assert_eq!(letters[0].error, "Invalid amount: -5");
+        assert_eq!(letters[0].attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_backpressure() {
+        let pipeline = pipeline::new().then(|order: Order| async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok::<_, String>(order.amount)
+        });
+        let ingestor = Ingestor::new(pipeline).capacity(1).start();
+
+        // The first event is processed, the second one waits for a permit and the third one
+        // fills the queue
+        for amount in 1..=3 {
+            ingestor
+                .try_send(&format!(r#"{{"amount": {amount}}}"#))
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(matches!(
+            ingestor.try_send(r#"{"amount": 4}"#),
+            Err(IngestError::QueueFull)
+        ));
+
+        ingestor.shutdown().await;
+    }
+}
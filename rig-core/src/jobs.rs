@@ -0,0 +1,461 @@
+//! This module provides [JobRunner], a runner of long agent or pipeline executions in background
+//! tasks, so that they can be started from a request handler and polled later.
+//!
+//! Each job gets an ID, which can be used to poll its [JobInfo] (status, progress and output),
+//! wait for its completion or cancel it. Status and progress changes are also broadcast as
+//! [JobEvent]s, and can be persisted with a [JobStore].
+//!
+//! This module is only available with the `jobs` feature, and requires a tokio runtime.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, jobs::JobRunner, providers::openai};
+//!
+//! # async fn run() {
+//! let openai = openai::Client::from_env();
+//! let agent = openai.agent(openai::GPT_4O).build();
+//!
+//! let runner = JobRunner::new();
+//!
+//! let id = runner.submit("report", move |ctx| async move {
+//!     ctx.progress(0.0, "Writing the report");
+//!     let report = agent.prompt("Write a report on the state of Rust web frameworks").await?;
+//!     ctx.progress(1.0, "Done");
+//!     Ok::<_, rig::completion::PromptError>(report)
+//! });
+//!
+//! // Later, e.g.: in another request handler
+//! if let Some(job) = runner.get(&id) {
+//!     println!("{:?} {:?}", job.status, job.progress);
+//! }
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+use crate::{cancellation::CancellationToken, test_mode};
+
+/// The status of a job.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed { output: serde_json::Value },
+    Failed { error: String },
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job is done (completed, failed or cancelled).
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobStatus::Running)
+    }
+}
+
+/// The progress reported by a job.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Progress {
+    /// Fraction of the work done, between 0 and 1
+    pub fraction: f64,
+    pub message: String,
+}
+
+/// The state of a job.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub status: JobStatus,
+    pub progress: Option<Progress>,
+    /// Creation time, in milliseconds since the Unix epoch
+    pub created_at: u64,
+    /// Time of the last status or progress change, in milliseconds since the Unix epoch
+    pub updated_at: u64,
+}
+
+/// A change of the status or progress of a job. The event contains the new state of the job.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobEvent(pub JobInfo);
+
+/// Persistence hook for jobs. [JobStore::save] is called with the new state of a job every time
+/// its status or progress changes.
+pub trait JobStore: Send + Sync {
+    fn save(&self, job: &JobInfo);
+
+    /// Load the jobs saved previously (e.g.: before a restart).
+    fn load(&self) -> Vec<JobInfo> {
+        vec![]
+    }
+}
+
+/// Handle given to a running job to report its progress and check for cancellation.
+#[derive(Clone)]
+pub struct JobContext {
+    id: String,
+    runner: JobRunner,
+    token: CancellationToken,
+}
+
+impl JobContext {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Report the progress of the job.
+    pub fn progress(&self, fraction: f64, message: &str) {
+        self.runner.update(&self.id, |job| {
+            job.progress = Some(Progress {
+                fraction: fraction.clamp(0.0, 1.0),
+                message: message.to_string(),
+            })
+        });
+    }
+
+    /// Whether the job was cancelled. Jobs are aborted at their next `.await` point when
+    /// cancelled, so this is only needed by jobs doing long blocking work between `.await`s.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// A token cancelled along with the job, e.g.: to abort requests made outside of the job's
+    /// task.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.child_token()
+    }
+}
+
+struct JobEntry {
+    info: JobInfo,
+    token: CancellationToken,
+    handle: Option<JoinHandle<()>>,
+}
+
+struct JobRunnerInner {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    events: broadcast::Sender<JobEvent>,
+    store: Option<Arc<dyn JobStore>>,
+}
+
+/// Runs jobs in background tasks. Clones of a [JobRunner] share the same jobs.
+#[derive(Clone)]
+pub struct JobRunner {
+    inner: Arc<JobRunnerInner>,
+}
+
+impl Default for JobRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobRunner {
+    pub fn new() -> Self {
+        Self::with_store_opt(None)
+    }
+
+    /// Create a runner persisting its jobs in the given store. The jobs previously saved in the
+    /// store are loaded; the ones that were still running are marked as failed.
+    pub fn with_store(store: Arc<dyn JobStore>) -> Self {
+        let runner = Self::with_store_opt(Some(store.clone()));
+
+        let mut jobs = runner.jobs();
+        for mut info in store.load() {
+            if !info.status.is_finished() {
+                info.status = JobStatus::Failed {
+                    error: "Interrupted".to_string(),
+                };
+                info.updated_at = now_ms();
+                store.save(&info);
+            }
+            jobs.insert(
+                info.id.clone(),
+                JobEntry {
+                    info,
+                    token: CancellationToken::new(),
+                    handle: None,
+                },
+            );
+        }
+        drop(jobs);
+
+        runner
+    }
+
+    fn with_store_opt(store: Option<Arc<dyn JobStore>>) -> Self {
+        Self {
+            inner: Arc::new(JobRunnerInner {
+                jobs: Mutex::new(HashMap::new()),
+                events: broadcast::channel(256).0,
+                store,
+            }),
+        }
+    }
+
+    /// Start a job in a background task and return its ID. The output of the job is stored
+    /// as JSON.
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn submit<F, Fut, T, E>(&self, name: &str, job: F) -> String
+    where
+        F: FnOnce(JobContext) -> Fut,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        T: Serialize,
+        E: Display,
+    {
+        let id = test_mode::generate_id("job");
+        let token = CancellationToken::new();
+        let now = now_ms();
+        let info = JobInfo {
+            id: id.clone(),
+            name: name.to_string(),
+            status: JobStatus::Running,
+            progress: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        // Register the job before spawning it, so that it can report progress right away
+        self.jobs().insert(
+            id.clone(),
+            JobEntry {
+                info: info.clone(),
+                token: token.clone(),
+                handle: None,
+            },
+        );
+        self.publish(&info);
+
+        let future = job(JobContext {
+            id: id.clone(),
+            runner: self.clone(),
+            token,
+        });
+        let runner = self.clone();
+        let job_id = id.clone();
+        let handle = tokio::spawn(async move {
+            let status = match future.await {
+                Ok(output) => match serde_json::to_value(output) {
+                    Ok(output) => JobStatus::Completed { output },
+                    Err(e) => JobStatus::Failed {
+                        error: format!("Failed to serialize the output: {e}"),
+                    },
+                },
+                Err(e) => JobStatus::Failed {
+                    error: e.to_string(),
+                },
+            };
+            runner.finish(&job_id, status);
+        });
+
+        if let Some(entry) = self.jobs().get_mut(&id) {
+            if !entry.info.status.is_finished() {
+                entry.handle = Some(handle);
+            }
+        }
+        id
+    }
+
+    /// The current state of a job.
+    pub fn get(&self, id: &str) -> Option<JobInfo> {
+        self.jobs().get(id).map(|entry| entry.info.clone())
+    }
+
+    /// The current state of all the jobs, most recent first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs = self
+            .jobs()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect::<Vec<_>>();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    /// Cancel a running job. Returns false if the job does not exist or is already finished.
+    pub fn cancel(&self, id: &str) -> bool {
+        let handle = match self.jobs().get_mut(id) {
+            Some(entry) if !entry.info.status.is_finished() => {
+                entry.token.cancel();
+                entry.handle.take()
+            }
+            _ => return false,
+        };
+
+        if let Some(handle) = handle {
+            handle.abort();
+        }
+        self.finish(id, JobStatus::Cancelled);
+        true
+    }
+
+    /// Wait for a job to finish and return its final state, or `None` if the job does not exist.
+    pub async fn wait(&self, id: &str) -> Option<JobInfo> {
+        let mut events = self.subscribe();
+
+        loop {
+            let job = self.get(id)?;
+            if job.status.is_finished() {
+                return Some(job);
+            }
+
+            loop {
+                match events.recv().await {
+                    Ok(JobEvent(job)) if job.id == id && job.status.is_finished() => {
+                        return Some(job)
+                    }
+                    Ok(_) => continue,
+                    // Missed some events, check the state of the job again
+                    Err(broadcast::error::RecvError::Lagged(_)) => break,
+                    Err(broadcast::error::RecvError::Closed) => return self.get(id),
+                }
+            }
+        }
+    }
+
+    /// Subscribe to the status and progress changes of all the jobs.
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.inner.events.subscribe()
+    }
+
+    /// Remove the finished jobs from the runner (they stay in the store, if any).
+    pub fn prune(&self) {
+        self.jobs()
+            .retain(|_, entry| !entry.info.status.is_finished());
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, HashMap<String, JobEntry>> {
+        self.inner.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn finish(&self, id: &str, status: JobStatus) {
+        self.update(id, |job| job.status = status);
+    }
+
+    /// Update a running job and publish its new state.
+    fn update(&self, id: &str, f: impl FnOnce(&mut JobInfo)) {
+        let info = {
+            let mut jobs = self.jobs();
+            let Some(entry) = jobs.get_mut(id) else {
+                return;
+            };
+            if entry.info.status.is_finished() {
+                return;
+            }
+            f(&mut entry.info);
+            entry.info.updated_at = now_ms();
+            if entry.info.status.is_finished() {
+                entry.handle = None;
+            }
+            entry.info.clone()
+        };
+
+        self.publish(&info);
+    }
+
+    fn publish(&self, info: &JobInfo) {
+        if let Some(store) = &self.inner.store {
+            store.save(info);
+        }
+        // No receivers is not an error
+        let _ = self.inner.events.send(JobEvent(info.clone()));
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, JobInfo>>);
+
+    impl JobStore for MemoryStore {
+        fn save(&self, job: &JobInfo) {
+            self.0.lock().unwrap().insert(job.id.clone(), job.clone());
+        }
+
+        fn load(&self) -> Vec<JobInfo> {
+            self.0.lock().unwrap().values().cloned().collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let store = Arc::new(MemoryStore::default());
+        let runner = JobRunner::with_store(store.clone());
+        let mut events = runner.subscribe();
+
+        let id = runner.submit("ok", |ctx| async move {
+            ctx.progress(0.5, "Halfway");
+            Ok::<_, String>(vec![1, 2])
+        });
+        let job = runner.wait(&id).await.unwrap();
+        assert_eq!(
+            job.status,
+            JobStatus::Completed {
+                output: serde_json::json!([1, 2])
+            }
+        );
+        assert_eq!(job.progress.unwrap().message, "Halfway");
+
+        let statuses = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|JobEvent(job)| job.progress.is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec![false, true, true]);
+
+        let id = runner.submit("error", |_| async { Err::<(), _>("Boom") });
+        assert_eq!(
+            runner.wait(&id).await.unwrap().status,
+            JobStatus::Failed {
+                error: "Boom".into()
+            }
+        );
+        assert_eq!(store.load().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_restore() {
+        let store = Arc::new(MemoryStore::default());
+        let runner = JobRunner::with_store(store.clone());
+
+        let id = runner.submit("slow", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<_, String>(())
+        });
+        assert_eq!(runner.get(&id).unwrap().status, JobStatus::Running);
+        let interrupted = runner.submit("interrupted", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<_, String>(())
+        });
+
+        assert!(runner.cancel(&id));
+        assert!(!runner.cancel(&id));
+        assert_eq!(runner.wait(&id).await.unwrap().status, JobStatus::Cancelled);
+
+        // Simulate a restart while the second job is running
+        let restored = JobRunner::with_store(store);
+        assert_eq!(restored.list().len(), 2);
+        assert!(matches!(
+            restored.get(&interrupted).unwrap().status,
+            JobStatus::Failed { .. }
+        ));
+        runner.cancel(&interrupted);
+    }
+}
@@ -1,5 +1,6 @@
-use serde::de::{self, Deserializer, SeqAccess, Visitor};
-use serde::Deserialize;
+use serde::de::{self, DeserializeOwned, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::fmt;
 use std::marker::PhantomData;
@@ -44,7 +45,7 @@ pub mod stringified_json {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        serde_json::from_str(&s).map_err(serde::de::Error::custom)
+        super::from_str_repaired(&s).map_err(serde::de::Error::custom)
     }
 }
 
@@ -140,6 +141,275 @@ where
     deserializer.deserialize_any(NullOrVec(PhantomData))
 }
 
+/// Repair near-JSON text produced by a model, e.g.:
+/// - JSON wrapped in a markdown code block, or surrounded by prose
+/// - single-quoted strings
+/// - trailing commas in objects and arrays
+/// - unescaped newlines and tabs in strings
+/// - Python literals (`True`, `False` and `None`)
+///
+/// The repaired text is not guaranteed to be valid JSON.
+pub fn repair(text: &str) -> String {
+    let text = extract_json(strip_code_fence(text.trim()));
+
+    let mut repaired = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    // The quote of the string being read, if any
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => match c {
+                '\\' => match chars.next() {
+                    // `\'` is not a valid escape sequence in JSON
+                    Some('\'') => repaired.push('\''),
+                    Some(escaped) => {
+                        repaired.push('\\');
+                        repaired.push(escaped);
+                    }
+                    None => repaired.push_str("\\\\"),
+                },
+                c if c == q => {
+                    repaired.push('"');
+                    quote = None;
+                }
+                '"' => repaired.push_str("\\\""),
+                '\n' => repaired.push_str("\\n"),
+                '\r' => repaired.push_str("\\r"),
+                '\t' => repaired.push_str("\\t"),
+                c => repaired.push(c),
+            },
+            None => match c {
+                '"' | '\'' => {
+                    repaired.push('"');
+                    quote = Some(c);
+                }
+                '}' | ']' => {
+                    let trimmed = repaired.trim_end();
+                    if trimmed.ends_with(',') {
+                        repaired.truncate(trimmed.len() - 1);
+                    }
+                    repaired.push(c);
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let mut word = c.to_string();
+                    while let Some(&next) = chars.peek() {
+                        if !next.is_ascii_alphanumeric() && next != '_' {
+                            break;
+                        }
+                        word.push(next);
+                        chars.next();
+                    }
+                    repaired.push_str(match word.as_str() {
+                        "True" => "true",
+                        "False" => "false",
+                        "None" => "null",
+                        word => word,
+                    });
+                }
+                c => repaired.push(c),
+            },
+        }
+    }
+
+    repaired
+}
+
+/// The content of the first markdown code block of `text`, if any.
+fn strip_code_fence(text: &str) -> &str {
+    let Some(start) = text.find("```") else {
+        return text;
+    };
+    let block = &text[start + 3..];
+    // Skip the language tag (e.g.: ```json)
+    let block = match block.find('\n') {
+        Some(newline) if !block[..newline].contains(['{', '[']) => &block[newline + 1..],
+        _ => block,
+    };
+    match block.find("```") {
+        Some(end) => block[..end].trim(),
+        None => block.trim(),
+    }
+}
+
+/// The JSON object or array of `text`, dropping the surrounding prose.
+fn extract_json(text: &str) -> &str {
+    let Some(start) = text.find(['{', '[']) else {
+        return text;
+    };
+    let close = if text[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    match text.rfind(close) {
+        Some(end) if end > start => &text[start..=end],
+        _ => &text[start..],
+    }
+}
+
+/// Deserialize the text produced by a model, [repairing](repair) it if it is not valid JSON.
+/// If the repaired text cannot be deserialized either, the error of the original text is returned.
+pub fn from_str_repaired<T: DeserializeOwned>(text: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(text)
+        .or_else(|error| serde_json::from_str(&repair(text)).map_err(|_| error))
+}
+
+/// How strictly the responses of the providers are deserialized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeserializationMode {
+    /// Unknown fields are ignored, and missing (or `null`) fields are filled with an empty
+    /// value accepted by their type (`null`, `""`, `0`, `false`, `[]` or `{}`) instead of
+    /// failing the whole parse.
+    #[default]
+    Lenient,
+    /// Unknown fields and missing fields are errors. Useful in tests, to detect changes of the
+    /// provider APIs.
+    Strict,
+}
+
+/// The values tried, in order, to fill a missing field in [DeserializationMode::Lenient] mode.
+fn empty_values() -> [serde_json::Value; 6] {
+    [
+        serde_json::Value::Null,
+        serde_json::json!(""),
+        serde_json::json!(0),
+        serde_json::json!(false),
+        serde_json::json!([]),
+        serde_json::json!({}),
+    ]
+}
+
+const MAX_REPAIRS: usize = 64;
+
+/// Deserialize `s` according to the given [DeserializationMode].
+pub fn from_str_with_mode<T>(s: &str, mode: DeserializationMode) -> Result<T, serde_json::Error>
+where
+    T: DeserializeOwned + Serialize,
+{
+    from_value_with_mode(serde_json::from_str(s)?, mode)
+}
+
+/// Deserialize `value` according to the given [DeserializationMode].
+pub fn from_value_with_mode<T>(
+    value: serde_json::Value,
+    mode: DeserializationMode,
+) -> Result<T, serde_json::Error>
+where
+    T: DeserializeOwned + Serialize,
+{
+    match mode {
+        DeserializationMode::Strict => {
+            let parsed = T::deserialize(&value)?;
+            // Fields that are not deserialized are not serialized back
+            match unknown_field(&value, &serde_json::to_value(&parsed)?) {
+                Some(path) => Err(de::Error::custom(format!("unknown field `{path}`"))),
+                None => Ok(parsed),
+            }
+        }
+        DeserializationMode::Lenient => {
+            let mut value = value;
+            let mut error = None;
+            // The index of the next empty value to try, for each filled field
+            let mut filled: HashMap<String, usize> = HashMap::new();
+
+            for _ in 0..MAX_REPAIRS {
+                let e = match serde_path_to_error::deserialize::<_, T>(&value) {
+                    Ok(parsed) => return Ok(parsed),
+                    Err(e) => e,
+                };
+                let Some(pointer) = failed_field(&e, &value) else {
+                    return Err(error.unwrap_or(e.into_inner()));
+                };
+                let start = match value.pointer(&pointer) {
+                    None => 0,
+                    // Null fields of the response have already been tried
+                    Some(serde_json::Value::Null) => 1,
+                    Some(_) if filled.contains_key(&pointer) => 0,
+                    // Never overwrite the values of the response
+                    Some(_) => return Err(error.unwrap_or(e.into_inner())),
+                };
+                let next = filled.entry(pointer.clone()).or_insert(start);
+                let Some(empty) = empty_values().into_iter().nth(*next) else {
+                    return Err(error.unwrap_or(e.into_inner()));
+                };
+                *next += 1;
+                set_pointer(&mut value, &pointer, empty);
+                error.get_or_insert(e.into_inner());
+            }
+            Err(error.expect("at least one repair was attempted"))
+        }
+    }
+}
+
+/// The JSON pointer of the missing or `null` field that made the deserialization fail, if any.
+fn failed_field(
+    error: &serde_path_to_error::Error<serde_json::Error>,
+    value: &serde_json::Value,
+) -> Option<String> {
+    let mut pointer = String::new();
+    for segment in error.path().iter() {
+        match segment {
+            serde_path_to_error::Segment::Map { key } => {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+            }
+            serde_path_to_error::Segment::Seq { index } => {
+                pointer.push_str(&format!("/{index}"));
+            }
+            _ => return None,
+        }
+    }
+
+    let message = error.inner().to_string();
+    if let Some(field) = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+    {
+        return Some(format!("{pointer}/{field}"));
+    }
+
+    match value.pointer(&pointer) {
+        Some(_) if !pointer.is_empty() && message.starts_with("invalid type") => Some(pointer),
+        _ => None,
+    }
+}
+
+fn set_pointer(value: &mut serde_json::Value, pointer: &str, new: serde_json::Value) {
+    if let Some(existing) = value.pointer_mut(pointer) {
+        *existing = new;
+    } else if let Some((parent, field)) = pointer.rsplit_once('/') {
+        if let Some(serde_json::Value::Object(map)) = value.pointer_mut(parent) {
+            map.insert(field.replace("~1", "/").replace("~0", "~"), new);
+        }
+    }
+}
+
+/// The path of the first field of `original` (that is not `null`) missing from `known`.
+fn unknown_field(original: &serde_json::Value, known: &serde_json::Value) -> Option<String> {
+    match (original, known) {
+        (serde_json::Value::Object(original), serde_json::Value::Object(known)) => original
+            .iter()
+            .find_map(|(key, value)| match known.get(key) {
+                None if !value.is_null() => Some(key.clone()),
+                None => None,
+                Some(known) => unknown_field(value, known).map(|path| format!("{key}.{path}")),
+            }),
+        (serde_json::Value::Array(original), serde_json::Value::Array(known))
+            if original.len() == known.len() =>
+        {
+            original
+                .iter()
+                .zip(known)
+                .enumerate()
+                .find_map(|(i, (value, known))| {
+                    unknown_field(value, known).map(|path| format!("{i}.{path}"))
+                })
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,4 +458,89 @@ mod tests {
         };
         assert_eq!(dummy, expected);
     }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Response {
+        id: String,
+        fingerprint: String,
+        choices: Vec<Choice>,
+        usage: Option<u64>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Choice {
+        index: u64,
+        text: String,
+    }
+
+    #[test]
+    fn test_lenient_deserialization() {
+        let body = r#"{"id": "1", "choices": [{"index": 0, "text": null, "logprobs": []}], "new_field": 1}"#;
+        assert!(serde_json::from_str::<Response>(body).is_err());
+
+        let response: Response = from_str_with_mode(body, DeserializationMode::Lenient).unwrap();
+        assert_eq!(
+            response,
+            Response {
+                id: "1".to_string(),
+                fingerprint: "".to_string(),
+                choices: vec![Choice {
+                    index: 0,
+                    text: "".to_string()
+                }],
+                usage: None,
+            }
+        );
+
+        // Present values of the wrong type are not overwritten
+        let body = r#"{"id": 1, "fingerprint": "fp", "choices": []}"#;
+        assert!(from_str_with_mode::<Response>(body, DeserializationMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn test_strict_deserialization() {
+        let body = r#"{"id": "1", "fingerprint": "fp", "choices": [{"index": 0, "text": "Hi"}], "usage": null}"#;
+        assert!(from_str_with_mode::<Response>(body, DeserializationMode::Strict).is_ok());
+
+        let body = r#"{"id": "1", "fingerprint": "fp", "choices": [{"index": 0, "text": "Hi", "logprobs": []}]}"#;
+        let error = from_str_with_mode::<Response>(body, DeserializationMode::Strict).unwrap_err();
+        assert_eq!(error.to_string(), "unknown field `choices.0.logprobs`");
+
+        let body = r#"{"id": "1", "choices": []}"#;
+        assert!(from_str_with_mode::<Response>(body, DeserializationMode::Strict).is_err());
+    }
+
+    #[test]
+    fn test_repair() {
+        let cases = [
+            (
+                "Here is the data:\n```json\n{\"name\": \"Rig\", \"tags\": [\"rust\", \"llm\",],}\n```\nHope it helps!",
+                serde_json::json!({"name": "Rig", "tags": ["rust", "llm"]}),
+            ),
+            (
+                "{'name': 'It\\'s \"Rig\"', 'stable': False, 'license': None}",
+                serde_json::json!({"name": "It's \"Rig\"", "stable": false, "license": null}),
+            ),
+            (
+                "Sure! {\"text\": \"line 1\nline 2\", \"note\": \"True story\"}",
+                serde_json::json!({"text": "line 1\nline 2", "note": "True story"}),
+            ),
+        ];
+
+        for (text, expected) in cases {
+            assert!(serde_json::from_str::<serde_json::Value>(text).is_err());
+            assert_eq!(
+                from_str_repaired::<serde_json::Value>(text).unwrap(),
+                expected
+            );
+        }
+
+        let error = from_str_repaired::<serde_json::Value>("{\"a\": }").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            serde_json::from_str::<serde_json::Value>("{\"a\": }")
+                .unwrap_err()
+                .to_string()
+        );
+    }
 }
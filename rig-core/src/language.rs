@@ -0,0 +1,226 @@
+//! This module provides the enforcement of the response language of agents, for multilingual
+//! products where the model should answer in the language of the user (or of the product)
+//! rather than in the language of the prompt or of the context documents.
+//!
+//! A [ResponseLanguage] appends an instruction to the preamble of the agent. If it has a
+//! [LanguageDetector], the text responses of the agent are also checked, and the agent is asked
+//! to answer again in the right language when they are not.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     language::{ResponseLanguage, StopwordDetector},
+//!     providers::openai,
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .preamble("You are a helpful assistant.")
+//!     .response_language(ResponseLanguage::new("fr", "French").detector(StopwordDetector))
+//!     .build();
+//! # }
+//! ```
+use std::sync::Arc;
+
+/// Trait for language detectors.
+pub trait LanguageDetector: Send + Sync {
+    /// Detect the language of the text, as a language code (e.g.: `en`). Returns `None` if
+    /// the language cannot be detected reliably.
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+/// The language in which an agent must respond, see
+/// [AgentBuilder::response_language](crate::agent::AgentBuilder::response_language).
+#[derive(Clone)]
+pub struct ResponseLanguage {
+    /// Language code, as returned by the detector (e.g.: `fr`)
+    pub code: String,
+    /// Language name, used in the instructions (e.g.: `French`)
+    pub name: String,
+    detector: Option<Arc<dyn LanguageDetector>>,
+    pub(crate) max_retries: usize,
+}
+
+impl ResponseLanguage {
+    pub fn new(code: &str, name: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            name: name.to_string(),
+            detector: None,
+            max_retries: 1,
+        }
+    }
+
+    /// Check the language of the responses with the given detector.
+    pub fn detector(mut self, detector: impl LanguageDetector + 'static) -> Self {
+        self.detector = Some(Arc::new(detector));
+        self
+    }
+
+    /// Set the number of times the agent is asked to answer again when its response is not in
+    /// the right language (defaults to 1). The last response is returned, with a warning, if
+    /// it is still not in the right language.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Instruction appended to the preamble of the agent.
+    pub(crate) fn instruction(&self) -> String {
+        format!(
+            "Always respond in {}, whatever the language of the messages and documents.",
+            self.name
+        )
+    }
+
+    /// Message sent when a response is not in the right language.
+    pub(crate) fn correction(&self) -> String {
+        format!(
+            "Your last response was not in {}. Respond again, in {} only.",
+            self.name, self.name
+        )
+    }
+
+    /// Whether the text is in the language. Texts whose language cannot be detected (or
+    /// without detector) match.
+    pub fn matches(&self, text: &str) -> bool {
+        self.detector
+            .as_ref()
+            .and_then(|detector| detector.detect(text))
+            .is_none_or(|code| code.eq_ignore_ascii_case(&self.code))
+    }
+}
+
+const STOPWORDS: [(&str, &[&str]); 7] = [
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "in", "that", "it", "you", "with", "for",
+            "this", "was", "have", "not", "be", "on",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "qui", "dans", "pour",
+            "pas", "vous", "avec", "ce", "sur", "au", "je",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "ich", "sie", "mit", "den",
+            "zu", "auf", "für", "es", "sich", "dem", "von",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "con",
+            "para", "no", "se", "del", "lo", "está",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "la", "e", "è", "che", "di", "un", "una", "per", "non", "con", "sono", "gli",
+            "del", "della", "le", "si", "questo",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "a", "os", "as", "e", "é", "que", "de", "um", "uma", "não", "para", "com", "do",
+            "da", "em", "se", "por", "você",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "van", "niet", "dat", "ik", "je", "met", "op", "voor",
+            "zijn", "te", "er", "wat",
+        ],
+    ),
+];
+
+/// A simple detector of the most common languages written with the Latin alphabet (English,
+/// French, German, Spanish, Italian, Portuguese and Dutch), based on their most frequent words.
+///
+/// Texts that are too short, or mostly written in another alphabet, are not detected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StopwordDetector;
+
+impl LanguageDetector for StopwordDetector {
+    fn detect(&self, text: &str) -> Option<String> {
+        let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+        let latin = text
+            .chars()
+            .filter(|c| c.is_alphabetic() && (c.is_ascii() || ('\u{c0}'..='\u{24f}').contains(c)))
+            .count();
+        if latin * 2 < letters {
+            return None;
+        }
+
+        let words = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+
+        let mut scores = STOPWORDS
+            .iter()
+            .map(|(code, stopwords)| {
+                let hits = words
+                    .iter()
+                    .filter(|word| stopwords.contains(&word.as_str()))
+                    .count();
+                (hits, *code)
+            })
+            .collect::<Vec<_>>();
+        scores.sort_by_key(|(hits, _)| std::cmp::Reverse(*hits));
+
+        // Require a few hits, and a clear winner
+        match scores[..] {
+            [(best, code), (second, _), ..] if best >= 2 && best > second => Some(code.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopword_detector() {
+        let detect = |text: &str| StopwordDetector.detect(text);
+
+        assert_eq!(
+            detect("The weather is nice and the sun is shining.").as_deref(),
+            Some("en")
+        );
+        assert_eq!(
+            detect("Le temps est beau et le soleil brille dans le ciel.").as_deref(),
+            Some("fr")
+        );
+        assert_eq!(
+            detect("Das Wetter ist schön und die Sonne scheint.").as_deref(),
+            Some("de")
+        );
+        assert_eq!(detect("OK"), None);
+        assert_eq!(detect("天气很好，阳光明媚。"), None);
+    }
+
+    #[test]
+    fn test_response_language() {
+        let french = ResponseLanguage::new("fr", "French");
+        assert!(french.matches("The weather is nice and the sun is shining."));
+
+        let french = french.detector(StopwordDetector);
+        assert!(!french.matches("The weather is nice and the sun is shining."));
+        assert!(french.matches("Le temps est beau et le soleil brille."));
+        assert!(french.matches("OK"));
+    }
+}
@@ -81,18 +81,68 @@
 //! implement the [VectorStoreIndex](crate::vector_store::VectorStoreIndex) trait.
 
 pub mod agent;
+#[cfg(feature = "artifacts")]
+pub mod artifacts;
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod cache;
+pub mod calculator;
+pub mod cancellation;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod classifier;
 pub mod cli_chatbot;
+pub mod coalesce;
 pub mod completion;
+pub mod continuation;
+pub mod conversation;
+pub mod debate;
+pub mod distillation;
 pub mod embeddings;
+pub mod eval;
+pub mod experiments;
 pub mod extractor;
+#[cfg(feature = "finetune")]
+pub mod finetune;
+pub mod grounding;
+pub mod guided;
+pub mod http;
+pub mod indexer;
+#[cfg(feature = "ingest")]
+pub mod ingest;
+#[cfg(feature = "jobs")]
+pub mod jobs;
 pub(crate) mod json_utils;
+pub mod language;
 pub mod loaders;
+pub mod memory;
+pub mod metadata;
+pub mod models;
 pub mod one_or_many;
+pub mod output_parsers;
 pub mod pipeline;
+pub mod post_processors;
 pub mod providers;
+pub mod race;
+pub mod rag;
+pub mod resume;
+#[cfg(feature = "retry")]
+pub mod retry;
+pub mod routing;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+pub mod sse;
 pub mod streaming;
+pub mod swarm;
+pub mod telemetry;
+pub mod test_mode;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod text_completion;
+pub mod tokens;
 pub mod tool;
 pub mod transcription;
+pub mod validation;
 pub mod vector_store;
 
 // Re-export commonly used types and traits
@@ -1,4 +1,5 @@
-use crate::loaders::file::FileLoaderError;
+use crate::loaders::file::{file_metadata, FileLoaderError};
+use crate::metadata;
 use epub::doc::EpubDoc;
 
 use std::fs::File;
@@ -226,6 +227,54 @@ impl<'a, P: TextProcessor> EpubFileLoader<'a, Result<PathBuf, EpubLoaderError>,
             _processor: PhantomData,
         }
     }
+
+    /// Directly reads the contents of the epub files within the iterator returned by
+    ///  [EpubFileLoader::with_glob] or [EpubFileLoader::with_dir] as [Document](metadata::Document)s,
+    ///  with their metadata: the path as id, the `file://` URI as source, the title of the epub
+    ///  (or the file stem), the file timestamps and the `author` and `language` of the epub (as
+    ///  custom metadata), when present.
+    ///
+    /// # Example
+    /// Read epub files in directory "tests/data/*.epub" and split them into chunks.
+    ///
+    /// ```rust
+    /// # use rig::loaders::{epub::RawTextProcessor, EpubFileLoader};
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let chunks = EpubFileLoader::<_, RawTextProcessor>::with_glob("tests/data/*.epub")?
+    ///     .read_with_metadata()
+    ///     .ignore_errors()
+    ///     .into_iter()
+    ///     .flat_map(|document| document.chunks(1000, 100));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_with_metadata(
+        self,
+    ) -> EpubFileLoader<'a, Result<metadata::Document, EpubLoaderError>, P> {
+        EpubFileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let (path, doc) = res.load_with_path()?;
+
+                let mut metadata = file_metadata(&path).map_err(FileLoaderError::IoError)?;
+                if let Some(title) = doc.mdata("title").filter(|title| !title.trim().is_empty()) {
+                    metadata.title = Some(title);
+                }
+                if let Some(author) = doc.mdata("creator") {
+                    metadata = metadata.custom("author", author);
+                }
+                if let Some(language) = doc.mdata("language") {
+                    metadata = metadata.custom("language", language);
+                }
+
+                let content = EpubChapterIterator::<P>::from(doc)
+                    .collect::<Result<Vec<String>, EpubLoaderError>>()?
+                    .into_iter()
+                    .collect::<String>();
+                Ok(metadata::Document::new(metadata, &content))
+            })),
+            _processor: PhantomData,
+        }
+    }
 }
 
 impl<'a, P: TextProcessor + 'a> EpubFileLoader<'a, EpubDoc<BufReader<File>>, P> {
@@ -533,4 +582,26 @@ mod tests {
         let (path, _) = &actual[0];
         assert_eq!(path, &PathBuf::from("tests/data/dummy.epub"));
     }
+
+    #[test]
+    fn test_single_file_with_metadata() {
+        let loader = EpubFileLoader::<_, RawTextProcessor>::with_glob("tests/data/*.epub").unwrap();
+
+        let actual = loader
+            .read_with_metadata()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(actual.len(), 1);
+
+        let metadata = &actual[0].metadata;
+        assert_eq!(metadata.id, "tests/data/dummy.epub");
+        assert!(metadata
+            .source
+            .as_ref()
+            .is_some_and(|source| source.starts_with("file://")));
+        assert!(metadata.title.is_some());
+        assert!(!actual[0].text.is_empty());
+    }
 }
@@ -1,8 +1,13 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use glob::glob;
 use thiserror::Error;
 
+use crate::metadata::{rfc3339, Document, DocumentMetadata};
+
 #[derive(Error, Debug)]
 pub enum FileLoaderError {
     #[error("Invalid glob pattern: {0}")]
@@ -24,6 +29,7 @@ pub enum FileLoaderError {
 pub(crate) trait Readable {
     fn read(self) -> Result<String, FileLoaderError>;
     fn read_with_path(self) -> Result<(PathBuf, String), FileLoaderError>;
+    fn read_with_metadata(self) -> Result<Document, FileLoaderError>;
 }
 
 impl<'a> FileLoader<'a, PathBuf> {
@@ -37,6 +43,11 @@ impl<'a> FileLoader<'a, PathBuf> {
             iterator: Box::new(self.iterator.map(|res| res.read_with_path())),
         }
     }
+    pub fn read_with_metadata(self) -> FileLoader<'a, Result<Document, FileLoaderError>> {
+        FileLoader {
+            iterator: Box::new(self.iterator.map(|res| res.read_with_metadata())),
+        }
+    }
 }
 
 impl Readable for PathBuf {
@@ -47,6 +58,32 @@ impl Readable for PathBuf {
         let contents = fs::read_to_string(&self);
         Ok((self, contents?))
     }
+    fn read_with_metadata(self) -> Result<Document, FileLoaderError> {
+        let contents = fs::read_to_string(&self)?;
+        Ok(Document::new(file_metadata(&self)?, &contents))
+    }
+}
+
+/// The metadata of a file: its path as id, its `file://` URI as source, its stem as title and
+/// its timestamps.
+pub(crate) fn file_metadata(path: &Path) -> Result<DocumentMetadata, std::io::Error> {
+    let file_metadata = fs::metadata(path)?;
+
+    let mut metadata = DocumentMetadata::new(&path.to_string_lossy()).source(&format!(
+        "file://{}",
+        fs::canonicalize(path)?.to_string_lossy()
+    ));
+    if let Some(stem) = path.file_stem() {
+        metadata = metadata.title(&stem.to_string_lossy());
+    }
+    // Creation times are not available on all platforms
+    if let Ok(created) = file_metadata.created() {
+        metadata = metadata.created_at(&rfc3339(created));
+    }
+    if let Ok(modified) = file_metadata.modified() {
+        metadata = metadata.updated_at(&rfc3339(modified));
+    }
+    Ok(metadata)
 }
 impl<T: Readable> Readable for Result<T, FileLoaderError> {
     fn read(self) -> Result<String, FileLoaderError> {
@@ -55,6 +92,9 @@ impl<T: Readable> Readable for Result<T, FileLoaderError> {
     fn read_with_path(self) -> Result<(PathBuf, String), FileLoaderError> {
         self.map(|t| t.read_with_path())?
     }
+    fn read_with_metadata(self) -> Result<Document, FileLoaderError> {
+        self.map(|t| t.read_with_metadata())?
+    }
 }
 
 // ================================================================
@@ -140,6 +180,28 @@ impl<'a> FileLoader<'a, Result<PathBuf, FileLoaderError>> {
             iterator: Box::new(self.iterator.map(|res| res.read_with_path())),
         }
     }
+    /// Reads the contents of the files within the iterator returned by [FileLoader::with_glob] or
+    ///  [FileLoader::with_dir] as [Document]s, with their metadata: the path as id, the
+    ///  `file://` URI as source, the file stem as title and the file timestamps.
+    ///
+    /// # Example
+    /// Read files in directory "files/*.txt" and print the citation of each file.
+    ///
+    /// ```rust
+    /// # use rig::loaders::FileLoader;
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let documents = FileLoader::with_glob("files/*.txt")?.read_with_metadata().ignore_errors();
+    /// for document in documents {
+    ///     println!("{}", document.metadata.citation())
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_with_metadata(self) -> FileLoader<'a, Result<Document, FileLoaderError>> {
+        FileLoader {
+            iterator: Box::new(self.iterator.map(|res| res.read_with_metadata())),
+        }
+    }
 }
 
 impl<'a, T: 'a> FileLoader<'a, Result<T, FileLoaderError>> {
@@ -270,4 +332,31 @@ mod tests {
         assert!(!actual.is_empty());
         assert!(expected == actual)
     }
+
+    #[test]
+    fn test_file_loader_with_metadata() {
+        let temp = assert_fs::TempDir::new().expect("Failed to create temp dir");
+        let foo_file = temp.child("foo.txt");
+        foo_file.write_str("foo").expect("Failed to write to foo");
+
+        let glob = temp.path().to_string_lossy().to_string() + "/*.txt";
+
+        let documents = FileLoader::with_glob(&glob)
+            .unwrap()
+            .read_with_metadata()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        assert_eq!(documents.len(), 1);
+        let metadata = &documents[0].metadata;
+        assert_eq!(documents[0].text, "foo");
+        assert_eq!(metadata.id, foo_file.path().to_string_lossy());
+        assert_eq!(metadata.title.as_deref(), Some("foo"));
+        assert!(metadata
+            .source
+            .as_ref()
+            .is_some_and(|source| source.starts_with("file://") && source.ends_with("foo.txt")));
+        assert!(metadata.updated_at.is_some());
+    }
 }
@@ -0,0 +1,568 @@
+//! This module provides [GitRepoLoader], which loads the source files of a git repository (a
+//! local working tree or a cloned remote) for code RAG, e.g.: code assistants.
+//!
+//! The files are walked with the rules of the `.gitignore` files of the repository (as well as
+//! `.ignore` files and the global gitignore), hidden files are skipped, and the files can be
+//! filtered by extension and size. The files are then chunked with [chunk_code], which splits the
+//! code at the boundaries of its top-level items (functions, classes, impl blocks, ...):
+//! - with the `tree-sitter` feature, Rust, Python, JavaScript, TypeScript and Go files are parsed,
+//!   and the items larger than a chunk are split at the boundaries of their own items (e.g.: the
+//!   methods of a class),
+//! - otherwise, the items are detected with a heuristic: non-indented lines following a blank
+//!   line.
+//!
+//! Note: The [GitRepoLoader] requires the `git` feature to be enabled in the `Cargo.toml` file,
+//! and [GitRepoLoader::from_remote] the `git` command.
+//!
+//! # Example
+//! ```rust
+//! use rig::{embeddings::EmbeddingsBuilder, loaders::GitRepoLoader, providers::openai};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let chunks = GitRepoLoader::from_remote("https://github.com/0xPlaygrounds/rig", "/tmp/rig")?
+//!     .extensions(["rs", "md"])
+//!     .max_size(100_000)
+//!     .chunks(2000)?;
+//!
+//! let openai = openai::Client::from_env();
+//! let embeddings = EmbeddingsBuilder::new(openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL))
+//!     .documents(chunks)?
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use thiserror::Error;
+
+use crate::metadata::{rfc3339, Document, DocumentMetadata};
+
+#[derive(Error, Debug)]
+pub enum GitLoaderError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Git clone failed: {0}")]
+    CloneError(String),
+
+    #[error("The repository at {path} is a clone of {origin}, not {url}")]
+    OriginMismatch {
+        path: PathBuf,
+        origin: String,
+        url: String,
+    },
+
+    #[error("Walk error: {0}")]
+    WalkError(#[from] ignore::Error),
+}
+
+/// The programming language of a source file, which decides how it is chunked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    /// Any other language (or text), chunked with the heuristic
+    Other,
+}
+
+impl Language {
+    /// The language of a file extension (without the dot).
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_lowercase().as_str() {
+            "rs" => Language::Rust,
+            "py" | "pyi" => Language::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Language::JavaScript,
+            "ts" | "tsx" | "mts" | "cts" => Language::TypeScript,
+            "go" => Language::Go,
+            _ => Language::Other,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Go => "go",
+            Language::Other => "other",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        [
+            Language::Rust,
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::Go,
+        ]
+        .into_iter()
+        .find(|language| language.name() == name)
+        .unwrap_or(Language::Other)
+    }
+}
+
+/// Loads the source files of a git repository, see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct GitRepoLoader {
+    root: PathBuf,
+    remote: Option<String>,
+    extensions: Vec<String>,
+    max_size: Option<u64>,
+}
+
+impl GitRepoLoader {
+    /// Load the files of a local repository (or any directory).
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GitLoaderError> {
+        Ok(Self {
+            root: fs::canonicalize(path)?,
+            remote: None,
+            extensions: vec![],
+            max_size: None,
+        })
+    }
+
+    /// Clone the remote repository (without its history) into the directory, and load its files.
+    /// The directory is reused if it already contains a clone of the same remote (an error is
+    /// returned if it is a clone of another one).
+    pub fn from_remote(url: &str, path: impl AsRef<Path>) -> Result<Self, GitLoaderError> {
+        let path = path.as_ref();
+
+        if path.join(".git").exists() {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(["remote", "get-url", "origin"])
+                .output()?;
+            let origin = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !output.status.success() || origin != url {
+                return Err(GitLoaderError::OriginMismatch {
+                    path: path.to_path_buf(),
+                    origin,
+                    url: url.to_string(),
+                });
+            }
+        } else {
+            // `--` keeps the URL from being parsed as an option (e.g.: `--upload-pack=...`)
+            let output = Command::new("git")
+                .args(["clone", "--depth", "1", "--quiet", "--"])
+                .arg(url)
+                .arg(path)
+                .output()?;
+            if !output.status.success() {
+                return Err(GitLoaderError::CloneError(
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            remote: Some(url.to_string()),
+            ..Self::open(path)?
+        })
+    }
+
+    /// Only load the files with one of the extensions (case insensitive, without the dot).
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: impl IntoIterator<Item = S>) -> Self {
+        self.extensions = extensions
+            .into_iter()
+            .map(|extension| extension.as_ref().trim_start_matches('.').to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Skip the files larger than the size, in bytes (e.g.: generated or minified files).
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    fn extension(path: &Path) -> String {
+        path.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// List the files to load, relative to the root of the repository, in lexicographic order.
+    pub fn files(&self) -> Result<Vec<PathBuf>, GitLoaderError> {
+        let walk = ignore::WalkBuilder::new(&self.root)
+            .require_git(false)
+            .max_filesize(self.max_size)
+            .sort_by_file_path(|a, b| a.cmp(b))
+            .build();
+
+        let mut files = vec![];
+        for entry in walk {
+            let entry = entry?;
+            if !entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+            {
+                continue;
+            }
+            let path = entry.path();
+            if !self.extensions.is_empty() && !self.extensions.contains(&Self::extension(path)) {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(&self.root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+        Ok(files)
+    }
+
+    /// Read the files as text documents, skipping the files which are not valid UTF-8 text
+    /// (e.g.: images). The documents have the relative paths of the files as ids, and their
+    /// language in the `language` custom metadata.
+    pub fn load(&self) -> Result<Vec<Document>, GitLoaderError> {
+        let mut documents = vec![];
+
+        for file in self.files()? {
+            let path = self.root.join(&file);
+            let Ok(text) = fs::read_to_string(&path) else {
+                tracing::debug!(target: "rig", "Skipping non-text file {}", file.display());
+                continue;
+            };
+            let id = file
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let language = Language::from_extension(&Self::extension(&file));
+
+            let mut metadata = DocumentMetadata::new(&id)
+                .source(&format!("file://{}", path.to_string_lossy()))
+                .custom("language", language.name());
+            if let Some(name) = file.file_name() {
+                metadata = metadata.title(&name.to_string_lossy());
+            }
+            if let Ok(modified) = fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+                metadata = metadata.updated_at(&rfc3339(modified));
+            }
+            if let Some(remote) = &self.remote {
+                metadata = metadata.custom("repository", remote.as_str());
+            }
+
+            documents.push(Document::new(metadata, &text));
+        }
+
+        Ok(documents)
+    }
+
+    /// Read the files and split them into chunks of at most (about) `size` characters, with
+    /// [chunk_code].
+    pub fn chunks(&self, size: usize) -> Result<Vec<Document>, GitLoaderError> {
+        Ok(self
+            .load()?
+            .iter()
+            .flat_map(|document| chunk_code(document, size))
+            .collect())
+    }
+}
+
+/// Split a source file into chunks of at most (about) `size` characters, at the boundaries of
+/// its items (see the [module documentation](self)). Consecutive small items are grouped, and
+/// items larger than a chunk are split at line boundaries (or, with the `tree-sitter` feature, at
+/// the boundaries of their own items first).
+///
+/// The language is read from the `language` custom metadata of the document (set by
+/// [GitRepoLoader::load]), or the extension of its id. The chunks have the ids
+/// `<document id>#<chunk index>`, link to the document with their
+/// [parent_id](DocumentMetadata::parent_id), and have the (1-based) `start_line` and `end_line`
+/// custom metadata.
+pub fn chunk_code(document: &Document, size: usize) -> Vec<Document> {
+    let language = match document.metadata.custom.get("language") {
+        Some(serde_json::Value::String(name)) => Language::from_name(name),
+        _ => Language::from_extension(
+            document
+                .metadata
+                .id
+                .rsplit_once('.')
+                .map_or("", |(_, extension)| extension),
+        ),
+    };
+    let text = &document.text;
+    let size = size.max(1);
+
+    let mut boundaries = syntax_boundaries(text, language, size)
+        .unwrap_or_else(|| heuristic_boundaries(text))
+        .into_iter()
+        .filter(|&boundary| boundary < text.len())
+        .collect::<Vec<_>>();
+    boundaries.push(0);
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries.push(text.len());
+
+    // Split the items larger than a chunk at line boundaries
+    let mut units = vec![];
+    for item in boundaries.windows(2) {
+        let (mut start, mut end, mut length) = (item[0], item[0], 0);
+        for line in text[item[0]..item[1]].split_inclusive('\n') {
+            let line_length = line.chars().count();
+            if length > 0 && length + line_length > size {
+                units.push((start, end, length));
+                (start, length) = (end, 0);
+            }
+            end += line.len();
+            length += line_length;
+        }
+        units.push((start, end, length));
+    }
+
+    // Group the consecutive units fitting in a chunk
+    let mut ranges: Vec<(usize, usize, usize)> = vec![];
+    for (start, end, length) in units {
+        match ranges.last_mut() {
+            Some((_, last_end, last_length)) if *last_length + length <= size => {
+                *last_end = end;
+                *last_length += length;
+            }
+            _ => ranges.push((start, end, length)),
+        }
+    }
+
+    let line = |byte: usize| text[..byte].matches('\n').count() + 1;
+    ranges
+        .into_iter()
+        .filter_map(|(start, end, _)| {
+            let chunk = text[start..end].trim_end();
+            // Skip the leading blank lines
+            let skipped = chunk.len() - chunk.trim_start_matches(['\n', '\r']).len();
+            let chunk = &chunk[skipped..];
+            (!chunk.trim().is_empty()).then_some((start + skipped, chunk))
+        })
+        .enumerate()
+        .map(|(index, (start, chunk))| Document {
+            metadata: document
+                .metadata
+                .chunk(index)
+                .custom("start_line", line(start))
+                .custom("end_line", line(start + chunk.len())),
+            text: chunk.to_string(),
+        })
+        .collect()
+}
+
+/// The start of the non-indented lines following a blank line.
+fn heuristic_boundaries(text: &str) -> Vec<usize> {
+    let mut boundaries = vec![];
+    let mut previous_blank = true;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        let closing = line.starts_with(['}', ')', ']']);
+        if previous_blank && !blank && !closing && !line.starts_with(char::is_whitespace) {
+            boundaries.push(offset);
+        }
+        previous_blank = blank;
+        offset += line.len();
+    }
+
+    boundaries
+}
+
+/// The start of the lines of the top-level items, and of the items of the items larger than a
+/// chunk, or `None` if the language is not supported.
+#[cfg(feature = "tree-sitter")]
+fn syntax_boundaries(text: &str, language: Language, size: usize) -> Option<Vec<usize>> {
+    let grammar: tree_sitter::Language = match language {
+        Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+        Language::Python => tree_sitter_python::LANGUAGE.into(),
+        Language::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+        Language::TypeScript => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        Language::Go => tree_sitter_go::LANGUAGE.into(),
+        Language::Other => return None,
+    };
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&grammar).ok()?;
+    let tree = parser.parse(text, None)?;
+
+    fn visit(node: tree_sitter::Node, text: &str, size: usize, boundaries: &mut Vec<usize>) {
+        let mut cursor = node.walk();
+        let mut attached = false;
+        for child in node.named_children(&mut cursor) {
+            // Comments and attributes stay with the item they precede
+            if !attached {
+                let line_start = text[..child.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+                boundaries.push(line_start);
+            }
+            attached = child.kind().contains("comment") || child.kind() == "attribute_item";
+
+            let oversized = text[child.byte_range()].chars().count() > size;
+            let mut body_cursor = child.walk();
+            let body = child
+                .named_children(&mut body_cursor)
+                .max_by_key(|body| body.end_byte() - body.start_byte());
+            if let Some(body) = body.filter(|_| oversized) {
+                visit(body, text, size, boundaries);
+            }
+        }
+    }
+
+    let mut boundaries = vec![];
+    visit(tree.root_node(), text, size, &mut boundaries);
+    Some(boundaries)
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+fn syntax_boundaries(_text: &str, _language: Language, _size: usize) -> Option<Vec<usize>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::{FileWriteBin, FileWriteStr, PathChild};
+
+    use super::*;
+
+    const RUST: &str = "use std::fmt;\n\n\
+        /// A point\n\
+        #[derive(Debug)]\n\
+        struct Point {\n    x: i32,\n    y: i32,\n}\n\n\
+        fn origin() -> Point {\n    Point { x: 0, y: 0 }\n}\n";
+
+    #[test]
+    fn test_git_repo_loader() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.child(".gitignore")
+            .write_str("target/\n*.log\n")
+            .unwrap();
+        temp.child("src/main.rs").write_str(RUST).unwrap();
+        temp.child("src/lib.py")
+            .write_str("def f():\n    pass\n")
+            .unwrap();
+        temp.child("target/debug/out.rs")
+            .write_str("fn main() {}")
+            .unwrap();
+        temp.child("debug.log").write_str("error").unwrap();
+        temp.child("README.md").write_str("# Points").unwrap();
+        temp.child("logo.rs").write_binary(&[0xff, 0xfe]).unwrap();
+
+        let loader = GitRepoLoader::open(temp.path()).unwrap();
+        assert_eq!(
+            loader.files().unwrap(),
+            ["README.md", "logo.rs", "src/lib.py", "src/main.rs"].map(PathBuf::from)
+        );
+
+        let documents = loader.extensions(["rs"]).load().unwrap();
+        assert_eq!(documents.len(), 1);
+        let main = &documents[0];
+        assert_eq!(main.metadata.id, "src/main.rs");
+        assert_eq!(main.metadata.title.as_deref(), Some("main.rs"));
+        assert_eq!(main.metadata.custom["language"], "rust");
+        assert!(main
+            .metadata
+            .source
+            .as_ref()
+            .unwrap()
+            .starts_with("file://"));
+    }
+
+    #[test]
+    fn test_from_remote() {
+        let git = |dir: &Path, args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let remote = temp.child("remote");
+        remote.child("main.rs").write_str(RUST).unwrap();
+        git(remote.path(), &["init", "--quiet"]);
+        git(remote.path(), &["add", "."]);
+        git(
+            remote.path(),
+            &[
+                "-c",
+                "user.name=rig",
+                "-c",
+                "user.email=rig@example.com",
+                "commit",
+                "--quiet",
+                "-m",
+                "init",
+            ],
+        );
+        let url = format!("file://{}", remote.path().display());
+
+        let clone = temp.child("clone");
+        let loader = GitRepoLoader::from_remote(&url, clone.path()).unwrap();
+        assert_eq!(loader.files().unwrap(), [PathBuf::from("main.rs")]);
+
+        // The clone is reused for the same remote only
+        assert!(GitRepoLoader::from_remote(&url, clone.path()).is_ok());
+        assert!(matches!(
+            GitRepoLoader::from_remote("https://example.com/other.git", clone.path()),
+            Err(GitLoaderError::OriginMismatch { origin, .. }) if origin == url
+        ));
+
+        // URLs are never parsed as options
+        let marker = temp.child("injected");
+        let url = format!("--upload-pack=touch {}", marker.path().display());
+        assert!(matches!(
+            GitRepoLoader::from_remote(&url, temp.child("other").path()),
+            Err(GitLoaderError::CloneError(_))
+        ));
+        assert!(!marker.path().exists());
+    }
+
+    #[test]
+    fn test_chunk_code() {
+        let document = Document::new(DocumentMetadata::new("src/main.rs"), RUST);
+
+        let chunks = chunk_code(&document, 80);
+        let texts = chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                "use std::fmt;",
+                "/// A point\n#[derive(Debug)]\nstruct Point {\n    x: i32,\n    y: i32,\n}",
+                "fn origin() -> Point {\n    Point { x: 0, y: 0 }\n}"
+            ]
+        );
+        assert_eq!(chunks[1].metadata.id, "src/main.rs#1");
+        assert_eq!(chunks[1].metadata.custom["start_line"], 3);
+        assert_eq!(chunks[1].metadata.custom["end_line"], 8);
+
+        // Small items are grouped, large items are split at line boundaries
+        assert_eq!(chunk_code(&document, 1000).len(), 1);
+        assert!(chunk_code(&document, 20)
+            .iter()
+            .all(|chunk| chunk.text.chars().count() <= 20 || !chunk.text.contains('\n')));
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    #[test]
+    fn test_chunk_code_syntax() {
+        let python = "class Shape:\n    def area(self):\n        return 0\n\n    def perimeter(self):\n        return 0\n";
+        let document = Document::new(DocumentMetadata::new("shape.py"), python);
+
+        let texts = chunk_code(&document, 60)
+            .into_iter()
+            .map(|chunk| chunk.text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                "class Shape:\n    def area(self):\n        return 0",
+                "    def perimeter(self):\n        return 0"
+            ]
+        );
+    }
+}
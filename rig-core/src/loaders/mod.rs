@@ -15,6 +15,20 @@
 //! and keeping track of the chapter numbers along with their contents.
 //!
 //! Note: The [EpubFileLoader] requires the `epub` feature to be enabled in the `Cargo.toml` file.
+//!
+//! The [ObjectStoreLoader] lists and reads text documents from object stores, e.g.: S3 buckets.
+//!
+//! Note: The [ObjectStoreLoader] requires the `s3` feature to be enabled in the `Cargo.toml` file.
+//!
+//! The [GitRepoLoader] loads the source files of git repositories, respecting their `.gitignore`
+//! files, and chunks them at the boundaries of their functions and classes.
+//!
+//! Note: The [GitRepoLoader] requires the `git` feature to be enabled in the `Cargo.toml` file.
+//!
+//! All the loaders can produce [Document](crate::metadata::Document)s carrying the
+//! [DocumentMetadata](crate::metadata::DocumentMetadata) of their files (id, `file://` or object
+//! store URI, title and timestamps): see the `read_with_metadata` methods of the file, PDF and
+//! EPUB loaders. The [ObjectStoreLoader] and the [GitRepoLoader] always do.
 
 pub mod file;
 
@@ -31,3 +45,15 @@ pub mod epub;
 
 #[cfg(feature = "epub")]
 pub use epub::{EpubFileLoader, RawTextProcessor, StripXmlProcessor, TextProcessor};
+
+#[cfg(feature = "s3")]
+pub mod s3;
+
+#[cfg(feature = "s3")]
+pub use s3::ObjectStoreLoader;
+
+#[cfg(feature = "git")]
+pub mod git;
+
+#[cfg(feature = "git")]
+pub use git::GitRepoLoader;
@@ -4,7 +4,8 @@ use glob::glob;
 use lopdf::{Document, Error as LopdfError};
 use thiserror::Error;
 
-use super::file::FileLoaderError;
+use super::file::{file_metadata, FileLoaderError};
+use crate::metadata;
 
 #[derive(Error, Debug)]
 pub enum PdfLoaderError {
@@ -208,6 +209,55 @@ impl<'a> PdfFileLoader<'a, Result<PathBuf, PdfLoaderError>> {
             })),
         }
     }
+
+    /// Reads the contents of the pdfs within the iterator returned by [PdfFileLoader::with_glob]
+    ///  or [PdfFileLoader::with_dir] as [Document](metadata::Document)s, with their metadata: the
+    ///  path as id, the `file://` URI as source, the title of the pdf (or the file stem), the file
+    ///  timestamps and the number of pages (as the `pages` custom metadata).
+    ///
+    /// # Example
+    /// Read pdfs in directory "tests/data/*.pdf" and split them into chunks.
+    ///
+    /// ```rust
+    /// # use rig::loaders::PdfFileLoader;
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let chunks = PdfFileLoader::with_glob("tests/data/*.pdf")?
+    ///     .read_with_metadata()
+    ///     .ignore_errors()
+    ///     .into_iter()
+    ///     .flat_map(|document| document.chunks(1000, 100));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_with_metadata(
+        self,
+    ) -> PdfFileLoader<'a, Result<metadata::Document, PdfLoaderError>> {
+        PdfFileLoader {
+            iterator: Box::new(self.iterator.map(|res| {
+                let (path, doc) = res.load_with_path()?;
+                let pages = doc.get_pages().len();
+                let content = (1..=pages as u32)
+                    .map(|page_no| doc.extract_text(&[page_no]))
+                    .collect::<Result<String, _>>()?;
+
+                let mut metadata = file_metadata(&path)
+                    .map_err(FileLoaderError::IoError)?
+                    .custom("pages", pages);
+                if let Some(title) = pdf_title(&doc) {
+                    metadata.title = Some(title);
+                }
+                Ok(metadata::Document::new(metadata, &content))
+            })),
+        }
+    }
+}
+
+/// The title of the document information dictionary of a pdf, if any.
+fn pdf_title(doc: &Document) -> Option<String> {
+    let info = doc.trailer.get(b"Info").ok()?;
+    let (_, info) = doc.dereference(info).ok()?;
+    let title = lopdf::decode_text_string(info.as_dict().ok()?.get(b"Title").ok()?).ok()?;
+    (!title.trim().is_empty()).then_some(title)
 }
 
 impl<'a> PdfFileLoader<'a, Document> {
@@ -409,6 +459,7 @@ mod tests {
     use std::path::PathBuf;
 
     use super::PdfFileLoader;
+    use crate::metadata::Document;
 
     #[test]
     fn test_pdf_loader() {
@@ -453,4 +504,24 @@ mod tests {
         assert!(!actual.is_empty());
         assert!(expected == actual)
     }
+
+    #[test]
+    fn test_pdf_loader_with_metadata() {
+        let mut documents = PdfFileLoader::with_glob("tests/data/*.pdf")
+            .unwrap()
+            .read_with_metadata()
+            .ignore_errors()
+            .into_iter()
+            .collect::<Vec<_>>();
+        documents.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+
+        let Document { metadata, text } = &documents[1];
+        assert_eq!(metadata.id, "tests/data/pages.pdf");
+        assert_eq!(text, "Page\n1\nPage\n2\nPage\n3\n");
+        assert_eq!(metadata.custom["pages"], 3);
+        assert!(metadata.source.as_ref().is_some_and(
+            |source| source.starts_with("file://") && source.ends_with("tests/data/pages.pdf")
+        ));
+        assert!(metadata.title.is_some() && metadata.updated_at.is_some());
+    }
 }
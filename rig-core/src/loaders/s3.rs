@@ -0,0 +1,240 @@
+//! This module provides [ObjectStoreLoader], which lists and reads text documents from object
+//! stores (e.g.: S3 or S3-compatible buckets such as MinIO or Cloudflare R2).
+//!
+//! The objects are listed under an optional prefix (the pages of the listing are fetched
+//! transparently), filtered by extension and size, and read concurrently into [Document]s
+//! whose metadata record the key, URI and last modification date of the objects. The documents
+//! can then be [chunked](Document::chunks) and embedded.
+//!
+//! Note: The [ObjectStoreLoader] requires the `s3` feature to be enabled in the `Cargo.toml` file.
+//!
+//! # Example
+//! ```rust
+//! use futures::StreamExt;
+//! use rig::{embeddings::EmbeddingsBuilder, loaders::ObjectStoreLoader, providers::openai};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! // Configured from the `AWS_*` environment variables
+//! let loader = ObjectStoreLoader::s3("acme-docs")?
+//!     .prefix("handbook/")
+//!     .extensions(["md", "txt"])
+//!     .concurrency(16);
+//!
+//! let mut chunks = vec![];
+//! let mut documents = loader.stream().await?;
+//! while let Some(document) = documents.next().await {
+//!     chunks.extend(document?.chunks(2000, 200));
+//! }
+//!
+//! let openai = openai::Client::from_env();
+//! let embeddings = EmbeddingsBuilder::new(openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL))
+//!     .documents(chunks)?
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{sync::Arc, time::SystemTime};
+
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use object_store::{path::Path, ObjectMeta, ObjectStore};
+use thiserror::Error;
+
+use crate::metadata::{rfc3339, Document, DocumentMetadata};
+
+#[derive(Error, Debug)]
+pub enum ObjectStoreLoaderError {
+    #[error("Object store error: {0}")]
+    ObjectStoreError(#[from] object_store::Error),
+
+    #[error("The object {0} is not valid UTF-8 text")]
+    InvalidUtf8(String),
+}
+
+/// Loads the text objects of an object store, see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct ObjectStoreLoader {
+    store: Arc<dyn ObjectStore>,
+    base_uri: String,
+    prefix: Option<Path>,
+    extensions: Vec<String>,
+    max_size: Option<usize>,
+    concurrency: usize,
+}
+
+impl ObjectStoreLoader {
+    /// Load the objects of the given object store, whose objects have URIs starting with
+    /// `base_uri` (e.g.: `s3://bucket`).
+    pub fn new(store: Arc<dyn ObjectStore>, base_uri: &str) -> Self {
+        Self {
+            store,
+            base_uri: base_uri.trim_end_matches('/').to_string(),
+            prefix: None,
+            extensions: vec![],
+            max_size: None,
+            concurrency: 8,
+        }
+    }
+
+    /// Load the objects of an S3 bucket, configured from the `AWS_*` environment variables
+    /// (credentials, region, endpoint for S3-compatible services).
+    pub fn s3(bucket: &str) -> Result<Self, ObjectStoreLoaderError> {
+        let store = object_store::aws::AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()?;
+        Ok(Self::new(Arc::new(store), &format!("s3://{bucket}")))
+    }
+
+    /// Only load the objects under the prefix (e.g.: `handbook/`).
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(Path::from(prefix));
+        self
+    }
+
+    /// Only load the objects with one of the extensions (case insensitive, without the dot).
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: impl IntoIterator<Item = S>) -> Self {
+        self.extensions = extensions
+            .into_iter()
+            .map(|extension| extension.as_ref().trim_start_matches('.').to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Skip the objects larger than the size, in bytes.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Set the maximum number of objects read concurrently (8 by default).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    fn accepts(&self, object: &ObjectMeta) -> bool {
+        let extension = object
+            .location
+            .extension()
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        (self.extensions.is_empty() || self.extensions.contains(&extension))
+            && self.max_size.is_none_or(|max_size| object.size <= max_size)
+    }
+
+    /// List the objects to load, in the order of the object store (lexicographic for S3).
+    pub async fn list(&self) -> Result<Vec<ObjectMeta>, ObjectStoreLoaderError> {
+        Ok(self
+            .store
+            .list(self.prefix.as_ref())
+            .try_filter(|object| futures::future::ready(self.accepts(object)))
+            .try_collect()
+            .await?)
+    }
+
+    /// Read the objects as text documents, concurrently. The documents are yielded as soon as
+    /// they are read, hence not necessarily in the order of the listing.
+    pub async fn stream(
+        &self,
+    ) -> Result<BoxStream<'_, Result<Document, ObjectStoreLoaderError>>, ObjectStoreLoaderError>
+    {
+        let objects = self.list().await?;
+
+        Ok(futures::stream::iter(objects)
+            .map(|object| self.read(object))
+            .buffer_unordered(self.concurrency)
+            .boxed())
+    }
+
+    /// Read all the objects as text documents.
+    pub async fn load(&self) -> Result<Vec<Document>, ObjectStoreLoaderError> {
+        self.stream().await?.try_collect().await
+    }
+
+    /// Read an object as a text document.
+    pub async fn read(&self, object: ObjectMeta) -> Result<Document, ObjectStoreLoaderError> {
+        let data = self.store.get(&object.location).await?.bytes().await?;
+        let key = object.location.to_string();
+        let text = String::from_utf8(data.to_vec())
+            .map_err(|_| ObjectStoreLoaderError::InvalidUtf8(key.clone()))?;
+
+        let mut metadata = DocumentMetadata::new(&key)
+            .source(&format!("{}/{}", self.base_uri, key))
+            .updated_at(&rfc3339(SystemTime::from(object.last_modified)));
+        if let Some(filename) = object.location.filename() {
+            let title = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+            metadata = metadata.title(title);
+        }
+        if let Some(e_tag) = object.e_tag {
+            metadata = metadata.custom("e_tag", e_tag);
+        }
+
+        Ok(Document::new(metadata, &text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::{memory::InMemory, PutPayload};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_object_store_loader() {
+        let store = Arc::new(InMemory::new());
+        for (key, content) in [
+            ("handbook/intro.md", "# Welcome".as_bytes()),
+            ("handbook/holidays.TXT", "25 days per year".as_bytes()),
+            ("handbook/logo.png", &[0x89, 0x50, 0x4e, 0x47]),
+            ("handbook/binary.txt", &[0xff, 0xfe]),
+            ("archive/old.md", "# Old".as_bytes()),
+        ] {
+            store
+                .put(&Path::from(key), PutPayload::from(content.to_vec()))
+                .await
+                .unwrap();
+        }
+
+        let loader = ObjectStoreLoader::new(store, "s3://acme/")
+            .prefix("handbook")
+            .extensions([".md", "txt"])
+            .concurrency(2);
+
+        let keys = loader
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|object| object.location.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "handbook/binary.txt",
+                "handbook/holidays.TXT",
+                "handbook/intro.md"
+            ]
+        );
+
+        let mut documents = loader.stream().await.unwrap().collect::<Vec<_>>().await;
+        documents.sort_by_key(|document| document.as_ref().map(|d| d.metadata.id.clone()).ok());
+
+        assert!(matches!(
+            documents[0],
+            Err(ObjectStoreLoaderError::InvalidUtf8(ref key)) if key == "handbook/binary.txt"
+        ));
+        let intro = documents[2].as_ref().unwrap();
+        assert_eq!(intro.text, "# Welcome");
+        assert_eq!(intro.metadata.id, "handbook/intro.md");
+        assert_eq!(
+            intro.metadata.source.as_deref(),
+            Some("s3://acme/handbook/intro.md")
+        );
+        assert_eq!(intro.metadata.title.as_deref(), Some("intro"));
+        assert!(intro.metadata.updated_at.is_some());
+
+        let loader = loader.max_size(10);
+        assert_eq!(loader.list().await.unwrap().len(), 2);
+    }
+}
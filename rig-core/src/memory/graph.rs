@@ -0,0 +1,510 @@
+//! Experimental entity/knowledge-graph memory.
+//!
+//! [GraphMemory] uses an [Extractor] to extract entities and the relations between them from
+//! conversations. The extracted facts are stored in a [GraphStore] (e.g.: [InMemoryGraphStore],
+//! or the Neo4j-backed store provided by `rig-neo4j`) and can be recalled later on, either
+//! directly with [GraphMemory::recall] or as dynamic context of an agent using [GraphMemory::index].
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::Prompt,
+//!     memory::{GraphMemory, InMemoryGraphStore},
+//!     message::Message,
+//!     providers::openai,
+//! };
+//!
+//! let openai = openai::Client::from_env();
+//!
+//! let memory = GraphMemory::new(
+//!     openai.completion_model(openai::GPT_4O),
+//!     InMemoryGraphStore::default(),
+//! );
+//!
+//! // Extract and store facts from a conversation
+//! memory
+//!     .observe(&[
+//!         Message::user("My sister Alice works at Acme as an engineer."),
+//!         Message::assistant("Nice! How long has Alice been at Acme?"),
+//!     ])
+//!     .await?;
+//!
+//! // Use the stored facts as context for future prompts
+//! let agent = openai.agent(openai::GPT_4O)
+//!     .preamble("You are a helpful assistant. Use the known facts about the user when relevant.")
+//!     .dynamic_context(5, memory.index())
+//!     .build();
+//!
+//! let response = agent.prompt("Where does Alice work?").await?;
+//! ```
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::CompletionModel,
+    extractor::{ExtractionError, Extractor, ExtractorBuilder},
+    message::{AssistantContent, Message, Text, UserContent},
+    vector_store::{VectorStoreError, VectorStoreIndex},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum GraphMemoryError {
+    /// Error extracting the entities and relations from the conversation
+    #[error("ExtractionError: {0}")]
+    ExtractionError(#[from] ExtractionError),
+
+    /// Error returned by the graph store
+    #[error("StoreError: {0}")]
+    StoreError(#[from] VectorStoreError),
+}
+
+/// An entity (person, place, organization, concept, etc.) mentioned in a conversation.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct Entity {
+    /// The name of the entity as mentioned in the conversation (e.g.: "Alice", "Acme Corp")
+    pub name: String,
+    /// The type of the entity (e.g.: "person", "organization", "location", "concept")
+    pub kind: String,
+}
+
+/// A directed relation between two entities (e.g.: "Alice" -"works at"-> "Acme Corp").
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct Relation {
+    /// The name of the source entity
+    pub source: String,
+    /// A short description of the relation (e.g.: "works at", "is the sister of")
+    pub relation: String,
+    /// The name of the target entity
+    pub target: String,
+}
+
+impl Relation {
+    /// Returns true if the relation connects the same entities with the same relation
+    /// as `other`, ignoring case.
+    fn same_as(&self, other: &Relation) -> bool {
+        self.source.eq_ignore_ascii_case(&other.source)
+            && self.relation.eq_ignore_ascii_case(&other.relation)
+            && self.target.eq_ignore_ascii_case(&other.target)
+    }
+}
+
+impl std::fmt::Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.source, self.relation, self.target)
+    }
+}
+
+/// The entities and relations extracted from a conversation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct KnowledgeGraph {
+    /// The entities mentioned in the conversation
+    pub entities: Vec<Entity>,
+    /// The relations between the entities mentioned in the conversation
+    pub relations: Vec<Relation>,
+}
+
+/// Trait for storage backends of a [GraphMemory].
+pub trait GraphStore: Send + Sync {
+    /// Add the entities and relations of `graph` to the store.
+    /// Entities and relations that already exist in the store should not be duplicated.
+    fn add_graph(
+        &self,
+        graph: KnowledgeGraph,
+    ) -> impl Future<Output = Result<(), VectorStoreError>> + Send;
+
+    /// Get at most `n` relations relevant to the given query, along with their relevance score.
+    /// The relations are returned in order of decreasing relevance.
+    fn facts(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> impl Future<Output = Result<Vec<(f64, Relation)>, VectorStoreError>> + Send;
+}
+
+#[derive(Default)]
+struct Graph {
+    /// Entities, keyed by their lowercased name
+    entities: HashMap<String, Entity>,
+    /// Relations, in insertion order
+    relations: Vec<Relation>,
+}
+
+/// [InMemoryGraphStore] is a simple [GraphStore] that keeps the graph in memory.
+///
+/// A relation is considered relevant to a query if the name of its source and/or target entity
+/// is mentioned in the query (case insensitive). Relations that mention both entities rank
+/// higher, and more recent relations win ties.
+#[derive(Default)]
+pub struct InMemoryGraphStore {
+    graph: RwLock<Graph>,
+}
+
+impl InMemoryGraphStore {
+    /// Get all entities currently in the store.
+    pub fn entities(&self) -> Vec<Entity> {
+        self.graph
+            .read()
+            .expect("Graph lock should not be poisoned")
+            .entities
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Get all relations currently in the store, in insertion order.
+    pub fn relations(&self) -> Vec<Relation> {
+        self.graph
+            .read()
+            .expect("Graph lock should not be poisoned")
+            .relations
+            .clone()
+    }
+}
+
+impl GraphStore for InMemoryGraphStore {
+    async fn add_graph(&self, graph: KnowledgeGraph) -> Result<(), VectorStoreError> {
+        let mut store = self
+            .graph
+            .write()
+            .expect("Graph lock should not be poisoned");
+
+        for entity in graph.entities {
+            store.entities.insert(entity.name.to_lowercase(), entity);
+        }
+
+        for relation in graph.relations {
+            if !store.relations.iter().any(|r| r.same_as(&relation)) {
+                store.relations.push(relation);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn facts(&self, query: &str, n: usize) -> Result<Vec<(f64, Relation)>, VectorStoreError> {
+        let store = self
+            .graph
+            .read()
+            .expect("Graph lock should not be poisoned");
+
+        let mut facts = store
+            .relations
+            .iter()
+            .enumerate()
+            .filter_map(|(i, relation)| {
+                let score = [&relation.source, &relation.target]
+                    .iter()
+                    .filter(|name| mentions(query, name))
+                    .count();
+
+                (score > 0).then_some((score, i, relation))
+            })
+            .collect::<Vec<_>>();
+
+        // Sort by score, then by recency
+        facts.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+        Ok(facts
+            .into_iter()
+            .take(n)
+            .map(|(score, _, relation)| (score as f64, relation.clone()))
+            .collect())
+    }
+}
+
+/// Whether the query mentions the entity name as a whole word or phrase, ignoring case,
+/// e.g.: "Al" is not mentioned in "Does alice know Bob?". Empty names are never mentioned.
+///
+/// [GraphStore] implementations use it to find the relations relevant to a query.
+pub fn mentions(query: &str, name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        return false;
+    }
+    let query = query.to_lowercase();
+
+    query.match_indices(name.as_str()).any(|(start, _)| {
+        let before = query[..start].chars().next_back();
+        let after = query[start + name.len()..].chars().next();
+        [before, after]
+            .iter()
+            .all(|c| !c.is_some_and(char::is_alphanumeric))
+    })
+}
+
+/// Experimental entity memory backed by a knowledge graph.
+///
+/// See the [module documentation](self) for more information.
+pub struct GraphMemory<M: CompletionModel, S: GraphStore> {
+    extractor: Extractor<M, KnowledgeGraph>,
+    store: Arc<S>,
+}
+
+impl<M: CompletionModel, S: GraphStore> GraphMemory<M, S> {
+    /// Create a new graph memory that uses `model` to extract entities and relations
+    /// and `store` to store them.
+    pub fn new(model: M, store: S) -> Self {
+        let extractor = ExtractorBuilder::new(model)
+            .preamble(
+                "Extract the entities (people, organizations, places, objects, concepts) mentioned \
+                in the conversation and the relations between them. Only extract facts that are \
+                explicitly stated. Use the same entity names in relations as in the list of entities.",
+            )
+            .build();
+
+        Self {
+            extractor,
+            store: Arc::new(store),
+        }
+    }
+
+    /// Get a reference to the underlying graph store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Extract the entities and relations from `text` and add them to the store.
+    /// Returns the extracted graph.
+    pub async fn observe_text(&self, text: &str) -> Result<KnowledgeGraph, GraphMemoryError> {
+        let graph = self.extractor.extract(text).await?;
+
+        tracing::info!(target: "rig",
+            "Graph memory extracted {} entities and {} relations",
+            graph.entities.len(),
+            graph.relations.len()
+        );
+
+        self.store.add_graph(graph.clone()).await?;
+        Ok(graph)
+    }
+
+    /// Extract the entities and relations from the text content of `messages` and add them
+    /// to the store. Returns the extracted graph.
+    pub async fn observe(&self, messages: &[Message]) -> Result<KnowledgeGraph, GraphMemoryError> {
+        self.observe_text(&transcript(messages)).await
+    }
+
+    /// Recall at most `n` facts relevant to the given query.
+    pub async fn recall(&self, query: &str, n: usize) -> Result<Vec<Relation>, GraphMemoryError> {
+        Ok(self
+            .store
+            .facts(query, n)
+            .await?
+            .into_iter()
+            .map(|(_, relation)| relation)
+            .collect())
+    }
+
+    /// Create a [VectorStoreIndex] over the facts of this memory so it can be used as the
+    /// dynamic context of an [Agent](crate::agent::Agent) or in a pipeline lookup op.
+    pub fn index(&self) -> GraphMemoryIndex<S> {
+        GraphMemoryIndex {
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// [VectorStoreIndex] view over the facts of a [GraphMemory].
+///
+/// Documents returned by the index are [Relation]s and their ids have the
+/// form `"{source} {relation} {target}"`.
+pub struct GraphMemoryIndex<S: GraphStore> {
+    store: Arc<S>,
+}
+
+impl<S: GraphStore> VectorStoreIndex for GraphMemoryIndex<S> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        self.store
+            .facts(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, relation)| {
+                Ok((
+                    score,
+                    relation.to_string(),
+                    serde_json::from_value(serde_json::to_value(&relation)?)?,
+                ))
+            })
+            .collect()
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(self
+            .store
+            .facts(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, relation)| (score, relation.to_string()))
+            .collect())
+    }
+}
+
+/// Render the text content of `messages` as a plain text transcript.
+pub(crate) fn transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .filter_map(|message| {
+            let (role, texts) = match message {
+                Message::User { content, .. } => (
+                    "user",
+                    content
+                        .iter()
+                        .filter_map(|content| match content {
+                            UserContent::Text(Text { text }) => Some(text.as_str()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                Message::Assistant { content, .. } => (
+                    "assistant",
+                    content
+                        .iter()
+                        .filter_map(|content| match content {
+                            AssistantContent::Text(Text { text }) => Some(text.as_str()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            };
+
+            (!texts.is_empty()).then(|| format!("{role}: {}", texts.join("\n")))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation(source: &str, relation: &str, target: &str) -> Relation {
+        Relation {
+            source: source.to_string(),
+            relation: relation.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_graph_store_dedup() {
+        let store = InMemoryGraphStore::default();
+
+        store
+            .add_graph(KnowledgeGraph {
+                entities: vec![Entity {
+                    name: "Alice".to_string(),
+                    kind: "person".to_string(),
+                }],
+                relations: vec![relation("Alice", "works at", "Acme")],
+            })
+            .await
+            .unwrap();
+
+        store
+            .add_graph(KnowledgeGraph {
+                entities: vec![Entity {
+                    name: "alice".to_string(),
+                    kind: "person".to_string(),
+                }],
+                relations: vec![relation("alice", "WORKS AT", "acme")],
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(store.entities().len(), 1);
+        assert_eq!(
+            store.relations(),
+            vec![relation("Alice", "works at", "Acme")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_graph_store_facts() {
+        let store = InMemoryGraphStore::default();
+
+        store
+            .add_graph(KnowledgeGraph {
+                entities: vec![],
+                relations: vec![
+                    relation("Alice", "works at", "Acme"),
+                    relation("Bob", "lives in", "Paris"),
+                    relation("Alice", "is the sister of", "Bob"),
+                    relation("Carol", "likes", "Paris"),
+                ],
+            })
+            .await
+            .unwrap();
+
+        let facts = store.facts("Does alice know Bob?", 10).await.unwrap();
+        assert_eq!(
+            facts,
+            vec![
+                (2.0, relation("Alice", "is the sister of", "Bob")),
+                (1.0, relation("Bob", "lives in", "Paris")),
+                (1.0, relation("Alice", "works at", "Acme")),
+            ]
+        );
+
+        let facts = store.facts("Does alice know Bob?", 1).await.unwrap();
+        assert_eq!(facts.len(), 1);
+
+        let facts = store.facts("What about Dave?", 10).await.unwrap();
+        assert!(facts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_graph_store_facts_match_whole_words() {
+        let store = InMemoryGraphStore::default();
+
+        store
+            .add_graph(KnowledgeGraph {
+                entities: vec![],
+                relations: vec![
+                    relation("Al", "works at", "Acme"),
+                    relation("", "knows", "Bob"),
+                    relation("New York", "is in", "USA"),
+                ],
+            })
+            .await
+            .unwrap();
+
+        let facts = store.facts("Does Alice know Bobby?", 10).await.unwrap();
+        assert!(facts.is_empty());
+
+        let facts = store.facts("Has Al been to new york?", 10).await.unwrap();
+        assert_eq!(
+            facts,
+            vec![
+                (1.0, relation("New York", "is in", "USA")),
+                (1.0, relation("Al", "works at", "Acme")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transcript() {
+        let messages = vec![
+            Message::user("My sister Alice works at Acme."),
+            Message::assistant("Good to know!"),
+        ];
+
+        assert_eq!(
+            transcript(&messages),
+            "user: My sister Alice works at Acme.\nassistant: Good to know!"
+        );
+    }
+}
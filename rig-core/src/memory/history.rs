@@ -0,0 +1,449 @@
+//! This module provides [HistoryPolicy], the strategies trimming the chat history of an agent
+//! before it is sent with a prompt, to keep long conversations within the context window (and
+//! the budget) of the model.
+//!
+//! Built-in policies:
+//! - [KeepLast]: keep the last messages,
+//! - [TokenBudget]: keep the most recent messages fitting in a token budget,
+//! - [ImportanceWeighted]: keep the messages with the best importance and recency fitting in a
+//!   token budget,
+//! - [SummarizeThenTrim]: replace the oldest messages with a summary written by a model.
+//!
+//! The policies keep whole turns (a user message and the assistant messages and tool results
+//! following it), so that tool results are never separated from their tool calls.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Chat, memory::history::TokenBudget, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .history_policy(TokenBudget::new(8_000))
+//!     .build();
+//!
+//! let history = vec![/* a long conversation */];
+//! let response = agent.chat("What did we decide?", history).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{future::Future, sync::Arc};
+
+use futures::future::BoxFuture;
+
+use super::graph::transcript;
+use crate::{
+    completion::{Prompt, PromptError},
+    message::{Message, UserContent},
+    models,
+    tokens::{self, Heuristic, TokenCounter},
+};
+
+/// Trait for strategies trimming the chat history of an agent, see the
+/// [module documentation](self).
+pub trait HistoryPolicy: Send + Sync {
+    /// Trim the chat history before it is sent with a prompt.
+    fn apply(
+        &self,
+        history: Vec<Message>,
+    ) -> impl Future<Output = Result<Vec<Message>, PromptError>> + Send;
+}
+
+/// Object-safe version of [HistoryPolicy], implemented for all history policies.
+pub trait HistoryPolicyDyn: Send + Sync {
+    fn apply(&self, history: Vec<Message>) -> BoxFuture<'_, Result<Vec<Message>, PromptError>>;
+}
+
+impl<P: HistoryPolicy> HistoryPolicyDyn for P {
+    fn apply(&self, history: Vec<Message>) -> BoxFuture<'_, Result<Vec<Message>, PromptError>> {
+        Box::pin(HistoryPolicy::apply(self, history))
+    }
+}
+
+/// Whether a turn starts with the message, i.e.: it is a user message without tool results.
+fn is_turn_start(message: &Message) -> bool {
+    match message {
+        Message::User { content, .. } => !content
+            .iter()
+            .any(|content| matches!(content, UserContent::ToolResult(_))),
+        Message::Assistant { .. } => false,
+    }
+}
+
+/// Get the index of the first turn starting at or after `index`.
+fn turn_start_from(history: &[Message], index: usize) -> usize {
+    (index..history.len())
+        .find(|&i| is_turn_start(&history[i]))
+        .unwrap_or(history.len())
+}
+
+/// Split the history into turns. The first turn may not start with a user message.
+fn turns(history: Vec<Message>) -> Vec<Vec<Message>> {
+    let mut turns: Vec<Vec<Message>> = vec![];
+    for message in history {
+        match turns.last_mut() {
+            Some(turn) if !is_turn_start(&message) => turn.push(message),
+            _ => turns.push(vec![message]),
+        }
+    }
+    turns
+}
+
+/// Drop the oldest half of the turns of the history (at least one turn), to shrink a request
+/// that does not fit in the context window of the model.
+pub(crate) fn drop_oldest_turns(history: Vec<Message>) -> Vec<Message> {
+    let mut turns = turns(history);
+    let dropped = turns.len().div_ceil(2);
+    turns.drain(..dropped);
+    turns.into_iter().flatten().collect()
+}
+
+/// Keep (at most) the last `n` messages of the history.
+#[derive(Clone, Copy, Debug)]
+pub struct KeepLast(pub usize);
+
+impl HistoryPolicy for KeepLast {
+    async fn apply(&self, mut history: Vec<Message>) -> Result<Vec<Message>, PromptError> {
+        let start = turn_start_from(&history, history.len().saturating_sub(self.0));
+        Ok(history.split_off(start))
+    }
+}
+
+/// Keep the most recent turns of the history fitting in a token budget.
+#[derive(Clone)]
+pub struct TokenBudget {
+    max_tokens: usize,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl TokenBudget {
+    /// Create a new token budget of `max_tokens` tokens, counted with the default [Heuristic].
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            counter: Arc::new(Heuristic::default()),
+        }
+    }
+
+    /// Create a token budget filling the context window of a model of the
+    /// [catalog](crate::models), less its output limit, counted with the best counter available
+    /// for the model. Returns `None` if the model is not in the catalog.
+    ///
+    /// The budget is an upper bound: the preamble, documents and prompt use the context window
+    /// too.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let info = models::get(model)?;
+        Some(Self {
+            max_tokens: info
+                .context_window
+                .saturating_sub(info.max_output_tokens.unwrap_or_default()),
+            counter: tokens::counter_for_model(model),
+        })
+    }
+
+    /// Set the token counter of the budget (e.g.: [counter_for_model](crate::tokens::counter_for_model)).
+    pub fn counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+}
+
+impl HistoryPolicy for TokenBudget {
+    async fn apply(&self, history: Vec<Message>) -> Result<Vec<Message>, PromptError> {
+        let mut budget = self.max_tokens;
+        let mut kept = vec![];
+        for turn in turns(history).into_iter().rev() {
+            match budget.checked_sub(self.counter.count_messages_tokens(&turn)) {
+                Some(remaining) => budget = remaining,
+                None => break,
+            }
+            kept.push(turn);
+        }
+        Ok(kept.into_iter().rev().flatten().collect())
+    }
+}
+
+/// Keep the turns of the history with the best scores fitting in a token budget, in their
+/// original order. The score of a turn is the importance of its most important message, halved
+/// every [half_life](ImportanceWeighted::half_life) turns of age.
+///
+/// Unlike [TokenBudget], old important turns (e.g.: where the user stated their goal) can be
+/// kept while more recent, less important or larger turns are dropped.
+#[derive(Clone)]
+pub struct ImportanceWeighted {
+    max_tokens: usize,
+    counter: Arc<dyn TokenCounter>,
+    importance: Arc<dyn Fn(&Message) -> f64 + Send + Sync>,
+    half_life: f64,
+}
+
+impl ImportanceWeighted {
+    /// Create a new policy with a budget of `max_tokens` tokens, counted with the default
+    /// [Heuristic]. All messages are equally important by default.
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            counter: Arc::new(Heuristic::default()),
+            importance: Arc::new(|_| 1.0),
+            half_life: 4.0,
+        }
+    }
+
+    /// Set the token counter of the budget.
+    pub fn counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+
+    /// Set the function scoring the importance of the messages (higher is more important).
+    pub fn importance(
+        mut self,
+        importance: impl Fn(&Message) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        self.importance = Arc::new(importance);
+        self
+    }
+
+    /// Set the number of turns after which the score of a turn is halved (defaults to 4).
+    pub fn half_life(mut self, turns: f64) -> Self {
+        self.half_life = turns.max(f64::MIN_POSITIVE);
+        self
+    }
+}
+
+impl HistoryPolicy for ImportanceWeighted {
+    async fn apply(&self, history: Vec<Message>) -> Result<Vec<Message>, PromptError> {
+        let turns = turns(history);
+        let last = turns.len().saturating_sub(1);
+
+        let mut ranked = turns
+            .iter()
+            .enumerate()
+            .map(|(i, turn)| {
+                let importance = turn
+                    .iter()
+                    .map(|message| (self.importance)(message))
+                    .fold(f64::MIN, f64::max);
+                let score = importance * 0.5f64.powf((last - i) as f64 / self.half_life);
+                (score, i)
+            })
+            .collect::<Vec<_>>();
+        // Most recent first among equal scores
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+
+        let mut budget = self.max_tokens;
+        let mut kept = vec![false; turns.len()];
+        for (_, i) in ranked {
+            if let Some(remaining) =
+                budget.checked_sub(self.counter.count_messages_tokens(&turns[i]))
+            {
+                budget = remaining;
+                kept[i] = true;
+            }
+        }
+
+        Ok(turns
+            .into_iter()
+            .zip(kept)
+            .filter_map(|(turn, kept)| kept.then_some(turn))
+            .flatten()
+            .collect())
+    }
+}
+
+/// Once the history is longer than [max_messages](SummarizeThenTrim::max_messages), replace its
+/// oldest messages with a summary written by a model, keeping the last
+/// [keep_last](SummarizeThenTrim::keep_last) messages as is.
+///
+/// The summary is sent as a user message. It is written again on every prompt exceeding the
+/// limit, so store the trimmed history (e.g.: in a [Conversation](crate::conversation::Conversation))
+/// rather than the full one to avoid repeated calls to the model.
+pub struct SummarizeThenTrim<P: Prompt> {
+    model: P,
+    max_messages: usize,
+    keep_last: usize,
+    prompt: String,
+}
+
+impl<P: Prompt> SummarizeThenTrim<P> {
+    pub fn new(model: P) -> Self {
+        Self {
+            model,
+            max_messages: 20,
+            keep_last: 10,
+            prompt: "Summarize the following conversation between a user and an assistant. \
+                Keep the facts, decisions and open questions, and nothing else.\n\n{transcript}"
+                .to_string(),
+        }
+    }
+
+    /// Set the number of messages above which the history is summarized (defaults to 20).
+    pub fn max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = max_messages;
+        self
+    }
+
+    /// Set the number of recent messages kept as is (defaults to 10).
+    pub fn keep_last(mut self, keep_last: usize) -> Self {
+        self.keep_last = keep_last;
+        self
+    }
+
+    /// Set the summarization prompt. `{transcript}` is replaced by the summarized messages.
+    pub fn prompt(mut self, prompt: &str) -> Self {
+        self.prompt = prompt.to_string();
+        self
+    }
+}
+
+impl<P: Prompt> HistoryPolicy for SummarizeThenTrim<P> {
+    async fn apply(&self, mut history: Vec<Message>) -> Result<Vec<Message>, PromptError> {
+        if history.len() <= self.max_messages {
+            return Ok(history);
+        }
+        let start = turn_start_from(&history, history.len().saturating_sub(self.keep_last));
+        if start == 0 {
+            return Ok(history);
+        }
+
+        let recent = history.split_off(start);
+        let summary = self
+            .model
+            .prompt(self.prompt.replace("{transcript}", &transcript(&history)))
+            .await?;
+
+        Ok(std::iter::once(Message::user(format!(
+            "Summary of the earlier conversation:\n{}",
+            summary.trim()
+        )))
+        .chain(recent)
+        .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::message::{ToolResult, ToolResultContent},
+        message::{AssistantContent, Text},
+        OneOrMany,
+    };
+
+    /// 3 turns of 2 messages, the second one with a tool call and its result
+    fn history() -> Vec<Message> {
+        vec![
+            Message::user("My goal is to learn Rust"),
+            Message::assistant("Great!"),
+            Message::user("What time is it?"),
+            Message::Assistant {
+                content: OneOrMany::one(AssistantContent::tool_call("1", "time", "{}".into())),
+                name: None,
+            },
+            Message::User {
+                content: OneOrMany::one(UserContent::ToolResult(ToolResult {
+                    id: "1".to_string(),
+                    content: OneOrMany::one(ToolResultContent::Text(Text {
+                        text: "12:00".to_string(),
+                    })),
+                })),
+                name: None,
+            },
+            Message::assistant("It is noon."),
+            Message::user("Thanks"),
+            Message::assistant("You're welcome"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_keep_last() {
+        // The 3 last messages start with a tool result, which is dropped with its turn
+        let kept = HistoryPolicy::apply(&KeepLast(3), history()).await.unwrap();
+        assert_eq!(kept, history()[6..]);
+
+        let kept = HistoryPolicy::apply(&KeepLast(100), history())
+            .await
+            .unwrap();
+        assert_eq!(kept, history());
+    }
+
+    #[tokio::test]
+    async fn test_token_budget() {
+        let counter = Heuristic::default();
+        let last_turns = counter.count_messages_tokens(&history()[2..]);
+
+        let kept = HistoryPolicy::apply(&TokenBudget::new(last_turns), history())
+            .await
+            .unwrap();
+        assert_eq!(kept, history()[2..]);
+
+        let kept = HistoryPolicy::apply(&TokenBudget::new(last_turns - 1), history())
+            .await
+            .unwrap();
+        assert_eq!(kept, history()[6..]);
+
+        let budget = TokenBudget::for_model("gpt-4o-2024-08-06").unwrap();
+        assert_eq!(budget.max_tokens, 128_000 - 16_384);
+        assert!(TokenBudget::for_model("unknown").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_importance_weighted() {
+        let counter = Heuristic::default();
+        let budget = counter.count_messages_tokens(&history()[..2])
+            + counter.count_messages_tokens(&history()[6..]);
+
+        // The goal of the user is more important than the tool call
+        let policy = ImportanceWeighted::new(budget).importance(|message| match message {
+            Message::User { content, .. } => match content.first() {
+                UserContent::Text(Text { text }) if text.contains("goal") => 10.0,
+                _ => 1.0,
+            },
+            _ => 1.0,
+        });
+
+        let kept = HistoryPolicy::apply(&policy, history()).await.unwrap();
+        assert_eq!(kept, [&history()[..2], &history()[6..]].concat());
+    }
+
+    struct MockSummarizer;
+
+    impl Prompt for MockSummarizer {
+        async fn prompt(&self, prompt: impl Into<Message> + Send) -> Result<String, PromptError> {
+            let Message::User { content, .. } = prompt.into() else {
+                unreachable!()
+            };
+            let UserContent::Text(Text { text }) = content.first() else {
+                unreachable!()
+            };
+            Ok(format!(" {} lines ", text.lines().count()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summarize_then_trim() {
+        let policy = SummarizeThenTrim::new(MockSummarizer)
+            .max_messages(4)
+            .keep_last(2)
+            .prompt("{transcript}");
+
+        // The tool call and result have no text, and are not in the transcript
+        let trimmed = HistoryPolicy::apply(&policy, history()).await.unwrap();
+        assert_eq!(
+            trimmed,
+            [
+                vec![Message::user(
+                    "Summary of the earlier conversation:\n4 lines"
+                )],
+                history()[6..].to_vec()
+            ]
+            .concat()
+        );
+
+        let short = HistoryPolicy::apply(&policy, history()[..4].to_vec())
+            .await
+            .unwrap();
+        assert_eq!(short, history()[..4]);
+    }
+}
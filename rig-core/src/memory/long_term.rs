@@ -0,0 +1,398 @@
+//! This module provides a long-term memory for agents: the [SaveMemoryTool] and
+//! [RecallMemoryTool] tools let an agent save facts (e.g.: the preferences of a user) and recall
+//! them by similarity in later sessions.
+//!
+//! Memories are stored in a [MemoryStore], in a namespace per user (or per tenant), so that an
+//! agent only recalls the memories of the user it is talking to:
+//! - [InMemoryMemoryStore] keeps the memories in memory,
+//! - [VectorMemoryStore] stores them in any vector store that supports namespaces
+//!   ([NamespacedVectorStoreIndex]) and imports ([VectorStoreImport]).
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use rig::{
+//!     memory::long_term::{memory_tools, InMemoryMemoryStore},
+//!     providers::openai,
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//! let store = Arc::new(InMemoryMemoryStore::new(
+//!     openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+//! ));
+//!
+//! let (save, recall) = memory_tools(store, "user-42");
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .preamble("Save what you learn about the user, and recall it when useful.")
+//!     .tool(save)
+//!     .tool(recall)
+//!     .build();
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    completion::ToolDefinition,
+    embeddings::{distance::VectorDistance, Embedding, EmbeddingModel},
+    metadata::rfc3339,
+    test_mode,
+    tool::Tool,
+    vector_store::{
+        NamespacedVectorStoreIndex, VectorStoreError, VectorStoreImport, VectorStoreIndex,
+    },
+    OneOrMany,
+};
+
+/// A memory saved by an agent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Memory {
+    pub text: String,
+    /// RFC 3339 date-time at which the memory was saved
+    pub created_at: String,
+}
+
+impl Memory {
+    /// Create a new memory, saved now.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            created_at: rfc3339(SystemTime::now()),
+        }
+    }
+}
+
+/// Trait for the stores of the memories of agents, partitioned into namespaces.
+pub trait MemoryStore: Send + Sync {
+    /// Save a memory in the namespace.
+    fn save(
+        &self,
+        namespace: &str,
+        memory: Memory,
+    ) -> impl Future<Output = Result<(), VectorStoreError>> + Send;
+
+    /// Recall the `n` memories of the namespace most similar to the query, most similar first.
+    /// The result is a list of tuples of the form (score, memory)
+    fn recall(
+        &self,
+        namespace: &str,
+        query: &str,
+        n: usize,
+    ) -> impl Future<Output = Result<Vec<(f64, Memory)>, VectorStoreError>> + Send;
+}
+
+/// A [MemoryStore] keeping the memories and their embeddings in memory.
+pub struct InMemoryMemoryStore<M: EmbeddingModel> {
+    model: M,
+    memories: Mutex<HashMap<String, Vec<(Memory, Embedding)>>>,
+}
+
+impl<M: EmbeddingModel> InMemoryMemoryStore<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            memories: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the memories of a namespace, in the order they were saved.
+    pub fn memories(&self, namespace: &str) -> Vec<Memory> {
+        self.memories
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(namespace)
+            .map(|memories| memories.iter().map(|(memory, _)| memory.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl<M: EmbeddingModel> MemoryStore for InMemoryMemoryStore<M> {
+    async fn save(&self, namespace: &str, memory: Memory) -> Result<(), VectorStoreError> {
+        let embedding = self.model.embed_text(&memory.text).await?;
+        self.memories
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(namespace.to_string())
+            .or_default()
+            .push((memory, embedding));
+        Ok(())
+    }
+
+    async fn recall(
+        &self,
+        namespace: &str,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, Memory)>, VectorStoreError> {
+        let query = self.model.embed_text(query).await?;
+        let memories = self.memories.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut results = memories
+            .get(namespace)
+            .into_iter()
+            .flatten()
+            .map(|(memory, embedding)| (embedding.cosine_similarity(&query, false), memory.clone()))
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| b.0.total_cmp(&a.0));
+        results.truncate(n);
+        Ok(results)
+    }
+}
+
+/// A [MemoryStore] backed by a vector store, whose namespaces are the namespaces of the vector
+/// store (e.g.: Elasticsearch indexes). The memories are embedded with the given model, which
+/// should be the model of the vector store.
+pub struct VectorMemoryStore<M, S> {
+    model: M,
+    store: S,
+}
+
+impl<M, S> VectorMemoryStore<M, S>
+where
+    M: EmbeddingModel,
+    S: NamespacedVectorStoreIndex,
+    S::Namespaced: VectorStoreImport<Memory> + Sync,
+{
+    pub fn new(model: M, store: S) -> Self {
+        Self { model, store }
+    }
+}
+
+impl<M, S> MemoryStore for VectorMemoryStore<M, S>
+where
+    M: EmbeddingModel,
+    S: NamespacedVectorStoreIndex,
+    S::Namespaced: VectorStoreImport<Memory> + Sync,
+{
+    async fn save(&self, namespace: &str, memory: Memory) -> Result<(), VectorStoreError> {
+        let embedding = self.model.embed_text(&memory.text).await?;
+        self.store
+            .namespace(namespace)
+            .import(vec![(
+                test_mode::generate_id("memory"),
+                memory,
+                OneOrMany::one(embedding),
+            )])
+            .await
+    }
+
+    async fn recall(
+        &self,
+        namespace: &str,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, Memory)>, VectorStoreError> {
+        Ok(self
+            .store
+            .namespace(namespace)
+            .top_n::<Memory>(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, _, memory)| (score, memory))
+            .collect())
+    }
+}
+
+/// Wrap a future to await it in a tool call, whose future must be `Sync`: the wrapped future
+/// is only polled through a mutex.
+fn sync_future<F: Future + Send>(future: F) -> impl Future<Output = F::Output> + Send + Sync {
+    let mut future = Mutex::new(Box::pin(future));
+    futures::future::poll_fn(move |cx| {
+        future
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+            .poll(cx)
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("MemoryError: {0}")]
+pub struct MemoryError(#[from] VectorStoreError);
+
+/// Create the [SaveMemoryTool] and [RecallMemoryTool] of a namespace (e.g.: a user id).
+pub fn memory_tools<S: MemoryStore>(
+    store: Arc<S>,
+    namespace: &str,
+) -> (SaveMemoryTool<S>, RecallMemoryTool<S>) {
+    (
+        SaveMemoryTool::new(store.clone(), namespace),
+        RecallMemoryTool::new(store, namespace),
+    )
+}
+
+#[derive(Deserialize)]
+pub struct SaveMemoryArgs {
+    pub memory: String,
+}
+
+/// A tool saving memories in a namespace of a [MemoryStore].
+pub struct SaveMemoryTool<S> {
+    store: Arc<S>,
+    namespace: String,
+}
+
+impl<S: MemoryStore> SaveMemoryTool<S> {
+    pub fn new(store: Arc<S>, namespace: &str) -> Self {
+        Self {
+            store,
+            namespace: namespace.to_string(),
+        }
+    }
+}
+
+impl<S: MemoryStore> Tool for SaveMemoryTool<S> {
+    const NAME: &'static str = "save_memory";
+
+    type Error = MemoryError;
+    type Args = SaveMemoryArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Save a fact worth remembering in future conversations with the user \
+                (e.g.: their preferences or decisions)."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "memory": {
+                        "type": "string",
+                        "description": "The fact to remember, as a self-contained sentence"
+                    }
+                },
+                "required": ["memory"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        sync_future(self.store.save(&self.namespace, Memory::new(&args.memory))).await?;
+        Ok("Memory saved.".to_string())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecallMemoryArgs {
+    pub query: String,
+}
+
+/// A tool recalling the memories of a namespace of a [MemoryStore] most similar to a query.
+pub struct RecallMemoryTool<S> {
+    store: Arc<S>,
+    namespace: String,
+    sample: usize,
+}
+
+impl<S: MemoryStore> RecallMemoryTool<S> {
+    pub fn new(store: Arc<S>, namespace: &str) -> Self {
+        Self {
+            store,
+            namespace: namespace.to_string(),
+            sample: 5,
+        }
+    }
+
+    /// Set the number of memories recalled per query (defaults to 5).
+    pub fn sample(mut self, sample: usize) -> Self {
+        self.sample = sample;
+        self
+    }
+}
+
+impl<S: MemoryStore> Tool for RecallMemoryTool<S> {
+    const NAME: &'static str = "recall_memory";
+
+    type Error = MemoryError;
+    type Args = RecallMemoryArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Recall the facts saved in previous conversations with the user that \
+                are related to a query."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What to recall"
+                    }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let memories =
+            sync_future(self.store.recall(&self.namespace, &args.query, self.sample)).await?;
+
+        Ok(if memories.is_empty() {
+            "No memories found.".to_string()
+        } else {
+            memories
+                .iter()
+                .map(|(_, memory)| format!("- {} (saved {})", memory.text, memory.created_at))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockEmbeddingModel;
+
+    #[tokio::test]
+    async fn test_memory_tools() {
+        let store = Arc::new(InMemoryMemoryStore::new(MockEmbeddingModel::counting(&[
+            "a", "b",
+        ])));
+        let (save, recall) = memory_tools(store.clone(), "alice");
+        let (_, other_recall) = memory_tools(store.clone(), "bob");
+
+        save.call(SaveMemoryArgs {
+            memory: "aaa".to_string(),
+        })
+        .await
+        .unwrap();
+        save.call(SaveMemoryArgs {
+            memory: "bbb".to_string(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(store.memories("alice").len(), 2);
+
+        let recalled = recall
+            .sample(1)
+            .call(RecallMemoryArgs {
+                query: "b".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(recalled.starts_with("- bbb (saved "));
+
+        let recalled = other_recall
+            .call(RecallMemoryArgs {
+                query: "b".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(recalled, "No memories found.");
+    }
+}
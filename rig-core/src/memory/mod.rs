@@ -0,0 +1,33 @@
+//! This module provides memory abstractions that let agents remember information across
+//! prompts and conversations.
+//!
+//! The [GraphMemory](graph::GraphMemory) struct is an experimental entity memory that uses an
+//! [Extractor](crate::extractor::Extractor) to pull entities and relations out of conversations,
+//! stores them in a [GraphStore](graph::GraphStore) and retrieves the relevant facts as context
+//! for future prompts.
+//!
+//! The [HistoryPolicy](history::HistoryPolicy) trait and its implementations trim the chat
+//! history of an agent before it is sent with a prompt (see
+//! [AgentBuilder::history_policy](crate::agent::AgentBuilder::history_policy)).
+//!
+//! The [ScratchpadTool](scratchpad::ScratchpadTool) gives agents a working memory for the
+//! duration of a run, and the [long_term] tools let them save and recall memories across
+//! sessions.
+
+pub mod graph;
+pub mod history;
+pub mod long_term;
+pub mod scratchpad;
+
+pub use graph::{
+    mentions, Entity, GraphMemory, GraphMemoryError, GraphStore, InMemoryGraphStore,
+    KnowledgeGraph, Relation,
+};
+pub use history::{
+    HistoryPolicy, HistoryPolicyDyn, ImportanceWeighted, KeepLast, SummarizeThenTrim, TokenBudget,
+};
+pub use long_term::{
+    memory_tools, InMemoryMemoryStore, Memory, MemoryStore, RecallMemoryTool, SaveMemoryTool,
+    VectorMemoryStore,
+};
+pub use scratchpad::ScratchpadTool;
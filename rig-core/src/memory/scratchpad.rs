@@ -0,0 +1,191 @@
+//! This module provides [ScratchpadTool], a working memory for multi-turn agents: a tool to
+//! read and write named notes (e.g.: intermediate results, plans or partial answers) without
+//! adding them to the chat history.
+//!
+//! The notes are scoped to a run: clones of a scratchpad share the same notes, which the
+//! application reads with [ScratchpadTool::notes] and clears with [ScratchpadTool::clear]
+//! between runs.
+//!
+//! # Example
+//! ```rust
+//! use rig::{memory::scratchpad::ScratchpadTool, providers::openai};
+//!
+//! # fn run() {
+//! let scratchpad = ScratchpadTool::new();
+//!
+//! let openai = openai::Client::from_env();
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .preamble("Write down your intermediate results in the scratchpad.")
+//!     .tool(scratchpad.clone())
+//!     .build();
+//!
+//! // ... run the agent, then inspect its notes
+//! let notes = scratchpad.notes();
+//! # }
+//! ```
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{completion::ToolDefinition, tool::Tool};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScratchpadError {
+    #[error("NoteNotFoundError: {0}")]
+    NoteNotFound(String),
+}
+
+/// An action of the model on the scratchpad.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ScratchpadAction {
+    /// List the names of the notes
+    List,
+    Read {
+        name: String,
+    },
+    /// Create or replace a note
+    Write {
+        name: String,
+        content: String,
+    },
+    /// Append to a note, creating it if needed
+    Append {
+        name: String,
+        content: String,
+    },
+    Delete {
+        name: String,
+    },
+}
+
+/// A tool to read and write named notes, see the [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct ScratchpadTool {
+    notes: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl ScratchpadTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the notes of the scratchpad, by name.
+    pub fn notes(&self) -> BTreeMap<String, String> {
+        self.notes.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Delete all the notes (e.g.: at the start of a new run).
+    pub fn clear(&self) {
+        self.notes.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl Tool for ScratchpadTool {
+    const NAME: &'static str = "scratchpad";
+
+    type Error = ScratchpadError;
+    type Args = ScratchpadAction;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "A scratchpad to store named notes (e.g.: intermediate results) while \
+                working on a task. Notes are not visible to the user."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "read", "write", "append", "delete"],
+                        "description": "`list` the names of the notes, `read`, `write` (create or replace), `append` to or `delete` a note"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the note (not needed to list the notes)"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The content to write or append"
+                    }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut notes = self.notes.lock().unwrap_or_else(|e| e.into_inner());
+
+        match args {
+            ScratchpadAction::List => Ok(if notes.is_empty() {
+                "The scratchpad is empty.".to_string()
+            } else {
+                notes.keys().cloned().collect::<Vec<_>>().join("\n")
+            }),
+            ScratchpadAction::Read { name } => notes
+                .get(&name)
+                .cloned()
+                .ok_or(ScratchpadError::NoteNotFound(name)),
+            ScratchpadAction::Write { name, content } => {
+                notes.insert(name.clone(), content);
+                Ok(format!("Note {name} written."))
+            }
+            ScratchpadAction::Append { name, content } => {
+                let note = notes.entry(name.clone()).or_default();
+                if !note.is_empty() {
+                    note.push('\n');
+                }
+                note.push_str(&content);
+                Ok(format!("Note {name} updated."))
+            }
+            ScratchpadAction::Delete { name } => notes
+                .remove(&name)
+                .map(|_| format!("Note {name} deleted."))
+                .ok_or(ScratchpadError::NoteNotFound(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::ToolSet;
+
+    #[tokio::test]
+    async fn test_scratchpad() {
+        let scratchpad = ScratchpadTool::new();
+        let toolset = ToolSet::from_tools(vec![scratchpad.clone()]);
+        let call = |args: serde_json::Value| toolset.call("scratchpad", args.to_string());
+
+        call(json!({"action": "write", "name": "plan", "content": "1. Search"}))
+            .await
+            .unwrap();
+        call(json!({"action": "append", "name": "plan", "content": "2. Answer"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            call(json!({"action": "read", "name": "plan"}))
+                .await
+                .unwrap(),
+            "\"1. Search\\n2. Answer\""
+        );
+        assert_eq!(call(json!({"action": "list"})).await.unwrap(), "\"plan\"");
+        assert!(call(json!({"action": "read", "name": "results"}))
+            .await
+            .is_err());
+
+        assert_eq!(scratchpad.notes()["plan"], "1. Search\n2. Answer");
+        scratchpad.clear();
+        assert!(call(json!({"action": "delete", "name": "plan"}))
+            .await
+            .is_err());
+    }
+}
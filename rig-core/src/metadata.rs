@@ -0,0 +1,464 @@
+//! This module defines [DocumentMetadata], the typed metadata of the documents flowing through
+//! the loaders, chunkers, embeddings and vector stores, and [Document], a text with its
+//! metadata. [Document] is also the type of the documents sent to the models (see
+//! [CompletionRequest::documents](crate::completion::CompletionRequest::documents)).
+//!
+//! Using the same metadata model everywhere enables:
+//! - generic filtering of search results with [MetadataFilter], either on the results of any
+//!   index (see [FilteredIndex](crate::vector_store::filter::FilteredIndex)) or translated into
+//!   the native filters of the vector store crates (e.g.: `rig-qdrant`),
+//! - citation rendering: documents are sent to the model with their title and source, which it
+//!   can cite,
+//! - parent-document retrieval: chunks link to their parent with
+//!   [parent_id](DocumentMetadata::parent_id) (see
+//!   [parent_document](crate::vector_store::parent_document)).
+//!
+//! Documents are stored in the vector stores as `{"metadata": {...}, "text": "..."}`, so the
+//! native filters of the stores apply to the `metadata.` fields.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     embeddings::EmbeddingsBuilder,
+//!     loaders::FileLoader,
+//!     providers::openai,
+//!     vector_store::in_memory_store::InMemoryVectorStore,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let model = openai::Client::from_env().embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+//!
+//! let chunks = FileLoader::with_glob("docs/*.md")?
+//!     .read_with_metadata()
+//!     .ignore_errors()
+//!     .into_iter()
+//!     .map(|document| document.tag("docs"))
+//!     .flat_map(|document| document.chunks(1_000, 100))
+//!     .collect::<Vec<_>>();
+//!
+//! let embeddings = EmbeddingsBuilder::new(model.clone())
+//!     .documents(chunks)?
+//!     .build()
+//!     .await?;
+//! let index = InMemoryVectorStore::from_documents_with_id_f(embeddings, |doc| {
+//!     doc.metadata.id.clone()
+//! })
+//! .index(model);
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    embeddings::{Embed, EmbedError, TextEmbedder},
+    pipeline::presets::{chunk_text, chunk_text_tokens},
+    tokens::TokenCounter,
+};
+
+/// The metadata of a document (or of a chunk of a document).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    /// Unique id of the document
+    pub id: String,
+    /// Where the document comes from, as a URI (e.g.: `file:///docs/intro.md`, `https://...`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Creation date-time, in RFC 3339 format (e.g.: `2024-01-31T12:00:00Z`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    /// Last update date-time, in RFC 3339 format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Id of the document this document is a chunk of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Application specific metadata
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub custom: Map<String, Value>,
+}
+
+impl DocumentMetadata {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Set the creation date-time, in RFC 3339 format.
+    pub fn created_at(mut self, created_at: &str) -> Self {
+        self.created_at = Some(created_at.to_string());
+        self
+    }
+
+    /// Set the last update date-time, in RFC 3339 format.
+    pub fn updated_at(mut self, updated_at: &str) -> Self {
+        self.updated_at = Some(updated_at.to_string());
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn custom(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.custom.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Render the metadata as a citation, e.g.: `Introduction (file:///docs/intro.md)`.
+    pub fn citation(&self) -> String {
+        match (&self.title, &self.source) {
+            (Some(title), Some(source)) => format!("{title} ({source})"),
+            (Some(title), None) => title.clone(),
+            (None, Some(source)) => source.clone(),
+            (None, None) => self.id.clone(),
+        }
+    }
+
+    /// The metadata of the `index`-th chunk of the document: the metadata of the document, with
+    /// the id `<document id>#<index>` and a link to the document.
+    pub fn chunk(&self, index: usize) -> Self {
+        Self {
+            id: format!("{}#{index}", self.id),
+            parent_id: Some(self.id.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// The metadata as the string properties rendered with a [Document] sent to a model (the id
+    /// excepted).
+    pub fn to_props(&self) -> HashMap<String, String> {
+        let mut props = HashMap::new();
+        let mut insert = |key: &str, value: &Option<String>| {
+            if let Some(value) = value {
+                props.insert(key.to_string(), value.clone());
+            }
+        };
+        insert("title", &self.title);
+        insert("source", &self.source);
+        insert("created_at", &self.created_at);
+        insert("updated_at", &self.updated_at);
+        if !self.tags.is_empty() {
+            props.insert("tags".to_string(), self.tags.join(", "));
+        }
+        for (key, value) in &self.custom {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            props.insert(key.clone(), value);
+        }
+        props
+    }
+}
+
+/// A text document with its metadata, as produced by the loaders and chunkers, stored in the
+/// vector stores and sent to the models.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Document {
+    pub metadata: DocumentMetadata,
+    pub text: String,
+}
+
+impl Document {
+    pub fn new(metadata: DocumentMetadata, text: &str) -> Self {
+        Self {
+            metadata,
+            text: text.to_string(),
+        }
+    }
+
+    /// Add a tag to the metadata of the document.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.metadata.tags.push(tag.to_string());
+        self
+    }
+
+    /// Split the document into chunks of at most `size` characters, with consecutive chunks
+    /// sharing (about) `overlap` characters. The chunks inherit the metadata of the document,
+    /// have the ids `<document id>#<chunk index>` and link to the document with their
+    /// [parent_id](DocumentMetadata::parent_id).
+    pub fn chunks(&self, size: usize, overlap: usize) -> Vec<Document> {
+        self.chunked(chunk_text(&self.text, size, overlap))
+    }
+
+    /// Split the document into chunks of at most `max_tokens` tokens, as counted by `counter`,
+    /// with consecutive chunks sharing (about) `overlap` tokens. See [Document::chunks].
+    pub fn chunks_tokens<C: TokenCounter + ?Sized>(
+        &self,
+        counter: &C,
+        max_tokens: usize,
+        overlap: usize,
+    ) -> Vec<Document> {
+        self.chunked(chunk_text_tokens(&self.text, counter, max_tokens, overlap))
+    }
+
+    fn chunked(&self, chunks: Vec<String>) -> Vec<Document> {
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, text)| Document {
+                metadata: self.metadata.chunk(index),
+                text,
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let props = self.metadata.to_props();
+        write!(
+            f,
+            concat!("<file id: {}>\n", "{}\n", "</file>\n"),
+            self.metadata.id,
+            if props.is_empty() {
+                self.text.clone()
+            } else {
+                let mut sorted_props = props.iter().collect::<Vec<_>>();
+                sorted_props.sort_by(|a, b| a.0.cmp(b.0));
+                let metadata = sorted_props
+                    .iter()
+                    .map(|(k, v)| format!("{}: {:?}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("<metadata {} />\n{}", metadata, self.text)
+            }
+        )
+    }
+}
+
+impl Embed for Document {
+    fn embed(&self, embedder: &mut TextEmbedder) -> Result<(), EmbedError> {
+        embedder.embed(self.text.clone());
+        Ok(())
+    }
+}
+
+/// A filter on the metadata of documents, see
+/// [FilteredIndex](crate::vector_store::filter::FilteredIndex).
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataFilter {
+    /// The document has the tag
+    Tag(String),
+    /// The source of the document starts with the prefix (e.g.: `https://docs.rs/`)
+    SourcePrefix(String),
+    /// The document was created at or after the given time
+    CreatedAfter(SystemTime),
+    /// The document was created before the given time
+    CreatedBefore(SystemTime),
+    /// The custom metadata of the document has the given value for the key
+    Custom(String, Value),
+    And(Vec<MetadataFilter>),
+    Or(Vec<MetadataFilter>),
+    Not(Box<MetadataFilter>),
+}
+
+impl MetadataFilter {
+    /// Whether the metadata matches the filter. Documents without a (valid) creation
+    /// date-time never match the date filters.
+    pub fn matches(&self, metadata: &DocumentMetadata) -> bool {
+        let created_at = || metadata.created_at.as_deref().and_then(parse_rfc3339);
+        let secs = |time: &SystemTime| match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs_f64(),
+            Err(e) => -e.duration().as_secs_f64(),
+        };
+
+        match self {
+            Self::Tag(tag) => metadata.tags.contains(tag),
+            Self::SourcePrefix(prefix) => metadata
+                .source
+                .as_ref()
+                .is_some_and(|source| source.starts_with(prefix)),
+            Self::CreatedAfter(time) => created_at().is_some_and(|created| created >= secs(time)),
+            Self::CreatedBefore(time) => created_at().is_some_and(|created| created < secs(time)),
+            Self::Custom(key, value) => metadata.custom.get(key) == Some(value),
+            Self::And(filters) => filters.iter().all(|filter| filter.matches(metadata)),
+            Self::Or(filters) => filters.iter().any(|filter| filter.matches(metadata)),
+            Self::Not(filter) => !filter.matches(metadata),
+        }
+    }
+}
+
+/// Format a system time as an RFC 3339 UTC date-time, as stored in the `created_at` and
+/// `updated_at` metadata.
+pub fn rfc3339(time: SystemTime) -> String {
+    crate::telemetry::rfc3339(
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_millis() as u64,
+    )
+}
+
+/// Parse an RFC 3339 date-time (e.g.: `2024-01-31T12:00:00.5+02:00`) into seconds since the
+/// UNIX epoch.
+pub(crate) fn parse_rfc3339(string: &str) -> Option<f64> {
+    let number = |s: &str| -> Option<i64> {
+        s.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| s.parse().ok())
+            .flatten()
+    };
+
+    let (date, time) = string.split_once(['T', 't', ' '])?;
+    let mut date = date.splitn(3, '-');
+    let (year, month, day) = (
+        number(date.next()?)?,
+        number(date.next()?)?,
+        number(date.next()?)?,
+    );
+
+    let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let i = time.rfind(['+', '-'])?;
+        let (hours, minutes) = time[i + 1..].split_once(':')?;
+        let offset = number(hours)? * 3600 + number(minutes)? * 60;
+        (
+            &time[..i],
+            if &time[i..i + 1] == "-" {
+                -offset
+            } else {
+                offset
+            },
+        )
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, format!("0.{fraction}").parse::<f64>().ok()?),
+        None => (time, 0.0),
+    };
+    let mut time = time.splitn(3, ':');
+    let (hours, minutes, seconds) = (
+        number(time.next()?)?,
+        number(time.next()?)?,
+        number(time.next()?)?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since 1970-01-01 from the civil date (H. Hinnant's algorithm)
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some((days * 86_400 + hours * 3600 + minutes * 60 + seconds - offset) as f64 + fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(parse_rfc3339("2024-01-31T12:00:00Z"), Some(1_706_702_400.0));
+        assert_eq!(
+            parse_rfc3339("2024-01-31T14:00:00.25+02:00"),
+            Some(1_706_702_400.25)
+        );
+        assert_eq!(
+            parse_rfc3339("2000-03-01 00:00:00-01:00"),
+            Some(951_872_400.0)
+        );
+        assert_eq!(parse_rfc3339("2024-13-01T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("yesterday"), None);
+        assert_eq!(
+            parse_rfc3339(&rfc3339(UNIX_EPOCH + Duration::from_secs(1_706_702_400))),
+            Some(1_706_702_400.0)
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        let document = Document::new(
+            DocumentMetadata::new("intro")
+                .title("Introduction")
+                .source("file:///docs/intro.md")
+                .created_at("2024-01-31T12:00:00Z")
+                .custom("lang", "en"),
+            "Hello world",
+        )
+        .tag("docs");
+
+        // Empty fields are skipped
+        assert_eq!(
+            serde_json::to_value(&document).unwrap(),
+            json!({
+                "metadata": {
+                    "id": "intro",
+                    "source": "file:///docs/intro.md",
+                    "title": "Introduction",
+                    "created_at": "2024-01-31T12:00:00Z",
+                    "tags": ["docs"],
+                    "custom": { "lang": "en" }
+                },
+                "text": "Hello world"
+            })
+        );
+        assert_eq!(
+            document.metadata.citation(),
+            "Introduction (file:///docs/intro.md)"
+        );
+
+        let chunks = document.chunks(6, 0);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].metadata.id, "intro#1");
+        assert_eq!(chunks[1].metadata.parent_id.as_deref(), Some("intro"));
+        assert_eq!(chunks[1].metadata.title.as_deref(), Some("Introduction"));
+
+        let rendered = chunks[1].to_string();
+        assert!(rendered.starts_with("<file id: intro#1>\n<metadata created_at: "));
+        assert!(rendered.contains("title: \"Introduction\""));
+        assert!(rendered.ends_with("/>\nworld\n</file>\n"));
+    }
+
+    #[test]
+    fn test_metadata_filter() {
+        let metadata = DocumentMetadata::new("a")
+            .source("https://docs.rs/rig-core")
+            .created_at("2024-01-31T12:00:00Z")
+            .tag("docs")
+            .custom("lang", "en");
+        let time = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+
+        assert!(MetadataFilter::Tag("docs".into()).matches(&metadata));
+        assert!(MetadataFilter::SourcePrefix("https://docs.rs/".into()).matches(&metadata));
+        assert!(MetadataFilter::CreatedAfter(time(1_706_702_400)).matches(&metadata));
+        assert!(!MetadataFilter::CreatedBefore(time(1_706_702_400)).matches(&metadata));
+        assert!(MetadataFilter::And(vec![
+            MetadataFilter::Custom("lang".into(), json!("en")),
+            MetadataFilter::Not(Box::new(MetadataFilter::Tag("blog".into()))),
+        ])
+        .matches(&metadata));
+        assert!(!MetadataFilter::Or(vec![]).matches(&metadata));
+        assert!(!MetadataFilter::CreatedAfter(time(0)).matches(&DocumentMetadata::new("b")));
+    }
+}
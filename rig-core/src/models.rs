@@ -0,0 +1,372 @@
+//! This module provides a catalog of models, mapping model identifiers to their metadata: context
+//! window, output limit, input modalities, tool support and prices ([ModelInfo]).
+//!
+//! The catalog is used:
+//! - by [Traced](crate::telemetry::Traced) models to compute the cost of the generations, when no
+//!   pricing is set,
+//! - by [TokenBudget::for_model](crate::memory::history::TokenBudget::for_model) to fit the chat
+//!   history in the context window of a model,
+//! - to check requests against the capabilities of a model before sending them
+//!   ([ModelInfo::check]).
+//!
+//! Dated or aliased versions of a model (e.g.: `gpt-4o-2024-08-06`, `claude-3-5-sonnet-latest`)
+//! and provider prefixes (e.g.: `openai/gpt-4o`) resolve to the model. The built-in entries can
+//! be overridden, and new models added, with [register].
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     models::{self, Modality, ModelInfo},
+//!     providers::openai,
+//! };
+//!
+//! let gpt_4o = models::get(openai::GPT_4O).unwrap();
+//! assert_eq!(gpt_4o.context_window, 128_000);
+//! assert!(gpt_4o.supports(Modality::Image));
+//!
+//! // A fine-tuned model, priced like its base model
+//! models::register(ModelInfo {
+//!     id: "ft:gpt-4o-mini:acme".to_string(),
+//!     ..models::get(openai::GPT_4O_MINI).unwrap()
+//! });
+//! ```
+use std::sync::{OnceLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::{CompletionRequest, ModelPricing},
+    message::{DocumentMediaType, Message, UserContent},
+};
+
+/// A kind of input a model accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modality {
+    Text,
+    Image,
+    Audio,
+    Video,
+    Pdf,
+}
+
+/// The metadata of a model.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub provider: String,
+    /// Maximum number of tokens of the input and output
+    pub context_window: usize,
+    /// Maximum number of output tokens, if the model generates text
+    pub max_output_tokens: Option<usize>,
+    /// Kinds of input the model accepts
+    pub modalities: Vec<Modality>,
+    /// Whether the model supports tool calls
+    pub tools: bool,
+    /// Price of the input tokens, in USD per million tokens
+    pub input_per_million: Option<f64>,
+    /// Price of the output tokens, in USD per million tokens
+    pub output_per_million: Option<f64>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum CapabilityError {
+    #[error("{model} does not support {modality:?} inputs")]
+    UnsupportedModality { model: String, modality: Modality },
+
+    #[error("{model} does not support tools")]
+    ToolsNotSupported { model: String },
+
+    #[error("The request has about {tokens} tokens, more than the context window of {model} ({context_window})")]
+    ContextWindowExceeded {
+        model: String,
+        tokens: usize,
+        context_window: usize,
+    },
+
+    #[error("The request asks for {max_tokens} tokens, more than the output limit of {model} ({max_output_tokens})")]
+    OutputLimitExceeded {
+        model: String,
+        max_tokens: u64,
+        max_output_tokens: usize,
+    },
+}
+
+impl ModelInfo {
+    /// Whether the model accepts the kind of input.
+    pub fn supports(&self, modality: Modality) -> bool {
+        self.modalities.contains(&modality)
+    }
+
+    /// The pricing of the model, if known, counting tokens with the best counter available for
+    /// it.
+    pub fn pricing(&self) -> Option<ModelPricing> {
+        Some(ModelPricing::for_model(
+            &self.id,
+            self.input_per_million?,
+            self.output_per_million?,
+        ))
+    }
+
+    /// Check that the model supports the request: the kinds of its inputs, its tools, its
+    /// estimated number of tokens and its maximum number of output tokens.
+    pub fn check(&self, request: &CompletionRequest) -> Result<(), CapabilityError> {
+        let model = || self.id.clone();
+
+        for message in request.chat_history.iter().chain([&request.prompt]) {
+            let Message::User { content, .. } = message else {
+                continue;
+            };
+            for content in content.iter() {
+                let modality = match content {
+                    UserContent::Image(_) => Modality::Image,
+                    UserContent::Audio(_) => Modality::Audio,
+                    UserContent::Document(document)
+                        if document.media_type == Some(DocumentMediaType::PDF) =>
+                    {
+                        Modality::Pdf
+                    }
+                    _ => continue,
+                };
+                if !self.supports(modality) {
+                    return Err(CapabilityError::UnsupportedModality {
+                        model: model(),
+                        modality,
+                    });
+                }
+            }
+        }
+
+        if !request.tools.is_empty() && !self.tools {
+            return Err(CapabilityError::ToolsNotSupported { model: model() });
+        }
+
+        if let (Some(max_tokens), Some(max_output_tokens)) =
+            (request.max_tokens, self.max_output_tokens)
+        {
+            if max_tokens > max_output_tokens as u64 {
+                return Err(CapabilityError::OutputLimitExceeded {
+                    model: model(),
+                    max_tokens,
+                    max_output_tokens,
+                });
+            }
+        }
+
+        let tokens = request
+            .estimate_cost(&ModelPricing::for_model(&self.id, 0.0, 0.0))
+            .input_tokens;
+        let output_tokens = request.max_tokens.unwrap_or_default() as usize;
+        if tokens + output_tokens > self.context_window {
+            return Err(CapabilityError::ContextWindowExceeded {
+                model: model(),
+                tokens: tokens + output_tokens,
+                context_window: self.context_window,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn catalog() -> &'static RwLock<Vec<ModelInfo>> {
+    static CATALOG: OnceLock<RwLock<Vec<ModelInfo>>> = OnceLock::new();
+    CATALOG.get_or_init(|| RwLock::new(built_in()))
+}
+
+/// Add a model to the catalog, or replace the model with the same id.
+pub fn register(info: ModelInfo) {
+    let mut catalog = catalog().write().unwrap_or_else(|e| e.into_inner());
+    match catalog.iter_mut().find(|model| model.id == info.id) {
+        Some(model) => *model = info,
+        None => catalog.push(info),
+    }
+}
+
+/// Get the metadata of a model. Provider prefixes are ignored, and versions of a model (its id
+/// followed by `-` and a suffix) resolve to the model.
+pub fn get(model: &str) -> Option<ModelInfo> {
+    let catalog = catalog().read().unwrap_or_else(|e| e.into_inner());
+    let find = |model: &str| {
+        catalog
+            .iter()
+            .filter(|info| {
+                model == info.id
+                    || model
+                        .strip_prefix(info.id.as_str())
+                        .is_some_and(|suffix| suffix.starts_with('-'))
+            })
+            .max_by_key(|info| info.id.len())
+            .cloned()
+    };
+
+    find(model).or_else(|| find(model.rsplit('/').next().unwrap_or(model)))
+}
+
+/// All the models of the catalog.
+pub fn all() -> Vec<ModelInfo> {
+    catalog().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The built-in catalog, with the public prices at the time of writing.
+#[rustfmt::skip]
+fn built_in() -> Vec<ModelInfo> {
+    use Modality::*;
+
+    let model = |provider: &str,
+                 id: &str,
+                 context_window: usize,
+                 max_output_tokens: usize,
+                 modalities: &[Modality],
+                 (input, output): (f64, f64)| ModelInfo {
+        id: id.to_string(),
+        provider: provider.to_string(),
+        context_window,
+        max_output_tokens: Some(max_output_tokens),
+        modalities: modalities.to_vec(),
+        tools: true,
+        input_per_million: Some(input),
+        output_per_million: Some(output),
+    };
+    let embedding = |provider: &str, id: &str, context_window: usize, price: f64| ModelInfo {
+        id: id.to_string(),
+        provider: provider.to_string(),
+        context_window,
+        max_output_tokens: None,
+        modalities: vec![Text],
+        tools: false,
+        input_per_million: Some(price),
+        output_per_million: Some(0.0),
+    };
+    let without_tools = |info: ModelInfo| ModelInfo {
+        tools: false,
+        ..info
+    };
+
+    vec![
+        // OpenAI
+        model("openai", "gpt-4.1", 1_047_576, 32_768, &[Text, Image], (2.0, 8.0)),
+        model("openai", "gpt-4.1-mini", 1_047_576, 32_768, &[Text, Image], (0.4, 1.6)),
+        model("openai", "gpt-4.1-nano", 1_047_576, 32_768, &[Text, Image], (0.1, 0.4)),
+        model("openai", "gpt-4o", 128_000, 16_384, &[Text, Image, Pdf], (2.5, 10.0)),
+        model("openai", "gpt-4o-mini", 128_000, 16_384, &[Text, Image, Pdf], (0.15, 0.6)),
+        model("openai", "gpt-4-turbo", 128_000, 4_096, &[Text, Image], (10.0, 30.0)),
+        model("openai", "gpt-4", 8_192, 8_192, &[Text], (30.0, 60.0)),
+        model("openai", "gpt-4-32k", 32_768, 32_768, &[Text], (60.0, 120.0)),
+        model("openai", "gpt-3.5-turbo", 16_385, 4_096, &[Text], (0.5, 1.5)),
+        model("openai", "o1", 200_000, 100_000, &[Text, Image], (15.0, 60.0)),
+        without_tools(model("openai", "o1-mini", 128_000, 65_536, &[Text], (1.1, 4.4))),
+        without_tools(model("openai", "o1-preview", 128_000, 32_768, &[Text], (15.0, 60.0))),
+        model("openai", "o3-mini", 200_000, 100_000, &[Text], (1.1, 4.4)),
+        embedding("openai", "text-embedding-3-small", 8_191, 0.02),
+        embedding("openai", "text-embedding-3-large", 8_191, 0.13),
+        embedding("openai", "text-embedding-ada-002", 8_191, 0.1),
+        // Anthropic
+        model("anthropic", "claude-3-7-sonnet", 200_000, 64_000, &[Text, Image, Pdf], (3.0, 15.0)),
+        model("anthropic", "claude-3-5-sonnet", 200_000, 8_192, &[Text, Image, Pdf], (3.0, 15.0)),
+        model("anthropic", "claude-3-5-haiku", 200_000, 8_192, &[Text, Image], (0.8, 4.0)),
+        model("anthropic", "claude-3-opus", 200_000, 4_096, &[Text, Image], (15.0, 75.0)),
+        model("anthropic", "claude-3-sonnet", 200_000, 4_096, &[Text, Image], (3.0, 15.0)),
+        model("anthropic", "claude-3-haiku", 200_000, 4_096, &[Text, Image], (0.25, 1.25)),
+        // Gemini
+        model("gemini", "gemini-2.0-flash", 1_048_576, 8_192, &[Text, Image, Audio, Video, Pdf], (0.1, 0.4)),
+        model("gemini", "gemini-1.5-flash", 1_048_576, 8_192, &[Text, Image, Audio, Video, Pdf], (0.075, 0.3)),
+        model("gemini", "gemini-1.5-pro", 2_097_152, 8_192, &[Text, Image, Audio, Video, Pdf], (1.25, 5.0)),
+        model("gemini", "gemini-1.0-pro", 32_760, 8_192, &[Text], (0.5, 1.5)),
+        embedding("gemini", "text-embedding-004", 2_048, 0.0),
+        // Cohere
+        model("cohere", "command-r-plus", 128_000, 4_000, &[Text], (2.5, 10.0)),
+        model("cohere", "command-r", 128_000, 4_000, &[Text], (0.15, 0.6)),
+        embedding("cohere", "embed-english-v3.0", 512, 0.1),
+        embedding("cohere", "embed-multilingual-v3.0", 512, 0.1),
+        // DeepSeek
+        model("deepseek", "deepseek-chat", 64_000, 8_192, &[Text], (0.27, 1.1)),
+        without_tools(model("deepseek", "deepseek-reasoner", 64_000, 8_192, &[Text], (0.55, 2.19))),
+        // xAI
+        model("xai", "grok-beta", 131_072, 131_072, &[Text], (5.0, 15.0)),
+        model("xai", "grok-2-vision", 32_768, 32_768, &[Text, Image], (2.0, 10.0)),
+        // Perplexity
+        without_tools(model("perplexity", "sonar", 127_072, 8_000, &[Text], (1.0, 1.0))),
+        without_tools(model("perplexity", "sonar-pro", 200_000, 8_000, &[Text], (3.0, 15.0))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::completion::ToolDefinition;
+
+    fn request(prompt: Message) -> CompletionRequest {
+        CompletionRequest {
+            prompt,
+            preamble: None,
+            chat_history: vec![],
+            documents: vec![],
+            tools: vec![],
+            temperature: None,
+            max_tokens: None,
+            n: None,
+            additional_params: None,
+            request_options: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_catalog() {
+        let gpt_4o_mini = get("openai/gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(gpt_4o_mini.id, "gpt-4o-mini");
+        assert_eq!(get("gpt-4o-2024-08-06").unwrap().id, "gpt-4o");
+        assert_eq!(
+            get("claude-3-5-sonnet-latest").unwrap().provider,
+            "anthropic"
+        );
+        assert_eq!(get("gpt-4ox"), None);
+        assert!(get("deepseek-chat").unwrap().pricing().is_some());
+
+        register(ModelInfo {
+            id: "acme-small".to_string(),
+            provider: "acme".to_string(),
+            context_window: 100,
+            max_output_tokens: Some(10),
+            modalities: vec![Modality::Text],
+            tools: false,
+            input_per_million: None,
+            output_per_million: None,
+        });
+        let acme = get("acme-small").unwrap();
+        assert!(acme.pricing().is_none());
+
+        // Capability checks
+        assert_eq!(acme.check(&request(Message::user("Hi"))), Ok(()));
+        assert!(matches!(
+            acme.check(&request(Message::User {
+                content: crate::OneOrMany::one(UserContent::image("data", None, None, None)),
+                name: None
+            })),
+            Err(CapabilityError::UnsupportedModality {
+                modality: Modality::Image,
+                ..
+            })
+        ));
+        let mut with_tools = request(Message::user("Hi"));
+        with_tools.tools.push(ToolDefinition {
+            name: "add".to_string(),
+            description: "Add numbers".to_string(),
+            parameters: serde_json::json!({}),
+        });
+        assert!(matches!(
+            acme.check(&with_tools),
+            Err(CapabilityError::ToolsNotSupported { .. })
+        ));
+        let mut long = request(Message::user("Hi"));
+        long.max_tokens = Some(20);
+        assert!(matches!(
+            acme.check(&long),
+            Err(CapabilityError::OutputLimitExceeded { .. })
+        ));
+        assert!(matches!(
+            acme.check(&request(Message::user("word ".repeat(100)))),
+            Err(CapabilityError::ContextWindowExceeded { .. })
+        ));
+    }
+}
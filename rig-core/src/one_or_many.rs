@@ -97,6 +97,23 @@ impl<T: Clone> OneOrMany<T> {
         OneOrMany::many(items)
     }
 
+    /// Append the items of another `OneOrMany` object, e.g.: to add a prompt after the history.
+    pub fn concat(mut self, other: OneOrMany<T>) -> Self {
+        self.rest.push(other.first);
+        self.rest.extend(other.rest);
+        self
+    }
+
+    /// After `OneOrMany<T>` is created, add a vector of items of type T to the `rest`.
+    pub fn extend_from_vec(&mut self, items: Vec<T>) {
+        self.rest.extend(items);
+    }
+
+    /// Split the `OneOrMany<T>` into its first item and the rest of the items.
+    pub fn split_first(self) -> (T, Vec<T>) {
+        (self.first, self.rest)
+    }
+
     /// Specialized map function for OneOrMany objects.
     ///
     /// Since OneOrMany objects have *atleast* 1 item, using `.collect::<Vec<_>>()` and
@@ -141,6 +158,43 @@ impl<T: Clone> OneOrMany<T> {
     }
 }
 
+/// Create a `OneOrMany` object from a non-empty array, e.g.: `OneOrMany::from([a, b, c])`.
+/// Empty arrays are rejected at compile time.
+impl<T, const N: usize> From<[T; N]> for OneOrMany<T> {
+    fn from(items: [T; N]) -> Self {
+        const { assert!(N > 0, "Cannot create OneOrMany with an empty array.") };
+
+        let mut iter = items.into_iter();
+        let Some(first) = iter.next() else {
+            unreachable!()
+        };
+        OneOrMany {
+            first,
+            rest: iter.collect(),
+        }
+    }
+}
+
+/// Create a `OneOrMany` object from a non-empty list of items, without the `unwrap` required by
+/// `OneOrMany::many`.
+///
+/// # Example
+/// ```rust
+/// use rig::{message::UserContent, one_or_many, OneOrMany};
+///
+/// let content: OneOrMany<UserContent> = one_or_many![
+///     UserContent::text("What is in this document?"),
+///     UserContent::text("Answer in one sentence."),
+/// ];
+/// assert_eq!(content.len(), 2);
+/// ```
+#[macro_export]
+macro_rules! one_or_many {
+    ($($item:expr),+ $(,)?) => {
+        $crate::OneOrMany::from([$($item),+])
+    };
+}
+
 // ================================================================
 // Implementations of Iterator for OneOrMany
 //   - OneOrMany<T>::iter() -> iterate over references of T objects
@@ -398,6 +452,50 @@ where
     deserializer.deserialize_option(StringOrOptionOneOrMany(PhantomData))
 }
 
+// A special serialize_with function for fields with `OneOrMany<T>`, serializing a single item as
+// a scalar and multiple items as a sequence (for provider APIs expecting e.g. `"stop": "\n"` or
+// `"stop": ["\n", "###"]`). This is the form accepted by `string_or_one_or_many`.
+//
+// Usage:
+// #[derive(Serialize)]
+// struct MyStruct {
+//     #[serde(serialize_with = "scalar_or_many")]
+//     field: OneOrMany<String>,
+// }
+pub fn scalar_or_many<T, S>(value: &OneOrMany<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + Clone,
+    S: Serializer,
+{
+    if value.rest.is_empty() {
+        value.first.serialize(serializer)
+    } else {
+        value.serialize(serializer)
+    }
+}
+
+// A variant of the `scalar_or_many` function for fields with `Option<OneOrMany<T>>`.
+//
+// Usage:
+// #[derive(Serialize)]
+// struct MyStruct {
+//     #[serde(serialize_with = "option_scalar_or_many")]
+//     field: Option<OneOrMany<String>>,
+// }
+pub fn option_scalar_or_many<T, S>(
+    value: &Option<OneOrMany<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + Clone,
+    S: Serializer,
+{
+    match value {
+        Some(value) => scalar_or_many(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use serde::{self, Deserialize};
@@ -511,6 +609,27 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_one_or_many_from_array() {
+        let one_or_many = OneOrMany::from(["hello".to_string(), "word".to_string()]);
+        assert_eq!(
+            one_or_many,
+            OneOrMany::many(vec!["hello".to_string(), "word".to_string()]).unwrap()
+        );
+
+        assert_eq!(OneOrMany::from([1]), OneOrMany::one(1));
+        assert_eq!(crate::one_or_many![1, 2, 3,].rest(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_one_or_many_concat() {
+        let mut one_or_many = OneOrMany::one(1).concat(OneOrMany::from([2, 3]));
+        one_or_many.extend_from_vec(vec![4]);
+        assert_eq!(one_or_many, OneOrMany::from([1, 2, 3, 4]));
+
+        assert_eq!(one_or_many.split_first(), (1, vec![2, 3, 4]));
+    }
+
     #[test]
     fn test_one_or_many_error() {
         assert!(OneOrMany::<String>::many(vec![]).is_err())
@@ -641,4 +760,39 @@ mod test {
 
         assert!(dummy.field.is_none());
     }
+
+    #[test]
+    fn test_serialize_scalar_or_many() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Request {
+            #[serde(
+                serialize_with = "scalar_or_many",
+                deserialize_with = "string_or_one_or_many"
+            )]
+            stop: OneOrMany<String>,
+            #[serde(
+                serialize_with = "option_scalar_or_many",
+                skip_serializing_if = "Option::is_none",
+                default
+            )]
+            ids: Option<OneOrMany<u32>>,
+        }
+
+        let request = Request {
+            stop: OneOrMany::one("\n".to_string()),
+            ids: Some(OneOrMany::from([1, 2])),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json, json!({ "stop": "\n", "ids": [1, 2] }));
+        assert_eq!(serde_json::from_value::<Request>(json).unwrap(), request);
+
+        let request = Request {
+            stop: OneOrMany::from(["\n".to_string(), "###".to_string()]),
+            ids: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({ "stop": ["\n", "###"] })
+        );
+    }
 }
@@ -0,0 +1,332 @@
+//! This module provides parsers for common non-JSON output formats of LLMs (comma separated lists,
+//! numbered lists, markdown tables and constrained choices). For structured JSON outputs, see the
+//! [extractor](crate::extractor) module.
+//!
+//! Parsers implement the [OutputParser] trait and can be used standalone, or as pipeline ops
+//! with [parse].
+//!
+//! # Example
+//! ```rust
+//! use rig::output_parsers::{CommaList, EnumParser, MarkdownTable, NumberedList, OutputParser};
+//!
+//! let fruits = CommaList.parse("apples, pears, and bananas.").unwrap();
+//! assert_eq!(fruits, vec!["apples", "pears", "bananas"]);
+//!
+//! let steps = NumberedList.parse("Here are the steps:\n1. Preheat\n2) Bake").unwrap();
+//! assert_eq!(steps, vec!["Preheat", "Bake"]);
+//!
+//! let rows = MarkdownTable::new()
+//!     .parse("| Name | Age |\n|------|-----|\n| John | 30 |")
+//!     .unwrap();
+//! assert_eq!(rows, vec![vec!["John", "30"]]);
+//!
+//! let sentiment = EnumParser::new(["positive", "negative", "neutral"])
+//!     .parse("Sentiment: **Positive**")
+//!     .unwrap();
+//! assert_eq!(sentiment, "positive");
+//! ```
+use std::marker::PhantomData;
+
+use crate::pipeline::Op;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("No {0} found in the output")]
+    NotFound(&'static str),
+
+    #[error("Invalid output: {0}")]
+    InvalidOutput(String),
+}
+
+/// A parser of the text output of a model into `Self::Output`.
+pub trait OutputParser: Send + Sync {
+    type Output: Send + Sync;
+
+    fn parse(&self, text: &str) -> Result<Self::Output, ParseError>;
+}
+
+/// Strip surrounding whitespace, quotes and markdown emphasis from an item.
+fn clean_item(item: &str) -> &str {
+    item.trim()
+        .trim_matches(|c: char| c == '"' || c == '\'' || c == '`' || c == '*' || c == '_')
+        .trim()
+}
+
+/// Parses a comma separated list (e.g.: `apples, pears and bananas`) into its items.
+/// A trailing period and a leading "and" on the last item are removed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CommaList;
+
+impl OutputParser for CommaList {
+    type Output = Vec<String>;
+
+    fn parse(&self, text: &str) -> Result<Self::Output, ParseError> {
+        let text = text.trim().trim_end_matches('.');
+
+        let items = text
+            .split([',', '\n'])
+            .map(|item| {
+                let item = item.trim();
+                clean_item(item.strip_prefix("and ").unwrap_or(item))
+            })
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err(ParseError::NotFound("list items"));
+        }
+        Ok(items)
+    }
+}
+
+/// Parses a numbered list (`1. item` or `1) item`, one per line) into its items.
+/// Lines that are not list items (e.g.: an introduction sentence) are ignored.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NumberedList;
+
+impl OutputParser for NumberedList {
+    type Output = Vec<String>;
+
+    fn parse(&self, text: &str) -> Result<Self::Output, ParseError> {
+        let items = text
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_start();
+                let digits = line.find(|c: char| !c.is_ascii_digit())?;
+                if digits == 0 {
+                    return None;
+                }
+                line[digits..]
+                    .strip_prefix(['.', ')'])
+                    .map(clean_item)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+            })
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Err(ParseError::NotFound("numbered list items"));
+        }
+        Ok(items)
+    }
+}
+
+/// Parses the first markdown table of the output into its rows of cells.
+/// By default, the header row is skipped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkdownTable {
+    include_header: bool,
+}
+
+impl MarkdownTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include the header row as the first row of the output.
+    pub fn include_header(mut self) -> Self {
+        self.include_header = true;
+        self
+    }
+}
+
+impl OutputParser for MarkdownTable {
+    type Output = Vec<Vec<String>>;
+
+    fn parse(&self, text: &str) -> Result<Self::Output, ParseError> {
+        let is_separator = |cells: &[String]| {
+            cells
+                .iter()
+                .all(|cell| !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':' | ' ')))
+        };
+
+        let mut rows = text
+            .lines()
+            .map(str::trim)
+            .skip_while(|line| !line.starts_with('|'))
+            .take_while(|line| line.starts_with('|'))
+            .map(|line| {
+                line.trim_matches('|')
+                    .split('|')
+                    .map(|cell| cell.trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if rows.len() < 2 || !is_separator(&rows[1]) {
+            return Err(ParseError::NotFound("markdown table"));
+        }
+
+        let width = rows[0].len();
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return Err(ParseError::InvalidOutput(format!(
+                "Table row has {} cells, expected {width}: {row:?}",
+                row.len()
+            )));
+        }
+
+        rows.remove(1);
+        if !self.include_header {
+            rows.remove(0);
+        }
+        Ok(rows)
+    }
+}
+
+/// Parses an output constrained to one of a fixed set of choices, returning the matching choice.
+///
+/// Matching is case insensitive. If the output is not exactly one of the choices, the choice
+/// it contains is returned, as long as it contains only one of them (e.g.: `Sentiment: positive.`).
+#[derive(Clone, Debug)]
+pub struct EnumParser {
+    choices: Vec<String>,
+}
+
+impl EnumParser {
+    pub fn new(choices: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            choices: choices.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn choices(&self) -> &[String] {
+        &self.choices
+    }
+}
+
+impl OutputParser for EnumParser {
+    type Output = String;
+
+    fn parse(&self, text: &str) -> Result<Self::Output, ParseError> {
+        let text = clean_item(text.trim().trim_end_matches('.')).to_lowercase();
+
+        if let Some(choice) = self
+            .choices
+            .iter()
+            .find(|choice| choice.to_lowercase() == text)
+        {
+            return Ok(choice.clone());
+        }
+
+        let words = text
+            .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+            .collect::<Vec<_>>();
+        let found = self
+            .choices
+            .iter()
+            .filter(|choice| words.contains(&choice.to_lowercase().as_str()))
+            .collect::<Vec<_>>();
+
+        match found[..] {
+            [choice] => Ok(choice.clone()),
+            [] => Err(ParseError::InvalidOutput(format!(
+                "Expected one of {:?}, got: {text}",
+                self.choices
+            ))),
+            _ => Err(ParseError::InvalidOutput(format!(
+                "Output matches several choices {found:?}: {text}"
+            ))),
+        }
+    }
+}
+
+pub struct Parse<P, In> {
+    parser: P,
+    _in: PhantomData<In>,
+}
+
+impl<P, In> Op for Parse<P, In>
+where
+    P: OutputParser,
+    In: AsRef<str> + Send + Sync,
+{
+    type Input = In;
+    type Output = Result<P::Output, ParseError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        self.parser.parse(input.as_ref())
+    }
+}
+
+/// Create a new parse operation.
+///
+/// The op will parse its input with the provided parser.
+///
+/// # Example
+/// ```rust
+/// use rig::{output_parsers::{self, CommaList}, pipeline::{self, Op}};
+///
+/// # async fn run() {
+/// let pipeline = pipeline::new()
+///     .map(|topic: &str| format!("{topic}: rust, python, go"))
+///     .chain(output_parsers::parse(CommaList));
+///
+/// let languages = pipeline.call("languages").await;
+/// # }
+/// ```
+pub fn parse<P, In>(parser: P) -> Parse<P, In>
+where
+    P: OutputParser,
+    In: AsRef<str> + Send + Sync,
+{
+    Parse {
+        parser,
+        _in: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            CommaList
+                .parse("`red`, \"green\", blue,\nand yellow.")
+                .unwrap(),
+            vec!["red", "green", "blue", "yellow"]
+        );
+        assert!(CommaList.parse(" . ").is_err());
+
+        assert_eq!(
+            NumberedList
+                .parse("Sure! Steps:\n\n1. **Mix**\n 2) Bake.\n10. Serve\nEnjoy!")
+                .unwrap(),
+            vec!["Mix", "Bake.", "Serve"]
+        );
+        assert!(NumberedList.parse("No list here").is_err());
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let text =
+            "Results:\n\n| Name | Age |\n|:-----|----:|\n| John | 30 |\n| Jane | 25 |\n\nDone";
+
+        assert_eq!(
+            MarkdownTable::new().include_header().parse(text).unwrap(),
+            vec![vec!["Name", "Age"], vec!["John", "30"], vec!["Jane", "25"]]
+        );
+        assert!(MarkdownTable::new().parse("| a | b |\n| c | d |").is_err());
+        assert!(MarkdownTable::new()
+            .parse("| a | b |\n|---|---|\n| c |")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_enum_parser() {
+        let parser = EnumParser::new(["Positive", "Negative"]);
+
+        assert_eq!(parser.parse("positive").unwrap(), "Positive");
+        assert_eq!(
+            parser.parse("The sentiment is *negative*.").unwrap(),
+            "Negative"
+        );
+        assert!(parser.parse("neutral").is_err());
+        assert!(parser.parse("positive or negative").is_err());
+
+        let op = parse(parser);
+        assert_eq!(op.call("NEGATIVE").await.unwrap(), "Negative");
+    }
+}
@@ -162,7 +162,7 @@ pub mod tests {
         async fn prompt(&self, prompt: impl Into<message::Message>) -> Result<String, PromptError> {
             let msg: message::Message = prompt.into();
             let prompt = match msg {
-                message::Message::User { content } => match content.first() {
+                message::Message::User { content, .. } => match content.first() {
                     message::UserContent::Text(message::Text { text }) => text,
                     _ => unreachable!(),
                 },
@@ -73,6 +73,13 @@ macro_rules! conditional {
                         }
                     }
                 }
+
+                fn graph(&self) -> $crate::pipeline::graph::OpGraph {
+                    $crate::pipeline::graph::OpGraph::fork(
+                        "conditional",
+                        vec![$((Some(stringify!($variant).to_string()), self.$variant.graph())),+],
+                    )
+                }
             }
 
             ConditionalOp { $($variant: $op),+ }
@@ -150,6 +157,13 @@ macro_rules! try_conditional {
                         ),+
                     }
                 }
+
+                fn try_graph(&self) -> $crate::pipeline::graph::OpGraph {
+                    $crate::pipeline::graph::OpGraph::fork(
+                        "conditional",
+                        vec![$((Some(stringify!($variant).to_string()), self.$variant.try_graph())),+],
+                    )
+                }
             }
 
             TryConditionalOp { $($variant: $op),+ }
@@ -0,0 +1,387 @@
+//! This module provides [OpGraph], the graph of the ops of a pipeline returned by
+//! [Op::graph](super::Op::graph), which can be exported to the DOT (Graphviz) and Mermaid
+//! formats to document or debug complex pipelines.
+//!
+//! # Example
+//! ```rust
+//! use rig::{parallel, pipeline::{self, map, Op}};
+//!
+//! let pipeline = pipeline::new()
+//!     .map(|x: i32| x + 1)
+//!     .chain(parallel!(map(|x: i32| x * 2), map(|x: i32| x.to_string())));
+//!
+//! // E.g.: in a README
+//! println!("```mermaid\n{}```", pipeline.graph().to_mermaid());
+//! ```
+
+use std::any::type_name;
+
+use super::{Op, TryOp};
+
+/// A node of the graph of a pipeline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OpGraph {
+    /// A single op, with its name and input and output types
+    Op {
+        name: String,
+        input: String,
+        output: String,
+    },
+    /// Ops run one after the other
+    Sequence(Vec<OpGraph>),
+    /// Ops run on the same input (or on parts of the input) concurrently or conditionally. The
+    /// kind is the name of the combinator (e.g.: `parallel`), and branches can have a label
+    /// (e.g.: the variant of a conditional op).
+    Fork {
+        kind: String,
+        branches: Vec<(Option<String>, OpGraph)>,
+    },
+}
+
+impl OpGraph {
+    /// Graph of a single op, named after its type.
+    pub fn op<T: Op + ?Sized>() -> Self {
+        Self::Op {
+            name: op_name::<T>(),
+            input: short_type_name(type_name::<T::Input>()),
+            output: short_type_name(type_name::<T::Output>()),
+        }
+    }
+
+    /// Graph of a single fallible op, named after its type.
+    pub fn try_op<T: TryOp + ?Sized>() -> Self {
+        Self::Op {
+            name: op_name::<T>(),
+            input: short_type_name(type_name::<T::Input>()),
+            output: short_type_name(&format!(
+                "Result<{}, {}>",
+                type_name::<T::Output>(),
+                type_name::<T::Error>()
+            )),
+        }
+    }
+
+    /// Graph of two ops run one after the other.
+    pub fn sequence(first: OpGraph, second: OpGraph) -> Self {
+        let mut ops = vec![];
+        for graph in [first, second] {
+            match graph {
+                Self::Sequence(graphs) => ops.extend(graphs),
+                graph => ops.push(graph),
+            }
+        }
+        Self::Sequence(ops)
+    }
+
+    /// Graph of ops run concurrently or conditionally. Unlabeled branches that are forks of the
+    /// same kind are flattened (e.g.: nested `parallel` ops).
+    pub fn fork(kind: &str, branches: Vec<(Option<String>, OpGraph)>) -> Self {
+        let flatten = matches!(kind, "parallel" | "race" | "race_ok");
+
+        let mut flat = vec![];
+        for branch in branches {
+            match branch {
+                (None, Self::Fork { kind: k, branches }) if flatten && k == kind => {
+                    flat.extend(branches)
+                }
+                branch => flat.push(branch),
+            }
+        }
+
+        Self::Fork {
+            kind: kind.to_string(),
+            branches: flat,
+        }
+    }
+
+    /// Export the graph in the DOT format (e.g.: to render it with Graphviz).
+    pub fn to_dot(&self) -> String {
+        let (nodes, edges) = self.layout();
+
+        let mut dot = "digraph pipeline {\n    node [shape=box];\n".to_string();
+        for (id, node) in nodes.iter().enumerate() {
+            let attributes = match node {
+                Node::Terminal(label) => format!("label=\"{label}\", shape=oval"),
+                Node::Op(label) => format!("label=\"{}\"", escape_dot(label)),
+                Node::Fork(kind) => format!("label=\"{}\", shape=diamond", escape_dot(kind)),
+                Node::Merge => "label=\"\", shape=point".to_string(),
+            };
+            dot.push_str(&format!("    n{id} [{attributes}];\n"));
+        }
+        for (from, to, label) in edges {
+            match label {
+                Some(label) => dot.push_str(&format!(
+                    "    n{from} -> n{to} [label=\"{}\"];\n",
+                    escape_dot(&label)
+                )),
+                None => dot.push_str(&format!("    n{from} -> n{to};\n")),
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Export the graph in the Mermaid flowchart format (e.g.: to render it in Markdown).
+    pub fn to_mermaid(&self) -> String {
+        let (nodes, edges) = self.layout();
+
+        let mut mermaid = "flowchart TD\n".to_string();
+        for (id, node) in nodes.iter().enumerate() {
+            let shape = match node {
+                Node::Terminal(label) => format!("([{label}])"),
+                Node::Op(label) => format!("[\"{}\"]", escape_mermaid(label)),
+                Node::Fork(kind) => format!("{{\"{}\"}}", escape_mermaid(kind)),
+                Node::Merge => "((\" \"))".to_string(),
+            };
+            mermaid.push_str(&format!("    n{id}{shape}\n"));
+        }
+        for (from, to, label) in edges {
+            match label {
+                Some(label) => mermaid.push_str(&format!(
+                    "    n{from} -->|\"{}\"| n{to}\n",
+                    escape_mermaid(&label)
+                )),
+                None => mermaid.push_str(&format!("    n{from} --> n{to}\n")),
+            }
+        }
+
+        mermaid
+    }
+
+    fn layout(&self) -> (Vec<Node>, Vec<Edge>) {
+        let mut layout = Layout::default();
+
+        let input = layout.node(Node::Terminal("input"));
+        let (entries, exits) = layout.add(self);
+        let output = layout.node(Node::Terminal("output"));
+
+        let mut edges = entries
+            .into_iter()
+            .map(|entry| (input, entry, None))
+            .collect::<Vec<_>>();
+        edges.append(&mut layout.edges);
+        edges.extend(exits.into_iter().map(|exit| (exit, output, None)));
+        // Nodes are numbered in the order they are visited, so this lists the edges from top to
+        // bottom
+        edges.sort_by_key(|(from, _, _)| *from);
+
+        (layout.nodes, edges)
+    }
+}
+
+enum Node {
+    Terminal(&'static str),
+    /// Label of the op, with its name and types on separate lines
+    Op(String),
+    Fork(String),
+    Merge,
+}
+
+type Edge = (usize, usize, Option<String>);
+
+#[derive(Default)]
+struct Layout {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Layout {
+    fn node(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: Option<String>) {
+        self.edges.push((from, to, label));
+    }
+
+    /// Add the nodes of the graph, returning its entry and exit nodes.
+    fn add(&mut self, graph: &OpGraph) -> (Vec<usize>, Vec<usize>) {
+        match graph {
+            OpGraph::Op {
+                name,
+                input,
+                output,
+            } => {
+                let node = self.node(Node::Op(format!("{name}\n{input} → {output}")));
+                (vec![node], vec![node])
+            }
+            OpGraph::Sequence(graphs) => {
+                let mut entries = None;
+                let mut exits: Vec<usize> = vec![];
+                for graph in graphs {
+                    let (graph_entries, graph_exits) = self.add(graph);
+                    for &exit in &exits {
+                        for &entry in &graph_entries {
+                            self.edge(exit, entry, None);
+                        }
+                    }
+                    entries.get_or_insert(graph_entries);
+                    exits = graph_exits;
+                }
+                (entries.unwrap_or_default(), exits)
+            }
+            OpGraph::Fork { kind, branches } => {
+                let fork = self.node(Node::Fork(kind.clone()));
+                let mut branch_exits = vec![];
+                for (label, graph) in branches {
+                    let (entries, exits) = self.add(graph);
+                    for entry in entries {
+                        self.edge(fork, entry, label.clone());
+                    }
+                    branch_exits.extend(exits);
+                }
+
+                let merge = self.node(Node::Merge);
+                for exit in branch_exits {
+                    self.edge(exit, merge, None);
+                }
+                (vec![fork], vec![merge])
+            }
+        }
+    }
+}
+
+/// Name of an op type, without its module path and generics (e.g.: `Map`).
+fn op_name<T: ?Sized>() -> String {
+    let name = type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name).to_string()
+}
+
+/// Remove the module paths from a type name (e.g.: `alloc::string::String` becomes `String`).
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+
+    for c in name.chars().chain(std::iter::once('\0')) {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+            continue;
+        }
+        short.push_str(path.rsplit("::").next().unwrap_or_default());
+        path.clear();
+        if c != '\0' {
+            short.push(c);
+        }
+    }
+
+    short
+}
+
+fn escape_dot(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_mermaid(label: &str) -> String {
+    label
+        .replace('"', "#quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br/>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        conditional, parallel,
+        pipeline::{self, map, parallel::race_ok},
+    };
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(
+            short_type_name("core::result::Result<alloc::vec::Vec<(f64, alloc::string::String)>, rig::completion::PromptError>"),
+            "Result<Vec<(f64, String)>, PromptError>"
+        );
+    }
+
+    #[test]
+    fn test_graph() {
+        let pipeline = pipeline::new()
+            .map(|x: i32| x + 1)
+            .chain(parallel!(
+                map(|x: i32| x * 2),
+                map(|x: i32| x * 3),
+                map(|x: i32| x.to_string())
+            ))
+            .map(|(a, b, c)| format!("{a} {b} {c}"));
+
+        let OpGraph::Sequence(ops) = pipeline.graph() else {
+            panic!("Expected a sequence");
+        };
+        assert_eq!(ops.len(), 4);
+        assert_eq!(
+            ops[0],
+            OpGraph::Op {
+                name: "Map".to_string(),
+                input: "i32".to_string(),
+                output: "i32".to_string()
+            }
+        );
+        assert!(
+            matches!(&ops[1], OpGraph::Fork { kind, branches } if kind == "parallel" && branches.len() == 3)
+        );
+    }
+
+    #[test]
+    fn test_graph_export() {
+        enum Route<T> {
+            Fast(T),
+            Careful(T),
+        }
+
+        let pipeline = pipeline::new()
+            .map(|x: i32| {
+                if x > 0 {
+                    Route::Fast(x)
+                } else {
+                    Route::Careful(x)
+                }
+            })
+            .chain(conditional!(Route,
+                Fast => map(|x: i32| Ok::<_, String>(x)),
+                Careful => race_ok(map(|x: i32| Ok(x)), map(|x: i32| Err(x.to_string()))),
+            ))
+            .map_ok(|x| x * 2);
+
+        assert_eq!(
+            pipeline.graph().to_mermaid(),
+            "flowchart TD
+    n0([input])
+    n1[\"Map<br/>i32 → Route&lt;i32&gt;\"]
+    n2{\"conditional\"}
+    n3[\"Map<br/>i32 → Result&lt;i32, String&gt;\"]
+    n4{\"race_ok\"}
+    n5[\"Map<br/>i32 → Result&lt;i32, String&gt;\"]
+    n6[\"Map<br/>i32 → Result&lt;i32, String&gt;\"]
+    n7((\" \"))
+    n8((\" \"))
+    n9[\"Map<br/>i32 → i32\"]
+    n10([output])
+    n0 --> n1
+    n1 --> n2
+    n2 -->|\"Fast\"| n3
+    n2 -->|\"Careful\"| n4
+    n3 --> n8
+    n4 --> n5
+    n4 --> n6
+    n5 --> n7
+    n6 --> n7
+    n7 --> n8
+    n8 --> n9
+    n9 --> n10
+"
+        );
+        assert!(pipeline
+            .graph()
+            .to_dot()
+            .contains("n2 -> n3 [label=\"Fast\"];"));
+        assert!(pipeline.try_graph() == pipeline.graph());
+    }
+}
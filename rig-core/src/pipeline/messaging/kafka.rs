@@ -0,0 +1,171 @@
+//! Kafka [MessageSource] and [MessageSink], based on [rdkafka].
+//!
+//! This module is only available with the `kafka` feature, and requires a tokio runtime.
+//!
+//! # Example
+//! ```rust
+//! use rdkafka::ClientConfig;
+//! use rig::pipeline::{
+//!     self,
+//!     messaging::{
+//!         kafka::{KafkaSink, KafkaSource},
+//!         publish, Consumer,
+//!     },
+//!     Op,
+//! };
+//!
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut config = ClientConfig::new();
+//! config
+//!     .set("bootstrap.servers", "localhost:9092")
+//!     .set("group.id", "enrichment");
+//!
+//! let source = KafkaSource::new(&config, &["reviews"])?;
+//! let sink = KafkaSink::new(&config, "enriched-reviews")?;
+//!
+//! let pipeline = pipeline::new()
+//!     .map(|review: String| review.to_uppercase())
+//!     .chain(publish(sink));
+//! let consumer = Consumer::new(source, pipeline).batch_size(100);
+//! # Ok(())
+//! # }
+//! ```
+use std::time::Duration;
+
+use rdkafka::{
+    consumer::{Consumer as _, StreamConsumer},
+    producer::{FutureProducer, FutureRecord},
+    util::Timeout,
+    ClientConfig, Message as _,
+};
+
+use super::{Delivery, MessageSink, MessageSource, MessagingError};
+
+fn client_error(error: rdkafka::error::KafkaError) -> MessagingError {
+    MessagingError::ClientError(error.into())
+}
+
+/// Position of a received Kafka message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KafkaReceipt {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+/// A [MessageSource] consuming Kafka topics.
+///
+/// The offsets of the messages are stored when they are acknowledged, and committed according to
+/// the `enable.auto.commit` setting of the consumer.
+pub struct KafkaSource {
+    consumer: StreamConsumer,
+    batch_timeout: Duration,
+}
+
+impl KafkaSource {
+    /// Create a consumer from `config` and subscribe it to `topics`. `enable.auto.offset.store`
+    /// is disabled, so that only the offsets of acknowledged messages are committed.
+    pub fn new(config: &ClientConfig, topics: &[&str]) -> Result<Self, MessagingError> {
+        let consumer: StreamConsumer = config
+            .clone()
+            .set("enable.auto.offset.store", "false")
+            .create()
+            .map_err(client_error)?;
+        consumer.subscribe(topics).map_err(client_error)?;
+
+        Ok(Self {
+            consumer,
+            batch_timeout: Duration::from_millis(100),
+        })
+    }
+
+    /// Set how long to wait for more messages to fill a batch once a message was received
+    /// (defaults to 100 milliseconds).
+    pub fn batch_timeout(mut self, batch_timeout: Duration) -> Self {
+        self.batch_timeout = batch_timeout;
+        self
+    }
+
+    pub fn consumer(&self) -> &StreamConsumer {
+        &self.consumer
+    }
+}
+
+impl MessageSource for KafkaSource {
+    type Receipt = KafkaReceipt;
+
+    async fn receive(
+        &mut self,
+        max: usize,
+    ) -> Result<Option<Vec<Delivery<KafkaReceipt>>>, MessagingError> {
+        let delivery = |message: &rdkafka::message::BorrowedMessage| Delivery {
+            payload: message.payload().unwrap_or_default().to_vec(),
+            receipt: KafkaReceipt {
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+            },
+        };
+
+        let message = self.consumer.recv().await.map_err(client_error)?;
+        let mut batch = vec![delivery(&message)];
+        drop(message);
+
+        while batch.len() < max {
+            match tokio::time::timeout(self.batch_timeout, self.consumer.recv()).await {
+                Ok(message) => batch.push(delivery(&message.map_err(client_error)?)),
+                Err(_) => break,
+            }
+        }
+
+        Ok(Some(batch))
+    }
+
+    async fn ack(&mut self, receipts: Vec<KafkaReceipt>) -> Result<(), MessagingError> {
+        for receipt in receipts {
+            self.consumer
+                .store_offset(&receipt.topic, receipt.partition, receipt.offset)
+                .map_err(client_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// A [MessageSink] producing to a Kafka topic.
+pub struct KafkaSink {
+    producer: FutureProducer,
+    topic: String,
+    queue_timeout: Duration,
+}
+
+impl KafkaSink {
+    pub fn new(config: &ClientConfig, topic: &str) -> Result<Self, MessagingError> {
+        Ok(Self {
+            producer: config.create().map_err(client_error)?,
+            topic: topic.to_string(),
+            queue_timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// Set how long to wait for room in the queue of the producer when it is full (defaults to
+    /// 5 seconds).
+    pub fn queue_timeout(mut self, queue_timeout: Duration) -> Self {
+        self.queue_timeout = queue_timeout;
+        self
+    }
+}
+
+impl MessageSink for KafkaSink {
+    async fn send(&self, payloads: Vec<Vec<u8>>) -> Result<(), MessagingError> {
+        futures::future::try_join_all(payloads.iter().map(|payload| {
+            self.producer.send(
+                FutureRecord::<(), _>::to(&self.topic).payload(payload),
+                Timeout::After(self.queue_timeout),
+            )
+        }))
+        .await
+        .map_err(|(error, _)| client_error(error))?;
+
+        Ok(())
+    }
+}
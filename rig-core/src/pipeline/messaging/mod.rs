@@ -0,0 +1,412 @@
+//! This module provides the consumption and production of messages from and to message
+//! brokers, so that pipelines (e.g.: LLM enrichment pipelines) can run inside existing streaming
+//! infrastructure.
+//!
+//! A [Consumer] receives batches of JSON messages from a [MessageSource], runs a pipeline on
+//! them, and acknowledges a batch only once all its messages were processed successfully. Each
+//! message is thus processed at least once: a batch whose processing fails (or is interrupted)
+//! is received again. The [publish] and [publish_batch] ops send JSON messages to a
+//! [MessageSink].
+//!
+//! Kafka and NATS JetStream are supported with the `kafka` and `nats` features, see
+//! [kafka](self::kafka) and [nats](self::nats).
+//!
+//! # Example
+//! ```rust
+//! use rig::pipeline::{
+//!     self,
+//!     messaging::{Consumer, MessageSink, MessageSource},
+//!     Op,
+//! };
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Review {
+//!     text: String,
+//! }
+//!
+//! # async fn run(source: impl MessageSource, sink: impl MessageSink + 'static) {
+//! let enrich = pipeline::new()
+//!     .map(|review: Review| serde_json::json!({ "text": review.text, "length": review.text.len() }))
+//!     .chain(pipeline::messaging::publish(sink));
+//!
+//! let mut consumer = Consumer::new(source, enrich).batch_size(32).concurrency(4);
+//! if let Err(e) = consumer.run().await {
+//!     eprintln!("Consumer stopped: {e}");
+//! }
+//! # }
+//! ```
+use std::{fmt::Display, future::Future, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::cancellation::CancellationToken;
+
+use super::Op;
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MessagingError {
+    /// Error of the client of the message broker
+    #[error("ClientError: {0}")]
+    ClientError(#[from] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("JsonError: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    /// The pipeline failed on a message of the batch, which was not acknowledged
+    #[error("PipelineError: {0}")]
+    PipelineError(String),
+}
+
+/// A message received from a [MessageSource].
+#[derive(Clone, Debug)]
+pub struct Delivery<R> {
+    pub payload: Vec<u8>,
+    /// Handle used to acknowledge the message
+    pub receipt: R,
+}
+
+/// Trait for sources of messages (e.g.: a Kafka topic).
+pub trait MessageSource: Send {
+    type Receipt: Send + Sync;
+
+    /// Receive a batch of at most `max` messages, waiting for at least one. Returns `None` when
+    /// the source is closed.
+    fn receive(
+        &mut self,
+        max: usize,
+    ) -> impl Future<Output = Result<Option<Vec<Delivery<Self::Receipt>>>, MessagingError>> + Send;
+
+    /// Acknowledge processed messages, so that they are not received again.
+    fn ack(
+        &mut self,
+        receipts: Vec<Self::Receipt>,
+    ) -> impl Future<Output = Result<(), MessagingError>> + Send;
+}
+
+/// Trait for destinations of messages (e.g.: a Kafka topic).
+pub trait MessageSink: Send + Sync {
+    /// Send messages, returning once the broker received them.
+    fn send(
+        &self,
+        payloads: Vec<Vec<u8>>,
+    ) -> impl Future<Output = Result<(), MessagingError>> + Send;
+}
+
+pub struct Publish<S, T> {
+    sink: S,
+    _t: PhantomData<fn(T)>,
+}
+
+impl<S, T> Op for Publish<S, T>
+where
+    S: MessageSink,
+    T: Serialize + Send + Sync,
+{
+    type Input = T;
+    type Output = Result<(), MessagingError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        self.sink.send(vec![serde_json::to_vec(&input)?]).await
+    }
+}
+
+/// Create an op sending its input as a JSON message to `sink`.
+pub fn publish<S, T>(sink: S) -> Publish<S, T>
+where
+    S: MessageSink,
+    T: Serialize + Send + Sync,
+{
+    Publish {
+        sink,
+        _t: PhantomData,
+    }
+}
+
+pub struct PublishBatch<S, T> {
+    sink: S,
+    _t: PhantomData<fn(T)>,
+}
+
+impl<S, T> Op for PublishBatch<S, T>
+where
+    S: MessageSink,
+    T: Serialize + Send + Sync,
+{
+    type Input = Vec<T>;
+    type Output = Result<(), MessagingError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let payloads = input
+            .iter()
+            .map(serde_json::to_vec)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.sink.send(payloads).await
+    }
+}
+
+/// Create an op sending the items of its input as JSON messages to `sink`, in a single batch.
+pub fn publish_batch<S, T>(sink: S) -> PublishBatch<S, T>
+where
+    S: MessageSink,
+    T: Serialize + Send + Sync,
+{
+    PublishBatch {
+        sink,
+        _t: PhantomData,
+    }
+}
+
+/// Runs a pipeline on the messages of a [MessageSource], see the
+/// [module documentation](self).
+pub struct Consumer<S, P, T> {
+    source: S,
+    pipeline: P,
+    batch_size: usize,
+    concurrency: usize,
+    token: Option<CancellationToken>,
+    _t: PhantomData<fn(T)>,
+}
+
+impl<S, P, T, O, E> Consumer<S, P, T>
+where
+    S: MessageSource,
+    P: Op<Input = T, Output = Result<O, E>>,
+    T: DeserializeOwned + Send + Sync,
+    O: Send + Sync,
+    E: Display + Send + Sync,
+{
+    /// Create a consumer running `pipeline` on the messages of `source`, deserialized from JSON.
+    /// A pipeline returning an error fails the processing of the message.
+    pub fn new(source: S, pipeline: P) -> Self {
+        Self {
+            source,
+            pipeline,
+            batch_size: 16,
+            concurrency: 1,
+            token: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Set the maximum number of messages received at once (defaults to 16).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set the number of messages of a batch processed concurrently (defaults to 1).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Stop consuming when `token` is cancelled. The batch in progress is dropped without being
+    /// acknowledged.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Consume the messages until the source is closed or the consumer is cancelled.
+    ///
+    /// Returns an error if receiving or acknowledging messages fails, or if the pipeline fails
+    /// on a message; in which case the messages of the current batch are not acknowledged and
+    /// will be received again by the next consumer of the source (e.g.: after a restart).
+    /// Messages that are not valid JSON for the input of the pipeline are skipped with a
+    /// warning, and acknowledged.
+    pub async fn run(&mut self) -> Result<(), MessagingError> {
+        let token = self.token.clone().unwrap_or_default();
+
+        loop {
+            let batch = match token
+                .run_until_cancelled(self.source.receive(self.batch_size))
+                .await
+            {
+                Ok(batch) => batch?,
+                Err(_) => return Ok(()),
+            };
+            let Some(batch) = batch else {
+                return Ok(());
+            };
+
+            let mut inputs = vec![];
+            let mut receipts = vec![];
+            for delivery in batch {
+                match serde_json::from_slice(&delivery.payload) {
+                    Ok(input) => inputs.push(input),
+                    Err(e) => tracing::warn!("Skipping an invalid message: {e}"),
+                }
+                receipts.push(delivery.receipt);
+            }
+
+            let outputs = match token
+                .run_until_cancelled(self.pipeline.batch_call(self.concurrency, inputs))
+                .await
+            {
+                Ok(outputs) => outputs,
+                Err(_) => return Ok(()),
+            };
+            if let Some(error) = outputs.into_iter().find_map(Result::err) {
+                return Err(MessagingError::PipelineError(error.to_string()));
+            }
+
+            self.source.ack(receipts).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::pipeline::{self, TryOp};
+
+    /// A source redelivering the messages that were not acknowledged when restarted
+    #[derive(Default)]
+    struct MockSource {
+        pending: VecDeque<(u64, String)>,
+        in_flight: Vec<(u64, String)>,
+        acked: Vec<u64>,
+    }
+
+    impl MockSource {
+        fn restart(&mut self) {
+            for message in self.in_flight.drain(..).rev() {
+                self.pending.push_front(message);
+            }
+        }
+    }
+
+    impl MessageSource for MockSource {
+        type Receipt = u64;
+
+        async fn receive(
+            &mut self,
+            max: usize,
+        ) -> Result<Option<Vec<Delivery<u64>>>, MessagingError> {
+            if self.pending.is_empty() {
+                return Ok(None);
+            }
+            let batch = self
+                .pending
+                .drain(..max.min(self.pending.len()))
+                .collect::<Vec<_>>();
+            self.in_flight.extend(batch.clone());
+            Ok(Some(
+                batch
+                    .into_iter()
+                    .map(|(offset, payload)| Delivery {
+                        payload: payload.into_bytes(),
+                        receipt: offset,
+                    })
+                    .collect(),
+            ))
+        }
+
+        async fn ack(&mut self, receipts: Vec<u64>) -> Result<(), MessagingError> {
+            self.in_flight
+                .retain(|(offset, _)| !receipts.contains(offset));
+            self.acked.extend(receipts);
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MockSink(Arc<Mutex<Vec<String>>>);
+
+    impl MessageSink for MockSink {
+        async fn send(&self, payloads: Vec<Vec<u8>>) -> Result<(), MessagingError> {
+            self.0.lock().unwrap().extend(
+                payloads
+                    .into_iter()
+                    .map(|payload| String::from_utf8(payload).unwrap()),
+            );
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Order {
+        amount: i64,
+    }
+
+    #[tokio::test]
+    async fn test_consumer() {
+        let fail = Arc::new(Mutex::new(true));
+        let fail_clone = fail.clone();
+        let sink = MockSink::default();
+
+        let pipeline = pipeline::new()
+            .map(move |order: Order| {
+                if order.amount == 3 && *fail_clone.lock().unwrap() {
+                    return Err("Unavailable".to_string());
+                }
+                Ok(Order {
+                    amount: order.amount * 10,
+                })
+            })
+            .chain_ok(publish(sink.clone()))
+            .map(|result| result?.map_err(|e| e.to_string()));
+
+        let source = MockSource {
+            pending: [
+                r#"{"amount": 1}"#,
+                "not json",
+                r#"{"amount": 3}"#,
+                r#"{"amount": 4}"#,
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(offset, payload)| (offset as u64, payload.to_string()))
+            .collect(),
+            ..Default::default()
+        };
+        let mut consumer = Consumer::new(source, pipeline).batch_size(2);
+
+        // The second batch fails and is not acknowledged
+        assert!(matches!(
+            consumer.run().await,
+            Err(MessagingError::PipelineError(e)) if e == "Unavailable"
+        ));
+        assert_eq!(consumer.source.acked, vec![0, 1]);
+
+        // It is received again after a restart
+        *fail.lock().unwrap() = false;
+        consumer.source.restart();
+        consumer.run().await.unwrap();
+        assert_eq!(consumer.source.acked, vec![0, 1, 2, 3]);
+        // The message processed in the failed batch is processed again
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![
+                r#"{"amount":10}"#,
+                r#"{"amount":40}"#,
+                r#"{"amount":30}"#,
+                r#"{"amount":40}"#
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_batch() {
+        let sink = MockSink::default();
+        publish_batch(sink.clone())
+            .call(vec![Order { amount: 1 }, Order { amount: 2 }])
+            .await
+            .unwrap();
+
+        assert_eq!(sink.0.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,142 @@
+//! NATS JetStream [MessageSource] and [MessageSink], based on [async_nats].
+//!
+//! This module is only available with the `nats` feature, and requires a tokio runtime.
+//!
+//! # Example
+//! ```rust
+//! use async_nats::jetstream::{self, consumer::pull};
+//! use rig::pipeline::{
+//!     self,
+//!     messaging::{
+//!         nats::{NatsSink, NatsSource},
+//!         publish, Consumer,
+//!     },
+//!     Op,
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = async_nats::connect("localhost:4222").await?;
+//! let jetstream = jetstream::new(client);
+//!
+//! let stream = jetstream.get_stream("REVIEWS").await?;
+//! let consumer = stream
+//!     .get_or_create_consumer(
+//!         "enrichment",
+//!         pull::Config {
+//!             durable_name: Some("enrichment".to_string()),
+//!             ..Default::default()
+//!         },
+//!     )
+//!     .await?;
+//!
+//! let pipeline = pipeline::new()
+//!     .map(|review: String| review.to_uppercase())
+//!     .chain(publish(NatsSink::new(jetstream, "reviews.enriched")));
+//! let mut consumer = Consumer::new(NatsSource::new(consumer), pipeline);
+//! consumer.run().await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{future::IntoFuture, time::Duration};
+
+use async_nats::jetstream::{self, consumer::PullConsumer};
+use futures::StreamExt;
+
+use super::{Delivery, MessageSink, MessageSource, MessagingError};
+
+fn client_error(error: impl std::error::Error + Send + Sync + 'static) -> MessagingError {
+    MessagingError::ClientError(Box::new(error))
+}
+
+/// A [MessageSource] fetching the messages of a JetStream pull consumer. Messages are
+/// acknowledged individually.
+pub struct NatsSource {
+    consumer: PullConsumer,
+    batch_timeout: Duration,
+}
+
+impl NatsSource {
+    pub fn new(consumer: PullConsumer) -> Self {
+        Self {
+            consumer,
+            batch_timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Set how long to wait for messages to fill a batch (defaults to 1 second).
+    pub fn batch_timeout(mut self, batch_timeout: Duration) -> Self {
+        self.batch_timeout = batch_timeout;
+        self
+    }
+}
+
+impl MessageSource for NatsSource {
+    type Receipt = jetstream::Message;
+
+    async fn receive(
+        &mut self,
+        max: usize,
+    ) -> Result<Option<Vec<Delivery<jetstream::Message>>>, MessagingError> {
+        let mut messages = self
+            .consumer
+            .batch()
+            .max_messages(max)
+            .expires(self.batch_timeout)
+            .messages()
+            .await
+            .map_err(client_error)?;
+
+        let mut batch = vec![];
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(MessagingError::ClientError)?;
+            batch.push(Delivery {
+                payload: message.payload.to_vec(),
+                receipt: message,
+            });
+        }
+
+        Ok(Some(batch))
+    }
+
+    async fn ack(&mut self, receipts: Vec<jetstream::Message>) -> Result<(), MessagingError> {
+        for message in receipts {
+            message.ack().await.map_err(MessagingError::ClientError)?;
+        }
+        Ok(())
+    }
+}
+
+/// A [MessageSink] publishing to a JetStream subject, waiting for the acknowledgment of the
+/// server.
+pub struct NatsSink {
+    context: jetstream::Context,
+    subject: String,
+}
+
+impl NatsSink {
+    pub fn new(context: jetstream::Context, subject: &str) -> Self {
+        Self {
+            context,
+            subject: subject.to_string(),
+        }
+    }
+}
+
+impl MessageSink for NatsSink {
+    async fn send(&self, payloads: Vec<Vec<u8>>) -> Result<(), MessagingError> {
+        let mut acks = vec![];
+        for payload in payloads {
+            acks.push(
+                self.context
+                    .publish(self.subject.clone(), payload.into())
+                    .await
+                    .map_err(client_error)?,
+            );
+        }
+
+        futures::future::try_join_all(acks.into_iter().map(IntoFuture::into_future))
+            .await
+            .map_err(client_error)?;
+        Ok(())
+    }
+}
@@ -63,6 +63,10 @@
 //! assert_eq!(result, "Result: 2, 0");
 //! ```
 //!
+//! The [parallel](self::parallel) module also provides the [split](parallel::split) (run each op on
+//! its own element of the input tuple), [join](parallel::join) (combine the outputs of ops run
+//! concurrently) and [race](parallel::race) (keep the output of the fastest op) combinators.
+//!
 //! Notes:
 //! - The [chain](Op::chain) method is similar to the [map](Op::map) method but it allows
 //!   for chaining arbitrary operations, as long as they implement the [Op] trait.
@@ -88,7 +92,10 @@
 //! ```
 
 pub mod agent_ops;
+pub mod graph;
+pub mod messaging;
 pub mod op;
+pub mod presets;
 pub mod try_op;
 #[macro_use]
 pub mod parallel;
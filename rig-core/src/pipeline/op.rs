@@ -13,6 +13,16 @@ pub trait Op: Send + Sync {
 
     fn call(&self, input: Self::Input) -> impl Future<Output = Self::Output> + Send;
 
+    /// Get the graph of the ops of the current pipeline, e.g.: to export it as a diagram with
+    /// [OpGraph::to_mermaid] or [OpGraph::to_dot].
+    ///
+    /// Ops are named after their type. Combinators (e.g.: [Sequential] or
+    /// [Parallel](super::parallel::Parallel)) override this method to return the graph of
+    /// their ops.
+    fn graph(&self) -> OpGraph {
+        OpGraph::op::<Self>()
+    }
+
     /// Execute the current pipeline with the given inputs. `n` is the number of concurrent
     /// inputs that will be processed concurrently.
     fn batch_call<I>(&self, n: usize, input: I) -> impl Future<Output = Vec<Self::Output>> + Send
@@ -167,6 +177,31 @@ pub trait Op: Send + Sync {
     {
         Sequential::new(self, Prompt::new(prompt))
     }
+
+    /// Abort the current op when `token` is cancelled, in which case the op returns
+    /// [Cancelled] instead of its output.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::{cancellation::CancellationToken, pipeline::{self, Op}};
+    ///
+    /// # async fn run() {
+    /// let token = CancellationToken::new();
+    /// let pipeline = pipeline::new()
+    ///    .map(|x: i32| x + 1)
+    ///    .with_cancellation(token.clone());
+    ///
+    /// token.cancel();
+    /// let result = pipeline.call(1).await;
+    /// assert!(result.is_err());
+    /// # }
+    /// ```
+    fn with_cancellation(self, token: CancellationToken) -> WithCancellation<Self>
+    where
+        Self: Sized,
+    {
+        WithCancellation::new(self, token)
+    }
 }
 
 impl<T: Op> Op for &T {
@@ -177,6 +212,10 @@ impl<T: Op> Op for &T {
     async fn call(&self, input: Self::Input) -> Self::Output {
         (*self).call(input).await
     }
+
+    fn graph(&self) -> OpGraph {
+        (*self).graph()
+    }
 }
 
 // ================================================================
@@ -206,11 +245,46 @@ where
         let prev = self.prev.call(input).await;
         self.op.call(prev).await
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::sequence(self.prev.graph(), self.op.graph())
+    }
+}
+
+use crate::{
+    cancellation::{CancellationToken, Cancelled},
+    completion, vector_store,
+};
+
+use super::{
+    agent_ops::{Lookup, Prompt},
+    graph::OpGraph,
+};
+
+pub struct WithCancellation<Op> {
+    op: Op,
+    token: CancellationToken,
+}
+
+impl<Op> WithCancellation<Op> {
+    pub(crate) fn new(op: Op, token: CancellationToken) -> Self {
+        Self { op, token }
+    }
 }
 
-use crate::{completion, vector_store};
+impl<T: Op> Op for WithCancellation<T> {
+    type Input = T::Input;
+    type Output = Result<T::Output, Cancelled>;
+
+    #[inline]
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        self.token.run_until_cancelled(self.op.call(input)).await
+    }
 
-use super::agent_ops::{Lookup, Prompt};
+    fn graph(&self) -> OpGraph {
+        self.op.graph()
+    }
+}
 
 // ================================================================
 // Core Op implementations
@@ -1,6 +1,11 @@
-use futures::{join, try_join};
+use std::pin::pin;
 
-use super::{Op, TryOp};
+use futures::{
+    future::{select, Either},
+    join, try_join,
+};
+
+use super::{graph::OpGraph, Op, TryOp};
 
 pub struct Parallel<Op1, Op2> {
     op1: Op1,
@@ -26,6 +31,13 @@ where
     async fn call(&self, input: Self::Input) -> Self::Output {
         join!(self.op1.call(input.clone()), self.op2.call(input))
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "parallel",
+            vec![(None, self.op1.graph()), (None, self.op2.graph())],
+        )
+    }
 }
 
 impl<Op1, Op2> TryOp for Parallel<Op1, Op2>
@@ -42,6 +54,257 @@ where
     async fn try_call(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
         try_join!(self.op1.try_call(input.clone()), self.op2.try_call(input))
     }
+
+    fn try_graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "parallel",
+            vec![(None, self.op1.try_graph()), (None, self.op2.try_graph())],
+        )
+    }
+}
+
+/// Op running each op on its own element of the input tuple concurrently, see [split].
+pub struct Split<Op1, Op2> {
+    op1: Op1,
+    op2: Op2,
+}
+
+impl<Op1, Op2> Op for Split<Op1, Op2>
+where
+    Op1: Op,
+    Op2: Op,
+{
+    type Input = (Op1::Input, Op2::Input);
+    type Output = (Op1::Output, Op2::Output);
+
+    #[inline]
+    async fn call(&self, (input1, input2): Self::Input) -> Self::Output {
+        join!(self.op1.call(input1), self.op2.call(input2))
+    }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "split",
+            vec![(None, self.op1.graph()), (None, self.op2.graph())],
+        )
+    }
+}
+
+impl<Op1, Op2> TryOp for Split<Op1, Op2>
+where
+    Op1: TryOp,
+    Op2: TryOp<Error = Op1::Error>,
+{
+    type Input = (Op1::Input, Op2::Input);
+    type Output = (Op1::Output, Op2::Output);
+    type Error = Op1::Error;
+
+    #[inline]
+    async fn try_call(&self, (input1, input2): Self::Input) -> Result<Self::Output, Self::Error> {
+        try_join!(self.op1.try_call(input1), self.op2.try_call(input2))
+    }
+
+    fn try_graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "split",
+            vec![(None, self.op1.try_graph()), (None, self.op2.try_graph())],
+        )
+    }
+}
+
+/// Create an op taking a tuple as input and running `op1` on its first element and `op2` on
+/// its second element concurrently (e.g.: to send a different prompt to each model). Splits can
+/// be nested for more than two ops.
+///
+/// # Example
+/// ```rust
+/// use rig::pipeline::{self, map, parallel::split, Op};
+///
+/// # async fn run() {
+/// let pipeline = pipeline::new()
+///     .map(|text: &str| (text.to_string(), text.len()))
+///     .chain(split(map(|text: String| text.to_uppercase()), map(|len| len * 2)));
+///
+/// let result = pipeline.call("abc").await;
+/// assert_eq!(result, ("ABC".to_string(), 6));
+/// # }
+/// ```
+pub fn split<Op1, Op2>(op1: Op1, op2: Op2) -> Split<Op1, Op2>
+where
+    Op1: Op,
+    Op2: Op,
+{
+    Split { op1, op2 }
+}
+
+/// Op running two ops on the same input concurrently and combining their outputs, see [join].
+pub struct Join<Op1, Op2, F> {
+    op1: Op1,
+    op2: Op2,
+    f: F,
+}
+
+impl<Op1, Op2, F, Output> Op for Join<Op1, Op2, F>
+where
+    Op1: Op,
+    Op1::Input: Clone,
+    Op2: Op<Input = Op1::Input>,
+    F: Fn(Op1::Output, Op2::Output) -> Output + Send + Sync,
+    Output: Send + Sync,
+{
+    type Input = Op1::Input;
+    type Output = Output;
+
+    #[inline]
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let (output1, output2) = join!(self.op1.call(input.clone()), self.op2.call(input));
+        (self.f)(output1, output2)
+    }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "join",
+            vec![(None, self.op1.graph()), (None, self.op2.graph())],
+        )
+    }
+}
+
+/// Create an op running `op1` and `op2` on the same input concurrently and combining their
+/// outputs with `f` (e.g.: to merge the analyses of several models).
+///
+/// # Example
+/// ```rust
+/// use rig::pipeline::{self, map, parallel::join, Op};
+///
+/// # async fn run() {
+/// let pipeline = pipeline::new().chain(join(
+///     map(|x: i32| x + 1),
+///     map(|x: i32| x * 3),
+///     |a, b| format!("{a} and {b}"),
+/// ));
+///
+/// let result = pipeline.call(2).await;
+/// assert_eq!(result, "3 and 6");
+/// # }
+/// ```
+pub fn join<Op1, Op2, F, Output>(op1: Op1, op2: Op2, f: F) -> Join<Op1, Op2, F>
+where
+    Op1: Op,
+    Op1::Input: Clone,
+    Op2: Op<Input = Op1::Input>,
+    F: Fn(Op1::Output, Op2::Output) -> Output + Send + Sync,
+    Output: Send + Sync,
+{
+    Join { op1, op2, f }
+}
+
+/// Op running two ops on the same input concurrently and returning the first output, see
+/// [race].
+pub struct Race<Op1, Op2> {
+    op1: Op1,
+    op2: Op2,
+}
+
+impl<Op1, Op2> Op for Race<Op1, Op2>
+where
+    Op1: Op,
+    Op1::Input: Clone,
+    Op2: Op<Input = Op1::Input, Output = Op1::Output>,
+{
+    type Input = Op1::Input;
+    type Output = Op1::Output;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let op1 = pin!(self.op1.call(input.clone()));
+        let op2 = pin!(self.op2.call(input));
+
+        match select(op1, op2).await {
+            Either::Left((output, _)) | Either::Right((output, _)) => output,
+        }
+    }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "race",
+            vec![(None, self.op1.graph()), (None, self.op2.graph())],
+        )
+    }
+}
+
+/// Create an op running `op1` and `op2` on the same input concurrently and returning the output
+/// of the first one to complete. The other one is dropped (i.e.: cancelled). Races can be nested
+/// for more than two ops.
+///
+/// See [race_ok] to ignore the ops that fail.
+pub fn race<Op1, Op2>(op1: Op1, op2: Op2) -> Race<Op1, Op2>
+where
+    Op1: Op,
+    Op1::Input: Clone,
+    Op2: Op<Input = Op1::Input, Output = Op1::Output>,
+{
+    Race { op1, op2 }
+}
+
+/// Op running two fallible ops on the same input concurrently and returning the first
+/// successful output, see [race_ok].
+pub struct RaceOk<Op1, Op2> {
+    op1: Op1,
+    op2: Op2,
+}
+
+impl<Op1, Op2> Op for RaceOk<Op1, Op2>
+where
+    Op1: TryOp,
+    Op1::Input: Clone,
+    Op2: TryOp<Input = Op1::Input, Output = Op1::Output, Error = Op1::Error>,
+{
+    type Input = Op1::Input;
+    type Output = Result<Op1::Output, Op1::Error>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let op1 = pin!(self.op1.try_call(input.clone()));
+        let op2 = pin!(self.op2.try_call(input));
+
+        match select(op1, op2).await {
+            Either::Left((Ok(output), _)) | Either::Right((Ok(output), _)) => Ok(output),
+            Either::Left((Err(_), op2)) => op2.await,
+            Either::Right((Err(_), op1)) => op1.await,
+        }
+    }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::fork(
+            "race_ok",
+            vec![(None, self.op1.try_graph()), (None, self.op2.try_graph())],
+        )
+    }
+}
+
+/// Create an op running the fallible ops `op1` and `op2` on the same input concurrently and
+/// returning the first successful output (e.g.: to query several providers and keep the
+/// fastest answer). If both ops fail, the error of the last one is returned.
+///
+/// # Example
+/// ```rust
+/// use rig::pipeline::{self, map, parallel::race_ok, then, Op};
+///
+/// # async fn run() {
+/// let pipeline = pipeline::new().chain(race_ok(
+///     map(|_: i32| Err::<i32, _>("Unavailable")),
+///     then(|x: i32| async move { Ok(x * 2) }),
+/// ));
+///
+/// let result = pipeline.call(2).await;
+/// assert_eq!(result, Ok(4));
+/// # }
+/// ```
+pub fn race_ok<Op1, Op2>(op1: Op1, op2: Op2) -> RaceOk<Op1, Op2>
+where
+    Op1: TryOp,
+    Op1::Input: Clone,
+    Op2: TryOp<Input = Op1::Input, Output = Op1::Output, Error = Op1::Error>,
+{
+    RaceOk { op1, op2 }
 }
 
 // See https://doc.rust-lang.org/src/core/future/join.rs.html#48
@@ -399,6 +662,63 @@ mod tests {
         assert_eq!(response, Err("1 is the number!".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_split() {
+        let pipeline = split(
+            map(|x: i32| x + 1),
+            split(map(|s: String| s.len()), passthrough()),
+        );
+
+        let result = pipeline.call((1, ("abc".to_string(), true))).await;
+        assert_eq!(result, (2, (3, true)));
+
+        let pipeline = split(
+            map(|x: i32| Ok::<_, String>(x)),
+            map(|x: i32| Err::<i32, _>(format!("{x} is odd"))),
+        );
+        assert_eq!(pipeline.try_call((1, 3)).await, Err("3 is odd".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_join() {
+        let pipeline = join(
+            map(|x: i32| x + 1),
+            join(map(|x: i32| x * 3), passthrough(), |a, b| a - b),
+            |a, b| (a, b),
+        );
+
+        assert_eq!(pipeline.call(2).await, (3, 4));
+    }
+
+    #[tokio::test]
+    async fn test_race() {
+        let slow = |delay: u64, output: Result<u64, String>| {
+            then(move |x: u64| {
+                let output = output.clone();
+                async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                    output.map(|y| x + y)
+                }
+            })
+        };
+
+        let pipeline = race(slow(50, Ok(1)), race(slow(10, Ok(2)), slow(30, Ok(3))));
+        assert_eq!(pipeline.call(10).await, Ok(12));
+
+        // The fastest op fails
+        let pipeline = race(slow(50, Ok(1)), slow(10, Err("Unavailable".to_string())));
+        assert_eq!(pipeline.call(10).await, Err("Unavailable".to_string()));
+
+        let pipeline = race_ok(slow(50, Ok(1)), slow(10, Err("Unavailable".to_string())));
+        assert_eq!(pipeline.call(10).await, Ok(11));
+
+        let pipeline = race_ok(
+            slow(50, Err("Timeout".to_string())),
+            slow(10, Err("Unavailable".to_string())),
+        );
+        assert_eq!(pipeline.call(10).await, Err("Timeout".to_string()));
+    }
+
     #[tokio::test]
     async fn test_try_parallel_macro_ok() {
         let op2 = map(|x: i32| Ok::<_, String>(x * 2));
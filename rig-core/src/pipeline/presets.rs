@@ -0,0 +1,558 @@
+//! Ready-made pipelines for common workflows:
+//! - [summarize_map_reduce]: summarization of large (sets of) documents,
+//! - [plan_and_execute]: breaking down a task into steps, executed one after the other by an agent.
+//!
+//! It also provides the text chunkers used by the pipelines and retrieval helpers:
+//! [chunk_text] (by characters) and [chunk_text_tokens] (by tokens).
+//!
+//! # Example
+//! ```rust
+//! use rig::{pipeline::{self, presets, Op}, providers::openai};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//!
+//! let summarizer = presets::summarize_map_reduce(
+//!     // Cheap model to summarize the chunks
+//!     openai.agent(openai::GPT_4O_MINI).build(),
+//!     // Stronger model to combine the summaries
+//!     openai.agent(openai::GPT_4O).build(),
+//! )
+//! .chunk_size(4_000)
+//! .concurrency(8);
+//!
+//! let pipeline = pipeline::new()
+//!     .map(|reports: Vec<String>| reports)
+//!     .chain(summarizer);
+//!
+//! let summary = pipeline
+//!     .call(vec!["<report 1>".to_string(), "<report 2>".to_string()])
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::{stream, StreamExt, TryStreamExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    completion::{CompletionModel, Prompt, PromptError},
+    extractor::{ExtractionError, Extractor, ExtractorBuilder},
+    tokens::TokenCounter,
+};
+
+use super::Op;
+
+/// Map-reduce summarization of documents, see [summarize_map_reduce].
+pub struct SummarizeMapReduce<P1, P2> {
+    mapper: P1,
+    reducer: P2,
+    chunk_size: usize,
+    chunk_overlap: usize,
+    fan_in: usize,
+    concurrency: usize,
+    map_prompt: String,
+    reduce_prompt: String,
+}
+
+impl<P1, P2> SummarizeMapReduce<P1, P2> {
+    /// Set the maximum size of the chunks, in characters (defaults to 8000).
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set the number of characters shared by consecutive chunks (defaults to 200).
+    pub fn chunk_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+
+    /// Set the number of summaries combined by each reduce call (defaults to 5).
+    pub fn fan_in(mut self, fan_in: usize) -> Self {
+        self.fan_in = fan_in.max(2);
+        self
+    }
+
+    /// Set the maximum number of concurrent calls to the models (defaults to 4).
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the prompt used to summarize a chunk. `{text}` is replaced by the chunk.
+    pub fn map_prompt(mut self, map_prompt: &str) -> Self {
+        self.map_prompt = map_prompt.to_string();
+        self
+    }
+
+    /// Set the prompt used to combine summaries. `{text}` is replaced by the summaries.
+    pub fn reduce_prompt(mut self, reduce_prompt: &str) -> Self {
+        self.reduce_prompt = reduce_prompt.to_string();
+        self
+    }
+}
+
+impl<P1, P2> Op for SummarizeMapReduce<P1, P2>
+where
+    P1: Prompt,
+    P2: Prompt,
+{
+    type Input = Vec<String>;
+    type Output = Result<String, PromptError>;
+
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        let chunks = input
+            .iter()
+            .flat_map(|document| chunk_text(document, self.chunk_size, self.chunk_overlap))
+            .collect::<Vec<_>>();
+
+        let mut summaries =
+            prompt_all(&self.mapper, &self.map_prompt, chunks, self.concurrency).await?;
+
+        while summaries.len() > 1 {
+            let groups = summaries
+                .chunks(self.fan_in)
+                .map(|group| group.join("\n\n"))
+                .collect();
+            summaries =
+                prompt_all(&self.reducer, &self.reduce_prompt, groups, self.concurrency).await?;
+        }
+
+        Ok(summaries.pop().unwrap_or_default())
+    }
+}
+
+/// Prompt the model with the template applied to each text, concurrently, in order.
+async fn prompt_all<P: Prompt>(
+    model: &P,
+    template: &str,
+    texts: Vec<String>,
+    concurrency: usize,
+) -> Result<Vec<String>, PromptError> {
+    let prompts = texts
+        .into_iter()
+        .map(|text| template.replace("{text}", &text))
+        .collect::<Vec<_>>();
+
+    stream::iter(prompts)
+        .map(|prompt| model.prompt(prompt))
+        .buffered(concurrency)
+        .try_collect()
+        .await
+}
+
+/// Create a new map-reduce summarization operation.
+///
+/// The op takes a list of documents, splits them into chunks, summarizes the chunks in parallel
+/// with `mapper` and then hierarchically combines the summaries with `reducer` (by groups of
+/// [fan_in](SummarizeMapReduce::fan_in) summaries) until a single summary is left.
+pub fn summarize_map_reduce<P1, P2>(mapper: P1, reducer: P2) -> SummarizeMapReduce<P1, P2>
+where
+    P1: Prompt,
+    P2: Prompt,
+{
+    SummarizeMapReduce {
+        mapper,
+        reducer,
+        chunk_size: 8_000,
+        chunk_overlap: 200,
+        fan_in: 5,
+        concurrency: 4,
+        map_prompt:
+            "Write a concise summary of the following text, keeping the key facts:\n\n{text}"
+                .to_string(),
+        reduce_prompt: "The following are summaries of parts of a set of documents. \
+            Combine them into a single concise summary, keeping the key facts:\n\n{text}"
+            .to_string(),
+    }
+}
+
+/// Split a text into chunks of at most `size` characters, preferably at paragraph, line or
+/// word boundaries, with consecutive chunks sharing (about) `overlap` characters.
+pub fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars = text.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+    let byte = |char_index: usize| chars.get(char_index).copied().unwrap_or(text.len());
+
+    let mut chunks = vec![];
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+
+        if end < chars.len() {
+            // Cut at the last boundary of the second half of the window, if any
+            let window = &text[byte(start + size / 2)..byte(end)];
+            let cut = ["\n\n", "\n", " "]
+                .iter()
+                .find_map(|separator| window.rfind(separator).map(|i| i + separator.len()));
+            if let Some(cut) = cut {
+                end = start + size / 2 + window[..cut].chars().count();
+            }
+        }
+
+        let chunk = text[byte(start)..byte(end)].trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+
+        if end == chars.len() {
+            break;
+        }
+        let previous_start = start;
+        start = end.saturating_sub(overlap).max(start + 1);
+        if overlap > 0 {
+            // Start the overlap at the beginning of a word
+            let is_space = |i: usize| text[byte(i)..].starts_with(char::is_whitespace);
+            while start > previous_start + 1 && !is_space(start - 1) {
+                start -= 1;
+            }
+        }
+    }
+
+    chunks
+}
+
+/// Split a text into chunks of at most `max_tokens` tokens, as counted by `counter`, with
+/// consecutive chunks sharing (about) `overlap` tokens. See [chunk_text].
+pub fn chunk_text_tokens<C: TokenCounter + ?Sized>(
+    text: &str,
+    counter: &C,
+    max_tokens: usize,
+    overlap: usize,
+) -> Vec<String> {
+    // Chunk by characters, with the average number of characters per token of the text,
+    // shrinking the chunks until they all fit
+    let mut chars_per_token =
+        text.chars().count() as f64 / counter.count_tokens(text).max(1) as f64;
+    loop {
+        let size = ((max_tokens as f64 * chars_per_token) as usize).max(1);
+        let chunks = chunk_text(text, size, (overlap as f64 * chars_per_token) as usize);
+        if size == 1
+            || chunks
+                .iter()
+                .all(|chunk| counter.count_tokens(chunk) <= max_tokens)
+        {
+            return chunks;
+        }
+        chars_per_token *= 0.9;
+    }
+}
+
+/// A plan to complete a task, as a list of steps.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct Plan {
+    /// The steps of the plan, in the order they must be executed
+    pub steps: Vec<PlanStep>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct PlanStep {
+    /// What must be done in this step, as a self-contained instruction
+    pub task: String,
+}
+
+/// The outcome of a step of a plan.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StepResult {
+    pub step: PlanStep,
+    pub output: String,
+}
+
+/// The outcome of a plan-and-execute run.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PlanExecution {
+    pub objective: String,
+    pub plan: Plan,
+    pub results: Vec<StepResult>,
+    /// The final answer, written from the results of the steps
+    pub answer: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlanAndExecuteError {
+    #[error("PlanningError: {0}")]
+    PlanningError(#[from] ExtractionError),
+
+    #[error("Step {step} failed: {error}")]
+    StepError { step: usize, error: PromptError },
+
+    #[error("AggregationError: {0}")]
+    AggregationError(PromptError),
+}
+
+/// Plan-and-execute orchestration, see [plan_and_execute].
+pub struct PlanAndExecute<M: CompletionModel, P> {
+    planner: Extractor<M, Plan>,
+    worker: P,
+    max_steps: usize,
+}
+
+impl<M: CompletionModel, P> PlanAndExecute<M, P> {
+    /// Set the maximum number of steps executed. Extra steps of the plan are dropped
+    /// (defaults to 10).
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+}
+
+impl<M, P> Op for PlanAndExecute<M, P>
+where
+    M: CompletionModel,
+    P: Prompt,
+{
+    type Input = String;
+    type Output = Result<PlanExecution, PlanAndExecuteError>;
+
+    async fn call(&self, objective: Self::Input) -> Self::Output {
+        let mut plan = self.planner.extract(&objective).await?;
+        plan.steps.truncate(self.max_steps);
+
+        let mut results: Vec<StepResult> = vec![];
+        for (i, step) in plan.steps.iter().enumerate() {
+            let prompt = format!(
+                "Objective: {objective}\n\n{}Your task (step {} of {}): {}",
+                render_results(&results),
+                i + 1,
+                plan.steps.len(),
+                step.task
+            );
+
+            let output = self
+                .worker
+                .prompt(prompt)
+                .await
+                .map_err(|error| PlanAndExecuteError::StepError { step: i, error })?;
+            results.push(StepResult {
+                step: step.clone(),
+                output,
+            });
+        }
+
+        let answer = self
+            .worker
+            .prompt(format!(
+                "Objective: {objective}\n\n{}\
+                All the steps are done. Using their results, write the final answer to the objective.",
+                render_results(&results)
+            ))
+            .await
+            .map_err(PlanAndExecuteError::AggregationError)?;
+
+        Ok(PlanExecution {
+            objective,
+            plan,
+            results,
+            answer,
+        })
+    }
+}
+
+fn render_results(results: &[StepResult]) -> String {
+    if results.is_empty() {
+        return String::new();
+    }
+
+    let results = results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| format!("{}. {}\n{}", i + 1, result.step.task, result.output))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    format!("Results of the previous steps:\n{results}\n\n")
+}
+
+/// Create a new plan-and-execute operation.
+///
+/// The op takes an objective and asks the `planner` model to break it down into a [Plan]. Each
+/// step of the plan is then executed in order by the `worker` (typically an agent with tools),
+/// with the results of the previous steps as context. Finally, the worker writes the answer to
+/// the objective from the results of all the steps.
+///
+/// # Example
+/// ```rust
+/// use rig::{pipeline::{presets, Op}, providers::openai};
+///
+/// # async fn run() -> Result<(), rig::pipeline::presets::PlanAndExecuteError> {
+/// let openai = openai::Client::from_env();
+///
+/// let worker = openai
+///     .agent(openai::GPT_4O)
+///     .preamble("You are a research assistant.")
+///     // .tool(WebSearch)
+///     .build();
+///
+/// let execution = presets::plan_and_execute(openai.completion_model(openai::GPT_4O), worker)
+///     .max_steps(5)
+///     .call("Compare the population growth of Lisbon and Porto since 2000".to_string())
+///     .await?;
+///
+/// println!("{}", execution.answer);
+/// # Ok(())
+/// # }
+/// ```
+pub fn plan_and_execute<M, P>(planner: M, worker: P) -> PlanAndExecute<M, P>
+where
+    M: CompletionModel,
+    P: Prompt,
+{
+    let planner = ExtractorBuilder::new(planner)
+        .preamble(
+            "Break down the objective in the text into a short list of steps that, executed in \
+            order by an assistant with access to tools, complete the objective. Each step must be \
+            a self-contained instruction. Do not execute the steps.",
+        )
+        .build();
+
+    PlanAndExecute {
+        planner,
+        worker,
+        max_steps: 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{
+        message::{AssistantContent, Message, Text, UserContent},
+        test_utils,
+        tokens::Heuristic,
+    };
+
+    /// Responds with the number of `#` in the prompt, and counts its calls
+    #[derive(Default)]
+    struct MockModel(AtomicUsize);
+
+    impl Prompt for MockModel {
+        async fn prompt(&self, prompt: impl Into<Message>) -> Result<String, PromptError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let prompt = match prompt.into() {
+                Message::User { content, .. } => match content.first() {
+                    UserContent::Text(Text { text }) => text,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+            Ok("#".repeat(prompt.matches('#').count()))
+        }
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        let text = "aaaa bbbb\n\ncccc dddd eeee";
+
+        assert_eq!(
+            chunk_text(text, 12, 0),
+            vec!["aaaa bbbb", "cccc dddd", "eeee"]
+        );
+        assert_eq!(
+            chunk_text(text, 12, 5),
+            vec!["aaaa bbbb", "bbbb\n\ncccc", "cccc dddd", "dddd eeee"]
+        );
+        assert_eq!(chunk_text("ééééé", 2, 0), vec!["éé", "éé", "é"]);
+        assert!(chunk_text("", 10, 0).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_text_tokens() {
+        let text = "aaaa bbbb\n\ncccc dddd eeee";
+        let counter = Heuristic::default();
+
+        let chunks = chunk_text_tokens(text, &counter, 3, 0);
+        assert_eq!(chunks, vec!["aaaa bbbb", "cccc", "dddd eeee"]);
+        assert!(chunks.iter().all(|chunk| counter.count_tokens(chunk) <= 3));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_map_reduce() {
+        let summarizer = summarize_map_reduce(MockModel::default(), MockModel::default())
+            .chunk_size(2)
+            .chunk_overlap(0)
+            .fan_in(3)
+            .map_prompt("{text}")
+            .reduce_prompt("{text}");
+
+        // 7 chunks of 2 `#` are reduced in 3 groups, then 1
+        let summary = summarizer
+            .call(vec!["#".repeat(10), "#".repeat(4)])
+            .await
+            .unwrap();
+
+        assert_eq!(summary, "#".repeat(14));
+        assert_eq!(summarizer.mapper.0.load(Ordering::SeqCst), 7);
+        assert_eq!(summarizer.reducer.0.load(Ordering::SeqCst), 4);
+    }
+
+    /// Plans one step per line of the prompt
+    fn planner() -> test_utils::MockModel {
+        test_utils::MockModel::new(|request| {
+            let steps = request
+                .prompt
+                .rag_text()
+                .unwrap()
+                .lines()
+                .map(|task| serde_json::json!({ "task": task }))
+                .collect::<Vec<_>>();
+
+            Ok(test_utils::response(
+                AssistantContent::tool_call(
+                    "call_1",
+                    "submit",
+                    serde_json::json!({ "steps": steps }),
+                ),
+                (),
+            ))
+        })
+    }
+
+    /// Responds with the last line of the prompt
+    struct MockWorker;
+
+    impl Prompt for MockWorker {
+        async fn prompt(&self, prompt: impl Into<Message>) -> Result<String, PromptError> {
+            match prompt.into() {
+                Message::User { content, .. } => match content.first() {
+                    UserContent::Text(Text { text }) => Ok(text.lines().last().unwrap().into()),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_execute() {
+        let execution = plan_and_execute(planner(), MockWorker)
+            .max_steps(2)
+            .call("search\nsummarize\nignored".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            execution.plan.steps,
+            vec![
+                PlanStep {
+                    task: "search".into()
+                },
+                PlanStep {
+                    task: "summarize".into()
+                }
+            ]
+        );
+        assert_eq!(
+            execution
+                .results
+                .iter()
+                .map(|result| result.output.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Your task (step 1 of 2): search",
+                "Your task (step 2 of 2): summarize"
+            ]
+        );
+        assert!(execution.answer.starts_with("All the steps are done."));
+    }
+}
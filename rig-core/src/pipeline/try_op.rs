@@ -4,7 +4,10 @@ use futures::stream;
 #[allow(unused_imports)] // Needed since this is used in a macro rule
 use futures::try_join;
 
-use super::op::{self};
+use super::{
+    graph::OpGraph,
+    op::{self},
+};
 
 // ================================================================
 // Core TryOp trait
@@ -20,6 +23,11 @@ pub trait TryOp: Send + Sync {
         input: Self::Input,
     ) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send;
 
+    /// Get the graph of the ops of the current op, see [Op::graph](super::Op::graph).
+    fn try_graph(&self) -> OpGraph {
+        OpGraph::try_op::<Self>()
+    }
+
     /// Execute the current op with the given inputs. `n` is the number of concurrent
     /// inputs that will be processed concurrently.
     /// If the op fails for one of the inputs, the entire operation will fail and the error will
@@ -57,6 +65,68 @@ pub trait TryOp: Send + Sync {
         }
     }
 
+    /// Execute the current op with the given inputs, like [TryOp::try_batch_call], but without
+    /// aborting on the first error: the outcome of every input is returned, with the failures
+    /// attributed to their input (e.g.: for batch workloads where a few failures should not
+    /// lose the whole batch).
+    ///
+    /// # Example
+    /// ```rust
+    /// use rig::pipeline::{self, TryOp};
+    ///
+    /// # async fn run() {
+    /// let op = pipeline::new()
+    ///    .map(|x: i32| if x % 2 == 0 { Ok(x + 1) } else { Err("x is odd") });
+    ///
+    /// let results = op.try_batch_call_partial(2, vec![2, 3, 4]).await;
+    /// let (successes, failures) = results.into_parts();
+    /// assert_eq!(successes, vec![3, 5]);
+    /// assert_eq!((failures[0].index, failures[0].input), (1, 3));
+    /// # }
+    /// ```
+    fn try_batch_call_partial<I>(
+        &self,
+        n: usize,
+        input: I,
+    ) -> impl Future<Output = PartialResults<Self::Input, Self::Output, Self::Error>> + Send
+    where
+        I: IntoIterator<Item = Self::Input> + Send,
+        I::IntoIter: Send,
+        Self::Input: Clone,
+        Self: Sized,
+    {
+        use stream::StreamExt;
+
+        async move {
+            let results = stream::iter(input.into_iter().enumerate())
+                .map(|(index, input)| async move {
+                    self.try_call(input.clone())
+                        .await
+                        .map_err(|error| ItemError {
+                            index,
+                            input,
+                            error,
+                        })
+                })
+                .buffered(n)
+                .collect()
+                .await;
+
+            PartialResults { results }
+        }
+    }
+
+    /// Turn the current op into an op taking a batch of inputs and returning the outcome of
+    /// every input, see [TryOp::try_batch_call_partial]. `n` is the number of inputs processed
+    /// concurrently.
+    fn partial_batch(self, n: usize) -> PartialBatch<Self>
+    where
+        Self::Input: Clone,
+        Self: Sized,
+    {
+        PartialBatch { op: self, n }
+    }
+
     /// Map the success return value (i.e., `Ok`) of the current op to a different value
     /// using the provided closure.
     ///
@@ -201,6 +271,10 @@ where
     async fn try_call(&self, input: Self::Input) -> Result<Self::Output, Self::Error> {
         self.call(input).await
     }
+
+    fn try_graph(&self) -> OpGraph {
+        self.graph()
+    }
 }
 
 // ================================================================
@@ -232,6 +306,10 @@ where
             Err(err) => Err(err),
         }
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::sequence(self.prev.try_graph(), self.op.graph())
+    }
 }
 
 pub struct MapErr<Op1, Op2> {
@@ -261,6 +339,10 @@ where
             Err(err) => Err(self.op.call(err).await),
         }
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::sequence(self.prev.try_graph(), self.op.graph())
+    }
 }
 
 pub struct AndThen<Op1, Op2> {
@@ -287,6 +369,10 @@ where
         let output = self.prev.try_call(input).await?;
         self.op.try_call(output).await
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::sequence(self.prev.try_graph(), self.op.try_graph())
+    }
 }
 
 pub struct OrElse<Op1, Op2> {
@@ -315,6 +401,10 @@ where
             Err(err) => self.op.try_call(err).await,
         }
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::sequence(self.prev.try_graph(), self.op.try_graph())
+    }
 }
 
 pub struct TrySequential<Op1, Op2> {
@@ -343,6 +433,97 @@ where
             Err(err) => Err(err),
         }
     }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::sequence(self.prev.try_graph(), self.op.graph())
+    }
+}
+
+/// Failure of an input of a batch, see [TryOp::try_batch_call_partial].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemError<I, E> {
+    /// Position of the input in the batch
+    pub index: usize,
+    pub input: I,
+    pub error: E,
+}
+
+impl<I, E: std::fmt::Display> std::fmt::Display for ItemError<I, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Item {} failed: {}", self.index, self.error)
+    }
+}
+
+impl<I, E> std::error::Error for ItemError<I, E>
+where
+    I: std::fmt::Debug,
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Outcome of every input of a batch, in the order of the inputs, see
+/// [TryOp::try_batch_call_partial].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartialResults<I, T, E> {
+    pub results: Vec<Result<T, ItemError<I, E>>>,
+}
+
+impl<I, T, E> PartialResults<I, T, E> {
+    /// Whether all the inputs succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    pub fn successes(&self) -> impl Iterator<Item = &T> {
+        self.results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ItemError<I, E>> {
+        self.results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+    }
+
+    /// Split the outcomes into the outputs of the successful inputs and the failures.
+    pub fn into_parts(self) -> (Vec<T>, Vec<ItemError<I, E>>) {
+        let mut successes = vec![];
+        let mut failures = vec![];
+        for result in self.results {
+            match result {
+                Ok(output) => successes.push(output),
+                Err(error) => failures.push(error),
+            }
+        }
+        (successes, failures)
+    }
+}
+
+pub struct PartialBatch<Op> {
+    op: Op,
+    n: usize,
+}
+
+impl<Op> op::Op for PartialBatch<Op>
+where
+    Op: TryOp,
+    Op::Input: Clone,
+{
+    type Input = Vec<Op::Input>;
+    type Output = PartialResults<Op::Input, Op::Output, Op::Error>;
+
+    #[inline]
+    async fn call(&self, input: Self::Input) -> Self::Output {
+        self.op.try_batch_call_partial(self.n, input).await
+    }
+
+    fn graph(&self) -> OpGraph {
+        OpGraph::fork("partial_batch", vec![(None, self.op.try_graph())])
+    }
 }
 
 // TODO: Implement TryParallel
@@ -376,7 +557,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pipeline::op::{map, then};
+    use crate::pipeline::op::{map, then, Op};
 
     #[tokio::test]
     async fn test_try_op() {
@@ -385,6 +566,37 @@ mod tests {
         assert_eq!(result, 2);
     }
 
+    #[tokio::test]
+    async fn test_try_batch_call_partial() {
+        let op = map(|x: i32| {
+            if x % 2 == 0 {
+                Ok(x * 10)
+            } else {
+                Err("x is odd")
+            }
+        });
+
+        let results = op.try_batch_call_partial(2, vec![1, 2, 3, 4]).await;
+        assert!(!results.is_complete());
+        assert_eq!(results.successes().collect::<Vec<_>>(), vec![&20, &40]);
+        assert_eq!(
+            results.results[2],
+            Err(ItemError {
+                index: 2,
+                input: 3,
+                error: "x is odd"
+            })
+        );
+
+        let pipeline = map(|xs: Vec<i32>| xs.into_iter().map(|x| x + 1).collect::<Vec<_>>())
+            .chain(op.partial_batch(2))
+            .map(|results| results.into_parts());
+        let (successes, failures) = pipeline.call(vec![1, 2, 3]).await;
+        assert_eq!(successes, vec![20, 40]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].to_string(), "Item 1 failed: x is odd");
+    }
+
     #[tokio::test]
     async fn test_map_ok_constructor() {
         let op1 = map(|x: i32| if x % 2 == 0 { Ok(x) } else { Err("x is odd") });
@@ -0,0 +1,320 @@
+//! This module provides post-processors of the text responses of agents, applied (in order)
+//! before the responses are returned by [Prompt](crate::completion::Prompt) and
+//! [Chat](crate::completion::Chat), and thus before they are stored in the chat history.
+//!
+//! A [ResponsePostProcessor] either rewrites the response (e.g.: [StripMarkdown], [MaxLength],
+//! [ProfanityFilter], [RegexRewrite]) or reports a violation (e.g.: [ReaskOnViolation]), in
+//! which case the agent asks the model to answer again, with the instruction of the violation.
+//! If the response still violates the rule after the maximum number of re-asks (see
+//! [AgentBuilder::max_reasks](crate::agent::AgentBuilder::max_reasks)), a warning is logged and
+//! the response goes through the remaining post-processors.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     post_processors::{MaxLength, ProfanityFilter, ReaskOnViolation, StripMarkdown},
+//!     providers::openai,
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//!
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .preamble("You are the voice assistant of ACME Inc.")
+//!     .post_processor(ReaskOnViolation::new(|response| {
+//!         (response.len() > 500).then(|| "Answer in less than 500 characters.".to_string())
+//!     }))
+//!     .post_processor(StripMarkdown)
+//!     .post_processor(ProfanityFilter::new(["darn", "heck"]))
+//!     .post_processor(MaxLength::new(500))
+//!     .build();
+//! # }
+//! ```
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// The result of a [ResponsePostProcessor].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostProcessed {
+    /// The (possibly rewritten) response
+    Text(String),
+    /// The response violates a rule: the model should be asked to answer again, with the given
+    /// instruction
+    Violation {
+        response: String,
+        instruction: String,
+    },
+}
+
+/// A post-processor of the text responses of an agent.
+pub trait ResponsePostProcessor: Send + Sync {
+    fn process(&self, response: String) -> PostProcessed;
+}
+
+/// Removes the markdown formatting of the responses (headings, emphasis, code spans and fences,
+/// blockquotes, links and images), e.g.: for text-to-speech or plain text channels.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StripMarkdown;
+
+impl StripMarkdown {
+    pub fn strip(text: &str) -> String {
+        static RULES: OnceLock<Vec<(Regex, &str)>> = OnceLock::new();
+        let rules = RULES.get_or_init(|| {
+            [
+                // Code fences (the code is kept)
+                (r"(?m)^[ \t]*(```|~~~).*\n?", ""),
+                // Headings, blockquotes and horizontal rules
+                (r"(?m)^[ \t]{0,3}#{1,6}[ \t]+", ""),
+                (r"(?m)^[ \t]*>[ \t]?", ""),
+                (r"(?m)^[ \t]*([-*_][ \t]*){3,}$\n?", ""),
+                // Images and links are replaced by their text
+                (r"!\[([^\]]*)\]\([^)]*\)", "$1"),
+                (r"\[([^\]]*)\]\([^)]*\)", "$1"),
+                // Emphasis and code spans
+                (r"(\*\*|__)(\S(?:.*?\S)?)(\*\*|__)", "$2"),
+                (r"(^|[^\w*])[*_](\S(?:.*?\S)?)[*_]($|[^\w*])", "$1$2$3"),
+                (r"~~(\S(?:.*?\S)?)~~", "$1"),
+                (r"`([^`]*)`", "$1"),
+            ]
+            .into_iter()
+            .map(|(pattern, replacement)| (Regex::new(pattern).expect("Valid regex"), replacement))
+            .collect()
+        });
+
+        let text = rules
+            .iter()
+            .fold(text.to_string(), |text, (pattern, replacement)| {
+                pattern.replace_all(&text, *replacement).into_owned()
+            });
+        text.trim().to_string()
+    }
+}
+
+impl ResponsePostProcessor for StripMarkdown {
+    fn process(&self, response: String) -> PostProcessed {
+        PostProcessed::Text(Self::strip(&response))
+    }
+}
+
+/// Truncates the responses longer than the maximum number of characters, at a word boundary
+/// if possible, and appends an ellipsis (`…` by default).
+#[derive(Clone, Debug)]
+pub struct MaxLength {
+    max_chars: usize,
+    ellipsis: String,
+}
+
+impl MaxLength {
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            ellipsis: "…".to_string(),
+        }
+    }
+
+    /// Set the text appended to the truncated responses (counted in the maximum length).
+    pub fn ellipsis(mut self, ellipsis: &str) -> Self {
+        self.ellipsis = ellipsis.to_string();
+        self
+    }
+}
+
+impl ResponsePostProcessor for MaxLength {
+    fn process(&self, response: String) -> PostProcessed {
+        if response.chars().count() <= self.max_chars {
+            return PostProcessed::Text(response);
+        }
+
+        let max_chars = self.max_chars.saturating_sub(self.ellipsis.chars().count());
+        let end = response
+            .char_indices()
+            .nth(max_chars)
+            .map_or(response.len(), |(i, _)| i);
+        let truncated = &response[..end];
+
+        // Cut at the last word boundary, if any
+        let ends_word = response[end..].starts_with(char::is_whitespace);
+        let truncated = match truncated.rfind(char::is_whitespace) {
+            Some(i) if !ends_word && i > 0 => &truncated[..i],
+            _ => truncated,
+        };
+        PostProcessed::Text(format!("{}{}", truncated.trim_end(), self.ellipsis))
+    }
+}
+
+/// Masks the given words in the responses (case insensitively, whole words only) with `*`.
+#[derive(Clone, Debug)]
+pub struct ProfanityFilter {
+    pattern: Option<Regex>,
+}
+
+impl ProfanityFilter {
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> Self {
+        let words = words
+            .into_iter()
+            .map(|word| regex::escape(word.as_ref()))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        Self {
+            pattern: (!words.is_empty()).then(|| {
+                Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|"))).expect("Valid regex")
+            }),
+        }
+    }
+
+    /// Whether the text contains one of the words (e.g.: to re-ask with [ReaskOnViolation]).
+    pub fn is_profane(&self, text: &str) -> bool {
+        self.pattern
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_match(text))
+    }
+}
+
+impl ResponsePostProcessor for ProfanityFilter {
+    fn process(&self, response: String) -> PostProcessed {
+        match &self.pattern {
+            Some(pattern) => PostProcessed::Text(
+                pattern
+                    .replace_all(&response, |captures: &regex::Captures| {
+                        "*".repeat(captures[0].chars().count())
+                    })
+                    .into_owned(),
+            ),
+            None => PostProcessed::Text(response),
+        }
+    }
+}
+
+/// Replaces the matches of a regular expression in the responses (see [Regex::replace_all] for
+/// the syntax of the replacement).
+#[derive(Clone, Debug)]
+pub struct RegexRewrite {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl RegexRewrite {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+impl ResponsePostProcessor for RegexRewrite {
+    fn process(&self, response: String) -> PostProcessed {
+        PostProcessed::Text(
+            self.pattern
+                .replace_all(&response, self.replacement.as_str())
+                .into_owned(),
+        )
+    }
+}
+
+/// Checks the responses with a function returning the instruction sent to the model when a
+/// response violates a rule (e.g.: "Answer in less than 500 characters."), and `None` otherwise.
+pub struct ReaskOnViolation {
+    check: CheckFn,
+}
+
+type CheckFn = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+impl ReaskOnViolation {
+    pub fn new(check: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> Self {
+        Self {
+            check: Box::new(check),
+        }
+    }
+}
+
+impl ResponsePostProcessor for ReaskOnViolation {
+    fn process(&self, response: String) -> PostProcessed {
+        match (self.check)(&response) {
+            Some(instruction) => PostProcessed::Violation {
+                response,
+                instruction,
+            },
+            None => PostProcessed::Text(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(processed: PostProcessed) -> String {
+        match processed {
+            PostProcessed::Text(text) => text,
+            PostProcessed::Violation { .. } => panic!("Unexpected violation"),
+        }
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        let markdown = "# Title\n\nSome **bold**, _italic_ and `code` with a \
+                        [link](https://example.com).\n\n> Quote\n\n```rust\nlet x = 1;\n```\n\
+                        - snake_case_name * 2";
+
+        assert_eq!(
+            text(StripMarkdown.process(markdown.to_string())),
+            "Title\n\nSome bold, italic and code with a link.\n\nQuote\n\nlet x = 1;\n\
+             - snake_case_name * 2"
+        );
+    }
+
+    #[test]
+    fn test_max_length() {
+        let response = "The weather is nice and the sun is shining.".to_string();
+
+        assert_eq!(
+            text(MaxLength::new(100).process(response.clone())),
+            response
+        );
+        assert_eq!(
+            text(MaxLength::new(20).process(response.clone())),
+            "The weather is nice…"
+        );
+        assert_eq!(
+            text(MaxLength::new(10).ellipsis("...").process(response)),
+            "The..."
+        );
+    }
+
+    #[test]
+    fn test_profanity_filter() {
+        let filter = ProfanityFilter::new(["darn"]);
+
+        assert!(filter.is_profane("Darn it!"));
+        assert!(!filter.is_profane("Darning socks"));
+        assert_eq!(
+            text(filter.process("Darn it! Darning socks is darn hard.".to_string())),
+            "**** it! Darning socks is **** hard."
+        );
+    }
+
+    #[test]
+    fn test_regex_rewrite_and_reask() {
+        let rewrite =
+            RegexRewrite::new(r"\b(\d{4})-\d{4}-\d{4}-(\d{4})\b", "$1-****-****-$2").unwrap();
+        assert_eq!(
+            text(rewrite.process("Card: 1234-5678-9012-3456".to_string())),
+            "Card: 1234-****-****-3456"
+        );
+
+        let reask =
+            ReaskOnViolation::new(|response| (response.len() > 5).then(|| "Be brief.".to_string()));
+        assert_eq!(
+            reask.process("Hello there".to_string()),
+            PostProcessed::Violation {
+                response: "Hello there".to_string(),
+                instruction: "Be brief.".to_string()
+            }
+        );
+        assert_eq!(text(reask.process("Hi".to_string())), "Hi");
+    }
+}
@@ -3,7 +3,7 @@
 use std::{convert::Infallible, str::FromStr};
 
 use crate::{
-    completion::{self, CompletionError},
+    completion::{self, roles::RoleMapping, CompletionError},
     json_utils,
     message::{self, MessageError},
     one_or_many::string_or_one_or_many,
@@ -13,7 +13,7 @@ use crate::{
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use super::client::Client;
+use super::{client::Client, computer_use::BuiltinTool};
 
 // ================================================================
 // Anthropic Completion API
@@ -92,6 +92,23 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
     type Error = CompletionError;
 
     fn try_from(response: CompletionResponse) -> Result<Self, Self::Error> {
+        if response.stop_reason.as_deref() == Some("refusal") {
+            let text = response
+                .content
+                .iter()
+                .filter_map(|content| match content {
+                    Content::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<String>();
+            return Err(CompletionError::Refused {
+                reason: match text.is_empty() {
+                    true => "The model declined to respond (stop reason: refusal)".to_string(),
+                    false => text,
+                },
+            });
+        }
+
         let content = response
             .content
             .iter()
@@ -118,6 +135,14 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            annotations: vec![],
+            alternatives: vec![],
+            finish_reason: response.stop_reason.as_deref().map(|reason| match reason {
+                "end_turn" | "stop_sequence" => completion::FinishReason::Stop,
+                "max_tokens" => completion::FinishReason::Length,
+                "tool_use" => completion::FinishReason::ToolCalls,
+                other => completion::FinishReason::Other(other.to_string()),
+            }),
             raw_response: response,
         })
     }
@@ -308,7 +333,7 @@ impl TryFrom<message::Message> for Message {
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         Ok(match message {
-            message::Message::User { content } => Message {
+            message::Message::User { content, .. } => Message {
                 role: Role::User,
                 content: content.try_map(|content| match content {
                     message::UserContent::Text(message::Text { text }) => {
@@ -380,7 +405,7 @@ impl TryFrom<message::Message> for Message {
                 })?,
             },
 
-            message::Message::Assistant { content } => Message {
+            message::Message::Assistant { content, .. } => Message {
                 content: content.map(|content| content.into()),
                 role: Role::Assistant,
             },
@@ -460,10 +485,12 @@ impl TryFrom<Message> for message::Message {
                         }
                     })
                 })?,
+                name: None,
             },
             Role::Assistant => match message.content.first() {
                 Content::Text { .. } | Content::ToolUse { .. } => message::Message::Assistant {
                     content: message.content.try_map(|content| content.try_into())?,
+                    name: None,
                 },
 
                 _ => {
@@ -481,6 +508,8 @@ pub struct CompletionModel {
     pub(crate) client: Client,
     pub model: String,
     pub default_max_tokens: Option<u64>,
+    /// Anthropic built-in tools made available to the model on every request.
+    pub builtin_tools: Vec<BuiltinTool>,
 }
 
 impl CompletionModel {
@@ -489,52 +518,60 @@ impl CompletionModel {
             client,
             model: model.to_string(),
             default_max_tokens: calculate_max_tokens(model),
+            builtin_tools: vec![],
         }
     }
-}
 
-/// Anthropic requires a `max_tokens` parameter to be set, which is dependent on the model. If not
-/// set or if set too high, the request will fail. The following values are based on the models
-/// available at the time of writing.
-///
-/// Dev Note: This is really bad design, I'm not sure why they did it like this..
-fn calculate_max_tokens(model: &str) -> Option<u64> {
-    if model.starts_with("claude-3-5-sonnet") || model.starts_with("claude-3-5-haiku") {
-        Some(8192)
-    } else if model.starts_with("claude-3-opus")
-        || model.starts_with("claude-3-sonnet")
-        || model.starts_with("claude-3-haiku")
-    {
-        Some(4096)
-    } else {
-        None
+    /// Make an Anthropic built-in tool (e.g.: computer use) available to the model.
+    /// See [computer_use](super::computer_use) for more information.
+    pub fn with_builtin_tool(mut self, tool: BuiltinTool) -> Self {
+        self.builtin_tools.push(tool);
+        self
     }
-}
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Metadata {
-    user_id: Option<String>,
-}
+    /// Build the `tools` and `tool_choice` fields of a request from the tools of the completion
+    /// request and the built-in tools of the model. Tools sharing their name with a built-in
+    /// tool are sent as the built-in tool.
+    pub(crate) fn tools_json(
+        &self,
+        tools: Vec<completion::ToolDefinition>,
+    ) -> Result<Option<serde_json::Value>, CompletionError> {
+        let mut definitions = self
+            .builtin_tools
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
 
-#[derive(Default, Debug, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum ToolChoice {
-    #[default]
-    Auto,
-    Any,
-    Tool {
-        name: String,
-    },
-}
+        for tool in tools {
+            if self
+                .builtin_tools
+                .iter()
+                .any(|builtin| builtin.name() == tool.name)
+            {
+                continue;
+            }
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
+            definitions.push(serde_json::to_value(ToolDefinition {
+                name: tool.name,
+                description: Some(tool.description),
+                input_schema: tool.parameters,
+            })?);
+        }
 
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+        if definitions.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(json!({
+            "tools": definitions,
+            "tool_choice": ToolChoice::Auto,
+        })))
+    }
+
+    pub(crate) fn create_completion_request(
         &self,
-        completion_request: completion::CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        mut completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
         // Note: Ideally we'd introduce provider-specific Request models to handle the
         // specific requirements of each provider. For now, we just manually check while
         // building the request as a raw JSON document.
@@ -550,6 +587,9 @@ impl completion::CompletionModel for CompletionModel {
             ));
         };
 
+        // Anthropic requires the first message to be a user message
+        completion_request.normalize_roles(RoleMapping::NONE.leading_user(true));
+
         let prompt_message: Message = completion_request
             .prompt_with_context()
             .try_into()
@@ -578,33 +618,67 @@ impl completion::CompletionModel for CompletionModel {
             json_utils::merge_inplace(&mut request, json!({ "temperature": temperature }));
         }
 
-        if !completion_request.tools.is_empty() {
-            json_utils::merge_inplace(
-                &mut request,
-                json!({
-                    "tools": completion_request
-                        .tools
-                        .into_iter()
-                        .map(|tool| ToolDefinition {
-                            name: tool.name,
-                            description: Some(tool.description),
-                            input_schema: tool.parameters,
-                        })
-                        .collect::<Vec<_>>(),
-                    "tool_choice": ToolChoice::Auto,
-                }),
-            );
+        if let Some(tools) = self.tools_json(completion_request.tools)? {
+            json_utils::merge_inplace(&mut request, tools);
         }
 
         if let Some(ref params) = completion_request.additional_params {
             json_utils::merge_inplace(&mut request, params.clone())
         }
 
+        Ok(request)
+    }
+}
+
+/// Anthropic requires a `max_tokens` parameter to be set, which is dependent on the model. If not
+/// set or if set too high, the request will fail. The following values are based on the models
+/// available at the time of writing.
+///
+/// Dev Note: This is really bad design, I'm not sure why they did it like this..
+fn calculate_max_tokens(model: &str) -> Option<u64> {
+    if model.starts_with("claude-3-5-sonnet") || model.starts_with("claude-3-5-haiku") {
+        Some(8192)
+    } else if model.starts_with("claude-3-opus")
+        || model.starts_with("claude-3-sonnet")
+        || model.starts_with("claude-3-haiku")
+    {
+        Some(4096)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Metadata {
+    user_id: Option<String>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    #[default]
+    Auto,
+    Any,
+    Tool {
+        name: String,
+    },
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
         tracing::debug!("Anthropic completion request: {request}");
 
-        let response = self
-            .client
-            .post("/v1/messages")
+        let response = request_options
+            .apply(self.client.post("/v1/messages"))
             .json(&request)
             .send()
             .await?;
@@ -624,6 +698,13 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -865,7 +946,7 @@ mod tests {
         let converted_tool_message: message::Message = tool_message.clone().try_into().unwrap();
 
         match converted_user_message.clone() {
-            message::Message::User { content } => {
+            message::Message::User { content, .. } => {
                 assert_eq!(content.len(), 3);
 
                 let mut iter = content.into_iter();
@@ -910,7 +991,7 @@ mod tests {
         }
 
         match converted_tool_message.clone() {
-            message::Message::User { content } => {
+            message::Message::User { content, .. } => {
                 let message::ToolResult { id, content, .. } = match content.first() {
                     message::UserContent::ToolResult(tool_result) => tool_result,
                     _ => panic!("Expected tool result content"),
@@ -927,7 +1008,7 @@ mod tests {
         }
 
         match converted_assistant_message.clone() {
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, .. } => {
                 assert_eq!(content.len(), 1);
 
                 match content.first() {
@@ -950,4 +1031,95 @@ mod tests {
         assert_eq!(assistant_message, original_assistant_message);
         assert_eq!(tool_message, original_tool_message);
     }
+
+    #[test]
+    fn test_builtin_tools_replace_custom_tools() {
+        let model = crate::providers::anthropic::ClientBuilder::new("key")
+            .build()
+            .completion_model(CLAUDE_3_5_SONNET)
+            .with_builtin_tool(BuiltinTool::bash());
+
+        let tools = model
+            .tools_json(vec![
+                completion::ToolDefinition {
+                    name: "bash".to_string(),
+                    description: "Run a command".to_string(),
+                    parameters: json!({"type": "object"}),
+                },
+                completion::ToolDefinition {
+                    name: "add".to_string(),
+                    description: "Add x and y".to_string(),
+                    parameters: json!({"type": "object"}),
+                },
+            ])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            tools["tools"],
+            json!([
+                {"type": "bash_20250124", "name": "bash"},
+                {"name": "add", "description": "Add x and y", "input_schema": {"type": "object"}},
+            ])
+        );
+        assert_eq!(
+            CompletionModel::new(
+                crate::providers::anthropic::ClientBuilder::new("key").build(),
+                "model"
+            )
+            .tools_json(vec![])
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_refusal() {
+        let response: CompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-7-sonnet-latest",
+            "content": [],
+            "stop_reason": "refusal",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 10, "output_tokens": 0}
+        }))
+        .unwrap();
+
+        let result: Result<completion::CompletionResponse<_>, _> = response.try_into();
+        assert!(matches!(result, Err(CompletionError::Refused { .. })));
+    }
+
+    #[test]
+    fn test_finish_reason() {
+        let response = |stop_reason: &str| -> completion::CompletionResponse<_> {
+            serde_json::from_value::<CompletionResponse>(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-7-sonnet-latest",
+                "content": [{"type": "text", "text": "Once upon a time"}],
+                "stop_reason": stop_reason,
+                "stop_sequence": null,
+                "usage": {"input_tokens": 10, "output_tokens": 4}
+            }))
+            .unwrap()
+            .try_into()
+            .unwrap()
+        };
+
+        assert_eq!(
+            response("end_turn").finish_reason,
+            Some(completion::FinishReason::Stop)
+        );
+        assert_eq!(
+            response("max_tokens").finish_reason,
+            Some(completion::FinishReason::Length)
+        );
+        assert_eq!(
+            response("pause_turn").finish_reason,
+            Some(completion::FinishReason::Other("pause_turn".to_string()))
+        );
+    }
 }
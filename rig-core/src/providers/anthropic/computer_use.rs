@@ -0,0 +1,356 @@
+//! Typed support for Anthropic's built-in (computer use) tools.
+//!
+//! Built-in tools are defined by Anthropic: the model knows their schema and only needs to
+//! be told which ones are available. They are added to a model with
+//! [CompletionModel::with_builtin_tool](super::completion::CompletionModel::with_builtin_tool),
+//! and the corresponding beta must be enabled on the client (see [BuiltinTool::beta]).
+//!
+//! The tool calls made by the model can be parsed with [BuiltinToolCall::parse]. They can also
+//! be executed by a regular rig [Tool](crate::tool::Tool) with the same name (e.g.: `"bash"`)
+//! whose `Args` type is one of [ComputerAction], [BashCommand] or [TextEditorCommand]; the
+//! definition of such a tool is replaced by the built-in definition when sent to Anthropic.
+//!
+//! # Example
+//! ```
+//! use rig::providers::anthropic::{self, computer_use::{BuiltinTool, ToolVersion}};
+//!
+//! let client = anthropic::ClientBuilder::new("YOUR_API_KEY")
+//!     .anthropic_beta(ToolVersion::V20250124.beta())
+//!     .build();
+//!
+//! let model = client
+//!     .completion_model(anthropic::CLAUDE_3_5_SONNET)
+//!     .with_builtin_tool(BuiltinTool::computer(1024, 768))
+//!     .with_builtin_tool(BuiltinTool::bash())
+//!     .with_builtin_tool(BuiltinTool::text_editor());
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Version of the built-in tools. Each version requires its own beta header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolVersion {
+    /// Tools released with Claude 3.5 Sonnet (new).
+    V20241022,
+    /// Tools released with Claude 3.7 Sonnet, adding new computer actions.
+    #[default]
+    V20250124,
+}
+
+impl ToolVersion {
+    /// Suffix of the tool types for this version (e.g.: `20250124` in `bash_20250124`).
+    fn suffix(&self) -> &'static str {
+        match self {
+            ToolVersion::V20241022 => "20241022",
+            ToolVersion::V20250124 => "20250124",
+        }
+    }
+
+    /// The `anthropic-beta` header value required to use the tools of this version.
+    pub fn beta(&self) -> &'static str {
+        match self {
+            ToolVersion::V20241022 => "computer-use-2024-10-22",
+            ToolVersion::V20250124 => "computer-use-2025-01-24",
+        }
+    }
+}
+
+/// An Anthropic built-in tool definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuiltinTool {
+    /// Lets the model take screenshots and control the mouse and keyboard of a display.
+    Computer {
+        version: ToolVersion,
+        display_width_px: u32,
+        display_height_px: u32,
+        /// X11 display number, for environments with several displays.
+        display_number: Option<u32>,
+    },
+    /// Lets the model run commands in a persistent bash session.
+    Bash { version: ToolVersion },
+    /// Lets the model view, create and edit text files.
+    TextEditor { version: ToolVersion },
+}
+
+impl BuiltinTool {
+    /// Computer tool for a display of the given size, using the latest tool version.
+    pub fn computer(display_width_px: u32, display_height_px: u32) -> Self {
+        Self::Computer {
+            version: ToolVersion::default(),
+            display_width_px,
+            display_height_px,
+            display_number: None,
+        }
+    }
+
+    /// Bash tool, using the latest tool version.
+    pub fn bash() -> Self {
+        Self::Bash {
+            version: ToolVersion::default(),
+        }
+    }
+
+    /// Text editor tool, using the latest tool version.
+    pub fn text_editor() -> Self {
+        Self::TextEditor {
+            version: ToolVersion::default(),
+        }
+    }
+
+    /// Use the given version of the tool.
+    pub fn version(mut self, tool_version: ToolVersion) -> Self {
+        match &mut self {
+            Self::Computer { version, .. }
+            | Self::Bash { version }
+            | Self::TextEditor { version } => *version = tool_version,
+        }
+        self
+    }
+
+    /// The name under which the model calls the tool.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Computer { .. } => "computer",
+            Self::Bash { .. } => "bash",
+            Self::TextEditor { .. } => "str_replace_editor",
+        }
+    }
+
+    /// The `anthropic-beta` header value required to use the tool.
+    pub fn beta(&self) -> &'static str {
+        match self {
+            Self::Computer { version, .. }
+            | Self::Bash { version }
+            | Self::TextEditor { version } => version.beta(),
+        }
+    }
+}
+
+impl Serialize for BuiltinTool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let definition = match self {
+            Self::Computer {
+                version,
+                display_width_px,
+                display_height_px,
+                display_number,
+            } => {
+                let mut definition = json!({
+                    "type": format!("computer_{}", version.suffix()),
+                    "name": self.name(),
+                    "display_width_px": display_width_px,
+                    "display_height_px": display_height_px,
+                });
+                if let Some(display_number) = display_number {
+                    definition["display_number"] = json!(display_number);
+                }
+                definition
+            }
+            Self::Bash { version } => json!({
+                "type": format!("bash_{}", version.suffix()),
+                "name": self.name(),
+            }),
+            Self::TextEditor { version } => json!({
+                "type": format!("text_editor_{}", version.suffix()),
+                "name": self.name(),
+            }),
+        };
+
+        definition.serialize(serializer)
+    }
+}
+
+/// Input of a call to the computer tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ComputerAction {
+    Key {
+        text: String,
+    },
+    Type {
+        text: String,
+    },
+    MouseMove {
+        coordinate: [i64; 2],
+    },
+    LeftClick {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coordinate: Option<[i64; 2]>,
+        /// Keys to hold down during the click (20250124 tools only).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    LeftClickDrag {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_coordinate: Option<[i64; 2]>,
+        coordinate: [i64; 2],
+    },
+    RightClick {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coordinate: Option<[i64; 2]>,
+    },
+    MiddleClick {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coordinate: Option<[i64; 2]>,
+    },
+    DoubleClick {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coordinate: Option<[i64; 2]>,
+    },
+    TripleClick {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coordinate: Option<[i64; 2]>,
+    },
+    LeftMouseDown,
+    LeftMouseUp,
+    Scroll {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coordinate: Option<[i64; 2]>,
+        scroll_direction: ScrollDirection,
+        scroll_amount: u32,
+    },
+    HoldKey {
+        text: String,
+        duration: f64,
+    },
+    Wait {
+        duration: f64,
+    },
+    Screenshot,
+    CursorPosition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Input of a call to the bash tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BashCommand {
+    /// The command to run. Not set when `restart` is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Whether the bash session should be restarted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<bool>,
+}
+
+/// Input of a call to the text editor tool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum TextEditorCommand {
+    View {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        view_range: Option<[i64; 2]>,
+    },
+    Create {
+        path: String,
+        file_text: String,
+    },
+    StrReplace {
+        path: String,
+        old_str: String,
+        #[serde(default)]
+        new_str: String,
+    },
+    Insert {
+        path: String,
+        insert_line: u64,
+        new_str: String,
+    },
+    UndoEdit {
+        path: String,
+    },
+}
+
+/// A call to one of the built-in tools, as returned by the model in a tool call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuiltinToolCall {
+    Computer(ComputerAction),
+    Bash(BashCommand),
+    TextEditor(TextEditorCommand),
+}
+
+impl BuiltinToolCall {
+    /// Parse the input of a tool call made by the model.
+    /// Returns `Ok(None)` if `name` is not the name of a built-in tool.
+    pub fn parse(name: &str, input: serde_json::Value) -> Result<Option<Self>, serde_json::Error> {
+        Ok(Some(match name {
+            "computer" => Self::Computer(serde_json::from_value(input)?),
+            "bash" => Self::Bash(serde_json::from_value(input)?),
+            "str_replace_editor" => Self::TextEditor(serde_json::from_value(input)?),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_builtin_tool_definitions() {
+        assert_eq!(
+            serde_json::to_value(BuiltinTool::computer(1024, 768)).unwrap(),
+            json!({
+                "type": "computer_20250124",
+                "name": "computer",
+                "display_width_px": 1024,
+                "display_height_px": 768,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(BuiltinTool::text_editor().version(ToolVersion::V20241022))
+                .unwrap(),
+            json!({"type": "text_editor_20241022", "name": "str_replace_editor"})
+        );
+        assert_eq!(BuiltinTool::bash().beta(), "computer-use-2025-01-24");
+    }
+
+    #[test]
+    fn test_parse_builtin_tool_calls() {
+        assert_eq!(
+            BuiltinToolCall::parse(
+                "computer",
+                json!({"action": "left_click", "coordinate": [10, 20]})
+            )
+            .unwrap(),
+            Some(BuiltinToolCall::Computer(ComputerAction::LeftClick {
+                coordinate: Some([10, 20]),
+                text: None
+            }))
+        );
+        assert_eq!(
+            BuiltinToolCall::parse(
+                "str_replace_editor",
+                json!({"command": "str_replace", "path": "/a.txt", "old_str": "a", "new_str": "b"})
+            )
+            .unwrap(),
+            Some(BuiltinToolCall::TextEditor(TextEditorCommand::StrReplace {
+                path: "/a.txt".to_string(),
+                old_str: "a".to_string(),
+                new_str: "b".to_string(),
+            }))
+        );
+        assert_eq!(
+            BuiltinToolCall::parse("bash", json!({"restart": true})).unwrap(),
+            Some(BuiltinToolCall::Bash(BashCommand {
+                command: None,
+                restart: Some(true)
+            }))
+        );
+        assert_eq!(
+            BuiltinToolCall::parse("get_weather", json!({})).unwrap(),
+            None
+        );
+    }
+}
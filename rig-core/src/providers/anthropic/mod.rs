@@ -11,6 +11,7 @@
 
 pub mod client;
 pub mod completion;
+pub mod computer_use;
 pub mod streaming;
 
 pub use client::{Client, ClientBuilder};
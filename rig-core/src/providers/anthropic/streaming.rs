@@ -3,11 +3,13 @@ use futures::StreamExt;
 use serde::Deserialize;
 use serde_json::json;
 
-use super::completion::{CompletionModel, Content, Message, ToolChoice, ToolDefinition, Usage};
+use super::completion::{CompletionModel, Content, Message, Usage};
 use crate::completion::{CompletionError, CompletionRequest};
 use crate::json_utils::merge_inplace;
 use crate::message::MessageError;
+use crate::sse;
 use crate::streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult};
+use crate::telemetry::TokenUsage;
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -28,7 +30,7 @@ pub enum StreamingEvent {
     },
     MessageDelta {
         delta: MessageDelta,
-        usage: Usage,
+        usage: MessageDeltaUsage,
     },
     MessageStop,
     Ping,
@@ -58,6 +60,12 @@ pub struct MessageDelta {
     pub stop_sequence: Option<String>,
 }
 
+/// The cumulative usage sent with `message_delta` events
+#[derive(Debug, Deserialize)]
+pub struct MessageDeltaUsage {
+    pub output_tokens: u64,
+}
+
 #[derive(Default)]
 struct ToolCallState {
     name: String,
@@ -109,31 +117,17 @@ impl StreamingCompletionModel for CompletionModel {
             merge_inplace(&mut request, json!({ "temperature": temperature }));
         }
 
-        if !completion_request.tools.is_empty() {
-            merge_inplace(
-                &mut request,
-                json!({
-                    "tools": completion_request
-                        .tools
-                        .into_iter()
-                        .map(|tool| ToolDefinition {
-                            name: tool.name,
-                            description: Some(tool.description),
-                            input_schema: tool.parameters,
-                        })
-                        .collect::<Vec<_>>(),
-                    "tool_choice": ToolChoice::Auto,
-                }),
-            );
+        if let Some(tools) = self.tools_json(completion_request.tools)? {
+            merge_inplace(&mut request, tools);
         }
 
         if let Some(ref params) = completion_request.additional_params {
             merge_inplace(&mut request, params.clone())
         }
 
-        let response = self
-            .client
-            .post("/v1/messages")
+        let response = completion_request
+            .request_options
+            .apply(self.client.post("/v1/messages"))
             .json(&request)
             .send()
             .await?;
@@ -144,80 +138,113 @@ impl StreamingCompletionModel for CompletionModel {
 
         Ok(Box::pin(stream! {
             let mut current_tool_call: Option<ToolCallState> = None;
-            let mut stream = response.bytes_stream();
+            let mut input_tokens = 0;
+            let mut events = std::pin::pin!(sse::events(response.bytes_stream()));
 
-            while let Some(chunk_result) = stream.next().await {
-                let chunk = match chunk_result {
-                    Ok(c) => c,
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
                     Err(e) => {
                         yield Err(CompletionError::from(e));
                         break;
                     }
                 };
 
-                let text = match String::from_utf8(chunk.to_vec()) {
-                    Ok(t) => t,
-                    Err(e) => {
-                        yield Err(CompletionError::ResponseError(e.to_string()));
-                        break;
-                    }
-                };
-
-                for line in text.lines() {
-                    if let Some(data) = line.strip_prefix("data: ") {
-                        if let Ok(event) = serde_json::from_str::<StreamingEvent>(data) {
-                            match event {
-                                StreamingEvent::ContentBlockDelta { delta, .. } => {
-                                    match delta {
-                                        ContentDelta::TextDelta { text } => {
-                                            if current_tool_call.is_none() {
-                                                yield Ok(StreamingChoice::Message(text));
-                                            }
-                                        }
-                                        ContentDelta::InputJsonDelta { partial_json } => {
-                                            if let Some(ref mut tool_call) = current_tool_call {
-                                                tool_call.input_json.push_str(&partial_json);
-                                            }
-                                        }
+                if let Ok(event) = serde_json::from_str::<StreamingEvent>(&event.data) {
+                    match event {
+                        StreamingEvent::MessageStart { message } => {
+                            input_tokens = message.usage.input_tokens
+                                + message.usage.cache_read_input_tokens.unwrap_or(0)
+                                + message.usage.cache_creation_input_tokens.unwrap_or(0);
+                        }
+                        StreamingEvent::MessageDelta { delta, usage } => {
+                            if delta.stop_reason.as_deref() == Some("refusal") {
+                                yield Err(CompletionError::Refused {
+                                    reason: "The model declined to respond (stop reason: refusal)"
+                                        .to_string(),
+                                });
+                                break;
+                            }
+                            yield Ok(StreamingChoice::Usage(TokenUsage {
+                                input_tokens,
+                                output_tokens: usage.output_tokens,
+                            }));
+                        }
+                        StreamingEvent::ContentBlockDelta { delta, .. } => {
+                            match delta {
+                                ContentDelta::TextDelta { text } => {
+                                    if current_tool_call.is_none() {
+                                        yield Ok(StreamingChoice::Message(text));
                                     }
                                 }
-                                StreamingEvent::ContentBlockStart {
-                                    content_block: Content::ToolUse { id, name, .. },
-                                    ..
-                                } => {
-                                    current_tool_call = Some(ToolCallState {
-                                        name,
-                                        id,
-                                        input_json: String::new(),
-                                    });
-                                }
-                                StreamingEvent::ContentBlockStop { .. } => {
-                                    if let Some(tool_call) = current_tool_call.take() {
-                                        let json_str = if tool_call.input_json.is_empty() {
-                                            "{}"
-                                        } else {
-                                            &tool_call.input_json
-                                        };
-                                        match serde_json::from_str(json_str) {
-                                            Ok(json_value) => {
-                                                yield Ok(StreamingChoice::ToolCall(
-                                                    tool_call.name,
-                                                    tool_call.id,
-                                                    json_value,
-                                                ));
-                                            }
-                                            Err(e) => {
-                                                yield Err(CompletionError::from(e));
-                                            }
-                                        }
+                                ContentDelta::InputJsonDelta { partial_json } => {
+                                    if let Some(ref mut tool_call) = current_tool_call {
+                                        tool_call.input_json.push_str(&partial_json);
                                     }
-                                },
-                                _ => {}
+                                }
                             }
                         }
+                        StreamingEvent::ContentBlockStart {
+                            content_block: Content::ToolUse { id, name, .. },
+                            ..
+                        } => {
+                            current_tool_call = Some(ToolCallState {
+                                name,
+                                id,
+                                input_json: String::new(),
+                            });
+                        }
+                        StreamingEvent::ContentBlockStop { .. } => {
+                            if let Some(tool_call) = current_tool_call.take() {
+                                let json_str = if tool_call.input_json.is_empty() {
+                                    "{}"
+                                } else {
+                                    &tool_call.input_json
+                                };
+                                match serde_json::from_str(json_str) {
+                                    Ok(json_value) => {
+                                        yield Ok(StreamingChoice::ToolCall(
+                                            tool_call.name,
+                                            tool_call.id,
+                                            json_value,
+                                        ));
+                                    }
+                                    Err(e) => {
+                                        yield Err(CompletionError::from(e));
+                                    }
+                                }
+                            }
+                        },
+                        _ => {}
                     }
                 }
             }
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_usage_events() {
+        let start: StreamingEvent = serde_json::from_str(
+            r#"{"type": "message_start", "message": {"id": "msg_1", "type": "message", "role": "assistant", "content": [], "model": "claude-3-5-sonnet-latest", "stop_reason": null, "stop_sequence": null, "usage": {"input_tokens": 25, "cache_read_input_tokens": 100, "output_tokens": 1}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            start,
+            StreamingEvent::MessageStart { message } if message.usage.input_tokens == 25
+        ));
+
+        let delta: StreamingEvent = serde_json::from_str(
+            r#"{"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}, "usage": {"output_tokens": 15}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            delta,
+            StreamingEvent::MessageDelta { usage, .. } if usage.output_tokens == 15
+        ));
+    }
+}
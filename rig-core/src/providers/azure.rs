@@ -13,6 +13,7 @@ use crate::{
     completion::{self, CompletionError, CompletionRequest},
     embeddings::{self, EmbeddingError, EmbeddingsBuilder},
     extractor::ExtractorBuilder,
+    http::RequestOptions,
     json_utils,
     providers::openai,
     Embed,
@@ -20,6 +21,7 @@ use crate::{
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 
 // ================================================================
 // Main Azure OpenAI Client
@@ -266,13 +268,22 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
     #[cfg_attr(feature = "worker", worker::send)]
     async fn embed_texts(
         &self,
-        documents: impl IntoIterator<Item = String>,
+        documents: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(documents, RequestOptions::default())
+            .await
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn embed_texts_with_options(
+        &self,
+        documents: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents = documents.into_iter().collect::<Vec<_>>();
 
-        let response = self
-            .client
-            .post_embedding(&self.model)
+        let response = request_options
+            .apply(self.client.post_embedding(&self.model))
             .json(&json!({
                 "input": documents,
             }))
@@ -351,11 +362,109 @@ pub const GPT_35_TURBO_INSTRUCT: &str = "gpt-3.5-turbo-instruct";
 /// `gpt-3.5-turbo-16k` completion model
 pub const GPT_35_TURBO_16K: &str = "gpt-3.5-turbo-16k";
 
+/// Harm category of the Azure OpenAI content filters.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterCategory {
+    Hate,
+    Sexual,
+    Violence,
+    SelfHarm,
+    /// Any other filter (e.g.: jailbreak, protected material)
+    #[serde(other)]
+    Other,
+}
+
+/// Severity of the content of a harm category, as annotated by the Azure OpenAI content filters.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFilterSeverity {
+    Safe,
+    Low,
+    Medium,
+    High,
+}
+
+/// Result of a content filter on a prompt or a response.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ContentFilterResult {
+    #[serde(default)]
+    pub filtered: bool,
+    pub severity: Option<ContentFilterSeverity>,
+    /// Whether the content was detected, for the filters without severity (e.g.: jailbreak)
+    pub detected: Option<bool>,
+}
+
+/// The content filter results annotating a completion response.
+#[derive(Debug, Default, Deserialize)]
+struct ContentFilterAnnotations {
+    #[serde(default)]
+    prompt_filter_results: Vec<PromptFilterResults>,
+    #[serde(default)]
+    choices: Vec<ChoiceFilterResults>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptFilterResults {
+    #[serde(default)]
+    content_filter_results: HashMap<ContentFilterCategory, ContentFilterResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceFilterResults {
+    finish_reason: Option<String>,
+    #[serde(default)]
+    content_filter_results: HashMap<ContentFilterCategory, ContentFilterResult>,
+}
+
+/// Error response of a prompt blocked by the content filters.
+#[derive(Debug, Deserialize)]
+struct ContentFilterError {
+    error: ContentFilterErrorDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentFilterErrorDetails {
+    code: Option<String>,
+    message: String,
+    innererror: Option<ContentFilterInnerError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentFilterInnerError {
+    #[serde(default)]
+    content_filter_result: HashMap<ContentFilterCategory, ContentFilterResult>,
+}
+
+/// The categories filtered in the results, or annotated with a severity from their threshold.
+fn filtered_categories(
+    results: &HashMap<ContentFilterCategory, ContentFilterResult>,
+    thresholds: &HashMap<ContentFilterCategory, ContentFilterSeverity>,
+) -> Vec<String> {
+    let mut categories = results
+        .iter()
+        .filter(|(category, result)| {
+            result.filtered
+                || matches!(
+                    (result.severity, thresholds.get(category)),
+                    (Some(severity), Some(threshold)) if severity >= *threshold
+                )
+        })
+        .map(|(category, result)| match result.severity {
+            Some(severity) => format!("{category:?} ({severity:?})"),
+            None => format!("{category:?}"),
+        })
+        .collect::<Vec<_>>();
+    categories.sort();
+    categories
+}
+
 #[derive(Clone)]
 pub struct CompletionModel {
     client: Client,
     /// Name of the model (e.g.: gpt-4o-mini)
     pub model: String,
+    content_filters: HashMap<ContentFilterCategory, ContentFilterSeverity>,
 }
 
 impl CompletionModel {
@@ -363,18 +472,62 @@ impl CompletionModel {
         Self {
             client,
             model: model.to_string(),
+            content_filters: HashMap::new(),
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = openai::CompletionResponse;
+    /// Set the severity from which the content of a harm category is blocked, in the prompts and
+    /// in the responses. Azure OpenAI content filters are configured on the deployment: this
+    /// threshold applies on top of them, to the severities they annotate the responses with.
+    /// Blocked requests fail with [CompletionError::ContentFiltered].
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::azure::{self, ContentFilterCategory, ContentFilterSeverity};
+    ///
+    /// let client = azure::Client::new("YOUR_API_KEY", "YOUR_API_VERSION", "YOUR_ENDPOINT");
+    /// let model = client
+    ///     .completion_model(azure::GPT_4O)
+    ///     .with_content_filter(ContentFilterCategory::Violence, ContentFilterSeverity::Low);
+    /// ```
+    pub fn with_content_filter(
+        mut self,
+        category: ContentFilterCategory,
+        threshold: ContentFilterSeverity,
+    ) -> Self {
+        self.content_filters.insert(category, threshold);
+        self
+    }
 
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    /// The reason why the prompt or the response was blocked by the content filters, if it was.
+    fn filter_reason(&self, response: &str) -> Option<String> {
+        let annotations =
+            serde_json::from_str::<ContentFilterAnnotations>(response).unwrap_or_default();
+
+        let prompt = annotations
+            .prompt_filter_results
+            .iter()
+            .flat_map(|results| {
+                filtered_categories(&results.content_filter_results, &self.content_filters)
+            })
+            .collect::<Vec<_>>();
+        if !prompt.is_empty() {
+            return Some(format!("Prompt blocked: {}", prompt.join(", ")));
+        }
+
+        let choice = annotations.choices.first()?;
+        let response = filtered_categories(&choice.content_filter_results, &self.content_filters);
+        match (choice.finish_reason.as_deref(), response.is_empty()) {
+            (_, false) => Some(format!("Response blocked: {}", response.join(", "))),
+            (Some("content_filter"), true) => Some("Response blocked".to_string()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<openai::Message> = match &completion_request.preamble {
             Some(preamble) => vec![openai::Message::system(preamble)],
@@ -414,16 +567,32 @@ impl completion::CompletionModel for CompletionModel {
             })
         };
 
-        let response = self
-            .client
-            .post_chat_completion(&self.model)
-            .json(
-                &if let Some(params) = completion_request.additional_params {
-                    json_utils::merge(request, params)
-                } else {
-                    request
-                },
-            )
+        let request = match completion_request.n {
+            Some(n) => json_utils::merge(request, json!({ "n": n })),
+            None => request,
+        };
+
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = openai::CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<openai::CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post_chat_completion(&self.model))
+            .json(&request)
             .send()
             .await?;
 
@@ -431,6 +600,10 @@ impl completion::CompletionModel for CompletionModel {
             let t = response.text().await?;
             tracing::debug!(target: "rig", "Azure completion error: {}", t);
 
+            if let Some(reason) = self.filter_reason(&t) {
+                return Err(CompletionError::ContentFiltered(reason));
+            }
+
             match serde_json::from_str::<ApiResponse<openai::CompletionResponse>>(&t)? {
                 ApiResponse::Ok(response) => {
                     tracing::info!(target: "rig",
@@ -442,9 +615,35 @@ impl completion::CompletionModel for CompletionModel {
                 ApiResponse::Err(err) => Err(CompletionError::ProviderError(err.message)),
             }
         } else {
-            Err(CompletionError::ProviderError(response.text().await?))
+            let t = response.text().await?;
+            match serde_json::from_str::<ContentFilterError>(&t) {
+                Ok(ContentFilterError { error })
+                    if error.code.as_deref() == Some("content_filter") =>
+                {
+                    let categories = error
+                        .innererror
+                        .map(|inner| {
+                            filtered_categories(&inner.content_filter_result, &HashMap::new())
+                        })
+                        .unwrap_or_default();
+                    Err(CompletionError::ContentFiltered(
+                        match categories.is_empty() {
+                            true => error.message,
+                            false => format!("Prompt blocked: {}", categories.join(", ")),
+                        },
+                    ))
+                }
+                _ => Err(CompletionError::ProviderError(t)),
+            }
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
 
 #[cfg(test)]
@@ -469,6 +668,47 @@ mod azure_tests {
         tracing::info!("Azure embedding: {:?}", embeddings);
     }
 
+    #[test]
+    fn test_content_filter_reason() {
+        let model = Client::new("key", "2024-10-21", "https://example.openai.azure.com")
+            .completion_model(GPT_4O)
+            .with_content_filter(
+                ContentFilterCategory::Violence,
+                ContentFilterSeverity::Medium,
+            );
+        let response = |violence: &str, finish_reason: &str| {
+            json!({
+                "prompt_filter_results": [{
+                    "prompt_index": 0,
+                    "content_filter_results": {
+                        "hate": {"filtered": false, "severity": "safe"},
+                        "jailbreak": {"filtered": false, "detected": false}
+                    }
+                }],
+                "choices": [{
+                    "index": 0,
+                    "finish_reason": finish_reason,
+                    "message": {"role": "assistant", "content": "..."},
+                    "content_filter_results": {
+                        "hate": {"filtered": false, "severity": "safe"},
+                        "violence": {"filtered": false, "severity": violence}
+                    }
+                }]
+            })
+            .to_string()
+        };
+
+        assert_eq!(model.filter_reason(&response("low", "stop")), None);
+        assert_eq!(
+            model.filter_reason(&response("medium", "stop")),
+            Some("Response blocked: Violence (Medium)".to_string())
+        );
+        assert_eq!(
+            model.filter_reason(&response("safe", "content_filter")),
+            Some("Response blocked".to_string())
+        );
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_azure_completion() {
@@ -483,9 +723,11 @@ mod azure_tests {
                 prompt: "Hello, world!".into(),
                 documents: vec![],
                 max_tokens: Some(100),
+                n: None,
                 temperature: Some(0.0),
                 tools: vec![],
                 additional_params: None,
+                request_options: Default::default(),
             })
             .await
             .unwrap();
@@ -15,6 +15,7 @@ use crate::{
     completion::{self, CompletionError},
     embeddings::{self, EmbeddingError, EmbeddingsBuilder},
     extractor::ExtractorBuilder,
+    http::RequestOptions,
     json_utils, message, Embed, OneOrMany,
 };
 
@@ -214,18 +215,79 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
     #[cfg_attr(feature = "worker", worker::send)]
     async fn embed_texts(
         &self,
-        documents: impl IntoIterator<Item = String>,
+        documents: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(documents, RequestOptions::default())
+            .await
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn embed_texts_with_options(
+        &self,
+        documents: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents = documents.into_iter().collect::<Vec<_>>();
 
-        let response = self
-            .client
-            .post("/v1/embed")
-            .json(&json!({
+        self.embed(
+            json!({
                 "model": self.model,
                 "texts": documents,
                 "input_type": self.input_type,
-            }))
+            }),
+            documents,
+            &request_options,
+        )
+        .await
+    }
+
+    /// Embed images with the Embed v3 models. The API accepts a single image per request.
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn embed_images(
+        &self,
+        images: impl IntoIterator<Item = embeddings::ImageInput> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        let images = images.into_iter().collect::<Vec<_>>();
+
+        let mut embeddings = vec![];
+        for image in images {
+            embeddings.extend(
+                self.embed(
+                    json!({
+                        "model": self.model,
+                        "images": [image.data_url()],
+                        "input_type": "image",
+                    }),
+                    vec![image.description()],
+                    &RequestOptions::default(),
+                )
+                .await?,
+            );
+        }
+        Ok(embeddings)
+    }
+}
+
+impl EmbeddingModel {
+    pub fn new(client: Client, model: &str, input_type: &str, ndims: usize) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            input_type: input_type.to_string(),
+            ndims,
+        }
+    }
+
+    /// Send an embedding request, `documents` being the descriptions of its inputs
+    async fn embed(
+        &self,
+        request: serde_json::Value,
+        documents: Vec<String>,
+        request_options: &RequestOptions,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        let response = request_options
+            .apply(self.client.post("/v1/embed"))
+            .json(&request)
             .send()
             .await?;
 
@@ -271,17 +333,6 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
     }
 }
 
-impl EmbeddingModel {
-    pub fn new(client: Client, model: &str, input_type: &str, ndims: usize) -> Self {
-        Self {
-            client,
-            model: model.to_string(),
-            input_type: input_type.to_string(),
-            ndims,
-        }
-    }
-}
-
 // ================================================================
 // Cohere Completion API
 // ================================================================
@@ -340,8 +391,52 @@ impl From<CompletionResponse> for completion::CompletionResponse<CompletionRespo
             vec![completion::AssistantContent::text(text.clone())]
         };
 
+        let annotations = response
+            .citations
+            .iter()
+            .flat_map(|citation| {
+                citation.document_ids.iter().map(|id| {
+                    let source = match response.documents.iter().find(|doc| &doc.id == id) {
+                        // Documents returned by web search connectors carry their url and title
+                        Some(Document {
+                            additional_prop, ..
+                        }) if additional_prop.contains_key("url") => {
+                            let prop = |key: &str| {
+                                additional_prop
+                                    .get(key)
+                                    .and_then(|value| value.as_str())
+                                    .map(|value| value.to_string())
+                            };
+                            completion::AnnotationSource::Url {
+                                url: prop("url").unwrap_or_default(),
+                                title: prop("title"),
+                            }
+                        }
+                        _ => completion::AnnotationSource::Document { id: id.clone() },
+                    };
+
+                    completion::Annotation {
+                        start_index: Some(citation.start as usize),
+                        end_index: Some(citation.end as usize),
+                        source,
+                        confidence: None,
+                    }
+                })
+            })
+            .collect();
+
         completion::CompletionResponse {
             choice: OneOrMany::many(model_response).expect("There is atleast one content"),
+            annotations,
+            alternatives: vec![],
+            finish_reason: Some(match response.finish_reason.as_str() {
+                "COMPLETE" | "STOP_SEQUENCE" => completion::FinishReason::Stop,
+                "MAX_TOKENS" => completion::FinishReason::Length,
+                "TOOL_CALL" => completion::FinishReason::ToolCalls,
+                "ERROR_TOXIC" => completion::FinishReason::ContentFilter,
+                _ if !response.tool_calls.is_empty() => completion::FinishReason::ToolCalls,
+                other => completion::FinishReason::Other(other.to_string()),
+            }),
             raw_response: response,
         }
     }
@@ -516,7 +611,7 @@ impl TryFrom<message::Message> for Vec<Message> {
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         match message {
-            message::Message::User { content } => content
+            message::Message::User { content, .. } => content
                 .into_iter()
                 .map(|content| {
                     Ok(Message::User {
@@ -552,16 +647,11 @@ impl CompletionModel {
             model: model.to_string(),
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: completion::CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         let chat_history = completion_request
             .chat_history
             .into_iter()
@@ -572,7 +662,7 @@ impl completion::CompletionModel for CompletionModel {
             .collect::<Vec<_>>();
 
         let message = match completion_request.prompt {
-            message::Message::User { content } => Ok(content
+            message::Message::User { content, .. } => Ok(content
                 .into_iter()
                 .map(|content| match content {
                     message::UserContent::Text(message::Text { text }) => Ok(text),
@@ -588,26 +678,49 @@ impl completion::CompletionModel for CompletionModel {
             )),
         }?;
 
+        // Cohere documents are flat maps of strings
+        let documents = completion_request
+            .documents
+            .iter()
+            .map(|document| {
+                let mut props = document.metadata.to_props();
+                props.insert("id".to_string(), document.metadata.id.clone());
+                props.insert("text".to_string(), document.text.clone());
+                props
+            })
+            .collect::<Vec<_>>();
+
         let request = json!({
             "model": self.model,
             "preamble": completion_request.preamble,
             "message": message,
-            "documents": completion_request.documents,
+            "documents": documents,
             "chat_history": chat_history,
             "temperature": completion_request.temperature,
             "tools": completion_request.tools.into_iter().map(ToolDefinition::from).collect::<Vec<_>>(),
         });
 
-        let response = self
-            .client
-            .post("/v1/chat")
-            .json(
-                &if let Some(ref params) = completion_request.additional_params {
-                    json_utils::merge(request.clone(), params.clone())
-                } else {
-                    request.clone()
-                },
-            )
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/v1/chat"))
+            .json(&request)
             .send()
             .await?;
 
@@ -620,4 +733,11 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
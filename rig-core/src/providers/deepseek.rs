@@ -9,9 +9,12 @@
 //! let deepseek_chat = client.completion_model(deepseek::DEEPSEEK_CHAT);
 //! ```
 use crate::{
-    completion::{self, CompletionError, CompletionModel, CompletionRequest},
+    completion::{self, roles::RoleMapping, CompletionError, CompletionModel, CompletionRequest},
     extractor::ExtractorBuilder,
-    json_utils, message, OneOrMany,
+    json_utils, message,
+    providers::openai,
+    text_completion::{self, FimCompletionModel, TextCompletionRequest},
+    OneOrMany,
 };
 use reqwest::Client as HttpClient;
 use schemars::JsonSchema;
@@ -75,6 +78,15 @@ impl Client {
         }
     }
 
+    /// Creates a DeepSeek fill-in-the-middle model with the given `model_name`, using the (beta)
+    /// FIM completions endpoint.
+    pub fn fim_model(&self, model_name: &str) -> DeepSeekFimModel {
+        DeepSeekFimModel {
+            client: self.clone(),
+            model: model_name.to_string(),
+        }
+    }
+
     /// Optionally add an agent() convenience:
     pub fn agent(&self, model_name: &str) -> crate::agent::AgentBuilder<DeepSeekCompletionModel> {
         crate::agent::AgentBuilder::new(self.completion_model(model_name))
@@ -193,7 +205,7 @@ impl TryFrom<message::Message> for Vec<Message> {
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         match message {
-            message::Message::User { content } => {
+            message::Message::User { content, name } => {
                 // extract tool results
                 let mut messages = vec![];
 
@@ -216,7 +228,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                     .filter_map(|content| match content {
                         message::UserContent::Text(text) => Some(Message::User {
                             content: text.text,
-                            name: None,
+                            name: name.clone(),
                         }),
                         _ => None,
                     })
@@ -225,7 +237,7 @@ impl TryFrom<message::Message> for Vec<Message> {
 
                 Ok(messages)
             }
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, name } => {
                 let mut messages: Vec<Message> = vec![];
 
                 // extract tool calls
@@ -244,7 +256,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                 if !tool_calls.is_empty() {
                     messages.push(Message::Assistant {
                         content: "".to_string(),
-                        name: None,
+                        name: name.clone(),
                         tool_calls,
                     });
                 }
@@ -255,7 +267,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                     .filter_map(|content| match content {
                         message::AssistantContent::Text(text) => Some(Message::Assistant {
                             content: text.text,
-                            name: None,
+                            name: name.clone(),
                             tool_calls: vec![],
                         }),
                         _ => None,
@@ -332,7 +344,7 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
                         .iter()
                         .map(|call| {
                             completion::AssistantContent::tool_call(
-                                &call.function.name,
+                                &call.id,
                                 &call.function.name,
                                 call.function.arguments.clone(),
                             )
@@ -354,6 +366,12 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            annotations: vec![],
+            alternatives: vec![],
+            finish_reason: response
+                .choices
+                .first()
+                .map(|choice| choice.finish_reason.as_str().into()),
             raw_response: response,
         })
     }
@@ -366,17 +384,17 @@ pub struct DeepSeekCompletionModel {
     pub model: String,
 }
 
-impl CompletionModel for DeepSeekCompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+impl DeepSeekCompletionModel {
+    pub(crate) fn create_completion_request(
         &self,
-        completion_request: CompletionRequest,
-    ) -> Result<
-        completion::CompletionResponse<CompletionResponse>,
-        crate::completion::CompletionError,
-    > {
+        mut completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        // The reasoner model requires alternating user and assistant messages
+        if self.model == DEEPSEEK_REASONER {
+            completion_request
+                .normalize_roles(RoleMapping::NONE.merge_consecutive(true).leading_user(true));
+        }
+
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
@@ -416,16 +434,30 @@ impl CompletionModel for DeepSeekCompletionModel {
             })
         };
 
-        let response = self
-            .client
-            .post("/chat/completions")
-            .json(
-                &if let Some(params) = completion_request.additional_params {
-                    json_utils::merge(request, params)
-                } else {
-                    request
-                },
-            )
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl CompletionModel for DeepSeekCompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<
+        completion::CompletionResponse<CompletionResponse>,
+        crate::completion::CompletionError,
+    > {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/chat/completions"))
+            .json(&request)
             .send()
             .await?;
 
@@ -441,6 +473,13 @@ impl CompletionModel for DeepSeekCompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
 
 // ================================================================
@@ -452,6 +491,110 @@ pub const DEEPSEEK_CHAT: &str = "deepseek-chat";
 /// `deepseek-reasoner` completion model
 pub const DEEPSEEK_REASONER: &str = "deepseek-reasoner";
 
+// ================================================================
+// DeepSeek FIM Completion API (beta)
+// ================================================================
+
+/// The response shape from the DeepSeek FIM completion API
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FimResponse {
+    pub choices: Vec<FimChoice>,
+    pub usage: Option<openai::Usage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FimChoice {
+    pub index: usize,
+    pub text: String,
+    pub finish_reason: Option<String>,
+}
+
+impl TryFrom<FimResponse> for text_completion::TextCompletionResponse<FimResponse> {
+    type Error = CompletionError;
+
+    fn try_from(response: FimResponse) -> Result<Self, Self::Error> {
+        let choice = response.choices.first().ok_or_else(|| {
+            CompletionError::ResponseError("Response contained no choices".to_owned())
+        })?;
+
+        Ok(text_completion::TextCompletionResponse {
+            text: choice.text.clone(),
+            finish_reason: choice.finish_reason.as_deref().map(Into::into),
+            raw_response: response,
+        })
+    }
+}
+
+/// The struct implementing the `FimCompletionModel` trait (only `deepseek-chat` supports FIM)
+#[derive(Clone)]
+pub struct DeepSeekFimModel {
+    pub client: Client,
+    pub model: String,
+}
+
+impl DeepSeekFimModel {
+    pub(crate) fn create_fim_request(&self, request: TextCompletionRequest) -> serde_json::Value {
+        let mut body = json!({
+            "model": self.model,
+            "prompt": request.prompt,
+        });
+        if let Some(suffix) = request.suffix {
+            body["suffix"] = json!(suffix);
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if !request.stop.is_empty() {
+            body["stop"] = json!(request.stop);
+        }
+
+        match request.additional_params {
+            Some(params) => json_utils::merge(body, params),
+            None => body,
+        }
+    }
+}
+
+impl text_completion::TextCompletionModel for DeepSeekFimModel {
+    type Response = FimResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn text_completion(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<text_completion::TextCompletionResponse<FimResponse>, CompletionError> {
+        let request = self.create_fim_request(request);
+
+        let response = self
+            .client
+            .post("/beta/completions")
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            match response.json::<ApiResponse<FimResponse>>().await? {
+                ApiResponse::Ok(response) => response.try_into(),
+                ApiResponse::Err(err) => Err(err.into()),
+            }
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
+
+    fn request_body(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        Ok(self.create_fim_request(request))
+    }
+}
+
+impl FimCompletionModel for DeepSeekFimModel {}
+
 // Tests
 #[cfg(test)]
 mod tests {
@@ -594,4 +737,37 @@ mod tests {
 
         assert_eq!(choice, expected_choice);
     }
+
+    #[test]
+    fn test_fim() {
+        use crate::text_completion::TextCompletionModel as _;
+
+        let model = Client::new("test-key").fim_model(DEEPSEEK_CHAT);
+        let request = model
+            .complete_fim("def fib(a):", "    return fib(a-1) + fib(a-2)")
+            .max_tokens(128)
+            .build();
+        assert_eq!(
+            model.request_body(request).unwrap(),
+            json!({
+                "model": DEEPSEEK_CHAT,
+                "prompt": "def fib(a):",
+                "suffix": "    return fib(a-1) + fib(a-2)",
+                "max_tokens": 128,
+            })
+        );
+
+        let response: FimResponse = serde_json::from_value(json!({
+            "id": "cmpl-1",
+            "object": "text_completion",
+            "created": 1,
+            "model": DEEPSEEK_CHAT,
+            "choices": [{"index": 0, "text": "\n    if a <= 1:\n        return a\n", "logprobs": null, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 12, "total_tokens": 32}
+        }))
+        .unwrap();
+        let response: text_completion::TextCompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(response.text, "\n    if a <= 1:\n        return a\n");
+        assert_eq!(response.finish_reason, Some(completion::FinishReason::Stop));
+    }
 }
@@ -237,7 +237,7 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         content.extend(message.tool_calls.iter().map(|call| {
             completion::AssistantContent::tool_call(
-                &call.function.name,
+                &call.id,
                 &call.function.name,
                 call.function.arguments.clone(),
             )
@@ -251,6 +251,12 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            annotations: vec![],
+            alternatives: vec![],
+            finish_reason: response
+                .choices
+                .first()
+                .map(|choice| choice.finish_reason.as_str().into()),
             raw_response: response,
         })
     }
@@ -292,6 +298,7 @@ impl TryFrom<Message> for message::Message {
                             message::MessageError::ConversionError("Empty user message".to_string())
                         })?,
                 ),
+                name: None,
             }),
             "assistant" => Ok(Self::Assistant {
                 content: OneOrMany::many(
@@ -308,6 +315,7 @@ impl TryFrom<Message> for message::Message {
                 .map_err(|_| {
                     message::MessageError::ConversionError("Empty assistant message".to_string())
                 })?,
+                name: None,
             }),
             _ => Err(message::MessageError::ConversionError(format!(
                 "Unknown role: {}",
@@ -322,7 +330,7 @@ impl TryFrom<message::Message> for Message {
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         match message {
-            message::Message::User { content } => Ok(Self {
+            message::Message::User { content, .. } => Ok(Self {
                 role: "user".to_string(),
                 content: content.iter().find_map(|c| match c {
                     message::UserContent::Text(text) => Some(text.text.clone()),
@@ -330,7 +338,7 @@ impl TryFrom<message::Message> for Message {
                 }),
                 tool_calls: vec![],
             }),
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, .. } => {
                 let mut text_content: Option<String> = None;
                 let mut tool_calls = vec![];
 
@@ -398,16 +406,11 @@ impl CompletionModel {
             model: model.to_string(),
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message {
@@ -448,16 +451,27 @@ impl completion::CompletionModel for CompletionModel {
             })
         };
 
-        let response = self
-            .client
-            .post("/chat/completions")
-            .json(
-                &if let Some(params) = completion_request.additional_params {
-                    json_utils::merge(request, params)
-                } else {
-                    request
-                },
-            )
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/chat/completions"))
+            .json(&request)
             .send()
             .await?;
 
@@ -479,4 +493,11 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
@@ -15,8 +15,9 @@ pub const GEMINI_1_5_PRO_8B: &str = "gemini-1.5-pro-8b";
 pub const GEMINI_1_0_PRO: &str = "gemini-1.0-pro";
 
 use gemini_api_types::{
-    Content, FunctionDeclaration, GenerateContentRequest, GenerateContentResponse,
-    GenerationConfig, Part, Role, Tool,
+    Content, ContentCandidate, DynamicRetrievalConfig, FinishReason, FunctionDeclaration,
+    GenerateContentRequest, GenerateContentResponse, GenerationConfig, GoogleSearch,
+    GoogleSearchRetrieval, HarmBlockThreshold, HarmCategory, Part, Role, SafetySetting, Tool,
 };
 use serde_json::{Map, Value};
 use std::convert::TryFrom;
@@ -32,10 +33,24 @@ use super::Client;
 // Rig Implementation Types
 // =================================================================
 
+/// Grounding of the responses of a model with Google Search, see
+/// [CompletionModel::with_google_search].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchGrounding {
+    /// The `googleSearch` tool (Gemini 2.0 models): the model decides when to search
+    GoogleSearch,
+    /// The `googleSearchRetrieval` tool (Gemini 1.5 models): the model searches when its
+    /// prediction that search is useful is above the threshold (between 0 and 1, 0.3 by
+    /// default), or always if no threshold is set
+    GoogleSearchRetrieval { dynamic_threshold: Option<f64> },
+}
+
 #[derive(Clone)]
 pub struct CompletionModel {
-    client: Client,
+    pub(crate) client: Client,
     pub model: String,
+    search_grounding: Option<SearchGrounding>,
+    safety_settings: Vec<SafetySetting>,
 }
 
 impl CompletionModel {
@@ -43,18 +58,71 @@ impl CompletionModel {
         Self {
             client,
             model: model.to_string(),
+            search_grounding: None,
+            safety_settings: vec![],
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = GenerateContentResponse;
+    /// Set the threshold from which the content of a harm category is blocked, in the prompts and
+    /// in the responses (overriding the default threshold of the category). Blocked requests fail
+    /// with [CompletionError::ContentFiltered].
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::gemini::{
+    ///     self,
+    ///     completion::{
+    ///         gemini_api_types::{HarmBlockThreshold, HarmCategory},
+    ///         GEMINI_2_0_FLASH,
+    ///     },
+    /// };
+    ///
+    /// let model = gemini::Client::new("your-gemini-api-key")
+    ///     .completion_model(GEMINI_2_0_FLASH)
+    ///     .with_safety_setting(
+    ///         HarmCategory::HarmCategoryDangerousContent,
+    ///         HarmBlockThreshold::BlockLowAndAbove,
+    ///     )
+    ///     .with_safety_setting(HarmCategory::HarmCategoryHarassment, HarmBlockThreshold::BlockNone);
+    /// ```
+    pub fn with_safety_setting(
+        mut self,
+        category: HarmCategory,
+        threshold: HarmBlockThreshold,
+    ) -> Self {
+        self.safety_settings
+            .retain(|setting| setting.category != category);
+        self.safety_settings.push(SafetySetting {
+            category,
+            threshold,
+        });
+        self
+    }
 
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    /// Ground the responses of the model with Google Search. The web sources supporting the
+    /// response are returned as its annotations, with the spans of text they support and the
+    /// confidence scores of the supports.
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::gemini::{
+    ///     self,
+    ///     completion::{SearchGrounding, GEMINI_2_0_FLASH},
+    /// };
+    ///
+    /// let model = gemini::Client::new("your-gemini-api-key")
+    ///     .completion_model(GEMINI_2_0_FLASH)
+    ///     .with_google_search(SearchGrounding::GoogleSearch);
+    /// ```
+    pub fn with_google_search(mut self, search_grounding: SearchGrounding) -> Self {
+        self.search_grounding = Some(search_grounding);
+        self
+    }
+
+    pub(crate) fn create_completion_request(
         &self,
         mut completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<GenerateContentResponse>, CompletionError> {
+    ) -> Result<GenerateContentRequest, CompletionError> {
         let mut full_history = Vec::new();
         full_history.append(&mut completion_request.chat_history);
 
@@ -76,6 +144,10 @@ impl completion::CompletionModel for CompletionModel {
             generation_config.max_output_tokens = Some(max_tokens);
         }
 
+        if let Some(n) = completion_request.n {
+            generation_config.candidate_count = Some(n as i32);
+        }
+
         let system_instruction = completion_request.preamble.clone().map(|preamble| Content {
             parts: OneOrMany::one(preamble.into()),
             role: Some(Role::Model),
@@ -90,26 +162,49 @@ impl completion::CompletionModel for CompletionModel {
                 })
                 .collect::<Result<Vec<_>, _>>()?,
             generation_config: Some(generation_config),
-            safety_settings: None,
+            safety_settings: (!self.safety_settings.is_empty())
+                .then(|| self.safety_settings.clone()),
             tools: Some(
                 completion_request
                     .tools
                     .into_iter()
                     .map(Tool::try_from)
+                    .chain(
+                        self.search_grounding
+                            .as_ref()
+                            .map(|grounding| Ok(grounding.into())),
+                    )
                     .collect::<Result<Vec<_>, _>>()?,
             ),
             tool_config: None,
             system_instruction,
         };
 
+        Ok(request)
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = GenerateContentResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<GenerateContentResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
         tracing::debug!(
             "Sending completion request to Gemini API {}",
             serde_json::to_string_pretty(&request)?
         );
 
-        let response = self
-            .client
-            .post(&format!("/v1beta/models/{}:generateContent", self.model))
+        let response = request_options
+            .apply(
+                self.client
+                    .post(&format!("/v1beta/models/{}:generateContent", self.model)),
+            )
             .json(&request)
             .send()
             .await?;
@@ -133,6 +228,15 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }?
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        Ok(serde_json::to_value(
+            self.create_completion_request(completion_request)?,
+        )?)
+    }
 }
 
 impl TryFrom<completion::ToolDefinition> for Tool {
@@ -140,58 +244,133 @@ impl TryFrom<completion::ToolDefinition> for Tool {
 
     fn try_from(tool: completion::ToolDefinition) -> Result<Self, Self::Error> {
         Ok(Self {
-            function_declarations: FunctionDeclaration {
+            function_declarations: Some(FunctionDeclaration {
                 name: tool.name,
                 description: tool.description,
                 parameters: Some(tool.parameters.try_into()?),
-            },
+            }),
             code_execution: None,
+            google_search: None,
+            google_search_retrieval: None,
         })
     }
 }
 
+impl From<&SearchGrounding> for Tool {
+    fn from(grounding: &SearchGrounding) -> Self {
+        let (google_search, google_search_retrieval) = match grounding {
+            SearchGrounding::GoogleSearch => (Some(GoogleSearch {}), None),
+            SearchGrounding::GoogleSearchRetrieval { dynamic_threshold } => (
+                None,
+                Some(GoogleSearchRetrieval {
+                    dynamic_retrieval_config: dynamic_threshold.map(|threshold| {
+                        DynamicRetrievalConfig {
+                            mode: "MODE_DYNAMIC".to_string(),
+                            dynamic_threshold: Some(threshold),
+                        }
+                    }),
+                }),
+            ),
+        };
+        Self {
+            function_declarations: None,
+            code_execution: None,
+            google_search,
+            google_search_retrieval,
+        }
+    }
+}
+
 impl TryFrom<GenerateContentResponse> for completion::CompletionResponse<GenerateContentResponse> {
     type Error = CompletionError;
 
     fn try_from(response: GenerateContentResponse) -> Result<Self, Self::Error> {
+        if let Some(reason) = response.block_reason() {
+            return Err(CompletionError::ContentFiltered(reason));
+        }
+
         let candidate = response.candidates.first().ok_or_else(|| {
             CompletionError::ResponseError("No response candidates in response".into())
         })?;
-
-        let content = candidate
-            .content
-            .parts
+        let choice = candidate_choice(candidate)?;
+        let has_tool_calls = choice
             .iter()
-            .map(|part| {
-                Ok(match part {
-                    Part::Text(text) => completion::AssistantContent::text(text),
-                    Part::FunctionCall(function_call) => completion::AssistantContent::tool_call(
-                        &function_call.name,
-                        &function_call.name,
-                        function_call.args.clone(),
-                    ),
-                    _ => {
-                        return Err(CompletionError::ResponseError(
-                            "Response did not contain a message or tool call".into(),
-                        ))
-                    }
-                })
-            })
+            .any(|content| matches!(content, completion::AssistantContent::ToolCall(_)));
+        let alternatives = response
+            .candidates
+            .iter()
+            .skip(1)
+            .map(candidate_choice)
             .collect::<Result<Vec<_>, _>>()?;
 
-        let choice = OneOrMany::many(content).map_err(|_| {
-            CompletionError::ResponseError(
-                "Response contained no message or tool call (empty)".to_owned(),
-            )
-        })?;
+        let annotations = match &candidate.grounding_metadata {
+            Some(grounding) => {
+                let text = choice
+                    .iter()
+                    .filter_map(|content| match content {
+                        completion::AssistantContent::Text(text) => Some(text.text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<String>();
+                grounding.annotations(&text)
+            }
+            None => vec![],
+        };
+
+        let finish_reason = candidate.finish_reason.as_ref().map(|reason| match reason {
+            FinishReason::Stop if has_tool_calls => completion::FinishReason::ToolCalls,
+            FinishReason::Stop => completion::FinishReason::Stop,
+            FinishReason::MaxTokens => completion::FinishReason::Length,
+            FinishReason::Safety
+            | FinishReason::Recitation
+            | FinishReason::Blocklist
+            | FinishReason::ProhibitedContent
+            | FinishReason::Spii => completion::FinishReason::ContentFilter,
+            other => completion::FinishReason::Other(format!("{other:?}")),
+        });
 
         Ok(completion::CompletionResponse {
             choice,
+            alternatives,
+            annotations,
+            finish_reason,
             raw_response: response,
         })
     }
 }
 
+/// The content of a response candidate.
+fn candidate_choice(
+    candidate: &ContentCandidate,
+) -> Result<OneOrMany<completion::AssistantContent>, CompletionError> {
+    let content = candidate
+        .content
+        .iter()
+        .flat_map(|content| content.parts.iter())
+        .map(|part| {
+            Ok(match part {
+                Part::Text(text) => completion::AssistantContent::text(text),
+                Part::FunctionCall(function_call) => completion::AssistantContent::tool_call(
+                    &function_call.name,
+                    &function_call.name,
+                    function_call.args.clone(),
+                ),
+                _ => {
+                    return Err(CompletionError::ResponseError(
+                        "Response did not contain a message or tool call".into(),
+                    ))
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    OneOrMany::many(content).map_err(|_| {
+        CompletionError::ResponseError(
+            "Response contained no message or tool call (empty)".to_owned(),
+        )
+    })
+}
+
 pub mod gemini_api_types {
     use std::{collections::HashMap, convert::Infallible, str::FromStr};
 
@@ -202,7 +381,7 @@ pub mod gemini_api_types {
     use serde_json::Value;
 
     use crate::{
-        completion::CompletionError,
+        completion::{self, CompletionError},
         message::{self, MimeType as _},
         one_or_many::string_or_one_or_many,
         providers::gemini::gemini_api_types::{CodeExecutionResult, ExecutableCode},
@@ -220,6 +399,7 @@ pub mod gemini_api_types {
     #[serde(rename_all = "camelCase")]
     pub struct GenerateContentResponse {
         /// Candidate responses from the model.
+        #[serde(default)]
         pub candidates: Vec<ContentCandidate>,
         /// Returns the prompt's feedback related to the content filters.
         pub prompt_feedback: Option<PromptFeedback>,
@@ -228,12 +408,57 @@ pub mod gemini_api_types {
         pub model_version: Option<String>,
     }
 
+    impl GenerateContentResponse {
+        /// The reason why the prompt or the first candidate was blocked by the content filters,
+        /// if it was.
+        pub fn block_reason(&self) -> Option<String> {
+            if let Some(feedback) = &self.prompt_feedback {
+                if let Some(reason) = &feedback.block_reason {
+                    return Some(format!(
+                        "Prompt blocked ({reason:?}){}",
+                        blocked_categories(feedback.safety_ratings.as_deref())
+                    ));
+                }
+            }
+
+            let candidate = self.candidates.first()?;
+            match candidate.finish_reason {
+                Some(
+                    ref reason @ (FinishReason::Safety
+                    | FinishReason::Recitation
+                    | FinishReason::Blocklist
+                    | FinishReason::ProhibitedContent
+                    | FinishReason::Spii),
+                ) => Some(format!(
+                    "Response blocked ({reason:?}){}",
+                    blocked_categories(candidate.safety_ratings.as_deref())
+                )),
+                _ => None,
+            }
+        }
+    }
+
+    /// The categories of the blocking ratings, e.g.: ": HarmCategoryHarassment"
+    fn blocked_categories(ratings: Option<&[SafetyRating]>) -> String {
+        let categories = ratings
+            .unwrap_or_default()
+            .iter()
+            .filter(|rating| rating.blocked == Some(true))
+            .map(|rating| format!("{:?}", rating.category))
+            .collect::<Vec<_>>();
+        match categories.is_empty() {
+            true => String::new(),
+            false => format!(": {}", categories.join(", ")),
+        }
+    }
+
     /// A response candidate generated from the model.
     #[derive(Debug, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub struct ContentCandidate {
-        /// Output only. Generated content returned from the model.
-        pub content: Content,
+        /// Output only. Generated content returned from the model. Missing when the candidate
+        /// was blocked.
+        pub content: Option<Content>,
         /// Optional. Output only. The reason why the model stopped generating tokens.
         /// If empty, the model has not stopped generating tokens.
         pub finish_reason: Option<FinishReason>,
@@ -252,6 +477,115 @@ pub mod gemini_api_types {
         pub logprobs_result: Option<LogprobsResult>,
         /// Output only. Index of the candidate in the list of response candidates.
         pub index: Option<i32>,
+        /// Output only. The sources of the response when it is grounded with Google Search.
+        pub grounding_metadata: Option<GroundingMetadata>,
+    }
+
+    /// The sources of a response grounded with Google Search.
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GroundingMetadata {
+        #[serde(default)]
+        pub web_search_queries: Vec<String>,
+        /// The search suggestions that must be displayed along with the grounded response.
+        pub search_entry_point: Option<SearchEntryPoint>,
+        #[serde(default)]
+        pub grounding_chunks: Vec<GroundingChunk>,
+        /// The spans of the response supported by the chunks.
+        #[serde(default)]
+        pub grounding_supports: Vec<GroundingSupport>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SearchEntryPoint {
+        /// HTML snippet of the search suggestions
+        pub rendered_content: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GroundingChunk {
+        pub web: Option<WebSource>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct WebSource {
+        pub uri: String,
+        pub title: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GroundingSupport {
+        pub segment: Segment,
+        /// Indices of the supporting chunks in [GroundingMetadata::grounding_chunks].
+        #[serde(default)]
+        pub grounding_chunk_indices: Vec<usize>,
+        /// Confidence of each supporting chunk, in the same order.
+        #[serde(default)]
+        pub confidence_scores: Vec<f64>,
+    }
+
+    /// A span of the response, in bytes.
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Segment {
+        #[serde(default)]
+        pub start_index: usize,
+        #[serde(default)]
+        pub end_index: usize,
+        pub text: Option<String>,
+    }
+
+    impl GroundingMetadata {
+        /// The annotations of the response `text`: one per supporting chunk of each supported
+        /// span, and one without span for each web source supporting no span.
+        pub fn annotations(&self, text: &str) -> Vec<completion::Annotation> {
+            // The segments are delimited by byte offsets, the annotations by character offsets
+            let chars = |byte_index: usize| {
+                text.char_indices()
+                    .take_while(|&(i, _)| i < byte_index)
+                    .count()
+            };
+            let web = |i: usize| self.grounding_chunks.get(i)?.web.as_ref();
+
+            let mut annotations = self
+                .grounding_supports
+                .iter()
+                .flat_map(|support| {
+                    support
+                        .grounding_chunk_indices
+                        .iter()
+                        .enumerate()
+                        .filter_map(move |(i, &chunk)| Some((support, i, web(chunk)?)))
+                })
+                .map(|(support, i, web)| {
+                    let annotation = completion::Annotation::url(&web.uri, web.title.clone()).span(
+                        chars(support.segment.start_index),
+                        chars(support.segment.end_index),
+                    );
+                    match support.confidence_scores.get(i) {
+                        Some(&confidence) => annotation.confidence(confidence),
+                        None => annotation,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let supporting = self
+                .grounding_supports
+                .iter()
+                .flat_map(|support| support.grounding_chunk_indices.iter().copied())
+                .collect::<std::collections::HashSet<_>>();
+            annotations.extend(
+                (0..self.grounding_chunks.len())
+                    .filter(|i| !supporting.contains(i))
+                    .filter_map(web)
+                    .map(|web| completion::Annotation::url(&web.uri, web.title.clone())),
+            );
+            annotations
+        }
     }
     #[derive(Debug, Deserialize, Serialize)]
     pub struct Content {
@@ -268,11 +602,11 @@ pub mod gemini_api_types {
 
         fn try_from(msg: message::Message) -> Result<Self, Self::Error> {
             Ok(match msg {
-                message::Message::User { content } => Content {
+                message::Message::User { content, .. } => Content {
                     parts: content.try_map(|c| c.try_into())?,
                     role: Some(Role::User),
                 },
-                message::Message::Assistant { content } => Content {
+                message::Message::Assistant { content, .. } => Content {
                     role: Some(Role::Model),
                     parts: content.map(|content| content.into()),
                 },
@@ -331,6 +665,7 @@ pub mod gemini_api_types {
                             }
                         })
                     })?,
+                    name: None,
                 }),
                 Some(Role::Model) => Ok(message::Message::Assistant {
                     content: content.parts.try_map(|part| {
@@ -347,6 +682,7 @@ pub mod gemini_api_types {
                             }
                         })
                     })?,
+                    name: None,
                 }),
             }
         }
@@ -567,6 +903,9 @@ pub mod gemini_api_types {
     pub struct SafetyRating {
         pub category: HarmCategory,
         pub probability: HarmProbability,
+        /// Whether the content was blocked because of this rating.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub blocked: Option<bool>,
     }
 
     #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -930,8 +1269,34 @@ pub mod gemini_api_types {
     #[derive(Debug, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct Tool {
-        pub function_declarations: FunctionDeclaration,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub function_declarations: Option<FunctionDeclaration>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub code_execution: Option<CodeExecution>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub google_search: Option<GoogleSearch>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub google_search_retrieval: Option<GoogleSearchRetrieval>,
+    }
+
+    /// The Google Search tool of Gemini 2.0 models.
+    #[derive(Debug, Serialize)]
+    pub struct GoogleSearch {}
+
+    /// The Google Search retrieval tool of Gemini 1.5 models.
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GoogleSearchRetrieval {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dynamic_retrieval_config: Option<DynamicRetrievalConfig>,
+    }
+
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DynamicRetrievalConfig {
+        pub mode: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dynamic_threshold: Option<f64>,
     }
 
     #[derive(Debug, Serialize)]
@@ -953,14 +1318,14 @@ pub mod gemini_api_types {
     #[serde(rename_all = "camelCase")]
     pub struct CodeExecution {}
 
-    #[derive(Debug, Serialize)]
+    #[derive(Clone, Debug, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct SafetySetting {
         pub category: HarmCategory,
         pub threshold: HarmBlockThreshold,
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Clone, Debug, Serialize)]
     #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
     pub enum HarmBlockThreshold {
         HarmBlockThresholdUnspecified,
@@ -1122,6 +1487,7 @@ mod tests {
 
         let msg = message::Message::Assistant {
             content: OneOrMany::one(message::AssistantContent::ToolCall(tool_call)),
+            name: None,
         };
 
         let content: Content = msg.try_into().unwrap();
@@ -1137,4 +1503,95 @@ mod tests {
             panic!("Expected function call part");
         }
     }
+
+    #[test]
+    fn test_google_search_grounding() {
+        let model = Client::new("test-key")
+            .completion_model(GEMINI_2_0_FLASH)
+            .with_google_search(SearchGrounding::GoogleSearch);
+        let request = completion::CompletionModel::completion_request(&model, "Who won Euro 2024?");
+        let body = completion::CompletionModel::request_body(&model, request.build()).unwrap();
+        assert_eq!(body["tools"], json!([{"googleSearch": {}}]));
+
+        let raw_response = json!({
+            "candidates": [{
+                "content": {
+                    "parts": [{"text": "Éspaña won Euro 2024. It beat England."}],
+                    "role": "model"
+                },
+                "finishReason": "STOP",
+                "groundingMetadata": {
+                    "webSearchQueries": ["euro 2024 winner"],
+                    "searchEntryPoint": {"renderedContent": "<div></div>"},
+                    "groundingChunks": [
+                        {"web": {"uri": "https://uefa.com", "title": "uefa.com"}},
+                        {"web": {"uri": "https://bbc.com", "title": "bbc.com"}}
+                    ],
+                    "groundingSupports": [{
+                        "segment": {"startIndex": 0, "endIndex": 23, "text": "Éspaña won Euro 2024."},
+                        "groundingChunkIndices": [0],
+                        "confidenceScores": [0.95]
+                    }]
+                }
+            }]
+        });
+        let response: GenerateContentResponse = serde_json::from_value(raw_response).unwrap();
+        let response: completion::CompletionResponse<_> = response.try_into().unwrap();
+
+        assert_eq!(
+            response.annotations,
+            vec![
+                completion::Annotation::url("https://uefa.com", Some("uefa.com".to_string()))
+                    .span(0, 21)
+                    .confidence(0.95),
+                completion::Annotation::url("https://bbc.com", Some("bbc.com".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_safety_settings_and_blocked_response() {
+        let model = Client::new("test-key")
+            .completion_model(GEMINI_2_0_FLASH)
+            .with_safety_setting(
+                HarmCategory::HarmCategoryHarassment,
+                HarmBlockThreshold::BlockOnlyHigh,
+            )
+            .with_safety_setting(
+                HarmCategory::HarmCategoryHarassment,
+                HarmBlockThreshold::BlockNone,
+            );
+        let request = completion::CompletionModel::completion_request(&model, "Hello");
+        let body = completion::CompletionModel::request_body(&model, request.build()).unwrap();
+        assert_eq!(
+            body["safetySettings"],
+            json!([{"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE"}])
+        );
+
+        let raw_response = json!({
+            "candidates": [{
+                "finishReason": "SAFETY",
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "NEGLIGIBLE"},
+                    {"category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH", "blocked": true}
+                ]
+            }]
+        });
+        let response: GenerateContentResponse = serde_json::from_value(raw_response).unwrap();
+        let result: Result<completion::CompletionResponse<_>, _> = response.try_into();
+        assert!(matches!(
+            result,
+            Err(CompletionError::ContentFiltered(reason))
+                if reason == "Response blocked (Safety): HarmCategoryHarassment"
+        ));
+
+        let raw_response = json!({"promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}});
+        let response: GenerateContentResponse = serde_json::from_value(raw_response).unwrap();
+        let result: Result<completion::CompletionResponse<_>, _> = response.try_into();
+        assert!(matches!(
+            result,
+            Err(CompletionError::ContentFiltered(reason))
+                if reason == "Prompt blocked (ProhibitedContent)"
+        ));
+    }
 }
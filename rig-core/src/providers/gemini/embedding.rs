@@ -5,7 +5,10 @@
 
 use serde_json::json;
 
-use crate::embeddings::{self, EmbeddingError};
+use crate::{
+    embeddings::{self, EmbeddingError},
+    http::RequestOptions,
+};
 
 use super::{client::ApiResponse, Client};
 
@@ -45,6 +48,16 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
     async fn embed_texts(
         &self,
         documents: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(documents, RequestOptions::default())
+            .await
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn embed_texts_with_options(
+        &self,
+        documents: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents: Vec<_> = documents.into_iter().collect();
         let mut request_body = json!({
@@ -58,9 +71,11 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
             request_body["output_dimensionality"] = json!(ndims);
         }
 
-        let response = self
-            .client
-            .post(&format!("/v1beta/models/{}:embedContent", self.model))
+        let response = request_options
+            .apply(
+                self.client
+                    .post(&format!("/v1beta/models/{}:embedContent", self.model)),
+            )
             .json(&request_body)
             .send()
             .await?
@@ -0,0 +1,112 @@
+// ================================================================
+//! Google Gemini Streaming Integration
+//! From [Gemini API Reference](https://ai.google.dev/api/generate-content#method:-models.streamgeneratecontent)
+// ================================================================
+
+use async_stream::stream;
+use futures::StreamExt;
+
+use super::completion::{
+    gemini_api_types::{GenerateContentResponse, Part, UsageMetadata},
+    CompletionModel,
+};
+use crate::{
+    completion::{CompletionError, CompletionRequest},
+    sse,
+    streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult},
+    telemetry::TokenUsage,
+};
+
+impl From<&UsageMetadata> for TokenUsage {
+    fn from(usage: &UsageMetadata) -> Self {
+        Self {
+            input_tokens: usage.prompt_token_count.max(0) as u64,
+            output_tokens: usage.candidates_token_count.max(0) as u64,
+        }
+    }
+}
+
+impl StreamingCompletionModel for CompletionModel {
+    async fn stream(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<StreamingResult, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post(&format!(
+                "/v1beta/models/{}:streamGenerateContent",
+                self.model
+            )))
+            .query(&[("alt", "sse")])
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(CompletionError::ProviderError(response.text().await?));
+        }
+
+        Ok(Box::pin(stream! {
+            let mut events = std::pin::pin!(sse::events(response.bytes_stream()));
+            // Every chunk reports the usage so far, the last one is sent at the end of the stream
+            let mut usage = None;
+
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        yield Err(CompletionError::from(e));
+                        break;
+                    }
+                };
+
+                let response = match serde_json::from_str::<GenerateContentResponse>(&event.data) {
+                    Ok(response) => response,
+                    Err(e) => {
+                        yield Err(CompletionError::from(e));
+                        continue;
+                    }
+                };
+
+                if let Some(ref metadata) = response.usage_metadata {
+                    usage = Some(TokenUsage::from(metadata));
+                }
+
+                if let Some(reason) = response.block_reason() {
+                    yield Err(CompletionError::ContentFiltered(reason));
+                    break;
+                }
+
+                let Some(content) = response
+                    .candidates
+                    .into_iter()
+                    .next()
+                    .and_then(|candidate| candidate.content)
+                else {
+                    continue;
+                };
+                for part in content.parts {
+                    match part {
+                        Part::Text(text) if !text.is_empty() => {
+                            yield Ok(StreamingChoice::Message(text));
+                        }
+                        Part::FunctionCall(function_call) => {
+                            yield Ok(StreamingChoice::ToolCall(
+                                function_call.name.clone(),
+                                function_call.name,
+                                function_call.args,
+                            ));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if let Some(usage) = usage {
+                yield Ok(StreamingChoice::Usage(usage));
+            }
+        }))
+    }
+}
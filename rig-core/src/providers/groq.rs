@@ -146,6 +146,7 @@ impl TryFrom<Message> for message::Message {
                             message::MessageError::ConversionError("Empty user message".to_string())
                         })?,
                 ),
+                name: None,
             }),
             "assistant" => Ok(Self::Assistant {
                 content: OneOrMany::one(
@@ -158,6 +159,7 @@ impl TryFrom<Message> for message::Message {
                             )
                         })?,
                 ),
+                name: None,
             }),
             _ => Err(message::MessageError::ConversionError(format!(
                 "Unknown role: {}",
@@ -172,14 +174,14 @@ impl TryFrom<message::Message> for Message {
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         match message {
-            message::Message::User { content } => Ok(Self {
+            message::Message::User { content, .. } => Ok(Self {
                 role: "user".to_string(),
                 content: content.iter().find_map(|c| match c {
                     message::UserContent::Text(text) => Some(text.text.clone()),
                     _ => None,
                 }),
             }),
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, .. } => {
                 let mut text_content: Option<String> = None;
 
                 for c in content.iter() {
@@ -256,16 +258,11 @@ impl CompletionModel {
             model: model.to_string(),
         }
     }
-}
-
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
 
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message {
@@ -305,16 +302,27 @@ impl completion::CompletionModel for CompletionModel {
             })
         };
 
-        let response = self
-            .client
-            .post("/chat/completions")
-            .json(
-                &if let Some(params) = completion_request.additional_params {
-                    json_utils::merge(request, params)
-                } else {
-                    request
-                },
-            )
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/chat/completions"))
+            .json(&request)
             .send()
             .await?;
 
@@ -333,4 +341,11 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
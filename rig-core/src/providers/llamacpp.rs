@@ -0,0 +1,249 @@
+//! llama.cpp server client and Rig integration, for its native text completion endpoints
+//! (`/completion`, and `/infill` when a suffix is given). The chat endpoint of the server is
+//! OpenAI-compatible: use the [openai](crate::providers::openai) client with its URL instead.
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::llamacpp, text_completion::TextCompletionModel};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! // Create a new llama.cpp client (defaults to http://localhost:8080)
+//! let client = llamacpp::Client::new();
+//!
+//! let model = client.text_completion_model();
+//!
+//! let response = model
+//!     .text_completion_request("The capital of France is")
+//!     .max_tokens(16)
+//!     .send()
+//!     .await?;
+//! println!("llama.cpp completion: {}", response.text);
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    completion::{CompletionError, FinishReason},
+    json_utils,
+    text_completion::{self, FimCompletionModel, TextCompletionRequest},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+// ---------- Main Client ----------
+
+const LLAMACPP_API_BASE_URL: &str = "http://localhost:8080";
+
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    http_client: reqwest::Client,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self::from_url(LLAMACPP_API_BASE_URL)
+    }
+    pub fn from_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_owned(),
+            http_client: reqwest::Client::builder()
+                .build()
+                .expect("llama.cpp reqwest client should build"),
+        }
+    }
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path);
+        self.http_client.post(url)
+    }
+    /// Create a text completion model, completing with the model loaded by the server.
+    pub fn text_completion_model(&self) -> TextCompletionModel {
+        TextCompletionModel::new(self.clone())
+    }
+}
+
+// ---------- Text Completion API ----------
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TextCompletionResponse {
+    pub content: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub tokens_predicted: Option<u64>,
+    #[serde(default)]
+    pub tokens_evaluated: Option<u64>,
+    /// Why the generation stopped (`eos`, `word` or `limit`), in the recent versions of the server
+    #[serde(default)]
+    pub stop_type: Option<String>,
+    #[serde(default)]
+    pub stopped_eos: bool,
+    #[serde(default)]
+    pub stopped_word: bool,
+    #[serde(default)]
+    pub stopped_limit: bool,
+    #[serde(default)]
+    pub stopping_word: Option<String>,
+}
+
+impl TextCompletionResponse {
+    fn finish_reason(&self) -> Option<FinishReason> {
+        match self.stop_type.as_deref() {
+            Some("eos" | "word") => Some(FinishReason::Stop),
+            Some("limit") => Some(FinishReason::Length),
+            Some("none") => None,
+            Some(other) => Some(FinishReason::Other(other.to_string())),
+            None if self.stopped_limit => Some(FinishReason::Length),
+            None if self.stopped_eos || self.stopped_word => Some(FinishReason::Stop),
+            None => None,
+        }
+    }
+}
+
+impl From<TextCompletionResponse>
+    for text_completion::TextCompletionResponse<TextCompletionResponse>
+{
+    fn from(response: TextCompletionResponse) -> Self {
+        text_completion::TextCompletionResponse {
+            text: response.content.clone(),
+            finish_reason: response.finish_reason(),
+            raw_response: response,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TextCompletionModel {
+    client: Client,
+}
+
+impl TextCompletionModel {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Returns the endpoint and the body of the request: `/infill` when the request has a
+    /// suffix, `/completion` otherwise.
+    fn create_text_completion_request(
+        &self,
+        request: TextCompletionRequest,
+    ) -> (&'static str, serde_json::Value) {
+        let (path, mut body) = match request.suffix {
+            Some(suffix) => (
+                "infill",
+                json!({
+                    "input_prefix": request.prompt,
+                    "input_suffix": suffix,
+                }),
+            ),
+            None => ("completion", json!({ "prompt": request.prompt })),
+        };
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["n_predict"] = json!(max_tokens);
+        }
+        if !request.stop.is_empty() {
+            body["stop"] = json!(request.stop);
+        }
+
+        let body = match request.additional_params {
+            Some(params) => json_utils::merge(body, params),
+            None => body,
+        };
+        (path, body)
+    }
+}
+
+impl text_completion::TextCompletionModel for TextCompletionModel {
+    type Response = TextCompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn text_completion(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<text_completion::TextCompletionResponse<TextCompletionResponse>, CompletionError>
+    {
+        let (path, body) = self.create_text_completion_request(request);
+
+        let response = self.client.post(path).json(&body).send().await?;
+
+        if response.status().is_success() {
+            let response = response.json::<TextCompletionResponse>().await?;
+            tracing::info!(target: "rig",
+                "llama.cpp text completion token usage: {:?} prompt tokens, {:?} completion tokens",
+                response.tokens_evaluated, response.tokens_predicted
+            );
+            Ok(response.into())
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
+
+    fn request_body(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        Ok(self.create_text_completion_request(request).1)
+    }
+}
+
+impl FimCompletionModel for TextCompletionModel {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_completion::TextCompletionModel as _;
+
+    #[test]
+    fn test_infill_request() {
+        let model = Client::new().text_completion_model();
+
+        let request = model
+            .text_completion_request("fn add(a: i32, b: i32) -> i32 {")
+            .max_tokens(32)
+            .build();
+        assert_eq!(
+            model.create_text_completion_request(request),
+            (
+                "completion",
+                json!({"prompt": "fn add(a: i32, b: i32) -> i32 {", "n_predict": 32})
+            )
+        );
+
+        let request = model
+            .complete_fim("fn add(a: i32, b: i32) -> i32 {", "}")
+            .stop("\n}")
+            .build();
+        assert_eq!(
+            model.create_text_completion_request(request),
+            (
+                "infill",
+                json!({
+                    "input_prefix": "fn add(a: i32, b: i32) -> i32 {",
+                    "input_suffix": "}",
+                    "stop": ["\n}"],
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn test_finish_reason() {
+        let response: TextCompletionResponse =
+            serde_json::from_value(json!({"content": " a + b", "stop_type": "limit"})).unwrap();
+        assert_eq!(response.finish_reason(), Some(FinishReason::Length));
+
+        let response: TextCompletionResponse =
+            serde_json::from_value(json!({"content": " a + b", "stopped_eos": true})).unwrap();
+        let response: text_completion::TextCompletionResponse<_> = response.into();
+        assert_eq!(response.text, " a + b");
+        assert_eq!(response.finish_reason, Some(FinishReason::Stop));
+    }
+}
@@ -0,0 +1,251 @@
+//! Mistral API client and Rig integration, for the fill-in-the-middle (FIM) code completions of
+//! the Codestral models (see [FimCompletionModel]).
+//!
+//! # Example
+//! ```
+//! use rig::providers::mistral;
+//!
+//! let client = mistral::Client::new("YOUR_API_KEY");
+//!
+//! let codestral = client.fim_model(mistral::CODESTRAL_LATEST);
+//! ```
+use crate::{
+    completion::CompletionError,
+    json_utils,
+    providers::openai,
+    text_completion::{self, FimCompletionModel, TextCompletionRequest},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+// ================================================================
+// Main Mistral Client
+// ================================================================
+const MISTRAL_API_BASE_URL: &str = "https://api.mistral.ai";
+
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    http_client: reqwest::Client,
+}
+
+impl Client {
+    /// Create a new Mistral client with the given API key.
+    pub fn new(api_key: &str) -> Self {
+        Self::from_url(api_key, MISTRAL_API_BASE_URL)
+    }
+
+    /// Create a new Mistral client with the given API key and base API URL (e.g.:
+    /// `https://codestral.mistral.ai` for the Codestral specific API keys).
+    pub fn from_url(api_key: &str, base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            http_client: reqwest::Client::builder()
+                .default_headers({
+                    let mut headers = reqwest::header::HeaderMap::new();
+                    headers.insert(
+                        "Authorization",
+                        format!("Bearer {}", api_key)
+                            .parse()
+                            .expect("Bearer token should parse"),
+                    );
+                    headers
+                })
+                .build()
+                .expect("Mistral reqwest client should build"),
+        }
+    }
+
+    /// Create a new Mistral client from the `MISTRAL_API_KEY` environment variable.
+    /// Panics if the environment variable is not set.
+    pub fn from_env() -> Self {
+        let api_key = std::env::var("MISTRAL_API_KEY").expect("MISTRAL_API_KEY not set");
+        Self::new(&api_key)
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path);
+        self.http_client.post(url)
+    }
+
+    /// Create a fill-in-the-middle model with the given name.
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::mistral::{Client, self};
+    ///
+    /// // Initialize the Mistral client
+    /// let mistral = Client::new("your-mistral-api-key");
+    ///
+    /// let codestral = mistral.fim_model(mistral::CODESTRAL_LATEST);
+    /// ```
+    pub fn fim_model(&self, model: &str) -> FimModel {
+        FimModel::new(self.clone(), model)
+    }
+}
+
+// ================================================================
+// Mistral FIM API
+// ================================================================
+/// The `codestral-latest` model. Used for fill-in-the-middle code completion.
+pub const CODESTRAL_LATEST: &str = "codestral-latest";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FimResponse {
+    pub id: String,
+    pub model: String,
+    pub created: u64,
+    pub choices: Vec<FimChoice>,
+    pub usage: Option<openai::Usage>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FimChoice {
+    pub index: usize,
+    pub message: FimMessage,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FimMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl TryFrom<FimResponse> for text_completion::TextCompletionResponse<FimResponse> {
+    type Error = CompletionError;
+
+    fn try_from(response: FimResponse) -> Result<Self, Self::Error> {
+        let choice = response.choices.first().ok_or_else(|| {
+            CompletionError::ResponseError("Response contained no choices".to_owned())
+        })?;
+
+        Ok(text_completion::TextCompletionResponse {
+            text: choice.message.content.clone(),
+            finish_reason: choice.finish_reason.as_deref().map(Into::into),
+            raw_response: response,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct FimModel {
+    client: Client,
+    /// Name of the model (e.g.: codestral-latest)
+    pub model: String,
+}
+
+impl FimModel {
+    pub fn new(client: Client, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+        }
+    }
+
+    pub(crate) fn create_fim_request(&self, request: TextCompletionRequest) -> serde_json::Value {
+        let mut body = json!({
+            "model": self.model,
+            "prompt": request.prompt,
+        });
+        if let Some(suffix) = request.suffix {
+            body["suffix"] = json!(suffix);
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if !request.stop.is_empty() {
+            body["stop"] = json!(request.stop);
+        }
+
+        match request.additional_params {
+            Some(params) => json_utils::merge(body, params),
+            None => body,
+        }
+    }
+}
+
+impl text_completion::TextCompletionModel for FimModel {
+    type Response = FimResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn text_completion(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<text_completion::TextCompletionResponse<FimResponse>, CompletionError> {
+        let request = self.create_fim_request(request);
+
+        let response = self
+            .client
+            .post("v1/fim/completions")
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let response = response.json::<FimResponse>().await?;
+            tracing::info!(target: "rig",
+                "Mistral FIM token usage: {:?}",
+                response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
+            );
+            response.try_into()
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
+
+    fn request_body(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        Ok(self.create_fim_request(request))
+    }
+}
+
+impl FimCompletionModel for FimModel {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{completion::FinishReason, text_completion::TextCompletionModel as _};
+
+    #[test]
+    fn test_fim() {
+        let model = Client::new("test-key").fim_model(CODESTRAL_LATEST);
+        let request = model
+            .complete_fim("def add(a, b):\n", "\nprint(add(1, 2))")
+            .max_tokens(32)
+            .stop("\n\n")
+            .build();
+        assert_eq!(
+            model.request_body(request).unwrap(),
+            json!({
+                "model": CODESTRAL_LATEST,
+                "prompt": "def add(a, b):\n",
+                "suffix": "\nprint(add(1, 2))",
+                "max_tokens": 32,
+                "stop": ["\n\n"],
+            })
+        );
+
+        let response: FimResponse = serde_json::from_value(json!({
+            "id": "fim-1",
+            "object": "chat.completion",
+            "model": CODESTRAL_LATEST,
+            "created": 1,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "    return a + b", "tool_calls": null},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 6, "total_tokens": 18}
+        }))
+        .unwrap();
+        let response: text_completion::TextCompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(response.text, "    return a + b");
+        assert_eq!(response.finish_reason, Some(FinishReason::Stop));
+    }
+}
@@ -10,6 +10,8 @@
 //! - EternalAI
 //! - DeepSeek
 //! - Azure OpenAI
+//! - llama.cpp (text completions)
+//! - Mistral (Codestral fill-in-the-middle completions)
 //!
 //! Each provider has its own module, which contains a `Client` implementation that can
 //! be used to initialize completion and embedding models and execute requests to those models.
@@ -52,9 +54,13 @@ pub mod galadriel;
 pub mod gemini;
 pub mod groq;
 pub mod hyperbolic;
+pub mod llamacpp;
+pub mod mistral;
 pub mod moonshot;
 pub mod ollama;
 pub mod openai;
 pub mod perplexity;
 pub mod together;
 pub mod xai;
+
+pub use crate::json_utils::DeserializationMode;
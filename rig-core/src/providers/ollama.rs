@@ -43,6 +43,7 @@ use crate::{
     completion::{self, CompletionError, CompletionRequest},
     embeddings::{self, EmbeddingError, EmbeddingsBuilder},
     extractor::ExtractorBuilder,
+    http::RequestOptions,
     json_utils, message,
     message::{ImageDetail, Text},
     Embed, OneOrMany,
@@ -182,16 +183,25 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
     #[cfg_attr(feature = "worker", worker::send)]
     async fn embed_texts(
         &self,
-        documents: impl IntoIterator<Item = String>,
+        documents: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(documents, RequestOptions::default())
+            .await
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn embed_texts_with_options(
+        &self,
+        documents: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let docs: Vec<String> = documents.into_iter().collect();
         let payload = json!({
             "model": self.model,
             "input": docs,
         });
-        let response = self
-            .client
-            .post("api/embed")
+        let response = request_options
+            .apply(self.client.post("api/embed"))
             .json(&payload)
             .send()
             .await
@@ -252,6 +262,7 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             // Process only if an assistant message is present.
             Message::Assistant {
                 content,
+                name,
                 tool_calls,
                 ..
             } => {
@@ -286,12 +297,22 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
                     message: Message::Assistant {
                         content,
                         images: None,
-                        name: None,
+                        name,
                         tool_calls,
                     },
                 };
+                // Ollama reports the `stop` reason for tool calls
+                let finish_reason = match &raw_response.message {
+                    Message::Assistant { tool_calls, .. } if !tool_calls.is_empty() => {
+                        Some(completion::FinishReason::ToolCalls)
+                    }
+                    _ => raw_response.done_reason.as_deref().map(Into::into),
+                };
                 Ok(completion::CompletionResponse {
                     choice,
+                    alternatives: vec![],
+                    annotations: vec![],
+                    finish_reason,
                     raw_response,
                 })
             }
@@ -317,18 +338,11 @@ impl CompletionModel {
             model: model.to_owned(),
         }
     }
-}
 
-// ---------- CompletionModel Implementation ----------
-
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<Self::Response>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         // Convert internal prompt into a provider Message
         let prompt: Message = completion_request.prompt_with_context().try_into()?;
         let options = if let Some(extra) = completion_request.additional_params {
@@ -364,10 +378,25 @@ impl completion::CompletionModel for CompletionModel {
                 .collect::<Vec<ToolDefinition>>());
         }
 
+        Ok(request_payload)
+    }
+}
+
+// ---------- CompletionModel Implementation ----------
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<Self::Response>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request_payload = self.create_completion_request(completion_request)?;
         tracing::debug!(target: "rig", "Chat mode payload: {}", request_payload);
-        let response = self
-            .client
-            .post("api/chat")
+        let response = request_options
+            .apply(self.client.post("api/chat"))
             .json(&request_payload)
             .send()
             .await
@@ -390,6 +419,13 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(err_text))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
 
 // ---------- Tool Definition Conversion ----------
@@ -481,7 +517,7 @@ impl TryFrom<crate::message::Message> for Message {
     fn try_from(internal_msg: crate::message::Message) -> Result<Self, Self::Error> {
         use crate::message::Message as InternalMessage;
         match internal_msg {
-            InternalMessage::User { content, .. } => {
+            InternalMessage::User { content, name } => {
                 let mut texts = Vec::new();
                 let mut images = Vec::new();
                 for uc in content.into_iter() {
@@ -500,10 +536,10 @@ impl TryFrom<crate::message::Message> for Message {
                 Ok(Message::User {
                     content: content_str,
                     images: images_opt,
-                    name: None,
+                    name,
                 })
             }
-            InternalMessage::Assistant { content, .. } => {
+            InternalMessage::Assistant { content, name } => {
                 let mut texts = Vec::new();
                 let mut tool_calls = Vec::new();
                 for ac in content.into_iter() {
@@ -524,7 +560,7 @@ impl TryFrom<crate::message::Message> for Message {
                 Ok(Message::Assistant {
                     content: content_str,
                     images: None,
-                    name: None,
+                    name,
                     tool_calls,
                 })
             }
@@ -537,13 +573,15 @@ impl TryFrom<crate::message::Message> for Message {
 impl From<Message> for crate::completion::Message {
     fn from(msg: Message) -> Self {
         match msg {
-            Message::User { content, .. } => crate::completion::Message::User {
+            Message::User { content, name, .. } => crate::completion::Message::User {
                 content: OneOrMany::one(crate::completion::message::UserContent::Text(Text {
                     text: content,
                 })),
+                name,
             },
             Message::Assistant {
                 content,
+                name,
                 tool_calls,
                 ..
             } => {
@@ -562,6 +600,7 @@ impl From<Message> for crate::completion::Message {
                 }
                 crate::completion::Message::Assistant {
                     content: OneOrMany::many(assistant_contents).unwrap(),
+                    name,
                 }
             }
             // System and ToolResult are converted to User message as needed.
@@ -569,6 +608,7 @@ impl From<Message> for crate::completion::Message {
                 content: OneOrMany::one(crate::completion::message::UserContent::Text(Text {
                     text: content,
                 })),
+                name: None,
             },
             Message::ToolResult {
                 tool_call_id,
@@ -578,6 +618,7 @@ impl From<Message> for crate::completion::Message {
                     tool_call_id,
                     content.map(|content| message::ToolResultContent::text(content.text)),
                 )),
+                name: None,
             },
         }
     }
@@ -747,7 +788,7 @@ mod tests {
         // Convert it into a completion::Message.
         let comp_msg: crate::completion::Message = provider_msg.into();
         match comp_msg {
-            crate::completion::Message::User { content } => {
+            crate::completion::Message::User { content, .. } => {
                 // Assume OneOrMany<T> has a method first() to access the first element.
                 let first_content = content.first();
                 // The expected type is crate::completion::message::UserContent::Text wrapping a Text struct.
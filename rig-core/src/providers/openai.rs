@@ -15,15 +15,17 @@ use crate::{
     completion::{self, CompletionError, CompletionRequest},
     embeddings::{self, EmbeddingError, EmbeddingsBuilder},
     extractor::ExtractorBuilder,
+    http::RequestOptions,
     json_utils,
-    message::{self, AudioMediaType, ImageDetail},
+    message::{self, AudioMediaType, ImageDetail, MimeType},
     one_or_many::string_or_one_or_many,
+    providers::DeserializationMode,
     transcription::{self, TranscriptionError},
     Embed, OneOrMany,
 };
 use reqwest::multipart::Part;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 
 // ================================================================
@@ -35,6 +37,7 @@ const OPENAI_API_BASE_URL: &str = "https://api.openai.com/v1";
 pub struct Client {
     base_url: String,
     http_client: reqwest::Client,
+    deserialization_mode: DeserializationMode,
 }
 
 impl Client {
@@ -60,9 +63,17 @@ impl Client {
                 })
                 .build()
                 .expect("OpenAI reqwest client should build"),
+            deserialization_mode: DeserializationMode::default(),
         }
     }
 
+    /// Set how strictly the responses of the API are deserialized (defaults to
+    /// [DeserializationMode::Lenient]).
+    pub fn with_deserialization_mode(mut self, mode: DeserializationMode) -> Self {
+        self.deserialization_mode = mode;
+        self
+    }
+
     /// Create a new OpenAI client from the `OPENAI_API_KEY` environment variable.
     /// Panics if the environment variable is not set.
     pub fn from_env() -> Self {
@@ -70,11 +81,30 @@ impl Client {
         Self::new(&api_key)
     }
 
-    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+    pub(crate) fn post(&self, path: &str) -> reqwest::RequestBuilder {
         let url = format!("{}/{}", self.base_url, path).replace("//", "/");
         self.http_client.post(url)
     }
 
+    #[cfg(feature = "finetune")]
+    pub(crate) fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path).replace("//", "/");
+        self.http_client.get(url)
+    }
+
+    /// Parse a response of the API according to the deserialization mode of the client.
+    fn parse_response<T>(&self, body: &str) -> Result<ApiResponse<T>, serde_json::Error>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        match serde_json::from_str::<ApiResponse<T>>(body) {
+            Ok(ApiResponse::Err(err)) => Ok(ApiResponse::Err(err)),
+            _ => {
+                json_utils::from_str_with_mode(body, self.deserialization_mode).map(ApiResponse::Ok)
+            }
+        }
+    }
+
     /// Create an embedding model with the given name.
     /// Note: default embedding dimension of 0 will be used if model is not known.
     /// If this is the case, it's better to use function `embedding_model_with_ndims`
@@ -187,6 +217,23 @@ impl Client {
     pub fn transcription_model(&self, model: &str) -> TranscriptionModel {
         TranscriptionModel::new(self.clone(), model)
     }
+
+    /// Create a completion model using the Responses API, which supports OpenAI's hosted tools.
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::openai::{Client, HostedTool, self};
+    ///
+    /// // Initialize the OpenAI client
+    /// let openai = Client::new("your-open-ai-api-key");
+    ///
+    /// let gpt4o = openai
+    ///     .responses_model(openai::GPT_4O)
+    ///     .with_hosted_tool(HostedTool::web_search());
+    /// ```
+    pub fn responses_model(&self, model: &str) -> ResponsesCompletionModel {
+        ResponsesCompletionModel::new(self.clone(), model)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -241,7 +288,7 @@ pub struct EmbeddingData {
     pub index: usize,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Usage {
     pub prompt_tokens: usize,
     pub total_tokens: usize,
@@ -274,13 +321,22 @@ impl embeddings::EmbeddingModel for EmbeddingModel {
     #[cfg_attr(feature = "worker", worker::send)]
     async fn embed_texts(
         &self,
-        documents: impl IntoIterator<Item = String>,
+        documents: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(documents, RequestOptions::default())
+            .await
+    }
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn embed_texts_with_options(
+        &self,
+        documents: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
     ) -> Result<Vec<embeddings::Embedding>, EmbeddingError> {
         let documents = documents.into_iter().collect::<Vec<_>>();
 
-        let response = self
-            .client
-            .post("/embeddings")
+        let response = request_options
+            .apply(self.client.post("/embeddings"))
             .json(&json!({
                 "model": self.model,
                 "input": documents,
@@ -386,7 +442,7 @@ pub const GPT_35_TURBO_1106: &str = "gpt-3.5-turbo-1106";
 /// `gpt-3.5-turbo-instruct` completion model
 pub const GPT_35_TURBO_INSTRUCT: &str = "gpt-3.5-turbo-instruct";
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CompletionResponse {
     pub id: String,
     pub object: String,
@@ -411,7 +467,41 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             CompletionError::ResponseError("Response contained no choices".to_owned())
         })?;
 
-        let content = match &choice.message {
+        if let Some(reason) = choice.message.refusal() {
+            return Err(CompletionError::Refused {
+                reason: reason.to_string(),
+            });
+        }
+
+        let alternatives = response
+            .choices
+            .iter()
+            .skip(1)
+            .map(Choice::content)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(completion::CompletionResponse {
+            choice: choice.content()?,
+            annotations: vec![],
+            alternatives,
+            finish_reason: Some(choice.finish_reason.as_str().into()),
+            raw_response: response,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Choice {
+    pub index: usize,
+    pub message: Message,
+    pub logprobs: Option<serde_json::Value>,
+    pub finish_reason: String,
+}
+
+impl Choice {
+    /// The content of the message of the choice.
+    fn content(&self) -> Result<OneOrMany<completion::AssistantContent>, CompletionError> {
+        let content = match &self.message {
             Message::Assistant {
                 content,
                 tool_calls,
@@ -446,27 +536,14 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
             )),
         }?;
 
-        let choice = OneOrMany::many(content).map_err(|_| {
+        OneOrMany::many(content).map_err(|_| {
             CompletionError::ResponseError(
                 "Response contained no message or tool call (empty)".to_owned(),
             )
-        })?;
-
-        Ok(completion::CompletionResponse {
-            choice,
-            raw_response: response,
         })
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Choice {
-    pub index: usize,
-    pub message: Message,
-    pub logprobs: Option<serde_json::Value>,
-    pub finish_reason: String,
-}
-
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(tag = "role", rename_all = "lowercase")]
 pub enum Message {
@@ -501,6 +578,7 @@ pub enum Message {
     #[serde(rename = "tool")]
     ToolResult {
         tool_call_id: String,
+        #[serde(deserialize_with = "string_or_one_or_many")]
         content: OneOrMany<ToolResultContent>,
     },
 }
@@ -512,6 +590,21 @@ impl Message {
             name: None,
         }
     }
+
+    /// The refusal of the model, if the message is an assistant message refusing the prompt.
+    pub fn refusal(&self) -> Option<&str> {
+        match self {
+            Message::Assistant {
+                content, refusal, ..
+            } => refusal.as_deref().or_else(|| {
+                content.iter().find_map(|content| match content {
+                    AssistantContent::Refusal { refusal } => Some(refusal.as_str()),
+                    AssistantContent::Text { .. } => None,
+                })
+            }),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -543,9 +636,16 @@ pub enum AssistantContent {
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum UserContent {
-    Text { text: String },
-    Image { image_url: ImageUrl },
-    Audio { input_audio: InputAudio },
+    Text {
+        text: String,
+    },
+    #[serde(rename = "image_url", alias = "image")]
+    Image {
+        image_url: ImageUrl,
+    },
+    Audio {
+        input_audio: InputAudio,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -629,12 +729,29 @@ pub struct Function {
     pub arguments: serde_json::Value,
 }
 
+/// The URL of an image sent to the OpenAI API: URLs are sent as is, and base64 encoded images
+/// as `data:` URLs (as JPEG, if the media type is unknown).
+fn image_url(image: &message::Image) -> String {
+    let is_url = ["https://", "http://", "data:"]
+        .iter()
+        .any(|prefix| image.data.starts_with(prefix));
+    if is_url || image.format == Some(message::ContentFormat::String) {
+        return image.data.clone();
+    }
+
+    let media_type = image
+        .media_type
+        .as_ref()
+        .map_or("image/jpeg", |media_type| media_type.to_mime_type());
+    format!("data:{media_type};base64,{}", image.data)
+}
+
 impl TryFrom<message::Message> for Vec<Message> {
     type Error = message::MessageError;
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         match message {
-            message::Message::User { content } => {
+            message::Message::User { content, name } => {
                 let (tool_results, other_content): (Vec<_>, Vec<_>) = content
                     .into_iter()
                     .partition(|content| matches!(content, message::UserContent::ToolResult(_)));
@@ -672,12 +789,10 @@ impl TryFrom<message::Message> for Vec<Message> {
                             message::UserContent::Text(message::Text { text }) => {
                                 UserContent::Text { text }
                             }
-                            message::UserContent::Image(message::Image {
-                                data, detail, ..
-                            }) => UserContent::Image {
+                            message::UserContent::Image(image) => UserContent::Image {
                                 image_url: ImageUrl {
-                                    url: data,
-                                    detail: detail.unwrap_or_default(),
+                                    url: image_url(&image),
+                                    detail: image.detail.unwrap_or_default(),
                                 },
                             },
                             message::UserContent::Document(message::Document { data, .. }) => {
@@ -698,11 +813,11 @@ impl TryFrom<message::Message> for Vec<Message> {
                             },
                             _ => unreachable!(),
                         }),
-                        name: None,
+                        name,
                     }])
                 }
             }
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, name } => {
                 let (text_content, tool_calls) = content.into_iter().fold(
                     (Vec::new(), Vec::new()),
                     |(mut texts, mut tools), content| {
@@ -723,7 +838,7 @@ impl TryFrom<message::Message> for Vec<Message> {
                         .collect::<Vec<_>>(),
                     refusal: None,
                     audio: None,
-                    name: None,
+                    name,
                     tool_calls: tool_calls
                         .into_iter()
                         .map(|tool_call| tool_call.into())
@@ -764,12 +879,14 @@ impl TryFrom<Message> for message::Message {
 
     fn try_from(message: Message) -> Result<Self, Self::Error> {
         Ok(match message {
-            Message::User { content, .. } => message::Message::User {
+            Message::User { content, name } => message::Message::User {
                 content: content.map(|content| content.into()),
+                name,
             },
             Message::Assistant {
                 content,
                 tool_calls,
+                name,
                 ..
             } => {
                 let mut content = content
@@ -799,6 +916,7 @@ impl TryFrom<Message> for message::Message {
                                 .to_owned(),
                         )
                     })?,
+                    name,
                 }
             }
 
@@ -810,12 +928,14 @@ impl TryFrom<Message> for message::Message {
                     tool_call_id,
                     content.map(|content| message::ToolResultContent::text(content.text)),
                 )),
+                name: None,
             },
 
             // System messages should get stripped out when converting message's, this is just a
             // stop gap to avoid obnoxious error handling or panic occuring.
             Message::System { content, .. } => message::Message::User {
                 content: content.map(|content| message::UserContent::text(content.text)),
+                name: None,
             },
         })
     }
@@ -905,16 +1025,11 @@ impl CompletionModel {
             model: model.to_string(),
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    pub(crate) fn create_completion_request(
         &self,
         completion_request: CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<serde_json::Value, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => vec![Message::system(preamble)],
@@ -966,16 +1081,32 @@ impl completion::CompletionModel for CompletionModel {
             request
         };
 
-        let response = self
-            .client
-            .post("/chat/completions")
-            .json(
-                &if let Some(params) = completion_request.additional_params {
-                    json_utils::merge(request, params)
-                } else {
-                    request
-                },
-            )
+        let request = match completion_request.n {
+            Some(n) => json_utils::merge(request, json!({ "n": n })),
+            None => request,
+        };
+
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/chat/completions"))
+            .json(&request)
             .send()
             .await?;
 
@@ -983,7 +1114,7 @@ impl completion::CompletionModel for CompletionModel {
             let t = response.text().await?;
             tracing::debug!(target: "rig", "OpenAI completion error: {}", t);
 
-            match serde_json::from_str::<ApiResponse<CompletionResponse>>(&t)? {
+            match self.client.parse_response::<CompletionResponse>(&t)? {
                 ApiResponse::Ok(response) => {
                     tracing::info!(target: "rig",
                         "OpenAI completion token usage: {:?}",
@@ -997,6 +1128,13 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
 
 // ================================================================
@@ -1098,6 +1236,560 @@ impl transcription::TranscriptionModel for TranscriptionModel {
     }
 }
 
+// ================================================================
+// OpenAI Responses API
+// ================================================================
+
+/// Tools hosted and run by OpenAI, only available with the Responses API
+/// (see [ResponsesCompletionModel::with_hosted_tool]).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostedTool {
+    /// Lets the model search the web. Results are cited with [Annotation::UrlCitation]s.
+    #[serde(rename = "web_search_preview")]
+    WebSearch {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        search_context_size: Option<SearchContextSize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        user_location: Option<UserLocation>,
+    },
+    /// Lets the model search files uploaded to OpenAI vector stores.
+    /// Results are cited with [Annotation::FileCitation]s.
+    FileSearch {
+        vector_store_ids: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_num_results: Option<u32>,
+    },
+}
+
+impl HostedTool {
+    /// Web search tool with the default options.
+    pub fn web_search() -> Self {
+        Self::WebSearch {
+            search_context_size: None,
+            user_location: None,
+        }
+    }
+
+    /// File search tool over the given vector stores.
+    pub fn file_search(vector_store_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::FileSearch {
+            vector_store_ids: vector_store_ids.into_iter().map(Into::into).collect(),
+            max_num_results: None,
+        }
+    }
+}
+
+/// Amount of context retrieved from the web by the web search tool.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchContextSize {
+    Low,
+    Medium,
+    High,
+}
+
+/// Approximate location of the user, used to refine web search results.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename = "approximate")]
+pub struct UserLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    /// Two-letter ISO country code (e.g.: `US`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// IANA timezone (e.g.: `America/Chicago`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Item of the `input` of a Responses API request.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum InputItem {
+    Message {
+        role: String,
+        content: Vec<InputContent>,
+    },
+    Item(ResponseItem),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputContent {
+    InputText {
+        text: String,
+    },
+    InputImage {
+        image_url: String,
+        detail: ImageDetail,
+    },
+    OutputText {
+        text: String,
+    },
+}
+
+/// Typed items (other than messages) of the input and output of a Responses API request.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseItem {
+    FunctionCall {
+        call_id: String,
+        name: String,
+        #[serde(with = "json_utils::stringified_json")]
+        arguments: serde_json::Value,
+    },
+    FunctionCallOutput {
+        call_id: String,
+        output: String,
+    },
+}
+
+impl TryFrom<message::Message> for Vec<InputItem> {
+    type Error = message::MessageError;
+
+    fn try_from(message: message::Message) -> Result<Self, Self::Error> {
+        let mut items = vec![];
+
+        match message {
+            message::Message::User { content, .. } => {
+                let mut contents = vec![];
+                for content in content {
+                    match content {
+                        message::UserContent::Text(message::Text { text })
+                        | message::UserContent::Document(message::Document {
+                            data: text, ..
+                        }) => contents.push(InputContent::InputText { text }),
+                        message::UserContent::Image(image) => {
+                            contents.push(InputContent::InputImage {
+                                image_url: image_url(&image),
+                                detail: image.detail.unwrap_or_default(),
+                            })
+                        }
+                        message::UserContent::ToolResult(message::ToolResult { id, content }) => {
+                            let output = content
+                                .into_iter()
+                                .map(|content| match content {
+                                    message::ToolResultContent::Text(message::Text { text }) => {
+                                        Ok(text)
+                                    }
+                                    _ => Err(message::MessageError::ConversionError(
+                                        "Tool result content does not support non-text".into(),
+                                    )),
+                                })
+                                .collect::<Result<Vec<_>, _>>()?
+                                .join("\n");
+                            items.push(InputItem::Item(ResponseItem::FunctionCallOutput {
+                                call_id: id,
+                                output,
+                            }))
+                        }
+                        message::UserContent::Audio(_) => {
+                            return Err(message::MessageError::ConversionError(
+                                "Audio input is not supported by the OpenAI Responses API".into(),
+                            ))
+                        }
+                    }
+                }
+
+                if !contents.is_empty() {
+                    items.push(InputItem::Message {
+                        role: "user".to_string(),
+                        content: contents,
+                    });
+                }
+            }
+            message::Message::Assistant { content, .. } => {
+                for content in content {
+                    match content {
+                        message::AssistantContent::Text(message::Text { text }) => {
+                            items.push(InputItem::Message {
+                                role: "assistant".to_string(),
+                                content: vec![InputContent::OutputText { text }],
+                            })
+                        }
+                        message::AssistantContent::ToolCall(tool_call) => {
+                            items.push(InputItem::Item(ResponseItem::FunctionCall {
+                                call_id: tool_call.id,
+                                name: tool_call.function.name,
+                                arguments: tool_call.function.arguments,
+                            }))
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+/// Raw response of the Responses API.
+#[derive(Debug, Deserialize)]
+pub struct ResponsesCompletionResponse {
+    pub id: String,
+    pub model: String,
+    /// `completed`, or `incomplete` when the response was cut (see `incomplete_details`)
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub incomplete_details: Option<IncompleteDetails>,
+    pub output: Vec<OutputItem>,
+    pub usage: Option<ResponsesUsage>,
+}
+
+/// Why a response of the Responses API is incomplete.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct IncompleteDetails {
+    /// `max_output_tokens` or `content_filter`
+    pub reason: String,
+}
+
+impl ResponsesCompletionResponse {
+    /// All the citations attached to the text generated by the model
+    /// (e.g.: the sources of the web search and file search tools).
+    pub fn annotations(&self) -> Vec<&Annotation> {
+        self.output
+            .iter()
+            .flat_map(|item| match item {
+                OutputItem::Message { content, .. } => content.iter().collect(),
+                _ => vec![],
+            })
+            .flat_map(|content| match content {
+                OutputContent::OutputText { annotations, .. } => annotations.iter().collect(),
+                _ => vec![],
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResponsesUsage {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub total_tokens: usize,
+}
+
+impl std::fmt::Display for ResponsesUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Input tokens: {} Output tokens: {} Total tokens: {}",
+            self.input_tokens, self.output_tokens, self.total_tokens
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputItem {
+    Message {
+        id: String,
+        content: Vec<OutputContent>,
+    },
+    FunctionCall {
+        call_id: String,
+        name: String,
+        #[serde(with = "json_utils::stringified_json")]
+        arguments: serde_json::Value,
+    },
+    WebSearchCall {
+        id: String,
+        status: String,
+    },
+    FileSearchCall {
+        id: String,
+        status: String,
+        #[serde(default)]
+        queries: Vec<String>,
+    },
+    /// Output items not (yet) supported by rig (e.g.: reasoning).
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputContent {
+    OutputText {
+        text: String,
+        #[serde(default)]
+        annotations: Vec<Annotation>,
+    },
+    Refusal {
+        refusal: String,
+    },
+}
+
+/// Citation attached to a span of the text generated by the model.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    /// Web page cited by the web search tool. The indices delimit the cited span of the text.
+    UrlCitation {
+        start_index: usize,
+        end_index: usize,
+        url: String,
+        title: String,
+    },
+    /// File cited by the file search tool. `index` is the position of the citation in the text.
+    FileCitation {
+        file_id: String,
+        index: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+    },
+    /// File generated by the model (e.g.: by the code interpreter).
+    FilePath { file_id: String, index: usize },
+}
+
+impl TryFrom<ResponsesCompletionResponse>
+    for completion::CompletionResponse<ResponsesCompletionResponse>
+{
+    type Error = CompletionError;
+
+    fn try_from(response: ResponsesCompletionResponse) -> Result<Self, Self::Error> {
+        let refusal = response.output.iter().find_map(|item| match item {
+            OutputItem::Message { content, .. } => {
+                content.iter().find_map(|content| match content {
+                    OutputContent::Refusal { refusal } => Some(refusal),
+                    OutputContent::OutputText { .. } => None,
+                })
+            }
+            _ => None,
+        });
+        if let Some(refusal) = refusal {
+            return Err(CompletionError::Refused {
+                reason: refusal.clone(),
+            });
+        }
+
+        let content = response
+            .output
+            .iter()
+            .flat_map(|item| match item {
+                OutputItem::Message { content, .. } => content
+                    .iter()
+                    .map(|content| match content {
+                        OutputContent::OutputText { text, .. } => {
+                            completion::AssistantContent::text(text)
+                        }
+                        OutputContent::Refusal { refusal } => {
+                            completion::AssistantContent::text(refusal)
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+                OutputItem::FunctionCall {
+                    call_id,
+                    name,
+                    arguments,
+                } => vec![completion::AssistantContent::tool_call(
+                    call_id,
+                    name,
+                    arguments.clone(),
+                )],
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+
+        let choice = OneOrMany::many(content).map_err(|_| {
+            CompletionError::ResponseError(
+                "Response contained no message or tool call (empty)".to_owned(),
+            )
+        })?;
+
+        let annotations = response
+            .annotations()
+            .into_iter()
+            .filter_map(|annotation| annotation.clone().into())
+            .collect();
+
+        let finish_reason = match &response.incomplete_details {
+            Some(details) => match details.reason.as_str() {
+                "max_output_tokens" => completion::FinishReason::Length,
+                "content_filter" => completion::FinishReason::ContentFilter,
+                other => completion::FinishReason::Other(other.to_string()),
+            },
+            None if response
+                .output
+                .iter()
+                .any(|item| matches!(item, OutputItem::FunctionCall { .. })) =>
+            {
+                completion::FinishReason::ToolCalls
+            }
+            None => completion::FinishReason::Stop,
+        };
+
+        Ok(completion::CompletionResponse {
+            choice,
+            annotations,
+            alternatives: vec![],
+            finish_reason: Some(finish_reason),
+            raw_response: response,
+        })
+    }
+}
+
+impl From<Annotation> for Option<completion::Annotation> {
+    fn from(annotation: Annotation) -> Self {
+        match annotation {
+            Annotation::UrlCitation {
+                start_index,
+                end_index,
+                url,
+                title,
+            } => Some(completion::Annotation::url(url, Some(title)).span(start_index, end_index)),
+            // File citations point at a position of the text rather than a span
+            Annotation::FileCitation {
+                file_id,
+                index,
+                filename,
+            } => Some(
+                completion::Annotation {
+                    start_index: None,
+                    end_index: None,
+                    source: completion::AnnotationSource::File { file_id, filename },
+                    confidence: None,
+                }
+                .span(index, index),
+            ),
+            Annotation::FilePath { .. } => None,
+        }
+    }
+}
+
+/// Completion model using the OpenAI Responses API (`/responses`) instead of the
+/// Chat Completions API, which is required to use OpenAI's [HostedTool]s.
+#[derive(Clone)]
+pub struct ResponsesCompletionModel {
+    client: Client,
+    /// Name of the model (e.g.: gpt-4o)
+    pub model: String,
+    /// Tools hosted by OpenAI made available to the model on every request.
+    pub hosted_tools: Vec<HostedTool>,
+}
+
+impl ResponsesCompletionModel {
+    pub fn new(client: Client, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            hosted_tools: vec![],
+        }
+    }
+
+    /// Make a tool hosted by OpenAI (e.g.: web search) available to the model.
+    pub fn with_hosted_tool(mut self, tool: HostedTool) -> Self {
+        self.hosted_tools.push(tool);
+        self
+    }
+
+    pub(crate) fn create_completion_request(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        // Convert prompt to input items
+        let prompt: Vec<InputItem> = completion_request.prompt_with_context().try_into()?;
+
+        // Convert existing chat history
+        let mut input: Vec<InputItem> = completion_request
+            .chat_history
+            .into_iter()
+            .map(|message| message.try_into())
+            .collect::<Result<Vec<Vec<InputItem>>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        input.extend(prompt);
+
+        let mut tools = completion_request
+            .tools
+            .into_iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                })
+            })
+            .collect::<Vec<_>>();
+        tools.extend(
+            self.hosted_tools
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+
+        let mut request = json!({
+            "model": self.model,
+            "input": input,
+        });
+
+        if let Some(preamble) = completion_request.preamble {
+            json_utils::merge_inplace(&mut request, json!({ "instructions": preamble }));
+        }
+
+        if !tools.is_empty() {
+            json_utils::merge_inplace(&mut request, json!({ "tools": tools }));
+        }
+
+        // only include temperature if it exists
+        // because some models don't support temperature
+        if let Some(temperature) = completion_request.temperature {
+            json_utils::merge_inplace(&mut request, json!({ "temperature": temperature }));
+        }
+
+        if let Some(max_tokens) = completion_request.max_tokens {
+            json_utils::merge_inplace(&mut request, json!({ "max_output_tokens": max_tokens }));
+        }
+
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for ResponsesCompletionModel {
+    type Response = ResponsesCompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<completion::CompletionResponse<ResponsesCompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/responses"))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let response = response.json::<ResponsesCompletionResponse>().await?;
+            tracing::info!(target: "rig",
+                "OpenAI responses token usage: {}",
+                response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
+            );
+            response.try_into()
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
+
+    fn request_body(
+        &self,
+        completion_request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1297,10 +1989,12 @@ mod tests {
     fn test_message_to_message_conversion() {
         let user_message = message::Message::User {
             content: OneOrMany::one(message::UserContent::text("Hello")),
+            name: None,
         };
 
         let assistant_message = message::Message::Assistant {
             content: OneOrMany::one(message::AssistantContent::text("Hi there!")),
+            name: None,
         };
 
         let converted_user_message: Vec<Message> = user_message.clone().try_into().unwrap();
@@ -1364,14 +2058,14 @@ mod tests {
             assistant_message.clone().try_into().unwrap();
 
         match converted_user_message.clone() {
-            message::Message::User { content } => {
+            message::Message::User { content, .. } => {
                 assert_eq!(content.first(), message::UserContent::text("Hello"));
             }
             _ => panic!("Expected user message"),
         }
 
         match converted_assistant_message.clone() {
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, .. } => {
                 assert_eq!(
                     content.first(),
                     message::AssistantContent::text("Hi there!")
@@ -1387,4 +2081,283 @@ mod tests {
         assert_eq!(original_user_message[0], user_message);
         assert_eq!(original_assistant_message[0], assistant_message);
     }
+
+    #[test]
+    fn test_responses_request() {
+        let model = Client::new("key")
+            .responses_model(GPT_4O)
+            .with_hosted_tool(HostedTool::web_search());
+
+        let request = model
+            .create_completion_request(CompletionRequest {
+                prompt: message::Message::User {
+                    content: OneOrMany::one(message::UserContent::tool_result(
+                        "call_1",
+                        OneOrMany::one(message::ToolResultContent::text("Sunny")),
+                    )),
+                    name: None,
+                },
+                preamble: Some("You are a weather bot".to_string()),
+                chat_history: vec![
+                    message::Message::user("What is the weather in Paris?"),
+                    message::Message::Assistant {
+                        content: OneOrMany::one(message::AssistantContent::tool_call(
+                            "call_1",
+                            "get_weather",
+                            json!({"city": "Paris"}),
+                        )),
+                        name: None,
+                    },
+                ],
+                documents: vec![],
+                tools: vec![],
+                temperature: None,
+                max_tokens: None,
+                n: None,
+                additional_params: None,
+                request_options: Default::default(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            request,
+            json!({
+                "model": "gpt-4o",
+                "instructions": "You are a weather bot",
+                "input": [
+                    {"role": "user", "content": [{"type": "input_text", "text": "What is the weather in Paris?"}]},
+                    {"type": "function_call", "call_id": "call_1", "name": "get_weather", "arguments": "{\"city\":\"Paris\"}"},
+                    {"type": "function_call_output", "call_id": "call_1", "output": "Sunny"},
+                ],
+                "tools": [{"type": "web_search_preview"}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_image_urls() {
+        let message = message::Message::User {
+            content: OneOrMany::many(vec![
+                message::UserContent::image(
+                    "aGVsbG8=",
+                    Some(message::ContentFormat::Base64),
+                    Some(message::ImageMediaType::PNG),
+                    None,
+                ),
+                message::UserContent::image(
+                    "https://example.com/cat.jpg",
+                    Some(message::ContentFormat::String),
+                    None,
+                    None,
+                ),
+            ])
+            .unwrap(),
+            name: None,
+        };
+
+        let items: Vec<InputItem> = message.clone().try_into().unwrap();
+        let items = serde_json::to_value(items).unwrap();
+        assert_eq!(
+            items[0]["content"][0]["image_url"],
+            "data:image/png;base64,aGVsbG8="
+        );
+        assert_eq!(
+            items[0]["content"][1]["image_url"],
+            "https://example.com/cat.jpg"
+        );
+
+        let messages: Vec<Message> = message.try_into().unwrap();
+        let messages = serde_json::to_value(messages).unwrap();
+        assert_eq!(
+            messages[0]["content"][0]["image_url"]["url"],
+            "data:image/png;base64,aGVsbG8="
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        use completion::CompletionModel as _;
+
+        let request = Client::new("key")
+            .completion_model(GPT_4O)
+            .completion_request("Hello")
+            .preamble("You are a helpful assistant".to_string())
+            .temperature(0.5)
+            .additional_params(json!({"temperature": 0.2, "seed": 42}))
+            .dry_run()
+            .unwrap();
+
+        assert_eq!(
+            request,
+            json!({
+                "model": "gpt-4o",
+                "messages": [
+                    {"role": "system", "content": [{"type": "text", "text": "You are a helpful assistant"}]},
+                    {"role": "user", "content": [{"type": "text", "text": "Hello"}]},
+                ],
+                "temperature": 0.2,
+                "seed": 42,
+            })
+        );
+    }
+
+    #[test]
+    fn test_responses_response_annotations() {
+        let response: ResponsesCompletionResponse = serde_json::from_value(json!({
+            "id": "resp_1",
+            "model": "gpt-4o",
+            "output": [
+                {"type": "web_search_call", "id": "ws_1", "status": "completed"},
+                {"type": "reasoning", "id": "rs_1", "summary": []},
+                {
+                    "type": "message",
+                    "id": "msg_1",
+                    "role": "assistant",
+                    "status": "completed",
+                    "content": [{
+                        "type": "output_text",
+                        "text": "Rig is a Rust library.",
+                        "annotations": [{
+                            "type": "url_citation",
+                            "start_index": 0,
+                            "end_index": 22,
+                            "url": "https://rig.rs",
+                            "title": "Rig"
+                        }]
+                    }]
+                }
+            ],
+            "usage": {"input_tokens": 10, "output_tokens": 5, "total_tokens": 15}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            response.annotations(),
+            vec![&Annotation::UrlCitation {
+                start_index: 0,
+                end_index: 22,
+                url: "https://rig.rs".to_string(),
+                title: "Rig".to_string(),
+            }]
+        );
+
+        let response: completion::CompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(
+            response.choice.first(),
+            completion::AssistantContent::text("Rig is a Rust library.")
+        );
+        assert_eq!(
+            response.annotations,
+            vec![
+                completion::Annotation::url("https://rig.rs", Some("Rig".to_string())).span(0, 22)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_refusal() {
+        let response: CompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "refusal": "I can't help with that."
+                },
+                "logprobs": null,
+                "finish_reason": "stop"
+            }]
+        }))
+        .unwrap();
+
+        let result: Result<completion::CompletionResponse<_>, _> = response.try_into();
+        let error = result.unwrap_err();
+        assert!(error.is_refusal());
+        assert!(matches!(
+            error,
+            CompletionError::Refused { reason } if reason == "I can't help with that."
+        ));
+    }
+
+    #[test]
+    fn test_multiple_choices() {
+        let model = Client::new("test-key").completion_model(GPT_4O);
+        let request = completion::CompletionModel::completion_request(&model, "Name a color")
+            .n(2)
+            .build();
+        let body = completion::CompletionModel::request_body(&model, request).unwrap();
+        assert_eq!(body["n"], 2);
+
+        let choice = |index: usize, text: &str| {
+            serde_json::json!({
+                "index": index,
+                "message": {"role": "assistant", "content": text},
+                "logprobs": null,
+                "finish_reason": "stop"
+            })
+        };
+        let response: CompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "gpt-4o",
+            "choices": [choice(0, "Red"), choice(1, "Blue")]
+        }))
+        .unwrap();
+
+        let response: completion::CompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(
+            response
+                .choices()
+                .map(|choice| choice.first())
+                .collect::<Vec<_>>(),
+            vec![
+                completion::AssistantContent::text("Red"),
+                completion::AssistantContent::text("Blue")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_names_and_tool_call_ids() {
+        let tool_call = message::Message::Assistant {
+            content: OneOrMany::one(message::AssistantContent::tool_call(
+                "call_1",
+                "add",
+                serde_json::json!({"x": 1, "y": 2}),
+            )),
+            name: None,
+        }
+        .with_name("planner");
+        let tool_result = message::Message::User {
+            content: OneOrMany::one(message::UserContent::tool_result(
+                "call_1",
+                OneOrMany::one(message::ToolResultContent::text("3")),
+            )),
+            name: None,
+        };
+        let prompt = message::Message::user("Thanks!").with_name("alice");
+
+        let messages = [tool_call.clone(), tool_result.clone(), prompt.clone()]
+            .into_iter()
+            .map(|message| Vec::<Message>::try_from(message).unwrap())
+            .collect::<Vec<_>>()
+            .concat();
+
+        let json = serde_json::to_value(&messages).unwrap();
+        assert_eq!(json[0]["name"], "planner");
+        assert_eq!(json[0]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(json[1]["tool_call_id"], "call_1");
+        assert_eq!(json[2]["name"], "alice");
+
+        let messages = messages
+            .into_iter()
+            .map(|message| message::Message::try_from(message).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec![tool_call, tool_result, prompt]);
+    }
 }
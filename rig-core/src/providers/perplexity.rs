@@ -11,7 +11,7 @@
 
 use crate::{
     agent::AgentBuilder,
-    completion::{self, message, CompletionError, MessageError},
+    completion::{self, message, roles::RoleMapping, CompletionError, MessageError},
     extractor::ExtractorBuilder,
     json_utils, OneOrMany,
 };
@@ -112,6 +112,9 @@ pub struct CompletionResponse {
     #[serde(default)]
     pub choices: Vec<Choice>,
     pub usage: Usage,
+    /// URLs of the sources used to generate the response.
+    #[serde(default)]
+    pub citations: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -173,6 +176,13 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
                 content,
             } => Ok(completion::CompletionResponse {
                 choice: OneOrMany::one(content.clone().into()),
+                annotations: response
+                    .citations
+                    .iter()
+                    .map(|url| completion::Annotation::url(url, None))
+                    .collect(),
+                alternatives: vec![],
+                finish_reason: Some(choice.finish_reason.as_str().into()),
                 raw_response: response,
             }),
             _ => Err(CompletionError::ResponseError(
@@ -202,7 +212,7 @@ impl TryFrom<message::Message> for Message {
 
     fn try_from(message: message::Message) -> Result<Self, Self::Error> {
         Ok(match message {
-            message::Message::User { content } => {
+            message::Message::User { content, .. } => {
                 let collapsed_content = content
                     .into_iter()
                     .map(|content| match content {
@@ -220,7 +230,7 @@ impl TryFrom<message::Message> for Message {
                 }
             }
 
-            message::Message::Assistant { content } => {
+            message::Message::Assistant { content, .. } => {
                 let collapsed_content = content
                     .into_iter()
                     .map(|content| {
@@ -257,14 +267,15 @@ impl From<Message> for message::Message {
     }
 }
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
-
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+impl CompletionModel {
+    pub(crate) fn create_completion_request(
         &self,
-        completion_request: completion::CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        mut completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        // Perplexity requires alternating user and assistant messages after the system message
+        completion_request
+            .normalize_roles(RoleMapping::NONE.merge_consecutive(true).leading_user(true));
+
         // Add context documents to current prompt
         let prompt_with_context = completion_request.prompt_with_context();
 
@@ -301,16 +312,27 @@ impl completion::CompletionModel for CompletionModel {
             "temperature": completion_request.temperature,
         });
 
-        let response = self
-            .client
-            .post("/chat/completions")
-            .json(
-                &if let Some(ref params) = completion_request.additional_params {
-                    json_utils::merge(request.clone(), params.clone())
-                } else {
-                    request.clone()
-                },
-            )
+        Ok(match completion_request.additional_params {
+            Some(params) => json_utils::merge(request, params),
+            None => request,
+        })
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/chat/completions"))
+            .json(&request)
             .send()
             .await?;
 
@@ -329,6 +351,13 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -381,4 +410,34 @@ mod tests {
         assert_eq!(user_message, back_to_user_message);
         assert_eq!(assistant_message, back_to_assistant_message);
     }
+
+    #[test]
+    fn test_citations_to_annotations() {
+        let response: CompletionResponse = serde_json::from_str(
+            r#"
+        {
+            "id": "id",
+            "model": "sonar",
+            "object": "chat.completion",
+            "created": 1,
+            "choices": [{
+                "index": 0,
+                "finish_reason": "stop",
+                "message": {"role": "assistant", "content": "Rig is a Rust library [1]."},
+                "delta": {"role": "assistant", "content": ""}
+            }],
+            "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2},
+            "citations": ["https://rig.rs"]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let response: completion::CompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(
+            response.annotations,
+            vec![completion::Annotation::url("https://rig.rs", None)]
+        );
+        assert_eq!(response.finish_reason, Some(completion::FinishReason::Stop));
+    }
 }
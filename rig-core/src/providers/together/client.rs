@@ -7,7 +7,10 @@ use crate::{
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::{completion::CompletionModel, embedding::EmbeddingModel, M2_BERT_80M_8K_RETRIEVAL};
+use super::{
+    completion::CompletionModel, embedding::EmbeddingModel, text_completion::TextCompletionModel,
+    M2_BERT_80M_8K_RETRIEVAL,
+};
 
 // ================================================================
 // Together AI Client
@@ -128,6 +131,12 @@ impl Client {
         CompletionModel::new(self.clone(), model)
     }
 
+    /// Create a text completion model with the given name, using the (non-chat) completions
+    /// endpoint (see the [text_completion](crate::text_completion) module).
+    pub fn text_completion_model(&self, model: &str) -> TextCompletionModel {
+        TextCompletionModel::new(self.clone(), model)
+    }
+
     /// Create an agent builder with the given completion model.
     /// # Example
     /// ```
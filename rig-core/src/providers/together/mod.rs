@@ -12,6 +12,7 @@
 pub mod client;
 pub mod completion;
 pub mod embedding;
+pub mod text_completion;
 
 pub use client::Client;
 pub use completion::{
@@ -0,0 +1,188 @@
+// ================================================================
+//! Together AI Text Completion Integration
+//! From [Together AI Reference](https://docs.together.ai/reference/completions-1)
+// ================================================================
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    completion::CompletionError,
+    json_utils,
+    providers::openai,
+    text_completion::{self, TextCompletionRequest},
+};
+
+use super::client::{together_ai_api_types::ApiResponse, Client};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TextCompletionResponse {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<TextChoice>,
+    pub usage: Option<openai::Usage>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TextChoice {
+    pub index: usize,
+    pub text: String,
+    pub finish_reason: Option<String>,
+}
+
+impl TryFrom<TextCompletionResponse>
+    for text_completion::TextCompletionResponse<TextCompletionResponse>
+{
+    type Error = CompletionError;
+
+    fn try_from(response: TextCompletionResponse) -> Result<Self, Self::Error> {
+        let choice = response.choices.first().ok_or_else(|| {
+            CompletionError::ResponseError("Response contained no choices".to_owned())
+        })?;
+
+        Ok(text_completion::TextCompletionResponse {
+            text: choice.text.clone(),
+            finish_reason: choice.finish_reason.as_deref().map(Into::into),
+            raw_response: response,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct TextCompletionModel {
+    client: Client,
+    pub model: String,
+}
+
+impl TextCompletionModel {
+    pub fn new(client: Client, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+        }
+    }
+
+    pub(crate) fn create_text_completion_request(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        if request.suffix.is_some() {
+            return Err(CompletionError::RequestError(
+                "Together AI text completions do not support a suffix".into(),
+            ));
+        }
+
+        let mut body = json!({
+            "model": self.model,
+            "prompt": request.prompt,
+        });
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if !request.stop.is_empty() {
+            body["stop"] = json!(request.stop);
+        }
+
+        Ok(match request.additional_params {
+            Some(params) => json_utils::merge(body, params),
+            None => body,
+        })
+    }
+}
+
+impl text_completion::TextCompletionModel for TextCompletionModel {
+    type Response = TextCompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn text_completion(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<text_completion::TextCompletionResponse<TextCompletionResponse>, CompletionError>
+    {
+        let request = self.create_text_completion_request(request)?;
+
+        let response = self
+            .client
+            .post("/v1/completions")
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            match response
+                .json::<ApiResponse<TextCompletionResponse>>()
+                .await?
+            {
+                ApiResponse::Ok(response) => {
+                    tracing::info!(target: "rig",
+                        "Together text completion token usage: {:?}",
+                        response.usage.clone().map(|usage| format!("{usage}")).unwrap_or("N/A".to_string())
+                    );
+                    response.try_into()
+                }
+                ApiResponse::Error(err) => Err(CompletionError::ProviderError(err.message())),
+            }
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
+
+    fn request_body(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_text_completion_request(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::FinishReason, providers::together::CODE_LLAMA_34B_INSTRUCT,
+        text_completion::TextCompletionModel as _,
+    };
+
+    #[test]
+    fn test_text_completion() {
+        let model = Client::new("test-key").text_completion_model(CODE_LLAMA_34B_INSTRUCT);
+        let request = model
+            .text_completion_request("def fibonacci(n):")
+            .max_tokens(64)
+            .stop("\n\n")
+            .build();
+        assert_eq!(
+            model.request_body(request).unwrap(),
+            json!({
+                "model": CODE_LLAMA_34B_INSTRUCT,
+                "prompt": "def fibonacci(n):",
+                "max_tokens": 64,
+                "stop": ["\n\n"],
+            })
+        );
+
+        let request = model.text_completion_request("a").suffix("c").build();
+        assert!(matches!(
+            model.request_body(request),
+            Err(CompletionError::RequestError(_))
+        ));
+
+        let response: TextCompletionResponse = serde_json::from_value(json!({
+            "id": "cmpl-1",
+            "object": "text_completion",
+            "created": 1,
+            "model": CODE_LLAMA_34B_INSTRUCT,
+            "choices": [{"index": 0, "text": "\n    return n", "finish_reason": "length"}],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 64, "total_tokens": 69}
+        }))
+        .unwrap();
+        let response: text_completion::TextCompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(response.text, "\n    return n");
+        assert_eq!(response.finish_reason, Some(FinishReason::Length));
+    }
+}
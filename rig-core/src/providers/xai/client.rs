@@ -2,12 +2,18 @@ use crate::{
     agent::AgentBuilder,
     embeddings::{self},
     extractor::ExtractorBuilder,
+    json_utils,
+    providers::DeserializationMode,
     Embed,
 };
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use super::{completion::CompletionModel, embedding::EmbeddingModel, EMBEDDING_V1};
+use super::{
+    completion::{CompletionModel, DeferredCompletion},
+    embedding::EmbeddingModel,
+    EMBEDDING_V1,
+};
 
 // ================================================================
 // xAI Client
@@ -18,6 +24,7 @@ const XAI_BASE_URL: &str = "https://api.x.ai";
 pub struct Client {
     base_url: String,
     http_client: reqwest::Client,
+    deserialization_mode: DeserializationMode,
 }
 
 impl Client {
@@ -44,9 +51,17 @@ impl Client {
                 })
                 .build()
                 .expect("xAI reqwest client should build"),
+            deserialization_mode: DeserializationMode::default(),
         }
     }
 
+    /// Set how strictly the responses of the API are deserialized (defaults to
+    /// [DeserializationMode::Lenient]).
+    pub fn with_deserialization_mode(mut self, mode: DeserializationMode) -> Self {
+        self.deserialization_mode = mode;
+        self
+    }
+
     /// Create a new xAI client from the `XAI_API_KEY` environment variable.
     /// Panics if the environment variable is not set.
     pub fn from_env() -> Self {
@@ -61,6 +76,30 @@ impl Client {
         self.http_client.post(url)
     }
 
+    pub fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.base_url, path).replace("//", "/");
+
+        tracing::debug!("GET {}", url);
+        self.http_client.get(url)
+    }
+
+    /// Parse a response of the API according to the deserialization mode of the client.
+    pub(crate) fn parse_response<T>(
+        &self,
+        body: &str,
+    ) -> Result<xai_api_types::ApiResponse<T>, serde_json::Error>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        match serde_json::from_str::<xai_api_types::ApiResponse<T>>(body) {
+            Ok(xai_api_types::ApiResponse::Error(err)) => {
+                Ok(xai_api_types::ApiResponse::Error(err))
+            }
+            _ => json_utils::from_str_with_mode(body, self.deserialization_mode)
+                .map(xai_api_types::ApiResponse::Ok),
+        }
+    }
+
     /// Create an embedding model with the given name.
     /// Note: default embedding dimension of 0 will be used if model is not known.
     /// If this is the case, it's better to use function `embedding_model_with_ndims`
@@ -126,6 +165,11 @@ impl Client {
         CompletionModel::new(self.clone(), model)
     }
 
+    /// Resume a deferred completion submitted with [CompletionModel::defer], from its request id.
+    pub fn deferred_completion(&self, request_id: &str) -> DeferredCompletion {
+        DeferredCompletion::new(self.clone(), request_id)
+    }
+
     /// Create an agent builder with the given completion model.
     /// # Example
     /// ```
@@ -6,25 +6,239 @@
 use crate::{
     completion::{self, CompletionError},
     json_utils,
+    message::{self, ContentFormat, ImageMediaType, MimeType},
     providers::openai::Message,
 };
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use xai_api_types::{CompletionResponse, ToolDefinition};
+use xai_api_types::{CompletionResponse, DeferredRequest, ToolDefinition};
 
 use super::client::{xai_api_types::ApiResponse, Client};
 
 /// `grok-beta` completion model
 pub const GROK_BETA: &str = "grok-beta";
+/// `grok-2-vision-1212` completion model, supporting images
+pub const GROK_2_VISION_1212: &str = "grok-2-vision-1212";
+/// `grok-vision-beta` completion model, supporting images
+pub const GROK_VISION_BETA: &str = "grok-vision-beta";
+
+/// Convert a message to xAI messages, which are OpenAI messages where images are URLs: base64
+/// images are sent as data URLs. xAI supports JPEG and PNG images, and base64 images without a
+/// media type are assumed to be JPEG.
+fn xai_messages(message: message::Message) -> Result<Vec<Message>, message::MessageError> {
+    let message = match message {
+        message::Message::User { content, name } => message::Message::User {
+            content: content.try_map(|content| match content {
+                message::UserContent::Image(image) => {
+                    Ok(message::UserContent::Image(message::Image {
+                        data: image_url(&image)?,
+                        format: Some(ContentFormat::String),
+                        ..image
+                    }))
+                }
+                content => Ok(content),
+            })?,
+            name,
+        },
+        message => message,
+    };
+
+    message.try_into()
+}
+
+fn image_url(image: &message::Image) -> Result<String, message::MessageError> {
+    let is_url = match image.format {
+        Some(ContentFormat::String) => true,
+        Some(ContentFormat::Base64) => false,
+        None => ["https://", "http://", "data:"]
+            .iter()
+            .any(|prefix| image.data.starts_with(prefix)),
+    };
+    if is_url {
+        return Ok(image.data.clone());
+    }
+
+    let media_type = match &image.media_type {
+        None | Some(ImageMediaType::JPEG) => ImageMediaType::JPEG,
+        Some(ImageMediaType::PNG) => ImageMediaType::PNG,
+        Some(media_type) => {
+            return Err(message::MessageError::ConversionError(format!(
+                "xAI does not support {} images",
+                media_type.to_mime_type()
+            )))
+        }
+    };
+    Ok(format!(
+        "data:{};base64,{}",
+        media_type.to_mime_type(),
+        image.data
+    ))
+}
 
 // =================================================================
 // Rig Implementation Types
 // =================================================================
 
+/// Format of the responses of a model, see [CompletionModel::with_response_format].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    /// Any JSON object
+    JsonObject,
+    /// A JSON object following a schema
+    JsonSchema {
+        json_schema: JsonSchemaFormat,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    pub strict: bool,
+}
+
+impl ResponseFormat {
+    /// Responses following the JSON schema of `T`.
+    pub fn json_schema<T: JsonSchema>() -> Self {
+        Self::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: T::schema_name(),
+                schema: json!(schemars::schema_for!(T)),
+                strict: true,
+            },
+        }
+    }
+}
+
+/// Whether the model searches the web, X and news sources before answering (Live Search).
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// The model decides whether to search
+    #[default]
+    Auto,
+    /// The model always searches
+    On,
+    /// The model never searches
+    Off,
+}
+
+/// A source searched by Live Search.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchSource {
+    Web {
+        /// ISO alpha-2 code of the country of the results
+        #[serde(skip_serializing_if = "Option::is_none")]
+        country: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_websites: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        excluded_websites: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        safe_search: Option<bool>,
+    },
+    News {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        country: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        excluded_websites: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        safe_search: Option<bool>,
+    },
+    X {
+        /// Only search the posts of these X handles
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        x_handles: Vec<String>,
+    },
+    Rss {
+        /// URLs of the RSS feeds
+        links: Vec<String>,
+    },
+}
+
+impl SearchSource {
+    pub fn web() -> Self {
+        Self::Web {
+            country: None,
+            allowed_websites: vec![],
+            excluded_websites: vec![],
+            safe_search: None,
+        }
+    }
+
+    pub fn news() -> Self {
+        Self::News {
+            country: None,
+            excluded_websites: vec![],
+            safe_search: None,
+        }
+    }
+
+    pub fn x() -> Self {
+        Self::X { x_handles: vec![] }
+    }
+}
+
+/// Parameters of xAI's Live Search, see [CompletionModel::with_search_parameters]. The URLs of
+/// the sources used by the model are returned as the annotations of the responses.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SearchParameters {
+    pub mode: SearchMode,
+    /// Sources to search (web and X by default)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<SearchSource>,
+    /// Only search the data published from this date (`YYYY-MM-DD`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_date: Option<String>,
+    /// Only search the data published until this date (`YYYY-MM-DD`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_date: Option<String>,
+    /// Maximum number of search results used by the model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_search_results: Option<u32>,
+    /// Whether to return the URLs of the sources (the default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_citations: Option<bool>,
+}
+
+impl SearchParameters {
+    pub fn new(mode: SearchMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Add a source to search.
+    pub fn source(mut self, source: SearchSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Only search the data published in the range of dates (`YYYY-MM-DD`, both optional).
+    pub fn date_range(mut self, from: Option<&str>, to: Option<&str>) -> Self {
+        self.from_date = from.map(String::from);
+        self.to_date = to.map(String::from);
+        self
+    }
+
+    pub fn max_search_results(mut self, max_search_results: u32) -> Self {
+        self.max_search_results = Some(max_search_results);
+        self
+    }
+}
+
 #[derive(Clone)]
 pub struct CompletionModel {
     client: Client,
     pub model: String,
+    response_format: Option<ResponseFormat>,
+    search_parameters: Option<SearchParameters>,
 }
 
 impl CompletionModel {
@@ -32,18 +246,116 @@ impl CompletionModel {
         Self {
             client,
             model: model.to_string(),
+            response_format: None,
+            search_parameters: None,
         }
     }
-}
 
-impl completion::CompletionModel for CompletionModel {
-    type Response = CompletionResponse;
+    /// Set the format of the responses of the model, e.g.: structured outputs following the
+    /// JSON schema of a type. Since the responses are then JSON text, the model can be used with
+    /// an [Extractor](crate::extractor::Extractor) to deserialize them.
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::xai::{self, completion::ResponseFormat};
+    /// use schemars::JsonSchema;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Deserialize, Serialize, JsonSchema)]
+    /// struct Person {
+    ///     name: String,
+    ///     age: u8,
+    /// }
+    ///
+    /// let model = xai::Client::new("your-xai-api-key")
+    ///     .completion_model(xai::GROK_BETA)
+    ///     .with_response_format(ResponseFormat::json_schema::<Person>());
+    /// ```
+    pub fn with_response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
 
-    #[cfg_attr(feature = "worker", worker::send)]
-    async fn completion(
+    /// Let the model search the web, X and news sources before answering (Live Search). The
+    /// parameters can also be set per agent or request, as the `search_parameters` additional
+    /// parameter.
+    ///
+    /// # Example
+    /// ```
+    /// use rig::providers::xai::{
+    ///     self,
+    ///     completion::{SearchMode, SearchParameters, SearchSource},
+    /// };
+    ///
+    /// let model = xai::Client::new("your-xai-api-key")
+    ///     .completion_model(xai::GROK_BETA)
+    ///     .with_search_parameters(
+    ///         SearchParameters::new(SearchMode::On)
+    ///             .source(SearchSource::news())
+    ///             .date_range(Some("2025-01-01"), None),
+    ///     );
+    /// ```
+    pub fn with_search_parameters(mut self, search_parameters: SearchParameters) -> Self {
+        self.search_parameters = Some(search_parameters);
+        self
+    }
+
+    /// Submit a deferred completion: the request returns immediately, and the completion is
+    /// retrieved later with [DeferredCompletion::poll] (e.g.: for long requests, or to not keep
+    /// connections open). The request id can be stored to resume polling with
+    /// [Client::deferred_completion].
+    ///
+    /// # Example
+    /// ```
+    /// use rig::{completion::CompletionModel, providers::xai};
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let model = xai::Client::new("your-xai-api-key").completion_model(xai::GROK_BETA);
+    ///
+    /// let request = model.completion_request("Write a haiku about rust").build();
+    /// let deferred = model.defer(request).await?;
+    ///
+    /// let response = loop {
+    ///     if let Some(response) = deferred.poll().await? {
+    ///         break response;
+    ///     }
+    ///     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn defer(
         &self,
         completion_request: completion::CompletionRequest,
-    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+    ) -> Result<DeferredCompletion, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let mut request = self.create_completion_request(completion_request)?;
+        request["deferred"] = json!(true);
+
+        let response = request_options
+            .apply(self.client.post("/v1/chat/completions"))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let t = response.text().await?;
+            match self.client.parse_response::<DeferredRequest>(&t)? {
+                ApiResponse::Ok(deferred) => Ok(DeferredCompletion::new(
+                    self.client.clone(),
+                    &deferred.request_id,
+                )),
+                ApiResponse::Error(error) => Err(CompletionError::ProviderError(error.message())),
+            }
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
+
+    pub(crate) fn create_completion_request(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
         // Add preamble to chat history (if available)
         let mut full_history: Vec<Message> = match &completion_request.preamble {
             Some(preamble) => {
@@ -57,13 +369,13 @@ impl completion::CompletionModel for CompletionModel {
         };
 
         // Convert prompt to user message
-        let prompt: Vec<Message> = completion_request.prompt_with_context().try_into()?;
+        let prompt: Vec<Message> = xai_messages(completion_request.prompt_with_context())?;
 
         // Convert existing chat history
         let chat_history: Vec<Message> = completion_request
             .chat_history
             .into_iter()
-            .map(|message| message.try_into())
+            .map(xai_messages)
             .collect::<Result<Vec<Vec<Message>>, _>>()?
             .into_iter()
             .flatten()
@@ -89,21 +401,46 @@ impl completion::CompletionModel for CompletionModel {
             })
         };
 
+        if let Some(response_format) = &self.response_format {
+            request["response_format"] = json!(response_format);
+        }
+        if let Some(search_parameters) = &self.search_parameters {
+            request["search_parameters"] = json!(search_parameters);
+        }
+        if let Some(n) = completion_request.n {
+            request["n"] = json!(n);
+        }
+
         request = if let Some(params) = completion_request.additional_params {
             json_utils::merge(request, params)
         } else {
             request
         };
 
-        let response = self
-            .client
-            .post("/v1/chat/completions")
+        Ok(request)
+    }
+}
+
+impl completion::CompletionModel for CompletionModel {
+    type Response = CompletionResponse;
+
+    #[cfg_attr(feature = "worker", worker::send)]
+    async fn completion(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<completion::CompletionResponse<CompletionResponse>, CompletionError> {
+        let request_options = completion_request.request_options.clone();
+        let request = self.create_completion_request(completion_request)?;
+
+        let response = request_options
+            .apply(self.client.post("/v1/chat/completions"))
             .json(&request)
             .send()
             .await?;
 
         if response.status().is_success() {
-            match response.json::<ApiResponse<CompletionResponse>>().await? {
+            let t = response.text().await?;
+            match self.client.parse_response::<CompletionResponse>(&t)? {
                 ApiResponse::Ok(completion) => completion.try_into(),
                 ApiResponse::Error(error) => Err(CompletionError::ProviderError(error.message())),
             }
@@ -111,6 +448,53 @@ impl completion::CompletionModel for CompletionModel {
             Err(CompletionError::ProviderError(response.text().await?))
         }
     }
+
+    fn request_body(
+        &self,
+        completion_request: completion::CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.create_completion_request(completion_request)
+    }
+}
+
+/// A completion submitted with [CompletionModel::defer].
+#[derive(Clone)]
+pub struct DeferredCompletion {
+    client: Client,
+    pub request_id: String,
+}
+
+impl DeferredCompletion {
+    pub(crate) fn new(client: Client, request_id: &str) -> Self {
+        Self {
+            client,
+            request_id: request_id.to_string(),
+        }
+    }
+
+    /// Get the completion, or `None` if it is not ready yet. Completions are available for a
+    /// limited time once ready, and can only be retrieved once.
+    pub async fn poll(
+        &self,
+    ) -> Result<Option<completion::CompletionResponse<CompletionResponse>>, CompletionError> {
+        let response = self
+            .client
+            .get(&format!("/v1/chat/deferred-completion/{}", self.request_id))
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::ACCEPTED {
+            Ok(None)
+        } else if response.status().is_success() {
+            let t = response.text().await?;
+            match self.client.parse_response::<CompletionResponse>(&t)? {
+                ApiResponse::Ok(completion) => completion.try_into().map(Some),
+                ApiResponse::Error(error) => Err(CompletionError::ProviderError(error.message())),
+            }
+        } else {
+            Err(CompletionError::ProviderError(response.text().await?))
+        }
+    }
 }
 
 pub mod xai_api_types {
@@ -127,7 +511,83 @@ pub mod xai_api_types {
             let choice = response.choices.first().ok_or_else(|| {
                 CompletionError::ResponseError("Response contained no choices".to_owned())
             })?;
-            let content = match &choice.message {
+            if let Some(reason) = choice.message.refusal() {
+                return Err(CompletionError::Refused {
+                    reason: reason.to_string(),
+                });
+            }
+            let alternatives = response
+                .choices
+                .iter()
+                .skip(1)
+                .map(Choice::content)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(completion::CompletionResponse {
+                choice: choice.content()?,
+                annotations: response
+                    .citations
+                    .iter()
+                    .map(|url| completion::Annotation::url(url, None))
+                    .collect(),
+                alternatives,
+                finish_reason: Some(choice.finish_reason.as_str().into()),
+                raw_response: response,
+            })
+        }
+    }
+
+    impl From<completion::ToolDefinition> for ToolDefinition {
+        fn from(tool: completion::ToolDefinition) -> Self {
+            Self {
+                r#type: "function".into(),
+                function: tool,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct ToolDefinition {
+        pub r#type: String,
+        pub function: completion::ToolDefinition,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct DeferredRequest {
+        pub request_id: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Function {
+        pub name: String,
+        pub arguments: String,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct CompletionResponse {
+        pub id: String,
+        pub model: String,
+        pub choices: Vec<Choice>,
+        pub created: i64,
+        pub object: String,
+        pub system_fingerprint: Option<String>,
+        pub usage: Usage,
+        /// URLs of the sources used by Live Search
+        #[serde(default)]
+        pub citations: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Choice {
+        pub finish_reason: String,
+        pub index: i32,
+        pub message: Message,
+    }
+
+    impl Choice {
+        /// The content of the message of the choice.
+        fn content(&self) -> Result<OneOrMany<completion::AssistantContent>, CompletionError> {
+            let content = match &self.message {
                 Message::Assistant {
                     content,
                     tool_calls,
@@ -150,7 +610,7 @@ pub mod xai_api_types {
                             .iter()
                             .map(|call| {
                                 completion::AssistantContent::tool_call(
-                                    &call.function.name,
+                                    &call.id,
                                     &call.function.name,
                                     call.function.arguments.clone(),
                                 )
@@ -164,62 +624,172 @@ pub mod xai_api_types {
                 )),
             }?;
 
-            let choice = OneOrMany::many(content).map_err(|_| {
+            OneOrMany::many(content).map_err(|_| {
                 CompletionError::ResponseError(
                     "Response contained no message or tool call (empty)".to_owned(),
                 )
-            })?;
-
-            Ok(completion::CompletionResponse {
-                choice,
-                raw_response: response,
             })
         }
     }
 
-    impl From<completion::ToolDefinition> for ToolDefinition {
-        fn from(tool: completion::ToolDefinition) -> Self {
-            Self {
-                r#type: "function".into(),
-                function: tool,
-            }
-        }
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Usage {
+        pub completion_tokens: i32,
+        pub prompt_tokens: i32,
+        pub total_tokens: i32,
     }
+}
 
-    #[derive(Clone, Debug, Deserialize, Serialize)]
-    pub struct ToolDefinition {
-        pub r#type: String,
-        pub function: completion::ToolDefinition,
-    }
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
 
-    #[derive(Debug, Deserialize)]
-    pub struct Function {
-        pub name: String,
-        pub arguments: String,
+    use super::*;
+    use crate::{message::UserContent, providers::xai::Client, OneOrMany};
+
+    #[test]
+    fn test_image_request() {
+        let model = Client::new("key").completion_model(GROK_2_VISION_1212);
+
+        let request = model
+            .create_completion_request(completion::CompletionRequest {
+                prompt: message::Message::User {
+                    content: OneOrMany::from([
+                        UserContent::image(
+                            "aGVsbG8=",
+                            Some(ContentFormat::Base64),
+                            Some(ImageMediaType::PNG),
+                            None,
+                        ),
+                        UserContent::image("https://example.com/cat.jpg", None, None, None),
+                        UserContent::text("What is the difference?"),
+                    ]),
+                    name: None,
+                },
+                preamble: None,
+                chat_history: vec![],
+                documents: vec![],
+                tools: vec![],
+                temperature: None,
+                max_tokens: None,
+                n: None,
+                additional_params: None,
+                request_options: Default::default(),
+            })
+            .unwrap();
+
+        let content = &request["messages"][0]["content"];
+        assert_eq!(content[0]["type"], json!("image_url"));
+        assert_eq!(
+            content[0]["image_url"]["url"],
+            json!("data:image/png;base64,aGVsbG8=")
+        );
+        assert_eq!(
+            content[1]["image_url"]["url"],
+            json!("https://example.com/cat.jpg")
+        );
+        assert_eq!(content[2]["text"], json!("What is the difference?"));
     }
 
-    #[derive(Debug, Deserialize)]
-    pub struct CompletionResponse {
-        pub id: String,
-        pub model: String,
-        pub choices: Vec<Choice>,
-        pub created: i64,
-        pub object: String,
-        pub system_fingerprint: String,
-        pub usage: Usage,
+    #[test]
+    fn test_response_format() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Person {
+            name: String,
+        }
+
+        let model = Client::new("key")
+            .completion_model(GROK_BETA)
+            .with_response_format(ResponseFormat::json_schema::<Person>());
+        let request = model
+            .create_completion_request(completion::CompletionRequest {
+                prompt: message::Message::user("Who wrote Dune?"),
+                preamble: None,
+                chat_history: vec![],
+                documents: vec![],
+                tools: vec![],
+                temperature: None,
+                max_tokens: None,
+                n: None,
+                additional_params: None,
+                request_options: Default::default(),
+            })
+            .unwrap();
+
+        let response_format = &request["response_format"];
+        assert_eq!(response_format["type"], json!("json_schema"));
+        assert_eq!(response_format["json_schema"]["name"], json!("Person"));
+        assert_eq!(
+            response_format["json_schema"]["schema"]["required"],
+            json!(["name"])
+        );
     }
 
-    #[derive(Debug, Deserialize)]
-    pub struct Choice {
-        pub finish_reason: String,
-        pub index: i32,
-        pub message: Message,
+    #[test]
+    fn test_search_parameters() {
+        let model = Client::new("key")
+            .completion_model(GROK_BETA)
+            .with_search_parameters(
+                SearchParameters::new(SearchMode::On)
+                    .source(SearchSource::X {
+                        x_handles: vec!["xai".to_string()],
+                    })
+                    .date_range(Some("2025-01-01"), None),
+            );
+        let request = model
+            .create_completion_request(completion::CompletionRequest {
+                prompt: message::Message::user("What's new?"),
+                preamble: None,
+                chat_history: vec![],
+                documents: vec![],
+                tools: vec![],
+                temperature: None,
+                max_tokens: None,
+                n: None,
+                additional_params: None,
+                request_options: Default::default(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            request["search_parameters"],
+            json!({
+                "mode": "on",
+                "sources": [{"type": "x", "x_handles": ["xai"]}],
+                "from_date": "2025-01-01"
+            })
+        );
+
+        let response: CompletionResponse = serde_json::from_value(json!({
+            "id": "id",
+            "model": "grok-3",
+            "object": "chat.completion",
+            "created": 1,
+            "system_fingerprint": null,
+            "choices": [{
+                "index": 0,
+                "finish_reason": "stop",
+                "message": {"role": "assistant", "content": "Grok 3 was released."}
+            }],
+            "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2},
+            "citations": ["https://x.ai/news"]
+        }))
+        .unwrap();
+        let response: completion::CompletionResponse<_> = response.try_into().unwrap();
+        assert_eq!(
+            response.annotations,
+            vec![completion::Annotation::url("https://x.ai/news", None)]
+        );
     }
 
-    #[derive(Debug, Deserialize)]
-    pub struct Usage {
-        pub completion_tokens: i32,
-        pub prompt_tokens: i32,
-        pub total_tokens: i32,
+    #[test]
+    fn test_unsupported_image() {
+        let image = message::Image {
+            data: "aGVsbG8=".to_string(),
+            media_type: Some(ImageMediaType::GIF),
+            ..Default::default()
+        };
+        assert!(image_url(&image).is_err());
     }
 }
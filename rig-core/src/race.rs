@@ -0,0 +1,284 @@
+//! This module provides [Race], a completion model sending each request to both a fast (cheap)
+//! model and a slow (strong) model, to answer as fast as possible without giving up on quality.
+//!
+//! The response of the fast model is returned as soon as it passes the check of the race (a
+//! [validator](Race::validator) or an asynchronous [judge](Race::judge)), and the call to the
+//! strong model is then dropped. Otherwise, the response of the strong model is awaited. The
+//! response of the strong model is returned as soon as it is received, even if the fast model has
+//! not answered yet.
+//!
+//! Note that both models are called for every request, so the fast model only saves latency, not
+//! cost.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, providers::openai, race::Race};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//!
+//! let model = Race::new(
+//!     openai.completion_model(openai::GPT_4O_MINI),
+//!     openai.completion_model(openai::GPT_4O),
+//! )
+//! .validator(|choice| {
+//!     // Only accept direct answers from the fast model
+//!     let text = rig::race::text(choice);
+//!     !text.is_empty() && !text.contains("I'm not sure")
+//! });
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//! let answer = agent.prompt("What is the capital of France?").await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use futures::{
+    future::{self, BoxFuture, Either},
+    FutureExt,
+};
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    message::AssistantContent,
+    OneOrMany,
+};
+
+/// The raw response of the model that won the race.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RaceResponse<F, S> {
+    Fast(F),
+    Strong(S),
+}
+
+/// Number of requests answered by each model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RaceStats {
+    pub fast: u64,
+    pub strong: u64,
+}
+
+type Check = Arc<dyn Fn(&OneOrMany<AssistantContent>) -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// Races a fast model against a strong model, see the [module documentation](self).
+/// Clones of a [Race] share the same stats.
+#[derive(Clone)]
+pub struct Race<F, S> {
+    pub fast: F,
+    pub strong: S,
+    check: Check,
+    fast_wins: Arc<AtomicU64>,
+    strong_wins: Arc<AtomicU64>,
+}
+
+impl<F, S> Race<F, S>
+where
+    F: CompletionModel,
+    S: CompletionModel,
+{
+    /// Race the fast model against the strong model. By default, any successful response of the
+    /// fast model is accepted.
+    pub fn new(fast: F, strong: S) -> Self {
+        Self {
+            fast,
+            strong,
+            check: Arc::new(|_| future::ready(true).boxed()),
+            fast_wins: Arc::new(AtomicU64::new(0)),
+            strong_wins: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Accept the responses of the fast model for which the validator returns `true`.
+    pub fn validator(
+        mut self,
+        validator: impl Fn(&OneOrMany<AssistantContent>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.check = Arc::new(move |choice| future::ready(validator(choice)).boxed());
+        self
+    }
+
+    /// Accept the responses of the fast model for which the (asynchronous) judge returns `true`,
+    /// e.g.: a classifier or a small model grading the response. The strong model keeps running
+    /// while the judge decides.
+    pub fn judge<Fut>(
+        mut self,
+        judge: impl Fn(OneOrMany<AssistantContent>) -> Fut + Send + Sync + 'static,
+    ) -> Self
+    where
+        Fut: std::future::Future<Output = bool> + Send + 'static,
+    {
+        self.check = Arc::new(move |choice| judge(choice.clone()).boxed());
+        self
+    }
+
+    pub fn stats(&self) -> RaceStats {
+        RaceStats {
+            fast: self.fast_wins.load(Ordering::Relaxed),
+            strong: self.strong_wins.load(Ordering::Relaxed),
+        }
+    }
+
+    fn fast_win(
+        &self,
+        response: CompletionResponse<F::Response>,
+    ) -> CompletionResponse<RaceResponse<F::Response, S::Response>> {
+        self.fast_wins.fetch_add(1, Ordering::Relaxed);
+        CompletionResponse {
+            choice: response.choice,
+            annotations: response.annotations,
+            alternatives: response.alternatives,
+            finish_reason: response.finish_reason,
+            raw_response: RaceResponse::Fast(response.raw_response),
+        }
+    }
+
+    fn strong_win(
+        &self,
+        response: CompletionResponse<S::Response>,
+    ) -> CompletionResponse<RaceResponse<F::Response, S::Response>> {
+        self.strong_wins.fetch_add(1, Ordering::Relaxed);
+        CompletionResponse {
+            choice: response.choice,
+            annotations: response.annotations,
+            alternatives: response.alternatives,
+            finish_reason: response.finish_reason,
+            raw_response: RaceResponse::Strong(response.raw_response),
+        }
+    }
+}
+
+/// The text of a response (its text contents, joined with newlines), e.g.: for validators.
+pub fn text(choice: &OneOrMany<AssistantContent>) -> String {
+    choice
+        .iter()
+        .filter_map(|content| match content {
+            AssistantContent::Text(text) => Some(text.text.as_str()),
+            AssistantContent::ToolCall(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl<F, S> CompletionModel for Race<F, S>
+where
+    F: CompletionModel,
+    S: CompletionModel,
+{
+    type Response = RaceResponse<F::Response, S::Response>;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let fast = self.fast.completion(request.clone());
+        let strong = self.strong.completion(request);
+        futures::pin_mut!(fast, strong);
+
+        match future::select(fast, strong).await {
+            Either::Left((Ok(response), strong)) => {
+                let check = (self.check)(&response.choice);
+                match future::select(check, strong).await {
+                    Either::Left((true, _)) => Ok(self.fast_win(response)),
+                    Either::Left((false, strong)) => {
+                        tracing::debug!(target: "rig", "Fast response rejected, awaiting the strong model");
+                        strong.await.map(|response| self.strong_win(response))
+                    }
+                    Either::Right((Ok(strong), _)) => Ok(self.strong_win(strong)),
+                    // The strong model failed: fall back to the fast response if it is accepted
+                    Either::Right((Err(e), check)) => {
+                        if check.await {
+                            Ok(self.fast_win(response))
+                        } else {
+                            Err(e)
+                        }
+                    }
+                }
+            }
+            Either::Left((Err(e), strong)) => {
+                tracing::warn!(target: "rig", "Fast model failed: {e}, awaiting the strong model");
+                strong.await.map(|response| self.strong_win(response))
+            }
+            Either::Right((Ok(response), _)) => Ok(self.strong_win(response)),
+            Either::Right((Err(e), fast)) => match fast.await {
+                Ok(response) if (self.check)(&response.choice).await => Ok(self.fast_win(response)),
+                _ => Err(e),
+            },
+        }
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.strong.request_body(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        test_utils::{response, MockModel},
+    };
+
+    /// Answers after the delay, or fails if the answer is empty
+    fn mock(answer: &'static str, delay_ms: u64) -> MockModel<&'static str> {
+        MockModel::new(move |_| match answer {
+            "" => Err(CompletionError::ProviderError("Overloaded".to_string())),
+            answer => Ok(response(AssistantContent::text(answer), answer)),
+        })
+        .delay(Duration::from_millis(delay_ms))
+    }
+
+    async fn send<F: CompletionModel, S: CompletionModel>(
+        race: &Race<F, S>,
+    ) -> Result<RaceResponse<F::Response, S::Response>, CompletionError> {
+        CompletionRequestBuilder::new(race.clone(), "What is the capital of France?")
+            .send()
+            .await
+            .map(|response| response.raw_response)
+    }
+
+    #[tokio::test]
+    async fn test_race() {
+        let accept_paris = |choice: &OneOrMany<AssistantContent>| text(choice).contains("Paris");
+
+        let race = Race::new(mock("Paris", 10), mock("Paris.", 200)).validator(accept_paris);
+        let start = std::time::Instant::now();
+        assert_eq!(send(&race).await.unwrap(), RaceResponse::Fast("Paris"));
+        assert!(start.elapsed() < Duration::from_millis(150));
+
+        let race = Race::new(mock("Lyon", 10), mock("Paris.", 50)).validator(accept_paris);
+        assert_eq!(send(&race).await.unwrap(), RaceResponse::Strong("Paris."));
+
+        // The strong model answers while the judge decides
+        let race = Race::new(mock("Paris", 10), mock("Paris.", 50)).judge(|_| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            true
+        });
+        assert_eq!(send(&race).await.unwrap(), RaceResponse::Strong("Paris."));
+
+        // Failures of one model fall back to the other one
+        let race = Race::new(mock("", 10), mock("Paris.", 50));
+        assert_eq!(send(&race).await.unwrap(), RaceResponse::Strong("Paris."));
+        let race = Race::new(mock("Paris", 50), mock("", 10));
+        assert_eq!(send(&race).await.unwrap(), RaceResponse::Fast("Paris"));
+        let race = Race::new(mock("Lyon", 50), mock("", 10)).validator(accept_paris);
+        assert!(matches!(
+            send(&race).await,
+            Err(CompletionError::ProviderError(_))
+        ));
+
+        let race = Race::new(mock("Paris", 10), mock("Paris.", 50)).validator(accept_paris);
+        send(&race).await.unwrap();
+        send(&race).await.unwrap();
+        assert_eq!(race.stats(), RaceStats { fast: 2, strong: 0 });
+    }
+}
@@ -0,0 +1,212 @@
+//! This module provides [Rag], a retrieval-augmented generation (RAG) agent wired end-to-end with
+//! sensible defaults: the documents are split into chunks, embedded and stored in an in-memory
+//! vector store, whose most relevant chunks are retrieved as the dynamic context of an
+//! [Agent] for each prompt.
+//!
+//! The defaults:
+//! - chunks of 1000 characters, overlapping by 100 characters (see [Document::chunks]),
+//! - the 4 most relevant chunks retrieved for each prompt,
+//! - a preamble asking the model to answer from the documents and to cite their sources.
+//!
+//! For more control (e.g.: a persistent vector store, or [incremental indexing](crate::indexer)),
+//! wire the components together as in the [agent](crate::agent) documentation.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, loaders::FileLoader, providers::openai, rag::Rag};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//!
+//! let rag = Rag::builder(
+//!     openai.completion_model(openai::GPT_4O),
+//!     openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL),
+//! )
+//! .documents(FileLoader::with_glob("docs/*.md")?.read_with_metadata().ignore_errors())
+//! .build()
+//! .await?;
+//!
+//! let answer = rag.prompt("How do I configure the retries?").await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    agent::{Agent, AgentBuilder},
+    completion::{Chat, CompletionModel, Message, Prompt, PromptError},
+    embeddings::{EmbedError, EmbeddingError, EmbeddingModel, EmbeddingsBuilder},
+    metadata::{Document, DocumentMetadata},
+    vector_store::in_memory_store::InMemoryVectorStore,
+};
+
+const PREAMBLE: &str = "You are a helpful assistant. Answer the questions of the user using the \
+    provided documents, and cite the sources of your answers. If the documents do not contain the \
+    answer, say so.";
+
+#[derive(Debug, thiserror::Error)]
+pub enum RagError {
+    #[error("Embed error: {0}")]
+    EmbedError(#[from] EmbedError),
+
+    #[error("Embedding error: {0}")]
+    EmbeddingError(#[from] EmbeddingError),
+}
+
+/// A RAG agent over a set of documents, see the [module documentation](self).
+pub struct Rag<M: CompletionModel> {
+    agent: Agent<M>,
+    chunks: usize,
+}
+
+impl<M: CompletionModel> Rag<M> {
+    /// Create a builder of a RAG agent answering with the completion model, and embedding the
+    /// documents with the embedding model.
+    pub fn builder<E: EmbeddingModel + 'static>(model: M, embedding_model: E) -> RagBuilder<M, E> {
+        RagBuilder::new(model, embedding_model)
+    }
+
+    pub fn agent(&self) -> &Agent<M> {
+        &self.agent
+    }
+
+    /// The number of indexed chunks.
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+}
+
+impl<M: CompletionModel> Prompt for Rag<M> {
+    async fn prompt(&self, prompt: impl Into<Message> + Send) -> Result<String, PromptError> {
+        self.agent.prompt(prompt).await
+    }
+}
+
+impl<M: CompletionModel> Chat for Rag<M> {
+    async fn chat(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<String, PromptError> {
+        self.agent.chat(prompt, chat_history).await
+    }
+}
+
+/// A builder of [Rag] agents.
+pub struct RagBuilder<M: CompletionModel, E: EmbeddingModel> {
+    agent: AgentBuilder<M>,
+    embedding_model: E,
+    documents: Vec<Document>,
+    chunk_size: usize,
+    chunk_overlap: usize,
+    top_k: usize,
+}
+
+impl<M: CompletionModel, E: EmbeddingModel + 'static> RagBuilder<M, E> {
+    pub fn new(model: M, embedding_model: E) -> Self {
+        Self {
+            agent: AgentBuilder::new(model).preamble(PREAMBLE),
+            embedding_model,
+            documents: vec![],
+            chunk_size: 1000,
+            chunk_overlap: 100,
+            top_k: 4,
+        }
+    }
+
+    /// Replace the default preamble of the agent.
+    pub fn preamble(mut self, preamble: &str) -> Self {
+        self.agent = self.agent.preamble(preamble);
+        self
+    }
+
+    /// Configure the agent further, e.g.: its temperature or tools.
+    pub fn agent(mut self, configure: impl FnOnce(AgentBuilder<M>) -> AgentBuilder<M>) -> Self {
+        self.agent = configure(self.agent);
+        self
+    }
+
+    /// Add a document, e.g.: loaded with its metadata by a [loader](crate::loaders).
+    pub fn document(mut self, document: Document) -> Self {
+        self.documents.push(document);
+        self
+    }
+
+    pub fn documents(mut self, documents: impl IntoIterator<Item = Document>) -> Self {
+        self.documents.extend(documents);
+        self
+    }
+
+    /// Add a plain text document with the given id.
+    pub fn text(self, id: &str, text: &str) -> Self {
+        self.document(Document::new(DocumentMetadata::new(id), text))
+    }
+
+    /// Set the size of the chunks and their overlap, in characters (1000 and 100 by default).
+    pub fn chunking(mut self, size: usize, overlap: usize) -> Self {
+        self.chunk_size = size;
+        self.chunk_overlap = overlap;
+        self
+    }
+
+    /// Set the number of chunks retrieved for each prompt (4 by default).
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Chunk and embed the documents, and build the agent.
+    pub async fn build(self) -> Result<Rag<M>, RagError> {
+        let chunks = self
+            .documents
+            .iter()
+            .flat_map(|document| document.chunks(self.chunk_size, self.chunk_overlap))
+            .collect::<Vec<_>>();
+
+        let embeddings = EmbeddingsBuilder::new(self.embedding_model.clone())
+            .documents(chunks)?
+            .build()
+            .await?;
+        let chunks = embeddings.len();
+        let index = InMemoryVectorStore::from_documents_with_id_f(embeddings, |chunk| {
+            chunk.metadata.id.clone()
+        })
+        .index(self.embedding_model);
+
+        Ok(Rag {
+            agent: self.agent.dynamic_context(self.top_k, index).build(),
+            chunks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockEmbeddingModel, MockModel};
+
+    /// Answers with the ids of the documents of the requests
+    fn mock() -> MockModel {
+        MockModel::reply(|request| {
+            request
+                .documents
+                .iter()
+                .map(|document| document.metadata.id.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+    }
+
+    #[tokio::test]
+    async fn test_rag() {
+        let rag = Rag::builder(mock(), MockEmbeddingModel::counting(&["a", "b"]))
+            .text("alpha", "aaaa aaaa")
+            .text("bravo", "bbbb bbbb bbbb")
+            .chunking(10, 0)
+            .top_k(1)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(rag.chunks(), 3);
+        assert_eq!(rag.prompt("aaa?").await.unwrap(), "alpha#0");
+    }
+}
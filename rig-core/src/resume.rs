@@ -0,0 +1,254 @@
+//! Recovery of streamed responses interrupted midway (e.g.: by a network reset).
+//!
+//! [Resume] wraps a streaming completion model. When its stream fails after some text was
+//! received, the model is asked to continue its response from where it stopped: the partial
+//! response is sent back as an assistant message, followed by a continuation instruction, and
+//! the rest of the response is streamed as a single chunk. When the response cannot be resumed,
+//! the stream ends with a [CompletionError::Interrupted] error holding the partial text, instead
+//! of the bare error.
+//!
+//! Streams failing before any text was received, or after a tool call, end with their error as
+//! is (see the `retry` module, with the `retry` feature, to retry them).
+//!
+//! # Example
+//! ```rust
+//! use futures::StreamExt;
+//! use rig::{
+//!     completion::{CompletionError, CompletionModel},
+//!     providers::gemini::{self, completion::GEMINI_1_5_PRO},
+//!     resume::Resume,
+//!     streaming::StreamingChoice,
+//! };
+//!
+//! # async fn run() -> Result<(), CompletionError> {
+//! let gemini = gemini::Client::from_env();
+//! let model = Resume::new(gemini.completion_model(GEMINI_1_5_PRO));
+//!
+//! let mut stream = model.completion_request("Write a long story").stream().await?;
+//! while let Some(chunk) = stream.next().await {
+//!     match chunk {
+//!         Ok(StreamingChoice::Message(text)) => print!("{text}"),
+//!         Err(CompletionError::Interrupted { partial, .. }) => {
+//!             eprintln!("Interrupted after {} characters", partial.len())
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use futures::StreamExt;
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    message::{AssistantContent, Message},
+    streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult},
+};
+
+/// Wraps a streaming model and resumes its responses interrupted midway, see the
+/// [module documentation](self).
+#[derive(Clone)]
+pub struct Resume<M> {
+    pub model: M,
+    max_resumes: usize,
+    instruction: String,
+}
+
+impl<M> Resume<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            max_resumes: 1,
+            instruction: "Your previous response was interrupted. Continue it exactly from \
+                where it stopped, without repeating anything. Continue from: {tail}"
+                .to_string(),
+        }
+    }
+
+    /// Set the maximum number of continuation requests sent when a response is interrupted
+    /// (defaults to 1). With 0, interrupted streams only end with a
+    /// [CompletionError::Interrupted] error.
+    pub fn max_resumes(mut self, max_resumes: usize) -> Self {
+        self.max_resumes = max_resumes;
+        self
+    }
+
+    /// Set the continuation instruction. `{tail}` is replaced by the end of the partial response.
+    pub fn instruction(mut self, instruction: &str) -> Self {
+        self.instruction = instruction.to_string();
+        self
+    }
+
+    /// The request asking the model to continue the partial response of `request`.
+    fn continuation(&self, request: &CompletionRequest, partial: &str) -> CompletionRequest {
+        let tail_start = partial
+            .char_indices()
+            .rev()
+            .nth(99)
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+
+        let mut request = request.clone();
+        request.chat_history.push(request.prompt.clone());
+        request.chat_history.push(Message::assistant(partial));
+        request.prompt = Message::user(self.instruction.replace("{tail}", &partial[tail_start..]));
+        request
+    }
+}
+
+impl<M: CompletionModel> CompletionModel for Resume<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        self.model.completion(request).await
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+/// The rest of an interrupted response is requested with [CompletionModel::completion], and
+/// streamed as a single chunk.
+impl<M: StreamingCompletionModel + Sync + 'static> StreamingCompletionModel for Resume<M> {
+    async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
+        let mut stream = self.model.stream(request.clone()).await?;
+        let resume = self.clone();
+
+        Ok(Box::pin(async_stream::stream! {
+            let mut partial = String::new();
+            let mut resumable = true;
+
+            while let Some(chunk) = stream.next().await {
+                let mut error = match chunk {
+                    Ok(chunk) => {
+                        match &chunk {
+                            StreamingChoice::Message(text) => partial.push_str(text),
+                            StreamingChoice::ToolCall(..) => resumable = false,
+                            StreamingChoice::Usage(_) => {}
+                        }
+                        yield Ok(chunk);
+                        continue;
+                    }
+                    Err(error) => error,
+                };
+                if partial.is_empty() || !resumable {
+                    yield Err(error);
+                    return;
+                }
+
+                tracing::warn!("Stream interrupted after {} characters: {}", partial.len(), error);
+                for _ in 0..resume.max_resumes {
+                    let continuation = resume.continuation(&request, &partial);
+                    match resume.model.completion(continuation).await {
+                        Ok(response) => {
+                            let text = response
+                                .choice
+                                .iter()
+                                .filter_map(|content| match content {
+                                    AssistantContent::Text(text) => Some(text.text.as_str()),
+                                    AssistantContent::ToolCall(_) => None,
+                                })
+                                .collect::<String>();
+                            yield Ok(StreamingChoice::Message(text));
+                            return;
+                        }
+                        Err(e) => error = e,
+                    }
+                }
+
+                yield Err(CompletionError::Interrupted {
+                    partial,
+                    error: Box::new(error),
+                });
+                return;
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::test_utils::{response, MockModel};
+
+    /// Streams "Once upon " then fails, and continues with "a time." when asked to, along with
+    /// the continuation requests it receives
+    fn flaky() -> (MockModel, Arc<Mutex<Vec<CompletionRequest>>>) {
+        let continuations = Arc::new(Mutex::new(vec![]));
+        let received = continuations.clone();
+
+        let model = MockModel::new(move |request| {
+            received.lock().unwrap().push(request);
+            Ok(response(AssistantContent::text("a time."), ()))
+        })
+        .stream(|_| {
+            Box::pin(futures::stream::iter([
+                Ok(StreamingChoice::Message("Once ".to_string())),
+                Ok(StreamingChoice::Message("upon ".to_string())),
+                Err(CompletionError::ResponseError(
+                    "Connection reset".to_string(),
+                )),
+            ]))
+        });
+
+        (model, continuations)
+    }
+
+    #[tokio::test]
+    async fn test_resume_interrupted_stream() {
+        let (model, continuations) = flaky();
+        let resume = Resume::new(model);
+
+        let chunks = resume
+            .completion_request("Tell me a story")
+            .stream()
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap().to_string())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(chunks.concat(), "Once upon a time.");
+
+        let continuations = continuations.lock().unwrap();
+        assert_eq!(
+            continuations[0].chat_history,
+            vec![
+                Message::user("Tell me a story"),
+                Message::assistant("Once upon ")
+            ]
+        );
+        assert!(continuations[0]
+            .prompt
+            .rag_text()
+            .unwrap()
+            .ends_with("Continue from: Once upon "));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_error() {
+        let resume = Resume::new(flaky().0).max_resumes(0);
+
+        let chunks = resume
+            .completion_request("Tell me a story")
+            .stream()
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert!(matches!(
+            chunks.last(),
+            Some(Err(CompletionError::Interrupted { partial, error }))
+                if partial == "Once upon "
+                    && matches!(**error, CompletionError::ResponseError(_))
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+//! Retries of model calls failing with transient errors (e.g.: rate limiting, server errors or
+//! network errors), with exponential backoff.
+//!
+//! [Retry] wraps a completion, streaming completion or embedding model. Completion requests are
+//! sent with an idempotency key (an `Idempotency-Key` header by default) which is the same for all
+//! the attempts of a request, so that providers supporting idempotency keys do not process (and
+//! charge) a request twice when its response was lost on a flaky network. Providers that do not
+//! support them ignore the header. A key set by the caller in the
+//! [request options](crate::http::RequestOptions) of a request is reused.
+//!
+//! This module is only available with the `retry` feature, and requires a tokio runtime.
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use rig::{completion::Prompt, providers::openai, retry::Retry};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//!
+//! let model = Retry::new(openai.completion_model(openai::GPT_4O))
+//!     .max_retries(5)
+//!     .initial_delay(Duration::from_secs(1));
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//! let answer = agent.prompt("Hello!").await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    http::RequestOptions,
+    streaming::{StreamingCompletionModel, StreamingResult},
+};
+
+/// Wraps a model and retries its calls failing with transient errors, see the
+/// [module documentation](self).
+#[derive(Clone)]
+pub struct Retry<M> {
+    pub model: M,
+    max_retries: usize,
+    initial_delay: Duration,
+    max_delay: Duration,
+    idempotency_header: Option<String>,
+}
+
+impl<M> Retry<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            max_retries: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            idempotency_header: Some("Idempotency-Key".to_string()),
+        }
+    }
+
+    /// Set the maximum number of retries of a call (defaults to 3).
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry, doubled at each retry (defaults to 500
+    /// milliseconds).
+    pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Set the maximum delay between two retries (defaults to 30 seconds).
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the header of the idempotency keys (defaults to `Idempotency-Key`), for providers
+    /// using another header.
+    pub fn idempotency_header(mut self, header: &str) -> Self {
+        self.idempotency_header = Some(header.to_string());
+        self
+    }
+
+    /// Do not send idempotency keys.
+    pub fn without_idempotency_keys(mut self) -> Self {
+        self.idempotency_header = None;
+        self
+    }
+
+    /// Add an idempotency key to the request, unless it already has one.
+    fn with_idempotency_key(&self, mut request: CompletionRequest) -> CompletionRequest {
+        if let Some(header) = &self.idempotency_header {
+            let has_key = request
+                .request_options
+                .headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(header));
+            if !has_key {
+                request.request_options =
+                    request.request_options.header(header, &idempotency_key());
+            }
+        }
+        request
+    }
+
+    /// Run `call` until it succeeds, fails with a permanent error, or the retries are exhausted.
+    async fn retry<T, E, F, Fut>(&self, mut call: F) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+        E: TransientError,
+    {
+        let mut delay = self.initial_delay;
+        let mut retries = 0;
+
+        loop {
+            match call().await {
+                Err(error) if retries < self.max_retries && error.is_transient() => {
+                    retries += 1;
+                    tracing::warn!(
+                        target: "rig",
+                        "Retrying a model call ({retries}/{}) in {delay:?} after error: {error}",
+                        self.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(self.max_delay);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+trait TransientError: std::fmt::Display {
+    /// Whether the call can succeed if retried.
+    fn is_transient(&self) -> bool;
+}
+
+impl TransientError for CompletionError {
+    fn is_transient(&self) -> bool {
+        match self {
+            CompletionError::HttpError(error) => is_transient_http_error(error),
+            // E.g.: a truncated response body
+            CompletionError::JsonError(_) => true,
+            CompletionError::ProviderError(message) => is_transient_message(message),
+            _ => false,
+        }
+    }
+}
+
+impl TransientError for EmbeddingError {
+    fn is_transient(&self) -> bool {
+        match self {
+            EmbeddingError::HttpError(error) => is_transient_http_error(error),
+            EmbeddingError::JsonError(_) => true,
+            EmbeddingError::ProviderError(message) => is_transient_message(message),
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_http_error(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => status.is_server_error() || status.as_u16() == 429,
+        None => error.is_timeout() || error.is_connect() || error.is_request(),
+    }
+}
+
+/// Providers return the body of error responses as the message of [CompletionError::ProviderError]
+/// (without the status code in most cases), so transient errors are recognized by their content.
+fn is_transient_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+
+    let has_status = message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| matches!(word, "429" | "500" | "502" | "503" | "504" | "529"));
+
+    has_status
+        || [
+            "rate limit",
+            "rate_limit",
+            "too many requests",
+            "overloaded",
+            "timed out",
+            "timeout",
+            "temporarily unavailable",
+            "internal server error",
+            "bad gateway",
+            "service unavailable",
+        ]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// A random key identifying a request.
+fn idempotency_key() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    // `RandomState` is randomly seeded for each instance
+    let random = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.finish()
+    };
+    format!("rig-{:016x}{:016x}", random(), random())
+}
+
+impl<M: CompletionModel> CompletionModel for Retry<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let request = self.with_idempotency_key(request);
+        self.retry(|| self.model.completion(request.clone())).await
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+/// Only the start of streams is retried: a stream failing midway is returned as is.
+impl<M: StreamingCompletionModel + Sync> StreamingCompletionModel for Retry<M> {
+    async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
+        let request = self.with_idempotency_key(request);
+        self.retry(|| self.model.stream(request.clone())).await
+    }
+}
+
+/// Embedding calls do not have side effects, and are retried without idempotency keys.
+impl<M: EmbeddingModel> EmbeddingModel for Retry<M> {
+    const MAX_DOCUMENTS: usize = M::MAX_DOCUMENTS;
+
+    fn ndims(&self) -> usize {
+        self.model.ndims()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(texts, RequestOptions::default())
+            .await
+    }
+
+    async fn embed_texts_with_options(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        let texts = texts.into_iter().collect::<Vec<_>>();
+        self.retry(|| {
+            self.model
+                .embed_texts_with_options(texts.clone(), request_options.clone())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        message::AssistantContent,
+        test_utils::{response, MockModel},
+    };
+
+    /// A model failing with the given errors, then succeeding, along with the idempotency keys
+    /// of the requests it receives
+    fn flaky(errors: Vec<CompletionError>) -> (MockModel, Arc<Mutex<Vec<String>>>) {
+        let errors = Mutex::new(errors);
+        let keys = Arc::new(Mutex::new(vec![]));
+        let received = keys.clone();
+
+        let model = MockModel::new(move |request| {
+            if let Some((_, key)) = request
+                .request_options
+                .headers
+                .iter()
+                .find(|(name, _)| name == "Idempotency-Key")
+            {
+                received.lock().unwrap().push(key.clone());
+            }
+
+            let mut errors = errors.lock().unwrap();
+            if !errors.is_empty() {
+                return Err(errors.remove(0));
+            }
+
+            Ok(response(AssistantContent::text("Hello!"), ()))
+        });
+
+        (model, keys)
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequestBuilder::new(MockModel::text("Hello!"), "Hi").build()
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_idempotency_key() {
+        let (model, received) = flaky(vec![
+            CompletionError::ProviderError("429 Too Many Requests".to_string()),
+            CompletionError::ProviderError("{\"type\": \"overloaded_error\"}".to_string()),
+        ]);
+        let retry = Retry::new(model).initial_delay(Duration::from_millis(1));
+
+        retry.completion(request()).await.unwrap();
+
+        let keys = received.lock().unwrap().clone();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.iter().all(|key| *key == keys[0]));
+
+        // Each request has its own key
+        retry.completion(request()).await.unwrap();
+        let keys = received.lock().unwrap().clone();
+        assert_ne!(keys[3], keys[0]);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_and_exhausted_retries() {
+        let (model, keys) = flaky(vec![CompletionError::ProviderError(
+            "Invalid API key".to_string(),
+        )]);
+        let retry = Retry::new(model).initial_delay(Duration::from_millis(1));
+        assert!(retry.completion(request()).await.is_err());
+        assert_eq!(keys.lock().unwrap().len(), 1);
+
+        let (model, keys) = flaky(
+            (0..3)
+                .map(|_| CompletionError::ProviderError("503 Service Unavailable".to_string()))
+                .collect(),
+        );
+        let retry = Retry::new(model)
+            .max_retries(1)
+            .initial_delay(Duration::from_millis(1));
+        assert!(retry.completion(request()).await.is_err());
+        assert_eq!(keys.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_is_transient_message() {
+        assert!(is_transient_message("429 Too Many Requests"));
+        assert!(is_transient_message("{\"error\": {\"code\": 503}}"));
+        assert!(!is_transient_message("max_tokens must be less than 5000"));
+    }
+
+    #[tokio::test]
+    async fn test_caller_idempotency_key() {
+        let (model, keys) = flaky(vec![]);
+        let retry = Retry::new(model);
+
+        let mut request = request();
+        request.request_options = RequestOptions::new().header("Idempotency-Key", "order-42");
+        retry.completion(request).await.unwrap();
+
+        assert_eq!(*keys.lock().unwrap(), vec!["order-42".to_string()]);
+    }
+}
@@ -0,0 +1,386 @@
+//! This module provides [Router], a completion model routing each request to the model of a
+//! complexity [Tier], to answer the simple requests with a cheap model and the complex ones with
+//! a strong model.
+//!
+//! The tier of a request is decided by a [ComplexityClassifier]: either the [Heuristics] of the
+//! router (length of the prompt, code, reasoning keywords, tools and length of the chat history),
+//! or a small model classifying the prompt (see [ModelClassifier]). The decisions of the router
+//! are counted in its [RoutingStats], and logged at the debug level.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     completion::Prompt,
+//!     providers::openai,
+//!     routing::{ModelClassifier, Router, Tier},
+//! };
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let openai = openai::Client::from_env();
+//!
+//! // Route with the default heuristics
+//! let model = Router::new(openai.completion_model(openai::GPT_4O))
+//!     .tier(Tier::Simple, openai.completion_model(openai::GPT_4O_MINI));
+//!
+//! // Or with a small model
+//! let model = model.classifier(ModelClassifier::new(
+//!     openai.completion_model(openai::GPT_4O_MINI),
+//! ));
+//!
+//! let agent = rig::agent::AgentBuilder::new(model.clone()).build();
+//! let answer = agent.prompt("What is the capital of France?").await?;
+//! println!("{:?}", model.stats());
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classifier::{Classifier, ClassifierBuilder},
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    tokens::{Heuristic, TokenCounter},
+};
+
+/// The complexity tier of a request.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, JsonSchema,
+)]
+pub enum Tier {
+    /// Small talk, simple factual questions, short rewrites
+    Simple,
+    /// Explanations, summaries, short code snippets
+    Moderate,
+    /// Multi-step reasoning, planning, math, complex code or long documents
+    Complex,
+}
+
+/// Decides the complexity tier of the requests.
+pub trait ComplexityClassifier: Send + Sync {
+    fn classify(&self, request: &CompletionRequest) -> impl Future<Output = Tier> + Send;
+}
+
+/// Classifies the requests with heuristics: the number of tokens of the prompt (with the
+/// documents and chat history, which count for half), code, reasoning keywords and tools.
+#[derive(Clone, Debug)]
+pub struct Heuristics {
+    moderate_tokens: usize,
+    complex_tokens: usize,
+    keywords: Vec<String>,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self {
+            moderate_tokens: 150,
+            complex_tokens: 1500,
+            keywords: [
+                "step by step",
+                "prove",
+                "derive",
+                "analyze",
+                "analyse",
+                "compare",
+                "design",
+                "implement",
+                "refactor",
+                "debug",
+                "optimize",
+                "plan",
+                "why",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+}
+
+impl Heuristics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of tokens from which a request is at least [Tier::Moderate] (150 by
+    /// default) and [Tier::Complex] (1500 by default).
+    pub fn token_thresholds(mut self, moderate: usize, complex: usize) -> Self {
+        self.moderate_tokens = moderate;
+        self.complex_tokens = complex;
+        self
+    }
+
+    /// Set the (case insensitive) keywords of the prompts requiring reasoning.
+    pub fn keywords<S: AsRef<str>>(mut self, keywords: impl IntoIterator<Item = S>) -> Self {
+        self.keywords = keywords
+            .into_iter()
+            .map(|keyword| keyword.as_ref().to_lowercase())
+            .collect();
+        self
+    }
+
+    pub fn tier(&self, request: &CompletionRequest) -> Tier {
+        let counter = Heuristic::default();
+        let prompt = request.prompt.rag_text().unwrap_or_default();
+        let context = counter.count_messages_tokens(&request.chat_history)
+            + request
+                .documents
+                .iter()
+                .map(|document| counter.count_tokens(&document.text))
+                .sum::<usize>();
+        let tokens = counter.count_tokens(&prompt) + context / 2;
+
+        if tokens >= self.complex_tokens {
+            return Tier::Complex;
+        }
+
+        // Each signal raises the tier by one level
+        let lowercase = prompt.to_lowercase();
+        let words = lowercase
+            .split(|c: char| !c.is_alphanumeric())
+            .collect::<Vec<_>>();
+        let signals = [
+            tokens >= self.moderate_tokens,
+            prompt.contains("```"),
+            self.keywords.iter().any(|keyword| {
+                if keyword.contains(' ') {
+                    lowercase.contains(keyword.as_str())
+                } else {
+                    words.contains(&keyword.as_str())
+                }
+            }),
+            prompt.matches('?').count() > 2,
+            request.tools.len() > 3,
+        ]
+        .into_iter()
+        .filter(|signal| *signal)
+        .count();
+
+        match signals {
+            0 => Tier::Simple,
+            1 => Tier::Moderate,
+            _ => Tier::Complex,
+        }
+    }
+}
+
+impl ComplexityClassifier for Heuristics {
+    async fn classify(&self, request: &CompletionRequest) -> Tier {
+        self.tier(request)
+    }
+}
+
+/// Classifies the prompts with a (small) model, see [crate::classifier]. Requests whose
+/// classification fails are routed to the fallback tier ([Tier::Complex] by default).
+pub struct ModelClassifier<M: CompletionModel> {
+    classifier: Classifier<M, Tier>,
+    fallback: Tier,
+}
+
+impl<M: CompletionModel> ModelClassifier<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            classifier: ClassifierBuilder::new(model)
+                .preamble(
+                    "Classify the complexity of the request of the user, i.e.: the capabilities \
+                     of the model required to answer it well.",
+                )
+                .build(),
+            fallback: Tier::Complex,
+        }
+    }
+
+    pub fn fallback(mut self, tier: Tier) -> Self {
+        self.fallback = tier;
+        self
+    }
+}
+
+impl<M: CompletionModel> ComplexityClassifier for ModelClassifier<M> {
+    async fn classify(&self, request: &CompletionRequest) -> Tier {
+        let Some(prompt) = request.prompt.rag_text() else {
+            return self.fallback;
+        };
+
+        match self.classifier.classify(&prompt).await {
+            Ok(classification) => classification.label,
+            Err(e) => {
+                tracing::warn!(target: "rig", "Complexity classification failed: {e}");
+                self.fallback
+            }
+        }
+    }
+}
+
+/// Number of requests routed to each tier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoutingStats {
+    pub simple: u64,
+    pub moderate: u64,
+    pub complex: u64,
+}
+
+/// Routes the requests to the model of their complexity tier, see the
+/// [module documentation](self). Clones of a [Router] share the same stats.
+pub struct Router<M, C = Heuristics> {
+    default: M,
+    models: HashMap<Tier, M>,
+    classifier: Arc<C>,
+    stats: Arc<Mutex<RoutingStats>>,
+}
+
+impl<M: Clone, C> Clone for Router<M, C> {
+    fn clone(&self) -> Self {
+        Self {
+            default: self.default.clone(),
+            models: self.models.clone(),
+            classifier: self.classifier.clone(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl<M: CompletionModel> Router<M> {
+    /// Route all the tiers to the default model, with the default [Heuristics].
+    pub fn new(default: M) -> Self {
+        Self {
+            default,
+            models: HashMap::new(),
+            classifier: Arc::new(Heuristics::default()),
+            stats: Arc::new(Mutex::new(RoutingStats::default())),
+        }
+    }
+}
+
+impl<M: CompletionModel, C: ComplexityClassifier> Router<M, C> {
+    /// Route the requests of the tier to the model.
+    pub fn tier(mut self, tier: Tier, model: M) -> Self {
+        self.models.insert(tier, model);
+        self
+    }
+
+    /// Set the classifier deciding the tiers of the requests.
+    pub fn classifier<C2: ComplexityClassifier>(self, classifier: C2) -> Router<M, C2> {
+        Router {
+            default: self.default,
+            models: self.models,
+            classifier: Arc::new(classifier),
+            stats: self.stats,
+        }
+    }
+
+    /// The model of the tier.
+    pub fn model(&self, tier: Tier) -> &M {
+        self.models.get(&tier).unwrap_or(&self.default)
+    }
+
+    pub fn stats(&self) -> RoutingStats {
+        *self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Decide the tier of the request and count the decision.
+    pub async fn route(&self, request: &CompletionRequest) -> Tier {
+        let tier = self.classifier.classify(request).await;
+        tracing::debug!(target: "rig", "Request routed to the {tier:?} tier");
+
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        match tier {
+            Tier::Simple => stats.simple += 1,
+            Tier::Moderate => stats.moderate += 1,
+            Tier::Complex => stats.complex += 1,
+        }
+        tier
+    }
+}
+
+impl<M, C> CompletionModel for Router<M, C>
+where
+    M: CompletionModel,
+    C: ComplexityClassifier,
+{
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let tier = self.route(&request).await;
+        self.model(tier).completion(request).await
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        // Dry runs do not call the classifier: the body of the default model is returned
+        self.default.request_body(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        message::AssistantContent,
+        metadata::{Document, DocumentMetadata},
+        test_utils::{response, MockModel},
+    };
+
+    /// Answers with its name, also returned as raw response
+    fn mock(name: &'static str) -> MockModel<&'static str> {
+        MockModel::new(move |_| Ok(response(AssistantContent::text(name), name)))
+    }
+
+    fn request(prompt: &str) -> CompletionRequest {
+        CompletionRequestBuilder::new(mock(""), prompt).build()
+    }
+
+    #[test]
+    fn test_heuristics() {
+        let heuristics = Heuristics::new();
+
+        assert_eq!(
+            heuristics.tier(&request("What is the capital of France?")),
+            Tier::Simple
+        );
+        assert_eq!(
+            heuristics.tier(&request("Why is the sky blue?")),
+            Tier::Moderate
+        );
+        assert_eq!(
+            heuristics.tier(&request(
+                "Debug this function step by step:\n```rust\nfn main() {}\n```"
+            )),
+            Tier::Complex
+        );
+
+        let mut long = request("Summarize the document.");
+        long.documents.push(Document::new(
+            DocumentMetadata::new("doc"),
+            &"Lorem ipsum dolor sit amet. ".repeat(500),
+        ));
+        assert_eq!(heuristics.tier(&long), Tier::Complex);
+    }
+
+    #[tokio::test]
+    async fn test_router() {
+        let router = Router::new(mock("strong")).tier(Tier::Simple, mock("cheap"));
+        let send = |prompt: &str| CompletionRequestBuilder::new(router.clone(), prompt).send();
+
+        assert_eq!(send("Hi!").await.unwrap().raw_response, "cheap");
+        assert_eq!(send("Why?").await.unwrap().raw_response, "strong");
+        assert_eq!(
+            router.stats(),
+            RoutingStats {
+                simple: 1,
+                moderate: 1,
+                complex: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,533 @@
+//! This module provides [Scheduler], a runner of agents or pipelines on cron-like schedules
+//! (e.g.: report-generation or monitoring agents).
+//!
+//! Tasks are async closures returning a `Result`. By default, a run is skipped when the previous
+//! run of the same task is still in progress (see [Overlap]), and failed runs are reported to
+//! the callback set with [Scheduler::on_failure].
+//!
+//! This module is only available with the `scheduler` feature, and requires a tokio runtime.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use rig::{
+//!     completion::Prompt,
+//!     providers::openai,
+//!     scheduler::{Schedule, Scheduler},
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let agent = Arc::new(openai.agent(openai::GPT_4O).build());
+//!
+//! let scheduler = Scheduler::new()
+//!     // Every weekday at 09:00 UTC
+//!     .task("daily-report", Schedule::cron("0 9 * * 1-5")?, move || {
+//!         let agent = agent.clone();
+//!         async move {
+//!             let report = agent.prompt("Write the daily report").await?;
+//!             println!("{report}");
+//!             Ok::<_, rig::completion::PromptError>(())
+//!         }
+//!     })
+//!     .on_failure(|task, error| eprintln!("Task {task} failed: {error}"))
+//!     .start();
+//!
+//! // Later, e.g.: on shutdown
+//! scheduler.stop();
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    fmt::Display,
+    future::Future,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use tokio::task::JoinHandle;
+
+use crate::telemetry::utc_datetime;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("InvalidCronError: {0}")]
+    InvalidCron(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Whether the day-of-month and day-of-week fields are restricted (i.e.: not `*`)
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Cron {
+    fn parse(expr: &str) -> Result<Self, ScheduleError> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expr => expr,
+        };
+
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(ScheduleError::InvalidCron(format!(
+                "expected 5 fields in `{expr}`"
+            )));
+        };
+
+        let field = |spec: &str, name: &str, min: u64, max: u64| {
+            parse_field(spec, min, max).ok_or_else(|| {
+                ScheduleError::InvalidCron(format!("invalid {name} field `{spec}` in `{expr}`"))
+            })
+        };
+
+        let weekday_bits = field(weekdays, "day-of-week", 0, 7)?;
+        Ok(Self {
+            minutes: field(minutes, "minute", 0, 59)?,
+            hours: field(hours, "hour", 0, 23)?,
+            days: field(days, "day-of-month", 1, 31)?,
+            months: field(months, "month", 1, 12)?,
+            // Both 0 and 7 are Sunday
+            weekdays: (weekday_bits | weekday_bits >> 7) & 0x7f,
+            days_restricted: !days.starts_with('*'),
+            weekdays_restricted: !weekdays.starts_with('*'),
+        })
+    }
+
+    fn day_matches(&self, day: u64, weekday: u64) -> bool {
+        let day = self.days & 1 << day != 0;
+        let weekday = self.weekdays & 1 << weekday != 0;
+
+        // As in cron, a day matches either field when both are restricted
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut minute = secs / 60 + 1;
+
+        // Enough to go through a full leap year cycle for schedules like `0 0 29 2 *`
+        for _ in 0..12_000 {
+            let (_, month, day, hours, minutes, _, _) = utc_datetime(minute * 60_000);
+            // 1970-01-01 was a Thursday
+            let weekday = (minute / 1440 + 4) % 7;
+
+            if self.months & 1 << month == 0 || !self.day_matches(day, weekday) {
+                minute = (minute / 1440 + 1) * 1440;
+            } else if self.hours & 1 << hours == 0 {
+                minute = (minute / 60 + 1) * 60;
+            } else if self.minutes & 1 << minutes == 0 {
+                minute += 1;
+            } else {
+                return Some(UNIX_EPOCH + Duration::from_secs(minute * 60));
+            }
+        }
+
+        None
+    }
+}
+
+/// Parse a cron field (e.g.: `*/15`, `1-5` or `0,30`) into a bit set of the values it matches.
+fn parse_field(spec: &str, min: u64, max: u64) -> Option<u64> {
+    let mut bits = 0;
+
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u64>().ok().filter(|s| *s > 0)?)),
+            None => (part, None),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let start = range.parse().ok()?;
+            // `5/10` means every 10 from 5
+            (start, if step.is_some() { max } else { start })
+        };
+
+        if start < min || end > max || start > end {
+            return None;
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Some(bits)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScheduleKind {
+    Cron(Cron),
+    Every(Duration),
+}
+
+/// When a task runs: either a cron expression, in UTC, or a fixed interval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule(ScheduleKind);
+
+impl Schedule {
+    /// Create a schedule from a standard 5 fields cron expression (minute, hour, day of month,
+    /// month and day of week, e.g.: `*/15 9-17 * * 1-5`), evaluated in UTC.
+    ///
+    /// Fields support `*`, lists (`1,15`), ranges (`1-5`) and steps (`*/10`, `0-30/5`), but
+    /// not names (e.g.: `MON`). The `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly`
+    /// shorthands are also supported.
+    pub fn cron(expr: &str) -> Result<Self, ScheduleError> {
+        Ok(Self(ScheduleKind::Cron(Cron::parse(expr)?)))
+    }
+
+    /// Create a schedule running every `interval`, starting one interval from now.
+    pub fn every(interval: Duration) -> Self {
+        Self(ScheduleKind::Every(interval))
+    }
+
+    /// The first time of the schedule strictly after `time`, or `None` if there is none (e.g.:
+    /// for `0 0 30 2 *`).
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        match self.0 {
+            ScheduleKind::Cron(cron) => cron.next_after(time),
+            ScheduleKind::Every(interval) => Some(time + interval),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        Self::cron(expr)
+    }
+}
+
+/// What to do when a task is due while its previous run is still in progress.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overlap {
+    /// Skip the run (the default)
+    #[default]
+    Skip,
+    /// Start the run anyway
+    Allow,
+}
+
+/// Statistics of the runs of a task.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Number of finished runs, including the failed ones
+    pub runs: u64,
+    pub failures: u64,
+    /// Number of runs skipped because the previous run was still in progress
+    pub skipped: u64,
+    /// Start time of the last run
+    pub last_run: Option<SystemTime>,
+    /// Error of the last run, if it failed
+    pub last_error: Option<String>,
+}
+
+type TaskFn = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+type FailureCallback = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+struct Task {
+    name: String,
+    schedule: Schedule,
+    run: TaskFn,
+}
+
+#[derive(Default)]
+struct TaskState {
+    running: AtomicUsize,
+    stats: Mutex<TaskStats>,
+}
+
+/// Decrements the number of running runs of a task, even if the run panics.
+struct RunningGuard(Arc<TaskState>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Builder of the scheduled tasks, see the [module documentation](self).
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Vec<Task>,
+    overlap: Overlap,
+    on_failure: Option<FailureCallback>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a task, replacing the task with the same name if any. `task` is called on every
+    /// run, and the output of its future is discarded.
+    pub fn task<F, Fut, T, E>(mut self, name: &str, schedule: Schedule, task: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Display,
+    {
+        self.tasks.retain(|t| t.name != name);
+        self.tasks.push(Task {
+            name: name.to_string(),
+            schedule,
+            run: Arc::new(move || {
+                task()
+                    .map(|result| result.map(|_| ()).map_err(|e| e.to_string()))
+                    .boxed()
+            }),
+        });
+        self
+    }
+
+    /// Set what to do when a task is due while its previous run is still in progress (defaults
+    /// to [Overlap::Skip]).
+    pub fn overlap(mut self, overlap: Overlap) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Set a callback called with the name of the task and the error when a run fails.
+    pub fn on_failure(mut self, callback: impl Fn(&str, &str) + Send + Sync + 'static) -> Self {
+        self.on_failure = Some(Arc::new(callback));
+        self
+    }
+
+    /// Start running the tasks in background tasks, until [SchedulerHandle::stop] is called.
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn start(self) -> SchedulerHandle {
+        let mut states = vec![];
+        let mut handles = vec![];
+
+        for task in self.tasks {
+            let state = Arc::new(TaskState::default());
+            states.push((task.name.clone(), state.clone()));
+
+            let overlap = self.overlap;
+            let on_failure = self.on_failure.clone();
+            handles.push(tokio::spawn(async move {
+                let mut next = task.schedule.next_after(SystemTime::now());
+
+                while let Some(time) = next {
+                    let delay = time.duration_since(SystemTime::now()).unwrap_or_default();
+                    tokio::time::sleep(delay).await;
+
+                    // Don't catch up on the runs missed while the runtime was busy
+                    next = task.schedule.next_after(time.max(SystemTime::now()));
+
+                    if overlap == Overlap::Skip && state.running.load(Ordering::SeqCst) > 0 {
+                        tracing::warn!(
+                            "Skipping a run of task {}: the previous run is still in progress",
+                            task.name
+                        );
+                        state
+                            .stats
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .skipped += 1;
+                        continue;
+                    }
+
+                    state.running.fetch_add(1, Ordering::SeqCst);
+                    let guard = RunningGuard(state.clone());
+                    let future = (task.run)();
+                    let name = task.name.clone();
+                    let on_failure = on_failure.clone();
+                    tokio::spawn(async move {
+                        let started = SystemTime::now();
+                        let result = future.await;
+
+                        {
+                            let mut stats = guard.0.stats.lock().unwrap_or_else(|e| e.into_inner());
+                            stats.runs += 1;
+                            stats.last_run = Some(started);
+                            stats.last_error = result.as_ref().err().cloned();
+                            if result.is_err() {
+                                stats.failures += 1;
+                            }
+                        }
+
+                        if let Err(error) = result {
+                            tracing::warn!("Task {name} failed: {error}");
+                            if let Some(on_failure) = on_failure {
+                                on_failure(&name, &error);
+                            }
+                        }
+                    });
+                }
+            }));
+        }
+
+        SchedulerHandle { states, handles }
+    }
+}
+
+/// Handle to the running tasks of a [Scheduler].
+pub struct SchedulerHandle {
+    states: Vec<(String, Arc<TaskState>)>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+    /// Get the statistics of the runs of a task.
+    pub fn stats(&self, name: &str) -> Option<TaskStats> {
+        self.states
+            .iter()
+            .find(|(task, _)| task == name)
+            .map(|(_, state)| {
+                state
+                    .stats
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone()
+            })
+    }
+
+    /// Whether a run of the task is in progress.
+    pub fn is_running(&self, name: &str) -> bool {
+        self.states
+            .iter()
+            .any(|(task, state)| task == name && state.running.load(Ordering::SeqCst) > 0)
+    }
+
+    /// Stop starting new runs. The runs in progress are not aborted.
+    pub fn stop(self) {
+        for handle in self.handles {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{parse_rfc3339, rfc3339};
+
+    fn next(expr: &str, after: &str) -> Option<String> {
+        let after = UNIX_EPOCH + Duration::from_secs_f64(parse_rfc3339(after).unwrap());
+        Schedule::cron(expr).unwrap().next_after(after).map(rfc3339)
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let after = "2024-01-31T07:30:00Z";
+
+        assert_eq!(
+            next("* * * * *", after).as_deref(),
+            Some("2024-01-31T07:31:00.000Z")
+        );
+        assert_eq!(
+            next("*/15 * * * *", after).as_deref(),
+            Some("2024-01-31T07:45:00.000Z")
+        );
+        assert_eq!(
+            next("0 9 * * 1-5", after).as_deref(),
+            Some("2024-01-31T09:00:00.000Z")
+        );
+        // 2024-02-03 is a Saturday
+        assert_eq!(
+            next("0 9 * * 6", after).as_deref(),
+            Some("2024-02-03T09:00:00.000Z")
+        );
+        assert_eq!(
+            next("0 0 29 2 *", after).as_deref(),
+            Some("2024-02-29T00:00:00.000Z")
+        );
+        assert_eq!(
+            next("@monthly", after).as_deref(),
+            Some("2024-02-01T00:00:00.000Z")
+        );
+        assert_eq!(
+            next("30 7 * * 0,7", after).as_deref(),
+            Some("2024-02-04T07:30:00.000Z")
+        );
+        // Either the 15th or a Friday
+        assert_eq!(
+            next("0 12 15 * 5", after).as_deref(),
+            Some("2024-02-02T12:00:00.000Z")
+        );
+        assert_eq!(next("0 0 30 2 *", after), None);
+    }
+
+    #[test]
+    fn test_cron_parse_errors() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+        ] {
+            assert!(Schedule::cron(expr).is_err(), "{expr}");
+        }
+        assert!("5-10/2,30 0 1,15 */3 *".parse::<Schedule>().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler() {
+        let failures = Arc::new(Mutex::new(vec![]));
+        let failures_clone = failures.clone();
+
+        let scheduler = Scheduler::new()
+            .task(
+                "slow",
+                Schedule::every(Duration::from_millis(20)),
+                || async {
+                    tokio::time::sleep(Duration::from_millis(70)).await;
+                    Ok::<_, String>(())
+                },
+            )
+            .task(
+                "failing",
+                Schedule::every(Duration::from_millis(20)),
+                || async { Err::<(), _>("Unavailable") },
+            )
+            .on_failure(move |task, error| {
+                failures_clone
+                    .lock()
+                    .unwrap()
+                    .push(format!("{task}: {error}"))
+            })
+            .start();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let slow = scheduler.stats("slow").unwrap();
+        let failing = scheduler.stats("failing").unwrap();
+        scheduler.stop();
+
+        assert!(slow.runs >= 1 && slow.skipped >= 2, "{slow:?}");
+        assert_eq!(slow.failures, 0);
+        assert!(failing.runs >= 2 && failing.failures == failing.runs);
+        assert_eq!(failing.last_error.as_deref(), Some("Unavailable"));
+        assert_eq!(failures.lock().unwrap()[0], "failing: Unavailable");
+    }
+}
@@ -0,0 +1,212 @@
+//! This module provides a parser for server-sent events (SSE), the format used by the
+//! providers to stream completions.
+//!
+//! The parser handles events split across (or sharing) network chunks, including UTF-8
+//! characters split across chunks, multi-line `data` fields, keep-alive comments and the
+//! `data: [DONE]` sentinel ending OpenAI-style streams.
+//!
+//! # Example
+//! ```rust
+//! use futures::StreamExt;
+//! use rig::sse;
+//!
+//! # async fn run() {
+//! let chunks = futures::stream::iter(vec![
+//!     Ok::<_, std::io::Error>(": keep-alive\n\nevent: message\ndata: {\"text\":".as_bytes()),
+//!     Ok(" \"Hello\"}\n\ndata: [DONE]\n\n".as_bytes()),
+//! ]);
+//!
+//! let mut events = std::pin::pin!(sse::events(chunks));
+//! while let Some(event) = events.next().await {
+//!     let event = event.unwrap();
+//!     assert_eq!(event.event.as_deref(), Some("message"));
+//!     assert_eq!(event.data, "{\"text\": \"Hello\"}");
+//! }
+//! # }
+//! ```
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+
+/// The data of the event ending OpenAI-style streams
+pub const DONE: &str = "[DONE]";
+
+/// A server-sent event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Event {
+    /// The type of the event (the `event` field), if any
+    pub event: Option<String>,
+    /// The data of the event, the `data` fields joined with newlines
+    pub data: String,
+    /// The `id` field of the event, if any
+    pub id: Option<String>,
+}
+
+impl Event {
+    /// Whether the event is the `[DONE]` sentinel ending OpenAI-style streams
+    pub fn is_done(&self) -> bool {
+        self.data.trim() == DONE
+    }
+}
+
+/// Incremental parser of server-sent events, fed with the chunks of the response body.
+#[derive(Debug, Default)]
+pub struct Parser {
+    /// Bytes of the incomplete line
+    buffer: Vec<u8>,
+    /// The event being parsed
+    event: Event,
+    /// Whether the event being parsed has a `data` field
+    has_data: bool,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a chunk of the response body, returning the events it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = vec![];
+        while let Some(newline) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=newline).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.parse_line(line.trim_end_matches(['\n', '\r'])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Complete the parsing at the end of the response body, returning the last event if
+    /// the body does not end with a blank line.
+    pub fn finish(&mut self) -> Option<Event> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+        if !line.is_empty() {
+            if let Some(event) = self.parse_line(line.trim_end_matches('\r')) {
+                return Some(event);
+            }
+        }
+        self.dispatch()
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        // Comments (e.g.: keep-alive messages)
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event.event = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.event.data.push('\n');
+                }
+                self.event.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" => self.event.id = Some(value.to_string()),
+            // `retry` and unknown fields are ignored
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = std::mem::take(&mut self.event);
+        std::mem::take(&mut self.has_data).then_some(event)
+    }
+}
+
+/// Parse a stream of response body chunks (e.g.: [reqwest::Response::bytes_stream]) into a
+/// stream of server-sent events. The stream ends at the `[DONE]` event, which is not returned.
+pub fn events<S, B, E>(chunks: S) -> impl Stream<Item = Result<Event, E>>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    stream! {
+        let mut parser = Parser::new();
+        let mut chunks = std::pin::pin!(chunks);
+
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+            for event in parser.feed(chunk.as_ref()) {
+                if event.is_done() {
+                    return;
+                }
+                yield Ok(event);
+            }
+        }
+
+        if let Some(event) = parser.finish() {
+            if !event.is_done() {
+                yield Ok(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser() {
+        let mut parser = Parser::new();
+
+        // "é" is split across chunks
+        let body = "event: delta\r\ndata: {\"text\": \"caf\u{e9}\"}\r\n\r\n: ping\n\ndata: line 1\ndata: line 2\nid: 7\n\n";
+        let (first, second) = body.as_bytes().split_at(body.find('\u{e9}').unwrap() + 1);
+
+        assert_eq!(parser.feed(b"").len(), 0);
+        assert_eq!(parser.feed(first), vec![]);
+        assert_eq!(
+            parser.feed(second),
+            vec![
+                Event {
+                    event: Some("delta".to_string()),
+                    data: "{\"text\": \"caf\u{e9}\"}".to_string(),
+                    id: None,
+                },
+                Event {
+                    event: None,
+                    data: "line 1\nline 2".to_string(),
+                    id: Some("7".to_string()),
+                },
+            ]
+        );
+
+        assert_eq!(parser.feed(b"data: last"), vec![]);
+        assert_eq!(parser.finish().map(|event| event.data), Some("last".into()));
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[tokio::test]
+    async fn test_events_until_done() {
+        let chunks = futures::stream::iter(vec![
+            Ok::<_, std::io::Error>("data: 1\n\nda".as_bytes()),
+            Ok("ta: 2\n\ndata: [DONE]\n\ndata: 3\n\n".as_bytes()),
+        ]);
+
+        let events = events(chunks)
+            .map(|event| event.unwrap().data)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events, vec!["1", "2"]);
+    }
+}
@@ -13,6 +13,7 @@ use crate::agent::Agent;
 use crate::completion::{
     CompletionError, CompletionModel, CompletionRequest, CompletionRequestBuilder, Message,
 };
+use crate::telemetry::TokenUsage;
 use futures::{Stream, StreamExt};
 use std::boxed::Box;
 use std::fmt::{Display, Formatter};
@@ -27,6 +28,10 @@ pub enum StreamingChoice {
 
     /// A tool call response chunk
     ToolCall(String, String, serde_json::Value),
+
+    /// The token usage of the whole response, sent by the providers that report it
+    /// (usually as the last chunk of the stream)
+    Usage(TokenUsage),
 }
 
 impl Display for StreamingChoice {
@@ -36,6 +41,11 @@ impl Display for StreamingChoice {
             StreamingChoice::ToolCall(name, id, params) => {
                 write!(f, "Tool call: {} {} {:?}", name, id, params)
             }
+            StreamingChoice::Usage(usage) => write!(
+                f,
+                "Usage: {} input tokens, {} output tokens",
+                usage.input_tokens, usage.output_tokens
+            ),
         }
     }
 }
@@ -105,6 +115,7 @@ pub async fn stream_to_stdout<M: StreamingCompletionModel>(
                     .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
                 println!("\nResult: {}", res);
             }
+            Ok(StreamingChoice::Usage(_)) => {}
             Err(e) => {
                 eprintln!("Error: {}", e);
                 break;
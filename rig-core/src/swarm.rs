@@ -0,0 +1,350 @@
+//! This module provides [Swarm], a group of named agents that can hand the conversation off to
+//! each other.
+//!
+//! Each agent of the swarm is given a `transfer_to_<name>` tool for every other agent. When an
+//! agent calls one of these tools, the conversation (the prompt and the full chat history) is
+//! transferred to the target agent, along with the reason of the handoff (as a context document),
+//! and the target agent responds instead. This enables triage/specialist patterns, where a front agent routes
+//! requests to specialized agents.
+//!
+//! The handoffs of each run are recorded in [SwarmResponse::handoffs]. To protect against
+//! agents handing the conversation back and forth forever, the number of handoffs per run is
+//! limited (see [Swarm::max_handoffs]).
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::openai, swarm::Swarm};
+//!
+//! # async fn run() -> Result<(), rig::swarm::SwarmError> {
+//! let openai = openai::Client::from_env();
+//!
+//! let swarm = Swarm::new(
+//!     "triage",
+//!     openai
+//!         .agent(openai::GPT_4O_MINI)
+//!         .preamble("You route customer requests to the right department.")
+//!         .build(),
+//! )
+//! .agent(
+//!     "billing",
+//!     "Handles invoices, payments and refunds",
+//!     openai.agent(openai::GPT_4O).preamble("You are a billing specialist.").build(),
+//! )
+//! .agent(
+//!     "support",
+//!     "Handles technical issues",
+//!     openai.agent(openai::GPT_4O).preamble("You are a support engineer.").build(),
+//! );
+//!
+//! let response = swarm.run("I was charged twice this month", vec![]).await?;
+//! println!("[{}] {}", response.agent, response.output);
+//!
+//! // Continue the conversation with the agent that answered
+//! let response = swarm
+//!     .run_from(&response.agent, "Can I get a refund?", response.history)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    agent::Agent,
+    completion::{
+        Completion, CompletionError, CompletionModel, Message, PromptError, ToolDefinition,
+    },
+    message::AssistantContent,
+    metadata::{Document, DocumentMetadata},
+};
+
+const HANDOFF_TOOL_PREFIX: &str = "transfer_to_";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SwarmError {
+    #[error("Unknown agent: {0}")]
+    UnknownAgent(String),
+
+    #[error("Handoff limit of {0} reached: {1:?}")]
+    HandoffLimit(usize, Vec<Handoff>),
+
+    #[error("CompletionError: {0}")]
+    CompletionError(#[from] CompletionError),
+
+    #[error("PromptError: {0}")]
+    PromptError(#[from] PromptError),
+}
+
+/// A transfer of the conversation from one agent to another.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Handoff {
+    pub from: String,
+    pub to: String,
+    /// The reason given by the agent handing the conversation off
+    pub reason: Option<String>,
+}
+
+/// The outcome of a [Swarm] run.
+#[derive(Clone, Debug)]
+pub struct SwarmResponse {
+    /// The name of the agent that responded
+    pub agent: String,
+    pub output: String,
+    /// The handoffs that happened during the run, in order
+    pub handoffs: Vec<Handoff>,
+    /// The chat history, including the prompt and the response of this run
+    pub history: Vec<Message>,
+}
+
+struct SwarmAgent<M: CompletionModel> {
+    name: String,
+    description: String,
+    agent: Agent<M>,
+}
+
+/// A group of named agents that can transfer the conversation to each other.
+pub struct Swarm<M: CompletionModel> {
+    agents: Vec<SwarmAgent<M>>,
+    max_handoffs: usize,
+}
+
+impl<M: CompletionModel> Swarm<M> {
+    /// Create a new swarm, with `entry` as the agent that receives the prompts of [Swarm::run].
+    pub fn new(entry: &str, agent: Agent<M>) -> Self {
+        Self {
+            agents: vec![SwarmAgent {
+                name: entry.to_string(),
+                description: "Handles the requests that no other agent handles".to_string(),
+                agent,
+            }],
+            max_handoffs: 5,
+        }
+    }
+
+    /// Add an agent to the swarm. The description tells the other agents when to transfer
+    /// the conversation to it.
+    pub fn agent(mut self, name: &str, description: &str, agent: Agent<M>) -> Self {
+        self.agents.push(SwarmAgent {
+            name: name.to_string(),
+            description: description.to_string(),
+            agent,
+        });
+        self
+    }
+
+    /// Set the maximum number of handoffs per run (defaults to 5).
+    pub fn max_handoffs(mut self, max_handoffs: usize) -> Self {
+        self.max_handoffs = max_handoffs;
+        self
+    }
+
+    /// The names of the agents of the swarm, the entry agent first.
+    pub fn agents(&self) -> impl Iterator<Item = &str> {
+        self.agents.iter().map(|agent| agent.name.as_str())
+    }
+
+    /// Send a prompt to the entry agent of the swarm.
+    pub async fn run(
+        &self,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<SwarmResponse, SwarmError> {
+        self.run_from(&self.agents[0].name, prompt, chat_history)
+            .await
+    }
+
+    /// Send a prompt to the given agent of the swarm (e.g.: the agent that responded to the
+    /// previous prompt).
+    pub async fn run_from(
+        &self,
+        agent: &str,
+        prompt: impl Into<Message> + Send,
+        chat_history: Vec<Message>,
+    ) -> Result<SwarmResponse, SwarmError> {
+        let prompt: Message = prompt.into();
+        let mut current = self.find(agent)?;
+        let mut handoffs: Vec<Handoff> = vec![];
+
+        loop {
+            let mut request = current
+                .agent
+                .completion(prompt.clone(), chat_history.clone())
+                .await?
+                .tools(self.handoff_tools(&current.name));
+            if let Some(handoff) = handoffs.last() {
+                request = request.document(Document::new(
+                    DocumentMetadata::new("handoff"),
+                    &format!(
+                        "The conversation was transferred to you by the `{}` agent{}",
+                        handoff.from,
+                        handoff
+                            .reason
+                            .as_ref()
+                            .map(|reason| format!(": {reason}"))
+                            .unwrap_or_default()
+                    ),
+                ));
+            }
+            let response = request.send().await?;
+
+            let output = match response.choice.first() {
+                AssistantContent::Text(text) => text.text,
+                AssistantContent::ToolCall(tool_call) => {
+                    match tool_call.function.name.strip_prefix(HANDOFF_TOOL_PREFIX) {
+                        Some(target) => {
+                            if handoffs.len() >= self.max_handoffs {
+                                return Err(SwarmError::HandoffLimit(self.max_handoffs, handoffs));
+                            }
+
+                            let next = self.find(target)?;
+                            handoffs.push(Handoff {
+                                from: current.name.clone(),
+                                to: next.name.clone(),
+                                reason: tool_call
+                                    .function
+                                    .arguments
+                                    .get("reason")
+                                    .and_then(|reason| reason.as_str())
+                                    .map(str::to_string),
+                            });
+                            current = next;
+                            continue;
+                        }
+                        None => current
+                            .agent
+                            .tools
+                            .call(
+                                &tool_call.function.name,
+                                tool_call.function.arguments.to_string(),
+                            )
+                            .await
+                            .map_err(PromptError::from)?,
+                    }
+                }
+            };
+
+            let mut history = chat_history;
+            history.push(prompt);
+            history.push(Message::assistant(output.clone()));
+
+            return Ok(SwarmResponse {
+                agent: current.name.clone(),
+                output,
+                handoffs,
+                history,
+            });
+        }
+    }
+
+    fn find(&self, name: &str) -> Result<&SwarmAgent<M>, SwarmError> {
+        self.agents
+            .iter()
+            .find(|agent| agent.name == name)
+            .ok_or_else(|| SwarmError::UnknownAgent(name.to_string()))
+    }
+
+    /// The definitions of the tools transferring the conversation from `from` to the other agents.
+    fn handoff_tools(&self, from: &str) -> Vec<ToolDefinition> {
+        self.agents
+            .iter()
+            .filter(|agent| agent.name != from)
+            .map(|agent| ToolDefinition {
+                name: format!("{HANDOFF_TOOL_PREFIX}{}", agent.name),
+                description: format!(
+                    "Transfer the conversation to the `{}` agent. {}",
+                    agent.name, agent.description
+                ),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "reason": {
+                            "type": "string",
+                            "description": "Why the conversation is transferred, and what the agent should know"
+                        }
+                    }
+                }),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        test_utils::{response, MockModel},
+    };
+
+    /// Transfers the conversation to the agent named in the prompt (`to:<name>`) if it has
+    /// a tool for it, otherwise answers with its preamble and context documents
+    fn mock() -> MockModel {
+        MockModel::new(|request| {
+            let prompt = request.prompt.rag_text().unwrap();
+            let target = prompt
+                .split_whitespace()
+                .filter_map(|word| word.strip_prefix("to:"))
+                .map(|name| format!("{HANDOFF_TOOL_PREFIX}{name}"))
+                .find(|tool| request.tools.iter().any(|def| &def.name == tool));
+
+            let choice = match target {
+                Some(tool) => {
+                    AssistantContent::tool_call("call_1", tool, json!({ "reason": "routing" }))
+                }
+                None => AssistantContent::text(
+                    std::iter::once(request.preamble.unwrap_or_default())
+                        .chain(request.documents.into_iter().map(|doc| doc.text))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ),
+            };
+            Ok(response(choice, ()))
+        })
+    }
+
+    fn swarm() -> Swarm<MockModel> {
+        let agent = |preamble: &str| AgentBuilder::new(mock()).preamble(preamble).build();
+
+        Swarm::new("triage", agent("triage"))
+            .agent("billing", "Billing", agent("billing"))
+            .agent("support", "Support", agent("support"))
+    }
+
+    #[tokio::test]
+    async fn test_handoff() {
+        let response = swarm().run("to:billing refund", vec![]).await.unwrap();
+
+        assert_eq!(response.agent, "billing");
+        assert_eq!(
+            response.output,
+            "billing\nThe conversation was transferred to you by the `triage` agent: routing"
+        );
+        assert_eq!(
+            response.handoffs,
+            vec![Handoff {
+                from: "triage".into(),
+                to: "billing".into(),
+                reason: Some("routing".into())
+            }]
+        );
+        assert_eq!(response.history.len(), 2);
+
+        let response = swarm().run("no handoff", vec![]).await.unwrap();
+        assert_eq!(response.agent, "triage");
+        assert!(response.handoffs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handoff_loop() {
+        let result = swarm()
+            .max_handoffs(3)
+            .run("to:billing to:support", vec![])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(SwarmError::HandoffLimit(3, handoffs)) if handoffs.len() == 3
+        ));
+    }
+}
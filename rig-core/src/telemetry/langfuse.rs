@@ -0,0 +1,197 @@
+//! Exporter for the [Langfuse](https://langfuse.com) ingestion API.
+//!
+//! Each trace is sent as a `trace-create` event and each generation as a `generation-create`
+//! event, see <https://api.reference.langfuse.com/#tag/ingestion>.
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{rfc3339, ExportError, Generation, TraceExporter};
+
+const LANGFUSE_CLOUD_URL: &str = "https://cloud.langfuse.com";
+
+#[derive(Clone)]
+pub struct LangfuseExporter {
+    base_url: String,
+    public_key: String,
+    secret_key: String,
+    http_client: reqwest::Client,
+}
+
+impl LangfuseExporter {
+    /// Create a new exporter for Langfuse Cloud with the given project keys.
+    pub fn new(public_key: &str, secret_key: &str) -> Self {
+        Self {
+            base_url: LANGFUSE_CLOUD_URL.to_string(),
+            public_key: public_key.to_string(),
+            secret_key: secret_key.to_string(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a new exporter from the `LANGFUSE_PUBLIC_KEY` and `LANGFUSE_SECRET_KEY`
+    /// environment variables, and the optional `LANGFUSE_HOST` environment variable.
+    /// Panics if the keys are not set.
+    pub fn from_env() -> Self {
+        let public_key = std::env::var("LANGFUSE_PUBLIC_KEY").expect("LANGFUSE_PUBLIC_KEY not set");
+        let secret_key = std::env::var("LANGFUSE_SECRET_KEY").expect("LANGFUSE_SECRET_KEY not set");
+        let exporter = Self::new(&public_key, &secret_key);
+
+        match std::env::var("LANGFUSE_HOST") {
+            Ok(host) => exporter.with_base_url(&host),
+            Err(_) => exporter,
+        }
+    }
+
+    /// Use a self-hosted Langfuse instance.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IngestionResponse {
+    #[serde(default)]
+    errors: Vec<Value>,
+}
+
+impl TraceExporter for LangfuseExporter {
+    async fn export(&self, generations: Vec<Generation>) -> Result<(), ExportError> {
+        let response = self
+            .http_client
+            .post(format!("{}/api/public/ingestion", self.base_url))
+            .basic_auth(&self.public_key, Some(&self.secret_key))
+            .json(&ingestion_batch(&generations))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ExportError::ProviderError(response.text().await?));
+        }
+
+        // Langfuse returns `207 Multi-Status` with the events that could not be ingested
+        let response: IngestionResponse = response.json().await?;
+        if response.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ExportError::ProviderError(
+                Value::Array(response.errors).to_string(),
+            ))
+        }
+    }
+}
+
+/// Build the body of an ingestion request for the given generations.
+fn ingestion_batch(generations: &[Generation]) -> Value {
+    let mut traces = HashSet::new();
+    let mut batch = vec![];
+
+    for generation in generations {
+        let timestamp = rfc3339(generation.start_time);
+
+        if traces.insert(&generation.trace_id) {
+            batch.push(json!({
+                "id": format!("{}-trace", generation.id),
+                "type": "trace-create",
+                "timestamp": timestamp,
+                "body": {
+                    "id": generation.trace_id,
+                    "name": generation.trace_name,
+                    "timestamp": timestamp,
+                },
+            }));
+        }
+
+        let mut body = json!({
+            "id": generation.id,
+            "traceId": generation.trace_id,
+            "name": "completion",
+            "startTime": timestamp,
+            "endTime": rfc3339(generation.end_time),
+            "model": generation.model,
+            "input": generation.input,
+            "output": generation.output,
+            "metadata": generation.metadata,
+        });
+        if let Some(usage) = generation.usage {
+            body["usage"] = json!({
+                "input": usage.input_tokens,
+                "output": usage.output_tokens,
+                "total": usage.total_tokens(),
+                "unit": "TOKENS",
+            });
+        }
+        if let Some(cost) = generation.cost {
+            body["costDetails"] = json!({ "total": cost });
+        }
+        if let Some(error) = &generation.error {
+            body["level"] = json!("ERROR");
+            body["statusMessage"] = json!(error);
+        }
+
+        batch.push(json!({
+            "id": format!("{}-generation", generation.id),
+            "type": "generation-create",
+            "timestamp": timestamp,
+            "body": body,
+        }));
+    }
+
+    json!({ "batch": batch })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::telemetry::TokenUsage;
+
+    #[test]
+    fn test_ingestion_batch() {
+        let generation = Generation {
+            id: "gen-1".to_string(),
+            trace_id: "trace-1".to_string(),
+            trace_name: "weather".to_string(),
+            model: Some("gpt-4o".to_string()),
+            start_time: 0,
+            end_time: 1_500,
+            input: json!([{"role": "user", "content": "Hi"}]),
+            output: Some(json!([{"text": "Hello!"}])),
+            tool_calls: vec![],
+            usage: Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 5,
+            }),
+            cost: Some(0.001),
+            error: None,
+            metadata: json!({}),
+        };
+        let second = Generation {
+            id: "gen-2".to_string(),
+            usage: None,
+            cost: None,
+            error: Some("Rate limited".to_string()),
+            ..generation.clone()
+        };
+
+        let batch = ingestion_batch(&[generation, second]);
+        let events = batch["batch"].as_array().unwrap();
+
+        // A single trace-create event for both generations
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["type"], "trace-create");
+        assert_eq!(events[0]["body"]["name"], "weather");
+        assert_eq!(events[1]["type"], "generation-create");
+        assert_eq!(events[1]["body"]["endTime"], "1970-01-01T00:00:01.500Z");
+        assert_eq!(
+            events[1]["body"]["usage"],
+            json!({"input": 10, "output": 5, "total": 15, "unit": "TOKENS"})
+        );
+        assert_eq!(events[1]["body"]["costDetails"]["total"], 0.001);
+        assert_eq!(events[2]["body"]["level"], "ERROR");
+        assert_eq!(events[2]["body"]["statusMessage"], "Rate limited");
+    }
+}
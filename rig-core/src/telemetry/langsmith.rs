@@ -0,0 +1,292 @@
+//! Exporter for the [LangSmith](https://smith.langchain.com) runs API.
+//!
+//! Each generation is sent as an `llm` run of the trace it belongs to, in a single
+//! `POST /runs/batch` request per exported batch. The trace itself is sent as a `chain` run,
+//! the first time one of its generations is exported.
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+
+use super::{new_uuid, rfc3339, utc_datetime, ExportError, Generation, TraceExporter};
+
+const LANGSMITH_API_URL: &str = "https://api.smith.langchain.com";
+
+/// Maximum number of traces whose root run is remembered by the exporter
+const MAX_ROOT_RUNS: usize = 10_000;
+
+/// The `dotted_order` of the root runs already sent, by trace ID.
+#[derive(Default)]
+struct RootRuns {
+    dotted_orders: HashMap<String, String>,
+    order: VecDeque<String>,
+}
+
+impl RootRuns {
+    fn insert(&mut self, trace_id: &str, dotted_order: String) {
+        if self.order.len() >= MAX_ROOT_RUNS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.dotted_orders.remove(&oldest);
+            }
+        }
+        self.order.push_back(trace_id.to_string());
+        self.dotted_orders
+            .insert(trace_id.to_string(), dotted_order);
+    }
+}
+
+#[derive(Clone)]
+pub struct LangSmithExporter {
+    base_url: String,
+    api_key: String,
+    project: String,
+    http_client: reqwest::Client,
+    roots: Arc<Mutex<RootRuns>>,
+}
+
+impl LangSmithExporter {
+    /// Create a new exporter that sends runs to the `default` project.
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            base_url: LANGSMITH_API_URL.to_string(),
+            api_key: api_key.to_string(),
+            project: "default".to_string(),
+            http_client: reqwest::Client::new(),
+            roots: Default::default(),
+        }
+    }
+
+    /// Create a new exporter from the `LANGSMITH_API_KEY` environment variable, and the optional
+    /// `LANGSMITH_PROJECT` and `LANGSMITH_ENDPOINT` environment variables.
+    /// Panics if the API key is not set.
+    pub fn from_env() -> Self {
+        let api_key = std::env::var("LANGSMITH_API_KEY").expect("LANGSMITH_API_KEY not set");
+        let mut exporter = Self::new(&api_key);
+
+        if let Ok(project) = std::env::var("LANGSMITH_PROJECT") {
+            exporter = exporter.with_project(&project);
+        }
+        if let Ok(endpoint) = std::env::var("LANGSMITH_ENDPOINT") {
+            exporter = exporter.with_base_url(&endpoint);
+        }
+        exporter
+    }
+
+    /// Send the runs to the given project.
+    pub fn with_project(mut self, project: &str) -> Self {
+        self.project = project.to_string();
+        self
+    }
+
+    /// Use a self-hosted LangSmith instance.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Build the body of a batch request for the given generations, along with the
+    /// `dotted_order` of the new root runs it contains (by trace ID).
+    fn runs_batch(&self, generations: &[Generation]) -> (Value, HashMap<String, String>) {
+        let roots = self.roots.lock().unwrap_or_else(|e| e.into_inner());
+        let mut new_roots = HashMap::new();
+        let mut runs = vec![];
+
+        // The root runs of the traces not sent yet. LangSmith requires their start time to be
+        // before the start time of their children, so it is the earliest one of the batch.
+        let mut new_traces: Vec<&Generation> = vec![];
+        for generation in generations {
+            if roots.dotted_orders.contains_key(&generation.trace_id) {
+                continue;
+            }
+            match new_traces
+                .iter_mut()
+                .find(|first| first.trace_id == generation.trace_id)
+            {
+                Some(first) if first.start_time > generation.start_time => *first = generation,
+                Some(_) => (),
+                None => new_traces.push(generation),
+            }
+        }
+        for first in new_traces {
+            let trace_order = dotted_order(first.start_time, &first.trace_id);
+            runs.push(json!({
+                "id": first.trace_id,
+                "trace_id": first.trace_id,
+                "dotted_order": trace_order,
+                "name": first.trace_name,
+                "run_type": "chain",
+                "start_time": rfc3339(first.start_time),
+                "inputs": {},
+                "session_name": self.project,
+            }));
+            new_roots.insert(first.trace_id.clone(), trace_order);
+        }
+
+        for generation in generations {
+            let trace_order = roots
+                .dotted_orders
+                .get(&generation.trace_id)
+                .or_else(|| new_roots.get(&generation.trace_id))
+                .cloned()
+                .unwrap_or_default();
+
+            let mut outputs = json!({ "choice": generation.output });
+            if let Some(usage) = generation.usage {
+                outputs["usage_metadata"] = json!({
+                    "input_tokens": usage.input_tokens,
+                    "output_tokens": usage.output_tokens,
+                    "total_tokens": usage.total_tokens(),
+                });
+            }
+
+            let mut metadata = generation.metadata.clone();
+            if let (Value::Object(metadata), Some(model)) = (&mut metadata, &generation.model) {
+                metadata.insert("ls_model_name".to_string(), json!(model));
+            }
+
+            // LangSmith requires run IDs to be UUIDs
+            let id = if generation.id.len() == 36 {
+                generation.id.clone()
+            } else {
+                new_uuid()
+            };
+
+            runs.push(json!({
+                "id": id,
+                "trace_id": generation.trace_id,
+                "parent_run_id": generation.trace_id,
+                "dotted_order": format!(
+                    "{trace_order}.{}",
+                    dotted_order(generation.start_time, &id)
+                ),
+                "name": "completion",
+                "run_type": "llm",
+                "start_time": rfc3339(generation.start_time),
+                "end_time": rfc3339(generation.end_time),
+                "inputs": { "messages": generation.input },
+                "outputs": outputs,
+                "error": generation.error,
+                "session_name": self.project,
+                "extra": {
+                    "metadata": metadata,
+                    "total_cost": generation.cost,
+                },
+            }));
+        }
+
+        (json!({ "post": runs }), new_roots)
+    }
+}
+
+/// Element of the `dotted_order` of a run: its start time (with microseconds) followed by its ID.
+fn dotted_order(start_time: u64, id: &str) -> String {
+    let (year, month, day, hours, minutes, seconds, millis) = utc_datetime(start_time);
+    format!("{year:04}{month:02}{day:02}T{hours:02}{minutes:02}{seconds:02}{millis:03}000Z{id}")
+}
+
+impl TraceExporter for LangSmithExporter {
+    async fn export(&self, generations: Vec<Generation>) -> Result<(), ExportError> {
+        let (body, new_roots) = self.runs_batch(&generations);
+        let response = self
+            .http_client
+            .post(format!("{}/runs/batch", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .json(&body)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let mut roots = self.roots.lock().unwrap_or_else(|e| e.into_inner());
+            for (trace_id, trace_order) in new_roots {
+                roots.insert(&trace_id, trace_order);
+            }
+            Ok(())
+        } else {
+            Err(ExportError::ProviderError(response.text().await?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::telemetry::TokenUsage;
+
+    #[test]
+    fn test_runs_batch() {
+        let trace_id = "00000000-0000-4000-8000-000000000001".to_string();
+        let generation = Generation {
+            id: "00000000-0000-4000-8000-000000000002".to_string(),
+            trace_id: trace_id.clone(),
+            trace_name: "weather".to_string(),
+            model: Some("gpt-4o".to_string()),
+            start_time: 1_000,
+            end_time: 2_000,
+            input: json!([{"role": "user", "content": "Hi"}]),
+            output: Some(json!([{"text": "Hello!"}])),
+            tool_calls: vec![],
+            usage: Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 5,
+            }),
+            cost: None,
+            error: None,
+            metadata: json!({"temperature": 0.5}),
+        };
+
+        let exporter = LangSmithExporter::new("key").with_project("my-project");
+        let (batch, new_roots) = exporter.runs_batch(std::slice::from_ref(&generation));
+        let runs = batch["post"].as_array().unwrap();
+
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0]["run_type"], "chain");
+        assert_eq!(runs[0]["id"], trace_id);
+        assert_eq!(
+            runs[0]["dotted_order"],
+            format!("19700101T000001000000Z{trace_id}")
+        );
+
+        assert_eq!(runs[1]["run_type"], "llm");
+        assert_eq!(runs[1]["parent_run_id"], trace_id);
+        assert_eq!(runs[1]["session_name"], "my-project");
+        assert_eq!(runs[1]["outputs"]["usage_metadata"]["total_tokens"], 15);
+        assert_eq!(
+            runs[1]["extra"]["metadata"],
+            json!({"temperature": 0.5, "ls_model_name": "gpt-4o"})
+        );
+        assert!(runs[1]["dotted_order"]
+            .as_str()
+            .unwrap()
+            .starts_with(&format!("19700101T000001000000Z{trace_id}.")));
+
+        // Once the root run has been exported, it is reused by the following batches
+        for (trace_id, trace_order) in new_roots {
+            exporter
+                .roots
+                .lock()
+                .unwrap()
+                .insert(&trace_id, trace_order);
+        }
+        let later = Generation {
+            id: "00000000-0000-4000-8000-000000000003".to_string(),
+            start_time: 5_000,
+            end_time: 6_000,
+            ..generation
+        };
+        let (batch, new_roots) = exporter.runs_batch(&[later]);
+        let runs = batch["post"].as_array().unwrap();
+
+        assert!(new_roots.is_empty());
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0]["dotted_order"]
+            .as_str()
+            .unwrap()
+            .starts_with(&format!(
+                "19700101T000001000000Z{trace_id}.19700101T000005000000Z"
+            )));
+    }
+}
@@ -0,0 +1,524 @@
+//! Metrics of the calls made to model providers (requests, errors, tokens and latency per
+//! provider and model), with an optional [Prometheus](https://prometheus.io) exposition endpoint.
+//!
+//! Calls are recorded by wrapping completion and embedding models with [Metered]. By default,
+//! they are recorded in the process-wide [Metrics::global] registry.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     providers::openai,
+//!     telemetry::metrics::{Metered, Metrics},
+//! };
+//!
+//! # fn run() -> std::io::Result<()> {
+//! let openai = openai::Client::from_env();
+//! let model = Metered::new(
+//!     openai.completion_model(openai::GPT_4O),
+//!     "openai",
+//!     openai::GPT_4O,
+//! );
+//!
+//! // Expose the metrics on http://127.0.0.1:9100/metrics
+//! Metrics::global().serve("127.0.0.1:9100")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    http::RequestOptions,
+};
+
+use super::{TokenUsage, UsageFn};
+
+/// Read and write timeout of the connections to the metrics endpoint.
+const SERVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bounds (in seconds) of the buckets of the latency histograms.
+pub const DEFAULT_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// The kind of call recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CallKind {
+    Completion,
+    Embedding,
+}
+
+impl CallKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CallKind::Completion => "completion",
+            CallKind::Embedding => "embedding",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Labels {
+    provider: String,
+    model: String,
+    kind: CallKind,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Series {
+    requests: u64,
+    errors: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    /// Number of calls per latency bucket (not cumulative)
+    buckets: Vec<u64>,
+    latency_sum: f64,
+}
+
+/// A registry of counters and histograms describing the calls made to model providers.
+pub struct Metrics {
+    buckets: Vec<f64>,
+    series: Mutex<BTreeMap<Labels, Series>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Create a new registry with the [DEFAULT_BUCKETS].
+    pub fn new() -> Self {
+        Self::with_buckets(DEFAULT_BUCKETS.to_vec())
+    }
+
+    /// Create a new registry whose latency histograms use the given bucket upper bounds,
+    /// in seconds.
+    pub fn with_buckets(mut buckets: Vec<f64>) -> Self {
+        buckets.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            buckets,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// The process-wide registry.
+    pub fn global() -> Arc<Metrics> {
+        static GLOBAL: OnceLock<Arc<Metrics>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Metrics::new())).clone()
+    }
+
+    /// Record a call made to a model.
+    pub fn record(
+        &self,
+        provider: &str,
+        model: &str,
+        kind: CallKind,
+        latency: Duration,
+        usage: Option<TokenUsage>,
+        is_error: bool,
+    ) {
+        let labels = Labels {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            kind,
+        };
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let series = series.entry(labels).or_insert_with(|| Series {
+            buckets: vec![0; self.buckets.len()],
+            ..Default::default()
+        });
+
+        series.requests += 1;
+        if is_error {
+            series.errors += 1;
+        }
+        if let Some(usage) = usage {
+            series.input_tokens += usage.input_tokens;
+            series.output_tokens += usage.output_tokens;
+        }
+
+        let latency = latency.as_secs_f64();
+        series.latency_sum += latency;
+        if let Some(bucket) = self.buckets.iter().position(|bound| latency <= *bound) {
+            series.buckets[bucket] += 1;
+        }
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let mut output = String::new();
+
+        let mut counter = |name: &str, help: &str, value: fn(&Series) -> u64| {
+            output.push_str(&format!("# HELP {name} {help}\n# TYPE {name} counter\n"));
+            for (labels, series) in series.iter() {
+                output.push_str(&format!(
+                    "{name}{{{}}} {}\n",
+                    labels.render(),
+                    value(series)
+                ));
+            }
+        };
+        counter(
+            "rig_requests_total",
+            "Total number of calls made to model providers.",
+            |series| series.requests,
+        );
+        counter(
+            "rig_errors_total",
+            "Total number of calls to model providers that returned an error.",
+            |series| series.errors,
+        );
+
+        output.push_str(
+            "# HELP rig_tokens_total Total number of tokens consumed by calls to model providers.\n\
+             # TYPE rig_tokens_total counter\n",
+        );
+        for (labels, series) in series.iter() {
+            for (direction, tokens) in [
+                ("input", series.input_tokens),
+                ("output", series.output_tokens),
+            ] {
+                output.push_str(&format!(
+                    "rig_tokens_total{{{},direction=\"{direction}\"}} {tokens}\n",
+                    labels.render()
+                ));
+            }
+        }
+
+        output.push_str(
+            "# HELP rig_request_duration_seconds Latency of the calls made to model providers.\n\
+             # TYPE rig_request_duration_seconds histogram\n",
+        );
+        for (labels, series) in series.iter() {
+            let labels = labels.render();
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&series.buckets) {
+                cumulative += count;
+                output.push_str(&format!(
+                    "rig_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}\n"
+                ));
+            }
+            output.push_str(&format!(
+                "rig_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}\n\
+                 rig_request_duration_seconds_sum{{{labels}}} {}\n\
+                 rig_request_duration_seconds_count{{{labels}}} {}\n",
+                series.requests, series.latency_sum, series.requests
+            ));
+        }
+
+        output
+    }
+
+    /// Serve the metrics over HTTP on the given address, from a background thread.
+    /// Every request is answered with the rendered metrics, regardless of its path.
+    /// Each connection is served from its own thread, with read and write timeouts.
+    /// Returns the address the server is bound to.
+    pub fn serve(self: &Arc<Self>, addr: impl ToSocketAddrs) -> std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let metrics = self.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept metrics connection: {}", e);
+                        continue;
+                    }
+                };
+                let metrics = metrics.clone();
+
+                std::thread::spawn(move || {
+                    if let Err(e) = metrics.serve_connection(stream) {
+                        tracing::warn!("Failed to serve metrics: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(local_addr)
+    }
+
+    fn serve_connection(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(SERVE_TIMEOUT))?;
+        stream.set_write_timeout(Some(SERVE_TIMEOUT))?;
+
+        // The content of the request is ignored
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request)?;
+
+        let body = self.render();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+}
+
+impl Labels {
+    fn render(&self) -> String {
+        format!(
+            "provider=\"{}\",model=\"{}\",kind=\"{}\"",
+            escape_label(&self.provider),
+            escape_label(&self.model),
+            self.kind.as_str()
+        )
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Extracts the token usage from the raw responses of a [Metered] completion model: either
+/// nothing (`()`, the default) or a [UsageFn] set with [Metered::with_usage].
+pub trait UsageExtractor<R>: Clone + Send + Sync {
+    fn usage(&self, response: &R) -> Option<TokenUsage>;
+}
+
+impl<R> UsageExtractor<R> for () {
+    fn usage(&self, _response: &R) -> Option<TokenUsage> {
+        None
+    }
+}
+
+impl<R> UsageExtractor<R> for UsageFn<R> {
+    fn usage(&self, response: &R) -> Option<TokenUsage> {
+        self(response)
+    }
+}
+
+/// Wraps a completion or embedding model and records metrics for all its calls.
+#[derive(Clone)]
+pub struct Metered<M, U = ()> {
+    pub model: M,
+    provider: String,
+    model_name: String,
+    metrics: Arc<Metrics>,
+    usage: U,
+}
+
+impl<M> Metered<M> {
+    /// Wrap the model, recording its calls in the [Metrics::global] registry under the
+    /// given provider and model names.
+    pub fn new(model: M, provider: &str, model_name: &str) -> Self {
+        Self {
+            model,
+            provider: provider.to_string(),
+            model_name: model_name.to_string(),
+            metrics: Metrics::global(),
+            usage: (),
+        }
+    }
+}
+
+impl<M, U> Metered<M, U> {
+    /// Record the calls in the given registry instead of the global one.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    fn record(&self, kind: CallKind, started: Instant, usage: Option<TokenUsage>, is_error: bool) {
+        self.metrics.record(
+            &self.provider,
+            &self.model_name,
+            kind,
+            started.elapsed(),
+            usage,
+            is_error,
+        );
+    }
+}
+
+impl<M: CompletionModel> Metered<M> {
+    /// Set the function used to extract the token usage from the raw responses of the model.
+    pub fn with_usage(self, usage: UsageFn<M::Response>) -> Metered<M, UsageFn<M::Response>> {
+        Metered {
+            model: self.model,
+            provider: self.provider,
+            model_name: self.model_name,
+            metrics: self.metrics,
+            usage,
+        }
+    }
+}
+
+impl<M: CompletionModel, U: UsageExtractor<M::Response>> CompletionModel for Metered<M, U> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let started = Instant::now();
+        let result = self.model.completion(request).await;
+
+        let usage = result
+            .as_ref()
+            .ok()
+            .and_then(|response| self.usage.usage(&response.raw_response));
+        self.record(CallKind::Completion, started, usage, result.is_err());
+
+        result
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+impl<M: EmbeddingModel, U: Clone + Send + Sync> EmbeddingModel for Metered<M, U> {
+    const MAX_DOCUMENTS: usize = M::MAX_DOCUMENTS;
+
+    fn ndims(&self) -> usize {
+        self.model.ndims()
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        self.embed_texts_with_options(texts, RequestOptions::default())
+            .await
+    }
+
+    async fn embed_texts_with_options(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+        request_options: RequestOptions,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        let started = Instant::now();
+        let result = self
+            .model
+            .embed_texts_with_options(texts, request_options)
+            .await;
+        self.record(CallKind::Embedding, started, None, result.is_err());
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{
+        completion::CompletionRequestBuilder,
+        message::AssistantContent,
+        test_utils::{response, MockModel},
+    };
+
+    /// Answers "Hi!", rejecting the requests with a temperature
+    fn mock() -> MockModel<u64> {
+        MockModel::new(|request| match request.temperature {
+            Some(_) => Err(CompletionError::ProviderError("Bad request".to_string())),
+            None => Ok(response(AssistantContent::text("Hi!"), 12)),
+        })
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::with_buckets(vec![1.0, 0.5]);
+        metrics.record(
+            "openai",
+            "gpt-4o",
+            CallKind::Completion,
+            Duration::from_millis(250),
+            Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 20,
+            }),
+            false,
+        );
+        metrics.record(
+            "openai",
+            "gpt-4o",
+            CallKind::Completion,
+            Duration::from_millis(2_000),
+            None,
+            true,
+        );
+
+        let rendered = metrics.render();
+        let labels = r#"provider="openai",model="gpt-4o",kind="completion""#;
+
+        for line in [
+            format!("rig_requests_total{{{labels}}} 2"),
+            format!("rig_errors_total{{{labels}}} 1"),
+            format!("rig_tokens_total{{{labels},direction=\"input\"}} 10"),
+            format!("rig_tokens_total{{{labels},direction=\"output\"}} 20"),
+            format!("rig_request_duration_seconds_bucket{{{labels},le=\"0.5\"}} 1"),
+            format!("rig_request_duration_seconds_bucket{{{labels},le=\"1\"}} 1"),
+            format!("rig_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 2"),
+            format!("rig_request_duration_seconds_sum{{{labels}}} 2.25"),
+            format!("rig_request_duration_seconds_count{{{labels}}} 2"),
+        ] {
+            assert!(rendered.lines().any(|l| l == line), "missing line: {line}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metered_model_and_endpoint() {
+        let metrics = Arc::new(Metrics::new());
+        let model = Metered::new(mock(), "mock", "mock-1")
+            .with_metrics(metrics.clone())
+            .with_usage(|tokens| {
+                Some(TokenUsage {
+                    input_tokens: *tokens,
+                    output_tokens: 1,
+                })
+            });
+
+        CompletionRequestBuilder::new(model.clone(), "Hello")
+            .send()
+            .await
+            .unwrap();
+        CompletionRequestBuilder::new(model, "Hello")
+            .temperature(0.5)
+            .send()
+            .await
+            .unwrap_err();
+
+        let addr = metrics.serve("127.0.0.1:0").unwrap();
+        // An idle connection doesn't block the other ones
+        let _idle = TcpStream::connect(addr).unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let labels = r#"provider="mock",model="mock-1",kind="completion""#;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains(&format!("rig_requests_total{{{labels}}} 2\n")));
+        assert!(response.contains(&format!("rig_errors_total{{{labels}}} 1\n")));
+        assert!(response.contains(&format!(
+            "rig_tokens_total{{{labels},direction=\"input\"}} 12\n"
+        )));
+    }
+}
@@ -0,0 +1,609 @@
+//! This module provides tracing of completion calls, and exporters that ship the recorded traces
+//! to LLM observability platforms ([Langfuse](https://langfuse.com) and
+//! [LangSmith](https://smith.langchain.com)).
+//!
+//! A [Tracer] buffers the [Generation]s recorded by [Traced] models until they are sent in
+//! batches to a [TraceExporter] by [Tracer::flush]. Each generation records the input and output
+//! of a completion call (including the tool calls requested by the model), its latency and, if
+//! configured, its token usage and cost. All the generations recorded while running a future in
+//! a [Trace] scope belong to that trace (e.g.: a single agent run), so concurrent runs are traced
+//! separately. Calls made outside of a trace scope each get their own trace.
+//!
+//! Custom exporters can be plugged in by implementing the [TraceExporter] trait.
+//!
+//! Aggregated metrics (e.g.: for Prometheus) are provided by the [metrics] module.
+//!
+//! # Example
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use rig::{
+//!     completion::Prompt,
+//!     providers::openai,
+//!     telemetry::{LangfuseExporter, Trace, Traced, Tracer},
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let tracer = Arc::new(Tracer::new(LangfuseExporter::from_env()));
+//!
+//! let openai = openai::Client::from_env();
+//! let model = Traced::new(openai.completion_model(openai::GPT_4O), tracer.clone())
+//!     .with_name(openai::GPT_4O)
+//!     .with_usage(|response| {
+//!         response.usage.as_ref().map(|usage| rig::telemetry::TokenUsage {
+//!             input_tokens: usage.prompt_tokens as u64,
+//!             output_tokens: (usage.total_tokens - usage.prompt_tokens) as u64,
+//!         })
+//!     })
+//!     .with_pricing(2.5, 10.0);
+//!
+//! let agent = rig::agent::AgentBuilder::new(model).build();
+//!
+//! let trace = Trace::new("weather question");
+//! let answer = trace
+//!     .scope(agent.prompt("What's the weather like in Paris?"))
+//!     .await?;
+//!
+//! // Send the buffered generations (e.g.: periodically from a background task, and on shutdown)
+//! tracer.flush().await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    cell::RefCell,
+    future::Future,
+    hash::BuildHasher,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    message::{AssistantContent, ToolCall},
+    models, test_mode,
+};
+
+pub mod langfuse;
+pub mod langsmith;
+pub mod metrics;
+
+pub use langfuse::LangfuseExporter;
+pub use langsmith::LangSmithExporter;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    /// Http error (e.g.: connection error, timeout, etc.)
+    #[error("HttpError: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    /// Error returned by the observability platform
+    #[error("ProviderError: {0}")]
+    ProviderError(String),
+}
+
+/// Number of tokens consumed by a completion call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// A single completion call, as recorded by a [Traced] model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Generation {
+    pub id: String,
+    pub trace_id: String,
+    pub trace_name: String,
+    /// Name of the model, if known
+    pub model: Option<String>,
+    /// Start of the call, in milliseconds since the UNIX epoch
+    pub start_time: u64,
+    /// End of the call, in milliseconds since the UNIX epoch
+    pub end_time: u64,
+    /// The messages sent to the model (preamble, context documents, chat history and prompt)
+    pub input: Value,
+    /// The content returned by the model, if the call succeeded
+    pub output: Option<Value>,
+    /// The tool calls requested by the model
+    pub tool_calls: Vec<ToolCall>,
+    pub usage: Option<TokenUsage>,
+    /// Cost of the call, in USD
+    pub cost: Option<f64>,
+    pub error: Option<String>,
+    /// Request parameters (e.g.: temperature, max tokens, available tools)
+    pub metadata: Value,
+}
+
+impl Generation {
+    pub fn latency_ms(&self) -> u64 {
+        self.end_time.saturating_sub(self.start_time)
+    }
+}
+
+/// Trait for the backends to which recorded generations are sent.
+pub trait TraceExporter: Send + Sync {
+    fn export(
+        &self,
+        generations: Vec<Generation>,
+    ) -> impl std::future::Future<Output = Result<(), ExportError>> + Send;
+}
+
+/// A trace groups the generations recorded while running a future in its [scope](Trace::scope).
+#[derive(Clone, Debug)]
+pub struct Trace {
+    id: Arc<str>,
+    name: Arc<str>,
+}
+
+thread_local! {
+    static CURRENT_TRACE: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+impl Trace {
+    /// Create a new trace with the given name and a random ID.
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: new_uuid().into(),
+            name: name.into(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run the future in this trace: the generations recorded by [Traced] models while polling
+    /// it belong to this trace. Futures spawned on other tasks do not inherit the trace and
+    /// should be wrapped in the scope of the trace themselves.
+    pub fn scope<F: Future>(&self, future: F) -> TraceScope<F> {
+        TraceScope {
+            trace: self.clone(),
+            future: Box::pin(future),
+        }
+    }
+
+    /// The trace of the future currently being polled, if any.
+    pub fn current() -> Option<Trace> {
+        CURRENT_TRACE.with(|current| current.borrow().clone())
+    }
+}
+
+/// Future returned by [Trace::scope].
+pub struct TraceScope<F> {
+    trace: Trace,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for TraceScope<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Restores the enclosing trace, even if the inner future panics
+        struct Restore(Option<Trace>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_TRACE.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+
+        let this = &mut *self;
+        let _restore =
+            Restore(CURRENT_TRACE.with(|current| current.borrow_mut().replace(this.trace.clone())));
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// Buffers recorded generations and sends them to an exporter in batches.
+pub struct Tracer<E: TraceExporter> {
+    exporter: E,
+    batch_size: usize,
+    buffer: Mutex<Vec<Generation>>,
+}
+
+impl<E: TraceExporter> Tracer<E> {
+    /// Create a new tracer that exports generations in batches of 10.
+    pub fn new(exporter: E) -> Self {
+        Self {
+            exporter,
+            batch_size: 10,
+            buffer: Mutex::new(vec![]),
+        }
+    }
+
+    /// Set the maximum number of generations sent to the exporter at once.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Add a generation to the buffer. Recording never blocks on the exporter: buffered
+    /// generations are only sent by [Tracer::flush].
+    pub fn record(&self, generation: Generation) {
+        self.buffer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(generation);
+    }
+
+    /// Number of generations waiting to be exported.
+    pub fn pending(&self) -> usize {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Export all the buffered generations, in batches. If a batch fails to be exported, it is
+    /// put back in the buffer (along with the following ones) and the error is returned.
+    pub async fn flush(&self) -> Result<(), ExportError> {
+        let mut generations =
+            std::mem::take(&mut *self.buffer.lock().unwrap_or_else(|e| e.into_inner()));
+
+        while !generations.is_empty() {
+            let rest = generations.split_off(self.batch_size.min(generations.len()));
+
+            if let Err(e) = self.exporter.export(generations.clone()).await {
+                let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+                generations.extend(rest);
+                generations.append(&mut buffer);
+                *buffer = generations;
+                return Err(e);
+            }
+            generations = rest;
+        }
+
+        Ok(())
+    }
+}
+
+/// Function extracting the token usage from the raw response of a model.
+pub type UsageFn<R> = fn(&R) -> Option<TokenUsage>;
+
+/// Wraps a completion model and records all its calls with a [Tracer].
+pub struct Traced<M: CompletionModel, E: TraceExporter> {
+    pub model: M,
+    tracer: Arc<Tracer<E>>,
+    name: Option<String>,
+    usage: Option<UsageFn<M::Response>>,
+    /// Price in USD per million input and output tokens
+    pricing: Option<(f64, f64)>,
+}
+
+impl<M: CompletionModel, E: TraceExporter> Clone for Traced<M, E> {
+    fn clone(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            tracer: self.tracer.clone(),
+            name: self.name.clone(),
+            usage: self.usage,
+            pricing: self.pricing,
+        }
+    }
+}
+
+impl<M: CompletionModel, E: TraceExporter> Traced<M, E> {
+    pub fn new(model: M, tracer: Arc<Tracer<E>>) -> Self {
+        Self {
+            model,
+            tracer,
+            name: None,
+            usage: None,
+            pricing: None,
+        }
+    }
+
+    /// Set the model name reported in the generations.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Set the function used to extract the token usage from the raw responses of the model.
+    pub fn with_usage(mut self, usage: UsageFn<M::Response>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Set the price of the model, in USD per million input and output tokens, used to compute
+    /// the cost of the generations. Requires [Traced::with_usage]. By default, the price of the
+    /// named model in the [catalog](crate::models) is used, if any.
+    pub fn with_pricing(mut self, input_per_million: f64, output_per_million: f64) -> Self {
+        self.pricing = Some((input_per_million, output_per_million));
+        self
+    }
+}
+
+impl<M: CompletionModel, E: TraceExporter> CompletionModel for Traced<M, E> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        let trace = Trace::current().unwrap_or_else(|| Trace::new("rig"));
+        let input = generation_input(&request);
+        let metadata = json!({
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+            "tools": request.tools.iter().map(|tool| &tool.name).collect::<Vec<_>>(),
+            "additional_params": request.additional_params,
+        });
+        let start_time = now_ms();
+
+        let result = self.model.completion(request).await;
+
+        let mut generation = Generation {
+            id: new_uuid(),
+            trace_id: trace.id.to_string(),
+            trace_name: trace.name.to_string(),
+            model: self.name.clone(),
+            start_time,
+            end_time: now_ms(),
+            input,
+            output: None,
+            tool_calls: vec![],
+            usage: None,
+            cost: None,
+            error: None,
+            metadata,
+        };
+
+        match &result {
+            Ok(response) => {
+                generation.output = Some(json!(response.choice));
+                generation.tool_calls = response
+                    .choice
+                    .iter()
+                    .filter_map(|content| match content {
+                        AssistantContent::ToolCall(tool_call) => Some(tool_call.clone()),
+                        AssistantContent::Text(_) => None,
+                    })
+                    .collect();
+                generation.usage = self.usage.and_then(|usage| usage(&response.raw_response));
+                let pricing = self.pricing.or_else(|| {
+                    let info = models::get(self.name.as_deref()?)?;
+                    info.input_per_million.zip(info.output_per_million)
+                });
+                generation.cost =
+                    generation
+                        .usage
+                        .zip(pricing)
+                        .map(|(usage, (input_price, output_price))| {
+                            (usage.input_tokens as f64 * input_price
+                                + usage.output_tokens as f64 * output_price)
+                                / 1_000_000.0
+                        });
+            }
+            Err(e) => generation.error = Some(e.to_string()),
+        }
+
+        self.tracer.record(generation);
+
+        result
+    }
+
+    fn request_body(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        self.model.request_body(request)
+    }
+}
+
+/// The input of a completion request, as a list of chat messages.
+fn generation_input(request: &CompletionRequest) -> Value {
+    let mut messages = vec![];
+
+    if let Some(preamble) = &request.preamble {
+        messages.push(json!({"role": "system", "content": preamble}));
+    }
+    if !request.documents.is_empty() {
+        messages.push(json!({"role": "documents", "content": request.documents}));
+    }
+    messages.extend(request.chat_history.iter().map(|message| json!(message)));
+    messages.push(json!(request.prompt));
+
+    Value::Array(messages)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Generate a random (version 4) UUID. In [test mode](crate::test_mode), UUIDs are derived from
+/// a counter instead.
+pub(crate) fn new_uuid() -> String {
+    let count = test_mode::next_id_count();
+
+    let (hi, lo) = if test_mode::is_enabled() {
+        (0, count)
+    } else {
+        let state = std::collections::hash_map::RandomState::new();
+        (state.hash_one(count), state.hash_one(now_ms()))
+    };
+    let hi = (hi & 0xffff_ffff_ffff_0fff) | 0x4000;
+    let lo = (lo & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        hi >> 32,
+        (hi >> 16) & 0xffff,
+        hi & 0xffff,
+        lo >> 48,
+        lo & 0xffff_ffff_ffff
+    )
+}
+
+/// Split a timestamp (in milliseconds since the UNIX epoch) into UTC
+/// `(year, month, day, hours, minutes, seconds, milliseconds)`.
+pub(crate) fn utc_datetime(ms: u64) -> (i64, u64, u64, u64, u64, u64, u64) {
+    let secs = ms / 1000;
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil date from the number of days since 1970-01-01 (H. Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        ms % 1000,
+    )
+}
+
+/// Format a timestamp (in milliseconds since the UNIX epoch) as an RFC 3339 UTC date-time
+/// (e.g.: `2024-01-31T12:00:00.000Z`).
+pub(crate) fn rfc3339(ms: u64) -> String {
+    let (year, month, day, hours, minutes, seconds, millis) = utc_datetime(ms);
+    format!("{year:04}-{month:02}-{day:02}T{hours:02}:{minutes:02}:{seconds:02}.{millis:03}Z")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        completion::{CompletionRequestBuilder, ToolDefinition},
+        message::AssistantContent,
+        test_utils::MockModel,
+        OneOrMany,
+    };
+
+    /// Answers with a text and a tool call
+    fn mock() -> MockModel<u64> {
+        MockModel::new(|_| {
+            Ok(CompletionResponse::new(
+                OneOrMany::many(vec![
+                    AssistantContent::text("Let me check."),
+                    AssistantContent::tool_call("call_0", "get_weather", json!({"city": "Paris"})),
+                ])
+                .unwrap(),
+                1_000,
+            ))
+        })
+    }
+
+    #[derive(Default)]
+    struct MockExporter(Mutex<Vec<Vec<Generation>>>);
+
+    impl TraceExporter for Arc<MockExporter> {
+        async fn export(&self, generations: Vec<Generation>) -> Result<(), ExportError> {
+            self.0.lock().unwrap().push(generations);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_traced_model() {
+        let exporter = Arc::new(MockExporter::default());
+        let tracer = Arc::new(Tracer::new(exporter.clone()).with_batch_size(2));
+        let model = Traced::new(mock(), tracer.clone())
+            .with_name("mock")
+            .with_usage(|tokens| {
+                Some(TokenUsage {
+                    input_tokens: *tokens,
+                    output_tokens: *tokens,
+                })
+            })
+            .with_pricing(1.0, 3.0);
+
+        let request = || {
+            CompletionRequestBuilder::new(model.clone(), "What's the weather in Paris?")
+                .preamble("You are a weather bot.".to_string())
+                .tool(ToolDefinition {
+                    name: "get_weather".to_string(),
+                    description: "Get the weather".to_string(),
+                    parameters: json!({}),
+                })
+                .send()
+        };
+
+        // Two concurrent runs, each in its own trace
+        let (weather, other) = (Trace::new("weather"), Trace::new("other"));
+        futures::join!(
+            weather.scope(async {
+                request().await.unwrap();
+                request().await.unwrap();
+            }),
+            other.scope(async {
+                request().await.unwrap();
+            }),
+        );
+        assert!(Trace::current().is_none());
+        request().await.unwrap();
+
+        // Nothing is exported until the tracer is flushed
+        assert_eq!(tracer.pending(), 4);
+        assert!(exporter.0.lock().unwrap().is_empty());
+        tracer.flush().await.unwrap();
+        assert_eq!(tracer.pending(), 0);
+
+        let batches = exporter.0.lock().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].len(), 2);
+
+        let generations = batches.concat();
+        let trace_ids = |name: &str| {
+            generations
+                .iter()
+                .filter(|generation| generation.trace_name == name)
+                .map(|generation| generation.trace_id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(trace_ids("weather"), vec![weather.id().to_string(); 2]);
+        assert_eq!(trace_ids("other"), vec![other.id().to_string()]);
+        assert_eq!(trace_ids("rig").len(), 1);
+        assert_ne!(trace_ids("rig")[0], weather.id());
+
+        let generation = &batches[0][0];
+        assert_eq!(generation.model, Some("mock".to_string()));
+        assert_eq!(generation.input[0]["role"], "system");
+        assert_eq!(generation.tool_calls.len(), 1);
+        assert_eq!(generation.tool_calls[0].function.name, "get_weather");
+        assert_eq!(generation.usage.unwrap().total_tokens(), 2_000);
+        assert_eq!(generation.cost, Some(0.004));
+        assert_eq!(generation.metadata["tools"], json!(["get_weather"]));
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(rfc3339(1_709_210_096_789), "2024-02-29T12:34:56.789Z");
+    }
+
+    #[test]
+    fn test_new_uuid() {
+        let uuid = new_uuid();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert_ne!(uuid, new_uuid());
+    }
+}
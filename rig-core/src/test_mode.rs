@@ -0,0 +1,288 @@
+//! Deterministic test mode, used to make snapshot tests of agents and pipelines reproducible.
+//!
+//! The [Deterministic] completion model wrapper makes every request sent to the wrapped model
+//! deterministic:
+//! - its temperature is forced to `0.0`,
+//! - its `seed` additional parameter is forced to the seed of the wrapper,
+//! - its tool definitions are sorted by name and the keys of its JSON values
+//!   (additional parameters and tool parameters) are sorted alphabetically.
+//!
+//! When test mode is enabled (with [enable] or by setting the `RIG_TEST_MODE` environment
+//! variable), IDs generated with [generate_id] also become stable: they are derived from a
+//! counter that starts at 0 (see [reset_ids]) instead of the current time.
+//!
+//! Note: some providers do not support the `seed` parameter and may reject requests made by a
+//! [Deterministic] model. It is meant to wrap mock or recorded models.
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::openai, test_mode::{self, Deterministic}};
+//!
+//! let openai = openai::Client::from_env();
+//! let model = Deterministic::new(openai.completion_model(openai::GPT_4O)).seed(1234);
+//!
+//! test_mode::enable();
+//! assert!(test_mode::is_enabled());
+//!
+//! // Snapshot a request with sorted JSON keys
+//! let snapshot = test_mode::canonical_json(&serde_json::json!({"b": 1, "a": 2})).unwrap();
+//! assert_eq!(snapshot, "{\n  \"a\": 2,\n  \"b\": 1\n}");
+//!
+//! test_mode::disable();
+//! ```
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    OnceLock,
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    json_utils,
+    streaming::{StreamingCompletionModel, StreamingResult},
+};
+
+/// Seed of a [Deterministic] model created without an explicit seed.
+pub const DEFAULT_SEED: u64 = 42;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the `RIG_TEST_MODE` environment variable is set (read once).
+fn env_enabled() -> bool {
+    static ENV_ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENV_ENABLED.get_or_init(|| {
+        std::env::var("RIG_TEST_MODE")
+            .map(|value| !matches!(value.as_str(), "" | "0" | "false"))
+            .unwrap_or(false)
+    })
+}
+
+/// Enable test mode, making generated IDs stable.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Disable test mode. Has no effect if test mode is enabled through the `RIG_TEST_MODE`
+/// environment variable.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+/// Whether test mode is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst) || env_enabled()
+}
+
+/// A completion model wrapper making the requests sent to the model deterministic.
+///
+/// See the [module documentation](self) for more information.
+#[derive(Clone)]
+pub struct Deterministic<M> {
+    pub model: M,
+    seed: u64,
+}
+
+impl<M: CompletionModel> Deterministic<M> {
+    /// Wrap a model, forcing the [DEFAULT_SEED] on its requests.
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Set the seed forced on the requests.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl<M: CompletionModel> CompletionModel for Deterministic<M> {
+    type Response = M::Response;
+
+    async fn completion(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<CompletionResponse<Self::Response>, CompletionError> {
+        make_deterministic(&mut request, self.seed);
+        self.model.completion(request).await
+    }
+
+    fn request_body(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError> {
+        make_deterministic(&mut request, self.seed);
+        self.model.request_body(request)
+    }
+}
+
+impl<M: StreamingCompletionModel + Sync> StreamingCompletionModel for Deterministic<M> {
+    async fn stream(
+        &self,
+        mut request: CompletionRequest,
+    ) -> Result<StreamingResult, CompletionError> {
+        make_deterministic(&mut request, self.seed);
+        self.model.stream(request).await
+    }
+}
+
+fn make_deterministic(request: &mut CompletionRequest, seed: u64) {
+    request.temperature = Some(0.0);
+
+    let params = json_utils::merge(
+        request
+            .additional_params
+            .take()
+            .unwrap_or_else(|| json!({})),
+        json!({ "seed": seed }),
+    );
+    request.additional_params = Some(canonicalize(params));
+
+    request.tools.sort_by(|a, b| a.name.cmp(&b.name));
+    for tool in request.tools.iter_mut() {
+        tool.parameters = canonicalize(tool.parameters.take());
+    }
+}
+
+/// Recursively sort the keys of all the objects contained in a JSON value.
+pub fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        value => value,
+    }
+}
+
+/// Serialize a value to pretty-printed JSON with sorted keys, suitable for snapshot tests.
+pub fn canonical_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&canonicalize(serde_json::to_value(value)?))
+}
+
+/// Generate an ID with the given prefix (e.g.: `run_0000000000000003`).
+/// In test mode, IDs are generated from a counter and are thus stable across runs. Otherwise,
+/// they are also derived from the current time.
+pub fn generate_id(prefix: &str) -> String {
+    let count = next_id_count();
+
+    if is_enabled() {
+        format!("{prefix}_{count:016x}")
+    } else {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        format!("{prefix}_{nanos:016x}{count:04x}")
+    }
+}
+
+/// Increment the counter used to generate IDs and return its previous value.
+pub(crate) fn next_id_count() -> u64 {
+    ID_COUNTER.fetch_add(1, Ordering::SeqCst)
+}
+
+/// Reset the counter used by [generate_id], so that the next generated IDs are the same as the
+/// ones generated after the previous reset.
+pub fn reset_ids() {
+    ID_COUNTER.store(0, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        agent::AgentBuilder,
+        completion::{Prompt, ToolDefinition},
+        message::Message,
+        test_utils::MockModel,
+    };
+
+    fn tool(name: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.to_string(),
+            description: format!("The {name} tool"),
+            parameters: json!({"type": "object", "properties": {"b": {}, "a": {}}}),
+        }
+    }
+
+    #[test]
+    fn test_make_deterministic() {
+        let mut request = CompletionRequest {
+            prompt: Message::user("hello"),
+            preamble: None,
+            chat_history: vec![],
+            documents: vec![],
+            tools: vec![tool("subtract"), tool("add")],
+            temperature: Some(0.9),
+            max_tokens: None,
+            n: None,
+            additional_params: Some(json!({"seed": 1, "top_p": 0.5})),
+            request_options: Default::default(),
+        };
+
+        make_deterministic(&mut request, 7);
+
+        assert_eq!(request.temperature, Some(0.0));
+        assert_eq!(
+            request.additional_params,
+            Some(json!({"seed": 7, "top_p": 0.5}))
+        );
+        assert_eq!(
+            request
+                .tools
+                .iter()
+                .map(|tool| tool.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["add", "subtract"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_model() {
+        // Answers with the temperature and the additional parameters of the request
+        let model = MockModel::reply(|request| {
+            format!(
+                "{:?} {}",
+                request.temperature,
+                request.additional_params.unwrap_or_default()
+            )
+        });
+
+        let agent = AgentBuilder::new(Deterministic::new(model.clone()).seed(7))
+            .temperature(0.9)
+            .build();
+        assert_eq!(
+            agent.prompt("hello").await.unwrap(),
+            r#"Some(0.0) {"seed":7}"#
+        );
+
+        // Requests are only changed by the wrapper
+        let agent = AgentBuilder::new(model).temperature(0.9).build();
+        assert_eq!(agent.prompt("hello").await.unwrap(), "Some(0.9) null");
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        let value = json!({"z": [{"b": 1, "a": 2}], "a": null});
+
+        assert_eq!(
+            canonical_json(&value).unwrap(),
+            serde_json::to_string_pretty(&json!({"a": null, "z": [{"a": 2, "b": 1}]})).unwrap()
+        );
+    }
+}
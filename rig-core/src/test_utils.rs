@@ -0,0 +1,337 @@
+//! Mock models and vector store index for testing code built on rig, without calling a provider.
+//! This module is only available with the `test-utils` feature.
+//!
+//! # Example
+//! ```rust
+//! use rig::{completion::Prompt, agent::AgentBuilder, test_utils::MockModel};
+//!
+//! # async fn run() -> Result<(), rig::completion::PromptError> {
+//! let agent = AgentBuilder::new(MockModel::text("Hello!")).build();
+//! assert_eq!(agent.prompt("Hi").await?, "Hello!");
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    completion::{CompletionError, CompletionModel, CompletionRequest, CompletionResponse},
+    embeddings::{Embedding, EmbeddingError, EmbeddingModel},
+    message::AssistantContent,
+    streaming::{StreamingChoice, StreamingCompletionModel, StreamingResult},
+    vector_store::{VectorStoreError, VectorStoreIndex},
+    OneOrMany,
+};
+
+type Respond<R> =
+    dyn Fn(CompletionRequest) -> Result<CompletionResponse<R>, CompletionError> + Send + Sync;
+
+type Stream = dyn Fn(CompletionRequest) -> StreamingResult + Send + Sync;
+
+/// A completion model answering the requests with a function, optionally after a delay, and
+/// counting the requests it receives (including the requests of its clones).
+///
+/// Unless a [stream](MockModel::stream) is set, the streaming requests are answered with the
+/// tool calls and the text of the response of the function, as separate chunks.
+pub struct MockModel<R = ()> {
+    respond: Arc<Respond<R>>,
+    stream: Option<Arc<Stream>>,
+    delay: Option<Duration>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl<R> Clone for MockModel<R> {
+    fn clone(&self) -> Self {
+        Self {
+            respond: self.respond.clone(),
+            stream: self.stream.clone(),
+            delay: self.delay,
+            calls: self.calls.clone(),
+        }
+    }
+}
+
+impl<R> MockModel<R> {
+    /// A model answering the requests with the function.
+    pub fn new(
+        respond: impl Fn(CompletionRequest) -> Result<CompletionResponse<R>, CompletionError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            respond: Arc::new(respond),
+            stream: None,
+            delay: None,
+            calls: Arc::default(),
+        }
+    }
+
+    /// Answer the streaming requests with the function.
+    pub fn stream(
+        mut self,
+        stream: impl Fn(CompletionRequest) -> StreamingResult + Send + Sync + 'static,
+    ) -> Self {
+        self.stream = Some(Arc::new(stream));
+        self
+    }
+
+    /// Wait for the delay before answering each request.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// The number of requests received by the model and its clones.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl MockModel {
+    /// A model answering the requests with the text computed by the function.
+    pub fn reply(reply: impl Fn(CompletionRequest) -> String + Send + Sync + 'static) -> Self {
+        Self::new(move |request| Ok(response(AssistantContent::text(reply(request)), ())))
+    }
+
+    /// A model always answering the text.
+    pub fn text(text: &str) -> Self {
+        let text = text.to_string();
+        Self::reply(move |_| text.clone())
+    }
+
+    /// A model always calling the tool with the arguments.
+    pub fn tool_call(name: &str, arguments: serde_json::Value) -> Self {
+        let name = name.to_string();
+        Self::new(move |_| {
+            Ok(response(
+                AssistantContent::tool_call("call_0", name.clone(), arguments.clone()),
+                (),
+            ))
+        })
+    }
+}
+
+impl<R: Send + Sync + 'static> CompletionModel for MockModel<R> {
+    type Response = R;
+
+    async fn completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<R>, CompletionError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        (self.respond)(request)
+    }
+}
+
+impl<R: Send + Sync + 'static> StreamingCompletionModel for MockModel<R> {
+    async fn stream(&self, request: CompletionRequest) -> Result<StreamingResult, CompletionError> {
+        if let Some(stream) = &self.stream {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            return Ok(stream(request));
+        }
+        let chunks = self
+            .completion(request)
+            .await?
+            .choice
+            .into_iter()
+            .map(|content| {
+                Ok(match content {
+                    AssistantContent::Text(text) => StreamingChoice::Message(text.text),
+                    AssistantContent::ToolCall(call) => StreamingChoice::ToolCall(
+                        call.function.name,
+                        call.id,
+                        call.function.arguments,
+                    ),
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+}
+
+/// A response with the single content.
+pub fn response<R>(content: AssistantContent, raw_response: R) -> CompletionResponse<R> {
+    CompletionResponse::new(OneOrMany::one(content), raw_response)
+}
+
+type Embed = dyn Fn(&str) -> Vec<f64> + Send + Sync;
+
+/// An embedding model embedding each text with a function, and counting the calls it receives
+/// and the texts it embeds (including the calls of its clones).
+#[derive(Clone)]
+pub struct MockEmbeddingModel {
+    ndims: usize,
+    embed: Arc<Embed>,
+    calls: Arc<AtomicUsize>,
+    texts: Arc<AtomicUsize>,
+}
+
+impl MockEmbeddingModel {
+    /// A model embedding each text with the function, as a vector of `ndims` dimensions.
+    pub fn new(ndims: usize, embed: impl Fn(&str) -> Vec<f64> + Send + Sync + 'static) -> Self {
+        Self {
+            ndims,
+            embed: Arc::new(embed),
+            calls: Arc::default(),
+            texts: Arc::default(),
+        }
+    }
+
+    /// A model embedding every text as the same vector.
+    pub fn constant(vec: Vec<f64>) -> Self {
+        Self::new(vec.len(), move |_| vec.clone())
+    }
+
+    /// A model embedding each text as the number of occurrences of each of the words.
+    pub fn counting(words: &[&str]) -> Self {
+        let words = words
+            .iter()
+            .map(|word| word.to_string())
+            .collect::<Vec<_>>();
+        Self::new(words.len(), move |text| {
+            words
+                .iter()
+                .map(|word| text.matches(word.as_str()).count() as f64)
+                .collect()
+        })
+    }
+
+    /// The number of calls to [embed_texts](EmbeddingModel::embed_texts) received by the model
+    /// and its clones.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// The number of texts embedded by the model and its clones.
+    pub fn texts(&self) -> usize {
+        self.texts.load(Ordering::SeqCst)
+    }
+}
+
+impl EmbeddingModel for MockEmbeddingModel {
+    const MAX_DOCUMENTS: usize = 16;
+
+    fn ndims(&self) -> usize {
+        self.ndims
+    }
+
+    async fn embed_texts(
+        &self,
+        texts: impl IntoIterator<Item = String> + Send,
+    ) -> Result<Vec<Embedding>, EmbeddingError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(texts
+            .into_iter()
+            .map(|text| {
+                self.texts.fetch_add(1, Ordering::SeqCst);
+                Embedding {
+                    vec: (self.embed)(&text),
+                    document: text,
+                }
+            })
+            .collect())
+    }
+}
+
+type Matches = dyn Fn(&str, &str, &Value) -> bool + Send + Sync;
+
+/// A vector store index returning its documents in order, all with a score of 1 unless
+/// [ranked](MockIndex::ranked).
+pub struct MockIndex {
+    documents: Vec<(String, Value)>,
+    matches: Option<Box<Matches>>,
+    ranked: bool,
+}
+
+impl MockIndex {
+    /// An index of the documents, with their ids.
+    pub fn new<D: Serialize>(documents: impl IntoIterator<Item = (impl Into<String>, D)>) -> Self {
+        Self {
+            documents: documents
+                .into_iter()
+                .map(|(id, document)| {
+                    let document = serde_json::to_value(document).expect("Serializable document");
+                    (id.into(), document)
+                })
+                .collect(),
+            matches: None,
+            ranked: false,
+        }
+    }
+
+    /// Only return the documents for which `matches(query, id, document)` is true.
+    pub fn matching(
+        mut self,
+        matches: impl Fn(&str, &str, &Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.matches = Some(Box::new(matches));
+        self
+    }
+
+    /// Give decreasing scores to the results: 1.0, 0.9, 0.8...
+    pub fn ranked(mut self) -> Self {
+        self.ranked = true;
+        self
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        n: usize,
+    ) -> impl Iterator<Item = (f64, &'a String, &'a Value)> {
+        self.documents
+            .iter()
+            .filter(move |(id, document)| {
+                self.matches
+                    .as_ref()
+                    .is_none_or(|matches| matches(query, id, document))
+            })
+            .take(n)
+            .enumerate()
+            .map(|(rank, (id, document))| {
+                let score = if self.ranked {
+                    1.0 - rank as f64 / 10.0
+                } else {
+                    1.0
+                };
+                (score, id, document)
+            })
+    }
+}
+
+impl VectorStoreIndex for MockIndex {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        self.search(query, n)
+            .map(|(score, id, document)| {
+                Ok((score, id.clone(), serde_json::from_value(document.clone())?))
+            })
+            .collect()
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(self
+            .search(query, n)
+            .map(|(score, id, _)| (score, id.clone()))
+            .collect())
+    }
+}
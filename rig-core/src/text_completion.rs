@@ -0,0 +1,210 @@
+//! This module provides functionality for working with raw text completion models, i.e.: the
+//! (non-chat) completion endpoints still exposed by some providers (e.g.: Together AI or the
+//! llama.cpp server), which continue a prompt instead of answering a conversation.
+//!
+//! Those endpoints are mostly useful with base models and for code completion, where the model
+//! can also fill in the middle of a text, between the prompt and a suffix (see
+//! [FimCompletionModel]).
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::together, text_completion::TextCompletionModel};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let together = together::Client::new("your-together-api-key");
+//! let model = together.text_completion_model(together::CODE_LLAMA_34B_INSTRUCT);
+//!
+//! let response = model
+//!     .text_completion_request("fn fibonacci(n: u64) -> u64 {")
+//!     .max_tokens(128)
+//!     .stop("\n}")
+//!     .send()
+//!     .await?;
+//!
+//! println!("{}", response.text);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    completion::{CompletionError, FinishReason},
+    json_utils,
+};
+
+/// Struct representing a general text completion request that can be sent to a text
+/// completion model provider.
+#[derive(Debug, Clone)]
+pub struct TextCompletionRequest {
+    /// The text to continue
+    pub prompt: String,
+    /// The text following the completion, for the models filling in the middle of a text
+    pub suffix: Option<String>,
+    /// The temperature sent to the model provider
+    pub temperature: Option<f64>,
+    /// The maximum number of tokens to generate
+    pub max_tokens: Option<u64>,
+    /// The sequences stopping the generation
+    pub stop: Vec<String>,
+    /// Additional parameters to be sent to the model provider
+    pub additional_params: Option<serde_json::Value>,
+}
+
+/// General text completion response struct that contains the generated text and the raw
+/// response.
+#[derive(Debug, Clone)]
+pub struct TextCompletionResponse<T> {
+    /// The generated text, which does not include the prompt nor the suffix
+    pub text: String,
+    /// Why the model stopped generating the text, if the provider reports it.
+    pub finish_reason: Option<FinishReason>,
+    /// The raw response returned by the model provider
+    pub raw_response: T,
+}
+
+/// Trait defining a text completion model that can be used to generate text completion
+/// requests. This trait is meant to be implemented by the user to define a custom text
+/// completion model, either from a third-party provider (e.g.: Together AI) or a local model.
+pub trait TextCompletionModel: Clone + Send + Sync {
+    /// The raw response type returned by the underlying model.
+    type Response: Sync + Send;
+
+    /// Generates a text completion response for the given text completion request.
+    fn text_completion(
+        &self,
+        request: TextCompletionRequest,
+    ) -> impl std::future::Future<
+        Output = Result<TextCompletionResponse<Self::Response>, CompletionError>,
+    > + Send;
+
+    /// Returns the JSON payload sent to the model provider for the given request.
+    fn request_body(
+        &self,
+        request: TextCompletionRequest,
+    ) -> Result<serde_json::Value, CompletionError>;
+
+    /// Generates a text completion request builder for the given `prompt`.
+    fn text_completion_request(
+        &self,
+        prompt: impl Into<String>,
+    ) -> TextCompletionRequestBuilder<Self> {
+        TextCompletionRequestBuilder::new(self.clone(), prompt)
+    }
+}
+
+/// Trait of the text completion models able to fill in the middle (FIM) of a text, between a
+/// prefix and a suffix, e.g.: to complete the code at the cursor of an editor.
+///
+/// # Example
+/// ```rust
+/// use rig::{providers::mistral, text_completion::FimCompletionModel};
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let mistral = mistral::Client::new("your-mistral-api-key");
+/// let codestral = mistral.fim_model(mistral::CODESTRAL_LATEST);
+///
+/// let response = codestral
+///     .complete_fim("fn is_even(n: u64) -> bool {\n", "\n}")
+///     .max_tokens(64)
+///     .stop("\n\n")
+///     .send()
+///     .await?;
+///
+/// println!("{}", response.text);
+/// # Ok(())
+/// # }
+/// ```
+pub trait FimCompletionModel: TextCompletionModel {
+    /// Generates a text completion request builder filling in the middle of `prefix` and
+    /// `suffix`.
+    fn complete_fim(
+        &self,
+        prefix: impl Into<String>,
+        suffix: impl Into<String>,
+    ) -> TextCompletionRequestBuilder<Self> {
+        self.text_completion_request(prefix).suffix(suffix)
+    }
+}
+
+/// Builder struct for a text completion request
+///
+/// Note: It is usually unnecessary to create a text completion request builder directly.
+/// Instead, use the [TextCompletionModel::text_completion_request] method.
+pub struct TextCompletionRequestBuilder<M: TextCompletionModel> {
+    model: M,
+    prompt: String,
+    suffix: Option<String>,
+    temperature: Option<f64>,
+    max_tokens: Option<u64>,
+    stop: Vec<String>,
+    additional_params: Option<serde_json::Value>,
+}
+
+impl<M: TextCompletionModel> TextCompletionRequestBuilder<M> {
+    pub fn new(model: M, prompt: impl Into<String>) -> Self {
+        Self {
+            model,
+            prompt: prompt.into(),
+            suffix: None,
+            temperature: None,
+            max_tokens: None,
+            stop: vec![],
+            additional_params: None,
+        }
+    }
+
+    /// Sets the text following the completion, for the models filling in the middle of a text.
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+
+    /// Sets the temperature for the text completion request.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the maximum number of tokens to generate.
+    pub fn max_tokens(mut self, max_tokens: u64) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Adds a sequence stopping the generation.
+    pub fn stop(mut self, stop: impl Into<String>) -> Self {
+        self.stop.push(stop.into());
+        self
+    }
+
+    /// Adds additional parameters to the text completion request.
+    pub fn additional_params(mut self, additional_params: serde_json::Value) -> Self {
+        match self.additional_params {
+            Some(params) => {
+                self.additional_params = Some(json_utils::merge(params, additional_params));
+            }
+            None => {
+                self.additional_params = Some(additional_params);
+            }
+        }
+        self
+    }
+
+    /// Builds the text completion request.
+    pub fn build(self) -> TextCompletionRequest {
+        TextCompletionRequest {
+            prompt: self.prompt,
+            suffix: self.suffix,
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            stop: self.stop,
+            additional_params: self.additional_params,
+        }
+    }
+
+    /// Sends the text completion request to the model provider and returns the response.
+    pub async fn send(self) -> Result<TextCompletionResponse<M::Response>, CompletionError> {
+        let model = self.model.clone();
+
+        model.text_completion(self.build()).await
+    }
+}
@@ -0,0 +1,254 @@
+//! This module provides approximate token counting, to fit prompts, documents and chat histories
+//! into the context window of a model, to chunk texts by tokens, and to estimate costs.
+//!
+//! Two [TokenCounter]s are available:
+//! - [Heuristic]: a fast estimate from the number of characters, good enough for budgets with
+//!   some margin. It works for any provider.
+//! - `Tiktoken`: the exact byte pair encoding of OpenAI models, from the
+//!   [tiktoken-rs](https://docs.rs/tiktoken-rs) crate. It requires the `tiktoken` feature.
+//!
+//! [counter_for_model] picks the best counter for a model: the encoding of OpenAI models with the
+//! `tiktoken` feature, a heuristic tuned for its provider otherwise. Counters can also be passed
+//! explicitly wherever tokens are counted (e.g.: [TokenBudget::counter](crate::memory::history::TokenBudget::counter)).
+//!
+//! # Example
+//! ```rust
+//! use rig::tokens::{self, TokenCounter};
+//!
+//! let counter = tokens::counter_for_model("gpt-4o");
+//! let count = counter.count_tokens("How many tokens is this?");
+//! ```
+use std::sync::Arc;
+
+use crate::completion::message::{
+    AssistantContent, ContentFormat, Message, ToolResultContent, UserContent,
+};
+
+/// Tokens added to each message for its role and separators.
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Tokens counted for an image, whose actual cost depends on its size and the provider.
+const IMAGE_TOKENS: usize = 765;
+
+/// Trait for types counting the tokens of texts and messages.
+pub trait TokenCounter: Send + Sync {
+    /// Count the tokens of a text.
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Count the tokens of a message, including the overhead of its role and separators.
+    /// Base64 content other than images is estimated from its size.
+    fn count_message_tokens(&self, message: &Message) -> usize {
+        let binary = |data: &str| data.len().div_ceil(4);
+        let content = match message {
+            Message::User { content, .. } => content
+                .iter()
+                .map(|content| match content {
+                    UserContent::Text(text) => self.count_tokens(&text.text),
+                    UserContent::ToolResult(result) => result
+                        .content
+                        .iter()
+                        .map(|content| match content {
+                            ToolResultContent::Text(text) => self.count_tokens(&text.text),
+                            ToolResultContent::Image(_) => IMAGE_TOKENS,
+                        })
+                        .sum(),
+                    UserContent::Image(_) => IMAGE_TOKENS,
+                    UserContent::Audio(audio) => binary(&audio.data),
+                    UserContent::Document(document) => match document.format {
+                        Some(ContentFormat::Base64) => binary(&document.data),
+                        _ => self.count_tokens(&document.data),
+                    },
+                })
+                .sum::<usize>(),
+            Message::Assistant { content, .. } => content
+                .iter()
+                .map(|content| match content {
+                    AssistantContent::Text(text) => self.count_tokens(&text.text),
+                    AssistantContent::ToolCall(call) => {
+                        self.count_tokens(&call.function.name)
+                            + self.count_tokens(&call.function.arguments.to_string())
+                    }
+                })
+                .sum(),
+        };
+        content + MESSAGE_OVERHEAD
+    }
+
+    /// Count the tokens of a list of messages.
+    fn count_messages_tokens(&self, messages: &[Message]) -> usize {
+        messages
+            .iter()
+            .map(|message| self.count_message_tokens(message))
+            .sum()
+    }
+}
+
+/// Estimate of the number of tokens from the number of characters. ASCII characters count for
+/// a fraction of a token (a quarter by default), other characters (e.g.: CJK scripts, emojis)
+/// for a full token, which overestimates accented Latin text a bit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Heuristic {
+    chars_per_token: f64,
+}
+
+impl Heuristic {
+    /// Create a heuristic counting `chars_per_token` ASCII characters per token.
+    pub fn new(chars_per_token: f64) -> Self {
+        Self {
+            chars_per_token: chars_per_token.max(1.0),
+        }
+    }
+}
+
+impl Default for Heuristic {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl TokenCounter for Heuristic {
+    fn count_tokens(&self, text: &str) -> usize {
+        let ascii = text.bytes().filter(u8::is_ascii).count();
+        let other = text.chars().count() - ascii;
+        (ascii as f64 / self.chars_per_token).ceil() as usize + other
+    }
+}
+
+/// The byte pair encoding of OpenAI models, as implemented by tiktoken.
+#[cfg(feature = "tiktoken")]
+#[derive(Clone, Copy)]
+pub struct Tiktoken(&'static tiktoken_rs::CoreBPE);
+
+#[cfg(feature = "tiktoken")]
+impl Tiktoken {
+    /// Get a tiktoken encoding by name (e.g.: `o200k_base` or `cl100k_base`), if known.
+    /// The encodings are loaded once, on first use.
+    pub fn encoding(name: &str) -> Option<Self> {
+        Some(Self(match name {
+            "o200k_base" => tiktoken_rs::o200k_base_singleton(),
+            "cl100k_base" => tiktoken_rs::cl100k_base_singleton(),
+            "p50k_base" => tiktoken_rs::p50k_base_singleton(),
+            "p50k_edit" => tiktoken_rs::p50k_edit_singleton(),
+            "r50k_base" => tiktoken_rs::r50k_base_singleton(),
+            _ => return None,
+        }))
+    }
+
+    /// Get the tiktoken encoding of an OpenAI model, if known (see [encoding_for_model]).
+    pub fn for_model(model: &str) -> Option<Self> {
+        encoding_for_model(model).and_then(Self::encoding)
+    }
+
+    /// Encode a text into token ranks. Special tokens are encoded as ordinary text.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        self.0.encode_ordinary(text)
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl std::fmt::Debug for Tiktoken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tiktoken").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for Tiktoken {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+}
+
+/// Get the tiktoken encoding of an OpenAI model, if known. Provider prefixes (e.g.:
+/// `openai/gpt-4o`) are ignored.
+pub fn encoding_for_model(model: &str) -> Option<&'static str> {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    let starts_with = |prefixes: &[&str]| prefixes.iter().any(|prefix| model.starts_with(prefix));
+
+    if starts_with(&[
+        "gpt-4o",
+        "chatgpt-4o",
+        "gpt-4.1",
+        "gpt-4.5",
+        "gpt-5",
+        "o1",
+        "o3",
+        "o4",
+    ]) {
+        Some("o200k_base")
+    } else if starts_with(&[
+        "gpt-4",
+        "gpt-3.5",
+        "text-embedding-3",
+        "text-embedding-ada-002",
+    ]) {
+        Some("cl100k_base")
+    } else {
+        None
+    }
+}
+
+/// Get the best available token counter for a model: its tiktoken encoding with the `tiktoken`
+/// feature, a heuristic tuned for its provider otherwise.
+pub fn counter_for_model(model: &str) -> Arc<dyn TokenCounter> {
+    #[cfg(feature = "tiktoken")]
+    if let Some(tiktoken) = Tiktoken::for_model(model) {
+        return Arc::new(tiktoken);
+    }
+
+    // Claude and Gemini tokenizers produce a bit more tokens than OpenAI's on English text
+    let model = model.to_lowercase();
+    if model.contains("claude") || model.contains("gemini") {
+        Arc::new(Heuristic::new(3.5))
+    } else {
+        Arc::new(Heuristic::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken() {
+        let tiktoken = Tiktoken::encoding("cl100k_base").unwrap();
+        assert_eq!(
+            tiktoken.encode("tiktoken is great!"),
+            vec![83, 1609, 5963, 374, 2294, 0]
+        );
+        assert_eq!(tiktoken.count_tokens("<|endoftext|>"), 7);
+        assert!(Tiktoken::encoding("unknown").is_none());
+
+        assert_eq!(
+            counter_for_model("gpt-3.5-turbo").count_tokens("tiktoken is great!"),
+            6
+        );
+    }
+
+    #[test]
+    fn test_heuristic() {
+        let counter = Heuristic::default();
+        assert_eq!(counter.count_tokens(""), 0);
+        assert_eq!(counter.count_tokens("Hello world!"), 3);
+        assert_eq!(counter.count_tokens("日本語"), 3);
+
+        let messages = vec![Message::user("Hello world!"), Message::assistant("Hi")];
+        assert_eq!(counter.count_messages_tokens(&messages), 3 + 1 + 2 * 4);
+    }
+
+    #[test]
+    fn test_counter_for_model() {
+        assert_eq!(encoding_for_model("gpt-4o-mini"), Some("o200k_base"));
+        assert_eq!(
+            encoding_for_model("openai/gpt-4-turbo"),
+            Some("cl100k_base")
+        );
+        assert_eq!(encoding_for_model("claude-3-5-sonnet-latest"), None);
+
+        assert_eq!(
+            counter_for_model("claude-3-5-sonnet-latest").count_tokens("abcdefg"),
+            2
+        );
+    }
+}
@@ -8,15 +8,37 @@
 //!
 //! The [ToolSet] struct is a collection of tools that can be used by an [Agent](crate::agent::Agent)
 //! and optionally RAGged.
+//!
+//! The [FnTool] struct (created with [FnTool::from_fn]) turns an async function into a tool for
+//! simple tools that do not warrant their own struct, trait implementation and error type.
+//!
+//! Tools coming from different sources (e.g.: several toolsets with a `search` tool) can be
+//! namespaced with [Namespaced] or [ToolSet::namespaced] to avoid name collisions.
+//!
+//! Large tool results can be truncated with [ToolSet::truncate_results], or split into pages
+//! that the model reads on demand with [ToolSet::paginate_results], so that they do not fill
+//! the context window of the model.
+//!
+//! A [ToolSet] records usage statistics ([ToolStats]) for each of its tools, and can limit the
+//! number of calls of a tool ([ToolLimit]) to stop a model calling the same tool in a loop.
 
-use std::{collections::HashMap, pin::Pin};
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use futures::Future;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    completion::{self, ToolDefinition},
+    completion::ToolDefinition,
     embeddings::{embed::EmbedError, tool::ToolSchema},
+    json_utils,
+    metadata::{Document, DocumentMetadata},
+    test_mode,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -27,6 +49,15 @@ pub enum ToolError {
 
     #[error("JsonError: {0}")]
     JsonError(#[from] serde_json::Error),
+
+    /// The arguments of the tool call could not be deserialized, even after repairing them
+    /// (e.g.: replacing single quotes, removing trailing commas)
+    #[error("Invalid tool arguments: {error} (arguments: {args})")]
+    InvalidArguments {
+        args: String,
+        #[source]
+        error: serde_json::Error,
+    },
 }
 
 /// Trait that represents a simple LLM tool
@@ -172,14 +203,14 @@ impl<T: Tool> ToolDyn for T {
         args: String,
     ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>> {
         Box::pin(async move {
-            match serde_json::from_str(&args) {
+            match json_utils::from_str_repaired(&args) {
                 Ok(args) => <Self as Tool>::call(self, args)
                     .await
                     .map_err(|e| ToolError::ToolCallError(Box::new(e)))
                     .and_then(|output| {
                         serde_json::to_string(&output).map_err(ToolError::JsonError)
                     }),
-                Err(e) => Err(ToolError::JsonError(e)),
+                Err(error) => Err(ToolError::InvalidArguments { args, error }),
             }
         })
     }
@@ -202,6 +233,131 @@ impl<T: ToolEmbedding> ToolEmbeddingDyn for T {
     }
 }
 
+impl ToolDyn for Box<dyn ToolDyn> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + '_>> {
+        (**self).definition(prompt)
+    }
+
+    fn call(
+        &self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>> {
+        (**self).call(args)
+    }
+}
+
+impl ToolDyn for Box<dyn ToolEmbeddingDyn> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + '_>> {
+        (**self).definition(prompt)
+    }
+
+    fn call(
+        &self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>> {
+        (**self).call(args)
+    }
+}
+
+impl ToolEmbeddingDyn for Box<dyn ToolEmbeddingDyn> {
+    fn context(&self) -> serde_json::Result<serde_json::Value> {
+        (**self).context()
+    }
+
+    fn embedding_docs(&self) -> Vec<String> {
+        (**self).embedding_docs()
+    }
+}
+
+/// Default separator between the namespace and the name of a [Namespaced] tool. Providers such
+/// as OpenAI and Anthropic only accept letters, digits, `_` and `-` in tool names.
+pub const NAMESPACE_SEPARATOR: &str = "_";
+
+/// A tool wrapper prefixing the name of the wrapped tool with a namespace (e.g.: `search_web`
+/// for the `web` tool in the `search` namespace), both in its definition and when it is called.
+///
+/// # Example
+/// ```rust
+/// use rig::tool::{FnTool, Namespaced, ToolDyn};
+///
+/// let web = FnTool::from_fn("web", "Search the web", serde_json::json!({"type": "object"}),
+///     |_: serde_json::Value| async { Ok::<_, String>("results") });
+///
+/// let tool = Namespaced::new("search", web).separator(".");
+/// assert_eq!(ToolDyn::name(&tool), "search.web");
+/// ```
+pub struct Namespaced<T> {
+    namespace: String,
+    separator: String,
+    tool: T,
+}
+
+impl<T: ToolDyn> Namespaced<T> {
+    pub fn new(namespace: &str, tool: T) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            separator: NAMESPACE_SEPARATOR.to_string(),
+            tool,
+        }
+    }
+
+    /// Set the separator between the namespace and the name of the tool (defaults to
+    /// [NAMESPACE_SEPARATOR]).
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+}
+
+impl<T: ToolDyn> ToolDyn for Namespaced<T> {
+    fn name(&self) -> String {
+        format!("{}{}{}", self.namespace, self.separator, self.tool.name())
+    }
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + '_>> {
+        Box::pin(async move {
+            ToolDefinition {
+                name: self.name(),
+                ..self.tool.definition(prompt).await
+            }
+        })
+    }
+
+    fn call(
+        &self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>> {
+        self.tool.call(args)
+    }
+}
+
+impl<T: ToolEmbeddingDyn> ToolEmbeddingDyn for Namespaced<T> {
+    fn context(&self) -> serde_json::Result<serde_json::Value> {
+        self.tool.context()
+    }
+
+    fn embedding_docs(&self) -> Vec<String> {
+        self.tool.embedding_docs()
+    }
+}
+
 pub(crate) enum ToolType {
     Simple(Box<dyn ToolDyn>),
     Embedding(Box<dyn ToolEmbeddingDyn>),
@@ -233,21 +389,200 @@ impl ToolType {
 #[derive(Debug, thiserror::Error)]
 pub enum ToolSetError {
     /// Error returned by the tool
-    #[error("ToolCallError: {0}")]
+    #[error(transparent)]
     ToolCallError(#[from] ToolError),
 
     #[error("ToolNotFoundError: {0}")]
     ToolNotFoundError(String),
 
+    /// Two tools have the same name (see [Namespaced] to avoid collisions)
+    #[error("DuplicateToolError: {0}")]
+    DuplicateToolError(String),
+
+    /// The call was rejected because the tool reached its [ToolLimit]
+    #[error("ToolLimitError: {0}")]
+    ToolLimitError(String),
+
     // TODO: Revisit this
     #[error("JsonError: {0}")]
     JsonError(#[from] serde_json::Error),
 }
 
+/// Name of the tool reading the pages of paginated tool results, see
+/// [ToolSet::paginate_results].
+pub const READ_RESULT_PAGE_TOOL: &str = "read_tool_result_page";
+
+/// Number of paginated results kept for the model to read.
+const MAX_PAGINATED_RESULTS: usize = 16;
+
+/// Truncate a text to its first and last characters, with a marker in the middle, if it is
+/// longer than `max_chars` characters.
+pub fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_string();
+    }
+
+    let head = text
+        .chars()
+        .take(max_chars - max_chars / 2)
+        .collect::<String>();
+    let tail = text.chars().skip(len - max_chars / 2).collect::<String>();
+    format!(
+        "{head}\n[... {} characters truncated ...]\n{tail}",
+        len - max_chars
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ResultLimit {
+    Truncate(usize),
+    Paginate(usize),
+}
+
+/// The paginated tool results, most recent last.
+type PaginatedResults = Arc<Mutex<VecDeque<(String, Vec<String>)>>>;
+
+#[derive(Deserialize)]
+struct ReadPageArgs {
+    result_id: String,
+    page: usize,
+}
+
+/// The tool reading the pages of paginated tool results.
+struct ReadResultPage(PaginatedResults);
+
+impl Tool for ReadResultPage {
+    const NAME: &'static str = READ_RESULT_PAGE_TOOL;
+
+    type Error = ToolError;
+    type Args = ReadPageArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: READ_RESULT_PAGE_TOOL.to_string(),
+            description: "Read a page of a tool result that was too large to be returned at once."
+                .to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "result_id": {
+                        "type": "string",
+                        "description": "The ID of the tool result"
+                    },
+                    "page": {
+                        "type": "integer",
+                        "description": "The number of the page, starting at 1"
+                    }
+                },
+                "required": ["result_id", "page"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let results = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((_, pages)) = results.iter().find(|(id, _)| *id == args.result_id) else {
+            return Err(ToolError::ToolCallError(
+                format!("Unknown tool result {}", args.result_id).into(),
+            ));
+        };
+        match args.page.checked_sub(1).and_then(|i| pages.get(i)) {
+            Some(page) => Ok(page_with_marker(
+                page,
+                &args.result_id,
+                args.page,
+                pages.len(),
+            )),
+            None => Err(ToolError::ToolCallError(
+                format!("Tool result {} has {} pages", args.result_id, pages.len()).into(),
+            )),
+        }
+    }
+}
+
+fn page_with_marker(page: &str, id: &str, number: usize, pages: usize) -> String {
+    format!(
+        "{page}\n[Page {number} of {pages} of tool result {id}. Call the {READ_RESULT_PAGE_TOOL} \
+        tool to read the other pages.]"
+    )
+}
+
+/// Usage statistics of a tool, see [ToolSet::stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ToolStats {
+    /// Number of calls of the tool (including failed calls)
+    pub calls: u64,
+    /// Number of calls that returned an error
+    pub failures: u64,
+    /// Number of calls rejected because of the [ToolLimit] of the tool
+    pub rejected: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+}
+
+impl ToolStats {
+    pub fn failure_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.calls as f64
+        }
+    }
+
+    pub fn mean_latency(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            self.total_latency / self.calls as u32
+        }
+    }
+}
+
+/// Limits on the number of calls of a tool, see [ToolSet::limit].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ToolLimit {
+    /// Maximum number of calls of the tool in a run, i.e.: since the toolset was created or
+    /// since the last call to [ToolSet::reset_limits]
+    pub max_calls: Option<u64>,
+    /// Maximum number of calls of the tool in any period of the given duration
+    pub rate: Option<(u64, Duration)>,
+}
+
+impl ToolLimit {
+    /// Limit the number of calls of the tool in a run.
+    pub fn max_calls(max_calls: u64) -> Self {
+        Self {
+            max_calls: Some(max_calls),
+            rate: None,
+        }
+    }
+
+    /// Limit the number of calls of the tool in any period of duration `per`.
+    pub fn rate(mut self, calls: u64, per: Duration) -> Self {
+        self.rate = Some((calls, per));
+        self
+    }
+}
+
+#[derive(Default)]
+struct ToolUsage {
+    stats: ToolStats,
+    /// Number of calls in the current run
+    run_calls: u64,
+    /// Start of the recent calls, for rate limits
+    recent_calls: VecDeque<Instant>,
+}
+
 /// A struct that holds a set of tools
 #[derive(Default)]
 pub struct ToolSet {
     pub(crate) tools: HashMap<String, ToolType>,
+    result_limit: Option<ResultLimit>,
+    paginated_results: PaginatedResults,
+    limits: HashMap<String, ToolLimit>,
+    usage: Mutex<HashMap<String, ToolUsage>>,
 }
 
 impl ToolSet {
@@ -276,9 +611,163 @@ impl ToolSet {
             .insert(tool.name(), ToolType::Simple(Box::new(tool)));
     }
 
-    /// Merge another toolset into this one
-    pub fn add_tools(&mut self, toolset: ToolSet) {
+    /// Merge another toolset into this one. Fails, without adding any tool, if a tool of the
+    /// other toolset has the same name as a tool of this one.
+    pub fn add_tools(&mut self, toolset: ToolSet) -> Result<(), ToolSetError> {
+        if let Some(name) = toolset.tools.keys().find(|name| self.contains(name)) {
+            return Err(ToolSetError::DuplicateToolError(name.clone()));
+        }
         self.tools.extend(toolset.tools);
+        Ok(())
+    }
+
+    /// Truncate the results of the tools longer than `max_chars` characters, keeping their
+    /// beginning and their end (see [truncate_middle]).
+    pub fn truncate_results(&mut self, max_chars: usize) {
+        self.result_limit = Some(ResultLimit::Truncate(max_chars.max(1)));
+    }
+
+    /// Split the results of the tools longer than `page_chars` characters into pages. Only the
+    /// first page is returned, and the [READ_RESULT_PAGE_TOOL] tool, added to the toolset, lets
+    /// the model read the other pages of the most recent results.
+    pub fn paginate_results(&mut self, page_chars: usize) {
+        self.result_limit = Some(ResultLimit::Paginate(page_chars.max(1)));
+        self.add_tool(ReadResultPage(self.paginated_results.clone()));
+    }
+
+    /// Limit the number of calls of a tool. Calls exceeding the limit fail with a
+    /// [ToolSetError::ToolLimitError], which stops the agent calling the tool.
+    pub fn limit(&mut self, toolname: &str, limit: ToolLimit) {
+        self.limits.insert(toolname.to_string(), limit);
+    }
+
+    /// Start a new run: reset the number of calls counted for the [ToolLimit::max_calls]
+    /// limits (the usage statistics are kept).
+    pub fn reset_limits(&self) {
+        self.usage
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values_mut()
+            .for_each(|usage| usage.run_calls = 0);
+    }
+
+    /// Get the usage statistics of the tools that were called, by tool name.
+    pub fn stats(&self) -> HashMap<String, ToolStats> {
+        self.usage
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(name, usage)| (name.clone(), usage.stats))
+            .collect()
+    }
+
+    /// Reset the usage statistics (and the limits) of all the tools.
+    pub fn reset_stats(&self) {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Check the limit of a tool before a call and count the call.
+    fn acquire(&self, toolname: &str) -> Result<(), ToolSetError> {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = usage.entry(toolname.to_string()).or_default();
+        let now = Instant::now();
+
+        if let Some(limit) = self.limits.get(toolname) {
+            if let Some((calls, per)) = limit.rate {
+                while usage
+                    .recent_calls
+                    .front()
+                    .is_some_and(|start| now.duration_since(*start) >= per)
+                {
+                    usage.recent_calls.pop_front();
+                }
+                if usage.recent_calls.len() as u64 >= calls {
+                    usage.stats.rejected += 1;
+                    return Err(ToolSetError::ToolLimitError(format!(
+                        "{toolname} can only be called {calls} times every {per:?}"
+                    )));
+                }
+            }
+            if limit.max_calls.is_some_and(|max| usage.run_calls >= max) {
+                usage.stats.rejected += 1;
+                return Err(ToolSetError::ToolLimitError(format!(
+                    "{toolname} was already called {} times",
+                    usage.run_calls
+                )));
+            }
+            if limit.rate.is_some() {
+                usage.recent_calls.push_back(now);
+            }
+        }
+
+        usage.run_calls += 1;
+        Ok(())
+    }
+
+    /// Record the latency and the outcome of a call.
+    fn record(&self, toolname: &str, latency: Duration, failed: bool) {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = &mut usage.entry(toolname.to_string()).or_default().stats;
+        stats.calls += 1;
+        stats.failures += u64::from(failed);
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+    }
+
+    /// Apply the result limit of the toolset to the result of a tool.
+    fn limit_result(&self, toolname: &str, result: String) -> String {
+        match self.result_limit {
+            Some(ResultLimit::Truncate(max_chars)) => truncate_middle(&result, max_chars),
+            Some(ResultLimit::Paginate(page_chars))
+                if toolname != READ_RESULT_PAGE_TOOL && result.chars().count() > page_chars =>
+            {
+                let chars = result.chars().collect::<Vec<_>>();
+                let pages = chars
+                    .chunks(page_chars)
+                    .map(|page| page.iter().collect::<String>())
+                    .collect::<Vec<_>>();
+                let id = test_mode::generate_id("result");
+                let first_page = page_with_marker(&pages[0], &id, 1, pages.len());
+
+                let mut results = self
+                    .paginated_results
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                if results.len() >= MAX_PAGINATED_RESULTS {
+                    results.pop_front();
+                }
+                results.push_back((id, pages));
+                first_page
+            }
+            _ => result,
+        }
+    }
+
+    /// Prefix the names of all the tools of the toolset with a namespace, see [Namespaced].
+    pub fn namespaced(self, namespace: &str) -> Self {
+        Self {
+            result_limit: self.result_limit,
+            paginated_results: self.paginated_results,
+            limits: self
+                .limits
+                .into_iter()
+                .map(|(name, limit)| (format!("{namespace}{NAMESPACE_SEPARATOR}{name}"), limit))
+                .collect(),
+            usage: Default::default(),
+            tools: self
+                .tools
+                .into_values()
+                .map(|tool| match tool {
+                    ToolType::Simple(tool) => {
+                        ToolType::Simple(Box::new(Namespaced::new(namespace, tool)))
+                    }
+                    ToolType::Embedding(tool) => {
+                        ToolType::Embedding(Box::new(Namespaced::new(namespace, tool)))
+                    }
+                })
+                .map(|tool| (tool.name(), tool))
+                .collect(),
+        }
     }
 
     pub(crate) fn get(&self, toolname: &str) -> Option<&ToolType> {
@@ -292,21 +781,25 @@ impl ToolSet {
                 "Calling tool {toolname} with args:\n{}",
                 serde_json::to_string_pretty(&args).unwrap_or_else(|_| args.clone())
             );
-            Ok(tool.call(args).await?)
+            self.acquire(toolname)?;
+            let start = Instant::now();
+            let result = tool.call(args).await;
+            self.record(toolname, start.elapsed(), result.is_err());
+            Ok(self.limit_result(toolname, result?))
         } else {
             Err(ToolSetError::ToolNotFoundError(toolname.to_string()))
         }
     }
 
     /// Get the documents of all the tools in the toolset
-    pub async fn documents(&self) -> Result<Vec<completion::Document>, ToolSetError> {
+    pub async fn documents(&self) -> Result<Vec<Document>, ToolSetError> {
         let mut docs = Vec::new();
         for tool in self.tools.values() {
             match tool {
                 ToolType::Simple(tool) => {
-                    docs.push(completion::Document {
-                        id: tool.name(),
-                        text: format!(
+                    docs.push(Document::new(
+                        DocumentMetadata::new(&tool.name()),
+                        &format!(
                             "\
                             Tool: {}\n\
                             Definition: \n\
@@ -315,13 +808,12 @@ impl ToolSet {
                             tool.name(),
                             serde_json::to_string_pretty(&tool.definition("".to_string()).await)?
                         ),
-                        additional_props: HashMap::new(),
-                    });
+                    ));
                 }
                 ToolType::Embedding(tool) => {
-                    docs.push(completion::Document {
-                        id: tool.name(),
-                        text: format!(
+                    docs.push(Document::new(
+                        DocumentMetadata::new(&tool.name()),
+                        &format!(
                             "\
                             Tool: {}\n\
                             Definition: \n\
@@ -330,8 +822,7 @@ impl ToolSet {
                             tool.name(),
                             serde_json::to_string_pretty(&tool.definition("".to_string()).await)?
                         ),
-                        additional_props: HashMap::new(),
-                    });
+                    ));
                 }
             }
         }
@@ -378,6 +869,298 @@ impl ToolSetBuilder {
                 .into_iter()
                 .map(|tool| (tool.name(), tool))
                 .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A tool built from a name, a description, a JSON schema of its arguments and an async function.
+/// Its errors are returned as [ToolError::ToolCallError].
+///
+/// The function can return any error that can be converted into a boxed error
+/// (e.g.: `String`, `anyhow::Error` or any type implementing [std::error::Error]).
+///
+/// # Example
+/// ```
+/// use rig::tool::{FnTool, ToolSet};
+///
+/// #[derive(serde::Deserialize)]
+/// struct AddArgs {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let adder = FnTool::from_fn(
+///     "add",
+///     "Add x and y together",
+///     serde_json::json!({
+///         "type": "object",
+///         "properties": {
+///             "x": { "type": "number", "description": "The first number to add" },
+///             "y": { "type": "number", "description": "The second number to add" }
+///         }
+///     }),
+///     |args: AddArgs| async move { Ok::<_, String>(args.x + args.y) },
+/// );
+///
+/// let toolset = ToolSet::builder().static_tool(adder).build();
+/// ```
+pub struct FnTool<F, Args, Output> {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+    f: F,
+    _marker: PhantomData<fn(Args) -> Output>,
+}
+
+impl<F, Fut, Args, Output, E> FnTool<F, Args, Output>
+where
+    F: Fn(Args) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Output, E>> + Send + Sync,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    /// Create a tool from a name, a description, a JSON schema of the arguments and an async
+    /// function.
+    pub fn from_fn(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+        f: F,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+            f,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F, Fut, Args, Output, E> ToolDyn for FnTool<F, Args, Output>
+where
+    F: Fn(Args) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Output, E>> + Send + Sync,
+    Args: for<'a> Deserialize<'a> + Send + Sync,
+    Output: Serialize,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> Pin<Box<dyn Future<Output = ToolDefinition> + Send + Sync + '_>> {
+        Box::pin(async move {
+            ToolDefinition {
+                name: self.name.clone(),
+                description: self.description.clone(),
+                parameters: self.parameters.clone(),
+            }
+        })
+    }
+
+    fn call(
+        &self,
+        args: String,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + Sync + '_>> {
+        Box::pin(async move {
+            let args = json_utils::from_str_repaired(&args)
+                .map_err(|error| ToolError::InvalidArguments { args, error })?;
+            let output = (self.f)(args)
+                .await
+                .map_err(|e| ToolError::ToolCallError(e.into()))?;
+            Ok(serde_json::to_string(&output)?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use std::time::Duration;
+
+    use super::{
+        truncate_middle, FnTool, Namespaced, ToolDyn, ToolLimit, ToolSet, ToolSetError,
+        READ_RESULT_PAGE_TOOL,
+    };
+
+    #[derive(Deserialize)]
+    struct DivArgs {
+        x: i32,
+        y: i32,
+    }
+
+    #[tokio::test]
+    async fn test_fn_tool() {
+        let divide = FnTool::from_fn(
+            "divide",
+            "Divide x by y",
+            serde_json::json!({"type": "object"}),
+            |args: DivArgs| async move {
+                if args.y == 0 {
+                    return Err("Division by zero");
+                }
+                Ok(args.x / args.y)
+            },
+        );
+
+        assert_eq!(ToolDyn::name(&divide), "divide");
+        let definition = ToolDyn::definition(&divide, String::new()).await;
+        assert_eq!(definition.description, "Divide x by y");
+
+        let toolset = ToolSet::builder().static_tool(divide).build();
+        assert_eq!(
+            toolset
+                .call("divide", r#"{"x": 6, "y": 3}"#.to_string())
+                .await
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(
+            toolset
+                .call("divide", r#"{"x": 6, "y": 0}"#.to_string())
+                .await
+                .unwrap_err()
+                .to_string(),
+            "ToolCallError: Division by zero"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_tools() {
+        let echo = |name: &str| {
+            FnTool::from_fn(
+                name,
+                "Echo",
+                serde_json::json!({"type": "object"}),
+                |args: serde_json::Value| async move { Ok::<_, String>(args) },
+            )
+        };
+
+        let mut toolset = ToolSet::from_tools(vec![echo("search")]);
+        let duplicate = ToolSet::from_tools(vec![echo("search")]);
+        assert!(matches!(
+            toolset.add_tools(duplicate),
+            Err(ToolSetError::DuplicateToolError(name)) if name == "search"
+        ));
+
+        let web = ToolSet::from_tools(vec![echo("search")]).namespaced("web");
+        toolset.add_tools(web).unwrap();
+        assert!(toolset.contains("search") && toolset.contains("web_search"));
+
+        let definition = toolset
+            .get("web_search")
+            .unwrap()
+            .definition(String::new())
+            .await;
+        assert_eq!(definition.name, "web_search");
+        assert_eq!(
+            toolset
+                .call("web_search", r#"{"q": "rig"}"#.to_string())
+                .await
+                .unwrap(),
+            r#"{"q":"rig"}"#
+        );
+
+        let dotted = Namespaced::new("search", echo("web")).separator(".");
+        assert_eq!(ToolDyn::name(&dotted), "search.web");
+    }
+
+    #[tokio::test]
+    async fn test_tool_result_limits() {
+        assert_eq!(truncate_middle("abcdefghij", 20), "abcdefghij");
+        assert_eq!(
+            truncate_middle("abcdefghij", 5),
+            "abc\n[... 5 characters truncated ...]\nij"
+        );
+
+        let digits = || {
+            FnTool::from_fn(
+                "digits",
+                "Return 25 digits",
+                serde_json::json!({"type": "object"}),
+                |_: serde_json::Value| async { Ok::<_, String>("0123456789".repeat(2) + "01234") },
+            )
+        };
+
+        let mut truncated = ToolSet::from_tools(vec![digits()]);
+        truncated.truncate_results(10);
+        assert_eq!(
+            truncated.call("digits", "{}".to_string()).await.unwrap(),
+            "\"0123\n[... 17 characters truncated ...]\n1234\""
+        );
+
+        // The result is a JSON string of 27 characters, split in 3 pages
+        let mut paginated = ToolSet::from_tools(vec![digits()]);
+        paginated.paginate_results(10);
+        let first_page = paginated.call("digits", "{}".to_string()).await.unwrap();
+        assert!(first_page.starts_with("\"012345678\n[Page 1 of 3 of tool result result_"));
+
+        let id = first_page
+            .split_whitespace()
+            .find(|word| word.starts_with("result_"))
+            .unwrap()
+            .trim_end_matches('.');
+        let last_page = paginated
+            .call(
+                READ_RESULT_PAGE_TOOL,
+                serde_json::json!({"result_id": id, "page": 3}).to_string(),
+            )
+            .await
+            .unwrap();
+        assert!(last_page.starts_with("\"901234\\\"\\n[Page 3 of 3"));
+
+        assert!(paginated
+            .call(
+                READ_RESULT_PAGE_TOOL,
+                serde_json::json!({"result_id": id, "page": 4}).to_string(),
+            )
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tool_stats_and_limits() {
+        let check = FnTool::from_fn(
+            "check",
+            "Fail on negative numbers",
+            serde_json::json!({"type": "object"}),
+            |x: i32| async move {
+                if x < 0 {
+                    return Err("Negative number");
+                }
+                Ok(x)
+            },
+        );
+        let mut toolset = ToolSet::from_tools(vec![check]);
+        toolset.limit("check", ToolLimit::max_calls(3));
+
+        for x in ["1", "-1", "2"] {
+            let _ = toolset.call("check", x.to_string()).await;
         }
+        assert!(matches!(
+            toolset.call("check", "3".to_string()).await,
+            Err(ToolSetError::ToolLimitError(_))
+        ));
+
+        let stats = toolset.stats()["check"];
+        assert_eq!((stats.calls, stats.failures, stats.rejected), (3, 1, 1));
+        assert!((stats.failure_rate() - 1.0 / 3.0).abs() < 1e-9);
+
+        // A new run resets the limits, but not the statistics
+        toolset.reset_limits();
+        assert_eq!(toolset.call("check", "3".to_string()).await.unwrap(), "3");
+        assert_eq!(toolset.stats()["check"].calls, 4);
+
+        toolset.limit(
+            "check",
+            ToolLimit::default().rate(1, Duration::from_secs(60)),
+        );
+        toolset.call("check", "4".to_string()).await.unwrap();
+        assert!(toolset.call("check", "5".to_string()).await.is_err());
     }
 }
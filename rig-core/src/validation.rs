@@ -0,0 +1,69 @@
+//! This module provides the validation of the responses of [agents](crate::agent::Agent) and
+//! [extractors](crate::extractor::Extractor), with retries.
+//!
+//! A validator is a function checking a response (the text of an agent, or the data extracted by
+//! an extractor), and returning a [ValidationError] describing the problem when the response is
+//! invalid. The error is then appended to the conversation and the model is asked to answer
+//! again, up to the maximum number of retries, after which the error is returned.
+//!
+//! # Example
+//! ```rust
+//! use rig::{providers::openai, validation::ValidationError};
+//!
+//! #[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
+//! struct Event {
+//!     name: String,
+//!     /// The date of the event, in the ISO 8601 format (YYYY-MM-DD)
+//!     date: String,
+//! }
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//!
+//! let extractor = openai
+//!     .extractor::<Event>(openai::GPT_4O)
+//!     .validate(|event| {
+//!         let valid = event.date.len() == 10
+//!             && event.date.char_indices().all(|(i, c)| match i {
+//!                 4 | 7 => c == '-',
+//!                 _ => c.is_ascii_digit(),
+//!             });
+//!         if valid {
+//!             Ok(())
+//!         } else {
+//!             Err(ValidationError::new("`date` must be a valid ISO date (YYYY-MM-DD)"))
+//!         }
+//!     })
+//!     .max_validation_retries(3)
+//!     .build();
+//! # }
+//! ```
+
+/// The reason why a response is invalid, sent to the model when it is asked to answer again.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct ValidationError(pub String);
+
+impl ValidationError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+/// A validator of responses of type `T`.
+pub type Validator<T> = Box<dyn Fn(&T) -> Result<(), ValidationError> + Send + Sync>;
+
+/// Run the validators in order, returning the first error.
+pub(crate) fn validate<T: ?Sized>(
+    validators: &[Validator<T>],
+    response: &T,
+) -> Result<(), ValidationError> {
+    validators
+        .iter()
+        .try_for_each(|validator| validator(response))
+}
+
+/// The message asking the model to answer again, fixing the error.
+pub(crate) fn correction(error: &ValidationError) -> String {
+    format!("Your answer is invalid: {error}\nAnswer again, fixing this error.")
+}
@@ -0,0 +1,140 @@
+//! This module provides [FilteredIndex], a vector store index wrapper only returning the
+//! documents whose [metadata](crate::metadata::DocumentMetadata) match a [MetadataFilter].
+//!
+//! The filter is applied to the results of the wrapped index, which works with any backend.
+//! Backends with native filtering (e.g.: Qdrant payload filters) are more efficient for
+//! selective filters, since the wrapped index is only searched for a bounded number of results.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     metadata::{MetadataFilter, Document},
+//!     providers::openai,
+//!     vector_store::{filter::FilteredIndex, in_memory_store::InMemoryVectorStore},
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//! let model = openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+//! let index = InMemoryVectorStore::<Document>::default().index(model);
+//!
+//! let index = FilteredIndex::new(index, MetadataFilter::Tag("docs".to_string()));
+//! let agent = openai.agent(openai::GPT_4O).dynamic_context(3, index).build();
+//! # }
+//! ```
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{VectorStoreError, VectorStoreIndex};
+use crate::metadata::{DocumentMetadata, MetadataFilter};
+
+/// A vector store index filtering the results of the wrapped index on the `metadata` field of
+/// the documents. Documents without a valid `metadata` field never match.
+pub struct FilteredIndex<I> {
+    index: I,
+    filter: MetadataFilter,
+    oversampling: usize,
+}
+
+impl<I: VectorStoreIndex> FilteredIndex<I> {
+    pub fn new(index: I, filter: MetadataFilter) -> Self {
+        Self {
+            index,
+            filter,
+            oversampling: 4,
+        }
+    }
+
+    /// Set the number of documents searched per requested document (defaults to 4). Fewer
+    /// than the requested number of documents are returned when too few of them match.
+    pub fn oversampling(mut self, oversampling: usize) -> Self {
+        self.oversampling = oversampling.max(1);
+        self
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, Value)>, VectorStoreError> {
+        Ok(self
+            .index
+            .top_n::<Value>(query, n.saturating_mul(self.oversampling))
+            .await?
+            .into_iter()
+            .filter(|(_, _, document)| {
+                document
+                    .get("metadata")
+                    .and_then(|metadata| DocumentMetadata::deserialize(metadata).ok())
+                    .is_some_and(|metadata| self.filter.matches(&metadata))
+            })
+            .take(n)
+            .collect())
+    }
+}
+
+impl<I: VectorStoreIndex> VectorStoreIndex for FilteredIndex<I> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        self.search(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, id, document)| Ok((score, id, serde_json::from_value(document)?)))
+            .collect()
+    }
+
+    /// The documents are needed to read their metadata, so this is as costly as `top_n`.
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(self
+            .search(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, id, _)| (score, id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metadata::Document, test_utils::MockIndex};
+
+    #[tokio::test]
+    async fn test_filtered_index() {
+        let document = |id: &str, tag: &str| {
+            (
+                id.to_string(),
+                Document::new(DocumentMetadata::new(id).tag(tag), "text"),
+            )
+        };
+        let index = MockIndex::new([
+            document("a", "blog"),
+            document("b", "docs"),
+            document("c", "blog"),
+            document("d", "docs"),
+            document("e", "docs"),
+        ]);
+
+        let index = FilteredIndex::new(index, MetadataFilter::Tag("docs".into())).oversampling(2);
+
+        // Only the 4 first documents are searched
+        let results = index.top_n::<Document>("query", 2).await.unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(_, id, doc)| (id.as_str(), doc.metadata.tags[0].as_str()))
+                .collect::<Vec<_>>(),
+            vec![("b", "docs"), ("d", "docs")]
+        );
+
+        let ids = index.top_n_ids("query", 1).await.unwrap();
+        assert_eq!(ids, vec![(1.0, "b".to_string())]);
+    }
+}
@@ -2,14 +2,21 @@
 use std::{
     cmp::Reverse,
     collections::{BinaryHeap, HashMap},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
 };
 
+use futures::{stream::BoxStream, StreamExt};
 use ordered_float::OrderedFloat;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use super::{VectorStoreError, VectorStoreIndex};
+use super::{
+    migration::ExportedDocument, DocumentStore, VectorStoreDelete, VectorStoreError,
+    VectorStoreExport, VectorStoreImport, VectorStoreIndex,
+};
 use crate::{
-    embeddings::{distance::VectorDistance, Embedding, EmbeddingModel},
+    embeddings::{distance, Embedding, EmbeddingModel},
     OneOrMany,
 };
 
@@ -77,7 +84,10 @@ impl<D: Serialize + Eq> InMemoryVectorStore<D> {
                 .iter()
                 .map(|embedding| {
                     (
-                        OrderedFloat(embedding.cosine_similarity(prompt_embedding, false)),
+                        OrderedFloat(distance::cosine_similarity(
+                            &embedding.vec,
+                            &prompt_embedding.vec,
+                        )),
                         &embedding.document,
                     )
                 })
@@ -191,6 +201,65 @@ impl<D: Serialize> InMemoryVectorStore<D> {
     pub fn is_empty(&self) -> bool {
         self.embeddings.is_empty()
     }
+
+    /// Save the store to a JSON Lines file, with one line per document containing its id,
+    /// the document itself and its embeddings. The file is overwritten if it already exists.
+    ///
+    /// The store can be loaded back with [InMemoryVectorStore::load_from_path].
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<(), VectorStoreError> {
+        let mut writer = BufWriter::new(File::create(path).map_err(io_to_rig_error)?);
+
+        let mut entries = self.embeddings.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (id, (document, embeddings)) in entries {
+            serde_json::to_writer(
+                &mut writer,
+                &StoredDocument {
+                    id,
+                    document,
+                    embeddings,
+                },
+            )?;
+            writer.write_all(b"\n").map_err(io_to_rig_error)?;
+        }
+
+        writer.flush().map_err(io_to_rig_error)
+    }
+}
+
+impl<D: Serialize + DeserializeOwned> InMemoryVectorStore<D> {
+    /// Load a store previously saved with [InMemoryVectorStore::save_to_path].
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, VectorStoreError> {
+        let reader = BufReader::new(File::open(path).map_err(io_to_rig_error)?);
+
+        let mut embeddings = HashMap::new();
+        for line in reader.lines() {
+            let line = line.map_err(io_to_rig_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let stored: StoredDocument<String, D, OneOrMany<Embedding>> =
+                serde_json::from_str(&line)?;
+            embeddings.insert(stored.id, (stored.document, stored.embeddings));
+        }
+
+        Ok(Self { embeddings })
+    }
+}
+
+/// A line of a file written by [InMemoryVectorStore::save_to_path].
+/// Generic over the field types so that it can be serialized from references.
+#[derive(Serialize, Deserialize)]
+struct StoredDocument<I, D, E> {
+    id: I,
+    document: D,
+    embeddings: E,
+}
+
+fn io_to_rig_error(e: std::io::Error) -> VectorStoreError {
+    VectorStoreError::DatastoreError(Box::new(e))
 }
 
 pub struct InMemoryVectorIndex<M: EmbeddingModel, D: Serialize> {
@@ -216,16 +285,13 @@ impl<M: EmbeddingModel, D: Serialize> InMemoryVectorIndex<M, D> {
     }
 }
 
-impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
-    for InMemoryVectorIndex<M, D>
-{
-    async fn top_n<T: for<'a> Deserialize<'a>>(
+impl<M: EmbeddingModel, D: Serialize + Eq> InMemoryVectorIndex<M, D> {
+    /// Get the `n` best documents for the embedding of a query.
+    fn search<T: for<'a> Deserialize<'a>>(
         &self,
-        query: &str,
+        prompt_embedding: &Embedding,
         n: usize,
     ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
-        let prompt_embedding = &self.model.embed_text(query).await?;
-
         let docs = self.store.vector_search(prompt_embedding, n);
 
         // Return n best
@@ -242,6 +308,20 @@ impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
             })
             .collect::<Result<Vec<_>, _>>()
     }
+}
+
+impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
+    for InMemoryVectorIndex<M, D>
+{
+    async fn top_n<T: for<'a> Deserialize<'a>>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let prompt_embedding = &self.model.embed_text(query).await?;
+
+        self.search(prompt_embedding, n)
+    }
 
     async fn top_n_ids(
         &self,
@@ -257,13 +337,112 @@ impl<M: EmbeddingModel + Sync, D: Serialize + Sync + Send + Eq> VectorStoreIndex
             .map(|Reverse(RankingItem(distance, id, _, _))| Ok((distance.0, id.clone())))
             .collect::<Result<Vec<_>, _>>()
     }
+
+    /// Embed all the queries in as few calls to the embedding model as possible.
+    async fn top_n_batch<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        queries: Vec<String>,
+        n: usize,
+    ) -> Result<Vec<Vec<(f64, String, T)>>, VectorStoreError> {
+        let mut prompt_embeddings = Vec::with_capacity(queries.len());
+        for batch in queries.chunks(M::MAX_DOCUMENTS.max(1)) {
+            prompt_embeddings.extend(self.model.embed_texts(batch.to_vec()).await?);
+        }
+
+        prompt_embeddings
+            .iter()
+            .map(|prompt_embedding| self.search(prompt_embedding, n))
+            .collect()
+    }
+}
+
+impl<D: Serialize + Send + Sync> DocumentStore for InMemoryVectorStore<D> {
+    async fn get_documents<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<(String, T)>, VectorStoreError> {
+        ids.iter()
+            .filter_map(|id| self.embeddings.get(id).map(|(doc, _)| (id, doc)))
+            .map(|(id, doc)| {
+                Ok((
+                    id.clone(),
+                    serde_json::from_value(serde_json::to_value(doc)?)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl<M: EmbeddingModel, D: Serialize + Send + Sync> DocumentStore for InMemoryVectorIndex<M, D> {
+    async fn get_documents<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<(String, T)>, VectorStoreError> {
+        self.store.get_documents(ids).await
+    }
+}
+
+impl<D: Serialize + Clone + Send + Sync> VectorStoreExport<D> for InMemoryVectorStore<D> {
+    fn export(
+        &self,
+        batch_size: usize,
+    ) -> BoxStream<'_, Result<Vec<ExportedDocument<D>>, VectorStoreError>> {
+        let mut documents = self
+            .embeddings
+            .iter()
+            .map(|(id, (doc, embeddings))| (id.clone(), doc.clone(), embeddings.clone()))
+            .collect::<Vec<_>>();
+        documents.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let batches = documents
+            .chunks(batch_size.max(1))
+            .map(|batch| Ok(batch.to_vec()))
+            .collect::<Vec<_>>();
+
+        futures::stream::iter(batches).boxed()
+    }
+}
+
+impl<D: Serialize + Send> VectorStoreImport<D> for InMemoryVectorStore<D> {
+    async fn import(
+        &mut self,
+        documents: Vec<ExportedDocument<D>>,
+    ) -> Result<(), VectorStoreError> {
+        for (id, doc, embeddings) in documents {
+            self.embeddings.insert(id, (doc, embeddings));
+        }
+        Ok(())
+    }
+}
+
+impl<D: Serialize + Send> VectorStoreDelete for InMemoryVectorStore<D> {
+    async fn delete(&mut self, ids: Vec<String>) -> Result<(), VectorStoreError> {
+        for id in ids {
+            self.embeddings.remove(&id);
+        }
+        Ok(())
+    }
+}
+
+impl<M: EmbeddingModel, D: Serialize + Clone + Send + Sync> VectorStoreExport<D>
+    for InMemoryVectorIndex<M, D>
+{
+    fn export(
+        &self,
+        batch_size: usize,
+    ) -> BoxStream<'_, Result<Vec<ExportedDocument<D>>, VectorStoreError>> {
+        self.store.export(batch_size)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::cmp::Reverse;
 
-    use crate::{embeddings::embedding::Embedding, OneOrMany};
+    use crate::{
+        embeddings::embedding::Embedding, test_utils::MockEmbeddingModel,
+        vector_store::VectorStoreIndex, OneOrMany,
+    };
 
     use super::{InMemoryVectorStore, RankingItem};
 
@@ -370,6 +549,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_save_and_load() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let path = temp.path().join("store.jsonl");
+
+        let store = InMemoryVectorStore::from_documents_with_ids(vec![
+            (
+                "a",
+                "glarb-glarb",
+                OneOrMany::one(Embedding {
+                    document: "glarb-glarb".to_string(),
+                    vec: vec![0.1, 0.2],
+                }),
+            ),
+            (
+                "b",
+                "flurbo",
+                OneOrMany::many(vec![
+                    Embedding {
+                        document: "flurbo".to_string(),
+                        vec: vec![0.3, 0.4],
+                    },
+                    Embedding {
+                        document: "green alien".to_string(),
+                        vec: vec![0.5, 0.6],
+                    },
+                ])
+                .unwrap(),
+            ),
+        ]);
+        store.save_to_path(&path).unwrap();
+
+        let loaded = InMemoryVectorStore::<String>::load_from_path(&path).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded.get_document::<String>("a").unwrap(),
+            Some("glarb-glarb".to_string())
+        );
+        let (_, embeddings) = loaded.embeddings.get("b").unwrap();
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(embeddings.rest()[0].document, "green alien");
+        assert_eq!(embeddings.rest()[0].vec, vec![0.5, 0.6]);
+    }
+
     #[test]
     fn test_single_embedding() {
         let vector_store = InMemoryVectorStore::from_documents_with_ids(vec![
@@ -502,4 +726,35 @@ mod tests {
             )]
         )
     }
+
+    #[tokio::test]
+    async fn test_top_n_batch() {
+        let model = MockEmbeddingModel::counting(&["a", "b"]);
+        let embedding = |vec: Vec<f64>| {
+            OneOrMany::one(Embedding {
+                document: String::new(),
+                vec,
+            })
+        };
+        let index = InMemoryVectorStore::from_documents_with_ids(vec![
+            ("doc1", "aaa".to_string(), embedding(vec![1.0, 0.0])),
+            ("doc2", "bbb".to_string(), embedding(vec![0.0, 1.0])),
+        ])
+        .index(model.clone());
+
+        let results = index
+            .top_n_batch::<String>(vec!["a".into(), "bb".into(), "aab".into()], 1)
+            .await
+            .unwrap();
+
+        assert_eq!(model.calls(), 1);
+        assert_eq!(
+            results
+                .iter()
+                .map(|results| results[0].1.as_str())
+                .collect::<Vec<_>>(),
+            vec!["doc1", "doc2", "doc1"]
+        );
+        assert_eq!(results[1][0].2, "bbb");
+    }
 }
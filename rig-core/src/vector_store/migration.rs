@@ -0,0 +1,152 @@
+//! Utilities to move documents and their embeddings from one vector store to another
+//! without having to re-embed them.
+//!
+//! A store can be used as the source of a migration if it implements [VectorStoreExport]
+//! and as the target of a migration if it implements [VectorStoreImport].
+//!
+//! # Example
+//! ```rust
+//! use rig::vector_store::{in_memory_store::InMemoryVectorStore, migrate};
+//!
+//! # async fn run(source: InMemoryVectorStore<String>) -> Result<(), rig::vector_store::VectorStoreError> {
+//! let mut target = InMemoryVectorStore::<String>::default();
+//!
+//! migrate(&source, &mut target, 100, |progress| {
+//!     println!("Migrated {} documents", progress.documents);
+//! })
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::{stream::BoxStream, StreamExt};
+
+use super::VectorStoreError;
+use crate::{embeddings::Embedding, OneOrMany};
+
+/// A document, its id and its embeddings, as exported from (or imported into) a vector store.
+pub type ExportedDocument<D> = (String, D, OneOrMany<Embedding>);
+
+/// Trait for vector stores whose documents and embeddings can be exported.
+pub trait VectorStoreExport<D>: Send + Sync {
+    /// Stream all the documents of the store, along with their ids and embeddings,
+    /// in batches of at most `batch_size` documents.
+    fn export(
+        &self,
+        batch_size: usize,
+    ) -> BoxStream<'_, Result<Vec<ExportedDocument<D>>, VectorStoreError>>;
+}
+
+/// Trait for vector stores into which documents with precomputed embeddings can be imported.
+pub trait VectorStoreImport<D>: Send {
+    /// Insert the documents into the store, keeping their ids and embeddings.
+    fn import(
+        &mut self,
+        documents: Vec<ExportedDocument<D>>,
+    ) -> impl std::future::Future<Output = Result<(), VectorStoreError>> + Send;
+}
+
+/// Progress of a migration, reported after each imported batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Number of batches imported so far.
+    pub batches: usize,
+    /// Number of documents imported so far.
+    pub documents: usize,
+}
+
+/// Copy all documents and embeddings from `source` into `target`, `batch_size` documents at a time.
+/// `on_progress` is called after each imported batch.
+///
+/// The migration stops at the first error. Batches imported before the error are not rolled back.
+pub async fn migrate<D, S, T>(
+    source: &S,
+    target: &mut T,
+    batch_size: usize,
+    mut on_progress: impl FnMut(MigrationProgress),
+) -> Result<MigrationProgress, VectorStoreError>
+where
+    S: VectorStoreExport<D>,
+    T: VectorStoreImport<D>,
+{
+    let mut progress = MigrationProgress::default();
+    let mut batches = source.export(batch_size.max(1));
+
+    while let Some(batch) = batches.next().await {
+        let batch = batch?;
+        let batch_len = batch.len();
+
+        target.import(batch).await?;
+
+        progress.batches += 1;
+        progress.documents += batch_len;
+        on_progress(progress);
+    }
+
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        embeddings::Embedding, vector_store::in_memory_store::InMemoryVectorStore, OneOrMany,
+    };
+
+    use super::{migrate, MigrationProgress};
+
+    fn embedding(document: &str, vec: Vec<f64>) -> OneOrMany<Embedding> {
+        OneOrMany::one(Embedding {
+            document: document.to_string(),
+            vec,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_migrate_in_batches() {
+        let source = InMemoryVectorStore::from_documents_with_ids(vec![
+            (
+                "a",
+                "glarb-glarb".to_string(),
+                embedding("glarb-glarb", vec![0.1, 0.2]),
+            ),
+            (
+                "b",
+                "flurbo".to_string(),
+                embedding("flurbo", vec![0.3, 0.4]),
+            ),
+            (
+                "c",
+                "linglingdong".to_string(),
+                embedding("linglingdong", vec![0.5, 0.6]),
+            ),
+        ]);
+        let mut target = InMemoryVectorStore::<String>::default();
+
+        let mut reported = vec![];
+        let progress = migrate(&source, &mut target, 2, |progress| reported.push(progress))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            reported,
+            vec![
+                MigrationProgress {
+                    batches: 1,
+                    documents: 2
+                },
+                MigrationProgress {
+                    batches: 2,
+                    documents: 3
+                },
+            ]
+        );
+        assert_eq!(progress.documents, 3);
+
+        assert_eq!(target.len(), 3);
+        assert_eq!(
+            target.get_document::<String>("b").unwrap(),
+            Some("flurbo".to_string())
+        );
+        let (_, (_, embeddings)) = target.iter().find(|(id, _)| *id == "c").unwrap();
+        assert_eq!(embeddings.first().vec, vec![0.5, 0.6]);
+    }
+}
@@ -1,10 +1,19 @@
+use std::collections::HashMap;
+
 use futures::future::BoxFuture;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::embeddings::EmbeddingError;
+use crate::{completion::PromptError, embeddings::EmbeddingError};
 
+pub mod filter;
 pub mod in_memory_store;
+pub mod migration;
+pub mod parent_document;
+pub mod query_transform;
+pub mod recency;
+
+pub use migration::{migrate, MigrationProgress, VectorStoreExport, VectorStoreImport};
 
 #[derive(Debug, thiserror::Error)]
 pub enum VectorStoreError {
@@ -20,6 +29,10 @@ pub enum VectorStoreError {
 
     #[error("Missing Id: {0}")]
     MissingIdError(String),
+
+    /// Error rewriting the query with a model (see [query_transform])
+    #[error("Query transformation error: {0}")]
+    QueryTransformError(#[from] PromptError),
 }
 
 /// Trait for vector store indexes
@@ -38,10 +51,105 @@ pub trait VectorStoreIndex: Send + Sync {
         query: &str,
         n: usize,
     ) -> impl std::future::Future<Output = Result<Vec<(f64, String)>, VectorStoreError>> + Send;
+
+    /// Get the top n documents for each of the given queries, in the order of the queries.
+    ///
+    /// The default implementation searches the queries concurrently with `top_n`. Indexes
+    /// embedding the queries themselves should override it to embed all the queries in one
+    /// call to the embedding model.
+    fn top_n_batch<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        queries: Vec<String>,
+        n: usize,
+    ) -> impl std::future::Future<Output = TopNBatchResults<T>> + Send {
+        async move {
+            futures::future::try_join_all(queries.iter().map(|query| self.top_n::<T>(query, n)))
+                .await
+        }
+    }
+}
+
+/// Trait for vector store indexes that can combine semantic (vector) search with
+/// keyword (full-text) search in a single query.
+///
+/// How the two scores are combined (e.g.: weighted sum, reciprocal rank fusion) is up to the
+/// implementation, so scores returned by `hybrid_top_n` should only be compared with scores
+/// returned by the same index.
+pub trait HybridSearchIndex: VectorStoreIndex {
+    /// Get the top n documents based on both the distance to the given query and the keyword
+    /// relevance of the documents to the given query.
+    /// The result is a list of tuples of the form (score, id, document)
+    fn hybrid_top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String, T)>, VectorStoreError>> + Send;
+
+    /// Same as `hybrid_top_n` but returns the document ids only.
+    fn hybrid_top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<(f64, String)>, VectorStoreError>> + Send;
+}
+
+/// Trait for vector store indexes whose documents can be partitioned into namespaces
+/// (e.g.: one namespace per tenant of a multi-tenant application).
+///
+/// Each backend maps namespaces onto its own isolation mechanism (e.g.: a Qdrant collection,
+/// a filter on a MongoDB field or an Elasticsearch index), so that searches and insertions
+/// made through a namespaced index never see the documents of another namespace.
+pub trait NamespacedVectorStoreIndex: VectorStoreIndex {
+    /// The index type returned by [NamespacedVectorStoreIndex::namespace].
+    type Namespaced: VectorStoreIndex;
+
+    /// Return a copy of the index that is scoped to the given namespace.
+    fn namespace(&self, namespace: &str) -> Self::Namespaced;
+}
+
+/// Trait for stores that can retrieve documents by id (e.g.: the parent documents of the
+/// chunks of an index, see [parent_document]).
+pub trait DocumentStore: Send + Sync {
+    /// Get the documents with the given ids. Unknown ids are skipped.
+    /// The result is a list of tuples of the form (id, document)
+    fn get_documents<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        ids: &[String],
+    ) -> impl std::future::Future<Output = Result<Vec<(String, T)>, VectorStoreError>> + Send;
+}
+
+/// Trait for vector stores whose documents can be deleted by id (e.g.: by the
+/// [Indexer](crate::indexer::Indexer), when their source documents change or are removed).
+pub trait VectorStoreDelete: Send {
+    /// Delete the documents with the given ids. Unknown ids are skipped.
+    fn delete(
+        &mut self,
+        ids: Vec<String>,
+    ) -> impl std::future::Future<Output = Result<(), VectorStoreError>> + Send;
+}
+
+impl<D: Serialize + Send + Sync> DocumentStore for HashMap<String, D> {
+    async fn get_documents<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<(String, T)>, VectorStoreError> {
+        ids.iter()
+            .filter_map(|id| self.get(id).map(|doc| (id, doc)))
+            .map(|(id, doc)| {
+                Ok((
+                    id.clone(),
+                    serde_json::from_value(serde_json::to_value(doc)?)?,
+                ))
+            })
+            .collect()
+    }
 }
 
 pub type TopNResults = Result<Vec<(f64, String, Value)>, VectorStoreError>;
 
+/// The results of [VectorStoreIndex::top_n_batch], one list of results per query.
+pub type TopNBatchResults<T = Value> = Result<Vec<Vec<(f64, String, T)>>, VectorStoreError>;
+
 pub trait VectorStoreIndexDyn: Send + Sync {
     fn top_n<'a>(&'a self, query: &'a str, n: usize) -> BoxFuture<'a, TopNResults>;
 
@@ -50,6 +158,8 @@ pub trait VectorStoreIndexDyn: Send + Sync {
         query: &'a str,
         n: usize,
     ) -> BoxFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>>;
+
+    fn top_n_batch(&self, queries: Vec<String>, n: usize) -> BoxFuture<'_, TopNBatchResults>;
 }
 
 impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
@@ -75,6 +185,24 @@ impl<I: VectorStoreIndex> VectorStoreIndexDyn for I {
     ) -> BoxFuture<'a, Result<Vec<(f64, String)>, VectorStoreError>> {
         Box::pin(self.top_n_ids(query, n))
     }
+
+    fn top_n_batch(&self, queries: Vec<String>, n: usize) -> BoxFuture<'_, TopNBatchResults> {
+        Box::pin(async move {
+            Ok(self
+                .top_n_batch::<serde_json::Value>(queries, n)
+                .await?
+                .into_iter()
+                .map(|results| {
+                    results
+                        .into_iter()
+                        .map(|(score, id, doc)| {
+                            (score, id, prune_document(doc).unwrap_or_default())
+                        })
+                        .collect()
+                })
+                .collect())
+        })
+    }
 }
 
 fn prune_document(document: serde_json::Value) -> Option<serde_json::Value> {
@@ -0,0 +1,213 @@
+//! This module implements parent-document retrieval: small chunks of the documents are
+//! embedded, which makes the search precise, but the documents they belong to (their parents)
+//! are returned, which gives the model enough context to answer.
+//!
+//! Each chunk stored in the index links to its parent with the
+//! [parent_id](crate::metadata::DocumentMetadata::parent_id) of its metadata (e.g.: the chunks
+//! of [Document::chunks](crate::metadata::Document::chunks)), and the parents are fetched by id
+//! from a [DocumentStore]. To return windows of text rather than whole documents, split the
+//! documents into large windows (the parents), and the windows into small chunks.
+//!
+//! # Example
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use rig::{
+//!     embeddings::EmbeddingsBuilder,
+//!     metadata::{Document, DocumentMetadata},
+//!     providers::openai,
+//!     vector_store::{in_memory_store::InMemoryVectorStore, parent_document::ParentDocumentIndex},
+//! };
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let openai = openai::Client::from_env();
+//! let model = openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+//!
+//! let parents = [
+//!     Document::new(DocumentMetadata::new("report"), "<long report>"),
+//!     Document::new(DocumentMetadata::new("minutes"), "<long meeting minutes>"),
+//! ];
+//! let chunks = parents
+//!     .iter()
+//!     .flat_map(|parent| parent.chunks(400, 50))
+//!     .collect::<Vec<_>>();
+//!
+//! let embeddings = EmbeddingsBuilder::new(model.clone())
+//!     .documents(chunks)?
+//!     .build()
+//!     .await?;
+//! let chunk_index = InMemoryVectorStore::from_documents_with_id_f(embeddings, |chunk| {
+//!     chunk.metadata.id.clone()
+//! })
+//! .index(model);
+//!
+//! // Search the chunks, return the parents
+//! let parents = parents
+//!     .into_iter()
+//!     .map(|parent| (parent.metadata.id.clone(), parent))
+//!     .collect::<HashMap<_, _>>();
+//! let index = ParentDocumentIndex::new(chunk_index, parents);
+//! let agent = openai.agent(openai::GPT_4O).dynamic_context(2, index).build();
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use super::{DocumentStore, VectorStoreError, VectorStoreIndex};
+
+/// The link of a chunk to its parent, in its metadata. Other fields of the chunks are ignored.
+#[derive(Deserialize)]
+struct ParentLink {
+    metadata: ParentLinkMetadata,
+}
+
+#[derive(Deserialize)]
+struct ParentLinkMetadata {
+    parent_id: Option<String>,
+}
+
+/// A vector store index searching the chunks of an index, and returning the parent documents
+/// of the best chunks. See the [module documentation](self).
+///
+/// The score of a parent is the score of its best chunk, and its id is the parent id.
+pub struct ParentDocumentIndex<I, S> {
+    chunks: I,
+    parents: S,
+    oversampling: usize,
+}
+
+impl<I: VectorStoreIndex, S: DocumentStore> ParentDocumentIndex<I, S> {
+    /// Create a new parent-document index from the index of the chunks, whose metadata must
+    /// have a `parent_id`, and the store of the parents.
+    pub fn new(chunks: I, parents: S) -> Self {
+        Self {
+            chunks,
+            parents,
+            oversampling: 4,
+        }
+    }
+
+    /// Set the number of chunks searched per requested parent (defaults to 4), since several
+    /// of the best chunks may belong to the same parent.
+    pub fn oversampling(mut self, oversampling: usize) -> Self {
+        self.oversampling = oversampling.max(1);
+        self
+    }
+
+    /// Search the chunks and return the ids of the `n` best parents with their scores.
+    async fn parent_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let chunks = self
+            .chunks
+            .top_n::<ParentLink>(query, n.saturating_mul(self.oversampling))
+            .await?;
+
+        // The chunks are sorted by score, so the first chunk of each parent is its best
+        let mut seen = HashSet::new();
+        let mut parents = vec![];
+        for (score, id, chunk) in chunks {
+            if parents.len() == n {
+                break;
+            }
+            let Some(parent_id) = chunk.metadata.parent_id else {
+                tracing::warn!("Chunk without parent id: {}", id);
+                continue;
+            };
+            if seen.insert(parent_id.clone()) {
+                parents.push((score, parent_id));
+            }
+        }
+        Ok(parents)
+    }
+}
+
+impl<I: VectorStoreIndex, S: DocumentStore> VectorStoreIndex for ParentDocumentIndex<I, S> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let parent_ids = self.parent_ids(query, n).await?;
+        let ids = parent_ids
+            .iter()
+            .map(|(_, id)| id.clone())
+            .collect::<Vec<_>>();
+
+        let mut parents = self
+            .parents
+            .get_documents::<T>(&ids)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        Ok(parent_ids
+            .into_iter()
+            .filter_map(|(score, id)| match parents.remove(&id) {
+                Some(parent) => Some((score, id, parent)),
+                None => {
+                    tracing::warn!("Parent document not found in store: {}", id);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        self.parent_ids(query, n).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metadata::{Document, DocumentMetadata},
+        test_utils::MockIndex,
+    };
+
+    #[tokio::test]
+    async fn test_parent_document_index() {
+        let parents = HashMap::from([
+            ("a".to_string(), "flurbo one. flurbo two.".to_string()),
+            ("b".to_string(), "glarb. flurbo three.".to_string()),
+            ("c".to_string(), "flurbo four.".to_string()),
+        ]);
+        let chunks = ["a", "b", "c"]
+            .into_iter()
+            .flat_map(|id| Document::new(DocumentMetadata::new(id), &parents[id]).chunks(12, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(chunks[1].metadata.id, "a#1");
+        assert_eq!(chunks[1].text, "flurbo two.");
+
+        // Returns the chunks containing the query, best first
+        let index = MockIndex::new(
+            chunks
+                .into_iter()
+                .map(|chunk| (chunk.metadata.id.clone(), chunk)),
+        )
+        .matching(|query, _, chunk| chunk["text"].as_str().unwrap().contains(query))
+        .ranked();
+        let index = ParentDocumentIndex::new(index, parents);
+
+        let results = index.top_n::<String>("flurbo", 2).await.unwrap();
+        assert_eq!(
+            results,
+            vec![
+                (1.0, "a".to_string(), "flurbo one. flurbo two.".to_string()),
+                (0.8, "b".to_string(), "glarb. flurbo three.".to_string()),
+            ]
+        );
+
+        let ids = index.top_n_ids("glarb", 2).await.unwrap();
+        assert_eq!(ids, vec![(1.0, "b".to_string())]);
+    }
+}
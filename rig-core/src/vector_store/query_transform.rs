@@ -0,0 +1,320 @@
+//! This module provides [QueryTransformIndex], a vector store index wrapper rewriting the
+//! queries with a model before searching the underlying index:
+//! - [HyDE](QueryTransformIndex::hyde) (Hypothetical Document Embeddings): the model writes a
+//!   hypothetical answer to the query, which is searched instead of the query. Answers tend to
+//!   be closer to the relevant documents than questions are.
+//! - [Multi-query expansion](QueryTransformIndex::multi_query): the model writes alternative
+//!   phrasings of the query, which are all searched. The results are merged with
+//!   [reciprocal rank fusion](reciprocal_rank_fusion).
+//!
+//! Since the wrapper is itself a [VectorStoreIndex], it can be used anywhere an index is
+//! expected, e.g.: as the dynamic context of an agent or in a
+//! [lookup](crate::pipeline::agent_ops::lookup) op.
+//!
+//! # Example
+//! ```rust
+//! use rig::{
+//!     providers::openai,
+//!     vector_store::{in_memory_store::InMemoryVectorStore, query_transform::QueryTransformIndex},
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//! let embedding_model = openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+//! let index = InMemoryVectorStore::<String>::default().index(embedding_model);
+//!
+//! // Search 3 rewrites of each query, along with the original query
+//! let index = QueryTransformIndex::multi_query(index, openai.agent(openai::GPT_4O_MINI).build(), 3);
+//!
+//! let agent = openai
+//!     .agent(openai::GPT_4O)
+//!     .dynamic_context(5, index)
+//!     .build();
+//! # }
+//! ```
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::{VectorStoreError, VectorStoreIndex};
+use crate::completion::Prompt;
+
+/// The constant of reciprocal rank fusion, dampening the weight of the top ranks.
+const RRF_K: f64 = 60.0;
+
+/// How the queries are rewritten, see [QueryTransformIndex].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryTransform {
+    /// Search a hypothetical answer to the query
+    Hyde,
+    /// Search alternative phrasings of the query
+    MultiQuery { queries: usize },
+}
+
+/// A vector store index rewriting the queries with a model before searching the wrapped
+/// index. See the [module documentation](self).
+pub struct QueryTransformIndex<I, P> {
+    index: I,
+    model: P,
+    transform: QueryTransform,
+    prompt: String,
+    include_original: bool,
+}
+
+impl<I: VectorStoreIndex, P: Prompt> QueryTransformIndex<I, P> {
+    /// Search a hypothetical answer to each query, written by the model, instead of the query.
+    pub fn hyde(index: I, model: P) -> Self {
+        Self {
+            index,
+            model,
+            transform: QueryTransform::Hyde,
+            prompt: "Write a short passage answering the following question, as it could appear \
+                in a reference document. Answer with the passage only.\n\nQuestion: {query}"
+                .to_string(),
+            include_original: false,
+        }
+    }
+
+    /// Search `queries` alternative phrasings of each query, written by the model, along with
+    /// the query itself.
+    pub fn multi_query(index: I, model: P, queries: usize) -> Self {
+        Self {
+            index,
+            model,
+            transform: QueryTransform::MultiQuery { queries },
+            prompt: "Write {n} different versions of the following search query, to retrieve \
+                relevant documents from a vector database. Cover different phrasings and \
+                aspects of the query. Answer with one query per line, and nothing else.\
+                \n\nQuery: {query}"
+                .to_string(),
+            include_original: true,
+        }
+    }
+
+    /// Set the prompt used to rewrite the queries. `{query}` is replaced by the query, and
+    /// `{n}` by the number of queries to write (multi-query expansion only).
+    pub fn prompt(mut self, prompt: &str) -> Self {
+        self.prompt = prompt.to_string();
+        self
+    }
+
+    /// Set whether the original query is searched along with the rewritten ones, the results
+    /// being merged with reciprocal rank fusion (defaults to false for HyDE, true for
+    /// multi-query expansion).
+    pub fn include_original(mut self, include_original: bool) -> Self {
+        self.include_original = include_original;
+        self
+    }
+
+    /// Rewrite the query into the list of queries to search.
+    pub async fn transform(&self, query: &str) -> Result<Vec<String>, VectorStoreError> {
+        let n = match self.transform {
+            QueryTransform::Hyde => 1,
+            QueryTransform::MultiQuery { queries } => queries,
+        };
+        let prompt = self
+            .prompt
+            .replace("{query}", query)
+            .replace("{n}", &n.to_string());
+        let response = self.model.prompt(prompt).await?;
+
+        let mut queries = match self.transform {
+            QueryTransform::Hyde => vec![response.trim().to_string()],
+            QueryTransform::MultiQuery { queries } => response
+                .lines()
+                .map(|line| {
+                    // Strip list markers (e.g.: "1.", "-", "*")
+                    line.trim()
+                        .trim_start_matches(|c: char| c.is_ascii_digit())
+                        .trim_start_matches(['.', ')', '-', '*'])
+                        .trim()
+                        .to_string()
+                })
+                .filter(|line| !line.is_empty() && line != query)
+                .take(queries)
+                .collect(),
+        };
+        queries.retain(|q| !q.is_empty());
+
+        if self.include_original || queries.is_empty() {
+            queries.insert(0, query.to_string());
+        }
+        Ok(queries)
+    }
+}
+
+/// Merge ranked lists of results with reciprocal rank fusion: each result scores the sum of
+/// `1 / (60 + rank)` over the lists it appears in. Results are identified by their id, and the
+/// first occurrence of each result is kept.
+pub fn reciprocal_rank_fusion<T>(
+    rankings: impl IntoIterator<Item = Vec<(String, T)>>,
+    n: usize,
+) -> Vec<(f64, String, T)> {
+    let mut fused: HashMap<String, (f64, usize, T)> = HashMap::new();
+    let mut order = 0;
+
+    for ranking in rankings {
+        for (rank, (id, item)) in ranking.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            match fused.get_mut(&id) {
+                Some(entry) => entry.0 += score,
+                None => {
+                    fused.insert(id, (score, order, item));
+                    order += 1;
+                }
+            }
+        }
+    }
+
+    let mut fused = fused
+        .into_iter()
+        .map(|(id, (score, order, item))| (score, order, id, item))
+        .collect::<Vec<_>>();
+    // Ties are broken by order of first appearance
+    fused.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    fused
+        .into_iter()
+        .take(n)
+        .map(|(score, _, id, item)| (score, id, item))
+        .collect()
+}
+
+impl<I: VectorStoreIndex, P: Prompt> VectorStoreIndex for QueryTransformIndex<I, P> {
+    /// Search the rewritten queries. With a single query, the results (and scores) of the
+    /// wrapped index are returned as is, otherwise they are fused.
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let queries = self.transform(query).await?;
+        let mut rankings = self.index.top_n_batch::<T>(queries, n).await?;
+
+        if rankings.len() == 1 {
+            return Ok(rankings.pop().unwrap_or_default());
+        }
+        Ok(reciprocal_rank_fusion(
+            rankings
+                .into_iter()
+                .map(|ranking| ranking.into_iter().map(|(_, id, doc)| (id, doc)).collect()),
+            n,
+        ))
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let queries = self.transform(query).await?;
+        let mut rankings = futures::future::try_join_all(
+            queries.iter().map(|query| self.index.top_n_ids(query, n)),
+        )
+        .await?;
+
+        if rankings.len() == 1 {
+            return Ok(rankings.pop().unwrap_or_default());
+        }
+        Ok(reciprocal_rank_fusion(
+            rankings
+                .into_iter()
+                .map(|ranking| ranking.into_iter().map(|(_, id)| (id, ())).collect()),
+            n,
+        )
+        .into_iter()
+        .map(|(score, id, _)| (score, id))
+        .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        completion::PromptError,
+        message::{Message, Text, UserContent},
+        test_utils::MockIndex,
+    };
+
+    /// Responds with a numbered list of rewrites, or a passage
+    struct MockModel;
+
+    impl Prompt for MockModel {
+        async fn prompt(&self, prompt: impl Into<Message> + Send) -> Result<String, PromptError> {
+            let Message::User { content, .. } = prompt.into() else {
+                unreachable!()
+            };
+            let UserContent::Text(Text { text }) = content.first() else {
+                unreachable!()
+            };
+            if text.starts_with("Write 2") {
+                Ok("1. flurbo meaning\n2) what is a flurbo\n- green alien".into())
+            } else {
+                Ok(" A flurbo is a green alien. ".into())
+            }
+        }
+    }
+
+    /// Returns the documents whose id shares a word with the query, in order
+    fn index() -> MockIndex {
+        MockIndex::new(["alien", "green", "meaning", "what"].map(|id| (id, id)))
+            .matching(|query, id, _| query.contains(id))
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let fused = reciprocal_rank_fusion(
+            vec![
+                vec![("a".to_string(), 1), ("b".to_string(), 2)],
+                vec![("b".to_string(), 3), ("c".to_string(), 4)],
+            ],
+            2,
+        );
+
+        assert_eq!(
+            fused
+                .iter()
+                .map(|(_, id, item)| (id.as_str(), *item))
+                .collect::<Vec<_>>(),
+            vec![("b", 2), ("a", 1)]
+        );
+        assert_eq!(fused[0].0, 1.0 / 62.0 + 1.0 / 61.0);
+    }
+
+    #[tokio::test]
+    async fn test_hyde() {
+        let index = QueryTransformIndex::hyde(index(), MockModel);
+
+        assert_eq!(
+            index.transform("What is a flurbo?").await.unwrap(),
+            vec!["A flurbo is a green alien."]
+        );
+        let results = index.top_n::<String>("What is a flurbo?", 5).await.unwrap();
+        assert_eq!(
+            results,
+            vec![
+                (1.0, "alien".to_string(), "alien".to_string()),
+                (1.0, "green".to_string(), "green".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multi_query() {
+        let index = QueryTransformIndex::multi_query(index(), MockModel, 2);
+
+        assert_eq!(
+            index.transform("flurbo").await.unwrap(),
+            vec!["flurbo", "flurbo meaning", "what is a flurbo"]
+        );
+
+        let ids = index
+            .top_n_ids("flurbo", 5)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["meaning", "what"]);
+    }
+}
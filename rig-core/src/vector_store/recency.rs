@@ -0,0 +1,216 @@
+//! This module provides [RecencyBoostIndex], a vector store index wrapper boosting the scores
+//! of recent documents, e.g.: for RAG over news or chat logs, where a recent document is
+//! usually more relevant than an equally similar old one.
+//!
+//! The age of a document is read from a timestamp field of the document, either a number of
+//! seconds since the UNIX epoch or an RFC 3339 date-time string (e.g.: `2024-01-31T12:00:00Z`).
+//! Its score is multiplied by an exponential decay halving every
+//! [half-life](RecencyBoostIndex::new): `score * (1 - weight + weight * 0.5 ^ (age / half_life))`.
+//!
+//! The wrapped index must return scores where higher is better (e.g.: cosine similarity).
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use rig::{
+//!     providers::openai,
+//!     vector_store::{in_memory_store::InMemoryVectorStore, recency::RecencyBoostIndex},
+//! };
+//!
+//! # fn run() {
+//! let openai = openai::Client::from_env();
+//! let model = openai.embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+//! let index = InMemoryVectorStore::<serde_json::Value>::default().index(model);
+//!
+//! // Documents look like `{"published_at": "2024-01-31T12:00:00Z", "text": "..."}`
+//! let index = RecencyBoostIndex::new(index, "published_at", Duration::from_secs(7 * 86_400))
+//!     .weight(0.5);
+//!
+//! let agent = openai.agent(openai::GPT_4O).dynamic_context(3, index).build();
+//! # }
+//! ```
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{VectorStoreError, VectorStoreIndex};
+use crate::metadata::parse_rfc3339;
+
+/// A vector store index boosting the scores of recent documents. See the
+/// [module documentation](self).
+pub struct RecencyBoostIndex<I> {
+    index: I,
+    field: String,
+    half_life: Duration,
+    weight: f64,
+    oversampling: usize,
+    now: Option<SystemTime>,
+}
+
+impl<I: VectorStoreIndex> RecencyBoostIndex<I> {
+    /// Create a new index boosting recent documents, reading their timestamp from `field`
+    /// (nested fields are separated by dots, e.g.: `metadata.created_at`).
+    pub fn new(index: I, field: &str, half_life: Duration) -> Self {
+        Self {
+            index,
+            field: field.to_string(),
+            half_life,
+            weight: 1.0,
+            oversampling: 3,
+            now: None,
+        }
+    }
+
+    /// Set the weight of the recency in the score, between 0 (no boost) and 1 (the score is
+    /// fully decayed with the age, the default).
+    pub fn weight(mut self, weight: f64) -> Self {
+        self.weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the number of documents searched per requested document (defaults to 3), since
+    /// the boost reorders the results of the wrapped index.
+    pub fn oversampling(mut self, oversampling: usize) -> Self {
+        self.oversampling = oversampling.max(1);
+        self
+    }
+
+    /// Set the time the ages are computed from (defaults to the current time).
+    pub fn now(mut self, now: SystemTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Get the multiplier of the score of a document. Documents without a valid timestamp are
+    /// considered infinitely old.
+    fn boost(&self, document: &Value, now: f64) -> f64 {
+        let timestamp = self
+            .field
+            .split('.')
+            .try_fold(document, |value, key| value.get(key))
+            .and_then(parse_timestamp);
+
+        let decay = match timestamp {
+            Some(timestamp) => {
+                let age = (now - timestamp).max(0.0);
+                0.5f64.powf(age / self.half_life.as_secs_f64().max(f64::MIN_POSITIVE))
+            }
+            None => 0.0,
+        };
+        1.0 - self.weight + self.weight * decay
+    }
+
+    /// Search the wrapped index and rerank the results with the boost.
+    async fn search(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, Value)>, VectorStoreError> {
+        let now = self
+            .now
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        let mut results = self
+            .index
+            .top_n::<Value>(query, n.saturating_mul(self.oversampling))
+            .await?
+            .into_iter()
+            .map(|(score, id, document)| (score * self.boost(&document, now), id, document))
+            .collect::<Vec<_>>();
+
+        // Stable sort, ties keep the order of the wrapped index
+        results.sort_by(|a, b| b.0.total_cmp(&a.0));
+        results.truncate(n);
+        Ok(results)
+    }
+}
+
+/// Parse a timestamp, in seconds since the UNIX epoch or as an RFC 3339 date-time string.
+fn parse_timestamp(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => parse_rfc3339(string),
+        _ => None,
+    }
+}
+
+impl<I: VectorStoreIndex> VectorStoreIndex for RecencyBoostIndex<I> {
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        self.search(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, id, document)| Ok((score, id, serde_json::from_value(document)?)))
+            .collect()
+    }
+
+    /// The documents are needed to read their timestamps, so this is as costly as `top_n`.
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(self
+            .search(query, n)
+            .await?
+            .into_iter()
+            .map(|(score, id, _)| (score, id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_utils::MockIndex;
+
+    #[tokio::test]
+    async fn test_recency_boost() {
+        let day = 86_400;
+        let now = 100 * day;
+        let index = MockIndex::new([
+            ("doc0", json!({ "meta": { "date": now - 2 * day } })),
+            (
+                "doc1",
+                json!({ "meta": { "date": "1970-04-11T00:00:00Z" } }),
+            ),
+            ("doc2", json!({ "meta": {} })),
+        ])
+        .ranked();
+
+        let index = RecencyBoostIndex::new(index, "meta.date", Duration::from_secs(day as u64))
+            .now(UNIX_EPOCH + Duration::from_secs(now as u64));
+
+        // The second document is from the reference day: 0.9 > 1.0 * 0.25 > 0.8 * 0
+        let ids = index.top_n_ids("query", 3).await.unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                (0.9, "doc1".to_string()),
+                (0.25, "doc0".to_string()),
+                (0.0, "doc2".to_string())
+            ]
+        );
+
+        // With half the weight: 0.9 > 1.0 * 0.625 > 0.8 * 0.5
+        let index = index.weight(0.5);
+        let results = index.top_n::<Value>("query", 2).await.unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(score, id, _)| (*score, id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(0.9, "doc1"), (0.625, "doc0")]
+        );
+    }
+}
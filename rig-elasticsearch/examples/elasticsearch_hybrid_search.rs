@@ -0,0 +1,72 @@
+// To run this example:
+//
+// export OPENAI_API_KEY=<YOUR-API-KEY>
+// docker run -p 9200:9200 -e "discovery.type=single-node" -e "xpack.security.enabled=false" docker.elastic.co/elasticsearch/elasticsearch:8.15.0
+// cargo run --release --example elasticsearch_hybrid_search --features rig-core/derive
+
+use rig::{
+    embeddings::{EmbeddingModel, EmbeddingsBuilder},
+    providers::openai::{Client, TEXT_EMBEDDING_ADA_002},
+    vector_store::{HybridSearchIndex, VectorStoreIndex},
+    Embed,
+};
+use rig_elasticsearch::{ElasticsearchClient, SearchParams};
+
+#[derive(Embed, serde::Deserialize, serde::Serialize, Debug)]
+struct Word {
+    id: String,
+    #[embed]
+    definition: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    const INDEX_NAME: &str = "rig-definitions";
+
+    // Initialize OpenAI client.
+    let openai_client = Client::from_env();
+    let model = openai_client.embedding_model(TEXT_EMBEDDING_ADA_002);
+
+    // Create an index storing the definitions for full-text search and their embeddings for kNN search.
+    let client = ElasticsearchClient::new("http://localhost:9200");
+    client
+        .create_index(INDEX_NAME, "definition", "embedding", model.ndims())
+        .await?;
+
+    let documents = EmbeddingsBuilder::new(model.clone())
+        .document(Word {
+            id: "doc0".to_string(),
+            definition: "Definition of a *flurbo*: A flurbo is a green alien that lives on cold planets".to_string(),
+        })?
+        .document(Word {
+            id: "doc1".to_string(),
+            definition: "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.".to_string(),
+        })?
+        .document(Word {
+            id: "doc2".to_string(),
+            definition: "Definition of a *linglingdong*: A term used by inhabitants of the far side of the moon to describe humans.".to_string(),
+        })?
+        .build()
+        .await?;
+
+    let index = client.index(
+        INDEX_NAME,
+        model,
+        SearchParams::new("embedding", "definition").boosts(0.7, 0.3),
+    );
+    index.insert_documents(documents).await?;
+
+    // Pure kNN search
+    let results = index
+        .top_n::<Word>("What do the people of Jiro farm with?", 1)
+        .await?;
+    println!("kNN results: {:?}", results);
+
+    // kNN + BM25 search
+    let results = index
+        .hybrid_top_n::<Word>("What is a linglingdong?", 1)
+        .await?;
+    println!("Hybrid results: {:?}", results);
+
+    Ok(())
+}
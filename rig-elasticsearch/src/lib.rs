@@ -0,0 +1,731 @@
+//! A Rig vector store for Elasticsearch and OpenSearch.
+//!
+//! This crate is a companion crate to the [rig-core crate](https://github.com/0xPlaygrounds/rig).
+//! It provides a vector store implementation that uses an Elasticsearch (or OpenSearch) index
+//! as the underlying datastore. Documents are stored with both their text and their embedding,
+//! which makes it possible to run plain kNN searches through [VectorStoreIndex] as well as
+//! hybrid kNN + BM25 searches through [HybridSearchIndex].
+//!
+//! The crate talks to the cluster through its REST API, so no additional client library is needed.
+//!
+//! ## Example
+//! ```no_run
+//! use rig::{providers::openai, vector_store::HybridSearchIndex};
+//! use rig_elasticsearch::{ElasticsearchClient, SearchParams};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let model = openai::Client::from_env().embedding_model(openai::TEXT_EMBEDDING_ADA_002);
+//!
+//! let client = ElasticsearchClient::new("http://localhost:9200");
+//! client.create_index("definitions", "definition", "embedding", 1536).await?;
+//!
+//! let index = client.index(
+//!     "definitions",
+//!     model,
+//!     SearchParams::new("embedding", "definition").boosts(0.7, 0.3),
+//! );
+//!
+//! let results = index
+//!     .hybrid_top_n::<serde_json::Value>("What is a flurbo?", 3)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use rig::{
+    embeddings::{Embedding, EmbeddingModel},
+    metadata::{rfc3339, MetadataFilter},
+    vector_store::{
+        migration::ExportedDocument, HybridSearchIndex, NamespacedVectorStoreIndex,
+        VectorStoreError, VectorStoreImport, VectorStoreIndex,
+    },
+    Embed, OneOrMany,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// The search engine the client talks to.
+/// Elasticsearch and OpenSearch share most of their REST API but expose kNN search differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// Elasticsearch 8.x, using the top-level `knn` search option.
+    Elasticsearch,
+    /// OpenSearch 2.x with the k-NN plugin, using the `knn` query.
+    OpenSearch,
+}
+
+#[derive(Debug, Clone)]
+enum Auth {
+    ApiKey(String),
+    Basic { username: String, password: String },
+}
+
+/// Client for an Elasticsearch or OpenSearch cluster.
+#[derive(Debug, Clone)]
+pub struct ElasticsearchClient {
+    http_client: reqwest::Client,
+    base_url: String,
+    flavor: Flavor,
+    auth: Option<Auth>,
+}
+
+impl ElasticsearchClient {
+    /// Create a new client for the Elasticsearch cluster at the given url
+    /// (e.g.: `http://localhost:9200`).
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            flavor: Flavor::Elasticsearch,
+            auth: None,
+        }
+    }
+
+    /// Create a new client for the OpenSearch cluster at the given url.
+    pub fn opensearch(base_url: &str) -> Self {
+        Self::new(base_url).flavor(Flavor::OpenSearch)
+    }
+
+    /// Set the search engine flavor of the cluster.
+    pub fn flavor(mut self, flavor: Flavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Authenticate requests with an Elasticsearch API key.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.auth = Some(Auth::ApiKey(api_key.to_string()));
+        self
+    }
+
+    /// Authenticate requests with a username and password.
+    pub fn with_basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.auth = Some(Auth::Basic {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .http_client
+            .request(method, format!("{}/{}", self.base_url, path));
+
+        match &self.auth {
+            Some(Auth::ApiKey(key)) => request.header("Authorization", format!("ApiKey {key}")),
+            Some(Auth::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value, VectorStoreError> {
+        let response = request.send().await.map_err(reqwest_to_rig_error)?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(reqwest_to_rig_error)?;
+
+        if !status.is_success() {
+            return Err(VectorStoreError::DatastoreError(
+                format!("Request failed with status {status}: {body}").into(),
+            ));
+        }
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Create an index that stores the text of the documents in `text_field` (analyzed for
+    /// BM25 full-text search) and their embeddings in `vector_field`, using cosine similarity.
+    ///
+    /// The `metadata` of [Document](rig::metadata::Document)s is mapped so that it can be
+    /// filtered with [SearchParams::metadata_filter]: its identifiers, source, tags and custom
+    /// strings as keywords and its timestamps as dates. Any other field of the inserted
+    /// documents is mapped dynamically by the cluster.
+    pub async fn create_index(
+        &self,
+        index_name: &str,
+        text_field: &str,
+        vector_field: &str,
+        ndims: usize,
+    ) -> Result<(), VectorStoreError> {
+        let metadata_mapping = json!({
+            "properties": {
+                "id": { "type": "keyword" },
+                "parent_id": { "type": "keyword" },
+                "source": { "type": "keyword" },
+                "tags": { "type": "keyword" },
+                "created_at": { "type": "date" },
+                "updated_at": { "type": "date" }
+            }
+        });
+        let dynamic_templates = json!([{
+            "custom_strings": {
+                "path_match": "metadata.custom.*",
+                "match_mapping_type": "string",
+                "mapping": { "type": "keyword" }
+            }
+        }]);
+        let body = match self.flavor {
+            Flavor::Elasticsearch => json!({
+                "mappings": {
+                    "properties": {
+                        text_field: { "type": "text" },
+                        vector_field: {
+                            "type": "dense_vector",
+                            "dims": ndims,
+                            "index": true,
+                            "similarity": "cosine"
+                        },
+                        "metadata": metadata_mapping
+                    },
+                    "dynamic_templates": dynamic_templates
+                }
+            }),
+            Flavor::OpenSearch => json!({
+                "settings": { "index.knn": true },
+                "mappings": {
+                    "properties": {
+                        text_field: { "type": "text" },
+                        vector_field: {
+                            "type": "knn_vector",
+                            "dimension": ndims,
+                            "method": {
+                                "name": "hnsw",
+                                "space_type": "cosinesimil",
+                                "engine": "lucene"
+                            }
+                        },
+                        "metadata": metadata_mapping
+                    },
+                    "dynamic_templates": dynamic_templates
+                }
+            }),
+        };
+
+        self.send(self.request(reqwest::Method::PUT, index_name).json(&body))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get a vector store index on the given (pre-existing) index.
+    pub fn index<M: EmbeddingModel>(
+        &self,
+        index_name: &str,
+        model: M,
+        search_params: SearchParams,
+    ) -> ElasticsearchVectorIndex<M> {
+        ElasticsearchVectorIndex::new(self.clone(), index_name, model, search_params)
+    }
+}
+
+fn reqwest_to_rig_error(e: reqwest::Error) -> VectorStoreError {
+    VectorStoreError::DatastoreError(Box::new(e))
+}
+
+/// Parameters used when searching an [ElasticsearchVectorIndex].
+#[derive(Debug, Clone)]
+pub struct SearchParams {
+    vector_field: String,
+    text_field: String,
+    num_candidates: Option<usize>,
+    filter: Option<Value>,
+    vector_boost: f64,
+    text_boost: f64,
+}
+
+impl SearchParams {
+    /// Create search parameters for an index storing embeddings in `vector_field`
+    /// and the document text in `text_field`.
+    pub fn new(vector_field: &str, text_field: &str) -> Self {
+        Self {
+            vector_field: vector_field.to_string(),
+            text_field: text_field.to_string(),
+            num_candidates: None,
+            filter: None,
+            vector_boost: 1.0,
+            text_boost: 1.0,
+        }
+    }
+
+    /// Number of approximate nearest neighbor candidates considered on each shard.
+    /// Defaults to `10 * n`. Ignored by OpenSearch.
+    pub fn num_candidates(mut self, num_candidates: usize) -> Self {
+        self.num_candidates = Some(num_candidates);
+        self
+    }
+
+    /// Query DSL filter applied to both the kNN and the full-text search,
+    /// e.g.: `json!({"term": {"category": "animals"}})`.
+    pub fn filter(mut self, filter: Value) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Filter the searched documents on their [DocumentMetadata](rig::metadata::DocumentMetadata),
+    /// stored under the `metadata` field (e.g.: [Document](rig::metadata::Document)s, see
+    /// [ElasticsearchClient::create_index] for the expected mapping).
+    /// Replaces the filter set with [SearchParams::filter].
+    pub fn metadata_filter(self, filter: &MetadataFilter) -> Self {
+        self.filter(metadata_query(filter))
+    }
+
+    /// Weights of the kNN score and of the BM25 score in hybrid searches.
+    /// The score of a hit is `vector_boost * knn_score + text_boost * bm25_score`.
+    pub fn boosts(mut self, vector_boost: f64, text_boost: f64) -> Self {
+        self.vector_boost = vector_boost;
+        self.text_boost = text_boost;
+        self
+    }
+
+    /// Build the body of a `_search` request.
+    /// The full-text clause is only added when `hybrid` is true.
+    fn search_body(
+        &self,
+        flavor: Flavor,
+        query: &str,
+        query_vector: &[f64],
+        n: usize,
+        hybrid: bool,
+        with_source: bool,
+    ) -> Value {
+        let SearchParams {
+            vector_field,
+            text_field,
+            num_candidates,
+            filter,
+            vector_boost,
+            text_boost,
+        } = self;
+
+        let vector_boost = if hybrid { *vector_boost } else { 1.0 };
+        let filters = filter.iter().cloned().collect::<Vec<_>>();
+        let text_query = json!({
+            "match": { text_field: { "query": query, "boost": text_boost } }
+        });
+
+        let mut body = match flavor {
+            Flavor::Elasticsearch => {
+                let mut knn = json!({
+                    "field": vector_field,
+                    "query_vector": query_vector,
+                    "k": n,
+                    "num_candidates": num_candidates.unwrap_or(10 * n).max(n),
+                    "boost": vector_boost,
+                });
+                if let Some(filter) = filter {
+                    knn["filter"] = filter.clone();
+                }
+
+                let mut body = json!({ "size": n, "knn": knn });
+                if hybrid {
+                    body["query"] = json!({
+                        "bool": { "must": [text_query], "filter": filters }
+                    });
+                }
+                body
+            }
+            Flavor::OpenSearch => {
+                let mut knn = json!({ "vector": query_vector, "k": n, "boost": vector_boost });
+                if let Some(filter) = filter {
+                    knn["filter"] = filter.clone();
+                }
+                let knn_query = json!({ "knn": { vector_field: knn } });
+
+                let query = if hybrid {
+                    json!({
+                        "bool": { "should": [knn_query, text_query], "filter": filters }
+                    })
+                } else {
+                    knn_query
+                };
+                json!({ "size": n, "query": query })
+            }
+        };
+
+        body["_source"] = if with_source {
+            json!({ "excludes": [vector_field] })
+        } else {
+            json!(false)
+        };
+
+        body
+    }
+}
+
+/// Translate a metadata filter into the equivalent Query DSL filter.
+fn metadata_query(filter: &MetadataFilter) -> Value {
+    let queries =
+        |filters: &[MetadataFilter]| filters.iter().map(metadata_query).collect::<Vec<_>>();
+
+    match filter {
+        MetadataFilter::Tag(tag) => json!({ "term": { "metadata.tags": tag } }),
+        MetadataFilter::SourcePrefix(prefix) => json!({ "prefix": { "metadata.source": prefix } }),
+        MetadataFilter::CreatedAfter(time) => {
+            json!({ "range": { "metadata.created_at": { "gte": rfc3339(*time) } } })
+        }
+        MetadataFilter::CreatedBefore(time) => {
+            json!({ "range": { "metadata.created_at": { "lt": rfc3339(*time) } } })
+        }
+        MetadataFilter::Custom(key, value) => {
+            json!({ "term": { format!("metadata.custom.{key}"): value } })
+        }
+        MetadataFilter::And(filters) => json!({ "bool": { "filter": queries(filters) } }),
+        MetadataFilter::Or(filters) => json!({
+            "bool": { "should": queries(filters), "minimum_should_match": 1 }
+        }),
+        MetadataFilter::Not(filter) => json!({ "bool": { "must_not": [metadata_query(filter)] } }),
+    }
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self::new("embedding", "text")
+    }
+}
+
+/// A vector store index backed by an Elasticsearch or OpenSearch index.
+pub struct ElasticsearchVectorIndex<M: EmbeddingModel> {
+    client: ElasticsearchClient,
+    index_name: String,
+    model: M,
+    search_params: SearchParams,
+}
+
+impl<M: EmbeddingModel> ElasticsearchVectorIndex<M> {
+    pub fn new(
+        client: ElasticsearchClient,
+        index_name: &str,
+        model: M,
+        search_params: SearchParams,
+    ) -> Self {
+        Self {
+            client,
+            index_name: index_name.to_string(),
+            model,
+            search_params,
+        }
+    }
+
+    /// Insert documents and their embeddings into the index using the bulk API.
+    /// One search document is created per embedding, with the embedding stored in the
+    /// vector field of the search params.
+    pub async fn insert_documents<Doc: Serialize + Embed + Send>(
+        &self,
+        documents: Vec<(Doc, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        self.bulk_insert(
+            documents
+                .into_iter()
+                .map(|(document, embeddings)| (None, document, embeddings)),
+        )
+        .await
+    }
+
+    /// Index the documents with the bulk API. When an id is given, documents with a single
+    /// embedding are indexed under that id and documents with several embeddings are indexed
+    /// under `{id}-{i}`, `i` being the index of the embedding.
+    async fn bulk_insert<Doc: Serialize>(
+        &self,
+        documents: impl IntoIterator<Item = (Option<String>, Doc, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        let mut body = String::new();
+
+        for (id, document, embeddings) in documents {
+            let Value::Object(document) = serde_json::to_value(&document)? else {
+                return Err(VectorStoreError::DatastoreError(
+                    "Documents must serialize to JSON objects".into(),
+                ));
+            };
+            let single_embedding = embeddings.len() == 1;
+
+            for (i, embedding) in embeddings.into_iter().enumerate() {
+                let mut document = document.clone();
+                document.insert(
+                    self.search_params.vector_field.clone(),
+                    json!(embedding.vec),
+                );
+
+                let action = match &id {
+                    Some(id) if single_embedding => json!({ "index": { "_id": id } }),
+                    Some(id) => json!({ "index": { "_id": format!("{id}-{i}") } }),
+                    None => json!({ "index": {} }),
+                };
+
+                body.push_str(&action.to_string());
+                body.push('\n');
+                body.push_str(&Value::Object(document).to_string());
+                body.push('\n');
+            }
+        }
+
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .send(
+                self.client
+                    .request(
+                        reqwest::Method::POST,
+                        &format!("{}/_bulk?refresh=wait_for", self.index_name),
+                    )
+                    .header("Content-Type", "application/x-ndjson")
+                    .body(body),
+            )
+            .await?;
+
+        if response["errors"].as_bool().unwrap_or(false) {
+            return Err(VectorStoreError::DatastoreError(
+                format!("Some documents could not be indexed: {response}").into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        n: usize,
+        hybrid: bool,
+        with_source: bool,
+    ) -> Result<Vec<SearchHit>, VectorStoreError> {
+        let embedding = self.model.embed_text(query).await?;
+        let body = self.search_params.search_body(
+            self.client.flavor,
+            query,
+            &embedding.vec,
+            n,
+            hybrid,
+            with_source,
+        );
+
+        let response = self
+            .client
+            .send(
+                self.client
+                    .request(
+                        reqwest::Method::POST,
+                        &format!("{}/_search", self.index_name),
+                    )
+                    .json(&body),
+            )
+            .await?;
+
+        Ok(serde_json::from_value::<SearchResponse>(response)?
+            .hits
+            .hits)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    hits: SearchHits,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchHits {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchHit {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "_score")]
+    score: Option<f64>,
+    #[serde(rename = "_source", default)]
+    source: Value,
+}
+
+fn with_documents<T: for<'a> Deserialize<'a>>(
+    hits: Vec<SearchHit>,
+) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+    hits.into_iter()
+        .map(|hit| {
+            Ok((
+                hit.score.unwrap_or_default(),
+                hit.id,
+                serde_json::from_value(hit.source)?,
+            ))
+        })
+        .collect()
+}
+
+fn ids_only(hits: Vec<SearchHit>) -> Vec<(f64, String)> {
+    hits.into_iter()
+        .map(|hit| (hit.score.unwrap_or_default(), hit.id))
+        .collect()
+}
+
+impl<M: EmbeddingModel> VectorStoreIndex for ElasticsearchVectorIndex<M> {
+    /// Search for the top `n` nearest neighbors of the query using kNN search.
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        with_documents(self.search(query, n, false, true).await?)
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(ids_only(self.search(query, n, false, false).await?))
+    }
+}
+
+impl<M: EmbeddingModel, D: Serialize + Send> VectorStoreImport<D> for ElasticsearchVectorIndex<M> {
+    /// Import documents exported from another vector store (see [rig::vector_store::migrate]).
+    async fn import(
+        &mut self,
+        documents: Vec<ExportedDocument<D>>,
+    ) -> Result<(), VectorStoreError> {
+        self.bulk_insert(
+            documents
+                .into_iter()
+                .map(|(id, document, embeddings)| (Some(id), document, embeddings)),
+        )
+        .await
+    }
+}
+
+impl<M: EmbeddingModel> NamespacedVectorStoreIndex for ElasticsearchVectorIndex<M> {
+    type Namespaced = Self;
+
+    /// Namespaces are mapped to indexes: the returned index searches and inserts documents
+    /// in the index named after the namespace, which must already exist
+    /// (see [ElasticsearchClient::create_index]).
+    fn namespace(&self, namespace: &str) -> Self {
+        Self::new(
+            self.client.clone(),
+            namespace,
+            self.model.clone(),
+            self.search_params.clone(),
+        )
+    }
+}
+
+impl<M: EmbeddingModel> HybridSearchIndex for ElasticsearchVectorIndex<M> {
+    /// Search for the top `n` documents using both kNN search on the vector field and
+    /// BM25 full-text search on the text field, weighted by the boosts of the search params.
+    async fn hybrid_top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        with_documents(self.search(query, n, true, true).await?)
+    }
+
+    async fn hybrid_top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        Ok(ids_only(self.search(query, n, true, false).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use rig::metadata::MetadataFilter;
+    use serde_json::json;
+
+    use super::{Flavor, SearchParams};
+
+    #[test]
+    fn test_elasticsearch_hybrid_body() {
+        let params = SearchParams::new("embedding", "definition")
+            .boosts(0.7, 0.3)
+            .filter(json!({"term": {"lang": "en"}}));
+
+        let body = params.search_body(Flavor::Elasticsearch, "flurbo", &[0.5, 1.0], 2, true, true);
+
+        assert_eq!(
+            body,
+            json!({
+                "size": 2,
+                "knn": {
+                    "field": "embedding",
+                    "query_vector": [0.5, 1.0],
+                    "k": 2,
+                    "num_candidates": 20,
+                    "boost": 0.7,
+                    "filter": {"term": {"lang": "en"}}
+                },
+                "query": {
+                    "bool": {
+                        "must": [{"match": {"definition": {"query": "flurbo", "boost": 0.3}}}],
+                        "filter": [{"term": {"lang": "en"}}]
+                    }
+                },
+                "_source": {"excludes": ["embedding"]}
+            })
+        );
+    }
+
+    #[test]
+    fn test_opensearch_knn_body() {
+        let params = SearchParams::default().boosts(0.7, 0.3);
+
+        let body = params.search_body(Flavor::OpenSearch, "flurbo", &[0.5], 1, false, false);
+
+        assert_eq!(
+            body,
+            json!({
+                "size": 1,
+                "query": {"knn": {"embedding": {"vector": [0.5], "k": 1, "boost": 1.0}}},
+                "_source": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_metadata_filter() {
+        let filter = MetadataFilter::And(vec![
+            MetadataFilter::Tag("rust".to_string()),
+            MetadataFilter::Or(vec![
+                MetadataFilter::SourcePrefix("https://docs.rs/".to_string()),
+                MetadataFilter::Custom("lang".to_string(), json!("en")),
+            ]),
+            MetadataFilter::Not(Box::new(MetadataFilter::CreatedBefore(
+                UNIX_EPOCH + Duration::from_secs(1_706_702_400),
+            ))),
+        ]);
+        let params = SearchParams::default().metadata_filter(&filter);
+
+        assert_eq!(
+            params.filter,
+            Some(json!({
+                "bool": {
+                    "filter": [
+                        {"term": {"metadata.tags": "rust"}},
+                        {
+                            "bool": {
+                                "should": [
+                                    {"prefix": {"metadata.source": "https://docs.rs/"}},
+                                    {"term": {"metadata.custom.lang": "en"}}
+                                ],
+                                "minimum_should_match": 1
+                            }
+                        },
+                        {
+                            "bool": {
+                                "must_not": [
+                                    {"range": {"metadata.created_at": {"lt": "2024-01-31T12:00:00.000Z"}}}
+                                ]
+                            }
+                        }
+                    ]
+                }
+            }))
+        );
+    }
+}
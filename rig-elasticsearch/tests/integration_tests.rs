@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt,
+};
+
+use rig::{
+    embeddings::EmbeddingsBuilder,
+    test_utils::MockEmbeddingModel,
+    vector_store::{HybridSearchIndex, VectorStoreIndex},
+    Embed,
+};
+use rig_elasticsearch::{ElasticsearchClient, SearchParams};
+
+const HTTP_PORT: u16 = 9200;
+const INDEX_NAME: &str = "rig-definitions";
+
+#[derive(Embed, Clone, serde::Deserialize, serde::Serialize, Debug)]
+struct Word {
+    id: String,
+    #[embed]
+    definition: String,
+}
+
+/// Embeds each text along one axis per defined word, plus a constant axis shared by all texts:
+/// a question about a word is closest to its definition, and a question mentioning none of the
+/// words is equally close to all of them.
+fn mock_embedding_model() -> MockEmbeddingModel {
+    MockEmbeddingModel::new(4, |text| {
+        let text = text.to_lowercase();
+        ["flurbo", "glarb-glarb", "linglingdong"]
+            .iter()
+            .map(|word| if text.contains(word) { 1.0 } else { 0.0 })
+            .chain([1.0])
+            .collect()
+    })
+}
+
+/// Wait until the cluster in the container accepts requests and return its url.
+async fn cluster_url(container: &ContainerAsync<GenericImage>) -> String {
+    let port = container.get_host_port_ipv4(HTTP_PORT).await.unwrap();
+    let host = container.get_host().await.unwrap().to_string();
+    let url = format!("http://{host}:{port}");
+
+    let http_client = reqwest::Client::new();
+    for _ in 0..60 {
+        let health = http_client
+            .get(format!(
+                "{url}/_cluster/health?wait_for_status=yellow&timeout=1s"
+            ))
+            .send()
+            .await;
+        if health.is_ok_and(|response| response.status().is_success()) {
+            return url;
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+
+    panic!("The cluster did not start in time");
+}
+
+/// Create an index, insert the definitions and run kNN and hybrid searches on it.
+async fn insert_and_search(client: ElasticsearchClient) {
+    let model = mock_embedding_model();
+
+    client
+        .create_index(INDEX_NAME, "definition", "embedding", 4)
+        .await
+        .unwrap();
+
+    let documents = EmbeddingsBuilder::new(model.clone())
+        .documents(vec![
+            Word {
+                id: "doc0".to_string(),
+                definition: "Definition of a *flurbo*: A flurbo is a green alien that lives on cold planets".to_string(),
+            },
+            Word {
+                id: "doc1".to_string(),
+                definition: "Definition of a *glarb-glarb*: A glarb-glarb is a ancient tool used by the ancestors of the inhabitants of planet Jiro to farm the land.".to_string(),
+            },
+            Word {
+                id: "doc2".to_string(),
+                definition: "Definition of a *linglingdong*: A term used by inhabitants of the far side of the moon to describe humans.".to_string(),
+            },
+        ])
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    let index = client.index(
+        INDEX_NAME,
+        model,
+        SearchParams::new("embedding", "definition").boosts(0.7, 0.3),
+    );
+    index.insert_documents(documents).await.unwrap();
+
+    // kNN search
+    let results = index
+        .top_n::<Word>("What is a linglingdong?", 1)
+        .await
+        .unwrap();
+    let (_, _, word) = results.first().unwrap();
+    assert_eq!(word.id, "doc2");
+
+    let results = index.top_n_ids("What is a flurbo?", 3).await.unwrap();
+    assert_eq!(results.len(), 3);
+
+    // Hybrid search: the query is equally close to all the definitions, BM25 breaks the tie
+    let results = index
+        .hybrid_top_n::<Word>("What is used to farm the land?", 1)
+        .await
+        .unwrap();
+    let (_, _, word) = results.first().unwrap();
+    assert_eq!(word.id, "doc1");
+
+    let results = index
+        .hybrid_top_n_ids("What is used to farm the land?", 3)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 3);
+}
+
+#[tokio::test]
+async fn elasticsearch_insert_and_search_test() {
+    // Setup a local Elasticsearch container for testing. NOTE: docker service must be running.
+    let container = GenericImage::new("docker.elastic.co/elasticsearch/elasticsearch", "8.15.0")
+        .with_wait_for(WaitFor::Duration {
+            length: Duration::from_secs(5),
+        })
+        .with_exposed_port(HTTP_PORT.tcp())
+        .with_env_var("discovery.type", "single-node")
+        .with_env_var("xpack.security.enabled", "false")
+        .with_env_var("ES_JAVA_OPTS", "-Xms512m -Xmx512m")
+        .start()
+        .await
+        .expect("Failed to start Elasticsearch container");
+
+    let url = cluster_url(&container).await;
+
+    insert_and_search(ElasticsearchClient::new(&url)).await;
+}
+
+#[tokio::test]
+async fn opensearch_insert_and_search_test() {
+    // Setup a local OpenSearch container for testing. NOTE: docker service must be running.
+    let container = GenericImage::new("opensearchproject/opensearch", "2.15.0")
+        .with_wait_for(WaitFor::Duration {
+            length: Duration::from_secs(5),
+        })
+        .with_exposed_port(HTTP_PORT.tcp())
+        .with_env_var("discovery.type", "single-node")
+        .with_env_var("DISABLE_SECURITY_PLUGIN", "true")
+        .with_env_var("DISABLE_INSTALL_DEMO_CONFIG", "true")
+        .with_env_var("OPENSEARCH_JAVA_OPTS", "-Xms512m -Xmx512m")
+        .start()
+        .await
+        .expect("Failed to start OpenSearch container");
+
+    let url = cluster_url(&container).await;
+
+    insert_and_search(ElasticsearchClient::opensearch(&url)).await;
+}
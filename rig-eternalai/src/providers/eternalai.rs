@@ -404,6 +404,12 @@ impl TryFrom<CompletionResponse> for completion::CompletionResponse<CompletionRe
 
         Ok(completion::CompletionResponse {
             choice,
+            annotations: vec![],
+            alternatives: vec![],
+            finish_reason: response
+                .choices
+                .first()
+                .map(|choice| choice.finish_reason.as_str().into()),
             raw_response: response,
         })
     }
@@ -49,19 +49,15 @@ async fn main() -> Result<(), anyhow::Error> {
         .execute()
         .await?;
 
-    // See [LanceDB indexing](https://lancedb.github.io/lancedb/concepts/index_ivfpq/#product-quantization) for more information
-    table
-        .create_index(
-            &["embedding"],
-            lancedb::index::Index::IvfPq(IvfPqIndexBuilder::default()),
-        )
-        .execute()
-        .await?;
-
     // Define search_params params that will be used by the vector store to perform the vector search.
     let search_params = SearchParams::default();
     let vector_store_index = LanceDbVectorIndex::new(table, model, "id", search_params).await?;
 
+    // See [LanceDB indexing](https://lancedb.github.io/lancedb/concepts/index_ivfpq/#product-quantization) for more information
+    vector_store_index
+        .create_ivf_pq_index("embedding", IvfPqIndexBuilder::default())
+        .await?;
+
     // Query the index
     let results = vector_store_index
         .top_n::<Word>("My boss says I zindle too much, what does that mean?", 1)
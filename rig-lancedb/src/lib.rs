@@ -1,4 +1,5 @@
 use lancedb::{
+    index::{vector::IvfPqIndexBuilder, Index},
     query::{QueryBase, VectorQuery},
     DistanceType,
 };
@@ -61,6 +62,37 @@ impl<M: EmbeddingModel> LanceDbVectorIndex<M> {
         })
     }
 
+    /// Create an IVF-PQ index on the given embedding column of the table.
+    /// The index is used to perform approximate nearest neighbor (ANN) searches.
+    ///
+    /// ❗IVF-PQ training requires the table to contain enough rows (at least 256 with the default
+    /// number of partitions). For small tables, prefer flat search instead.
+    ///
+    /// See [LanceDB IVF-PQ index](https://lancedb.github.io/lancedb/concepts/index_ivfpq/) for more information.
+    ///
+    /// # Example
+    /// ```
+    /// use lancedb::{index::vector::IvfPqIndexBuilder, DistanceType};
+    ///
+    /// vector_store_index
+    ///     .create_ivf_pq_index(
+    ///         "embedding",
+    ///         IvfPqIndexBuilder::default().distance_type(DistanceType::Cosine),
+    ///     )
+    ///     .await?;
+    /// ```
+    pub async fn create_ivf_pq_index(
+        &self,
+        column: &str,
+        index_builder: IvfPqIndexBuilder,
+    ) -> Result<(), VectorStoreError> {
+        self.table
+            .create_index(&[column], Index::IvfPq(index_builder))
+            .execute()
+            .await
+            .map_err(lancedb_to_rig_error)
+    }
+
     /// Apply the search_params to the vector query.
     /// This is a helper function used by the methods `top_n` and `top_n_ids` of the `VectorStoreIndex` trait.
     fn build_query(&self, mut query: VectorQuery) -> VectorQuery {
@@ -71,6 +103,7 @@ impl<M: EmbeddingModel> LanceDbVectorIndex<M> {
             refine_factor,
             post_filter,
             column,
+            filter,
         } = self.search_params.clone();
 
         if let Some(distance_type) = distance_type {
@@ -90,6 +123,10 @@ impl<M: EmbeddingModel> LanceDbVectorIndex<M> {
             }
         }
 
+        if let Some(filter) = filter {
+            query = query.only_if(filter);
+        }
+
         if let Some(true) = post_filter {
             query = query.postfilter();
         }
@@ -124,6 +161,7 @@ pub struct SearchParams {
     refine_factor: Option<u32>,
     post_filter: Option<bool>,
     column: Option<String>,
+    filter: Option<String>,
 }
 
 impl SearchParams {
@@ -167,6 +205,15 @@ impl SearchParams {
         self
     }
 
+    /// Sets the filter of the search params. The filter is a SQL-like predicate on the columns of
+    /// the table (e.g.: `"category = 'fruit' AND price < 10"`) and is pushed down to LanceDB.
+    /// By default, the filter is applied before the vector search (see [SearchParams::post_filter]).
+    /// See [LanceDb filtering](https://lancedb.github.io/lancedb/sql/) for more information.
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
     /// Sets the column of the search params.
     /// Only set this value if there is more than one column that contains lists of floats.
     /// If there is only one column of list of floats, this column will be chosen for the vector search automatically.
@@ -2,11 +2,16 @@ use serde_json::json;
 
 use arrow_array::RecordBatchIterator;
 use fixture::{as_record_batch, schema, words, Word};
-use lancedb::index::vector::IvfPqIndexBuilder;
+use lancedb::{
+    index::{vector::IvfPqIndexBuilder, IndexType},
+    DistanceType,
+};
 use rig::{
-    embeddings::{EmbeddingModel, EmbeddingsBuilder},
+    embeddings::{Embedding, EmbeddingsBuilder},
     providers::openai,
+    test_utils::MockEmbeddingModel,
     vector_store::VectorStoreIndex,
+    OneOrMany,
 };
 use rig_lancedb::{LanceDbVectorIndex, SearchParams};
 use std::sync::Arc;
@@ -174,3 +179,105 @@ async fn vector_search_test() {
 
     db.drop_db().await.unwrap();
 }
+
+/// Embeds every text as the same vector, so that only the filters select the results.
+fn mock_embedding_model() -> MockEmbeddingModel {
+    MockEmbeddingModel::constant(vec![0.1, 0.2, 0.3, 0.4])
+}
+
+/// A table of `rows` words with distinct embeddings of 4 dimensions.
+async fn mock_table(db: &lancedb::Connection, rows: usize) -> lancedb::Table {
+    let records = (0..rows)
+        .map(|i| {
+            let word = Word {
+                id: format!("doc{}", i),
+                definition: format!("Definition of word {}", i),
+            };
+            let embedding = Embedding {
+                document: word.definition.clone(),
+                vec: vec![i as f64, 1.0, (i % 7) as f64, (i % 3) as f64],
+            };
+            (word, OneOrMany::one(embedding))
+        })
+        .collect();
+
+    db.create_table(
+        "words",
+        RecordBatchIterator::new(vec![as_record_batch(records, 4)], Arc::new(schema(4))),
+    )
+    .execute()
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn filter_test() {
+    let db = lancedb::connect("data/lancedb-filter-store")
+        .execute()
+        .await
+        .unwrap();
+    let table = mock_table(&db, 10).await;
+
+    let search_params = SearchParams::default().filter("id IN ('doc2', 'doc7')");
+    let vector_store_index =
+        LanceDbVectorIndex::new(table, mock_embedding_model(), "id", search_params)
+            .await
+            .unwrap();
+
+    let mut ids = vector_store_index
+        .top_n_ids("anything", 5)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+    ids.sort();
+
+    // Only the rows matching the filter are returned, even though more were requested
+    assert_eq!(ids, vec!["doc2".to_string(), "doc7".to_string()]);
+
+    db.drop_db().await.unwrap();
+}
+
+#[tokio::test]
+async fn create_ivf_pq_index_test() {
+    let db = lancedb::connect("data/lancedb-index-store")
+        .execute()
+        .await
+        .unwrap();
+    // IVF-PQ training requires at least 256 rows
+    let table = mock_table(&db, 300).await;
+
+    let vector_store_index = LanceDbVectorIndex::new(
+        table.clone(),
+        mock_embedding_model(),
+        "id",
+        SearchParams::default().distance_type(DistanceType::Cosine),
+    )
+    .await
+    .unwrap();
+
+    vector_store_index
+        .create_ivf_pq_index(
+            "embedding",
+            IvfPqIndexBuilder::default()
+                .distance_type(DistanceType::Cosine)
+                .num_partitions(2)
+                .num_sub_vectors(2),
+        )
+        .await
+        .unwrap();
+
+    let indices = table.list_indices().await.unwrap();
+    assert_eq!(indices.len(), 1);
+    assert_eq!(indices[0].index_type, IndexType::IvfPq);
+    assert_eq!(indices[0].columns, vec!["embedding".to_string()]);
+
+    // The index can only be created on the columns of the table
+    assert!(vector_store_index
+        .create_ivf_pq_index("missing", IvfPqIndexBuilder::default())
+        .await
+        .is_err());
+
+    db.drop_db().await.unwrap();
+}
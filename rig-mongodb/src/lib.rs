@@ -3,7 +3,12 @@ use mongodb::bson::{self, doc};
 
 use rig::{
     embeddings::embedding::{Embedding, EmbeddingModel},
-    vector_store::{VectorStoreError, VectorStoreIndex},
+    metadata::MetadataFilter,
+    vector_store::{
+        migration::ExportedDocument, NamespacedVectorStoreIndex, VectorStoreError,
+        VectorStoreImport, VectorStoreIndex,
+    },
+    OneOrMany,
 };
 use serde::{Deserialize, Serialize};
 
@@ -99,16 +104,44 @@ pub struct MongoDbVectorIndex<M: EmbeddingModel, C: Send + Sync> {
     index_name: String,
     embedded_field: String,
     search_params: SearchParams,
+    /// Namespace of the index, combined with the filter of the search params and written to the
+    /// namespace field of the inserted documents (see [NamespacedVectorStoreIndex]).
+    namespace: Option<String>,
 }
 
 impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
+    /// Document field holding the namespace of each document.
+    fn namespace_field(&self) -> &str {
+        self.search_params
+            .namespace_field
+            .as_deref()
+            .unwrap_or("namespace")
+    }
+
+    /// Pre-filter of the vector search: the filter of the search params, restricted to the
+    /// namespace of the index (if any).
+    fn filter(&self) -> bson::Document {
+        let filter = self.search_params.filter.clone();
+        match &self.namespace {
+            None => filter,
+            Some(namespace) => {
+                let namespace_filter = doc! { self.namespace_field(): namespace };
+                if filter.is_empty() {
+                    namespace_filter
+                } else {
+                    doc! { "$and": [filter, namespace_filter] }
+                }
+            }
+        }
+    }
+
     /// Vector search stage of aggregation pipeline of mongoDB collection.
     /// To be used by implementations of top_n and top_n_ids methods on VectorStoreIndex trait for MongoDbVectorIndex.
     fn pipeline_search_stage(&self, prompt_embedding: &Embedding, n: usize) -> bson::Document {
         let SearchParams {
-            filter,
             exact,
             num_candidates,
+            ..
         } = &self.search_params;
 
         doc! {
@@ -118,7 +151,7 @@ impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
             "queryVector": &prompt_embedding.vec,
             "numCandidates": num_candidates.unwrap_or((n * 10) as u32),
             "limit": n as u32,
-            "filter": filter,
+            "filter": self.filter(),
             "exact": exact.unwrap_or(false)
           }
         }
@@ -171,17 +204,58 @@ impl<M: EmbeddingModel, C: Send + Sync> MongoDbVectorIndex<M, C> {
             index_name: index_name.to_string(),
             embedded_field,
             search_params,
+            namespace: None,
         })
     }
+
+    /// Insert the documents and their embeddings into the collection: one MongoDB document per
+    /// embedding, stored under the embedded field of the index. Documents inserted through a
+    /// [namespaced](NamespacedVectorStoreIndex::namespace) index are stored in its namespace.
+    pub async fn insert_documents<Doc: Serialize + Send>(
+        &self,
+        documents: Vec<(Doc, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        self.insert(
+            documents
+                .into_iter()
+                .map(|(document, embeddings)| (None, document, embeddings)),
+        )
+        .await
+    }
+
+    async fn insert<Doc: Serialize>(
+        &self,
+        documents: impl IntoIterator<Item = (Option<String>, Doc, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        let documents = mongo_documents(
+            &self.embedded_field,
+            self.namespace
+                .as_deref()
+                .map(|namespace| (self.namespace_field(), namespace)),
+            documents,
+        )?;
+        if documents.is_empty() {
+            return Ok(());
+        }
+
+        self.collection
+            .clone_with_type::<bson::Document>()
+            .insert_many(documents)
+            .await
+            .map_err(mongodb_to_rig_error)?;
+
+        Ok(())
+    }
 }
 
 /// See [MongoDB Vector Search](`https://www.mongodb.com/docs/atlas/atlas-vector-search/vector-search-stage/`) for more information
 /// on each of the fields
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct SearchParams {
     filter: mongodb::bson::Document,
     exact: Option<bool>,
     num_candidates: Option<u32>,
+    namespace_field: Option<String>,
 }
 
 impl SearchParams {
@@ -191,6 +265,7 @@ impl SearchParams {
             filter: doc! {},
             exact: None,
             num_candidates: None,
+            namespace_field: None,
         }
     }
 
@@ -201,6 +276,18 @@ impl SearchParams {
         self
     }
 
+    /// Sets the pre-filter field of the search params to a filter on the
+    /// [DocumentMetadata](rig::metadata::DocumentMetadata) of the documents, stored under their
+    /// `metadata` field (e.g.: [Document](rig::metadata::Document)s). The filtered fields must be
+    /// indexed as `filter` fields of the vector search index.
+    ///
+    /// Vector search pre-filters cannot match string prefixes nor compare the (string)
+    /// timestamps of the metadata: [MetadataFilter::SourcePrefix], [MetadataFilter::CreatedAfter]
+    /// and [MetadataFilter::CreatedBefore] are rejected.
+    pub fn metadata_filter(self, filter: &MetadataFilter) -> Result<Self, VectorStoreError> {
+        Ok(self.filter(metadata_filter(filter)?))
+    }
+
     /// Sets the exact field of the search params.
     /// If exact is true, an ENN vector search will be performed, otherwise, an ANN search will be performed.
     /// By default, exact is false.
@@ -218,6 +305,119 @@ impl SearchParams {
         self.num_candidates = Some(num_candidates);
         self
     }
+
+    /// Sets the document field holding the namespace of each document (defaults to `"namespace"`).
+    /// The field must be indexed as a `filter` field of the vector search index.
+    /// See [NamespacedVectorStoreIndex] for more information.
+    pub fn namespace_field(mut self, namespace_field: &str) -> Self {
+        self.namespace_field = Some(namespace_field.to_string());
+        self
+    }
+}
+
+/// Translate a metadata filter into the equivalent vector search pre-filter.
+fn metadata_filter(filter: &MetadataFilter) -> Result<bson::Document, VectorStoreError> {
+    let filters = |filters: &[MetadataFilter]| {
+        filters
+            .iter()
+            .map(metadata_filter)
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    Ok(match filter {
+        MetadataFilter::Tag(tag) => doc! { "metadata.tags": { "$eq": tag } },
+        MetadataFilter::Custom(key, value) => {
+            let value = bson::to_bson(value)
+                .map_err(|err| VectorStoreError::DatastoreError(Box::new(err)))?;
+            doc! { format!("metadata.custom.{key}"): { "$eq": value } }
+        }
+        MetadataFilter::And(and) => doc! { "$and": filters(and)? },
+        MetadataFilter::Or(or) => doc! { "$or": filters(or)? },
+        MetadataFilter::Not(filter) => doc! { "$nor": [metadata_filter(filter)?] },
+        MetadataFilter::SourcePrefix(_)
+        | MetadataFilter::CreatedAfter(_)
+        | MetadataFilter::CreatedBefore(_) => {
+            return Err(VectorStoreError::DatastoreError(
+                format!("MongoDB vector search cannot pre-filter on {filter:?}").into(),
+            ))
+        }
+    })
+}
+
+/// The MongoDB documents of the inserted documents: one document per embedding, stored under
+/// `embedded_field`, with the given `(field, namespace)` (if any). Documents with an id get it as
+/// `_id` (suffixed with the index of the embedding for documents with several embeddings).
+fn mongo_documents<D: Serialize>(
+    embedded_field: &str,
+    namespace: Option<(&str, &str)>,
+    documents: impl IntoIterator<Item = (Option<String>, D, OneOrMany<Embedding>)>,
+) -> Result<Vec<bson::Document>, VectorStoreError> {
+    let mut inserted = vec![];
+
+    for (id, document, embeddings) in documents {
+        let document = bson::to_document(&document)
+            .map_err(|err| VectorStoreError::DatastoreError(Box::new(err)))?;
+        let single_embedding = embeddings.len() == 1;
+
+        for (i, embedding) in embeddings.into_iter().enumerate() {
+            let mut document = document.clone();
+            match &id {
+                Some(id) if single_embedding => {
+                    document.insert("_id", id);
+                }
+                Some(id) => {
+                    document.insert("_id", format!("{id}-{i}"));
+                }
+                None => (),
+            }
+            document.insert(embedded_field, embedding.vec);
+            if let Some((field, namespace)) = namespace {
+                document.insert(field, namespace);
+            }
+            inserted.push(document);
+        }
+    }
+
+    Ok(inserted)
+}
+
+impl<M: EmbeddingModel + Sync + Send, C: Sync + Send, D: Serialize + Send> VectorStoreImport<D>
+    for MongoDbVectorIndex<M, C>
+{
+    /// Import documents exported from another vector store (see [rig::vector_store::migrate]).
+    /// The ids of the documents must not already exist in the collection.
+    async fn import(
+        &mut self,
+        documents: Vec<ExportedDocument<D>>,
+    ) -> Result<(), VectorStoreError> {
+        self.insert(
+            documents
+                .into_iter()
+                .map(|(id, document, embeddings)| (Some(id), document, embeddings)),
+        )
+        .await
+    }
+}
+
+impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> NamespacedVectorStoreIndex
+    for MongoDbVectorIndex<M, C>
+{
+    type Namespaced = Self;
+
+    /// Namespaces are mapped to a pre-filter on the namespace field of the search params,
+    /// combined with the existing filter (if any). The documents inserted through the returned
+    /// index are stored with the namespace in their namespace field. The namespace replaces the
+    /// namespace of the index, if any.
+    fn namespace(&self, namespace: &str) -> Self {
+        Self {
+            collection: self.collection.clone(),
+            model: self.model.clone(),
+            index_name: self.index_name.clone(),
+            embedded_field: self.embedded_field.clone(),
+            search_params: self.search_params.clone(),
+            namespace: Some(namespace.to_string()),
+        }
+    }
 }
 
 impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorStoreIndex
@@ -311,3 +511,147 @@ impl<M: EmbeddingModel + Sync + Send, C: Sync + Send> VectorStoreIndex
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{self, doc};
+    use rig::{
+        embeddings::Embedding, metadata::MetadataFilter, test_utils::MockEmbeddingModel,
+        vector_store::NamespacedVectorStoreIndex, OneOrMany,
+    };
+    use serde_json::json;
+
+    use super::{mongo_documents, MongoDbVectorIndex, SearchParams};
+
+    #[test]
+    fn test_mongo_documents() {
+        let embedding = |vec: Vec<f64>| Embedding {
+            document: String::new(),
+            vec,
+        };
+        let documents = mongo_documents(
+            "embedding",
+            None,
+            vec![
+                (
+                    Some("doc0".to_string()),
+                    json!({ "word": "flurbo" }),
+                    OneOrMany::one(embedding(vec![0.5, 0.25])),
+                ),
+                (
+                    Some("doc1".to_string()),
+                    json!({ "word": "glarb" }),
+                    OneOrMany::many(vec![embedding(vec![1.0, 0.0]), embedding(vec![0.0, 1.0])])
+                        .unwrap(),
+                ),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            documents,
+            vec![
+                doc! { "word": "flurbo", "_id": "doc0", "embedding": [0.5, 0.25] },
+                doc! { "word": "glarb", "_id": "doc1-0", "embedding": [1.0, 0.0] },
+                doc! { "word": "glarb", "_id": "doc1-1", "embedding": [0.0, 1.0] },
+            ]
+        );
+
+        // Documents inserted in a namespace are stored with it
+        let documents = mongo_documents(
+            "embedding",
+            Some(("tenant", "acme")),
+            vec![(
+                None,
+                json!({ "word": "zindle" }),
+                OneOrMany::one(embedding(vec![1.0])),
+            )],
+        )
+        .unwrap();
+        assert_eq!(
+            documents,
+            vec![doc! { "word": "zindle", "embedding": [1.0], "tenant": "acme" }]
+        );
+
+        // Documents must serialize to BSON documents
+        assert!(mongo_documents(
+            "embedding",
+            None,
+            vec![(
+                Some("doc".to_string()),
+                json!("flurbo"),
+                OneOrMany::one(embedding(vec![1.0])),
+            )],
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_namespace() {
+        let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap();
+        let index = MongoDbVectorIndex {
+            collection: client.database("rig").collection::<bson::Document>("words"),
+            model: MockEmbeddingModel::constant(vec![1.0]),
+            index_name: "vector_index".to_string(),
+            embedded_field: "embedding".to_string(),
+            search_params: SearchParams::new()
+                .filter(doc! { "lang": "en" })
+                .namespace_field("tenant"),
+            namespace: None,
+        };
+
+        // Searches are restricted to the namespace and insertions store it
+        let namespaced = index.namespace("acme");
+        assert_eq!(namespaced.namespace.as_deref(), Some("acme"));
+
+        let embedding = Embedding {
+            document: String::new(),
+            vec: vec![1.0],
+        };
+        let stage = namespaced.pipeline_search_stage(&embedding, 2);
+        assert_eq!(
+            stage.get_document("$vectorSearch").unwrap().get("filter"),
+            Some(&bson::Bson::Document(doc! {
+                "$and": [{ "lang": "en" }, { "tenant": "acme" }]
+            }))
+        );
+
+        // Namespacing again replaces the namespace
+        let stage = namespaced
+            .namespace("globex")
+            .pipeline_search_stage(&embedding, 2);
+        assert_eq!(
+            stage.get_document("$vectorSearch").unwrap().get("filter"),
+            Some(&bson::Bson::Document(doc! {
+                "$and": [{ "lang": "en" }, { "tenant": "globex" }]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_metadata_filter() {
+        let filter = MetadataFilter::Or(vec![
+            MetadataFilter::Tag("rust".to_string()),
+            MetadataFilter::Not(Box::new(MetadataFilter::Custom(
+                "lang".to_string(),
+                json!("en"),
+            ))),
+        ]);
+        let params = SearchParams::new().metadata_filter(&filter).unwrap();
+
+        assert_eq!(
+            params.filter,
+            doc! {
+                "$or": [
+                    { "metadata.tags": { "$eq": "rust" } },
+                    { "$nor": [{ "metadata.custom.lang": { "$eq": "en" } }] },
+                ]
+            }
+        );
+
+        let filter = MetadataFilter::SourcePrefix("https://docs.rs/".to_string());
+        assert!(SearchParams::new().metadata_filter(&filter).is_err());
+    }
+}
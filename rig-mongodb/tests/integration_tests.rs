@@ -5,7 +5,11 @@ use mongodb::{
     Collection, SearchIndexModel,
 };
 use rig::{
-    embeddings::EmbeddingsBuilder, providers::openai, vector_store::VectorStoreIndex, Embed,
+    embeddings::{Embedding, EmbeddingsBuilder},
+    providers::openai,
+    test_utils::MockEmbeddingModel,
+    vector_store::{NamespacedVectorStoreIndex, VectorStoreIndex},
+    Embed, OneOrMany,
 };
 use rig_mongodb::{MongoDbVectorIndex, SearchParams};
 use serde_json::json;
@@ -128,7 +132,20 @@ async fn vector_search_test() {
     let port = container.get_host_port_ipv4(MONGODB_PORT).await.unwrap();
     let host = container.get_host().await.unwrap().to_string();
 
-    let collection = bootstrap_collection(host, port).await;
+    let collection = bootstrap_collection(
+        host,
+        port,
+        COLLECTION_NAME,
+        doc! {
+            "fields": [{
+                "numDimensions": 1536,
+                "path": "embedding",
+                "similarity": "cosine",
+                "type": "vector"
+            }]
+        },
+    )
+    .await;
 
     let embeddings = create_embeddings(model.clone()).await;
 
@@ -167,7 +184,7 @@ async fn vector_search_test() {
     )
 }
 
-async fn create_search_index(collection: &Collection<bson::Document>) {
+async fn create_search_index(collection: &Collection<bson::Document>, definition: bson::Document) {
     let max_attempts = 5;
 
     for attempt in 0..max_attempts {
@@ -176,14 +193,7 @@ async fn create_search_index(collection: &Collection<bson::Document>) {
                 SearchIndexModel::builder()
                     .name(Some(VECTOR_SEARCH_INDEX_NAME.to_string()))
                     .index_type(Some(mongodb::SearchIndexType::VectorSearch))
-                    .definition(doc! {
-                        "fields": [{
-                            "numDimensions": 1536,
-                            "path": "embedding",
-                            "similarity": "cosine",
-                            "type": "vector"
-                        }]
-                    })
+                    .definition(definition.clone())
                     .build(),
             )
             .await
@@ -238,7 +248,12 @@ async fn create_search_index(collection: &Collection<bson::Document>) {
     );
 }
 
-async fn bootstrap_collection(host: String, port: u16) -> Collection<bson::Document> {
+async fn bootstrap_collection(
+    host: String,
+    port: u16,
+    collection_name: &str,
+    index_definition: bson::Document,
+) -> Collection<bson::Document> {
     // Initialize MongoDB client
     let options = ClientOptions::parse(format!(
         "mongodb://{USERNAME}:{PASSWORD}@{host}:{port}/?directConnection=true"
@@ -252,17 +267,17 @@ async fn bootstrap_collection(host: String, port: u16) -> Collection<bson::Docum
     // Initialize MongoDB database and collection
     mongodb_client
         .database(DATABASE_NAME)
-        .create_collection(COLLECTION_NAME)
+        .create_collection(collection_name)
         .await
         .expect("Collection should be created");
 
     // Get the created collection
     let collection: Collection<bson::Document> = mongodb_client
         .database(DATABASE_NAME)
-        .collection(COLLECTION_NAME);
+        .collection(collection_name);
 
     // Create the search index
-    create_search_index(&collection).await;
+    create_search_index(&collection, index_definition).await;
 
     collection
 }
@@ -301,3 +316,114 @@ async fn create_embeddings(model: openai::EmbeddingModel) -> Vec<bson::Document>
         })
         .collect()
 }
+
+/// Embeds every text as the same vector, so that only the namespaces select the results.
+fn mock_embedding_model() -> MockEmbeddingModel {
+    MockEmbeddingModel::constant(vec![0.1, 0.2, 0.3, 0.4])
+}
+
+#[tokio::test]
+async fn namespaced_insert_and_search_test() {
+    // Setup a local MongoDB Atlas container for testing. NOTE: docker service must be running.
+    let container = GenericImage::new("mongodb/mongodb-atlas-local", "latest")
+        .with_exposed_port(MONGODB_PORT.tcp())
+        .with_wait_for(WaitFor::Duration {
+            length: std::time::Duration::from_secs(5),
+        })
+        .with_env_var("MONGODB_INITDB_ROOT_USERNAME", USERNAME)
+        .with_env_var("MONGODB_INITDB_ROOT_PASSWORD", PASSWORD)
+        .start()
+        .await
+        .expect("Failed to start MongoDB Atlas container");
+
+    let port = container.get_host_port_ipv4(MONGODB_PORT).await.unwrap();
+    let host = container.get_host().await.unwrap().to_string();
+
+    // The namespace field must be indexed as a filter field
+    let collection = bootstrap_collection(
+        host,
+        port,
+        "tenants",
+        doc! {
+            "fields": [
+                {
+                    "numDimensions": 4,
+                    "path": "embedding",
+                    "similarity": "cosine",
+                    "type": "vector"
+                },
+                {
+                    "path": "namespace",
+                    "type": "filter"
+                }
+            ]
+        },
+    )
+    .await;
+
+    let index = MongoDbVectorIndex::new(
+        collection.clone(),
+        mock_embedding_model(),
+        VECTOR_SEARCH_INDEX_NAME,
+        SearchParams::new(),
+    )
+    .await
+    .unwrap();
+
+    let word = |id: &str, definition: &str| {
+        (
+            Word {
+                id: id.to_string(),
+                definition: definition.to_string(),
+            },
+            OneOrMany::one(Embedding {
+                document: definition.to_string(),
+                vec: vec![0.1, 0.2, 0.3, 0.4],
+            }),
+        )
+    };
+    let tenant_a = index.namespace("tenant-a");
+    tenant_a
+        .insert_documents(vec![word("doc0", "Definition of a *flurbo*")])
+        .await
+        .unwrap();
+    let tenant_b = index.namespace("tenant-b");
+    tenant_b
+        .insert_documents(vec![
+            word("doc1", "Definition of a *glarb-glarb*"),
+            word("doc2", "Definition of a *linglingdong*"),
+        ])
+        .await
+        .unwrap();
+
+    // The namespace is written to the inserted documents
+    let stored = collection
+        .find_one(doc! { "_id": "doc0" })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.get_str("namespace"), Ok("tenant-a"));
+
+    // Wait for the new documents to be indexed
+    sleep(Duration::from_secs(5)).await;
+
+    // Each namespace only returns its own documents
+    let results = tenant_a.top_n::<Word>("flurbo", 10).await.unwrap();
+    assert_eq!(
+        results
+            .into_iter()
+            .map(|(_, _, word)| word.id)
+            .collect::<Vec<_>>(),
+        vec!["doc0".to_string()]
+    );
+
+    let mut ids = tenant_b
+        .top_n::<Word>("glarb-glarb", 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, _, word)| word.id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, vec!["doc1".to_string(), "doc2".to_string()]);
+}
@@ -71,7 +71,7 @@ async fn main() -> Result<(), anyhow::Error> {
         .get_index(
             model,
             INDEX_NAME,
-            SearchParams::new(Some("node.year > 1990".to_string())),
+            SearchParams::new(Some("node.year > $year".to_string())).param("year", 1990),
         )
         .await?;
 
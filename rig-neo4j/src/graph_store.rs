@@ -0,0 +1,188 @@
+//! A Neo4j-backed store for rig's experimental graph memory.
+//!
+//! Entities are stored as nodes with the `Entity` label and relations are stored as
+//! `RELATES_TO` relationships between them, with the relation description stored
+//! as a property of the relationship.
+//!
+//! # Example
+//! ```
+//! use rig::memory::GraphMemory;
+//! use rig_neo4j::Neo4jClient;
+//!
+//! let client = Neo4jClient::connect("neo4j://localhost:7687", "neo4j", "password").await?;
+//!
+//! let memory = GraphMemory::new(model, client.graph_store());
+//! ```
+use neo4rs::Graph;
+use rig::{
+    memory::{mentions, GraphStore, KnowledgeGraph, Relation},
+    vector_store::VectorStoreError,
+};
+use serde::Deserialize;
+
+use crate::{neo4j_to_rig_error, Neo4jClient, ToBoltType};
+
+const ADD_ENTITIES_QUERY: &str = "
+    UNWIND $entities AS entity
+    MERGE (e:Entity {key: toLower(entity.name)})
+    SET e.name = entity.name, e.kind = entity.kind
+";
+
+const ADD_RELATIONS_QUERY: &str = "
+    UNWIND $relations AS rel
+    MERGE (s:Entity {key: toLower(rel.source)})
+        ON CREATE SET s.name = rel.source
+    MERGE (t:Entity {key: toLower(rel.target)})
+        ON CREATE SET t.name = rel.target
+    MERGE (s)-[r:RELATES_TO {key: toLower(rel.relation)}]->(t)
+        ON CREATE SET r.relation = rel.relation, r.created_at = timestamp()
+";
+
+/// Candidate facts: relations between entities whose key appears in the query. They are then
+/// filtered and scored in Rust with [mentions], which only matches whole words.
+const FACTS_QUERY: &str = "
+    MATCH (s:Entity)-[r:RELATES_TO]->(t:Entity)
+    WHERE (s.key <> '' AND toLower($query) CONTAINS s.key)
+        OR (t.key <> '' AND toLower($query) CONTAINS t.key)
+    RETURN s.name AS source, r.relation AS relation, t.name AS target,
+        coalesce(r.created_at, 0) AS created_at
+";
+
+/// [GraphStore] implementation that stores the entities and relations of a
+/// [GraphMemory](rig::memory::GraphMemory) in a Neo4j database.
+pub struct Neo4jGraphStore {
+    graph: Graph,
+}
+
+impl Neo4jGraphStore {
+    pub fn new(graph: Graph) -> Self {
+        Self { graph }
+    }
+}
+
+impl Neo4jClient {
+    /// Returns a [Neo4jGraphStore] that can be used as the store of a
+    /// [GraphMemory](rig::memory::GraphMemory).
+    pub fn graph_store(&self) -> Neo4jGraphStore {
+        Neo4jGraphStore::new(self.graph.clone())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FactRow {
+    source: String,
+    relation: String,
+    target: String,
+    created_at: i64,
+}
+
+/// Score the candidate facts by the number of their entities mentioned in the query, and return
+/// the `n` best ones, the most recent first in case of a tie.
+fn rank_facts(query: &str, rows: Vec<FactRow>, n: usize) -> Vec<(f64, Relation)> {
+    let mut facts = rows
+        .into_iter()
+        .filter_map(|row| {
+            let score = [&row.source, &row.target]
+                .iter()
+                .filter(|name| mentions(query, name))
+                .count();
+
+            (score > 0).then_some((score, row))
+        })
+        .collect::<Vec<_>>();
+
+    facts.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.created_at.cmp(&a.1.created_at)));
+
+    facts
+        .into_iter()
+        .take(n)
+        .map(|(score, row)| {
+            (
+                score as f64,
+                Relation {
+                    source: row.source,
+                    relation: row.relation,
+                    target: row.target,
+                },
+            )
+        })
+        .collect()
+}
+
+impl GraphStore for Neo4jGraphStore {
+    async fn add_graph(&self, graph: KnowledgeGraph) -> Result<(), VectorStoreError> {
+        if !graph.entities.is_empty() {
+            self.graph
+                .run(
+                    neo4rs::query(ADD_ENTITIES_QUERY)
+                        .param("entities", graph.entities.to_bolt_type()),
+                )
+                .await
+                .map_err(neo4j_to_rig_error)?;
+        }
+
+        if !graph.relations.is_empty() {
+            self.graph
+                .run(
+                    neo4rs::query(ADD_RELATIONS_QUERY)
+                        .param("relations", graph.relations.to_bolt_type()),
+                )
+                .await
+                .map_err(neo4j_to_rig_error)?;
+        }
+
+        Ok(())
+    }
+
+    async fn facts(&self, query: &str, n: usize) -> Result<Vec<(f64, Relation)>, VectorStoreError> {
+        let rows = Neo4jClient::execute_and_collect::<FactRow>(
+            &self.graph,
+            neo4rs::query(FACTS_QUERY).param("query", query),
+        )
+        .await?;
+
+        Ok(rank_facts(query, rows, n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(source: &str, relation: &str, target: &str, created_at: i64) -> FactRow {
+        FactRow {
+            source: source.to_string(),
+            relation: relation.to_string(),
+            target: target.to_string(),
+            created_at,
+        }
+    }
+
+    fn relation(source: &str, relation: &str, target: &str) -> Relation {
+        Relation {
+            source: source.to_string(),
+            relation: relation.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rank_facts() {
+        // Candidates returned by the substring match of the query
+        let rows = vec![
+            row("Al", "works at", "Acme", 1),
+            row("Alice", "works at", "Globex", 2),
+            row("Alice", "is the sister of", "Bob", 3),
+            row("Bob", "lives in", "Paris", 4),
+        ];
+
+        assert_eq!(
+            rank_facts("Does alice know Bob?", rows, 10),
+            vec![
+                (2.0, relation("Alice", "is the sister of", "Bob")),
+                (1.0, relation("Bob", "lives in", "Paris")),
+                (1.0, relation("Alice", "works at", "Globex")),
+            ]
+        );
+    }
+}
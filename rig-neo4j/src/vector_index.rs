@@ -4,14 +4,14 @@
 //! It uses the [Neo4j vector index](https://neo4j.com/docs/cypher-manual/current/indexes/semantic-indexes/vector-indexes/)
 //! to search for similar nodes based on a query.
 
-use neo4rs::{Graph, Query};
+use neo4rs::{BoltType, Graph, Query};
 use rig::{
     embeddings::{Embedding, EmbeddingModel},
     vector_store::{VectorStoreError, VectorStoreIndex},
 };
 use serde::{de::Error, Deserialize, Serialize};
 
-use crate::Neo4jClient;
+use crate::{Neo4jClient, ToBoltType};
 
 pub struct Neo4jVectorIndex<M: EmbeddingModel> {
     graph: Graph,
@@ -126,50 +126,76 @@ impl<M: EmbeddingModel> Neo4jVectorIndex<M> {
     /// WHERE {where_clause}
     /// RETURN score, ID(node) as element_id, node {.*, embedding:null } as node
     /// ```
+    ///
+    /// The parameters bound with [SearchParams::param] are added to the query.
     pub fn build_vector_search_query(
         &self,
         prompt_embedding: Embedding,
         return_node: bool,
         n: usize,
     ) -> Query {
-        let where_clause = match &self.search_params.post_vector_search_filter {
-            Some(filter) => format!("WHERE {}", filter),
-            None => "".to_string(),
-        };
-
-        // Propertiy containing the embedding vectors are excluded from the returned node
-        let query = format!(
-            "\
-            {}\
-            \t{}\n\
-            \tRETURN score, ID(node) as element_id {}
-            ",
-            BASE_VECTOR_SEARCH_QUERY,
-            where_clause,
-            if return_node {
-                format!(
-                    ", node {{.*, {}:null }} as node",
-                    self.index_config.embedding_property
-                )
-            } else {
-                "".to_string()
-            }
+        let query = vector_search_cypher(
+            &self.search_params,
+            &self.index_config.embedding_property,
+            return_node,
         );
 
         tracing::debug!("Query before params: {}", query);
 
         Query::new(query)
+            .params(self.search_params.filter_params.clone())
             .param("queryVector", prompt_embedding.vec)
             .param("num_candidates", n as i64)
             .param("index_name", self.index_config.index_name.clone())
     }
 }
 
+/// The Cypher text of the vector search query (without its parameters).
+fn vector_search_cypher(
+    search_params: &SearchParams,
+    embedding_property: &str,
+    return_node: bool,
+) -> String {
+    let where_clause = match &search_params.post_vector_search_filter {
+        Some(filter) => format!("WHERE {}", filter),
+        None => "".to_string(),
+    };
+
+    // Propertiy containing the embedding vectors are excluded from the returned node
+    format!(
+        "\
+        {}\
+        \t{}\n\
+        \tRETURN score, ID(node) as element_id {}
+        ",
+        BASE_VECTOR_SEARCH_QUERY,
+        where_clause,
+        if return_node {
+            format!(", node {{.*, {}:null }} as node", embedding_property)
+        } else {
+            "".to_string()
+        }
+    )
+}
+
 /// Search parameters for a vector search. Neo4j currently only supports post-vector-search filtering.
+///
+/// # Example
+/// ```
+/// use rig_neo4j::vector_index::SearchParams;
+///
+/// // Only return movies released after 1990 with a rating of at least 7
+/// let search_params = SearchParams::default()
+///     .filter("node.year > $year AND node.imdbRating >= $rating".to_string())
+///     .param("year", 1990)
+///     .param("rating", 7.0);
+/// ```
 pub struct SearchParams {
     /// Sets the **post-filter** field of the search params. Uses a WHERE clause.
     /// See [Neo4j WHERE clause](https://neo4j.com/docs/cypher-manual/current/clauses/where/) for more information.
     post_vector_search_filter: Option<String>,
+    /// Parameters referenced in the post-filter (e.g.: `$year` in `node.year > $year`)
+    filter_params: Vec<(String, BoltType)>,
 }
 
 impl SearchParams {
@@ -177,6 +203,7 @@ impl SearchParams {
     pub fn new(filter: Option<String>) -> Self {
         Self {
             post_vector_search_filter: filter,
+            filter_params: vec![],
         }
     }
 
@@ -184,6 +211,17 @@ impl SearchParams {
         self.post_vector_search_filter = Some(filter);
         self
     }
+
+    /// Binds a parameter referenced in the post-filter clause (e.g.: `$year` in `node.year > $year`).
+    /// Prefer binding values as parameters over formatting them into the filter string, as
+    /// parameters are not subject to Cypher injection.
+    ///
+    /// ❗The names `queryVector`, `num_candidates` and `index_name` are reserved by the vector search query.
+    pub fn param(mut self, key: &str, value: impl ToBoltType) -> Self {
+        self.filter_params
+            .push((key.to_string(), value.to_bolt_type()));
+        self
+    }
 }
 
 impl Default for SearchParams {
@@ -259,3 +297,41 @@ impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for Neo4jVec
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use neo4rs::BoltType;
+
+    use super::{vector_search_cypher, SearchParams};
+    use crate::ToBoltType;
+
+    #[test]
+    fn test_search_params() {
+        let search_params = SearchParams::default()
+            .filter("node.year > $year AND node.title <> $title".to_string())
+            .param("year", 1990)
+            .param("title", "'; MATCH (n) DETACH DELETE n //");
+
+        // The values are bound as parameters instead of being formatted into the query
+        let query = vector_search_cypher(&search_params, "embedding", true);
+        assert!(query.contains("WHERE node.year > $year AND node.title <> $title"));
+        assert!(query
+            .contains("RETURN score, ID(node) as element_id , node {.*, embedding:null } as node"));
+        assert!(!query.contains("1990") && !query.contains("DETACH DELETE"));
+
+        assert_eq!(
+            search_params.filter_params,
+            vec![
+                ("year".to_string(), 1990.to_bolt_type()),
+                (
+                    "title".to_string(),
+                    BoltType::from("'; MATCH (n) DETACH DELETE n //")
+                ),
+            ]
+        );
+
+        let query = vector_search_cypher(&SearchParams::default(), "embedding", false);
+        assert!(!query.contains("WHERE"));
+        assert!(!query.contains("as node"));
+    }
+}
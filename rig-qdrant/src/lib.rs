@@ -1,12 +1,18 @@
 use qdrant_client::{
     qdrant::{
-        point_id::PointIdOptions, PointId, PointStruct, Query, QueryPoints, UpsertPointsBuilder,
+        point_id::PointIdOptions, Condition, DatetimeRange, Filter, Fusion, NamedVectors, PointId,
+        PointStruct, PrefetchQuery, Query, QueryBatchPointsBuilder, QueryPoints, ScoredPoint,
+        UpsertPointsBuilder, Vector, VectorInput,
     },
     Payload, Qdrant,
 };
 use rig::{
-    embeddings::{Embedding, EmbeddingModel},
-    vector_store::{VectorStoreError, VectorStoreIndex},
+    embeddings::{Embedding, EmbeddingModel, SparseEmbedding, SparseEmbeddingModel},
+    metadata::MetadataFilter,
+    vector_store::{
+        migration::ExportedDocument, HybridSearchIndex, NamespacedVectorStoreIndex,
+        VectorStoreError, VectorStoreImport, VectorStoreIndex,
+    },
     Embed, OneOrMany,
 };
 use serde::{Deserialize, Serialize};
@@ -42,6 +48,34 @@ impl<M: EmbeddingModel> QdrantVectorStore<M> {
         &self.client
     }
 
+    /// Filter the searched points on the [DocumentMetadata](rig::metadata::DocumentMetadata)
+    /// of their payload, stored under the `metadata` field (e.g.: [Document](rig::metadata::Document)s).
+    /// Replaces the filter of the query params, see [metadata_filter].
+    pub fn with_metadata_filter(
+        mut self,
+        filter: &MetadataFilter,
+    ) -> Result<Self, VectorStoreError> {
+        self.query_params.filter = Some(metadata_filter(filter)?);
+        Ok(self)
+    }
+
+    /// Add a sparse embedding model to the store, enabling hybrid dense + sparse searches.
+    ///
+    /// The collection must have a sparse vector named `sparse_vector_name` next to its dense
+    /// vector (named after the `using` field of the query params, or unnamed).
+    /// See [QdrantHybridVectorStore].
+    pub fn with_sparse_model<S: SparseEmbeddingModel>(
+        self,
+        sparse_model: S,
+        sparse_vector_name: &str,
+    ) -> QdrantHybridVectorStore<M, S> {
+        QdrantHybridVectorStore {
+            store: self,
+            sparse_model,
+            sparse_vector_name: sparse_vector_name.to_string(),
+        }
+    }
+
     /// Embed query based on `QdrantVectorStore` model and modify the vector in the required format.
     async fn generate_query_vector(&self, query: &str) -> Result<Vec<f32>, VectorStoreError> {
         let embedding = self.model.embed_text(query).await?;
@@ -89,6 +123,103 @@ impl<M: EmbeddingModel> QdrantVectorStore<M> {
     }
 }
 
+/// Qdrant point ids are unsigned integers or UUIDs: other ids are mapped to name-based UUIDs,
+/// so that importing the same document twice overwrites its point.
+fn point_id(id: &str) -> PointId {
+    if let Ok(num) = id.parse::<u64>() {
+        num.into()
+    } else if let Ok(uuid) = Uuid::parse_str(id) {
+        uuid.to_string().into()
+    } else {
+        Uuid::new_v3(&Uuid::NAMESPACE_OID, id.as_bytes())
+            .to_string()
+            .into()
+    }
+}
+
+/// The points of the imported documents: one point per embedding, with the id of the document
+/// (suffixed with the index of the embedding for documents with several embeddings).
+fn import_points<D: Serialize>(
+    documents: Vec<ExportedDocument<D>>,
+) -> Result<Vec<PointStruct>, VectorStoreError> {
+    let mut points = vec![];
+
+    for (id, document, embeddings) in documents {
+        let payload = Payload::try_from(serde_json::to_value(&document)?).map_err(|err| {
+            VectorStoreError::DatastoreError(format!("Invalid payload: {err}").into())
+        })?;
+        let single_embedding = embeddings.len() == 1;
+
+        for (i, embedding) in embeddings.into_iter().enumerate() {
+            let point_id = if single_embedding {
+                point_id(&id)
+            } else {
+                point_id(&format!("{id}-{i}"))
+            };
+            let vector: Vec<f32> = embedding.vec.into_iter().map(|x| x as f32).collect();
+            points.push(PointStruct::new(point_id, vector, payload.clone()));
+        }
+    }
+
+    Ok(points)
+}
+
+/// Translate a metadata filter into the equivalent Qdrant filter on the `metadata` field of the
+/// payloads.
+///
+/// [MetadataFilter::SourcePrefix] requires a keyword index with prefix matching on
+/// `metadata.source`, and only string, integer and boolean custom values can be matched.
+pub fn metadata_filter(filter: &MetadataFilter) -> Result<Filter, VectorStoreError> {
+    let conditions = |filters: &[MetadataFilter]| {
+        filters
+            .iter()
+            .map(|filter| metadata_filter(filter).map(Condition::from))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    Ok(match filter {
+        MetadataFilter::Tag(tag) => {
+            Filter::must([Condition::matches("metadata.tags", tag.clone())])
+        }
+        MetadataFilter::SourcePrefix(prefix) => {
+            Filter::must([Condition::matches_prefix("metadata.source", prefix.clone())])
+        }
+        MetadataFilter::CreatedAfter(time) => Filter::must([Condition::datetime_range(
+            "metadata.created_at",
+            DatetimeRange {
+                gte: Some((*time).into()),
+                ..Default::default()
+            },
+        )]),
+        MetadataFilter::CreatedBefore(time) => Filter::must([Condition::datetime_range(
+            "metadata.created_at",
+            DatetimeRange {
+                lt: Some((*time).into()),
+                ..Default::default()
+            },
+        )]),
+        MetadataFilter::Custom(key, value) => {
+            let key = format!("metadata.custom.{key}");
+            let condition = match value {
+                serde_json::Value::String(string) => Condition::matches(key, string.clone()),
+                serde_json::Value::Bool(boolean) => Condition::matches(key, *boolean),
+                serde_json::Value::Number(number) if number.is_i64() => {
+                    Condition::matches(key, number.as_i64().unwrap_or_default())
+                }
+                _ => {
+                    return Err(VectorStoreError::DatastoreError(
+                        format!("Qdrant cannot match the custom metadata value {value}").into(),
+                    ))
+                }
+            };
+            Filter::must([condition])
+        }
+        MetadataFilter::And(filters) => Filter::must(conditions(filters)?),
+        MetadataFilter::Or(filters) => Filter::should(conditions(filters)?),
+        MetadataFilter::Not(filter) => Filter::must_not([metadata_filter(filter)?.into()]),
+    })
+}
+
 /// Converts a `PointId` to its string representation.
 fn stringify_id(id: PointId) -> Result<String, VectorStoreError> {
     match id.point_id_options {
@@ -100,6 +231,39 @@ fn stringify_id(id: PointId) -> Result<String, VectorStoreError> {
     }
 }
 
+/// Converts scored points to (score, id, payload) tuples.
+fn with_payloads<T: for<'a> Deserialize<'a>>(
+    points: Vec<ScoredPoint>,
+) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+    points
+        .into_iter()
+        .map(|item| {
+            let id = stringify_id(
+                item.id
+                    .ok_or_else(|| VectorStoreError::DatastoreError("Missing point ID".into()))?,
+            )?;
+            let score = item.score as f64;
+            let payload = serde_json::from_value(serde_json::to_value(item.payload)?)?;
+            Ok((score, id, payload))
+        })
+        .collect()
+}
+
+/// Converts scored points to (score, id) tuples.
+fn ids_only(points: Vec<ScoredPoint>) -> Result<Vec<(f64, String)>, VectorStoreError> {
+    points
+        .into_iter()
+        .map(|point| {
+            let id = stringify_id(
+                point
+                    .id
+                    .ok_or_else(|| VectorStoreError::DatastoreError("Missing point ID".into()))?,
+            )?;
+            Ok((point.score as f64, id))
+        })
+        .collect()
+}
+
 impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for QdrantVectorStore<M> {
     /// Search for the top `n` nearest neighbors to the given query within the Qdrant vector store.
     /// Returns a vector of tuples containing the score, ID, and payload of the nearest neighbors.
@@ -120,19 +284,7 @@ impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for QdrantVe
             .await
             .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))?;
 
-        result
-            .result
-            .into_iter()
-            .map(|item| {
-                let id =
-                    stringify_id(item.id.ok_or_else(|| {
-                        VectorStoreError::DatastoreError("Missing point ID".into())
-                    })?)?;
-                let score = item.score as f64;
-                let payload = serde_json::from_value(serde_json::to_value(item.payload)?)?;
-                Ok((score, id, payload))
-            })
-            .collect()
+        with_payloads(result.result)
     }
 
     /// Search for the top `n` nearest neighbors to the given query within the Qdrant vector store.
@@ -155,15 +307,447 @@ impl<M: EmbeddingModel + std::marker::Sync + Send> VectorStoreIndex for QdrantVe
             .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))?
             .result;
 
-        points
+        ids_only(points)
+    }
+
+    /// Embed all the queries in as few calls to the embedding model as possible, and search
+    /// them in a single batch request.
+    async fn top_n_batch<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        queries: Vec<String>,
+        n: usize,
+    ) -> Result<Vec<Vec<(f64, String, T)>>, VectorStoreError> {
+        let queries = match self.query_params.query {
+            Some(ref q) => vec![Some(q.clone()); queries.len()],
+            None => {
+                let mut vectors = Vec::with_capacity(queries.len());
+                for batch in queries.chunks(M::MAX_DOCUMENTS.max(1)) {
+                    vectors.extend(self.model.embed_texts(batch.to_vec()).await?);
+                }
+                vectors
+                    .into_iter()
+                    .map(|embedding| {
+                        let vector = embedding.vec.iter().map(|&x| x as f32).collect::<Vec<_>>();
+                        Some(Query::new_nearest(vector))
+                    })
+                    .collect()
+            }
+        };
+        if queries.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let params = queries
             .into_iter()
-            .map(|point| {
-                let id =
-                    stringify_id(point.id.ok_or_else(|| {
-                        VectorStoreError::DatastoreError("Missing point ID".into())
-                    })?)?;
-                Ok((point.score as f64, id))
-            })
+            .map(|query| self.prepare_query_params(query, n))
+            .collect::<Vec<_>>();
+        let request =
+            QueryBatchPointsBuilder::new(self.query_params.collection_name.clone(), params);
+        self.client
+            .query_batch(request)
+            .await
+            .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))?
+            .result
+            .into_iter()
+            .map(|batch| with_payloads(batch.result))
             .collect()
     }
 }
+
+impl<M: EmbeddingModel + std::marker::Sync + Send, D: Serialize + Send> VectorStoreImport<D>
+    for QdrantVectorStore<M>
+{
+    /// Import documents exported from another vector store (see [rig::vector_store::migrate]).
+    /// Ids that are not unsigned integers nor UUIDs are mapped to name-based UUIDs, as Qdrant
+    /// does not accept other point ids.
+    async fn import(
+        &mut self,
+        documents: Vec<ExportedDocument<D>>,
+    ) -> Result<(), VectorStoreError> {
+        let points = import_points(documents)?;
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        let request =
+            UpsertPointsBuilder::new(&self.query_params.collection_name, points).wait(true);
+        self.client.upsert_points(request).await.map_err(|err| {
+            VectorStoreError::DatastoreError(format!("Error while upserting: {err}").into())
+        })?;
+
+        Ok(())
+    }
+}
+
+impl<M: EmbeddingModel + std::marker::Sync + Send> NamespacedVectorStoreIndex
+    for QdrantVectorStore<M>
+{
+    type Namespaced = Self;
+
+    /// Namespaces are mapped to Qdrant collections: the returned store searches and
+    /// upserts points in the collection named after the namespace, which must already exist.
+    fn namespace(&self, namespace: &str) -> Self {
+        let mut query_params = self.query_params.clone();
+        query_params.collection_name = namespace.to_string();
+
+        Self::new(self.client.clone(), self.model.clone(), query_params)
+    }
+}
+
+/// A Qdrant vector store searching both dense and sparse vectors, created with
+/// [QdrantVectorStore::with_sparse_model].
+///
+/// Points are upserted with a dense vector (named after the `using` field of the query params,
+/// or unnamed) and a sparse vector (e.g.: SPLADE or [BM25](rig::embeddings::Bm25) weights).
+/// [HybridSearchIndex] searches run both queries and merge them with reciprocal rank fusion,
+/// while [VectorStoreIndex] searches only use the dense vectors.
+///
+/// # Example
+/// ```no_run
+/// use qdrant_client::{
+///     qdrant::{
+///         CreateCollectionBuilder, Distance, Modifier, QueryPointsBuilder,
+///         SparseVectorParamsBuilder, SparseVectorsConfigBuilder, VectorParamsBuilder,
+///     },
+///     Qdrant,
+/// };
+/// use rig::{embeddings::Bm25, providers::openai, vector_store::HybridSearchIndex};
+/// use rig_qdrant::QdrantVectorStore;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Qdrant::from_url("http://localhost:6334").build()?;
+///
+/// // Qdrant computes the inverse document frequencies of the unfitted BM25 model
+/// let mut sparse_config = SparseVectorsConfigBuilder::default();
+/// sparse_config.add_named_vector_params(
+///     "bm25",
+///     SparseVectorParamsBuilder::default().modifier(Modifier::Idf),
+/// );
+/// client
+///     .create_collection(
+///         CreateCollectionBuilder::new("definitions")
+///             .vectors_config(VectorParamsBuilder::new(1536, Distance::Cosine))
+///             .sparse_vectors_config(sparse_config),
+///     )
+///     .await?;
+///
+/// let model = openai::Client::from_env().embedding_model(openai::TEXT_EMBEDDING_ADA_002);
+/// let store = QdrantVectorStore::new(
+///     client,
+///     model,
+///     QueryPointsBuilder::new("definitions").with_payload(true).build(),
+/// )
+/// .with_sparse_model(Bm25::new(), "bm25");
+///
+/// let results = store
+///     .hybrid_top_n::<serde_json::Value>("What is a flurbo?", 3)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct QdrantHybridVectorStore<M: EmbeddingModel, S: SparseEmbeddingModel> {
+    store: QdrantVectorStore<M>,
+    /// Model used to generate the sparse embeddings
+    sparse_model: S,
+    /// Name of the sparse vector in the collection
+    sparse_vector_name: String,
+}
+
+impl<M: EmbeddingModel, S: SparseEmbeddingModel> QdrantHybridVectorStore<M, S> {
+    pub fn client(&self) -> &Qdrant {
+        self.store.client()
+    }
+
+    /// Name of the dense vector in the collection (empty for the unnamed vector)
+    fn dense_vector_name(&self) -> String {
+        self.store.query_params.using.clone().unwrap_or_default()
+    }
+
+    /// Insert documents with the dense and sparse embeddings of each of their embedded texts.
+    pub async fn insert_documents<Doc: Serialize + Embed + Send>(
+        &self,
+        documents: Vec<(Doc, OneOrMany<Embedding>)>,
+    ) -> Result<(), VectorStoreError> {
+        let collection_name = self.store.query_params.collection_name.clone();
+        let dense_vector_name = self.dense_vector_name();
+
+        for (document, embeddings) in documents {
+            let json_document = serde_json::to_value(&document)?;
+            let doc_as_payload = Payload::try_from(json_document).map_err(|err| {
+                VectorStoreError::DatastoreError(format!("Invalid payload: {err}").into())
+            })?;
+
+            let embeddings = embeddings.into_iter().collect::<Vec<_>>();
+            let sparse_embeddings = self
+                .sparse_model
+                .embed_sparse_texts(embeddings.iter().map(|e| e.document.clone()))
+                .await?;
+
+            let points = embeddings
+                .into_iter()
+                .zip(sparse_embeddings)
+                .map(|(embedding, sparse_embedding)| {
+                    let vectors = NamedVectors::default()
+                        .add_vector(
+                            dense_vector_name.clone(),
+                            Vector::new_dense(
+                                embedding
+                                    .vec
+                                    .into_iter()
+                                    .map(|x| x as f32)
+                                    .collect::<Vec<_>>(),
+                            ),
+                        )
+                        .add_vector(
+                            self.sparse_vector_name.clone(),
+                            sparse_vector(&sparse_embedding),
+                        );
+                    PointStruct::new(Uuid::new_v4().to_string(), vectors, doc_as_payload.clone())
+                })
+                .collect::<Vec<PointStruct>>();
+
+            let request = UpsertPointsBuilder::new(&collection_name, points);
+            self.store
+                .client
+                .upsert_points(request)
+                .await
+                .map_err(|err| {
+                    VectorStoreError::DatastoreError(format!("Error while upserting: {err}").into())
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Build the hybrid query: dense and sparse prefetches merged with reciprocal rank fusion.
+    async fn hybrid_query_params(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<QueryPoints, VectorStoreError> {
+        let dense = self.store.generate_query_vector(query).await?;
+        let sparse = self.sparse_model.embed_sparse_query(query).await?;
+
+        let prefetch = |query: VectorInput, using: String| PrefetchQuery {
+            query: Some(Query::new_nearest(query)),
+            using: Some(using),
+            filter: self.store.query_params.filter.clone(),
+            limit: Some(n as u64),
+            ..Default::default()
+        };
+
+        let mut params = self
+            .store
+            .prepare_query_params(Some(Query::new_fusion(Fusion::Rrf)), n);
+        // The filter is applied by the prefetches, and `using` does not apply to fusion queries
+        params.filter = None;
+        params.using = None;
+        params.prefetch = vec![
+            prefetch(VectorInput::new_dense(dense), self.dense_vector_name()),
+            prefetch(
+                VectorInput::new_sparse(
+                    sparse.indices(),
+                    sparse
+                        .values()
+                        .into_iter()
+                        .map(|x| x as f32)
+                        .collect::<Vec<_>>(),
+                ),
+                self.sparse_vector_name.clone(),
+            ),
+        ];
+        Ok(params)
+    }
+}
+
+/// Converts a sparse embedding to a Qdrant sparse vector.
+fn sparse_vector(embedding: &SparseEmbedding) -> Vector {
+    Vector::new_sparse(
+        embedding.indices(),
+        embedding
+            .values()
+            .into_iter()
+            .map(|x| x as f32)
+            .collect::<Vec<_>>(),
+    )
+}
+
+impl<M, S> VectorStoreIndex for QdrantHybridVectorStore<M, S>
+where
+    M: EmbeddingModel + std::marker::Sync + Send,
+    S: SparseEmbeddingModel,
+{
+    /// Dense vector search, see [QdrantVectorStore::top_n].
+    async fn top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        self.store.top_n(query, n).await
+    }
+
+    async fn top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        self.store.top_n_ids(query, n).await
+    }
+
+    async fn top_n_batch<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        queries: Vec<String>,
+        n: usize,
+    ) -> Result<Vec<Vec<(f64, String, T)>>, VectorStoreError> {
+        self.store.top_n_batch(queries, n).await
+    }
+}
+
+impl<M, S> HybridSearchIndex for QdrantHybridVectorStore<M, S>
+where
+    M: EmbeddingModel + std::marker::Sync + Send,
+    S: SparseEmbeddingModel,
+{
+    /// Search for the top `n` points of both the dense and the sparse vector searches, merged
+    /// with reciprocal rank fusion.
+    async fn hybrid_top_n<T: for<'a> Deserialize<'a> + Send>(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String, T)>, VectorStoreError> {
+        let params = self.hybrid_query_params(query, n).await?;
+        let result = self
+            .store
+            .client
+            .query(params)
+            .await
+            .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))?;
+
+        with_payloads(result.result)
+    }
+
+    async fn hybrid_top_n_ids(
+        &self,
+        query: &str,
+        n: usize,
+    ) -> Result<Vec<(f64, String)>, VectorStoreError> {
+        let params = self.hybrid_query_params(query, n).await?;
+        let result = self
+            .store
+            .client
+            .query(params)
+            .await
+            .map_err(|e| VectorStoreError::DatastoreError(Box::new(e)))?;
+
+        ids_only(result.result)
+    }
+}
+
+impl<M, S> NamespacedVectorStoreIndex for QdrantHybridVectorStore<M, S>
+where
+    M: EmbeddingModel + std::marker::Sync + Send,
+    S: SparseEmbeddingModel,
+{
+    type Namespaced = Self;
+
+    /// Namespaces are mapped to Qdrant collections, see [QdrantVectorStore::namespace].
+    fn namespace(&self, namespace: &str) -> Self {
+        self.store
+            .namespace(namespace)
+            .with_sparse_model(self.sparse_model.clone(), &self.sparse_vector_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use qdrant_client::qdrant::{point_id::PointIdOptions, Condition, DatetimeRange, Filter};
+    use rig::{embeddings::Embedding, metadata::MetadataFilter, OneOrMany};
+    use serde_json::json;
+
+    use super::{import_points, metadata_filter, point_id};
+
+    #[test]
+    fn test_import_points() {
+        let embedding = |vec: Vec<f64>| Embedding {
+            document: String::new(),
+            vec,
+        };
+        let points = import_points(vec![
+            (
+                "42".to_string(),
+                json!({ "word": "flurbo" }),
+                OneOrMany::one(embedding(vec![0.5, 0.25])),
+            ),
+            (
+                "doc1".to_string(),
+                json!({ "word": "glarb" }),
+                OneOrMany::many(vec![embedding(vec![1.0, 0.0]), embedding(vec![0.0, 1.0])])
+                    .unwrap(),
+            ),
+        ])
+        .unwrap();
+
+        let ids = points
+            .iter()
+            .map(|point| point.id.clone().unwrap().point_id_options.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                PointIdOptions::Num(42),
+                point_id("doc1-0").point_id_options.unwrap(),
+                point_id("doc1-1").point_id_options.unwrap(),
+            ]
+        );
+        assert_eq!(points[1].payload["word"], "glarb".into());
+
+        // Name-based UUIDs are stable, and UUIDs are kept
+        assert_eq!(point_id("doc1"), point_id("doc1"));
+        assert_ne!(point_id("doc1"), point_id("doc2"));
+        let uuid = "f9e17d59-32e5-440c-be02-b2759a654824";
+        assert_eq!(
+            point_id(uuid).point_id_options,
+            Some(PointIdOptions::Uuid(uuid.to_string()))
+        );
+
+        // Payloads must be JSON objects
+        assert!(import_points(vec![(
+            "doc".to_string(),
+            json!("flurbo"),
+            OneOrMany::one(embedding(vec![1.0])),
+        )])
+        .is_err());
+    }
+
+    #[test]
+    fn test_metadata_filter() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_706_702_400);
+        let filter = MetadataFilter::And(vec![
+            MetadataFilter::Tag("rust".to_string()),
+            MetadataFilter::Not(Box::new(MetadataFilter::CreatedBefore(time))),
+            MetadataFilter::Custom("stars".to_string(), json!(5)),
+        ]);
+
+        assert_eq!(
+            metadata_filter(&filter).unwrap(),
+            Filter::must([
+                Filter::must([Condition::matches("metadata.tags", "rust".to_string())]).into(),
+                Filter::must_not([Filter::must([Condition::datetime_range(
+                    "metadata.created_at",
+                    DatetimeRange {
+                        lt: Some(time.into()),
+                        ..Default::default()
+                    },
+                )])
+                .into()])
+                .into(),
+                Filter::must([Condition::matches("metadata.custom.stars", 5)]).into(),
+            ])
+        );
+
+        let filter = MetadataFilter::Custom("score".to_string(), json!(0.5));
+        assert!(metadata_filter(&filter).is_err());
+    }
+}
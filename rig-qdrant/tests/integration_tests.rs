@@ -13,7 +13,11 @@ use qdrant_client::{
     Payload, Qdrant,
 };
 use rig::{
-    embeddings::EmbeddingsBuilder, providers::openai, vector_store::VectorStoreIndex, Embed,
+    embeddings::{Embedding, EmbeddingsBuilder},
+    providers::openai,
+    test_utils::MockEmbeddingModel,
+    vector_store::{NamespacedVectorStoreIndex, VectorStoreIndex},
+    Embed, OneOrMany,
 };
 use rig_qdrant::QdrantVectorStore;
 
@@ -205,3 +209,97 @@ async fn create_points(model: openai::EmbeddingModel) -> Vec<PointStruct> {
         })
         .collect()
 }
+
+/// Embeds every text as the same vector, so that only the namespaces select the results.
+fn mock_embedding_model() -> MockEmbeddingModel {
+    MockEmbeddingModel::constant(vec![0.1, 0.2, 0.3, 0.4])
+}
+
+#[tokio::test]
+async fn namespaced_insert_and_search_test() {
+    // Setup a local qdrant container for testing. NOTE: docker service must be running.
+    let container = GenericImage::new("qdrant/qdrant", "latest")
+        .with_wait_for(WaitFor::Duration {
+            length: std::time::Duration::from_secs(5),
+        })
+        .with_exposed_port(QDRANT_PORT.tcp())
+        .with_exposed_port(QDRANT_PORT_SECONDARY.tcp())
+        .start()
+        .await
+        .expect("Failed to start qdrant container");
+
+    let port = container
+        .get_host_port_ipv4(QDRANT_PORT_SECONDARY)
+        .await
+        .unwrap();
+    let host = container.get_host().await.unwrap().to_string();
+
+    let client = Qdrant::from_url(&format!("http://{host}:{port}"))
+        .build()
+        .unwrap();
+
+    // Namespaces are mapped to collections, which must already exist
+    for tenant in ["tenant-a", "tenant-b"] {
+        client
+            .create_collection(
+                CreateCollectionBuilder::new(tenant)
+                    .vectors_config(VectorParamsBuilder::new(4, Distance::Cosine)),
+            )
+            .await
+            .unwrap();
+    }
+
+    let vector_store = QdrantVectorStore::new(
+        client,
+        mock_embedding_model(),
+        QueryPointsBuilder::new(COLLECTION_NAME)
+            .with_payload(true)
+            .build(),
+    );
+
+    let word = |id: &str, definition: &str| {
+        (
+            Word {
+                id: id.to_string(),
+                definition: definition.to_string(),
+            },
+            OneOrMany::one(Embedding {
+                document: definition.to_string(),
+                vec: vec![0.1, 0.2, 0.3, 0.4],
+            }),
+        )
+    };
+    let tenant_a = vector_store.namespace("tenant-a");
+    tenant_a
+        .insert_documents(vec![word("doc0", "Definition of a *flurbo*")])
+        .await
+        .unwrap();
+    let tenant_b = vector_store.namespace("tenant-b");
+    tenant_b
+        .insert_documents(vec![
+            word("doc1", "Definition of a *glarb-glarb*"),
+            word("doc2", "Definition of a *linglingdong*"),
+        ])
+        .await
+        .unwrap();
+
+    // Each namespace only returns its own documents
+    let results = tenant_a.top_n::<Word>("flurbo", 10).await.unwrap();
+    assert_eq!(
+        results
+            .into_iter()
+            .map(|(_, _, word)| word.id)
+            .collect::<Vec<_>>(),
+        vec!["doc0".to_string()]
+    );
+
+    let mut ids = tenant_b
+        .top_n::<Word>("glarb-glarb", 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(_, _, word)| word.id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, vec!["doc1".to_string(), "doc2".to_string()]);
+}